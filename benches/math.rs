@@ -0,0 +1,63 @@
+//! Timings of the math operations accelerated by the `simd` feature.
+//!
+//! Compare the scalar and SIMD paths by running both:
+//! ```sh
+//! cargo bench --bench math
+//! cargo bench --bench math --features simd
+//! ```
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use est_render::math::{Matrix4, Vector3, Vector4};
+
+const ITERATIONS: u32 = 1_000_000;
+
+fn bench<T>(name: &str, mut f: impl FnMut() -> T) {
+    // Warm up caches and the branch predictor before measuring.
+    for _ in 0..ITERATIONS / 10 {
+        black_box(f());
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    let elapsed = start.elapsed();
+
+    let per_iteration = elapsed.as_nanos() as f64 / f64::from(ITERATIONS);
+    println!(
+        "{name:<24} {per_iteration:>8.2} ns/iter ({:?} total)",
+        round(elapsed)
+    );
+}
+
+fn round(duration: Duration) -> Duration {
+    Duration::from_micros(duration.as_micros() as u64)
+}
+
+fn main() {
+    let mode = if cfg!(feature = "simd") {
+        "simd"
+    } else {
+        "scalar"
+    };
+    println!("est-render math benchmarks ({mode}, {ITERATIONS} iterations)");
+
+    let a = Matrix4::perspective(60f32.to_radians(), 16.0 / 9.0, 0.1, 100.0);
+    let b = Matrix4::look_at(
+        Vector3::new(3.0, 4.0, 5.0),
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    );
+    let v = Vector4::new(1.0, 2.0, 3.0, 1.0);
+    let w = Vector4::new(-4.0, 0.5, 2.0, 0.0);
+
+    // Operands go through `black_box` by reference, copying them every iteration would mostly
+    // measure the copy.
+    bench("Matrix4 * Matrix4", || *black_box(&a) * *black_box(&b));
+    bench("Matrix4 * Vector4", || *black_box(&a) * *black_box(&v));
+    bench("Matrix4::inverse", || black_box(&b).inverse());
+    bench("Vector4::dot", || black_box(&v).dot(black_box(&w)));
+    bench("Vector4::normalize", || black_box(&v).normalize());
+}
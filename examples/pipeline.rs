@@ -157,6 +157,7 @@ fn main() {
                     window_id,
                     key,
                     pressed,
+                    ..
                 } => {
                     if *window_id == window.id() && key == "Escape" && *pressed {
                         window.quit();
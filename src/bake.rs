@@ -0,0 +1,368 @@
+//! Offline asset baking, meant to be called from build scripts or asset tools.
+//!
+//! Packing a texture atlas or rasterizing a font on every start can take a noticeable
+//! amount of time, these functions do the work ahead of time and write the result to a
+//! binary file that is loaded at runtime without decoding or packing anything.
+//!
+//! ```no_run
+//! // build.rs
+//! use est_render::bake::{self, AtlasSource, NineSlice};
+//!
+//! let mut atlas = bake::pack_atlas([
+//!     ("player", AtlasSource::File("assets/player.png".into())),
+//!     ("button_idle", AtlasSource::File("assets/button_idle.png".into())),
+//! ]).unwrap();
+//! atlas.set_nine_slice("button_idle", NineSlice::uniform(8)).unwrap();
+//! atlas.save("assets/sprites.atlas").unwrap();
+//!
+//! bake::bake_font_cache("assets/font.ttf", 16.0, None, "assets/font.cache").unwrap();
+//! ```
+//!
+//! At runtime the atlas is loaded with [GPU::load_atlas], the font with
+//! [FontManager::load_font_cached].
+//!
+//! # Atlas file format
+//!
+//! All values are little endian.
+//!
+//! | Type      | Description                                       |
+//! |-----------|---------------------------------------------------|
+//! | `[u8; 5]` | Magic, `eATLS`                                    |
+//! | `u32`     | Format version, currently 2                       |
+//! | `u32`     | Compressed payload size                           |
+//! | `u32`     | Uncompressed payload size                         |
+//! | `[u8]`    | Payload, compressed with zlib                     |
+//!
+//! The payload contains:
+//!
+//! | Type      | Description                                       |
+//! |-----------|---------------------------------------------------|
+//! | `u32`     | Atlas width in pixels, at most 2048               |
+//! | `u32`     | Atlas height in pixels, at most 2048              |
+//! | `u32`     | Item count                                        |
+//! | items     | For each item, sorted by id: `u32` id length, UTF-8 id bytes, then `i32` x, y, width and height in pixels |
+//! |           | followed by a `u8` nine-slice flag, and when it is 1 the `i32` left, top, right and bottom insets |
+//! | `[u8]`    | RGBA8 pixels, `width * height * 4` bytes          |
+//!
+//! # Font cache file format
+//!
+//! Written by [Font::save_font_cache], using the same header as the atlas with the `eFONT`
+//! magic and without the version field. The payload contains:
+//!
+//! | Type      | Description                                       |
+//! |-----------|---------------------------------------------------|
+//! | `u32`     | Family name length, followed by the UTF-8 name    |
+//! | `u8`      | [FontStyle](crate::font::FontStyle) bits          |
+//! | `u32`     | Glyph count                                       |
+//! | glyphs    | For each glyph: `u32` codepoint, then `f32` advance, atlas start x/y, atlas end x/y, width, height, bearing x/y, advance x/y, ascender and descender |
+//! | `u32`     | Glyph atlas width, at most 2048                   |
+//! | `u32`     | Glyph atlas height, at most 2048                  |
+//! | `[u8]`    | Grayscale glyph atlas, `width * height` bytes     |
+//! | `f32`     | Ascender, descender, line height and space width  |
+//! | `f32`     | Underline offset and thickness, strikeout offset and thickness |
+
+use std::io::{Read, Write};
+
+use byteorder_lite::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use flate2::bufread::ZlibDecoder;
+
+use crate::{
+    font::{Font, FontError, FontManager},
+    gpu::{
+        GPU,
+        texture::atlas::{
+            MAX_WIDTH_SIZE,
+            PackedAtlas,
+            PackedItem,
+            TextureAtlas,
+            TextureAtlasBuilderError,
+            pack_items,
+        },
+    },
+    math::{Point2, Rect},
+};
+
+pub use crate::gpu::texture::atlas::{AtlasSource, NineSlice};
+
+const ATLAS_FILE_MAGIC: [u8; 5] = *b"eATLS";
+
+/// Version of the atlas layout, bumped when the payload changes. Files from before nine-slice
+/// items have no version, the value read in its place is their compressed size, which is never 2.
+const ATLAS_FILE_VERSION: u32 = 2;
+
+/// Largest payload buffer allocated up front, the pixels of a full size atlas.
+const MAX_PREALLOCATED_SIZE: usize = (MAX_WIDTH_SIZE * MAX_WIDTH_SIZE * 4) as usize;
+
+/// A texture atlas packed on the CPU, which can be saved to a file and loaded back at runtime.
+#[derive(Debug, Clone)]
+pub struct AtlasFile {
+    packed: PackedAtlas,
+}
+
+/// Packs the images into a single atlas without a GPU, see [GPU::create_texture_atlas] for
+/// packing at runtime.
+///
+/// Items are packed in id order, so the same inputs always produce the same file.
+pub fn pack_atlas<I, S>(inputs: I) -> Result<AtlasFile, TextureAtlasBuilderError>
+where
+    I: IntoIterator<Item = (S, AtlasSource)>,
+    S: Into<String>,
+{
+    let packed = pack_items(inputs.into_iter().map(|(id, source)| (id.into(), source)))?;
+
+    Ok(AtlasFile { packed })
+}
+
+/// Rasterizes a font and writes its glyph cache to `output`, to be loaded with
+/// [FontManager::load_font_cached].
+///
+/// `font_name` is either a path to a font file or the family name of a system font, and
+/// `glyph_range` defaults to the printable ASCII range.
+pub fn bake_font_cache(
+    font_name: &str,
+    size: f32,
+    glyph_range: Option<&[(u32, u32)]>,
+    output: &str,
+) -> Result<Font, FontError> {
+    let font = FontManager::new().load_font(font_name, glyph_range, size)?;
+    font.save_font_cache(output).map_err(FontError::IoError)?;
+
+    Ok(font)
+}
+
+impl AtlasFile {
+    /// Loads an atlas file written by [AtlasFile::save].
+    pub fn load(path: &str) -> Result<Self, std::io::Error> {
+        let data = std::fs::read(path)?;
+
+        Self::from_bytes(&data)
+    }
+
+    /// Parses an atlas file from memory, eg. one embedded with `include_bytes!`.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, std::io::Error> {
+        let mut reader = std::io::Cursor::new(data);
+
+        let mut magic = [0; 5];
+        reader.read_exact(&mut magic)?;
+        if magic != ATLAS_FILE_MAGIC {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Invalid atlas file",
+            ));
+        }
+
+        let version = reader.read_u32::<LittleEndian>()?;
+        if version != ATLAS_FILE_VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unsupported atlas file version {}", version),
+            ));
+        }
+
+        let compressed_size = reader.read_u32::<LittleEndian>()?;
+        let uncompressed_size = reader.read_u32::<LittleEndian>()?;
+
+        let start = reader.position() as usize;
+        let compressed_data = start
+            .checked_add(compressed_size as usize)
+            .and_then(|end| data.get(start..end))
+            .ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Truncated atlas file")
+            })?;
+
+        // The sizes in the header are untrusted, the payload grows as it is inflated and stops
+        // at the announced size
+        let decoder = ZlibDecoder::new(compressed_data);
+        let mut decompressed_data =
+            Vec::with_capacity((uncompressed_size as usize).min(MAX_PREALLOCATED_SIZE));
+        decoder
+            .take(uncompressed_size as u64)
+            .read_to_end(&mut decompressed_data)?;
+
+        if decompressed_data.len() != uncompressed_size as usize {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Atlas payload size mismatch",
+            ));
+        }
+
+        let mut reader = std::io::Cursor::new(decompressed_data);
+
+        let width = reader.read_u32::<LittleEndian>()?;
+        let height = reader.read_u32::<LittleEndian>()?;
+
+        if width == 0 || height == 0 || width > MAX_WIDTH_SIZE as u32 || height > MAX_WIDTH_SIZE as u32 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Invalid atlas size",
+            ));
+        }
+
+        let size = Point2::new(width as i32, height as i32);
+
+        let num_items = reader.read_u32::<LittleEndian>()?;
+        let mut items = Vec::new();
+        for _ in 0..num_items {
+            let id_len = reader.read_u32::<LittleEndian>()?;
+            let mut id = Vec::new();
+            (&mut reader).take(id_len as u64).read_to_end(&mut id)?;
+            if id.len() != id_len as usize {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "Truncated atlas item id",
+                ));
+            }
+            let id = String::from_utf8(id).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid UTF-8 in atlas item id")
+            })?;
+
+            let rect = Rect::new(
+                reader.read_i32::<LittleEndian>()?,
+                reader.read_i32::<LittleEndian>()?,
+                reader.read_i32::<LittleEndian>()?,
+                reader.read_i32::<LittleEndian>()?,
+            );
+
+            let right = rect.x.checked_add(rect.w);
+            let bottom = rect.y.checked_add(rect.h);
+
+            if rect.x < 0
+                || rect.y < 0
+                || rect.w < 0
+                || rect.h < 0
+                || right.is_none_or(|right| right > size.x)
+                || bottom.is_none_or(|bottom| bottom > size.y)
+            {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Atlas item out of bounds",
+                ));
+            }
+
+            let nine_slice = match reader.read_u8()? {
+                0 => None,
+                _ => {
+                    let nine_slice = NineSlice::new(
+                        reader.read_i32::<LittleEndian>()?,
+                        reader.read_i32::<LittleEndian>()?,
+                        reader.read_i32::<LittleEndian>()?,
+                        reader.read_i32::<LittleEndian>()?,
+                    );
+
+                    if !nine_slice.fits(Point2::new(rect.w, rect.h)) {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            "Invalid nine-slice insets",
+                        ));
+                    }
+
+                    Some(nine_slice)
+                }
+            };
+
+            items.push(PackedItem {
+                id,
+                rect,
+                nine_slice,
+            });
+        }
+
+        let mut pixels = vec![0; (width * height * 4) as usize];
+        reader.read_exact(&mut pixels)?;
+
+        Ok(Self {
+            packed: PackedAtlas {
+                pixels,
+                size,
+                items,
+            },
+        })
+    }
+
+    /// Saves the atlas to a file.
+    pub fn save(&self, path: &str) -> Result<(), std::io::Error> {
+        std::fs::write(path, self.to_bytes()?)
+    }
+
+    /// Serializes the atlas in the format described in the [module documentation](self).
+    pub fn to_bytes(&self) -> Result<Vec<u8>, std::io::Error> {
+        let packed = &self.packed;
+
+        let mut payload = std::io::Cursor::new(Vec::<u8>::new());
+
+        payload.write_u32::<LittleEndian>(packed.size.x as u32)?;
+        payload.write_u32::<LittleEndian>(packed.size.y as u32)?;
+
+        payload.write_u32::<LittleEndian>(packed.items.len() as u32)?;
+        for item in &packed.items {
+            payload.write_u32::<LittleEndian>(item.id.len() as u32)?;
+            payload.write_all(item.id.as_bytes())?;
+            payload.write_i32::<LittleEndian>(item.rect.x)?;
+            payload.write_i32::<LittleEndian>(item.rect.y)?;
+            payload.write_i32::<LittleEndian>(item.rect.w)?;
+            payload.write_i32::<LittleEndian>(item.rect.h)?;
+
+            match item.nine_slice {
+                Some(nine_slice) => {
+                    payload.write_u8(1)?;
+                    payload.write_i32::<LittleEndian>(nine_slice.left)?;
+                    payload.write_i32::<LittleEndian>(nine_slice.top)?;
+                    payload.write_i32::<LittleEndian>(nine_slice.right)?;
+                    payload.write_i32::<LittleEndian>(nine_slice.bottom)?;
+                }
+                None => payload.write_u8(0)?,
+            }
+        }
+
+        payload.write_all(&packed.pixels)?;
+
+        let uncompressed_data = payload.into_inner();
+
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&uncompressed_data)?;
+        let compressed_data = encoder.finish()?;
+
+        let mut writer = Vec::with_capacity(compressed_data.len() + 17);
+        writer.write_all(&ATLAS_FILE_MAGIC)?;
+        writer.write_u32::<LittleEndian>(ATLAS_FILE_VERSION)?;
+        writer.write_u32::<LittleEndian>(compressed_data.len() as u32)?;
+        writer.write_u32::<LittleEndian>(uncompressed_data.len() as u32)?;
+        writer.write_all(&compressed_data)?;
+
+        Ok(writer)
+    }
+
+    /// Size of the atlas image in pixels.
+    pub fn size(&self) -> Point2 {
+        self.packed.size
+    }
+
+    /// RGBA8 pixels of the atlas image.
+    pub fn pixels(&self) -> &[u8] {
+        &self.packed.pixels
+    }
+
+    /// Retrieves the placement of an item in pixels.
+    pub fn get_id(&self, id: &str) -> Option<Rect> {
+        self.packed
+            .items
+            .iter()
+            .find(|item| item.id == id)
+            .map(|item| item.rect)
+    }
+
+    /// Marks an item as a nine-slice region, see [NineSlice].
+    pub fn set_nine_slice(
+        &mut self,
+        id: &str,
+        nine_slice: NineSlice,
+    ) -> Result<(), TextureAtlasBuilderError> {
+        self.packed.set_nine_slice(id, nine_slice)
+    }
+
+    /// Uploads the atlas to the GPU, see also [GPU::load_atlas].
+    pub fn create_texture_atlas(&self, gpu: &mut GPU) -> Result<TextureAtlas, TextureAtlasBuilderError> {
+        TextureAtlas::from_packed(gpu.inner.clone(), &self.packed)
+    }
+}
@@ -0,0 +1,415 @@
+//! Benchmark harness running rendering code on a headless GPU.
+//!
+//! A [Bench] records a closure into a command buffer for a number of frames, and reports the
+//! CPU time spent recording and submitting, the GPU time measured with timestamp queries when
+//! the adapter supports them, and the buffers, pipelines and bind groups created every frame.
+//! This makes regressions in the upload, batching and pipeline caching paths measurable.
+//!
+//! # Example
+//! ```ignore
+//! let mut bench = est_render::bench::new("sprite batching")
+//!     .set_frames(200)
+//!     .build()?;
+//!
+//! let target = bench
+//!     .gpu()
+//!     .create_texture()
+//!     .set_render_target(Point2::new(1280, 720), None)
+//!     .build()?;
+//!
+//! let report = bench.run(|frame| {
+//!     frame.scope("sprites", |cmd| {
+//!         let mut pass = cmd.begin_texture(&target);
+//!         // ...
+//!     });
+//! });
+//!
+//! println!("{}", report);
+//! assert_eq!(report.max_usage.new_pipelines, 0);
+//! ```
+
+use std::time::{Duration, Instant};
+
+use crate::gpu::{
+    GPU, GPUWaitType, GpuBuildError,
+    budget::FrameUsage,
+    command::{CommandBuffer, CommandBufferBuildError},
+    profiler::GpuProfiler,
+};
+
+/// Number of profiler scopes available per frame, including the scope of the whole frame.
+const PROFILER_CAPACITY: u32 = 32;
+
+/// Creates a new [BenchBuilder], `name` is shown in the report.
+pub fn new(name: &str) -> BenchBuilder {
+    BenchBuilder::new(name)
+}
+
+pub struct BenchBuilder {
+    name: String,
+    frames: u32,
+    warmup_frames: u32,
+}
+
+impl BenchBuilder {
+    pub(crate) fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            frames: 100,
+            warmup_frames: 10,
+        }
+    }
+
+    /// Sets the number of measured frames, 100 by default.
+    pub fn set_frames(mut self, frames: u32) -> Self {
+        self.frames = frames;
+        self
+    }
+
+    /// Sets the number of frames run before measuring, 10 by default.
+    ///
+    /// Warmup frames fill the pipeline and bind group caches, so the measured frames only
+    /// create resources if the code under test keeps creating them.
+    pub fn set_warmup_frames(mut self, frames: u32) -> Self {
+        self.warmup_frames = frames;
+        self
+    }
+
+    /// Creates the headless GPU the frames are run on.
+    pub fn build(self) -> Result<Bench, BenchError> {
+        if self.frames == 0 {
+            return Err(BenchError::InvalidFrameCount);
+        }
+
+        let mut gpu = crate::gpu::new(None).build().map_err(BenchError::Gpu)?;
+
+        let profiler = match gpu.create_profiler(PROFILER_CAPACITY) {
+            Ok(profiler) => Some(profiler),
+            Err(_err) => {
+                crate::dbg_log!("Benchmark GPU timings disabled: {}", _err);
+                None
+            }
+        };
+
+        Ok(Bench {
+            name: self.name,
+            frames: self.frames,
+            warmup_frames: self.warmup_frames,
+            gpu,
+            profiler,
+        })
+    }
+}
+
+/// Runs a closure on a headless GPU and measures it, created with [new].
+#[derive(Debug)]
+pub struct Bench {
+    name: String,
+    frames: u32,
+    warmup_frames: u32,
+
+    gpu: GPU,
+    profiler: Option<GpuProfiler>,
+}
+
+impl Bench {
+    /// The GPU the frames are run on, to create the resources used by the benchmark.
+    pub fn gpu(&mut self) -> &mut GPU {
+        &mut self.gpu
+    }
+
+    /// Returns true if the adapter supports timestamp queries, and the report includes GPU timings.
+    pub fn has_gpu_timings(&self) -> bool {
+        self.profiler.is_some()
+    }
+
+    /// Runs the warmup frames then the measured frames, `f` records each frame.
+    ///
+    /// Every frame is submitted and waited for before the next one starts, so the GPU time of
+    /// a frame doesn't overlap the CPU time of the next.
+    pub fn run<F>(&mut self, mut f: F) -> Result<BenchReport, BenchError>
+    where
+        F: FnMut(&mut BenchFrame),
+    {
+        let mut samples = Vec::with_capacity(self.frames as usize);
+
+        for index in 0..self.warmup_frames + self.frames {
+            let sample = self.run_frame(index, &mut f)?;
+
+            if index >= self.warmup_frames {
+                samples.push(sample);
+            }
+        }
+
+        Ok(BenchReport::new(&self.name, self.warmup_frames, samples))
+    }
+
+    fn run_frame<F>(&mut self, index: u32, f: &mut F) -> Result<FrameSample, BenchError>
+    where
+        F: FnMut(&mut BenchFrame),
+    {
+        let start = Instant::now();
+
+        let mut cmd = self.gpu.begin_command().map_err(BenchError::Command)?;
+        let frame_scope = self
+            .profiler
+            .as_mut()
+            .and_then(|profiler| profiler.begin_scope(&mut cmd, "frame"));
+
+        let mut frame = BenchFrame {
+            gpu: &mut self.gpu,
+            cmd,
+            profiler: self.profiler.as_mut(),
+            index,
+        };
+
+        f(&mut frame);
+
+        let mut cmd = frame.cmd;
+        if let Some(profiler) = &mut self.profiler {
+            if let Some(scope) = frame_scope {
+                profiler.end_scope(&mut cmd, scope);
+            }
+
+            profiler.resolve(&mut cmd);
+        }
+
+        cmd.end(false);
+        let cpu = start.elapsed();
+
+        self.gpu.wait(GPUWaitType::Wait);
+        let frame_time = start.elapsed();
+
+        // Headless GPUs are not cycled by a window, age the caches like a windowed frame would
+        self.gpu.inner.borrow_mut().cycle();
+        self.gpu.end_frame();
+
+        let timings = match &mut self.profiler {
+            Some(profiler) => profiler.read_timings().to_vec(),
+            None => Vec::new(),
+        };
+
+        let gpu = timings
+            .iter()
+            .find(|timing| timing.label == "frame")
+            .map(|timing| timing.duration);
+
+        let scopes = timings
+            .into_iter()
+            .filter(|timing| timing.label != "frame")
+            .map(|timing| (timing.label, timing.duration))
+            .collect();
+
+        Ok(FrameSample {
+            cpu,
+            gpu,
+            frame: frame_time,
+            scopes,
+            usage: self.gpu.last_frame_usage(),
+        })
+    }
+}
+
+/// A frame being recorded by [Bench::run].
+pub struct BenchFrame<'a> {
+    gpu: &'a mut GPU,
+    cmd: CommandBuffer,
+    profiler: Option<&'a mut GpuProfiler>,
+    index: u32,
+}
+
+impl BenchFrame<'_> {
+    pub fn gpu(&mut self) -> &mut GPU {
+        self.gpu
+    }
+
+    /// The command buffer of the frame, submitted once the closure returns.
+    pub fn command(&mut self) -> &mut CommandBuffer {
+        &mut self.cmd
+    }
+
+    /// Index of the frame, warmup frames included.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Runs `f` and reports its GPU time separately, under `label`.
+    ///
+    /// Passes must be finished (dropped) before `f` returns to be included in the scope.
+    pub fn scope<R>(&mut self, label: &str, f: impl FnOnce(&mut CommandBuffer) -> R) -> R {
+        match &mut self.profiler {
+            Some(profiler) => profiler.scope(&mut self.cmd, label, f),
+            None => f(&mut self.cmd),
+        }
+    }
+}
+
+struct FrameSample {
+    cpu: Duration,
+    gpu: Option<Duration>,
+    frame: Duration,
+    scopes: Vec<(String, Duration)>,
+    usage: FrameUsage,
+}
+
+/// Distribution of a timing over the measured frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BenchStats {
+    pub min: Duration,
+    pub mean: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+impl BenchStats {
+    fn from_samples(mut samples: Vec<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+
+        samples.sort();
+
+        let sum: Duration = samples.iter().sum();
+        let p95 = ((samples.len() * 95).div_ceil(100)).saturating_sub(1);
+
+        Self {
+            min: samples[0],
+            mean: sum / samples.len() as u32,
+            median: samples[samples.len() / 2],
+            p95: samples[p95],
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+impl std::fmt::Display for BenchStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "mean {:?}, median {:?}, p95 {:?}, min {:?}, max {:?}",
+            self.mean, self.median, self.p95, self.min, self.max
+        )
+    }
+}
+
+/// Results of [Bench::run], warmup frames excluded.
+#[derive(Clone, Debug, Default)]
+pub struct BenchReport {
+    pub name: String,
+    pub frames: u32,
+    pub warmup_frames: u32,
+
+    /// Time spent recording and submitting a frame.
+    pub cpu: BenchStats,
+    /// GPU time of a frame, [None] if the adapter doesn't support timestamp queries.
+    pub gpu: Option<BenchStats>,
+    /// Time from the start of a frame until the GPU finished it.
+    pub frame: BenchStats,
+    /// GPU time of the scopes recorded with [BenchFrame::scope], in the order they first ran.
+    pub scopes: Vec<(String, BenchStats)>,
+
+    /// Resources created over all measured frames.
+    pub total_usage: FrameUsage,
+    /// Highest count of each resource created in a single frame.
+    pub max_usage: FrameUsage,
+}
+
+impl BenchReport {
+    fn new(name: &str, warmup_frames: u32, samples: Vec<FrameSample>) -> Self {
+        let mut total_usage = FrameUsage::default();
+        let mut max_usage = FrameUsage::default();
+
+        for sample in &samples {
+            total_usage.transient_buffer_bytes += sample.usage.transient_buffer_bytes;
+            total_usage.new_pipelines += sample.usage.new_pipelines;
+            total_usage.bind_groups += sample.usage.bind_groups;
+
+            max_usage.transient_buffer_bytes = max_usage
+                .transient_buffer_bytes
+                .max(sample.usage.transient_buffer_bytes);
+            max_usage.new_pipelines = max_usage.new_pipelines.max(sample.usage.new_pipelines);
+            max_usage.bind_groups = max_usage.bind_groups.max(sample.usage.bind_groups);
+        }
+
+        let mut scope_samples: Vec<(String, Vec<Duration>)> = Vec::new();
+        for (label, duration) in samples.iter().flat_map(|sample| sample.scopes.iter()) {
+            match scope_samples.iter_mut().find(|(name, _)| name == label) {
+                Some((_, durations)) => durations.push(*duration),
+                None => scope_samples.push((label.clone(), vec![*duration])),
+            }
+        }
+
+        let gpu = samples
+            .iter()
+            .map(|sample| sample.gpu)
+            .collect::<Option<Vec<_>>>()
+            .map(BenchStats::from_samples);
+
+        Self {
+            name: name.to_string(),
+            frames: samples.len() as u32,
+            warmup_frames,
+            cpu: BenchStats::from_samples(samples.iter().map(|sample| sample.cpu).collect()),
+            gpu,
+            frame: BenchStats::from_samples(samples.iter().map(|sample| sample.frame).collect()),
+            scopes: scope_samples
+                .into_iter()
+                .map(|(label, durations)| (label, BenchStats::from_samples(durations)))
+                .collect(),
+            total_usage,
+            max_usage,
+        }
+    }
+}
+
+impl std::fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}: {} frames ({} warmup)",
+            self.name, self.frames, self.warmup_frames
+        )?;
+        writeln!(f, "  cpu:   {}", self.cpu)?;
+
+        match &self.gpu {
+            Some(gpu) => writeln!(f, "  gpu:   {}", gpu)?,
+            None => writeln!(f, "  gpu:   unavailable")?,
+        }
+
+        writeln!(f, "  frame: {}", self.frame)?;
+
+        for (label, stats) in &self.scopes {
+            writeln!(f, "  {}: {}", label, stats)?;
+        }
+
+        write!(
+            f,
+            "  created: {} buffer bytes, {} pipelines, {} bind groups (max per frame: {}, {}, {})",
+            self.total_usage.transient_buffer_bytes,
+            self.total_usage.new_pipelines,
+            self.total_usage.bind_groups,
+            self.max_usage.transient_buffer_bytes,
+            self.max_usage.new_pipelines,
+            self.max_usage.bind_groups
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum BenchError {
+    InvalidFrameCount,
+    /// The headless GPU could not be created, eg. no adapter is available.
+    Gpu(GpuBuildError),
+    Command(CommandBufferBuildError),
+}
+
+impl std::fmt::Display for BenchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BenchError::InvalidFrameCount => write!(f, "Benchmark must run at least one frame"),
+            BenchError::Gpu(e) => write!(f, "Failed to create GPU: {}", e),
+            BenchError::Command(e) => write!(f, "Failed to begin command buffer: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
+
+use crate::{math::Vector2, utils::ArcRef};
+
+use super::{Font, Overflow, TextLayout};
+
+/// Default number of entries kept by each text cache of a [FontManager](super::FontManager).
+pub(crate) const DEFAULT_TEXT_CACHE_CAPACITY: usize = 512;
+
+/// Options that change the result of measuring or laying out a text, floats are stored as bits
+/// so they can be hashed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum TextStyle {
+    Size {
+        max_bounds: Option<(u32, u32)>,
+    },
+    Layout {
+        max_width: Option<u32>,
+        overflow: (u8, u32),
+    },
+}
+
+impl TextStyle {
+    pub fn size(max_bounds: Option<Vector2>) -> Self {
+        TextStyle::Size {
+            max_bounds: max_bounds.map(|b| (b.x.to_bits(), b.y.to_bits())),
+        }
+    }
+
+    pub fn layout(layout: &TextLayout) -> Self {
+        TextStyle::Layout {
+            max_width: layout.max_width.map(f32::to_bits),
+            overflow: match layout.overflow {
+                Overflow::Clip => (0, 0),
+                Overflow::Ellipsis => (1, 0),
+                Overflow::FadeOut(width) => (2, width.to_bits()),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TextCacheEntry<V> {
+    // Keeps the font alive, so its address is not reused by another font while cached
+    font: Font,
+    text: String,
+    style: TextStyle,
+    value: V,
+    last_used: u64,
+}
+
+/// Least recently used cache of text measurements, keyed by font, text and style.
+#[derive(Clone, Debug)]
+pub(crate) struct TextCache<V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<u64, TextCacheEntry<V>>,
+}
+
+impl<V: Clone> TextCache<V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > capacity {
+            self.evict();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Returns the cached value, or computes and caches it with `compute`.
+    pub fn get_or_insert_with(
+        &mut self,
+        font: &Font,
+        text: &str,
+        style: TextStyle,
+        compute: impl FnOnce() -> V,
+    ) -> V {
+        if self.capacity == 0 {
+            return compute();
+        }
+
+        let key = {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            (ArcRef::as_ptr(&font.inner) as usize).hash(&mut hasher);
+            text.hash(&mut hasher);
+            style.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        self.tick += 1;
+
+        if let Some(entry) = self.entries.get_mut(&key)
+            && entry.font.inner.ptr_eq(&font.inner)
+            && entry.style == style
+            && entry.text == text
+        {
+            entry.last_used = self.tick;
+            return entry.value.clone();
+        }
+
+        let value = compute();
+
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            self.evict();
+        }
+
+        self.entries.insert(
+            key,
+            TextCacheEntry {
+                font: font.clone(),
+                text: text.to_string(),
+                style,
+                value: value.clone(),
+                last_used: self.tick,
+            },
+        );
+
+        value
+    }
+
+    fn evict(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| *key);
+
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+        }
+    }
+}
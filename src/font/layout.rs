@@ -0,0 +1,319 @@
+use crate::math::Vector2;
+
+use super::{Font, FontInner};
+
+/// How a line that is wider than the layout max width is handled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Overflow {
+    /// Glyphs that don't fit are dropped.
+    Clip,
+    /// The line is cut and an ellipsis is appended, so the result fits the max width.
+    Ellipsis,
+    /// Glyphs fade out over the given width in pixels, ending at the max width.
+    FadeOut(f32),
+}
+
+/// A positioned glyph produced by [TextLayout::layout].
+#[derive(Clone, Copy, Debug)]
+pub struct LayoutGlyph {
+    pub codepoint: u32,
+    /// Top-left of the glyph quad, relative to the layout origin.
+    pub position: Vector2,
+    pub size: Vector2,
+    /// Opacity of the glyph, less than 1.0 for glyphs inside a fade out region.
+    pub alpha: f32,
+}
+
+/// A laid out line of text, used to place decorations and highlights.
+#[derive(Clone, Copy, Debug)]
+pub struct LayoutLine {
+    /// Top-left of the line box, relative to the layout origin.
+    pub position: Vector2,
+    pub width: f32,
+    pub height: f32,
+    /// Y position of the baseline, relative to the layout origin.
+    pub baseline: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextDecoration(u8);
+
+bitflags::bitflags! {
+    impl TextDecoration: u8 {
+        /// Draw a line below the baseline.
+        const UNDERLINE = 0b00000001;
+        /// Draw a line through the text.
+        const STRIKETHROUGH = 0b00000010;
+    }
+}
+
+/// The result of laying out a text.
+#[derive(Clone, Debug)]
+pub struct TextLayoutResult {
+    pub glyphs: Vec<LayoutGlyph>,
+    pub lines: Vec<LayoutLine>,
+    pub size: Vector2,
+    /// True if any line was cut to fit the max width.
+    pub truncated: bool,
+}
+
+/// Lays out text line by line, optionally truncating lines to a maximum width.
+///
+/// Used by `DrawingContext::draw_text_layout`, and can be used on its own to measure text
+/// the same way it will be drawn.
+#[derive(Clone, Copy, Debug)]
+pub struct TextLayout {
+    pub max_width: Option<f32>,
+    pub overflow: Overflow,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const ELLIPSIS_CODEPOINT: u32 = 0x2026;
+const DOT_CODEPOINT: u32 = '.' as u32;
+
+/// A single character of a line, before truncation is applied.
+struct LineItem {
+    codepoint: u32,
+    offset: f32,
+    advance: f32,
+    has_glyph: bool,
+}
+
+impl TextLayout {
+    pub fn new() -> Self {
+        Self {
+            max_width: None,
+            overflow: Overflow::Clip,
+        }
+    }
+
+    /// Set the maximum width of a line, `None` to disable truncation.
+    pub fn set_max_width(mut self, max_width: Option<f32>) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    pub fn set_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Returns the size of the laid out text and whether it was truncated.
+    pub fn measure(&self, font: &Font, text: &str) -> (Vector2, bool) {
+        let result = self.layout(font, text);
+        (result.size, result.truncated)
+    }
+
+    pub fn layout(&self, font: &Font, text: &str) -> TextLayoutResult {
+        let inner = font.inner.borrow();
+
+        let mut glyphs = Vec::new();
+        let mut lines = Vec::new();
+        let mut width = 0.0f32;
+        let mut line_count = 0;
+        let mut truncated = false;
+
+        for (line_index, line) in text.split('\n').enumerate() {
+            line_count += 1;
+
+            let items = Self::collect_line(&inner, line);
+            let line_y = line_index as f32 * inner.line_height;
+            let line_width = items.last().map(|i| i.offset + i.advance).unwrap_or(0.0);
+
+            let (line_width, line_truncated) = match self.max_width {
+                Some(max_width) if line_width > max_width => {
+                    let width = self.push_truncated(&inner, &items, line_y, max_width, &mut glyphs);
+                    (width, true)
+                }
+                _ => {
+                    for item in items.iter().filter(|i| i.has_glyph) {
+                        Self::push_glyph(&inner, &mut glyphs, item.codepoint, item.offset, line_y, 1.0);
+                    }
+
+                    (line_width, false)
+                }
+            };
+
+            lines.push(LayoutLine {
+                position: Vector2::new(0.0, line_y),
+                width: line_width,
+                height: inner.line_height,
+                baseline: line_y + inner.ascender,
+            });
+
+            width = width.max(line_width);
+            truncated |= line_truncated;
+        }
+
+        // Align the top of the tallest glyph to the origin, same as DrawingContext::draw_text.
+        let min_y = glyphs
+            .iter()
+            .map(|g| g.position.y)
+            .fold(f32::MAX, f32::min);
+
+        if min_y != f32::MAX {
+            for glyph in glyphs.iter_mut() {
+                glyph.position.y -= min_y;
+            }
+
+            for line in lines.iter_mut() {
+                line.position.y -= min_y;
+                line.baseline -= min_y;
+            }
+        }
+
+        TextLayoutResult {
+            glyphs,
+            lines,
+            size: Vector2::new(width, line_count as f32 * inner.line_height),
+            truncated,
+        }
+    }
+
+    fn collect_line(inner: &FontInner, line: &str) -> Vec<LineItem> {
+        let mut items = Vec::with_capacity(line.len());
+        let mut pen_x = 0.0;
+
+        for c in line.chars() {
+            let codepoint = c as u32;
+            if codepoint == 0 {
+                continue;
+            }
+
+            let (advance, has_glyph) = if codepoint == ' ' as u32 {
+                (inner.space_width, false)
+            } else if let Some(glyph) = inner.glyphs.get(&codepoint) {
+                (glyph.advance_x, true)
+            } else {
+                continue;
+            };
+
+            items.push(LineItem {
+                codepoint,
+                offset: pen_x,
+                advance,
+                has_glyph,
+            });
+
+            pen_x += advance;
+        }
+
+        items
+    }
+
+    /// Pushes the glyphs of a line that is wider than `max_width`, returns the resulting width.
+    fn push_truncated(
+        &self,
+        inner: &FontInner,
+        items: &[LineItem],
+        line_y: f32,
+        max_width: f32,
+        glyphs: &mut Vec<LayoutGlyph>,
+    ) -> f32 {
+        match self.overflow {
+            Overflow::Clip => {
+                let mut width = 0.0;
+                for item in items.iter().take_while(|i| i.offset + i.advance <= max_width) {
+                    if item.has_glyph {
+                        Self::push_glyph(inner, glyphs, item.codepoint, item.offset, line_y, 1.0);
+                    }
+
+                    width = item.offset + item.advance;
+                }
+
+                width
+            }
+            Overflow::Ellipsis => {
+                let ellipsis: &[u32] = if inner.glyphs.contains_key(&ELLIPSIS_CODEPOINT) {
+                    &[ELLIPSIS_CODEPOINT]
+                } else {
+                    &[DOT_CODEPOINT; 3]
+                };
+
+                let ellipsis_width: f32 = ellipsis
+                    .iter()
+                    .filter_map(|c| inner.glyphs.get(c))
+                    .map(|g| g.advance_x)
+                    .sum();
+
+                let available = (max_width - ellipsis_width).max(0.0);
+                let kept = items
+                    .iter()
+                    .take_while(|i| i.offset + i.advance <= available)
+                    .count();
+
+                // Don't leave whitespace between the text and the ellipsis
+                let kept = items[..kept]
+                    .iter()
+                    .rposition(|i| i.has_glyph)
+                    .map(|i| i + 1)
+                    .unwrap_or(0);
+
+                for item in items[..kept].iter().filter(|i| i.has_glyph) {
+                    Self::push_glyph(inner, glyphs, item.codepoint, item.offset, line_y, 1.0);
+                }
+
+                let mut pen_x = items[..kept]
+                    .last()
+                    .map(|i| i.offset + i.advance)
+                    .unwrap_or(0.0);
+
+                for codepoint in ellipsis {
+                    if let Some(glyph) = inner.glyphs.get(codepoint) {
+                        if pen_x + glyph.advance_x > max_width {
+                            break;
+                        }
+
+                        Self::push_glyph(inner, glyphs, *codepoint, pen_x, line_y, 1.0);
+                        pen_x += glyph.advance_x;
+                    }
+                }
+
+                pen_x
+            }
+            Overflow::FadeOut(fade_width) => {
+                let fade_width = fade_width.max(f32::EPSILON);
+                let mut width = 0.0;
+
+                for item in items.iter().take_while(|i| i.offset + i.advance <= max_width) {
+                    let center = item.offset + item.advance * 0.5;
+                    let alpha = ((max_width - center) / fade_width).clamp(0.0, 1.0);
+
+                    if item.has_glyph && alpha > 0.0 {
+                        Self::push_glyph(inner, glyphs, item.codepoint, item.offset, line_y, alpha);
+                    }
+
+                    width = item.offset + item.advance;
+                }
+
+                width
+            }
+        }
+    }
+
+    fn push_glyph(
+        inner: &FontInner,
+        glyphs: &mut Vec<LayoutGlyph>,
+        codepoint: u32,
+        pen_x: f32,
+        line_y: f32,
+        alpha: f32,
+    ) {
+        if let Some(glyph) = inner.glyphs.get(&codepoint) {
+            glyphs.push(LayoutGlyph {
+                codepoint,
+                position: Vector2::new(
+                    pen_x + glyph.bearing_x,
+                    line_y + inner.ascender - (glyph.bearing_y + glyph.height),
+                ),
+                size: Vector2::new(glyph.width, glyph.height),
+                alpha,
+            });
+        }
+    }
+}
@@ -15,6 +15,7 @@ use crate::{
     },
     math::{Point2, Vector2},
     utils::ArcRef,
+    window::Window,
 };
 
 
@@ -36,10 +37,14 @@ pub fn load_font(path: &str, glyph: Option<&[(u32, u32)]>, size: f32) -> Result<
     }
 
     let font_info = font_info.unwrap();
-    Font::new(font_info, size, glyph.unwrap_or(&[(0x20, 0x7E)]))
+    Font::new(font_info, size, glyph.unwrap_or(ranges::ASCII))
 }
 
 mod system;
+mod cache;
+mod layout;
+pub mod ranges;
+pub use layout::*;
 
 #[derive(Clone, Copy, Debug)]
 pub struct FontStyle(u8);
@@ -60,6 +65,33 @@ pub struct FontInfo {
     pub style: FontStyle,
 }
 
+/// Position and thickness of a text decoration line, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecorationMetrics {
+    /// Distance from the baseline to the top of the line, positive values are below the baseline.
+    pub offset: f32,
+    pub thickness: f32,
+}
+
+impl DecorationMetrics {
+    /// Fallback metrics for faces without `post`/`OS/2` decoration data.
+    fn fallback(ascender: f32, descender: f32, size: f32) -> (Self, Self) {
+        let thickness = (size / 16.0).max(1.0);
+
+        let underline = Self {
+            offset: -descender * 0.5,
+            thickness,
+        };
+
+        let strikeout = Self {
+            offset: -ascender * 0.3,
+            thickness,
+        };
+
+        (underline, strikeout)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FontInner {
     pub info: FontInfo,
@@ -71,6 +103,8 @@ pub struct FontInner {
     pub descender: f32,
     pub line_height: f32,
     pub space_width: f32,
+    pub underline: DecorationMetrics,
+    pub strikeout: DecorationMetrics,
 }
 
 #[derive(Clone, Debug)]
@@ -117,9 +151,18 @@ impl std::fmt::Debug for FontError {
     }
 }
 
+impl PartialEq for Font {
+    fn eq(&self, other: &Self) -> bool {
+        ArcRef::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for Font {}
+
 impl Font {
     pub(crate) fn new(info: FontInfo, size: f32, glyph_range: &[(u32, u32)]) -> Result<Self, FontError> {
         let data = std::fs::read(&info.path).expect("Failed to read font file");
+        let face_decoration = Self::read_decoration_metrics(&data, size);
         let font = fontdue::Font::from_bytes(data, fontdue::FontSettings::default())
             .expect("Failed to parse font file");
 
@@ -148,6 +191,12 @@ impl Font {
         let line_height = line_metrics.ascent - line_metrics.descent + line_metrics.line_gap;
         let space_metrics = font.metrics(' ', size);
 
+        let (fallback_underline, fallback_strikeout) =
+            DecorationMetrics::fallback(ascender, descender, size);
+        let (underline, strikeout) = face_decoration.unwrap_or((None, None));
+        let underline = underline.unwrap_or(fallback_underline);
+        let strikeout = strikeout.unwrap_or(fallback_strikeout);
+
         // Calculate texture estimated width based on glyph range
         // to avoid very WIDE font atlas
         let tex_width = {
@@ -273,6 +322,8 @@ impl Font {
             descender,
             line_height,
             space_width: space_metrics.advance_width as f32,
+            underline,
+            strikeout,
         };
 
         let inner = ArcRef::new(inner);
@@ -282,10 +333,37 @@ impl Font {
         })
     }
 
+    /// Reads the underline and strikeout metrics from the face, scaled to the font size.
+    fn read_decoration_metrics(
+        data: &[u8],
+        size: f32,
+    ) -> Option<(Option<DecorationMetrics>, Option<DecorationMetrics>)> {
+        let face = ttf_parser::Face::parse(data, 0).ok()?;
+        let scale = size / face.units_per_em() as f32;
+
+        let convert = |metrics: ttf_parser::LineMetrics| DecorationMetrics {
+            offset: -(metrics.position as f32) * scale,
+            thickness: (metrics.thickness as f32 * scale).max(1.0),
+        };
+
+        Some((
+            face.underline_metrics().map(convert),
+            face.strikeout_metrics().map(convert),
+        ))
+    }
+
     pub fn line_height(&self) -> f32 {
         self.inner.borrow().line_height
     }
 
+    pub fn underline_metrics(&self) -> DecorationMetrics {
+        self.inner.borrow().underline
+    }
+
+    pub fn strikeout_metrics(&self) -> DecorationMetrics {
+        self.inner.borrow().strikeout
+    }
+
     pub fn ascender(&self) -> f32 {
         self.inner.borrow().ascender
     }
@@ -584,6 +662,22 @@ impl Font {
         let line_height = reader.read_f32::<LittleEndian>()?;
         let space_width = reader.read_f32::<LittleEndian>()?;
 
+        // Decoration metrics were added later, older caches end before them.
+        let (fallback_underline, fallback_strikeout) =
+            DecorationMetrics::fallback(ascender, descender, ascender - descender);
+        let mut read_decoration = |fallback: DecorationMetrics| -> DecorationMetrics {
+            match (
+                reader.read_f32::<LittleEndian>(),
+                reader.read_f32::<LittleEndian>(),
+            ) {
+                (Ok(offset), Ok(thickness)) => DecorationMetrics { offset, thickness },
+                _ => fallback,
+            }
+        };
+
+        let underline = read_decoration(fallback_underline);
+        let strikeout = read_decoration(fallback_strikeout);
+
         let inner = FontInner {
             info,
             glyphs,
@@ -594,6 +688,8 @@ impl Font {
             descender,
             line_height,
             space_width,
+            underline,
+            strikeout,
         };
 
         let inner = ArcRef::new(inner);
@@ -645,6 +741,11 @@ impl Font {
         writer2.write_f32::<LittleEndian>(inner.line_height)?;
         writer2.write_f32::<LittleEndian>(inner.space_width)?;
 
+        writer2.write_f32::<LittleEndian>(inner.underline.offset)?;
+        writer2.write_f32::<LittleEndian>(inner.underline.thickness)?;
+        writer2.write_f32::<LittleEndian>(inner.strikeout.offset)?;
+        writer2.write_f32::<LittleEndian>(inner.strikeout.thickness)?;
+
         let uncompressed_data: Vec<u8> = writer2.into_inner();
         let uncompressed_size = uncompressed_data.len() as u32;
 
@@ -788,13 +889,52 @@ impl PartialEq for Glyph {
     }
 }
 
+/// A font rasterized at the scale factor of a window, see [FontManager::load_font_scaled].
+#[derive(Clone, Debug)]
+pub struct ScaledFont {
+    font: Font,
+    window: Window,
+    font_name: String,
+    glyph_range: Vec<(u32, u32)>,
+    logical_size: f32,
+    scale_factor: f64,
+}
+
+impl ScaledFont {
+    /// The font rasterized at [ScaledFont::physical_size].
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    /// The window the font is scaled for.
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    /// The size in logical units, as passed to [FontManager::load_font_scaled].
+    pub fn logical_size(&self) -> f32 {
+        self.logical_size
+    }
+
+    /// The scale factor the font is currently rasterized at.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// The size in pixels the font is currently rasterized at.
+    pub fn physical_size(&self) -> f32 {
+        self.logical_size * self.scale_factor as f32
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FontManager {
     fonts: Vec<FontInfo>,
     cached_font: HashMap<u64, Font>,
+    cached_sizes: cache::TextCache<Vector2>,
+    cached_layouts: cache::TextCache<TextLayoutResult>,
 }
 
-const DEFAULT_GLYPH_RANGE: [(u32, u32); 1] = [(0x20, 0x7E)]; // ASCII range
 
 impl FontManager {
     /// Creates a new FontManager instance.
@@ -806,11 +946,51 @@ impl FontManager {
         FontManager {
             fonts,
             cached_font: HashMap::new(),
+            cached_sizes: cache::TextCache::new(cache::DEFAULT_TEXT_CACHE_CAPACITY),
+            cached_layouts: cache::TextCache::new(cache::DEFAULT_TEXT_CACHE_CAPACITY),
         }
     }
 
+    /// Same as [Font::calculate_text_size], but the result is cached for the font, text and
+    /// bounds.
+    ///
+    /// Useful for UI layouts that measure the same strings every frame.
+    pub fn measure_text(&mut self, font: &Font, text: &str, max_bounds: Option<Vector2>) -> Vector2 {
+        self.cached_sizes
+            .get_or_insert_with(font, text, cache::TextStyle::size(max_bounds), || {
+                font.calculate_text_size(text, max_bounds)
+            })
+    }
+
+    /// Same as [TextLayout::layout], but the result is cached for the font, text and layout
+    /// options.
+    pub fn layout_text(&mut self, font: &Font, text: &str, layout: &TextLayout) -> TextLayoutResult {
+        self.cached_layouts
+            .get_or_insert_with(font, text, cache::TextStyle::layout(layout), || {
+                layout.layout(font, text)
+            })
+    }
+
+    /// Sets the number of entries kept by the measurement and layout caches, the least
+    /// recently used are dropped first. `0` disables caching.
+    ///
+    /// The default is 512 entries each.
+    pub fn set_text_cache_capacity(&mut self, capacity: usize) {
+        self.cached_sizes.set_capacity(capacity);
+        self.cached_layouts.set_capacity(capacity);
+    }
+
+    /// Drops every cached measurement and layout.
+    pub fn clear_text_cache(&mut self) {
+        self.cached_sizes.clear();
+        self.cached_layouts.clear();
+    }
+
     /// Loads a font by name and size, optionally specifying a glyph range.
     ///
+    /// The glyph range defaults to [ranges::ASCII], see [ranges] for other presets and
+    /// [ranges::union] to combine them.
+    ///
     /// If the font is already cached, it will return the cached version.
     /// If the font is not found, it will return `None`.
     pub fn load_font(
@@ -819,7 +999,7 @@ impl FontManager {
         glyph_range: Option<&[(u32, u32)]>,
         size: f32,
     ) -> Result<Font, FontError> {
-        let glyph_range = glyph_range.unwrap_or(&DEFAULT_GLYPH_RANGE);
+        let glyph_range = glyph_range.unwrap_or(ranges::ASCII);
 
         let hashed_name = {
             let mut hasher = std::collections::hash_map::DefaultHasher::new();
@@ -880,6 +1060,71 @@ impl FontManager {
         )))
     }
 
+    /// Loads a font by name at `logical_size` multiplied by the scale factor of the window.
+    ///
+    /// Call [FontManager::update_scaled_font] when the window reports
+    /// [Event::WindowScaleFactorChanged](crate::runner::Event::WindowScaleFactorChanged), so
+    /// the text stays sharp when the window moves between monitors with a different DPI.
+    pub fn load_font_scaled(
+        &mut self,
+        font_name: &str,
+        logical_size: f32,
+        window: &Window,
+    ) -> Result<ScaledFont, FontError> {
+        self.load_font_scaled_ex(font_name, None, logical_size, window)
+    }
+
+    /// Same as [FontManager::load_font_scaled], with a glyph range like [FontManager::load_font].
+    pub fn load_font_scaled_ex(
+        &mut self,
+        font_name: &str,
+        glyph_range: Option<&[(u32, u32)]>,
+        logical_size: f32,
+        window: &Window,
+    ) -> Result<ScaledFont, FontError> {
+        let glyph_range = glyph_range.unwrap_or(ranges::ASCII);
+        let scale_factor = window.scale_factor();
+        let font = self.load_font(
+            font_name,
+            Some(glyph_range),
+            logical_size * scale_factor as f32,
+        )?;
+
+        Ok(ScaledFont {
+            font,
+            window: window.clone(),
+            font_name: font_name.to_string(),
+            glyph_range: glyph_range.to_vec(),
+            logical_size,
+            scale_factor,
+        })
+    }
+
+    /// Reloads a [ScaledFont] if the scale factor of its window has changed.
+    ///
+    /// Returns `true` when the font was reloaded, in which case any text baked or texture
+    /// created from the previous font must be created again.
+    pub fn update_scaled_font(&mut self, font: &mut ScaledFont) -> Result<bool, FontError> {
+        let scale_factor = font.window.scale_factor();
+        if scale_factor == font.scale_factor {
+            return Ok(false);
+        }
+
+        let size = font.logical_size * scale_factor as f32;
+        crate::dbg_log!(
+            "Reloading font '{}' at {}px, scale factor changed from {} to {}",
+            font.font_name,
+            size,
+            font.scale_factor,
+            scale_factor
+        );
+
+        font.font = self.load_font(&font.font_name, Some(&font.glyph_range), size)?;
+        font.scale_factor = scale_factor;
+
+        Ok(true)
+    }
+
     /// Loads a font from a cached file.
     ///
     /// This will load the font from a binary file created by [Font::save_font_cache].
@@ -0,0 +1,76 @@
+//! Predefined glyph ranges for [FontManager::load_font](super::FontManager::load_font).
+//!
+//! Every preset includes the printable ASCII range, so it can be used on its own. Presets can
+//! be combined with [union], eg. `union(&[LATIN1, CYRILLIC])`.
+//!
+//! All the glyphs of the ranges are packed into a single atlas of at most 2048x2048, large
+//! ranges only fit at small font sizes.
+
+/// Printable ASCII, the default range.
+pub const ASCII: &[(u32, u32)] = &[(0x20, 0x7E)];
+
+/// ASCII and the Latin-1 Supplement (Western European accents, currency signs).
+pub const LATIN1: &[(u32, u32)] = &[(0x20, 0x7E), (0xA0, 0xFF)];
+
+/// Latin-1 with Latin Extended-A, Extended-B and Latin Extended Additional (Central European,
+/// Vietnamese, etc).
+pub const LATIN_EXTENDED: &[(u32, u32)] = &[(0x20, 0x7E), (0xA0, 0x24F), (0x1E00, 0x1EFF)];
+
+/// ASCII with Cyrillic, Cyrillic Supplement and Cyrillic Extended-A/B.
+pub const CYRILLIC: &[(u32, u32)] = &[
+    (0x20, 0x7E),
+    (0x400, 0x52F),
+    (0x2DE0, 0x2DFF),
+    (0xA640, 0xA69F),
+];
+
+/// ASCII with Greek and Coptic.
+pub const GREEK: &[(u32, u32)] = &[(0x20, 0x7E), (0x370, 0x3FF)];
+
+/// ASCII with CJK punctuation, Hiragana, Katakana and the half/full width forms.
+///
+/// CJK ideographs are not included, their block ([CJK_IDEOGRAPHS]) only fits the atlas at
+/// about 12px and below.
+pub const CJK_COMMON: &[(u32, u32)] = &[
+    (0x20, 0x7E),
+    (0x3000, 0x30FF),
+    (0x31F0, 0x31FF),
+    (0xFF00, 0xFFEF),
+];
+
+/// The CJK Unified Ideographs block, about 21000 glyphs.
+pub const CJK_IDEOGRAPHS: &[(u32, u32)] = &[(0x4E00, 0x9FFF)];
+
+/// ASCII with the common emoji and pictograph blocks.
+///
+/// **NOTE:** Glyphs are rasterized from their outlines, color emoji fonts (bitmap or layered
+/// color glyphs) render as empty or monochrome glyphs.
+pub const EMOJI: &[(u32, u32)] = &[
+    (0x20, 0x7E),
+    (0x2600, 0x27BF),
+    (0x1F300, 0x1F64F),
+    (0x1F680, 0x1F6FF),
+    (0x1F900, 0x1F9FF),
+];
+
+/// Merges glyph ranges into a sorted list without overlaps, so shared glyphs are only
+/// rasterized once.
+pub fn union(ranges: &[&[(u32, u32)]]) -> Vec<(u32, u32)> {
+    let mut sorted: Vec<(u32, u32)> = ranges
+        .iter()
+        .flat_map(|range| range.iter())
+        .map(|&(start, end)| (start.min(end), start.max(end)))
+        .collect();
+
+    sorted.sort_unstable();
+
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(sorted.len());
+    for (start, end) in sorted {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+}
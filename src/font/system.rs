@@ -21,7 +21,8 @@ pub fn search_system_font() -> Vec<FontInfo> {
         crate::dbg_log!("Searching system fonts...");
     }
 
-    let mut font_dirs = Vec::new();
+    #[cfg_attr(target_arch = "wasm32", allow(unused_mut))]
+    let mut font_dirs: Vec<String> = Vec::new();
 
     #[cfg(target_os = "windows")]
     {
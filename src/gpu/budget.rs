@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+/// Soft limits on the resources created during a single frame.
+///
+/// Pipelines and bind groups are cached, so a bug that creates a new one every frame keeps
+/// working while the cache grows until memory runs out. Budgets report those frames instead,
+/// see [GPU::set_frame_budget](super::GPU::set_frame_budget).
+///
+/// ```rust,ignore
+/// gpu.set_frame_budget(Some(
+///     FrameBudget::new()
+///         .set_max_new_pipelines(2)
+///         .set_max_bind_groups(32),
+/// ));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameBudget {
+    pub max_transient_buffer_bytes: Option<u64>,
+    pub max_new_pipelines: Option<u32>,
+    pub max_bind_groups: Option<u32>,
+}
+
+impl FrameBudget {
+    /// Creates a budget without any limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the bytes of buffers created during a frame, including the buffers created
+    /// internally, eg. by `set_attachment_buffer_raw` or the drawing context.
+    pub fn set_max_transient_buffer_bytes(mut self, bytes: u64) -> Self {
+        self.max_transient_buffer_bytes = Some(bytes);
+        self
+    }
+
+    /// Limits the render and compute pipelines created during a frame, pipelines found in the
+    /// cache are not counted.
+    pub fn set_max_new_pipelines(mut self, count: u32) -> Self {
+        self.max_new_pipelines = Some(count);
+        self
+    }
+
+    /// Limits the bind groups created during a frame, bind groups found in the cache are not
+    /// counted.
+    pub fn set_max_bind_groups(mut self, count: u32) -> Self {
+        self.max_bind_groups = Some(count);
+        self
+    }
+}
+
+/// Resources created during a frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameUsage {
+    pub transient_buffer_bytes: u64,
+    pub new_pipelines: u32,
+    pub bind_groups: u32,
+}
+
+/// A limit of the [FrameBudget] that was exceeded, reported once per limit and frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BudgetExceeded {
+    TransientBufferBytes { used: u64, limit: u64 },
+    NewPipelines { used: u32, limit: u32 },
+    BindGroups { used: u32, limit: u32 },
+}
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BudgetExceeded::TransientBufferBytes { used, limit } => write!(
+                f,
+                "Frame buffer budget exceeded: {} bytes created, limit is {} bytes",
+                used, limit
+            ),
+            BudgetExceeded::NewPipelines { used, limit } => write!(
+                f,
+                "Frame pipeline budget exceeded: {} pipelines created, limit is {}",
+                used, limit
+            ),
+            BudgetExceeded::BindGroups { used, limit } => write!(
+                f,
+                "Frame bind group budget exceeded: {} bind groups created, limit is {}",
+                used, limit
+            ),
+        }
+    }
+}
+
+type BudgetCallback = Arc<dyn Fn(&BudgetExceeded) + Send + Sync>;
+type PendingReport = Option<(BudgetExceeded, Option<BudgetCallback>)>;
+
+/// Counts the resources created in the current frame and reports the exceeded limits.
+#[derive(Clone, Default)]
+pub(crate) struct FrameBudgetTracker {
+    pub budget: Option<FrameBudget>,
+    pub callback: Option<BudgetCallback>,
+
+    pub usage: FrameUsage,
+    pub last_usage: FrameUsage,
+
+    // Limits already reported this frame, in the order of the FrameBudget fields
+    reported: [bool; 3],
+}
+
+impl std::fmt::Debug for FrameBudgetTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameBudgetTracker")
+            .field("budget", &self.budget)
+            .field("callback", &self.callback.is_some())
+            .field("usage", &self.usage)
+            .field("last_usage", &self.last_usage)
+            .finish()
+    }
+}
+
+impl FrameBudgetTracker {
+    pub fn add_buffer_bytes(&mut self, bytes: u64) -> PendingReport {
+        self.usage.transient_buffer_bytes += bytes;
+
+        let used = self.usage.transient_buffer_bytes;
+        let limit = self.budget?.max_transient_buffer_bytes?;
+
+        self.check(0, used > limit, BudgetExceeded::TransientBufferBytes { used, limit })
+    }
+
+    pub fn add_pipeline(&mut self) -> PendingReport {
+        self.usage.new_pipelines += 1;
+
+        let used = self.usage.new_pipelines;
+        let limit = self.budget?.max_new_pipelines?;
+
+        self.check(1, used > limit, BudgetExceeded::NewPipelines { used, limit })
+    }
+
+    pub fn add_bind_groups(&mut self, count: u32) -> PendingReport {
+        self.usage.bind_groups += count;
+
+        let used = self.usage.bind_groups;
+        let limit = self.budget?.max_bind_groups?;
+
+        self.check(2, used > limit, BudgetExceeded::BindGroups { used, limit })
+    }
+
+    pub fn end_frame(&mut self) {
+        self.last_usage = std::mem::take(&mut self.usage);
+        self.reported = [false; 3];
+    }
+
+    fn check(&mut self, index: usize, exceeded: bool, report: BudgetExceeded) -> PendingReport {
+        if !exceeded || self.reported[index] {
+            return None;
+        }
+
+        self.reported[index] = true;
+        Some((report, self.callback.clone()))
+    }
+
+    /// Reports an exceeded limit returned by one of the `add_*` functions, called once the
+    /// tracker is unlocked so the callback may change the budget.
+    pub fn report(exceeded: PendingReport) {
+        match exceeded {
+            Some((exceeded, Some(callback))) => callback(&exceeded),
+            Some((exceeded, None)) => {
+                crate::warn_log!("{}", exceeded);
+            }
+            None => {}
+        }
+    }
+}
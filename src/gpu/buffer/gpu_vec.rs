@@ -0,0 +1,221 @@
+use std::ops::Range;
+
+use crate::utils::ArcRef;
+
+use super::{super::GPUInner, Buffer, BufferError, BufferUsage, COPY_ALIGNMENT};
+
+/// Capacity, in elements, of the buffer of an empty [GpuVec].
+const MIN_CAPACITY: usize = 16;
+
+/// A growable array of `T` backed by a GPU buffer, for data rebuilt every frame such as UI or
+/// debug line vertices.
+///
+/// Elements are changed in a CPU copy, [GpuVec::flush] then uploads the range modified since
+/// the last flush, usually once per frame before recording the passes using it. When the
+/// elements outgrow the buffer its capacity doubles and the buffer is recreated, fetch
+/// [GpuVec::buffer] again after flushing instead of keeping it around.
+///
+/// ```rust,ignore
+/// let mut lines = gpu.create_gpu_vec::<LineVertex>(BufferUsage::VERTEX)?;
+///
+/// // Every frame
+/// lines.clear();
+/// lines.extend_from_slice(&frame_lines);
+/// lines.flush()?;
+///
+/// pass.set_gpu_buffer(Some(lines.buffer()), None);
+/// pass.draw(0..lines.len() as u32, 1);
+/// ```
+#[derive(Debug)]
+pub struct GpuVec<T: bytemuck::Pod> {
+    graphics: ArcRef<GPUInner>,
+    buffer: Buffer,
+    data: Vec<T>,
+    /// Number of elements the buffer can hold.
+    capacity: usize,
+    usage: BufferUsage,
+    /// Elements modified since the last flush.
+    dirty: Option<Range<usize>>,
+}
+
+impl<T: bytemuck::Pod> GpuVec<T> {
+    pub(crate) fn new(graphics: ArcRef<GPUInner>, usage: BufferUsage) -> Result<Self, BufferError> {
+        if std::mem::size_of::<T>() == 0 {
+            return Err(BufferError::InvalidSize);
+        }
+
+        let usage = usage | BufferUsage::COPY_DST;
+        let buffer = create_buffer::<T>(&graphics, MIN_CAPACITY, usage)?;
+
+        Ok(Self {
+            graphics,
+            buffer,
+            data: Vec::new(),
+            capacity: MIN_CAPACITY,
+            usage,
+            dirty: None,
+        })
+    }
+
+    /// The GPU buffer, its content matches the elements once [GpuVec::flush] was called.
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    pub fn usage(&self) -> BufferUsage {
+        self.usage
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Number of elements the GPU buffer can hold without being recreated.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns true if some elements changed since the last flush.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+            .as_ref()
+            .is_some_and(|range| range.start < self.data.len())
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.data.get(index)
+    }
+
+    /// Returns the element at `index` for modification, marking it dirty.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.data.len() {
+            return None;
+        }
+
+        self.mark_dirty(index..index + 1);
+        self.data.get_mut(index)
+    }
+
+    /// Replaces the element at `index`, panics if it is out of bounds.
+    pub fn set(&mut self, index: usize, value: T) {
+        self.data[index] = value;
+        self.mark_dirty(index..index + 1);
+    }
+
+    pub fn push(&mut self, value: T) {
+        let index = self.data.len();
+        self.data.push(value);
+        self.mark_dirty(index..index + 1);
+    }
+
+    pub fn extend_from_slice(&mut self, values: &[T]) {
+        let start = self.data.len();
+        self.data.extend_from_slice(values);
+        self.mark_dirty(start..self.data.len());
+    }
+
+    /// Removes every element, the GPU buffer keeps its capacity.
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    /// Keeps the first `len` elements, the GPU buffer keeps its capacity.
+    pub fn truncate(&mut self, len: usize) {
+        self.data.truncate(len);
+    }
+
+    /// Grows the GPU buffer at the next flush so at least `additional` more elements fit.
+    pub fn reserve(&mut self, additional: usize) {
+        self.data.reserve(additional);
+    }
+
+    /// Uploads the elements modified since the last flush, recreating the GPU buffer with a
+    /// doubled capacity if they no longer fit.
+    ///
+    /// Returns true when the buffer was recreated, bind groups made with the previous
+    /// [GpuVec::buffer] must be created again.
+    pub fn flush(&mut self) -> Result<bool, BufferError> {
+        let required = self.data.len().max(self.data.capacity());
+        let mut recreated = false;
+
+        if required > self.capacity {
+            let capacity = required.max(self.capacity * 2);
+
+            self.buffer = create_buffer::<T>(&self.graphics, capacity, self.usage)?;
+            self.capacity = capacity;
+            self.dirty = Some(0..self.data.len());
+            recreated = true;
+
+            crate::dbg_log!("GpuVec grown to {} elements", capacity);
+        }
+
+        let Some(range) = self.dirty.take() else {
+            return Ok(recreated);
+        };
+
+        let end = range.end.min(self.data.len());
+        if range.start >= end {
+            return Ok(recreated);
+        }
+
+        let size = std::mem::size_of::<T>() as u64;
+        let bytes: &[u8] = bytemuck::cast_slice(&self.data);
+
+        // Queue writes need an aligned offset and size, the buffer size is aligned as well
+        let start = (range.start as u64 * size) / COPY_ALIGNMENT * COPY_ALIGNMENT;
+        let end = (end as u64 * size).next_multiple_of(COPY_ALIGNMENT);
+
+        let mut data = bytes[start as usize..(end as usize).min(bytes.len())].to_vec();
+        data.resize((end - start) as usize, 0);
+
+        let inner = self.buffer.inner.borrow();
+        let graphics_ref = self.graphics.borrow();
+        graphics_ref
+            .queue()
+            .write_buffer(&inner.buffer, start, &data);
+
+        Ok(recreated)
+    }
+
+    fn mark_dirty(&mut self, range: Range<usize>) {
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+            None => range,
+        });
+    }
+}
+
+impl<T: bytemuck::Pod> Extend<T> for GpuVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let start = self.data.len();
+        self.data.extend(iter);
+
+        if self.data.len() > start {
+            self.mark_dirty(start..self.data.len());
+        }
+    }
+}
+
+fn create_buffer<T: bytemuck::Pod>(
+    graphics: &ArcRef<GPUInner>,
+    capacity: usize,
+    usage: BufferUsage,
+) -> Result<Buffer, BufferError> {
+    let size = (capacity * std::mem::size_of::<T>()) as u64;
+
+    Buffer::new(
+        ArcRef::clone(graphics),
+        size.next_multiple_of(COPY_ALIGNMENT),
+        usage,
+        false,
+        Some("GpuVec".to_string()),
+    )
+}
@@ -1,18 +1,22 @@
 #![allow(dead_code)]
 
-use std::cell::RefMut;
-
 // use crate::{gpu::gpu_inner::GPUInner, utils::ArcRef};
 
 // use super::command::CommandBuffer;
 
-use crate::utils::ArcRef;
+use std::sync::Arc;
+
+use crate::utils::{ArcRef, LockRank, RefMut};
 
 use super::{
-    command::CommandBuffer,
+    command::{utils::BufferRange, CommandBuffer},
     GPUInner,
 };
 
+pub mod gpu_vec;
+pub mod pool;
+pub mod uniform;
+pub mod uniform_arena;
 pub(crate) mod staging_buffer;
 
 /// Represents the usage flags for a GPU buffer.
@@ -51,6 +55,8 @@ pub struct BufferBuilder<T: bytemuck::Pod + bytemuck::Zeroable> {
     len: usize,
     usage: BufferUsage,
     mapped: bool,
+    label: Option<String>,
+    pool: Option<pool::BufferPool>,
 }
 
 impl<T: bytemuck::Pod + bytemuck::Zeroable> BufferBuilder<T> {
@@ -61,6 +67,8 @@ impl<T: bytemuck::Pod + bytemuck::Zeroable> BufferBuilder<T> {
             usage: BufferUsage::empty(),
             len: 0,
             mapped: false,
+            label: None,
+            pool: None,
         }
     }
 
@@ -101,20 +109,54 @@ impl<T: bytemuck::Pod + bytemuck::Zeroable> BufferBuilder<T> {
         self
     }
 
+    /// Set the debug label of the buffer, shown in graphics debuggers.
+    pub fn set_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Allocate the buffer from a range of one of the slabs of `pool` instead of creating a
+    /// buffer of its own, see [BufferPool](pool::BufferPool).
+    ///
+    /// The build will fail with [BufferError::InvalidUsage] if the buffer is mapped or if its
+    /// usage isn't part of the pool usage. Pooled buffers can always be copied from and to.
+    pub fn set_pool(mut self, pool: &pool::BufferPool) -> Self {
+        self.pool = Some(pool.clone());
+        self
+    }
+
     pub fn build(self) -> Result<Buffer, BufferError> {
         if self.len == 0 && matches!(self.data, BufferData::None) {
             return Err(BufferError::InvalidSize);
         }
 
+        if let Some(pool) = self.pool {
+            let data = match self.data {
+                BufferData::None => None,
+                BufferData::Data(data) => Some(bytemuck::cast_slice::<T, u8>(&data).to_vec()),
+            };
+
+            return Buffer::from_pool(
+                self.graphics,
+                &pool,
+                self.len as wgpu::BufferAddress,
+                data.as_deref(),
+                self.usage,
+                self.mapped,
+                self.label,
+            );
+        }
+
         match self.data {
             BufferData::None => Buffer::new(
                 self.graphics,
                 self.len as wgpu::BufferAddress,
                 self.usage,
                 self.mapped,
+                self.label,
             ),
             BufferData::Data(data) => {
-                Buffer::from_slice(self.graphics, &data, self.usage, self.mapped)
+                Buffer::from_slice(self.graphics, &data, self.usage, self.mapped, self.label)
             }
         }
     }
@@ -127,6 +169,26 @@ pub(crate) struct BufferInner {
     pub size: wgpu::BufferAddress,
     pub usage: BufferUsage,
     pub mapped: bool,
+    pub label: Option<String>,
+
+    /// Offset of the buffer in [BufferInner::buffer], only non-zero for pooled buffers.
+    pub offset: wgpu::BufferAddress,
+    /// Range of the pool slab owned by the buffer.
+    pub allocation: Option<Arc<pool::PoolAllocation>>,
+}
+
+impl BufferInner {
+    /// The part of [BufferInner::buffer] holding this buffer.
+    pub fn range(&self) -> BufferRange {
+        match self.allocation {
+            Some(_) => BufferRange {
+                buffer: self.buffer.clone(),
+                offset: self.offset,
+                size: self.size,
+            },
+            None => self.buffer.clone().into(),
+        }
+    }
 }
 
 /// Represents a GPU buffer.
@@ -146,14 +208,22 @@ pub enum BufferError {
     BufferNotReadable,
     BufferNotWritable,
     FailedToMapBuffer,
+    /// An offset or size of [Buffer::copy_range] is not a multiple of [COPY_ALIGNMENT].
+    InvalidAlignment,
+    /// The range of [Buffer::copy_range] goes past the end of one of the buffers.
+    InvalidRange,
 }
 
+/// Alignment in bytes of the offsets and size of [Buffer::copy_range].
+pub const COPY_ALIGNMENT: u64 = wgpu::COPY_BUFFER_ALIGNMENT;
+
 impl Buffer {
     pub(crate) fn new(
         graphics: ArcRef<GPUInner>,
         size: wgpu::BufferAddress,
         usage: BufferUsage,
         mapped: bool,
+        label: Option<String>,
     ) -> Result<Self, BufferError> {
         if size == 0 {
             return Err(BufferError::InvalidSize);
@@ -163,7 +233,7 @@ impl Buffer {
             let mut graphics_ref = graphics.borrow_mut();
             let usage_wgpu: wgpu::BufferUsages = usage.clone().into();
 
-            graphics_ref.create_buffer(size, usage_wgpu, mapped)
+            graphics_ref.create_buffer_labeled(size, usage_wgpu, mapped, label.as_deref())
         };
 
         let inner = BufferInner {
@@ -171,11 +241,21 @@ impl Buffer {
             size,
             usage,
             mapped,
+            label,
+            offset: 0,
+            allocation: None,
         };
 
+        let (handle, allocation) = (inner.buffer.clone(), inner.allocation.clone());
+        let inner = ArcRef::new_ranked(inner, LockRank::Resource);
+        graphics
+            .borrow_mut()
+            .resources
+            .register_buffer(&inner, handle, allocation);
+
         Ok(Buffer {
             graphics,
-            inner: ArcRef::new(inner),
+            inner,
             mapped_buffer: if mapped {
                 vec![0; size as usize]
             } else {
@@ -190,6 +270,7 @@ impl Buffer {
         data: &[T],
         usage: BufferUsage,
         mapped: bool,
+        label: Option<String>,
     ) -> Result<Self, BufferError> {
         if data.is_empty() {
             return Err(BufferError::InvalidSize);
@@ -200,7 +281,7 @@ impl Buffer {
             let mut graphics_ref = graphics.borrow_mut();
             let usage_wgpu: wgpu::BufferUsages = usage.clone().into();
 
-            graphics_ref.create_buffer_with(data, usage_wgpu)
+            graphics_ref.create_buffer_with_labeled(data, usage_wgpu, label.as_deref())
         };
 
         let inner = BufferInner {
@@ -208,11 +289,21 @@ impl Buffer {
             size,
             usage,
             mapped,
+            label,
+            offset: 0,
+            allocation: None,
         };
 
+        let (handle, allocation) = (inner.buffer.clone(), inner.allocation.clone());
+        let inner = ArcRef::new_ranked(inner, LockRank::Resource);
+        graphics
+            .borrow_mut()
+            .resources
+            .register_buffer(&inner, handle, allocation);
+
         Ok(Buffer {
             graphics,
-            inner: ArcRef::new(inner),
+            inner,
             mapped_buffer: if mapped {
                 bytemuck::cast_slice(data).to_vec()
             } else {
@@ -222,10 +313,70 @@ impl Buffer {
         })
     }
 
+    pub(crate) fn from_pool(
+        graphics: ArcRef<GPUInner>,
+        pool: &pool::BufferPool,
+        size: wgpu::BufferAddress,
+        data: Option<&[u8]>,
+        usage: BufferUsage,
+        mapped: bool,
+        label: Option<String>,
+    ) -> Result<Self, BufferError> {
+        // Slabs can always be copied from and to
+        let usage = usage | BufferUsage::COPY_SRC | BufferUsage::COPY_DST;
+        if mapped || !pool.slab_usage().contains(usage) {
+            return Err(BufferError::InvalidUsage);
+        }
+
+        if size == 0 {
+            return Err(BufferError::InvalidSize);
+        }
+
+        let size = pool::pooled_size(size);
+        let (buffer, allocation) = pool.allocate(size);
+        let offset = allocation.offset();
+
+        if let Some(data) = data {
+            let mut data = data.to_vec();
+            data.resize(size as usize, 0);
+
+            graphics.borrow().queue().write_buffer(&buffer, offset, &data);
+        }
+
+        let inner = BufferInner {
+            buffer,
+            size,
+            usage,
+            mapped: false,
+            label,
+            offset,
+            allocation: Some(allocation),
+        };
+
+        let (handle, allocation) = (inner.buffer.clone(), inner.allocation.clone());
+        let inner = ArcRef::new_ranked(inner, LockRank::Resource);
+        graphics
+            .borrow_mut()
+            .resources
+            .register_buffer(&inner, handle, allocation);
+
+        Ok(Buffer {
+            graphics,
+            inner,
+            mapped_buffer: vec![],
+            mapped_type: BufferMapMode::Write,
+        })
+    }
+
     pub fn usage(&self) -> BufferUsage {
         self.inner.wait_borrow().usage
     }
 
+    /// Returns true if the buffer was allocated from a [BufferPool](pool::BufferPool).
+    pub fn is_pooled(&self) -> bool {
+        self.inner.wait_borrow().allocation.is_some()
+    }
+
     pub fn size(&self) -> u64 {
         self.inner.wait_borrow().size
     }
@@ -236,11 +387,17 @@ impl Buffer {
     /// - The old buffer has usage [BufferUsages::COPY_SRC] and [BufferUsages::MAP_READ].
     ///
     /// Otherwise, it will simply resize the buffer without copying the data.
+    ///
+    /// Pooled buffers can't be resized and return [BufferError::InvalidUsage].
     pub fn resize(&mut self, size: u64) -> Result<(), BufferError> {
         if size == 0 {
             return Err(BufferError::InvalidSize);
         }
 
+        if self.is_pooled() {
+            return Err(BufferError::InvalidUsage);
+        }
+
         let old_data = self.read::<u8>();
 
         let mut inner = self.inner.wait_borrow_mut();
@@ -259,16 +416,25 @@ impl Buffer {
                     old_data.truncate(size as usize);
                 }
 
-                graphics_ref.create_buffer_with(&old_data, inner.usage.clone().into())
+                graphics_ref.create_buffer_with_labeled(
+                    &old_data,
+                    inner.usage.into(),
+                    inner.label.as_deref(),
+                )
             } else {
-                graphics_ref.create_buffer(
+                graphics_ref.create_buffer_labeled(
                     size as wgpu::BufferAddress,
                     inner.usage.clone().into(),
                     false,
+                    inner.label.as_deref(),
                 )
             }
         };
 
+        graphics_ref
+            .resources
+            .update_buffer(&self.inner, new_buffer.clone());
+
         inner.buffer = new_buffer;
         inner.size = size as wgpu::BufferAddress;
 
@@ -277,16 +443,15 @@ impl Buffer {
 
     /// Writes the contents of the source buffer to this buffer.
     pub fn write(&self, src: &Buffer) {
-        let graphics_ref = self.graphics.borrow();
-        let mut encoder =
-            graphics_ref
-                .device()
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Buffer Write Command Encoder"),
-                });
+        let mut encoder = self.graphics.borrow().device().create_command_encoder(
+            &wgpu::CommandEncoderDescriptor {
+                label: Some("Buffer Write Command Encoder"),
+            },
+        );
 
         self.internal_write_cmd(src, &mut encoder);
 
+        let graphics_ref = self.graphics.borrow();
         graphics_ref
             .queue()
             .submit(std::iter::once(encoder.finish()));
@@ -343,7 +508,13 @@ impl Buffer {
         let src_inner = src.inner.wait_borrow();
         let inner = self.inner.wait_borrow();
 
-        encoder.copy_buffer_to_buffer(&src_inner.buffer, 0, &inner.buffer, 0, inner.size);
+        encoder.copy_buffer_to_buffer(
+            &src_inner.buffer,
+            src_inner.offset,
+            &inner.buffer,
+            inner.offset,
+            inner.size.min(src_inner.size),
+        );
     }
 
     #[inline(always)]
@@ -369,7 +540,146 @@ impl Buffer {
         let src_inner = src.inner.wait_borrow();
         let inner = self.inner.wait_borrow();
 
-        encoder.copy_buffer_to_buffer(&src_inner.buffer, 0, &inner.buffer, 0, inner.size);
+        encoder.copy_buffer_to_buffer(
+            &src_inner.buffer,
+            src_inner.offset,
+            &inner.buffer,
+            inner.offset,
+            inner.size.min(src_inner.size),
+        );
+    }
+
+    /// Copies `size` bytes of `src` starting at `src_offset` into this buffer at `dst_offset`,
+    /// waiting for the copy to complete.
+    ///
+    /// Useful to move data between sub-allocations of larger buffers. The offsets and size
+    /// must be multiples of [COPY_ALIGNMENT], `src` needs [BufferUsage::COPY_SRC] and this
+    /// buffer [BufferUsage::COPY_DST]. A buffer can't be copied into itself.
+    pub fn copy_range(
+        &self,
+        src: &Buffer,
+        src_offset: u64,
+        dst_offset: u64,
+        size: u64,
+    ) -> Result<(), BufferError> {
+        self.validate_copy_range(src, src_offset, dst_offset, size)?;
+
+        let mut encoder = self.graphics.borrow().device().create_command_encoder(
+            &wgpu::CommandEncoderDescriptor {
+                label: Some("Buffer Copy Range Command Encoder"),
+            },
+        );
+
+        self.internal_copy_range_cmd(src, src_offset, dst_offset, size, &mut encoder);
+
+        let graphics_ref = self.graphics.borrow();
+        graphics_ref
+            .queue()
+            .submit(std::iter::once(encoder.finish()));
+        _ = graphics_ref.device().poll(wgpu::PollType::Wait);
+
+        Ok(())
+    }
+
+    /// Records the copy of `size` bytes of `src` starting at `src_offset` into this buffer at
+    /// `dst_offset`, see [Buffer::copy_range].
+    ///
+    /// [CommandBuffer::copy_range] is a more convenient way to copy a range in a command buffer
+    /// context.
+    pub fn copy_range_cmd(
+        &self,
+        src: &Buffer,
+        src_offset: u64,
+        dst_offset: u64,
+        size: u64,
+        encoder: &mut CommandBuffer,
+    ) -> Result<(), BufferError> {
+        self.validate_copy_range(src, src_offset, dst_offset, size)?;
+
+        let mut cmd = encoder.command.as_ref().unwrap().borrow_mut();
+        self.internal_copy_range_cmd(src, src_offset, dst_offset, size, &mut cmd);
+
+        Ok(())
+    }
+
+    fn validate_copy_range(
+        &self,
+        src: &Buffer,
+        src_offset: u64,
+        dst_offset: u64,
+        size: u64,
+    ) -> Result<(), BufferError> {
+        if ArcRef::ptr_eq(&self.inner, &src.inner) {
+            return Err(BufferError::InvalidUsage);
+        }
+
+        let inner = self.inner.wait_borrow();
+        let src_inner = src.inner.wait_borrow();
+
+        if !src_inner.usage.contains(BufferUsage::COPY_SRC) {
+            return Err(BufferError::BufferNotReadable);
+        }
+
+        if !inner.usage.contains(BufferUsage::COPY_DST) {
+            return Err(BufferError::BufferNotWritable);
+        }
+
+        if size == 0 {
+            return Err(BufferError::InvalidSize);
+        }
+
+        if !src_offset.is_multiple_of(COPY_ALIGNMENT)
+            || !dst_offset.is_multiple_of(COPY_ALIGNMENT)
+            || !size.is_multiple_of(COPY_ALIGNMENT)
+        {
+            crate::dbg_log!(
+                "Buffer copy range src_offset: {} dst_offset: {} size: {} is not aligned to {} bytes",
+                src_offset,
+                dst_offset,
+                size,
+                COPY_ALIGNMENT
+            );
+            return Err(BufferError::InvalidAlignment);
+        }
+
+        let src_end = src_offset.checked_add(size);
+        let dst_end = dst_offset.checked_add(size);
+
+        if src_end.is_none_or(|end| end > src_inner.size)
+            || dst_end.is_none_or(|end| end > inner.size)
+        {
+            crate::dbg_log!(
+                "Buffer copy range of {} bytes from {} to {} is outside of the {} and {} bytes buffers",
+                size,
+                src_offset,
+                dst_offset,
+                src_inner.size,
+                inner.size
+            );
+            return Err(BufferError::InvalidRange);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn internal_copy_range_cmd(
+        &self,
+        src: &Buffer,
+        src_offset: u64,
+        dst_offset: u64,
+        size: u64,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let src_inner = src.inner.wait_borrow();
+        let inner = self.inner.wait_borrow();
+
+        encoder.copy_buffer_to_buffer(
+            &src_inner.buffer,
+            src_inner.offset + src_offset,
+            &inner.buffer,
+            inner.offset + dst_offset,
+            size,
+        );
     }
 
     /// Writes raw data to the buffer.
@@ -391,17 +701,15 @@ impl Buffer {
             }
         }
 
-        let graphics_ref = self.graphics.borrow();
-
-        let mut encoder =
-            graphics_ref
-                .device()
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Buffer Write Raw Command Encoder"),
-                });
+        let mut encoder = self.graphics.borrow().device().create_command_encoder(
+            &wgpu::CommandEncoderDescriptor {
+                label: Some("Buffer Write Raw Command Encoder"),
+            },
+        );
 
         self.internal_write_raw_cmd(data, &mut encoder);
 
+        let graphics_ref = self.graphics.borrow();
         graphics_ref
             .queue()
             .submit(std::iter::once(encoder.finish()));
@@ -488,7 +796,7 @@ impl Buffer {
             &buffer,
             0,
             &inner.buffer,
-            0,
+            inner.offset,
             buffer.size() as wgpu::BufferAddress,
         );
     }
@@ -520,7 +828,7 @@ impl Buffer {
             &buffer,
             0,
             &inner.buffer,
-            0,
+            inner.offset,
             buffer.size() as wgpu::BufferAddress,
         );
     }
@@ -530,8 +838,8 @@ impl Buffer {
     /// Unless if the buffer was created with [BufferUsages::COPY_SRC] or [BufferUsages::MAP_READ], this will create an
     /// intermediate buffer to copy the data into, and then read from that buffer.
     pub fn read<T: bytemuck::Pod + bytemuck::Zeroable>(&self) -> Result<Vec<T>, BufferError> {
-        let mut graphics_ref = self.graphics.borrow_mut();
         let inner = self.inner.wait_borrow();
+        let mut graphics_ref = self.graphics.borrow_mut();
 
         if !inner.usage.contains(BufferUsage::COPY_SRC)
             && !inner.usage.contains(BufferUsage::MAP_READ)
@@ -561,7 +869,7 @@ impl Buffer {
 
             encoder.copy_buffer_to_buffer(
                 &inner.buffer,
-                0,
+                inner.offset,
                 &buffer,
                 0,
                 inner.size as wgpu::BufferAddress,
@@ -632,7 +940,10 @@ impl Buffer {
 
         match self.mapped_type {
             BufferMapMode::Write => {
-                inner.buffer.unmap();
+                // Pooled buffers share the slab, which is never mapped
+                if inner.allocation.is_none() {
+                    inner.buffer.unmap();
+                }
 
                 drop(inner);
 
@@ -0,0 +1,296 @@
+use std::{ops::Range, sync::Arc};
+
+use crate::utils::{ArcMut, ArcRef};
+
+use super::{super::GPUInner, BufferError, BufferUsage, COPY_ALIGNMENT};
+
+/// A slab size for [GPU::create_buffer_pool](crate::gpu::GPU::create_buffer_pool), fitting a
+/// few hundred small meshes.
+pub const DEFAULT_SLAB_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Sub-allocates many small buffers out of a few large ones, called slabs, instead of creating
+/// a GPU buffer for each, eg. for the vertex and index buffers of the meshes of a scene.
+///
+/// Buffers are allocated from the pool with [BufferBuilder::set_pool](super::BufferBuilder::set_pool)
+/// and used like any other buffer. Their usage must be part of the pool usage, copies are always
+/// allowed. The space of a dropped buffer is recycled once the GPU is done with it, and slabs
+/// left empty are released.
+///
+/// Pooled buffers can't be mapped or resized. The pool must be created again after
+/// [GPU::recover_device](crate::gpu::GPU::recover_device), the buffers allocated before keep
+/// working but are no longer pooled.
+///
+/// ```rust,ignore
+/// let pool = gpu.create_buffer_pool(BufferUsage::VERTEX | BufferUsage::INDEX, DEFAULT_SLAB_SIZE)?;
+///
+/// let vertices = gpu
+///     .create_buffer::<Vertex>()
+///     .set_data_slice(&mesh.vertices)
+///     .set_usage(BufferUsage::VERTEX)
+///     .set_pool(&pool)
+///     .build()?;
+/// ```
+#[derive(Debug)]
+pub struct BufferPool {
+    pub(crate) graphics: ArcRef<GPUInner>,
+    pub(crate) inner: ArcMut<BufferPoolInner>,
+}
+
+impl BufferPool {
+    pub(crate) fn new(
+        graphics: ArcRef<GPUInner>,
+        usage: BufferUsage,
+        slab_size: u64,
+    ) -> Result<Self, BufferError> {
+        if usage.contains(BufferUsage::MAP_READ) || usage.contains(BufferUsage::MAP_WRITE) {
+            return Err(BufferError::InvalidUsage);
+        }
+
+        if slab_size == 0 {
+            return Err(BufferError::InvalidSize);
+        }
+
+        // Aligned for the offsets of storage and uniform bindings, which also covers vertex,
+        // index and copy offsets
+        let alignment = {
+            let limits = graphics.borrow().limits();
+            (limits.min_uniform_buffer_offset_alignment as u64)
+                .max(limits.min_storage_buffer_offset_alignment as u64)
+                .max(16)
+        };
+
+        let inner = BufferPoolInner {
+            usage,
+            slab_size: slab_size.next_multiple_of(alignment),
+            alignment,
+            slabs: Vec::new(),
+            next_slab_id: 0,
+        };
+
+        Ok(Self {
+            graphics,
+            inner: ArcMut::new(inner),
+        })
+    }
+
+    /// Usages the buffers allocated from the pool may have.
+    pub fn usage(&self) -> BufferUsage {
+        self.inner.lock().usage
+    }
+
+    pub(crate) fn slab_usage(&self) -> BufferUsage {
+        self.usage() | BufferUsage::COPY_SRC | BufferUsage::COPY_DST
+    }
+
+    /// Size in bytes of a slab, allocations larger than it get a slab of their own.
+    pub fn slab_size(&self) -> u64 {
+        self.inner.lock().slab_size
+    }
+
+    pub fn slab_count(&self) -> usize {
+        self.inner.lock().slabs.len()
+    }
+
+    /// Total size in bytes of the slabs.
+    pub fn capacity(&self) -> u64 {
+        self.inner
+            .lock()
+            .slabs
+            .iter()
+            .map(|slab| slab.buffer.size())
+            .sum()
+    }
+
+    /// Bytes used by the live allocations, alignment padding included.
+    pub fn allocated(&self) -> u64 {
+        self.inner
+            .lock()
+            .slabs
+            .iter()
+            .map(|slab| slab.buffer.size() - slab.free_bytes())
+            .sum()
+    }
+
+    /// Allocates `size` bytes, creating a slab if none has a large enough free range.
+    pub(crate) fn allocate(&self, size: u64) -> (wgpu::Buffer, Arc<PoolAllocation>) {
+        let usage = self.slab_usage();
+        let (size, slab_size) = {
+            let mut inner = self.inner.lock();
+            let size = size.next_multiple_of(inner.alignment);
+
+            if let Some((slab, offset, buffer)) = inner.slabs.iter_mut().find_map(|slab| {
+                slab.allocate(size)
+                    .map(|offset| (slab.id, offset, slab.buffer.clone()))
+            }) {
+                return (buffer, self.allocation(slab, offset, size));
+            }
+
+            (size, inner.slab_size.max(size))
+        };
+
+        // The pool isn't locked while creating the slab, releasing an allocation locks it
+        // while the GPU is borrowed
+        let buffer = self.graphics.borrow_mut().create_buffer_labeled(
+            slab_size,
+            usage.into(),
+            false,
+            Some("Buffer Pool Slab"),
+        );
+
+        crate::dbg_log!("Buffer pool created a slab of {} bytes", slab_size);
+
+        let mut inner = self.inner.lock();
+        let id = inner.next_slab_id;
+        inner.next_slab_id += 1;
+
+        let mut slab = Slab {
+            id,
+            buffer: buffer.clone(),
+            free: std::iter::once(0..slab_size).collect(),
+        };
+        let offset = slab.allocate(size).unwrap();
+        inner.slabs.push(slab);
+        drop(inner);
+
+        (buffer, self.allocation(id, offset, size))
+    }
+
+    fn allocation(&self, slab: u64, offset: u64, size: u64) -> Arc<PoolAllocation> {
+        Arc::new(PoolAllocation {
+            pool: self.inner.clone(),
+            slab,
+            range: offset..offset + size,
+        })
+    }
+}
+
+impl Clone for BufferPool {
+    fn clone(&self) -> Self {
+        Self {
+            graphics: ArcRef::clone(&self.graphics),
+            inner: ArcMut::clone(&self.inner),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct BufferPoolInner {
+    usage: BufferUsage,
+    slab_size: u64,
+    alignment: u64,
+    slabs: Vec<Slab>,
+    next_slab_id: u64,
+}
+
+impl BufferPoolInner {
+    fn free(&mut self, slab: u64, range: Range<u64>) {
+        let Some(index) = self.slabs.iter().position(|s| s.id == slab) else {
+            return;
+        };
+
+        self.slabs[index].free(range);
+
+        // Keep one slab around for the next allocations
+        if self.slabs[index].is_empty() && self.slabs.len() > 1 {
+            self.slabs.remove(index);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Slab {
+    id: u64,
+    buffer: wgpu::Buffer,
+    /// Free ranges sorted by offset, adjacent ranges are merged.
+    free: Vec<Range<u64>>,
+}
+
+impl Slab {
+    /// Takes `size` bytes from the first free range large enough, both are aligned.
+    fn allocate(&mut self, size: u64) -> Option<u64> {
+        let index = self
+            .free
+            .iter()
+            .position(|range| range.end - range.start >= size)?;
+
+        let range = &mut self.free[index];
+        let offset = range.start;
+        range.start += size;
+
+        if range.start == range.end {
+            self.free.remove(index);
+        }
+
+        Some(offset)
+    }
+
+    fn free(&mut self, range: Range<u64>) {
+        let index = self.free.partition_point(|free| free.start < range.start);
+        self.free.insert(index, range);
+
+        if index + 1 < self.free.len() && self.free[index].end == self.free[index + 1].start {
+            self.free[index].end = self.free[index + 1].end;
+            self.free.remove(index + 1);
+        }
+
+        if index > 0 && self.free[index - 1].end == self.free[index].start {
+            self.free[index - 1].end = self.free[index].end;
+            self.free.remove(index);
+        }
+    }
+
+    fn free_bytes(&self) -> u64 {
+        self.free.iter().map(|range| range.end - range.start).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.free_bytes() == self.buffer.size()
+    }
+}
+
+/// A range of a slab owned by a buffer, given back to the pool when dropped.
+///
+/// The last reference is held by the deletion queue, so the range is only recycled once the
+/// GPU is done with the buffer.
+#[derive(Debug)]
+pub(crate) struct PoolAllocation {
+    pool: ArcMut<BufferPoolInner>,
+    slab: u64,
+    range: Range<u64>,
+}
+
+impl PoolAllocation {
+    pub fn offset(&self) -> u64 {
+        self.range.start
+    }
+
+    pub fn size(&self) -> u64 {
+        self.range.end - self.range.start
+    }
+}
+
+impl Drop for PoolAllocation {
+    fn drop(&mut self) {
+        self.pool.lock().free(self.slab, self.range.clone());
+    }
+}
+
+impl PartialEq for PoolAllocation {
+    fn eq(&self, other: &Self) -> bool {
+        self.slab == other.slab && self.range == other.range
+    }
+}
+
+impl Eq for PoolAllocation {}
+
+impl std::hash::Hash for PoolAllocation {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.slab.hash(state);
+        self.range.hash(state);
+    }
+}
+
+/// Size of a pooled buffer holding `size` bytes, copies work on multiples of [COPY_ALIGNMENT].
+pub(crate) fn pooled_size(size: u64) -> u64 {
+    size.next_multiple_of(COPY_ALIGNMENT)
+}
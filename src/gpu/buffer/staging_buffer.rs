@@ -33,7 +33,9 @@ impl StagingBuffer {
         let size = (data.len() as wgpu::BufferAddress + aligned - 1) / aligned * aligned;
 
         let buffer = {
-            if let Some(item) = self.buffers.iter_mut().find(|item| !item.used && item.buffer.size() >= size) {
+            if let Some(item) = self.buffers.iter_mut().find(|item| {
+                !item.used && item.buffer.size() >= size && item.buffer.usage().contains(usage)
+            }) {
                 item.used = true;
                 item.cycle = 0;
                 item.buffer.clone()
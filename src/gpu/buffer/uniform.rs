@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::utils::ArcRef;
+
+use super::{super::GPUInner, Buffer, BufferError, BufferUsage, COPY_ALIGNMENT};
+
+/// A value of type `T` kept in a uniform buffer, uploaded only when it changed.
+///
+/// The value is modified on the CPU with [Uniform::set], which marks it dirty. Binding it with
+/// [RenderPass::set_attachment_uniform_value](crate::gpu::command::renderpass::RenderPass::set_attachment_uniform_value)
+/// or [ComputePass::set_attachment_uniform_value](crate::gpu::command::computepass::ComputePass::set_attachment_uniform_value)
+/// records the upload through a staging buffer ahead of the pass, so there is no need to call
+/// [Buffer::write_raw] every frame.
+///
+/// A pass sees the value the uniform had when it was last bound in that pass, use an
+/// [UniformArena](super::uniform_arena::UniformArena) for values changing between draws.
+///
+/// ```rust,ignore
+/// let mut globals = gpu.create_uniform(Globals::default())?;
+///
+/// // Every frame
+/// globals.set(|v| v.time += dt);
+/// pass.set_attachment_uniform_value(0, 0, &globals);
+/// ```
+#[derive(Debug)]
+pub struct Uniform<T: bytemuck::Pod> {
+    graphics: ArcRef<GPUInner>,
+    buffer: Buffer,
+    value: T,
+    dirty: AtomicBool,
+}
+
+impl<T: bytemuck::Pod> Uniform<T> {
+    pub(crate) fn new(graphics: ArcRef<GPUInner>, value: T) -> Result<Self, BufferError> {
+        let size = std::mem::size_of::<T>() as u64;
+        if size == 0 {
+            return Err(BufferError::InvalidSize);
+        }
+
+        // Uniform structs are laid out in 16 bytes blocks
+        let buffer = Buffer::new(
+            ArcRef::clone(&graphics),
+            size.next_multiple_of(16),
+            BufferUsage::UNIFORM | BufferUsage::COPY_DST,
+            false,
+            Some("Uniform".to_string()),
+        )?;
+
+        Ok(Self {
+            graphics,
+            buffer,
+            value,
+            dirty: AtomicBool::new(true),
+        })
+    }
+
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Modifies the value in place, it is uploaded the next time the uniform is bound.
+    pub fn set(&mut self, f: impl FnOnce(&mut T)) {
+        f(&mut self.value);
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// Replaces the value, it is uploaded the next time the uniform is bound.
+    pub fn replace(&mut self, value: T) {
+        self.value = value;
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// Returns true if the value changed since it was last uploaded.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::Acquire)
+    }
+
+    /// The uniform buffer, its content is only up to date after the uniform was bound or
+    /// [Uniform::flush] was called.
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Uploads the value now if it changed, for buffers used outside of a pass binding, eg.
+    /// by a render pipeline built with it.
+    pub fn flush(&self) {
+        if !self.dirty.swap(false, Ordering::AcqRel) {
+            return;
+        }
+
+        let mut data = bytemuck::bytes_of(&self.value).to_vec();
+        data.resize(self.upload_size() as usize, 0);
+
+        let inner = self.buffer.inner.borrow();
+        let graphics_ref = self.graphics.borrow();
+        graphics_ref
+            .queue()
+            .write_buffer(&inner.buffer, inner.offset, &data);
+    }
+
+    /// Records the upload of the value into `encoder` if it changed, the copy executes before
+    /// the commands recorded after it.
+    pub(crate) fn upload_cmd(&self, encoder: &mut wgpu::CommandEncoder) {
+        if !self.dirty.swap(false, Ordering::AcqRel) {
+            return;
+        }
+
+        let staging = self.graphics.borrow_mut().create_staging_buffer(
+            bytemuck::bytes_of(&self.value),
+            wgpu::BufferUsages::COPY_SRC,
+        );
+
+        // The staging buffer may be a larger recycled one
+        let inner = self.buffer.inner.borrow();
+        encoder.copy_buffer_to_buffer(
+            &staging,
+            0,
+            &inner.buffer,
+            inner.offset,
+            self.upload_size(),
+        );
+    }
+
+    fn upload_size(&self) -> u64 {
+        (std::mem::size_of::<T>() as u64).next_multiple_of(COPY_ALIGNMENT)
+    }
+}
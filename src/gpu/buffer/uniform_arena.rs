@@ -0,0 +1,207 @@
+use crate::utils::ArcRef;
+
+use super::{super::GPUInner, Buffer, BufferError, BufferUsage, COPY_ALIGNMENT};
+
+/// Sub-allocates the uniform data of a frame out of one large uniform buffer, instead of
+/// creating a small buffer per draw.
+///
+/// Each [UniformArena::allocate] copies the value at the next offset aligned to
+/// [GPU::uniform_offset_alignment](crate::gpu::GPU::uniform_offset_alignment) and returns that
+/// offset, to be used with
+/// [RenderPass::set_attachment_uniform_dynamic](crate::gpu::command::renderpass::RenderPass::set_attachment_uniform_dynamic)
+/// on a binding declared with `GraphicsShaderBuilder::set_dynamic_offset`.
+///
+/// The values are uploaded at once by [UniformArena::flush], which must be called after the
+/// last allocation and before the command buffer is submitted. [UniformArena::reset] starts
+/// the next frame, the previous contents are overwritten in queue order so the GPU never sees
+/// the data of a later frame.
+///
+/// ```rust,ignore
+/// let mut arena = gpu.create_uniform_arena(64 * 1024)?;
+///
+/// // Every frame
+/// arena.reset()?;
+/// for object in &objects {
+///     let offset = arena.allocate(&object.transform)?;
+///     pass.set_attachment_uniform_dynamic(1, 0, arena.buffer(), offset);
+///     pass.draw(0..object.vertex_count, 1);
+/// }
+/// arena.flush();
+/// ```
+#[derive(Debug)]
+pub struct UniformArena {
+    graphics: ArcRef<GPUInner>,
+    buffer: Buffer,
+    /// CPU copy of the allocations of the current frame.
+    data: Vec<u8>,
+    alignment: u32,
+    cursor: u32,
+    /// Bytes asked for during the frame, including the allocations that didn't fit.
+    requested: u64,
+}
+
+impl UniformArena {
+    pub(crate) fn new(
+        graphics: ArcRef<GPUInner>,
+        capacity: u32,
+    ) -> Result<Self, UniformArenaError> {
+        let alignment = graphics
+            .borrow()
+            .limits()
+            .min_uniform_buffer_offset_alignment;
+
+        if capacity == 0 {
+            return Err(UniformArenaError::InvalidSize);
+        }
+
+        let capacity = capacity.next_multiple_of(alignment);
+        let buffer = create_buffer(&graphics, capacity)?;
+
+        Ok(Self {
+            graphics,
+            buffer,
+            data: vec![0; capacity as usize],
+            alignment,
+            cursor: 0,
+            requested: 0,
+        })
+    }
+
+    /// The uniform buffer the offsets point into.
+    ///
+    /// The buffer is recreated when [UniformArena::reset] grows the arena, fetch it again every
+    /// frame instead of keeping it around.
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Size of the buffer in bytes.
+    pub fn capacity(&self) -> u32 {
+        self.data.len() as u32
+    }
+
+    /// Bytes allocated during the current frame, alignment padding included.
+    pub fn used(&self) -> u32 {
+        self.cursor
+    }
+
+    /// Alignment of the returned offsets.
+    pub fn alignment(&self) -> u32 {
+        self.alignment
+    }
+
+    /// Copies `value` into the arena, returning its offset in [UniformArena::buffer].
+    ///
+    /// Fails with [UniformArenaError::Full] once the buffer is full, the next
+    /// [UniformArena::reset] then grows the arena to fit the whole frame.
+    pub fn allocate<T: bytemuck::Pod>(&mut self, value: &T) -> Result<u32, UniformArenaError> {
+        self.allocate_slice(std::slice::from_ref(value))
+    }
+
+    /// Copies `values` one after another into the arena, eg. an array of lights, returning the
+    /// offset of the first one.
+    pub fn allocate_slice<T: bytemuck::Pod>(
+        &mut self,
+        values: &[T],
+    ) -> Result<u32, UniformArenaError> {
+        let bytes: &[u8] = bytemuck::cast_slice(values);
+        if bytes.is_empty() {
+            return Err(UniformArenaError::InvalidSize);
+        }
+
+        let size = bytes.len() as u64;
+        let offset = self.cursor as u64;
+        let end = offset + size;
+
+        self.requested += size.next_multiple_of(self.alignment as u64);
+
+        if end > self.data.len() as u64 {
+            return Err(UniformArenaError::Full(self.capacity()));
+        }
+
+        self.data[offset as usize..end as usize].copy_from_slice(bytes);
+        self.cursor = (end.next_multiple_of(self.alignment as u64) as u32).min(self.capacity());
+
+        Ok(offset as u32)
+    }
+
+    /// Uploads the allocations of the current frame, call it once after the last allocation.
+    pub fn flush(&mut self) {
+        if self.cursor == 0 {
+            return;
+        }
+
+        let len = (self.cursor as u64).next_multiple_of(COPY_ALIGNMENT) as usize;
+
+        let inner = self.buffer.inner.borrow();
+        let graphics_ref = self.graphics.borrow();
+        graphics_ref
+            .queue()
+            .write_buffer(&inner.buffer, 0, &self.data[..len]);
+    }
+
+    /// Starts a new frame, the offsets handed out before are no longer valid.
+    ///
+    /// If allocations failed during the last frame the buffer is recreated with a capacity
+    /// fitting all of them, and true is returned.
+    pub fn reset(&mut self) -> Result<bool, UniformArenaError> {
+        let requested = self.requested;
+
+        self.cursor = 0;
+        self.requested = 0;
+
+        if requested <= self.data.len() as u64 {
+            return Ok(false);
+        }
+
+        let capacity = requested
+            .max(self.data.len() as u64 * 2)
+            .next_multiple_of(self.alignment as u64);
+        let capacity = u32::try_from(capacity).map_err(|_| UniformArenaError::InvalidSize)?;
+
+        self.buffer = create_buffer(&self.graphics, capacity)?;
+        self.data = vec![0; capacity as usize];
+
+        crate::dbg_log!("Uniform arena grown to {} bytes", capacity);
+
+        Ok(true)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum UniformArenaError {
+    /// The allocation doesn't fit in the remaining space of the buffer of the given size.
+    Full(u32),
+    InvalidSize,
+    Buffer(BufferError),
+}
+
+impl std::fmt::Display for UniformArenaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UniformArenaError::Full(capacity) => {
+                write!(f, "Uniform arena of {} bytes is full", capacity)
+            }
+            UniformArenaError::InvalidSize => write!(f, "Invalid uniform arena allocation size"),
+            UniformArenaError::Buffer(e) => write!(f, "Buffer error: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for UniformArenaError {}
+
+impl From<BufferError> for UniformArenaError {
+    fn from(e: BufferError) -> Self {
+        UniformArenaError::Buffer(e)
+    }
+}
+
+fn create_buffer(graphics: &ArcRef<GPUInner>, capacity: u32) -> Result<Buffer, BufferError> {
+    Buffer::new(
+        ArcRef::clone(graphics),
+        capacity as u64,
+        BufferUsage::UNIFORM | BufferUsage::COPY_DST,
+        false,
+        Some("Uniform Arena".to_string()),
+    )
+}
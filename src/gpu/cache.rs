@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+/// Eviction policy of the pipeline and bind group caches.
+///
+/// Entries not used for `max_age_frames` frames are dropped, and once a cache holds more than
+/// `max_entries` entries the least recently used ones are dropped first. Evicted entries are
+/// simply created again the next time they are needed.
+///
+/// ```rust,ignore
+/// gpu.set_bind_group_cache_policy(
+///     CachePolicy::new()
+///         .set_max_entries(1024)
+///         .set_max_age_frames(30),
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CachePolicy {
+    pub max_entries: Option<usize>,
+    pub max_age_frames: Option<usize>,
+}
+
+impl CachePolicy {
+    /// Creates a policy that never evicts anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the number of entries kept in the cache.
+    pub fn set_max_entries(mut self, count: usize) -> Self {
+        self.max_entries = Some(count);
+        self
+    }
+
+    /// Drops the entries not used during the given number of frames.
+    pub fn set_max_age_frames(mut self, frames: usize) -> Self {
+        self.max_age_frames = Some(frames);
+        self
+    }
+}
+
+/// Lookups and evictions of a cache since the [GPU](super::GPU) was created.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// Returns the ratio of lookups found in the cache, between 0.0 and 1.0.
+    pub fn hit_rate(&self) -> f32 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+
+        self.hits as f32 / lookups as f32
+    }
+
+    pub(crate) fn record_lookup(&mut self, hit: bool) {
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+    }
+}
+
+/// Cached values along with the frames since they were last used.
+pub(crate) type CacheEntries<V> = HashMap<usize, (V, usize)>;
+
+/// Evicts the entries according to the policy, then ages the remaining ones by one frame.
+/// Returns the number of evicted entries.
+pub(crate) fn cycle_entries<V>(entries: &mut CacheEntries<V>, policy: &CachePolicy) -> u64 {
+    let evicted = apply_policy(entries, policy);
+
+    for (_, value) in entries.iter_mut() {
+        value.1 += 1;
+    }
+
+    evicted
+}
+
+/// Evicts the entries exceeding the limits of the policy. Returns the number of evicted entries.
+pub(crate) fn apply_policy<V>(entries: &mut CacheEntries<V>, policy: &CachePolicy) -> u64 {
+    let before = entries.len();
+
+    if let Some(max_age) = policy.max_age_frames {
+        entries.retain(|_, value| value.1 < max_age);
+    }
+
+    (before - entries.len()) as u64 + trim_entries(entries, policy.max_entries)
+}
+
+/// Evicts the least recently used entries until at most `max_entries` are left. Returns the
+/// number of evicted entries.
+pub(crate) fn trim_entries<V>(entries: &mut CacheEntries<V>, max_entries: Option<usize>) -> u64 {
+    let Some(max_entries) = max_entries else {
+        return 0;
+    };
+
+    if entries.len() <= max_entries {
+        return 0;
+    }
+
+    let mut ages = entries
+        .iter()
+        .map(|(key, (_, age))| (*key, *age))
+        .collect::<Vec<_>>();
+
+    // Oldest first
+    ages.sort_unstable_by_key(|(_, age)| std::cmp::Reverse(*age));
+
+    let excess = entries.len() - max_entries;
+    for (key, _) in ages.into_iter().take(excess) {
+        entries.remove(&key);
+    }
+
+    excess as u64
+}
@@ -1,10 +1,16 @@
 use std::{collections::HashMap, hash::{DefaultHasher, Hash, Hasher}, sync::{atomic::AtomicBool, Arc}};
 
+use bytemuck::{Pod, Zeroable};
+
 use crate::utils::ArcRef;
 
 use super::{
     super::{
         GPUInner,
+        texture::{
+            SampleCount, SamplerError, Texture, TextureFormat, TextureInner, TextureSampler,
+            TextureUsage, view::TextureView,
+        },
         shader::{
             ComputeShader,
             bind_group_manager::BindGroupCreateInfo,
@@ -13,11 +19,12 @@ use super::{
             ShaderBindingType,
         },
         buffer::{
+            uniform::Uniform,
             Buffer,
             BufferUsage
         },
         pipeline::{
-            compute::ComputePipeline,
+            compute::{ComputePipeline, ComputePipelineInner},
             manager::ComputePipelineDesc,
         },
         command::{
@@ -48,7 +55,6 @@ impl ComputePass {
             attachments: Vec::new(),
             push_constant: None,
 
-            #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
             reflection: None,
         };
 
@@ -83,14 +89,21 @@ impl ComputePass {
                     shader: shader_inner.shader.clone(),
                     layout,
                     entry_point: shader_entry_point,
+                    push_constant_ranges: shader_inner.push_constant_ranges.clone(),
                 };
 
                 inner.shader = Some(ComputeShaderBinding::Intermediate(shader_binding));
+                inner.reflection = Some(shader_inner.reflection.clone());
             }
             None => {
                 inner.shader = None;
+                inner.reflection = None;
             }
         }
+
+        // Attachments and push constants are matched against the previous shader bindings
+        inner.attachments.clear();
+        inner.push_constant = None;
     }
 
     pub fn set_pipeline(&mut self, pipeline: Option<&ComputePipeline>) {
@@ -98,70 +111,394 @@ impl ComputePass {
 
         match pipeline {
             Some(pipeline) => {
-                inner.shader = Some(ComputeShaderBinding::Pipeline(pipeline.clone()));
+                // A snapshot is enough, pipelines are only rebuilt between frames
+                inner.shader = Some(ComputeShaderBinding::Pipeline(pipeline.inner.borrow().clone()));
             }
             None => {
                 inner.shader = None;
             }
         }
+
+        inner.reflection = None;
+        inner.attachments.clear();
+        inner.push_constant = None;
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn set_push_constants(&mut self, push_constant: Option<&[u8]>) {
-        let mut inner = self.inner.borrow_mut();
+    /// Returns the group and binding of a resource declared by the current shader, to bind it
+    /// by the name of its WGSL variable.
+    ///
+    /// ```rust,ignore
+    /// let (group, binding) = pass.get_binding_location("output")?;
+    /// pass.set_attachment_texture_storage(group, binding, Some(&output))?;
+    /// ```
+    pub fn get_binding_location(&self, name: &str) -> Result<(u32, u32), ComputePassError> {
+        let inner = self.inner.borrow();
+
+        let bindings = match &inner.reflection {
+            Some(ShaderReflect::Compute { bindings, .. }) => bindings,
+            _ => return Err(ComputePassError::ShaderNotSet),
+        };
+
+        bindings
+            .iter()
+            .find(|binding| {
+                binding.name == name && !matches!(binding.ty, ShaderBindingType::PushConstant(_))
+            })
+            .map(|binding| (binding.group, binding.binding))
+            .ok_or_else(|| ComputePassError::BindingNameNotFound(name.to_string()))
+    }
+
+    fn binding_type(&self, group: u32, binding: u32) -> Option<ShaderBindingType> {
+        match &self.inner.borrow().reflection {
+            Some(ShaderReflect::Compute { bindings, .. }) => bindings
+                .iter()
+                .find(|b| b.group == group && b.binding == binding)
+                .map(|b| b.ty),
+            _ => None,
+        }
+    }
+
+    /// Checks a sampled texture against the binding declared by the shader.
+    fn validate_texture(
+        &self,
+        group: u32,
+        binding: u32,
+        texture: &TextureInner,
+        format: TextureFormat,
+    ) -> Result<(), ComputePassError> {
+        if !texture.usages.contains(TextureUsage::Sampler) {
+            return Err(ComputePassError::InvalidTexture(
+                group,
+                binding,
+                "the texture was not created with TextureUsage::Sampler".to_string(),
+            ));
+        }
 
+        let Some(ty) = self.binding_type(group, binding) else {
+            return Ok(());
+        };
+
+        let filterable = self
+            .graphics
+            .borrow()
+            .format_features(format.into())
+            .flags
+            .contains(wgpu::TextureFormatFeatureFlags::FILTERABLE);
+
+        ty.validate_texture(
+            format,
+            texture.sample_count != SampleCount::SampleCount1,
+            filterable,
+        )
+        .map_err(|reason| ComputePassError::InvalidTexture(group, binding, reason))
+    }
+
+    /// Sets the push constant block used by the following dispatches, replacing it from
+    /// offset 0.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_push_constants(
+        &mut self,
+        push_constant: Option<&[u8]>,
+    ) -> Result<(), ComputePassError> {
         match push_constant {
             Some(push_constant) => {
-                let mut push_constant = push_constant.to_vec();
-                if push_constant.len() % 4 != 0 {
-                    push_constant.resize(push_constant.len() + (4 - push_constant.len() % 4), 0);
-                }
+                self.inner.borrow_mut().push_constant = None;
+                self.set_push_constants_at(0, push_constant)
+            }
+            None => {
+                self.inner.borrow_mut().push_constant = None;
+                Ok(())
+            }
+        }
+    }
 
-                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    /// Writes `data` into the push constant block at `offset` (in bytes, a multiple of 4),
+    /// keeping the rest of the block as is.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_push_constants_at(
+        &mut self,
+        offset: u32,
+        data: &[u8],
+    ) -> Result<(), ComputePassError> {
+        let mut inner = self.inner.borrow_mut();
+
+        let start = offset as usize;
+        let end = start + data.len().next_multiple_of(wgpu::PUSH_CONSTANT_ALIGNMENT as usize);
+
+        let size = match (&inner.shader, &inner.reflection) {
+            (None, _) => return Err(ComputePassError::ShaderNotSet),
+            (Some(ComputeShaderBinding::Pipeline(pipeline)), _) => pipeline
+                .pipeline_desc
+                .push_constant_ranges
+                .iter()
+                .map(|range| range.range.end)
+                .max()
+                .unwrap_or(0),
+            (Some(ComputeShaderBinding::Intermediate(_)), reflection) => match reflection {
+                Some(ShaderReflect::Compute { bindings, .. }) => bindings
+                    .iter()
+                    .find_map(|binding| match binding.ty {
+                        ShaderBindingType::PushConstant(size) => Some(size),
+                        _ => None,
+                    })
+                    .unwrap_or(0),
+                _ => 0,
+            },
+        };
+
+        if size == 0 {
+            return Err(ComputePassError::NoPushConstants);
+        }
+
+        if !offset.is_multiple_of(wgpu::PUSH_CONSTANT_ALIGNMENT) {
+            return Err(ComputePassError::InvalidPushConstantOffset(offset));
+        }
+
+        if end > size.next_multiple_of(wgpu::PUSH_CONSTANT_ALIGNMENT) as usize {
+            return Err(ComputePassError::PushConstantOutOfRange { start, end, size });
+        }
+
+        let block = inner.push_constant.get_or_insert_with(Vec::new);
+        if block.len() < end {
+            block.resize(end, 0);
+        }
+
+        block[start..start + data.len()].copy_from_slice(data);
+
+        Ok(())
+    }
+
+    /// Same as [ComputePass::set_push_constants], from a slice of plain data.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_push_constants_raw<T: bytemuck::Pod + bytemuck::Zeroable>(
+        &mut self,
+        data: Option<&[T]>,
+    ) -> Result<(), ComputePassError> {
+        self.set_push_constants(data.map(bytemuck::cast_slice))
+    }
+
+    /// Same as [ComputePass::set_push_constants_at], from a slice of plain data.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_push_constants_raw_at<T: bytemuck::Pod + bytemuck::Zeroable>(
+        &mut self,
+        offset: u32,
+        data: &[T],
+    ) -> Result<(), ComputePassError> {
+        self.set_push_constants_at(offset, bytemuck::cast_slice(data))
+    }
+
+    pub fn set_attachment_sampler(
+        &mut self,
+        group: u32,
+        binding: u32,
+        sampler: Option<&TextureSampler>,
+    ) -> Result<(), ComputePassError> {
+        match sampler {
+            Some(sampler) => {
+                let features = self.graphics.borrow().device().features();
+                sampler
+                    .validate_features(features)
+                    .map_err(|e| ComputePassError::InvalidSampler(group, binding, e))?;
+
+                if let Some(ShaderBindingType::Sampler(comparison)) =
+                    self.binding_type(group, binding)
+                    && comparison != sampler.compare.is_some()
                 {
-                    if inner.shader.is_none() {
-                        panic!("Shader must be set before setting push constants");
-                    }
+                    return Err(ComputePassError::InvalidSampler(
+                        group,
+                        binding,
+                        SamplerError::CompareMismatch,
+                    ));
+                }
 
-                    let size = {
-                        let shader_reflection = inner.reflection.as_ref().unwrap();
+                let sampler = sampler.make_wgpu(self.graphics.borrow().device());
 
-                        match &shader_reflection {
-                            ShaderReflect::Compute { bindings, .. } => bindings
-                                .iter()
-                                .find_map(|binding| {
-                                    if let ShaderBindingType::PushConstant(size) = binding.ty {
-                                        Some(size)
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .unwrap_or(0),
-                            _ => panic!("Shader is not a compute shader"),
-                        }
-                    };
+                self.insert_or_replace_attachment(
+                    group,
+                    binding,
+                    BindGroupAttachment {
+                        group,
+                        binding,
+                        attachment: BindGroupType::Sampler(sampler),
+                    },
+                )
+            }
+            None => self.remove_attachment(group, binding),
+        }
+    }
 
-                    if size == 0 {
-                        panic!("No push constant found in shader");
-                    }
+    pub fn set_attachment_texture(
+        &mut self,
+        group: u32,
+        binding: u32,
+        texture: Option<&Texture>,
+    ) -> Result<(), ComputePassError> {
+        match texture {
+            Some(texture) => {
+                let view = {
+                    let inner = texture.inner.borrow();
+                    self.validate_texture(group, binding, &inner, inner.format)?;
+
+                    inner.sample_view().clone()
+                };
 
-                    if push_constant.len() > size as usize {
-                        panic!("Push constant size is too large");
+                self.insert_or_replace_attachment(
+                    group,
+                    binding,
+                    BindGroupAttachment {
+                        group,
+                        binding,
+                        attachment: BindGroupType::Texture(view),
+                    },
+                )
+            }
+            None => self.remove_attachment(group, binding),
+        }
+    }
+
+    /// Binds the texture at `binding` and its default sampler, see
+    /// [TextureBuilder::set_sampler](crate::gpu::texture::TextureBuilder::set_sampler), at
+    /// `sampler_binding` of the same group.
+    pub fn set_attachment_texture_sampled(
+        &mut self,
+        group: u32,
+        binding: u32,
+        sampler_binding: u32,
+        texture: Option<&Texture>,
+    ) -> Result<(), ComputePassError> {
+        let sampler = texture.map(|texture| texture.sampler());
+
+        self.set_attachment_texture(group, binding, texture)?;
+        self.set_attachment_sampler(group, sampler_binding, sampler.as_ref())
+    }
+
+    /// Binds a texture created with
+    /// [TextureBuilder::set_storage_binding](crate::gpu::texture::TextureBuilder::set_storage_binding).
+    ///
+    /// The format of the texture must match the format declared by the shader, the access
+    /// (`read`, `write` or `read_write`) is taken from the shader declaration.
+    pub fn set_attachment_texture_storage(
+        &mut self,
+        group: u32,
+        binding: u32,
+        texture: Option<&Texture>,
+    ) -> Result<(), ComputePassError> {
+        match texture {
+            Some(texture) => {
+                let attachment = {
+                    let inner = texture.inner.borrow();
+
+                    if !inner.usages.contains(TextureUsage::StorageBinding)
+                        || inner.sample_count != SampleCount::SampleCount1
+                    {
+                        return Err(ComputePassError::InvalidStorageTexture(group, binding));
                     }
+
+                    BindGroupType::TextureStorage(inner.wgpu_view.clone(), inner.format)
+                };
+
+                self.insert_or_replace_attachment(
+                    group,
+                    binding,
+                    BindGroupAttachment {
+                        group,
+                        binding,
+                        attachment,
+                    },
+                )
+            }
+            None => self.remove_attachment(group, binding),
+        }
+    }
+
+    /// Binds a [TextureView] of a single array layer, eg. one mip level of a texture.
+    pub fn set_attachment_texture_view(
+        &mut self,
+        group: u32,
+        binding: u32,
+        view: Option<&TextureView>,
+    ) -> Result<(), ComputePassError> {
+        match view {
+            Some(view) => {
+                if !view.is_single_layer() {
+                    return Err(ComputePassError::InvalidTextureView(group, binding));
                 }
 
-                inner.push_constant = Some(push_constant);
+                let texture = view.texture.inner.borrow();
+                self.validate_texture(group, binding, &texture, view.format())?;
+                drop(texture);
+
+                self.insert_or_replace_attachment(
+                    group,
+                    binding,
+                    BindGroupAttachment {
+                        group,
+                        binding,
+                        attachment: BindGroupType::Texture(view.view.clone()),
+                    },
+                )
             }
-            None => {
-                inner.push_constant = None;
+            None => self.remove_attachment(group, binding),
+        }
+    }
+
+    /// Binds a [TextureView] of a single mip level and array layer as a storage texture, eg. the
+    /// destination of a downsampling pass.
+    ///
+    /// The texture must be created with
+    /// [TextureBuilder::set_storage_binding](crate::gpu::texture::TextureBuilder::set_storage_binding),
+    /// and the view format must match the format declared by the shader.
+    pub fn set_attachment_texture_storage_view(
+        &mut self,
+        group: u32,
+        binding: u32,
+        view: Option<&TextureView>,
+    ) -> Result<(), ComputePassError> {
+        match view {
+            Some(view) => {
+                {
+                    let inner = view.texture.inner.borrow();
+
+                    if !inner.usages.contains(TextureUsage::StorageBinding)
+                        || inner.sample_count != SampleCount::SampleCount1
+                    {
+                        return Err(ComputePassError::InvalidStorageTexture(group, binding));
+                    }
+                }
+
+                if !view.is_single_layer() || view.mip_level_count() != 1 {
+                    return Err(ComputePassError::InvalidTextureView(group, binding));
+                }
+
+                self.insert_or_replace_attachment(
+                    group,
+                    binding,
+                    BindGroupAttachment {
+                        group,
+                        binding,
+                        attachment: BindGroupType::TextureStorage(view.view.clone(), view.format()),
+                    },
+                )
             }
+            None => self.remove_attachment(group, binding),
         }
     }
 
-    pub fn set_attachment_buffer(&mut self, group: u32, binding: u32, attachment: Option<&Buffer>) {
-        match attachment {
-            Some(attachment) => {
-                let buffer = attachment.inner.borrow().buffer.clone();
+    pub fn set_attachment_uniform(
+        &mut self,
+        group: u32,
+        binding: u32,
+        buffer: Option<&Buffer>,
+    ) -> Result<(), ComputePassError> {
+        match buffer {
+            Some(buffer) => {
+                let inner = buffer.inner.borrow();
+                if !inner.usage.contains(BufferUsage::UNIFORM) {
+                    return Err(ComputePassError::InvalidBufferUsage(group, binding));
+                }
+
+                let attachment = BindGroupType::Uniform(inner.range());
+                drop(inner);
 
                 self.insert_or_replace_attachment(
                     group,
@@ -169,27 +506,162 @@ impl ComputePass {
                     BindGroupAttachment {
                         group,
                         binding,
-                        attachment: BindGroupType::Storage(buffer),
+                        attachment,
                     },
+                )
+            }
+            None => self.remove_attachment(group, binding),
+        }
+    }
+
+    /// Binds the buffer of `uniform`, recording the upload of its value ahead of the pass if it
+    /// changed, see [Uniform].
+    pub fn set_attachment_uniform_value<T>(
+        &mut self,
+        group: u32,
+        binding: u32,
+        uniform: &Uniform<T>,
+    ) -> Result<(), ComputePassError>
+    where
+        T: bytemuck::Pod,
+    {
+        {
+            let inner = self.inner.borrow();
+            uniform.upload_cmd(&mut inner.cmd.borrow_mut());
+        }
+
+        self.set_attachment_uniform(group, binding, Some(uniform.buffer()))
+    }
+
+    pub fn set_attachment_uniform_vec<T>(
+        &mut self,
+        group: u32,
+        binding: u32,
+        buffer: Option<Vec<T>>,
+    ) -> Result<(), ComputePassError>
+    where
+        T: bytemuck::Pod + bytemuck::Zeroable,
+    {
+        self.set_attachment_uniform_raw(group, binding, buffer.as_deref())
+    }
+
+    pub fn set_attachment_uniform_raw<T>(
+        &mut self,
+        group: u32,
+        binding: u32,
+        buffer: Option<&[T]>,
+    ) -> Result<(), ComputePassError>
+    where
+        T: bytemuck::Pod + bytemuck::Zeroable,
+    {
+        match buffer {
+            Some(buffer) => {
+                let buffer = self.graphics.borrow_mut().create_buffer_with(
+                    buffer,
+                    wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
                 );
+
+                self.insert_or_replace_attachment(
+                    group,
+                    binding,
+                    BindGroupAttachment {
+                        group,
+                        binding,
+                        attachment: BindGroupType::Uniform(buffer.into()),
+                    },
+                )
             }
-            None => {
-                self.remove_attachment(group, binding);
+            None => self.remove_attachment(group, binding),
+        }
+    }
+
+    pub fn set_attachment_storage(
+        &mut self,
+        group: u32,
+        binding: u32,
+        buffer: Option<&Buffer>,
+    ) -> Result<(), ComputePassError> {
+        match buffer {
+            Some(buffer) => {
+                let inner = buffer.inner.borrow();
+                if !inner.usage.contains(BufferUsage::STORAGE) {
+                    return Err(ComputePassError::InvalidBufferUsage(group, binding));
+                }
+
+                let attachment = BindGroupType::Storage(inner.range());
+                drop(inner);
+
+                self.insert_or_replace_attachment(
+                    group,
+                    binding,
+                    BindGroupAttachment {
+                        group,
+                        binding,
+                        attachment,
+                    },
+                )
             }
+            None => self.remove_attachment(group, binding),
         }
     }
 
+    pub fn set_attachment_storage_vec<T>(
+        &mut self,
+        group: u32,
+        binding: u32,
+        buffer: Option<Vec<T>>,
+    ) -> Result<(), ComputePassError>
+    where
+        T: bytemuck::Pod + bytemuck::Zeroable,
+    {
+        self.set_attachment_storage_raw(group, binding, buffer.as_deref())
+    }
+
+    pub fn set_attachment_storage_raw<T>(
+        &mut self,
+        group: u32,
+        binding: u32,
+        buffer: Option<&[T]>,
+    ) -> Result<(), ComputePassError>
+    where
+        T: bytemuck::Pod + bytemuck::Zeroable,
+    {
+        self.set_attachment_buffer_raw(
+            group,
+            binding,
+            buffer,
+            BufferUsage::STORAGE | BufferUsage::COPY_DST | BufferUsage::COPY_SRC,
+        )
+    }
+
+    /// Same as [ComputePass::set_attachment_storage].
+    pub fn set_attachment_buffer(
+        &mut self,
+        group: u32,
+        binding: u32,
+        attachment: Option<&Buffer>,
+    ) -> Result<(), ComputePassError> {
+        self.set_attachment_storage(group, binding, attachment)
+    }
+
+    /// Binds a new storage buffer created from `attachment` with the given usages, which must
+    /// include [BufferUsage::STORAGE].
     pub fn set_attachment_buffer_raw<T>(
         &mut self,
         group: u32,
         binding: u32,
         attachment: Option<&[T]>,
         usages: BufferUsage,
-    ) where
+    ) -> Result<(), ComputePassError>
+    where
         T: bytemuck::Pod + bytemuck::Zeroable,
     {
         match attachment {
             Some(attachment) => {
+                if !usages.contains(BufferUsage::STORAGE) {
+                    return Err(ComputePassError::InvalidBufferUsage(group, binding));
+                }
+
                 let buffer = self
                     .graphics
                     .borrow_mut()
@@ -201,22 +673,30 @@ impl ComputePass {
                     BindGroupAttachment {
                         group,
                         binding,
-                        attachment: BindGroupType::Storage(buffer),
+                        attachment: BindGroupType::Storage(buffer.into()),
                     },
-                );
-            }
-            None => {
-                self.remove_attachment(group, binding);
+                )
             }
+            None => self.remove_attachment(group, binding),
         }
     }
 
-    pub(crate) fn remove_attachment(&mut self, group: u32, binding: u32) {
+    pub(crate) fn remove_attachment(
+        &mut self,
+        group: u32,
+        binding: u32,
+    ) -> Result<(), ComputePassError> {
         let mut inner = self.inner.borrow_mut();
 
+        if let Some(ComputeShaderBinding::Pipeline(_)) = &inner.shader {
+            return Err(ComputePassError::PipelineBound);
+        }
+
         inner
             .attachments
             .retain(|a| a.group != group || a.binding != binding);
+
+        Ok(())
     }
 
     pub(crate) fn insert_or_replace_attachment(
@@ -224,68 +704,47 @@ impl ComputePass {
         group: u32,
         binding: u32,
         attachment: BindGroupAttachment,
-    ) {
+    ) -> Result<(), ComputePassError> {
         let mut inner = self.inner.borrow_mut();
 
-        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
-        {
-            if inner.shader.is_none() {
-                panic!("Shader is not set");
-            }
-
-            match &inner.shader {
-                Some(ComputeShaderBinding::Pipeline(_)) => {
-                    panic!("Cannot insert or replace attachment when using a pipeline shader");
-                }
-                _ => {}
+        let bindings = match (&inner.shader, &inner.reflection) {
+            (None, _) => return Err(ComputePassError::ShaderNotSet),
+            (Some(ComputeShaderBinding::Pipeline(_)), _) => {
+                return Err(ComputePassError::PipelineBound);
             }
+            (_, Some(ShaderReflect::Compute { bindings, .. })) => bindings,
+            _ => return Err(ComputePassError::ShaderNotSet),
+        };
 
-            let reflection = inner.reflection.as_ref().unwrap();
+        let r#type = bindings
+            .iter()
+            .find(|shaderbinding| shaderbinding.group == group && shaderbinding.binding == binding)
+            .ok_or(ComputePassError::BindingNotFound(group, binding))?;
 
-            let r#type = match reflection {
-                ShaderReflect::Compute { bindings, .. } => bindings
-                    .iter()
-                    .find_map(|shaderbinding| {
-                        if shaderbinding.group == group && shaderbinding.binding == binding {
-                            Some(shaderbinding)
-                        } else {
-                            None
-                        }
-                    })
-                    .unwrap_or_else(|| {
-                        panic!(
-                            "Shader does not have binding group: {} binding: {}",
-                            group, binding
-                        );
-                    }),
-                _ => panic!("Shader is not a compute shader"),
-            };
-
-            if !match r#type.ty {
-                ShaderBindingType::UniformBuffer(_) => {
-                    matches!(attachment.attachment, BindGroupType::Uniform(_))
-                }
-                ShaderBindingType::StorageBuffer(_, _) => {
-                    matches!(attachment.attachment, BindGroupType::Storage(_))
-                }
-                ShaderBindingType::StorageTexture(_) => {
-                    matches!(attachment.attachment, BindGroupType::TextureStorage(_))
-                }
-                ShaderBindingType::Sampler(_) => {
-                    matches!(attachment.attachment, BindGroupType::Sampler(_))
-                }
-                ShaderBindingType::Texture(_) => {
-                    matches!(attachment.attachment, BindGroupType::Texture(_))
-                }
-                ShaderBindingType::PushConstant(_) => {
-                    matches!(attachment.attachment, BindGroupType::Uniform(_))
-                }
-            } {
-                panic!(
-                    "Attachment group: {} binding: {} type: {} not match with shader type: {}",
-                    group, binding, attachment.attachment, r#type.ty
-                );
+        if !match r#type.ty {
+            ShaderBindingType::UniformBuffer(_) => {
+                matches!(attachment.attachment, BindGroupType::Uniform(_))
             }
+            ShaderBindingType::StorageBuffer(_, _) => {
+                matches!(attachment.attachment, BindGroupType::Storage(_))
+            }
+            ShaderBindingType::StorageTexture(_, format) => {
+                matches!(attachment.attachment, BindGroupType::TextureStorage(_, f) if f == format)
+            }
+            ShaderBindingType::Sampler(_) => {
+                matches!(attachment.attachment, BindGroupType::Sampler(_))
+            }
+            ShaderBindingType::Texture(_) | ShaderBindingType::DepthTexture(_) => {
+                matches!(attachment.attachment, BindGroupType::Texture(_))
+            }
+            ShaderBindingType::PushConstant(_) => false,
+        } {
+            return Err(ComputePassError::InvalidAttachmentType(
+                group,
+                binding,
+                r#type.ty,
+                attachment.attachment.to_string(),
+            ));
         }
 
         let index = inner
@@ -298,6 +757,8 @@ impl ComputePass {
         } else {
             inner.attachments.push(attachment);
         }
+
+        Ok(())
     }
 
     pub fn dispatch(&mut self, x: u32, y: u32, z: u32) {
@@ -324,6 +785,11 @@ impl ComputePass {
         inner.queues.push(queue);
     }
 
+    /// Dispatches with the workgroup counts read from `buffer` at `offset`, a
+    /// [DispatchIndirectArgs].
+    ///
+    /// The counts can be written by a previous compute pass, so the size of the work doesn't
+    /// need to be read back on the CPU.
     pub fn dispatch_indirect(&mut self, buffer: &Buffer, offset: u64) {
         #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
         {
@@ -332,18 +798,39 @@ impl ComputePass {
             if inner.shader.is_none() {
                 panic!("Shader must be set before dispatching");
             }
+
+            let buffer_inner = buffer.inner.borrow();
+
+            if !buffer_inner.usage.contains(BufferUsage::INDIRECT) {
+                panic!("Buffer must have INDIRECT usage");
+            }
+
+            if !offset.is_multiple_of(4) {
+                panic!("Indirect buffer offset {} must be a multiple of 4", offset);
+            }
+
+            let end = offset + DispatchIndirectArgs::SIZE;
+            if end > buffer_inner.size {
+                panic!(
+                    "Indirect buffer is too small, {} bytes are read but the buffer is {} bytes",
+                    end, buffer_inner.size
+                );
+            }
         }
 
         let (pipeline, bind_group) = self.prepare_pipeline();
         let mut inner = self.inner.borrow_mut();
 
+        // Pooled buffers start inside their slab
+        let (buffer, offset) = {
+            let buffer_inner = buffer.inner.borrow();
+            (buffer_inner.buffer.clone(), buffer_inner.offset + offset)
+        };
+
         let queue = ComputePassQueue {
             pipeline,
             bind_group,
-            ty: DispatchType::DispatchIndirect {
-                buffer: buffer.inner.borrow().buffer.clone(),
-                offset,
-            },
+            ty: DispatchType::DispatchIndirect { buffer, offset },
             push_constant: inner.push_constant.clone(),
             debug: None,
         };
@@ -371,7 +858,7 @@ impl ComputePass {
                             BindGroupType::Storage(buffer) => {
                                 buffer.hash(&mut hasher);
                             }
-                            BindGroupType::TextureStorage(texture) => {
+                            BindGroupType::TextureStorage(texture, _) => {
                                 texture.hash(&mut hasher);
                             }
                             BindGroupType::Sampler(sampler) => {
@@ -400,23 +887,25 @@ impl ComputePass {
                                     (e.group, e.binding, &e.attachment);
 
                                 let entry = match attachment {
-                                    BindGroupType::TextureStorage(texture) => {
+                                    BindGroupType::TextureStorage(texture, _) => {
                                         wgpu::BindGroupEntry {
                                             binding,
                                             resource: wgpu::BindingResource::TextureView(texture),
                                         }
                                     }
-                                    BindGroupType::Storage(buffer) => wgpu::BindGroupEntry {
+                                    BindGroupType::Storage(buffer)
+                                    | BindGroupType::Uniform(buffer) => wgpu::BindGroupEntry {
                                         binding,
-                                        resource: wgpu::BindingResource::Buffer(
-                                            wgpu::BufferBinding {
-                                                buffer,
-                                                offset: 0,
-                                                size: None,
-                                            },
-                                        ),
+                                        resource: wgpu::BindingResource::Buffer(buffer.binding(None)),
+                                    },
+                                    BindGroupType::Texture(texture) => wgpu::BindGroupEntry {
+                                        binding,
+                                        resource: wgpu::BindingResource::TextureView(texture),
+                                    },
+                                    BindGroupType::Sampler(sampler) => wgpu::BindGroupEntry {
+                                        binding,
+                                        resource: wgpu::BindingResource::Sampler(sampler),
                                     },
-                                    _ => panic!("Unsupported bind group type"),
                                 };
 
                                 map.entry(group).or_insert_with(Vec::new).push(entry);
@@ -477,6 +966,8 @@ impl ComputePass {
                                 shader_module: shader_binding.shader.clone(),
                                 entry_point: entry_point.to_owned(),
                                 bind_group_layout,
+                                push_constant_ranges: shader_binding.push_constant_ranges.clone(),
+                                label: None,
                             };
 
                             gpu_inner.create_compute_pipeline(pipeline_hash_key, pipeline_desc)
@@ -581,7 +1072,6 @@ pub(crate) struct ComputePassInner {
     pub attachments: Vec<BindGroupAttachment>,
     pub push_constant: Option<Vec<u8>>,
 
-    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
     pub reflection: Option<ShaderReflect>,
 }
 
@@ -591,20 +1081,133 @@ pub enum DispatchType {
     DispatchIndirect { buffer: wgpu::Buffer, offset: u64 },
 }
 
+/// Workgroup counts of an indirect dispatch, as read by [ComputePass::dispatch_indirect].
+///
+/// To write it from a compute shader, use this WGSL struct:
+/// ```wgsl
+/// struct DispatchIndirectArgs {
+///     x: u32,
+///     y: u32,
+///     z: u32,
+/// };
+/// ```
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct DispatchIndirectArgs {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+impl DispatchIndirectArgs {
+    /// Size in bytes.
+    pub const SIZE: u64 = std::mem::size_of::<Self>() as u64;
+}
+
 #[derive(Clone, Debug, Hash)]
 pub(crate) struct IntermediateComputeBinding {
     pub shader: wgpu::ShaderModule,
     pub layout: Vec<BindGroupLayout>,
     pub entry_point: String,
+    pub push_constant_ranges: Vec<wgpu::PushConstantRange>,
 }
 
 #[derive(Clone, Debug)]
 pub(crate) enum ComputeShaderBinding {
     Intermediate(IntermediateComputeBinding),
-    Pipeline(ComputePipeline),
+    Pipeline(ComputePipelineInner),
 }
 
 #[derive(Clone, Debug)]
 pub enum ComputePassBuildError {
     None
-}
\ No newline at end of file
+}
+
+/// Error returned when an attachment or push constant doesn't match the shader of a
+/// [ComputePass].
+#[derive(Clone, Debug)]
+pub enum ComputePassError {
+    ShaderNotSet,
+    /// The attachments of a [ComputePipeline] are set when it is built, they can't be changed
+    /// on the pass.
+    PipelineBound,
+    BindingNotFound(u32, u32),
+    BindingNameNotFound(String),
+    /// The attachment doesn't match the shader binding type.
+    InvalidAttachmentType(u32, u32, ShaderBindingType, String),
+    /// The texture is multisampled or was not created with `TextureUsage::StorageBinding`.
+    InvalidStorageTexture(u32, u32),
+    /// The buffer doesn't have the `UNIFORM` or `STORAGE` usage required by the binding.
+    InvalidBufferUsage(u32, u32),
+    /// The view sees more than one array layer, or more than one mip level for a storage binding.
+    InvalidTextureView(u32, u32),
+    /// The sampled texture doesn't match the shader binding, eg. a depth texture bound to a
+    /// `texture_2d<f32>` instead of a `texture_depth_2d`.
+    InvalidTexture(u32, u32, String),
+    InvalidSampler(u32, u32, SamplerError),
+    NoPushConstants,
+    InvalidPushConstantOffset(u32),
+    PushConstantOutOfRange { start: usize, end: usize, size: u32 },
+}
+
+impl std::fmt::Display for ComputePassError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComputePassError::ShaderNotSet => write!(f, "Compute shader is not set"),
+            ComputePassError::PipelineBound => write!(
+                f,
+                "Attachments cannot be changed while a compute pipeline is set"
+            ),
+            ComputePassError::BindingNotFound(group, binding) => write!(
+                f,
+                "Shader does not have binding group: {} binding: {}",
+                group, binding
+            ),
+            ComputePassError::BindingNameNotFound(name) => {
+                write!(f, "Shader does not have a binding named '{}'", name)
+            }
+            ComputePassError::InvalidAttachmentType(group, binding, ty, attachment) => write!(
+                f,
+                "Attachment group: {} binding: {} type: {} not match with shader type: {}",
+                group, binding, attachment, ty
+            ),
+            ComputePassError::InvalidStorageTexture(group, binding) => write!(
+                f,
+                "Texture at group: {} binding: {} must be single sampled and created with TextureUsage::StorageBinding",
+                group, binding
+            ),
+            ComputePassError::InvalidTextureView(group, binding) => write!(
+                f,
+                "Texture view at group: {} binding: {} must see a single layer, and a single mip level when bound as storage",
+                group, binding
+            ),
+            ComputePassError::InvalidTexture(group, binding, reason) => write!(
+                f,
+                "Texture at group: {} binding: {} is invalid: {}",
+                group, binding, reason
+            ),
+            ComputePassError::InvalidBufferUsage(group, binding) => write!(
+                f,
+                "Buffer at group: {} binding: {} does not have the usage required by the shader",
+                group, binding
+            ),
+            ComputePassError::InvalidSampler(group, binding, err) => write!(
+                f,
+                "Sampler at group: {} binding: {} is invalid: {}",
+                group, binding, err
+            ),
+            ComputePassError::NoPushConstants => write!(f, "Shader does not have push constants"),
+            ComputePassError::InvalidPushConstantOffset(offset) => write!(
+                f,
+                "Push constant offset {} must be a multiple of {}",
+                offset,
+                wgpu::PUSH_CONSTANT_ALIGNMENT
+            ),
+            ComputePassError::PushConstantOutOfRange { start, end, size } => write!(
+                f,
+                "Push constant data ({}..{}) exceeds the push constant size ({})",
+                start, end, size
+            ),
+        }
+    }
+}
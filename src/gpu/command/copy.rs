@@ -0,0 +1,268 @@
+use crate::math::Rect;
+
+use super::{
+    super::{
+        buffer::{Buffer, BufferUsage},
+        texture::{SampleCount, Texture, TextureInner},
+    },
+    CommandBuffer,
+};
+
+/// Alignment in bytes of [BufferTextureLayout::bytes_per_row].
+pub const COPY_ROW_ALIGNMENT: u32 = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+/// How the texels of a copied region are laid out in a buffer, see
+/// [CommandBuffer::copy_buffer_to_texture] and [CommandBuffer::copy_texture_to_buffer].
+///
+/// Rows are stored one after another starting at `offset`, each `bytes_per_row` apart. The
+/// bytes between the end of a row and the start of the next one are left untouched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BufferTextureLayout {
+    /// Offset in bytes of the first texel, must be a multiple of the texel size.
+    pub offset: u64,
+    /// Distance in bytes between the start of two rows, must be a multiple of
+    /// [COPY_ROW_ALIGNMENT] and hold at least a row of the region.
+    pub bytes_per_row: u32,
+}
+
+impl BufferTextureLayout {
+    pub fn new(offset: u64, bytes_per_row: u32) -> Self {
+        Self {
+            offset,
+            bytes_per_row,
+        }
+    }
+
+    /// Layout at offset 0 with rows of `width` texels of `texel_size` bytes, padded to
+    /// [COPY_ROW_ALIGNMENT].
+    pub fn padded(width: u32, texel_size: u32) -> Self {
+        Self {
+            offset: 0,
+            bytes_per_row: (width * texel_size).next_multiple_of(COPY_ROW_ALIGNMENT),
+        }
+    }
+
+    /// Number of bytes of the buffer used by `rows` rows of `row_size` bytes, from `offset`.
+    pub fn required_size(&self, row_size: u32, rows: u32) -> u64 {
+        if rows == 0 {
+            return self.offset;
+        }
+
+        self.offset + self.bytes_per_row as u64 * (rows - 1) as u64 + row_size as u64
+    }
+}
+
+/// A rectangle of one mip level and array layer of a texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextureRegion {
+    /// Area of the mip level, in texels of that level.
+    pub rect: Rect,
+    pub mip_level: u32,
+    pub array_layer: u32,
+}
+
+impl TextureRegion {
+    /// A rectangle of the first mip level and layer.
+    pub fn new(rect: Rect) -> Self {
+        Self {
+            rect,
+            mip_level: 0,
+            array_layer: 0,
+        }
+    }
+
+    /// The whole first mip level and layer of `texture`.
+    pub fn full(texture: &Texture) -> Self {
+        let size = texture.size();
+        Self::new(Rect::new(0, 0, size.x, size.y))
+    }
+
+    pub fn with_mip_level(mut self, mip_level: u32) -> Self {
+        self.mip_level = mip_level;
+        self
+    }
+
+    pub fn with_array_layer(mut self, array_layer: u32) -> Self {
+        self.array_layer = array_layer;
+        self
+    }
+}
+
+impl From<Rect> for TextureRegion {
+    fn from(rect: Rect) -> Self {
+        Self::new(rect)
+    }
+}
+
+impl CommandBuffer {
+    /// Copies texels laid out in `src` into a region of `dst`, eg. pixels generated by a compute
+    /// pass into a texture.
+    ///
+    /// Will panic if `src` doesn't have [BufferUsage::COPY_SRC], if `dst` is a depth or
+    /// multisampled texture, or if the region or layout is invalid, see [BufferTextureLayout].
+    pub fn copy_buffer_to_texture(
+        &mut self,
+        src: &Buffer,
+        layout: BufferTextureLayout,
+        dst: &Texture,
+        region: TextureRegion,
+    ) {
+        if !src.usage().contains(BufferUsage::COPY_SRC) {
+            panic!("Source buffer must have BufferUsage::COPY_SRC");
+        }
+
+        let dst_inner = dst.inner.borrow();
+        validate_copy(&dst_inner, src, layout, region);
+
+        let buffer = src.inner.borrow();
+        let mut cmd = self.command.as_ref().unwrap().borrow_mut();
+
+        cmd.copy_buffer_to_texture(
+            buffer_info(&buffer.buffer, buffer.offset, layout),
+            texture_info(&dst_inner, region),
+            extent(region),
+        );
+    }
+
+    /// Copies a region of `src` into `dst` with the given layout, eg. to read a render target
+    /// back once the command buffer completed, without stalling on it.
+    ///
+    /// Will panic if `dst` doesn't have [BufferUsage::COPY_DST], if `src` is a depth or
+    /// multisampled texture, or if the region or layout is invalid, see [BufferTextureLayout].
+    pub fn copy_texture_to_buffer(
+        &mut self,
+        src: &Texture,
+        region: TextureRegion,
+        dst: &Buffer,
+        layout: BufferTextureLayout,
+    ) {
+        if !dst.usage().contains(BufferUsage::COPY_DST) {
+            panic!("Destination buffer must have BufferUsage::COPY_DST");
+        }
+
+        let src_inner = src.inner.borrow();
+        validate_copy(&src_inner, dst, layout, region);
+
+        let buffer = dst.inner.borrow();
+        let mut cmd = self.command.as_ref().unwrap().borrow_mut();
+
+        cmd.copy_texture_to_buffer(
+            texture_info(&src_inner, region),
+            buffer_info(&buffer.buffer, buffer.offset, layout),
+            extent(region),
+        );
+    }
+}
+
+fn validate_copy(
+    texture: &TextureInner,
+    buffer: &Buffer,
+    layout: BufferTextureLayout,
+    region: TextureRegion,
+) {
+    if texture.format.is_depth() || texture.sample_count != SampleCount::SampleCount1 {
+        panic!("Only single sampled color textures can be copied to or from a buffer");
+    }
+
+    let mip_level_count = texture.wgpu_texture.mip_level_count();
+    if region.mip_level >= mip_level_count {
+        panic!(
+            "Mip level {} is outside of the {} levels of the texture",
+            region.mip_level, mip_level_count
+        );
+    }
+
+    let layer_count = texture.wgpu_texture.depth_or_array_layers();
+    if region.array_layer >= layer_count {
+        panic!(
+            "Array layer {} is outside of the {} layers of the texture",
+            region.array_layer, layer_count
+        );
+    }
+
+    let width = (texture.size.x >> region.mip_level).max(1);
+    let height = (texture.size.y >> region.mip_level).max(1);
+    let rect = region.rect;
+
+    if rect.x < 0
+        || rect.y < 0
+        || rect.w <= 0
+        || rect.h <= 0
+        || rect.x + rect.w > width
+        || rect.y + rect.h > height
+    {
+        panic!(
+            "Copy region {:?} is outside of the {}x{} mip level",
+            rect, width, height
+        );
+    }
+
+    let texel_size = texture.format.get_size();
+    let row_size = texel_size * rect.w as u32;
+
+    if !layout.bytes_per_row.is_multiple_of(COPY_ROW_ALIGNMENT) {
+        panic!(
+            "Bytes per row {} must be a multiple of {}",
+            layout.bytes_per_row, COPY_ROW_ALIGNMENT
+        );
+    }
+
+    if layout.bytes_per_row < row_size {
+        panic!(
+            "Bytes per row {} is smaller than a row of the region, {} bytes",
+            layout.bytes_per_row, row_size
+        );
+    }
+
+    if !layout.offset.is_multiple_of(texel_size as u64) {
+        panic!(
+            "Buffer offset {} must be a multiple of the texel size {}",
+            layout.offset, texel_size
+        );
+    }
+
+    let required = layout.required_size(row_size, rect.h as u32);
+    if required > buffer.size() {
+        panic!(
+            "Buffer is too small, the copy needs {} bytes but the buffer is {} bytes",
+            required,
+            buffer.size()
+        );
+    }
+}
+
+fn buffer_info(
+    buffer: &wgpu::Buffer,
+    offset: u64,
+    layout: BufferTextureLayout,
+) -> wgpu::TexelCopyBufferInfo<'_> {
+    wgpu::TexelCopyBufferInfo {
+        buffer,
+        layout: wgpu::TexelCopyBufferLayout {
+            offset: offset + layout.offset,
+            bytes_per_row: Some(layout.bytes_per_row),
+            rows_per_image: None,
+        },
+    }
+}
+
+fn texture_info(texture: &TextureInner, region: TextureRegion) -> wgpu::TexelCopyTextureInfo<'_> {
+    wgpu::TexelCopyTextureInfo {
+        texture: &texture.wgpu_texture,
+        mip_level: region.mip_level,
+        origin: wgpu::Origin3d {
+            x: region.rect.x as u32,
+            y: region.rect.y as u32,
+            z: region.array_layer,
+        },
+        aspect: wgpu::TextureAspect::All,
+    }
+}
+
+fn extent(region: TextureRegion) -> wgpu::Extent3d {
+    wgpu::Extent3d {
+        width: region.rect.w as u32,
+        height: region.rect.h as u32,
+        depth_or_array_layers: 1,
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{
+    math::{RectF, Vector2, Vertex},
+    utils::ArcRef,
+};
+
+/// A retained list of tagged primitives recorded by a [DrawingContext](super::drawing::DrawingContext).
+///
+/// The list is filled while drawing and stays valid after the drawing context is dropped,
+/// so it can be hit-tested against input events until the next frame records into it again.
+#[derive(Clone, Debug)]
+pub struct DisplayList {
+    pub(crate) inner: ArcRef<DisplayListInner>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DisplayListInner {
+    pub items: Vec<DisplayItem>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DisplayItem {
+    pub tag: u64,
+    pub bounds: RectF,
+    pub clip: Option<RectF>,
+    pub triangles: Vec<[Vector2; 3]>,
+}
+
+impl DisplayList {
+    pub fn new() -> Self {
+        Self {
+            inner: ArcRef::new(DisplayListInner { items: Vec::new() }),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.inner.borrow_mut().items.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.borrow().items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.borrow().items.is_empty()
+    }
+
+    /// Returns the tags of all primitives under the point, topmost first.
+    ///
+    /// Each tag is returned once, even if several primitives with the same tag are hit.
+    pub fn pick(&self, point: Vector2) -> Vec<u64> {
+        let inner = self.inner.borrow();
+        let mut tags = Vec::new();
+
+        for item in inner.items.iter().rev() {
+            if tags.contains(&item.tag) {
+                continue;
+            }
+
+            if item.clip.is_some_and(|clip| !clip.is_touch(point.x, point.y)) {
+                continue;
+            }
+
+            // Inclusive, so points on the right and bottom edges still reach the triangle test
+            let bounds = &item.bounds;
+            if point.x < bounds.x
+                || point.y < bounds.y
+                || point.x > bounds.x + bounds.w
+                || point.y > bounds.y + bounds.h
+            {
+                continue;
+            }
+
+            if item
+                .triangles
+                .iter()
+                .any(|triangle| point_in_triangle(point, triangle))
+            {
+                tags.push(item.tag);
+            }
+        }
+
+        tags
+    }
+
+    /// Returns the union of the bounds of all primitives with the given tag.
+    pub fn bounds(&self, tag: u64) -> Option<RectF> {
+        let inner = self.inner.borrow();
+
+        inner
+            .items
+            .iter()
+            .filter(|item| item.tag == tag)
+            .map(|item| item.bounds)
+            .reduce(|a, b| {
+                let x0 = a.x.min(b.x);
+                let y0 = a.y.min(b.y);
+                let x1 = (a.x + a.w).max(b.x + b.w);
+                let y1 = (a.y + a.h).max(b.y + b.h);
+
+                RectF::new(x0, y0, x1 - x0, y1 - y0)
+            })
+    }
+
+    pub(crate) fn push(
+        &mut self,
+        tag: u64,
+        clip: Option<RectF>,
+        vertices: &[Vertex],
+        indices: &[u16],
+    ) {
+        let mut min = Vector2::new(f32::MAX, f32::MAX);
+        let mut max = Vector2::new(f32::MIN, f32::MIN);
+
+        let triangles = indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                let points = [
+                    vertices[triangle[0] as usize].position,
+                    vertices[triangle[1] as usize].position,
+                    vertices[triangle[2] as usize].position,
+                ]
+                .map(|p| Vector2::new(p.x, p.y));
+
+                for p in &points {
+                    min = Vector2::new(min.x.min(p.x), min.y.min(p.y));
+                    max = Vector2::new(max.x.max(p.x), max.y.max(p.y));
+                }
+
+                points
+            })
+            .collect::<Vec<_>>();
+
+        if triangles.is_empty() {
+            return;
+        }
+
+        self.inner.borrow_mut().items.push(DisplayItem {
+            tag,
+            bounds: RectF::new(min.x, min.y, max.x - min.x, max.y - min.y),
+            clip,
+            triangles,
+        });
+    }
+}
+
+impl Default for DisplayList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn point_in_triangle(p: Vector2, triangle: &[Vector2; 3]) -> bool {
+    let [a, b, c] = *triangle;
+
+    let d1 = (p.x - b.x) * (a.y - b.y) - (a.x - b.x) * (p.y - b.y);
+    let d2 = (p.x - c.x) * (b.y - c.y) - (b.x - c.x) * (p.y - c.y);
+    let d3 = (p.x - a.x) * (c.y - a.y) - (c.x - a.x) * (p.y - a.y);
+
+    let has_neg = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_pos = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+
+    !(has_neg && has_pos)
+}
@@ -1,17 +1,18 @@
 //! Drawing, an intermediate mode drawing for some 2D primitives.
 
 use std::{cell::RefCell, collections::HashMap};
-use super::RenderPass;
+use super::{BindGroupAttachment, RenderPass, display_list::DisplayList, utils::BindGroupType};
 
 use crate::{
-    font::{Font, FontManager}, math::{Color, Point2, RectF, Vector2, Vector3, Vertex}, utils::ArcRef
+    font::{Font, FontManager, TextDecoration, TextLayout}, math::{Color, Obb2, Point2, RectF, Vector2, Vector3, Vertex}, utils::ArcRef
 };
 
 use super::{
     super::{
         GPUInner,
         texture::{
-            atlas::TextureAtlas,
+            animated::AnimatedTexture,
+            atlas::{AtlasRegion, TextureAtlas},
             Texture, 
             TextureBuilder, 
             TextureUsage, 
@@ -26,6 +27,7 @@ use super::{
 pub(crate) struct DrawingGlobalState {
     pub texture: Texture,
     pub shader: GraphicsShader,
+    pub sdf_shader: GraphicsShader,
     pub font_manager: FontManager,
     pub font_textures: HashMap<String, Texture>,
 }
@@ -43,15 +45,49 @@ impl DrawingGlobalState {
             .build()
             .ok()?;
 
+        let sdf_shader = GraphicsShaderBuilder::new(ArcRef::clone(gpu_inner))
+            .set_source(include_str!("./resources/drawing_sdf_shader.wgsl"))
+            .build()
+            .ok()?;
+
         let font_manager = FontManager::new();
 
         Some(Self {
             texture: default_texture,
             shader: default_shader,
+            sdf_shader,
             font_manager,
             font_textures: HashMap::new(),
         })
     }
+
+    /// Returns the drawing state shared by the GPU, created on first use.
+    pub fn get_or_create(gpu_inner: &ArcRef<GPUInner>) -> Option<ArcRef<Self>> {
+        if gpu_inner.borrow().drawing_state.is_none() {
+            let state = DrawingGlobalState::new(gpu_inner)?;
+            gpu_inner.borrow_mut().drawing_state = Some(ArcRef::new(state));
+        }
+
+        gpu_inner.borrow().drawing_state.as_ref().map(ArcRef::clone)
+    }
+
+    /// Returns the atlas texture of the font, created on first use.
+    pub fn font_texture(&mut self, gpu_inner: &ArcRef<GPUInner>, font: &Font) -> Option<Texture> {
+        let name = {
+            let font_inner = font.inner.borrow();
+            font_inner.info.path.clone().into_os_string().into_string().ok()?
+        };
+
+        if !self.font_textures.contains_key(&name) {
+            let texture = font
+                .create_texture_inner(gpu_inner)
+                .expect("Failed to create font texture");
+
+            self.font_textures.insert(name.clone(), texture);
+        }
+
+        self.font_textures.get(&name).cloned()
+    }
 }
 
 pub(crate) struct DrawingContextInner {
@@ -66,13 +102,20 @@ pub(crate) struct DrawingContextInner {
     texture_atlas_uv: Option<RectF>,
     shader: Option<GraphicsShader>,
     scissor: Option<RectF>,
+    clip_stack: Vec<Option<RectF>>,
     viewport: Option<RectF>,
     rotation: f32,
+    shape: Option<DrawingShapeParams>,
     current_queue: Option<DrawingQueue>,
     queue: Vec<DrawingQueue>,
 
     current_font: Option<Font>,
     current_font_texture: Option<Texture>,
+    text_decoration: TextDecoration,
+    text_highlight: Option<Color>,
+
+    display_list: Option<DisplayList>,
+    tag: Option<u64>,
 }
 
 impl DrawingContextInner {
@@ -163,6 +206,10 @@ impl DrawingContextInner {
         } else {
             self.vertices.extend_from_slice(vertices);
         }
+
+        if let (Some(display_list), Some(tag)) = (self.display_list.as_mut(), self.tag) {
+            display_list.push(tag, self.scissor, &self.vertices, &indices);
+        }
         
         self.indices.extend_from_slice(&indices);
     }
@@ -228,6 +275,11 @@ impl DrawingContextInner {
             if ref_queue.shader != self.shader {
                 push_new_queue = true;
             }
+
+            // SDF shapes carry their parameters in a uniform, so each shape gets its own queue
+            if ref_queue.shape.is_some() || self.shape.is_some() {
+                push_new_queue = true;
+            }
         } else {
             push_new_queue = true;
         }
@@ -250,6 +302,7 @@ impl DrawingContextInner {
                 shader: None,
                 scissors: self.scissor.clone(),
                 viewport: self.viewport.clone(),
+                shape: self.shape,
                 start_index: self.indices.len() as u32,
                 start_vertex: 0, // TODO: Fix this
                 count,
@@ -282,13 +335,12 @@ impl DrawingContextInner {
     }
 
     pub fn set_font(&mut self, font: &Font) {
-        let name = {
-            let font_inner = font.inner.borrow();
-            font_inner.info.path.clone().into_os_string().into_string()
-                .ok()
-        };
+        let texture = self
+            .drawing_global_state
+            .borrow_mut()
+            .font_texture(&self.pass.graphics, font);
 
-        if name.is_none() {
+        if texture.is_none() {
             #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
             {
                 crate::dbg_log!("Font path is None, cannot set font");
@@ -296,18 +348,8 @@ impl DrawingContextInner {
             return;
         }
 
-        let name = name.unwrap();
-
-        let mut state = self.drawing_global_state.borrow_mut();
-        if !state.font_textures.contains_key(&name) {
-            let texture = font.create_texture_inner(&self.pass.graphics)
-                .expect("Failed to create font texture");
-
-            state.font_textures.insert(name.to_string(), texture);
-        }
-
         self.current_font = Some(font.clone());
-        self.current_font_texture = state.font_textures.get(&name).cloned();
+        self.current_font_texture = texture;
     }
 }
 
@@ -317,6 +359,7 @@ pub(crate) struct DrawingQueue {
 
     pub scissors: Option<RectF>,
     pub viewport: Option<RectF>,
+    pub shape: Option<DrawingShapeParams>,
 
     pub start_index: u32,
     pub start_vertex: u32,
@@ -325,6 +368,17 @@ pub(crate) struct DrawingQueue {
     pub blend_states: Vec<(Option<wgpu::BlendState>, Option<wgpu::ColorWrites>)>,
 }
 
+/// Uniform parameters for the SDF shapes, must match `ShapeParams` in drawing_sdf_shader.wgsl.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct DrawingShapeParams {
+    pub size: [f32; 2],
+    pub half_extents: [f32; 2],
+    pub radius: f32,
+    pub thickness: f32,
+    pub _padding: [f32; 2],
+}
+
 /// DrawingContext is an intermediate mode for drawing 2D primitives.
 ///
 /// It provides methods to draw rectangles, lines, triangles, circles, and images with various options for colors and textures.
@@ -338,16 +392,7 @@ pub struct DrawingContext {
 
 impl DrawingContext {
     pub(crate) fn new(pass: RenderPass) -> Option<Self> {
-        if pass.graphics.borrow().drawing_state.is_none() {
-            let state = DrawingGlobalState::new(&pass.graphics)?;
-
-            let mut gpu_inner = pass.graphics.borrow_mut();
-            gpu_inner.drawing_state = Some(ArcRef::new(state));
-        }
-
-        let drawing_state = ArcRef::clone(
-            &pass.graphics.borrow().drawing_state.as_ref().unwrap()
-        );
+        let drawing_state = DrawingGlobalState::get_or_create(&pass.graphics)?;
 
         let inner = DrawingContextInner {
             pass: pass,
@@ -356,17 +401,24 @@ impl DrawingContext {
             vertices: Vec::new(),
             indices: Vec::new(),
             rotation: 0.0,
+            shape: None,
             texture: None,
             texture_uv: None,
             texture_atlas_uv: None,
             shader: None,
             scissor: None,
+            clip_stack: Vec::new(),
             viewport: None,
             current_queue: None,
             queue: Vec::new(),
             
             current_font: None,
             current_font_texture: None,
+            text_decoration: TextDecoration::empty(),
+            text_highlight: None,
+
+            display_list: None,
+            tag: None,
         };
 
         Some(DrawingContext {
@@ -394,7 +446,6 @@ impl DrawingContext {
         Some((vertices, indices))
     }
 
-    #[allow(dead_code)]
     fn construct_quad(pos: Vector2, size: Vector2) -> ([Vector2; 4], [u16; 6]) {
         let vertices = [
             Vector2::new(pos.x, pos.y),
@@ -432,111 +483,214 @@ impl DrawingContext {
         })
     }
 
+    /// Record tagged primitives into the display list, `None` to stop recording.
+    ///
+    /// The list is cleared when recording starts, only geometry drawn while a tag is set
+    /// with [`DrawingContext::set_tag`] is recorded.
+    pub fn record_display_list(&mut self, display_list: Option<&DisplayList>) {
+        let mut inner = self.inner.borrow_mut();
+        inner.display_list = display_list.map(|list| {
+            let mut list = list.clone();
+            list.clear();
+            list
+        });
+    }
+
+    /// Set the tag attached to the following draws in the display list, `None` to not record them.
+    pub fn set_tag(&mut self, tag: Option<u64>) {
+        let mut inner = self.inner.borrow_mut();
+        inner.tag = tag;
+    }
+
+    pub fn get_tag(&self) -> Option<u64> {
+        self.inner.borrow().tag
+    }
+
+    /// Set the decorations drawn by the text functions, eg. `TextDecoration::UNDERLINE`.
+    ///
+    /// The line position and thickness are taken from the current font.
+    pub fn set_text_decoration(&mut self, decoration: TextDecoration) {
+        let mut inner = self.inner.borrow_mut();
+        inner.text_decoration = decoration;
+    }
+
+    pub fn get_text_decoration(&self) -> TextDecoration {
+        self.inner.borrow().text_decoration
+    }
+
+    /// Set the background color drawn behind each line of text, `None` to disable it.
+    pub fn set_text_highlight(&mut self, color: Option<Color>) {
+        let mut inner = self.inner.borrow_mut();
+        inner.text_highlight = color;
+    }
+
+    pub fn get_text_highlight(&self) -> Option<Color> {
+        self.inner.borrow().text_highlight
+    }
+
     /// Draw text with a specified position, color, and font.
     pub fn draw_text(&mut self, text: &str, pos: Vector2, color: Color) {
+        self.draw_text_layout(text, pos, color, &TextLayout::new());
+    }
+
+    /// Draw text using the given layout, eg. to truncate it to a maximum width.
+    ///
+    /// Returns true if the text was truncated.
+    pub fn draw_text_layout(
+        &mut self,
+        text: &str,
+        pos: Vector2,
+        color: Color,
+        layout: &TextLayout,
+    ) -> bool {
         let mut inner = self.inner.borrow_mut();
         if inner.current_font.is_none() {
             inner.load_font("Arial", None, 16.0);
         }
 
+        let font = inner.current_font.clone().unwrap();
+        let texture_size = font.texture_size();
+        let result = inner
+            .drawing_global_state
+            .borrow_mut()
+            .font_manager
+            .layout_text(&font, text, layout);
+
+        if let Some(highlight) = inner.text_highlight {
+            let rects = result
+                .lines
+                .iter()
+                .map(|line| (pos + line.position, Vector2::new(line.width, line.height)))
+                .collect::<Vec<_>>();
+
+            Self::push_text_rects(
+                &mut inner,
+                &mut self.vertex_cache,
+                &mut self.index_cache,
+                &rects,
+                highlight,
+            );
+        }
+
         vec_clear(&mut self.vertex_cache);
         vec_clear(&mut self.index_cache);
 
-        let font = inner.current_font.as_ref().unwrap();
-        let texture_size = font.texture_size();
-        let line_height = font.line_height();
-        let ascender = font.ascender();
-        let space_width = font.space_width();
-
-        // Calculate the minimum Y offset for the text
-        let mut pen_y = 0.0;
-        let mut min_y = f32::MAX;
-        for c in text.chars() {
-            let codepoint = c as u32;
-            if codepoint == 0 {
+        for glyph in &result.glyphs {
+            let Ok(atlas_glyph) = font.get_glyph(glyph.codepoint) else {
                 continue;
-            }
+            };
 
-            if codepoint == '\n' as u32 {
-                pen_y += line_height;
-                continue;
-            }
+            let x0 = pos.x + glyph.position.x;
+            let y0 = pos.y + glyph.position.y;
+            let x1 = x0 + glyph.size.x;
+            let y1 = y0 + glyph.size.y;
 
-            if let Ok(glyph) = font.get_glyph(codepoint) {
-                min_y = f32::min(min_y, pen_y + ascender - (glyph.bearing_y + glyph.height));
-            }
+            let uv_x0 = atlas_glyph.atlas_start_offset.x / texture_size.x as f32;
+            let uv_y0 = atlas_glyph.atlas_start_offset.y / texture_size.y as f32;
+            let uv_x1 = (atlas_glyph.atlas_start_offset.x + atlas_glyph.width) / texture_size.x as f32;
+            let uv_y1 = (atlas_glyph.atlas_start_offset.y + atlas_glyph.height) / texture_size.y as f32;
+
+            let color = Color {
+                a: color.a * glyph.alpha,
+                ..color
+            };
+
+            let vertices = [
+                Vertex::new(Vector3::new(x0, y0, 0.0), color, Vector2::new(uv_x0, uv_y0)),
+                Vertex::new(Vector3::new(x1, y0, 0.0), color, Vector2::new(uv_x1, uv_y0)),
+                Vertex::new(Vector3::new(x1, y1, 0.0), color, Vector2::new(uv_x1, uv_y1)),
+                Vertex::new(Vector3::new(x0, y1, 0.0), color, Vector2::new(uv_x0, uv_y1)),
+            ];
+
+            let base_index = self.vertex_cache.len() as u16;
+            let indices = [
+                base_index,
+                base_index + 1,
+                base_index + 2,
+                base_index,
+                base_index + 2,
+                base_index + 3,
+            ];
+
+            self.vertex_cache.extend_from_slice(&vertices);
+            self.index_cache.extend_from_slice(&indices);
         }
 
-        let mut pen = pos;
-        for c in text.chars() {
-            let codepoint = c as u32;
-            if codepoint == 0 {
-                continue;
-            }
+        if !self.index_cache.is_empty() {
+            let all_vertices = &self.vertex_cache;
+            let all_indices = &self.index_cache;
 
-            if codepoint == '\n' as u32 {
-                pen.x = pos.x;
-                pen.y += line_height;
-                continue;
-            }
+            let current_texture = inner.texture.clone();
+            let font_texture = inner.current_font_texture.clone();
+            inner.texture = Some((
+                font_texture.unwrap(),
+                TextureSampler::DEFAULT,
+            ));
+
+            inner.push_geometry(all_vertices, all_indices, true);
+
+            inner.texture = current_texture;
+        }
 
-            if codepoint == ' ' as u32 {
-                pen.x += space_width;
+        let decorations = [
+            (TextDecoration::UNDERLINE, font.underline_metrics()),
+            (TextDecoration::STRIKETHROUGH, font.strikeout_metrics()),
+        ];
+
+        let mut rects = Vec::new();
+        for (decoration, metrics) in decorations {
+            if !inner.text_decoration.contains(decoration) {
                 continue;
             }
 
-            if let Ok(glyph) = font.get_glyph(codepoint) {
-                let x0 = pen.x + glyph.bearing_x;
-                let y0 = pen.y + ascender - (glyph.bearing_y + glyph.height) - min_y;
-                let x1 = x0 + glyph.width;
-                let y1 = y0 + glyph.height;
-
-                let uv_x0 = glyph.atlas_start_offset.x as f32 / texture_size.x as f32;
-                let uv_y0 = glyph.atlas_start_offset.y as f32 / texture_size.y as f32;
-                let uv_x1 = (glyph.atlas_start_offset.x + glyph.width) as f32 / texture_size.x as f32;
-                let uv_y1 = (glyph.atlas_start_offset.y + glyph.height) as f32 / texture_size.y as f32;
-
-                let vertices = [
-                    Vertex::new(Vector3::new(x0, y0, 0.0), color, Vector2::new(uv_x0, uv_y0)),
-                    Vertex::new(Vector3::new(x1, y0, 0.0), color, Vector2::new(uv_x1, uv_y0)),
-                    Vertex::new(Vector3::new(x1, y1, 0.0), color, Vector2::new(uv_x1, uv_y1)),
-                    Vertex::new(Vector3::new(x0, y1, 0.0), color, Vector2::new(uv_x0, uv_y1)),
-                ];
-
-                let base_index = self.vertex_cache.len() as u16;
-                let indices = [
-                    base_index + 0,
-                    base_index + 1,
-                    base_index + 2,
-                    base_index + 0,
-                    base_index + 2,
-                    base_index + 3,
-                ];
-
-                self.vertex_cache.extend_from_slice(&vertices);
-                self.index_cache.extend_from_slice(&indices);
-
-                pen.x += glyph.advance_x;
+            for line in result.lines.iter().filter(|line| line.width > 0.0) {
+                rects.push((
+                    pos + Vector2::new(line.position.x, line.baseline + metrics.offset),
+                    Vector2::new(line.width, metrics.thickness),
+                ));
             }
         }
 
-        if self.index_cache.is_empty() {
-            return;
+        if !rects.is_empty() {
+            Self::push_text_rects(
+                &mut inner,
+                &mut self.vertex_cache,
+                &mut self.index_cache,
+                &rects,
+                color,
+            );
         }
 
-        let all_vertices = &self.vertex_cache;
-        let all_indices = &self.index_cache;
+        result.truncated
+    }
 
-        let current_texture = inner.texture.clone();
-        let font_texture = inner.current_font_texture.clone();
-        inner.texture = Some((
-            font_texture.unwrap(),
-            TextureSampler::DEFAULT,
-        ));
+    /// Push untextured rectangles used for text highlights and decorations.
+    fn push_text_rects(
+        inner: &mut DrawingContextInner,
+        vertices: &mut Vec<Vertex>,
+        indices: &mut Vec<u16>,
+        rects: &[(Vector2, Vector2)],
+        color: Color,
+    ) {
+        vec_clear(vertices);
+        vec_clear(indices);
 
-        inner.push_geometry(&all_vertices, &all_indices, true);
+        for (pos, size) in rects {
+            if size.x <= 0.0 || size.y <= 0.0 {
+                continue;
+            }
 
-        inner.texture = current_texture;
+            let (quad, quad_indices) = Self::construct_quad(*pos, *size);
+            let base_index = vertices.len() as u16;
+
+            vertices.extend(
+                quad.iter()
+                    .map(|v| Vertex::new(Vector3::new(v.x, v.y, 0.0), color, Vector2::ZERO)),
+            );
+            indices.extend(quad_indices.iter().map(|i| i + base_index));
+        }
+
+        inner.push_geometry(vertices, indices, false);
     }
 
     /// Draw hollow rectangle with a specified position, size, thickness, and color.
@@ -640,6 +794,23 @@ impl DrawingContext {
             .push_geometry(&vertices, &indices, false);
     }
 
+    /// Draw rectangle filled rotated by `angle` degrees around its center.
+    ///
+    /// The rectangle covers the same area as [Obb2::new] with the same values, which can be used
+    /// to hit-test it.
+    pub fn rect_rotated(&mut self, center: Vector2, size: Vector2, angle: f32, color: Color) {
+        let corners = Obb2::new(center, size, angle).corners();
+
+        let vertices = corners.map(|corner| {
+            Vertex::new(Vector3::new(corner.x, corner.y, 0.0), color, Vector2::ZERO)
+        });
+
+        let indices = [0, 1, 2, 0, 2, 3];
+
+        self.inner.borrow_mut()
+            .push_geometry(&vertices, &indices, false);
+    }
+
     /// Draw rectangle filled with specified colors for each corner.
     pub fn draw_rect_filled_colors(
         &mut self,
@@ -899,6 +1070,66 @@ impl DrawingContext {
         inner.push_geometry(&vertices, &indices, true);
     }
 
+    /// Draws a named region of a texture atlas, stretched to `size`.
+    ///
+    /// Regions with [NineSlice](crate::gpu::texture::atlas::NineSlice) insets keep their
+    /// corners at the original size, and are shrunk evenly when `size` is smaller than the
+    /// borders. The current texture and UV are left unchanged.
+    pub fn draw_atlas_region(
+        &mut self,
+        atlas: &TextureAtlas,
+        name: &str,
+        pos: Vector2,
+        size: Vector2,
+        color: Color,
+    ) {
+        let Some(region) = atlas.region(name) else {
+            #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+            panic!("Texture atlas does not contain the specified id: {}", name);
+
+            #[cfg(not(any(debug_assertions, feature = "enable-release-validation")))]
+            return;
+        };
+
+        self.draw_region(atlas, &region, pos, size, color);
+    }
+
+    /// Draws the current frame of an animated texture, stretched to `size`. The animation is
+    /// advanced with [AnimatedTexture::update].
+    pub fn draw_animated_texture(
+        &mut self,
+        animation: &AnimatedTexture,
+        pos: Vector2,
+        size: Vector2,
+        color: Color,
+    ) {
+        self.draw_region(animation.atlas(), &animation.frame(), pos, size, color);
+    }
+
+    /// Draws a region returned by [TextureAtlas::insert], stretched to `size`.
+    ///
+    /// Behaves like [DrawingContext::draw_atlas_region], the region may be on any page of the
+    /// atlas.
+    pub fn draw_region(
+        &mut self,
+        atlas: &TextureAtlas,
+        region: &AtlasRegion,
+        pos: Vector2,
+        size: Vector2,
+        color: Color,
+    ) {
+        let (vertices, indices) = atlas_region_geometry(region, pos, size, color);
+        let texture = atlas.region_texture(region);
+
+        let mut inner = self.inner.borrow_mut();
+        let previous_texture = inner
+            .texture
+            .replace((texture.clone(), texture.sampler()));
+
+        inner.push_geometry(&vertices, &indices, true);
+        inner.texture = previous_texture;
+    }
+
     pub fn draw_rect_image_colors(
         &mut self,
         pos: Vector2,
@@ -1016,6 +1247,86 @@ impl DrawingContext {
         inner.push_geometry(&vertices, &indices, true);
     }
 
+    /// Draw rounded rectangle filled with a specified position, size, corner radius, and color.
+    ///
+    /// Unlike the tessellated primitives, the edges are computed by a signed distance field
+    /// in the fragment shader, so it stays smooth and anti-aliased at any scale.
+    pub fn rounded_rectangle_filled(&mut self, pos: Vector2, size: Vector2, radius: f32, color: Color) {
+        self.push_sdf_shape(pos, size, radius, 0.0, color);
+    }
+
+    /// Draw hollow rounded rectangle with a specified position, size, corner radius, thickness, and color.
+    ///
+    /// The outline is drawn inward, so the shape never exceeds the given bounds.
+    pub fn rounded_rectangle_outline(
+        &mut self,
+        pos: Vector2,
+        size: Vector2,
+        radius: f32,
+        thickness: f32,
+        color: Color,
+    ) {
+        if thickness <= 0.0 {
+            return;
+        }
+
+        self.push_sdf_shape(pos, size, radius, thickness, color);
+    }
+
+    /// Draw anti-aliased circle filled with a specified center, radius, and color.
+    pub fn circle_filled(&mut self, center: Vector2, radius: f32, color: Color) {
+        let size = Vector2::new(radius * 2.0, radius * 2.0);
+        self.push_sdf_shape(center - Vector2::new(radius, radius), size, radius, 0.0, color);
+    }
+
+    /// Draw anti-aliased hollow circle with a specified center, radius, thickness, and color.
+    pub fn circle_outline(&mut self, center: Vector2, radius: f32, thickness: f32, color: Color) {
+        if thickness <= 0.0 {
+            return;
+        }
+
+        let size = Vector2::new(radius * 2.0, radius * 2.0);
+        self.push_sdf_shape(center - Vector2::new(radius, radius), size, radius, thickness, color);
+    }
+
+    fn push_sdf_shape(&mut self, pos: Vector2, size: Vector2, radius: f32, thickness: f32, color: Color) {
+        if size.x <= 0.0 || size.y <= 0.0 {
+            return;
+        }
+
+        // Pad the quad by a pixel on each side so the anti-aliased edge isn't clipped.
+        const PADDING: f32 = 1.0;
+
+        let half_extents = size * 0.5;
+        let radius = radius.clamp(0.0, f32::min(half_extents.x, half_extents.y));
+        let thickness = thickness.min(f32::min(half_extents.x, half_extents.y));
+
+        let pos = pos - Vector2::new(PADDING, PADDING);
+        let size = size + Vector2::new(PADDING * 2.0, PADDING * 2.0);
+
+        let vertices = [
+            Vertex::new(Vector3::new(pos.x, pos.y, 0.0), color, Vector2::new(0.0, 0.0)),
+            Vertex::new(Vector3::new(pos.x + size.x, pos.y, 0.0), color, Vector2::new(1.0, 0.0)),
+            Vertex::new(Vector3::new(pos.x + size.x, pos.y + size.y, 0.0), color, Vector2::new(1.0, 1.0)),
+            Vertex::new(Vector3::new(pos.x, pos.y + size.y, 0.0), color, Vector2::new(0.0, 1.0)),
+        ];
+
+        let indices = [0, 1, 2, 0, 2, 3];
+
+        let mut inner = self.inner.borrow_mut();
+        inner.shape = Some(DrawingShapeParams {
+            size: [size.x, size.y],
+            half_extents: [half_extents.x, half_extents.y],
+            radius,
+            thickness,
+            _padding: [0.0; 2],
+        });
+
+        inner.push_geometry(&vertices, &indices, false);
+
+        inner.shape = None;
+    }
+
     pub fn set_rotation(&mut self, rotation: f32) {
         let mut inner = self.inner.borrow_mut();
         inner.rotation = rotation;
@@ -1030,6 +1341,36 @@ impl DrawingContext {
         inner.scissor = Some(scissor);
     }
 
+    /// Push a clip rectangle, intersected with the current clip, to be applied to following draws.
+    ///
+    /// Must be paired with [`DrawingContext::pop_clip`], which restores the previous clip.
+    pub fn push_clip(&mut self, clip: RectF) {
+        let mut inner = self.inner.borrow_mut();
+
+        let scissor = match inner.scissor {
+            Some(current) => current.intersect(&clip),
+            None => clip,
+        };
+
+        let previous = inner.scissor.replace(scissor);
+        inner.clip_stack.push(previous);
+    }
+
+    /// Pop the last clip rectangle pushed with [`DrawingContext::push_clip`].
+    pub fn pop_clip(&mut self) {
+        let mut inner = self.inner.borrow_mut();
+
+        match inner.clip_stack.pop() {
+            Some(previous) => {
+                inner.scissor = previous;
+            }
+            None => {
+                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+                panic!("pop_clip called without a matching push_clip");
+            }
+        }
+    }
+
     pub fn set_viewport(&mut self, viewport: RectF) {
         let mut inner = self.inner.borrow_mut();
         inner.viewport = Some(viewport);
@@ -1055,8 +1396,8 @@ impl DrawingContext {
                     panic!("Texture must be created with TextureUsage::Sampler");
                 }
 
-                let default_sampler = TextureSampler::DEFAULT;
-                let sampler = sampler.unwrap_or(default_sampler);
+                // Fall back to the texture's own sampler
+                let sampler = sampler.unwrap_or_else(|| texture.sampler());
 
                 inner.texture = Some((texture.clone(), sampler));
             }
@@ -1089,24 +1430,15 @@ impl DrawingContext {
     ) {
         match atlas {
             Some((atlas, id)) => {
-                let tex_coord = atlas.get_id(id);
+                let region = atlas.region(id);
 
                 #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
-                if tex_coord.is_none() {
+                if region.is_none() {
                     panic!("Texture atlas does not contain the specified id: {}", id);
                 }
 
-                let (tex_coord, _) = tex_coord.unwrap();
-                let texture = atlas.get_texture();
-
-                let mut inner = self.inner.borrow_mut();
-
-                let default_sampler = TextureSampler::DEFAULT;
-                inner.texture_atlas_uv = Some(tex_coord);
-                inner.texture = Some((
-                    texture.clone(),
-                    default_sampler,
-                ));
+                let region = region.unwrap();
+                self.set_texture_region(Some((atlas, &region)));
             }
             None => {
                 self.inner.borrow_mut().texture_atlas_uv = None;
@@ -1115,6 +1447,23 @@ impl DrawingContext {
         };
     }
 
+    /// Sets the texture to the page holding an atlas region, and the UV to the region.
+    pub fn set_texture_region(&mut self, region: Option<(&TextureAtlas, &AtlasRegion)>) {
+        let mut inner = self.inner.borrow_mut();
+
+        match region {
+            Some((atlas, region)) => {
+                let texture = atlas.region_texture(region);
+
+                inner.texture_atlas_uv = Some(region.uv);
+                inner.texture = Some((texture.clone(), texture.sampler()));
+            }
+            None => {
+                inner.texture_atlas_uv = None;
+            }
+        }
+    }
+
     pub fn set_shader(&mut self, shader: Option<&GraphicsShader>) {
         let mut inner = self.inner.borrow_mut();
 
@@ -1179,6 +1528,16 @@ impl DrawingContext {
             inner.queue.push(queue);
         }
 
+        let dpi_scale = {
+            let graphics_inner = inner.pass.graphics.borrow();
+
+            if graphics_inner.dpi_scaling {
+                graphics_inner.scale_factor() as f32
+            } else {
+                1.0
+            }
+        };
+
         let mut queues = inner.queue.drain(..).collect::<Vec<_>>();
         let mut vertices = inner.vertices.drain(..).collect::<Vec<_>>();
         let indices = inner.indices.drain(..).collect::<Vec<_>>();
@@ -1196,6 +1555,9 @@ impl DrawingContext {
                 )
             };
 
+            // Vertices are in logical coordinates with DPI scaling, the surface is in pixels
+            let swapchain_size = swapchain_size / dpi_scale;
+
             for vertex in vertices.iter_mut() {
                 vertex.position.x = vertex.position.x / swapchain_size.x * 2.0 - 1.0;
                 vertex.position.y = 1.0 - (vertex.position.y / swapchain_size.y * 2.0);
@@ -1211,6 +1573,10 @@ impl DrawingContext {
                     queue.texture = Some((default_texture, sampler));
                 }
 
+                if queue.shader.is_none() && queue.shape.is_some() {
+                    queue.shader = Some(drawing.sdf_shader.clone());
+                }
+
                 if queue.shader.is_none() {
                     let default_shader = drawing
                         .shader
@@ -1252,18 +1618,49 @@ impl DrawingContext {
                 }
             }
 
-            pass.set_scissor(queue.scissors);
-            pass.set_viewport(queue.viewport, 0.0, 1.0);
+            let scale_rect = |rect: RectF| {
+                RectF::new(
+                    rect.x * dpi_scale,
+                    rect.y * dpi_scale,
+                    rect.w * dpi_scale,
+                    rect.h * dpi_scale,
+                )
+            };
+
+            pass.set_scissor(queue.scissors.map(scale_rect));
+            pass.set_viewport(queue.viewport.map(scale_rect), 0.0, 1.0);
 
             let (texture, sampler) = queue.texture.as_ref().unwrap();
 
             pass.set_shader(queue.shader.as_ref());
             pass
-                .set_gpu_buffer_wgpu(Some(vertex_buffer.clone()), Some(index_buffer.clone()));
+                .set_gpu_buffer_wgpu(Some(vertex_buffer.clone().into()), Some(index_buffer.clone().into()));
 
             pass.set_attachment_texture(0, 0, Some(&texture));
             pass.set_attachment_sampler(0, 1, Some(sampler));
 
+            match queue.shape {
+                Some(shape) => {
+                    let buffer = pass.graphics.borrow_mut().create_staging_buffer(
+                        bytemuck::bytes_of(&shape),
+                        wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    );
+
+                    pass.insert_or_replace_attachment(
+                        0,
+                        2,
+                        BindGroupAttachment {
+                            group: 0,
+                            binding: 2,
+                            attachment: BindGroupType::Uniform(buffer.into()),
+                        },
+                    );
+                }
+                None => {
+                    pass.remove_attachment(0, 2);
+                }
+            }
+
             pass
                 .draw_indexed(queue.start_index..(queue.start_index + queue.count), queue.start_vertex as i32, 1);
         }
@@ -1322,3 +1719,73 @@ fn vec_clear<T>(vec: &mut Vec<T>) {
         vec.set_len(0);
     }
 }
+
+/// Builds the quad of an atlas region, or its 9 patches when it has nine-slice insets.
+fn atlas_region_geometry(
+    region: &AtlasRegion,
+    pos: Vector2,
+    size: Vector2,
+    color: Color,
+) -> (Vec<Vertex>, Vec<u16>) {
+    let uv = region.uv;
+
+    let Some(nine_slice) = region.nine_slice else {
+        let vertices = vec![
+            Vertex::new(Vector3::new(pos.x, pos.y, 0.0), color, Vector2::new(uv.x, uv.y)),
+            Vertex::new(Vector3::new(pos.x + size.x, pos.y, 0.0), color, Vector2::new(uv.w, uv.y)),
+            Vertex::new(Vector3::new(pos.x + size.x, pos.y + size.y, 0.0), color, Vector2::new(uv.w, uv.h)),
+            Vertex::new(Vector3::new(pos.x, pos.y + size.y, 0.0), color, Vector2::new(uv.x, uv.h)),
+        ];
+
+        return (vertices, vec![0, 1, 2, 0, 2, 3]);
+    };
+
+    let region_w = region.size.x as f32;
+    let region_h = region.size.y as f32;
+
+    let (left, right) = (nine_slice.left as f32, nine_slice.right as f32);
+    let (top, bottom) = (nine_slice.top as f32, nine_slice.bottom as f32);
+
+    // Shrink the borders evenly when the destination is smaller than them
+    let scale_x = if left + right > size.x && left + right > 0.0 { size.x / (left + right) } else { 1.0 };
+    let scale_y = if top + bottom > size.y && top + bottom > 0.0 { size.y / (top + bottom) } else { 1.0 };
+
+    let xs = [pos.x, pos.x + left * scale_x, pos.x + size.x - right * scale_x, pos.x + size.x];
+    let ys = [pos.y, pos.y + top * scale_y, pos.y + size.y - bottom * scale_y, pos.y + size.y];
+
+    let us = [
+        uv.x,
+        uv.x + (uv.w - uv.x) * (left / region_w),
+        uv.w - (uv.w - uv.x) * (right / region_w),
+        uv.w,
+    ];
+    let vs = [
+        uv.y,
+        uv.y + (uv.h - uv.y) * (top / region_h),
+        uv.h - (uv.h - uv.y) * (bottom / region_h),
+        uv.h,
+    ];
+
+    let mut vertices = Vec::with_capacity(16);
+    for row in 0..4 {
+        for col in 0..4 {
+            vertices.push(Vertex::new(
+                Vector3::new(xs[col], ys[row], 0.0),
+                color,
+                Vector2::new(us[col], vs[row]),
+            ));
+        }
+    }
+
+    let mut indices = Vec::with_capacity(54);
+    for row in 0..3u16 {
+        for col in 0..3u16 {
+            let tl = row * 4 + col;
+            let (tr, bl, br) = (tl + 1, tl + 4, tl + 5);
+
+            indices.extend_from_slice(&[tl, tr, br, tl, br, bl]);
+        }
+    }
+
+    (vertices, indices)
+}
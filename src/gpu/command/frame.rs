@@ -0,0 +1,561 @@
+//! Frame description, a retained mode alternative to the [DrawingContext](super::drawing::DrawingContext).
+//!
+//! The frame is described as data, a list of layers holding rectangles, sprites, text and
+//! meshes, and drawn with a [FrameRenderer]. The renderer keeps the geometry of every layer
+//! on the GPU, and only re-encodes the layers which changed since the previous frame.
+//!
+//! # Example
+//! ```ignore
+//! let mut renderer = FrameRenderer::new();
+//! let mut frame = FrameDesc::new();
+//!
+//! let mut background = FrameLayer::new();
+//! background.push(FrameItem::Rect {
+//!     pos: Vector2::new(0.0, 0.0),
+//!     size: Vector2::new(800.0, 600.0),
+//!     color: Color::new(0.1, 0.1, 0.1, 1.0),
+//! });
+//! frame.push_layer(background);
+//!
+//! // In the render loop, only re-render when something changed
+//! if renderer.is_changed(&frame) {
+//!     let mut pass = cmd.begin_renderpass();
+//!     let stats = renderer.draw(&mut pass, &frame);
+//! }
+//! ```
+
+use std::ops::Range;
+
+use crate::{
+    font::{Font, TextLayout},
+    math::{Color, Point2, RectF, Vector2, Vector3, Vertex},
+};
+
+use super::{
+    super::texture::{Texture, TextureSampler},
+    drawing::DrawingGlobalState,
+    renderpass::RenderPass,
+};
+
+/// Translation, rotation and scale applied to a [FrameItem].
+///
+/// Items are scaled, then rotated around their local origin, then translated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameTransform {
+    pub translation: Vector2,
+    /// Rotation in degrees, clockwise on screen.
+    pub rotation: f32,
+    pub scale: Vector2,
+}
+
+impl FrameTransform {
+    pub const IDENTITY: Self = Self {
+        translation: Vector2::ZERO,
+        rotation: 0.0,
+        scale: Vector2::ONE,
+    };
+
+    pub fn new(translation: Vector2) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vector2) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    fn apply(&self, point: Vector2) -> Vector2 {
+        let x = point.x * self.scale.x;
+        let y = point.y * self.scale.y;
+
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+
+        Vector2::new(
+            x * cos - y * sin + self.translation.x,
+            x * sin + y * cos + self.translation.y,
+        )
+    }
+}
+
+impl Default for FrameTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// A primitive of a [FrameLayer], positions are in pixels before the item transform.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrameItem {
+    Rect {
+        pos: Vector2,
+        size: Vector2,
+        color: Color,
+    },
+    /// A textured rectangle, `uv` defaults to the whole texture.
+    Sprite {
+        texture: Texture,
+        uv: Option<RectF>,
+        pos: Vector2,
+        size: Vector2,
+        color: Color,
+    },
+    Text {
+        font: Font,
+        text: String,
+        pos: Vector2,
+        color: Color,
+    },
+    /// Triangles with positions in pixels, drawn with the default texture when `texture` is [None].
+    Mesh {
+        texture: Option<Texture>,
+        vertices: Vec<Vertex>,
+        indices: Vec<u16>,
+    },
+}
+
+/// A list of items drawn in order, and re-encoded as a whole when any of them changes.
+///
+/// Splitting static and animated content in separate layers keeps the static ones from being
+/// re-encoded every frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameLayer {
+    items: Vec<(FrameItem, FrameTransform)>,
+    clip: Option<RectF>,
+}
+
+impl FrameLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clips the layer to the rectangle, in pixels.
+    pub fn set_clip(&mut self, clip: Option<RectF>) {
+        self.clip = clip;
+    }
+
+    pub fn get_clip(&self) -> Option<RectF> {
+        self.clip
+    }
+
+    pub fn push(&mut self, item: FrameItem) {
+        self.items.push((item, FrameTransform::IDENTITY));
+    }
+
+    pub fn push_transformed(&mut self, item: FrameItem, transform: FrameTransform) {
+        self.items.push((item, transform));
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+/// Description of a frame, the layers are drawn in order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameDesc {
+    layers: Vec<FrameLayer>,
+}
+
+impl FrameDesc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_layer(&mut self, layer: FrameLayer) {
+        self.layers.push(layer);
+    }
+
+    pub fn layer(&self, index: usize) -> Option<&FrameLayer> {
+        self.layers.get(index)
+    }
+
+    pub fn layer_mut(&mut self, index: usize) -> Option<&mut FrameLayer> {
+        self.layers.get_mut(index)
+    }
+
+    pub fn layers(&self) -> &[FrameLayer] {
+        &self.layers
+    }
+
+    pub fn clear(&mut self) {
+        self.layers.clear();
+    }
+}
+
+/// Work done by [FrameRenderer::draw].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// Layers whose geometry was rebuilt and uploaded.
+    pub encoded_layers: u32,
+    /// Layers drawn from the geometry kept from a previous frame.
+    pub reused_layers: u32,
+    pub draw_calls: u32,
+}
+
+/// Draws [FrameDesc]s, keeping the encoded layers between frames.
+///
+/// A renderer should draw to the same target every frame, a target of a different size
+/// re-encodes every layer.
+#[derive(Clone, Debug, Default)]
+pub struct FrameRenderer {
+    layers: Vec<EncodedLayer>,
+    surface_size: Option<Point2>,
+}
+
+#[derive(Clone, Debug)]
+struct EncodedLayer {
+    desc: FrameLayer,
+    buffers: Option<(wgpu::Buffer, wgpu::Buffer)>,
+    batches: Vec<FrameBatch>,
+}
+
+#[derive(Clone)]
+struct FrameBatch {
+    texture: Option<(Texture, TextureSampler)>,
+    indices: Range<u32>,
+    base_vertex: i32,
+}
+
+impl std::fmt::Debug for FrameBatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameBatch")
+            .field(
+                "texture",
+                &self.texture.as_ref().map(|(texture, _)| texture),
+            )
+            .field("indices", &self.indices)
+            .field("base_vertex", &self.base_vertex)
+            .finish()
+    }
+}
+
+impl FrameRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if `frame` differs from the last frame drawn.
+    ///
+    /// When the target keeps its contents between frames, static frames don't need to be
+    /// rendered at all.
+    pub fn is_changed(&self, frame: &FrameDesc) -> bool {
+        self.layers.len() != frame.layers.len()
+            || self
+                .layers
+                .iter()
+                .zip(&frame.layers)
+                .any(|(encoded, layer)| encoded.desc != *layer)
+    }
+
+    /// Drops the encoded layers, the next frame is encoded from scratch.
+    pub fn clear(&mut self) {
+        self.layers.clear();
+        self.surface_size = None;
+    }
+
+    /// Draws the frame to the pass, re-encoding only the layers which changed.
+    pub fn draw(&mut self, pass: &mut RenderPass, frame: &FrameDesc) -> FrameStats {
+        let mut stats = FrameStats::default();
+
+        let Some(drawing) = DrawingGlobalState::get_or_create(&pass.graphics) else {
+            crate::error_log!("FrameRenderer::draw: Failed to create the drawing state");
+            return stats;
+        };
+
+        let surface_size = pass.surface_size();
+        if self.surface_size != Some(surface_size) {
+            self.layers.clear();
+            self.surface_size = Some(surface_size);
+        }
+
+        self.layers.truncate(frame.layers.len());
+
+        for (index, layer) in frame.layers.iter().enumerate() {
+            if self
+                .layers
+                .get(index)
+                .is_some_and(|encoded| encoded.desc == *layer)
+            {
+                stats.reused_layers += 1;
+                continue;
+            }
+
+            let encoded = Self::encode_layer(pass, &drawing, layer, surface_size);
+            match self.layers.get_mut(index) {
+                Some(old) => *old = encoded,
+                None => self.layers.push(encoded),
+            }
+
+            stats.encoded_layers += 1;
+        }
+
+        let drawing = drawing.borrow();
+
+        for layer in &self.layers {
+            let Some((vertex_buffer, index_buffer)) = &layer.buffers else {
+                continue;
+            };
+
+            pass.set_scissor(layer.desc.clip);
+            pass.set_viewport(None, 0.0, 1.0);
+
+            for batch in &layer.batches {
+                let (texture, sampler) = match &batch.texture {
+                    Some((texture, sampler)) => (texture, *sampler),
+                    None => (&drawing.texture, TextureSampler::DEFAULT),
+                };
+
+                pass.set_shader(Some(&drawing.shader));
+                pass.set_gpu_buffer_wgpu(Some(vertex_buffer.clone().into()), Some(index_buffer.clone().into()));
+                pass.set_attachment_texture(0, 0, Some(texture));
+                pass.set_attachment_sampler(0, 1, Some(&sampler));
+                pass.draw_indexed(batch.indices.clone(), batch.base_vertex, 1);
+
+                stats.draw_calls += 1;
+            }
+        }
+
+        stats
+    }
+
+    fn encode_layer(
+        pass: &RenderPass,
+        drawing: &crate::utils::ArcRef<DrawingGlobalState>,
+        layer: &FrameLayer,
+        surface_size: Point2,
+    ) -> EncodedLayer {
+        let mut encoder = LayerEncoder::default();
+
+        for (item, transform) in &layer.items {
+            match item {
+                FrameItem::Rect { pos, size, color } => {
+                    let (vertices, indices) =
+                        quad(*pos, *size, *color, RectF::new(0.0, 0.0, 1.0, 1.0));
+                    encoder.push(None, &vertices, &indices, transform);
+                }
+                FrameItem::Sprite {
+                    texture,
+                    uv,
+                    pos,
+                    size,
+                    color,
+                } => {
+                    let uv = uv.unwrap_or(RectF::new(0.0, 0.0, 1.0, 1.0));
+                    let (vertices, indices) = quad(*pos, *size, *color, uv);
+                    encoder.push(
+                        Some((texture.clone(), texture.sampler())),
+                        &vertices,
+                        &indices,
+                        transform,
+                    );
+                }
+                FrameItem::Text {
+                    font,
+                    text,
+                    pos,
+                    color,
+                } => {
+                    let Some(font_texture) =
+                        drawing.borrow_mut().font_texture(&pass.graphics, font)
+                    else {
+                        continue;
+                    };
+
+                    let (vertices, indices) = text_geometry(font, text, *pos, *color);
+                    encoder.push(
+                        Some((font_texture, TextureSampler::DEFAULT)),
+                        &vertices,
+                        &indices,
+                        transform,
+                    );
+                }
+                FrameItem::Mesh {
+                    texture,
+                    vertices,
+                    indices,
+                } => {
+                    let texture = texture.as_ref().map(|t| (t.clone(), t.sampler()));
+                    encoder.push(texture, vertices, indices, transform);
+                }
+            }
+        }
+
+        encoder.finish(pass, layer.clone(), surface_size)
+    }
+}
+
+#[derive(Default)]
+struct LayerEncoder {
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
+    batches: Vec<FrameBatch>,
+}
+
+impl LayerEncoder {
+    fn push(
+        &mut self,
+        texture: Option<(Texture, TextureSampler)>,
+        vertices: &[Vertex],
+        indices: &[u16],
+        transform: &FrameTransform,
+    ) {
+        if vertices.is_empty() || indices.is_empty() {
+            return;
+        }
+
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        if vertices.len() > u16::MAX as usize {
+            panic!("FrameItem has more than {} vertices", u16::MAX);
+        }
+
+        // Indices are relative to the batch base vertex, so a batch holds up to 65536 vertices
+        let same_batch = self.batches.last().is_some_and(|batch| {
+            batch.texture == texture
+                && self.vertices.len() - batch.base_vertex as usize + vertices.len()
+                    <= u16::MAX as usize + 1
+        });
+
+        if !same_batch {
+            let start = self.indices.len() as u32;
+            self.batches.push(FrameBatch {
+                texture,
+                indices: start..start,
+                base_vertex: self.vertices.len() as i32,
+            });
+        }
+
+        let batch = self.batches.last_mut().unwrap();
+        let base_index = (self.vertices.len() - batch.base_vertex as usize) as u16;
+
+        self.vertices.extend(vertices.iter().map(|vertex| {
+            let position = transform.apply(Vector2::new(vertex.position.x, vertex.position.y));
+
+            Vertex {
+                position: Vector3::new(position.x, position.y, 0.0),
+                ..*vertex
+            }
+        }));
+        self.indices
+            .extend(indices.iter().map(|index| index + base_index));
+
+        batch.indices.end = self.indices.len() as u32;
+    }
+
+    fn finish(mut self, pass: &RenderPass, desc: FrameLayer, surface_size: Point2) -> EncodedLayer {
+        if self.indices.is_empty() {
+            return EncodedLayer {
+                desc,
+                buffers: None,
+                batches: Vec::new(),
+            };
+        }
+
+        let width = surface_size.x as f32;
+        let height = surface_size.y as f32;
+
+        for vertex in self.vertices.iter_mut() {
+            vertex.position.x = vertex.position.x / width * 2.0 - 1.0;
+            vertex.position.y = 1.0 - (vertex.position.y / height * 2.0);
+        }
+
+        let buffers = {
+            let mut graphics = pass.graphics.borrow_mut();
+
+            let vertex_buffer = graphics.create_buffer_with_labeled(
+                &self.vertices,
+                wgpu::BufferUsages::VERTEX,
+                Some("Frame Layer Vertex Buffer"),
+            );
+            let index_buffer = graphics.create_buffer_with_labeled(
+                &self.indices,
+                wgpu::BufferUsages::INDEX,
+                Some("Frame Layer Index Buffer"),
+            );
+
+            (vertex_buffer, index_buffer)
+        };
+
+        EncodedLayer {
+            desc,
+            buffers: Some(buffers),
+            batches: self.batches,
+        }
+    }
+}
+
+fn quad(pos: Vector2, size: Vector2, color: Color, uv: RectF) -> ([Vertex; 4], [u16; 6]) {
+    let vertices = [
+        Vertex::new(
+            Vector3::new(pos.x, pos.y, 0.0),
+            color,
+            Vector2::new(uv.x, uv.y),
+        ),
+        Vertex::new(
+            Vector3::new(pos.x + size.x, pos.y, 0.0),
+            color,
+            Vector2::new(uv.w, uv.y),
+        ),
+        Vertex::new(
+            Vector3::new(pos.x + size.x, pos.y + size.y, 0.0),
+            color,
+            Vector2::new(uv.w, uv.h),
+        ),
+        Vertex::new(
+            Vector3::new(pos.x, pos.y + size.y, 0.0),
+            color,
+            Vector2::new(uv.x, uv.h),
+        ),
+    ];
+
+    (vertices, [0, 1, 2, 0, 2, 3])
+}
+
+fn text_geometry(font: &Font, text: &str, pos: Vector2, color: Color) -> (Vec<Vertex>, Vec<u16>) {
+    let texture_size = font.texture_size();
+    let result = TextLayout::new().layout(font, text);
+
+    let mut vertices = Vec::with_capacity(result.glyphs.len() * 4);
+    let mut indices = Vec::with_capacity(result.glyphs.len() * 6);
+
+    for glyph in &result.glyphs {
+        let Ok(atlas_glyph) = font.get_glyph(glyph.codepoint) else {
+            continue;
+        };
+
+        let uv = RectF::new(
+            atlas_glyph.atlas_start_offset.x / texture_size.x as f32,
+            atlas_glyph.atlas_start_offset.y / texture_size.y as f32,
+            (atlas_glyph.atlas_start_offset.x + atlas_glyph.width) / texture_size.x as f32,
+            (atlas_glyph.atlas_start_offset.y + atlas_glyph.height) / texture_size.y as f32,
+        );
+
+        let color = Color {
+            a: color.a * glyph.alpha,
+            ..color
+        };
+
+        let (quad_vertices, quad_indices) = quad(pos + glyph.position, glyph.size, color, uv);
+        let base_index = vertices.len() as u16;
+
+        vertices.extend_from_slice(&quad_vertices);
+        indices.extend(quad_indices.iter().map(|index| index + base_index));
+    }
+
+    (vertices, indices)
+}
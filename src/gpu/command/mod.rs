@@ -5,17 +5,20 @@ use std::sync::atomic::Ordering;
 
 use std::sync::{atomic::AtomicBool, Arc};
 
-use crate::utils::ArcRef;
+use crate::{math::Rect, utils::ArcRef};
 use super::{
     GPUInner,
     SwapchainError,
     texture::{Texture, BlendState},
-    buffer::Buffer,
+    buffer::{Buffer, BufferError},
 };
 
 pub(crate) mod renderpass;
 pub(crate) mod computepass;
+pub(crate) mod copy;
 pub(crate) mod drawing;
+pub(crate) mod display_list;
+pub(crate) mod frame;
 pub(crate) mod utils;
 
 use renderpass::{
@@ -56,12 +59,25 @@ pub struct CommandBuffer {
     pub(crate) command: Option<ArcRef<wgpu::CommandEncoder>>,
     pub(crate) on_renderpass: Arc<AtomicBool>,
     pub(crate) on_compute: Arc<AtomicBool>,
+    pub(crate) debug_group_depth: u32,
+    /// Recorded on another thread, submitted with [GPU::submit](super::GPU::submit).
+    pub(crate) secondary: bool,
 
     pub(crate) swapchain: SurfaceTexture,
 }
 
+/// A command buffer finished with [CommandBuffer::finish], waiting to be submitted with
+/// [GPU::submit](super::GPU::submit).
+#[derive(Debug)]
+pub struct FinishedCommand {
+    pub(crate) command: wgpu::CommandBuffer,
+}
+
 impl CommandBuffer {
     pub(crate) fn new(inner: ArcRef<GPUInner>) -> Result<Self, CommandBufferBuildError> {
+        #[cfg(feature = "gpu-capture")]
+        Self::begin_pending_capture(&inner);
+
         let inner_ref = inner.borrow();
         let command =
             inner_ref
@@ -77,6 +93,31 @@ impl CommandBuffer {
             command: Some(ArcRef::new(command)),
             on_renderpass: Arc::new(AtomicBool::new(false)),
             on_compute: Arc::new(AtomicBool::new(false)),
+            debug_group_depth: 0,
+            secondary: false,
+
+            swapchain: SurfaceTexture::new(),
+        })
+    }
+
+    pub(crate) fn new_secondary(inner: ArcRef<GPUInner>) -> Result<Self, CommandBufferBuildError> {
+        let inner_ref = inner.borrow();
+        let command =
+            inner_ref
+                .device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Secondary Command Encoder"),
+                });
+
+        drop(inner_ref);
+
+        Ok(Self {
+            inner,
+            command: Some(ArcRef::new(command)),
+            on_renderpass: Arc::new(AtomicBool::new(false)),
+            on_compute: Arc::new(AtomicBool::new(false)),
+            debug_group_depth: 0,
+            secondary: true,
 
             swapchain: SurfaceTexture::new(),
         })
@@ -86,6 +127,9 @@ impl CommandBuffer {
         inner: ArcRef<GPUInner>,
         surface: SurfaceTexture,
     ) -> Result<Self, CommandBufferBuildError> {
+        #[cfg(feature = "gpu-capture")]
+        Self::begin_pending_capture(&inner);
+
         let inner_ref = inner.borrow();
         let command =
             inner_ref
@@ -101,11 +145,30 @@ impl CommandBuffer {
             command: Some(ArcRef::new(command)),
             on_renderpass: Arc::new(AtomicBool::new(false)),
             on_compute: Arc::new(AtomicBool::new(false)),
+            debug_group_depth: 0,
+            secondary: false,
 
             swapchain: surface,
         })
     }
 
+    #[cfg(feature = "gpu-capture")]
+    fn begin_pending_capture(inner: &ArcRef<GPUInner>) {
+        use super::CaptureState;
+
+        let mut inner_ref = inner.borrow_mut();
+        if inner_ref.capture_state == CaptureState::Pending {
+            // SAFETY: The capture is stopped in `end` once the frame is presented, or submitted
+            // when there is no surface.
+            unsafe {
+                inner_ref.device().start_graphics_debugger_capture();
+            }
+
+            inner_ref.capture_state = CaptureState::Capturing;
+            crate::dbg_log!("Graphics debugger capture started");
+        }
+    }
+
     /// Creates a new renderpass builder.
     /// 
     /// This function is used to create a renderpass builder that can be used to
@@ -227,6 +290,12 @@ impl CommandBuffer {
 
         self.on_renderpass.store(false, Ordering::Relaxed);
 
+        self.inner
+            .borrow()
+            .diagnostics
+            .lock()
+            .record_pass("Compute pass".to_string());
+
         let gpu_arc_ref = ArcRef::clone(&self.inner);
         let cmd_ref = ArcRef::clone(self.command.as_ref().unwrap());
         let atomic_pass = Arc::clone(&self.on_compute);
@@ -250,25 +319,60 @@ impl CommandBuffer {
         dst.write_raw_cmd(data, self);
     }
 
+    /// Copies `size` bytes of `src` starting at `src_offset` into `dst` at `dst_offset`.
+    ///
+    /// The offsets and size must be multiples of
+    /// [COPY_ALIGNMENT](super::buffer::COPY_ALIGNMENT), see [Buffer::copy_range].
+    pub fn copy_range(
+        &mut self,
+        src: &Buffer,
+        src_offset: u64,
+        dst: &Buffer,
+        dst_offset: u64,
+        size: u64,
+    ) -> Result<(), BufferError> {
+        dst.copy_range_cmd(src, src_offset, dst_offset, size, self)
+    }
+
+    /// Uploads tightly packed pixels into a rectangle of the destination texture.
+    ///
+    /// The upload is ordered with the passes recorded in this command buffer, see
+    /// [Texture::write_region] for the immediate variant. Will panic if the rectangle is outside
+    /// of the texture or the data doesn't hold exactly `rect.w * rect.h` pixels.
+    pub fn write_texture_region<T: bytemuck::Pod>(
+        &mut self,
+        data: &[T],
+        rect: Rect,
+        dst: &Texture,
+    ) {
+        let data: &[u8] = bytemuck::cast_slice(data);
+
+        if let Err(err) = dst.validate_region(data, rect) {
+            panic!("Invalid texture region {:?}: {}", rect, err);
+        }
+
+        let mut cmd = self.command.as_ref().unwrap().borrow_mut();
+        dst.write_region_cmd(data, rect, &mut cmd);
+    }
+
     /// Copies a source texture to a destination texture.
     ///
     /// This function uses a texture blitter to perform the copy operation, such copying
     /// between different texture formats or sizes (ex from a render target to a texture).
     pub fn blit_texture(&mut self, src: &Texture, dst: &Texture) {
-        let gpu_inner = self.inner.borrow();
-        let mut cmd = self.command.as_ref().unwrap().borrow_mut();
+        let src_view = src.inner.borrow().wgpu_view.clone();
 
-        let blitter = {
-            let dst_format = dst.inner.borrow().format;
-
-            TextureBlitter::new(gpu_inner.device(), dst_format.into())
+        let (dst_view, dst_format) = {
+            let dst_inner = dst.inner.borrow();
+            (dst_inner.wgpu_view.clone(), dst_inner.format)
         };
 
-        let src_view = &src.inner.borrow().wgpu_view;
+        let gpu_inner = self.inner.borrow();
+        let mut cmd = self.command.as_ref().unwrap().borrow_mut();
 
-        let dst_view = &dst.inner.borrow().wgpu_view;
+        let blitter = TextureBlitter::new(gpu_inner.device(), dst_format.into());
 
-        blitter.copy(&gpu_inner.device(), &mut cmd, src_view, dst_view);
+        blitter.copy(gpu_inner.device(), &mut cmd, &src_view, &dst_view);
     }
 
     /// Copies a source texture to a destination texture.
@@ -324,21 +428,142 @@ impl CommandBuffer {
         );
     }
 
-    pub fn end(&mut self, present: bool) {
-        let inner_ref = self.inner.borrow();
+    /// Opens a labeled debug group, shown as a scope in graphics debuggers.
+    ///
+    /// Every group must be closed with [CommandBuffer::pop_debug_group] before the command buffer ends.
+    /// Groups can be nested, but must not be pushed while a render pass or compute pass is active.
+    pub fn push_debug_group(&mut self, name: &str) {
+        self.validate_debug_command();
 
-        if self.command.is_none() {
-            return;
+        let mut cmd = self.command.as_ref().unwrap().borrow_mut();
+        cmd.push_debug_group(name);
+
+        self.debug_group_depth += 1;
+    }
+
+    /// Closes the debug group opened by the last [CommandBuffer::push_debug_group].
+    pub fn pop_debug_group(&mut self) {
+        self.validate_debug_command();
+
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        if self.debug_group_depth == 0 {
+            panic!("No debug group to pop");
+        }
+
+        let mut cmd = self.command.as_ref().unwrap().borrow_mut();
+        cmd.pop_debug_group();
+
+        self.debug_group_depth = self.debug_group_depth.saturating_sub(1);
+    }
+
+    /// Inserts a single labeled marker, shown as an event in graphics debuggers.
+    pub fn insert_debug_marker(&mut self, label: &str) {
+        self.validate_debug_command();
+
+        let mut cmd = self.command.as_ref().unwrap().borrow_mut();
+        cmd.insert_debug_marker(label);
+    }
+
+    fn validate_debug_command(&self) {
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        {
+            if self.command.is_none() {
+                panic!("Command buffer has already ended");
+            }
+
+            if self.on_renderpass.load(Ordering::Relaxed) || self.on_compute.load(Ordering::Relaxed) {
+                panic!("Debug groups and markers cannot be recorded while in a render pass or compute pass");
+            }
+        }
+    }
+
+    /// Finishes the recording without submitting it, the result can be sent to the thread owning
+    /// the surface and submitted there with [GPU::submit](super::GPU::submit).
+    ///
+    /// ```rust,ignore
+    /// let mut worker_gpu = gpu.clone();
+    /// let worker = std::thread::spawn(move || {
+    ///     let mut cmd = worker_gpu.create_secondary_command().unwrap();
+    ///     // ... record compute passes or render passes to textures
+    ///     cmd.finish()
+    /// });
+    ///
+    /// gpu.submit([worker.join().unwrap()]);
+    /// ```
+    pub fn finish(mut self) -> FinishedCommand {
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        if self.on_renderpass.load(Ordering::Relaxed) || self.on_compute.load(Ordering::Relaxed) {
+            panic!("Command buffer finished while still in a render pass or compute pass");
+        }
+
+        let command = self
+            .take_encoder()
+            .expect("Command buffer has already ended");
+
+        FinishedCommand {
+            command: command.finish(),
         }
+    }
+
+    fn take_encoder(&mut self) -> Option<wgpu::CommandEncoder> {
+        let command = self.command.take()?;
 
-        let cmd = ArcRef::try_unwrap(self.command.take().unwrap()).unwrap_or_else(|_| {
+        let mut cmd = ArcRef::try_unwrap(command).unwrap_or_else(|_| {
             panic!("Command buffer dropped while still in use");
         });
 
-        inner_ref.queue().submit(std::iter::once(cmd.finish()));
+        // This also runs when dropped during unwinding, close the groups instead of panicking
+        if self.debug_group_depth != 0 {
+            crate::error_log!(
+                "Command buffer ended with {} unclosed debug group(s)",
+                self.debug_group_depth
+            );
+
+            for _ in 0..self.debug_group_depth {
+                cmd.pop_debug_group();
+            }
+
+            self.debug_group_depth = 0;
+        }
+
+        Some(cmd)
+    }
+
+    pub fn end(&mut self, present: bool) {
+        let Some(cmd) = self.take_encoder() else {
+            return;
+        };
+
+        let inner_ref = self.inner.borrow();
+        inner_ref.submit(std::iter::once(cmd.finish()));
+
+        // Secondary command buffers never own the surface, nor end the frame
+        let present = present && !self.secondary;
 
         if present {
             self.swapchain.present();
+            inner_ref.frame_budget.lock().end_frame();
+            inner_ref.frame_stats.lock().end_frame();
+            inner_ref.diagnostics.lock().end_frame();
+        }
+
+        // Without a surface nothing is ever presented, the frame ends with its submission
+        #[cfg(feature = "gpu-capture")]
+        if present || (!self.secondary && inner_ref.surface.is_none()) {
+            use super::CaptureState;
+
+            drop(inner_ref);
+
+            let mut inner_ref = self.inner.borrow_mut();
+            if inner_ref.capture_state == CaptureState::Capturing {
+                // SAFETY: Matches the start in `begin_pending_capture`, all work is submitted.
+                unsafe {
+                    inner_ref.device().stop_graphics_debugger_capture();
+                }
+
+                inner_ref.capture_state = CaptureState::Idle;
+                crate::dbg_log!("Graphics debugger capture stopped");
+            }
         }
     }
 
@@ -346,6 +571,10 @@ impl CommandBuffer {
     /// 
     /// This will attach current swapchain texture to the command buffer if it is not already set.
     pub fn get_surface_texture(&mut self) -> Result<SurfaceTexture, SurfaceTextureError> {
+        if self.secondary {
+            return Err(SurfaceTextureError::NotAvailable);
+        }
+
         if !self.swapchain.is_valid() {
             let inner_ref = self.inner.borrow();
 
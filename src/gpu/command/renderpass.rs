@@ -1,30 +1,37 @@
 use std::{collections::HashMap, hash::{DefaultHasher, Hash, Hasher}, ops::Range, sync::{atomic::{AtomicBool, Ordering}, Arc}};
 
+use bytemuck::{Pod, Zeroable};
+
 use crate::{
     math::{Color, Point2, RectF},
     utils::ArcRef,
 };
 
 use super::{
-    utils::BindGroupType,
+    utils::{BindGroupType, BufferRange},
     drawing::DrawingContext,
     super::{
         GPUInner,
+        stats::DrawStats,
         texture::{
+            msaa::MsaaTargets,
+            view::TextureView,
             Texture, 
             BlendState, 
+            StencilState,
             TextureSampler, 
             TextureUsage,
             TextureFormat, 
             SampleCount
         },
-        buffer::{Buffer, BufferUsage},
+        buffer::{uniform::Uniform, Buffer},
         pipeline::{
-            render::RenderPipeline,
+            render::{RenderPipeline, RenderPipelineInner},
             manager::{VertexAttributeLayout, GraphicsPipelineDesc},
         },
         shader::{
             graphics::{GraphicsShader, GraphicsShaderType},
+            bind_group::BindGroup,
             bind_group_manager::BindGroupCreateInfo,
             types::ShaderReflect,
             BindGroupLayout,
@@ -33,12 +40,17 @@ use super::{
             ShaderFrontFace,
             ShaderPollygonMode,
             IndexBufferSize,
-            ShaderBindingType,
         },
         command::{BindGroupAttachment, SurfaceTexture},
     }
 };
 
+#[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+use super::super::{
+    buffer::BufferUsage,
+    shader::ShaderBindingType,
+    texture::TextureInner,
+};
 
 /// Represents a render pass in the graphics pipeline.
 ///
@@ -92,6 +104,8 @@ impl RenderPass {
             render_targets: Vec::new(),
             depth_target: None,
             depth_target_format: None,
+            depth_ops: DepthStencilOps::default(),
+            depth_test: true,
             surface_size: Point2::new(0.0, 0.0),
 
             multi_sample_count: None,
@@ -100,8 +114,11 @@ impl RenderPass {
             clear_color: None,
             viewport: None,
             scissor: None,
+            stencil: None,
+            stencil_reference: 0,
 
             vertex: None,
+            vertex_slots: Vec::new(),
             index: None,
 
             shader: None,
@@ -109,6 +126,8 @@ impl RenderPass {
             shader_reflection: None,
 
             attachments: Vec::new(),
+            bind_groups: Vec::new(),
+            dynamic_offsets: Vec::new(),
             push_constant: None,
 
             queues: Vec::new(),
@@ -159,6 +178,44 @@ impl RenderPass {
         }
     }
 
+    /// Set the stencil state used by intermediate mode draws, pipelines created with
+    /// `RenderPipelineBuilder` use their own stencil state.
+    #[inline]
+    pub fn set_stencil(&mut self, stencil: Option<&StencilState>) {
+        let mut inner = self.inner.borrow_mut();
+        inner.stencil = stencil.map(|stencil| stencil.create_wgpu_stencil_state());
+    }
+
+    /// Set whether intermediate mode draws are tested against and write to the depth target,
+    /// enabled by default. Disabling it draws over the scene regardless of depth, eg. for gizmos.
+    ///
+    /// Pipelines created with `RenderPipelineBuilder` always use the depth test.
+    #[inline]
+    pub fn set_depth_test(&mut self, enabled: bool) {
+        let mut inner = self.inner.borrow_mut();
+        inner.depth_test = enabled;
+    }
+
+    #[inline]
+    pub fn get_depth_test(&self) -> bool {
+        let inner = self.inner.borrow();
+        inner.depth_test
+    }
+
+    /// Set the reference value used by the stencil test and `StencilOperation::Replace`
+    /// for the following draw calls.
+    #[inline]
+    pub fn set_stencil_reference(&mut self, reference: u32) {
+        let mut inner = self.inner.borrow_mut();
+        inner.stencil_reference = reference;
+    }
+
+    #[inline]
+    pub fn get_stencil_reference(&self) -> u32 {
+        let inner = self.inner.borrow();
+        inner.stencil_reference
+    }
+
     #[inline]
     pub fn get_blend(&self, index: usize) -> Option<BlendState> {
         let inner = self.inner.borrow();
@@ -177,8 +234,8 @@ impl RenderPass {
     #[inline]
     pub fn set_gpu_buffer(&mut self, vertex: Option<&Buffer>, index: Option<&Buffer>) {
         self.set_gpu_buffer_wgpu(
-            vertex.map(|v| v.inner.borrow().buffer.clone()),
-            index.map(|i| i.inner.borrow().buffer.clone()),
+            vertex.map(|v| v.inner.borrow().range()),
+            index.map(|i| i.inner.borrow().range()),
         );
     }
 
@@ -210,13 +267,13 @@ impl RenderPass {
             (vertex_buffer, index_buffer)
         };
 
-        self.set_gpu_buffer_wgpu(vertex_buffer, index_buffer);
+        self.set_gpu_buffer_wgpu(vertex_buffer.map(Into::into), index_buffer.map(Into::into));
     }
 
     pub(crate) fn set_gpu_buffer_wgpu(
         &mut self,
-        vertex: Option<wgpu::Buffer>,
-        index: Option<wgpu::Buffer>,
+        vertex: Option<BufferRange>,
+        index: Option<BufferRange>,
     ) {
         #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
         {
@@ -232,7 +289,7 @@ impl RenderPass {
                     index_format,
                     ..
                 }) => index_format,
-                RenderShaderBinding::Pipeline(RenderPipeline { index_format, .. }) => index_format,
+                RenderShaderBinding::Pipeline(RenderPipelineInner { index_format, .. }) => index_format,
             };
 
             if index_format.is_none() && index.is_some() {
@@ -249,7 +306,46 @@ impl RenderPass {
     #[inline]
     pub fn get_gpu_buffer(&self) -> (Option<wgpu::Buffer>, Option<wgpu::Buffer>) {
         let inner = self.inner.borrow();
-        (inner.vertex.clone(), inner.index.clone())
+        (
+            inner.vertex.as_ref().map(|v| v.buffer.clone()),
+            inner.index.as_ref().map(|i| i.buffer.clone()),
+        )
+    }
+
+    /// Binds `buffer` to the vertex buffer `slot` of the shader, eg. a per-instance stream
+    /// read with [VertexStepMode::Instance](crate::gpu::shader::VertexStepMode::Instance).
+    ///
+    /// Slot 0 is the vertex buffer set with [RenderPass::set_gpu_buffer].
+    #[inline]
+    pub fn set_vertex_buffer(&mut self, slot: u32, buffer: &Buffer) {
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        {
+            if !buffer.inner.borrow().usage.contains(BufferUsage::VERTEX) {
+                panic!("Buffer must have VERTEX usage");
+            }
+
+            let inner = self.inner.borrow();
+            let slot_count = inner.vertex_buffer_layouts().len() as u32;
+            if slot >= slot_count {
+                panic!(
+                    "Vertex buffer slot {} is out of range, shader has {} vertex buffers",
+                    slot, slot_count
+                );
+            }
+        }
+
+        let wgpu_buffer = buffer.inner.borrow().range();
+        let mut inner = self.inner.borrow_mut();
+
+        if slot == 0 {
+            inner.vertex = Some(wgpu_buffer);
+            return;
+        }
+
+        match inner.vertex_slots.binary_search_by_key(&slot, |(s, _)| *s) {
+            Ok(index) => inner.vertex_slots[index].1 = wgpu_buffer,
+            Err(index) => inner.vertex_slots.insert(index, (slot, wgpu_buffer)),
+        }
     }
 
     #[inline]
@@ -326,9 +422,10 @@ impl RenderPass {
                 let attrib_inner = shader.attrib.borrow();
                 let shader_binding = IntermediateRenderPipeline {
                     shader: (vertex_shader, fragment_shader),
-                    vertex_attribute: (attrib_inner.stride, attrib_inner.attributes.clone()),
+                    vertex_buffers: attrib_inner.buffers.clone(),
                     shader_entry: (vertex_entry_point.clone(), fragment_entry_point.clone()),
                     layout: layout,
+                    push_constant_ranges: shader_inner.push_constant_ranges.clone(),
                     topology: topology.unwrap_or(attrib_inner.topology),
                     cull_mode: cull_mode.into(),
                     front_face: front_face.unwrap_or(attrib_inner.front_face),
@@ -359,7 +456,8 @@ impl RenderPass {
 
         match pipeline {
             Some(pipeline) => {
-                inner.shader = Some(RenderShaderBinding::Pipeline(pipeline.clone()));
+                // A snapshot is enough, pipelines are only rebuilt between frames
+                inner.shader = Some(RenderShaderBinding::Pipeline(pipeline.inner.borrow().clone()));
             }
             None => {
                 inner.shader = None;
@@ -384,6 +482,9 @@ impl RenderPass {
         inner
             .attachments
             .retain(|a| a.group != group || a.binding != binding);
+        inner
+            .dynamic_offsets
+            .retain(|(g, b, _)| *g != group || *b != binding);
     }
 
     pub(crate) fn insert_or_replace_attachment(
@@ -442,13 +543,13 @@ impl RenderPass {
                 ShaderBindingType::StorageBuffer(_, _) => {
                     matches!(attachment.attachment, BindGroupType::Storage(_))
                 }
-                ShaderBindingType::StorageTexture(_) => {
-                    matches!(attachment.attachment, BindGroupType::TextureStorage(_))
+                ShaderBindingType::StorageTexture(_, format) => {
+                    matches!(attachment.attachment, BindGroupType::TextureStorage(_, f) if f == format)
                 }
                 ShaderBindingType::Sampler(_) => {
                     matches!(attachment.attachment, BindGroupType::Sampler(_))
                 }
-                ShaderBindingType::Texture(_) => {
+                ShaderBindingType::Texture(_) | ShaderBindingType::DepthTexture(_) => {
                     matches!(attachment.attachment, BindGroupType::Texture(_))
                 }
                 ShaderBindingType::PushConstant(_) => {
@@ -462,6 +563,12 @@ impl RenderPass {
             }
         }
 
+        // Attachments replace a bind group set on the same group
+        inner.bind_groups.retain(|(g, _)| *g != group);
+        inner
+            .dynamic_offsets
+            .retain(|(g, b, _)| *g != group || *b != binding);
+
         let index = inner
             .attachments
             .iter()
@@ -474,6 +581,27 @@ impl RenderPass {
         }
     }
 
+    /// Binds a [BindGroup] built ahead of time to `group`, replacing the attachments set on
+    /// that group.
+    ///
+    /// Unlike the `set_attachment_*` functions, the bind group is used as is on every draw,
+    /// without hashing the attachments or looking up the bind group cache. It also works
+    /// with a render pipeline, overriding the bind group the pipeline was built with.
+    #[inline]
+    pub fn set_bind_group(&mut self, group: u32, bind_group: &BindGroup) {
+        let mut inner = self.inner.borrow_mut();
+
+        inner.attachments.retain(|a| a.group != group);
+        inner.dynamic_offsets.retain(|(g, _, _)| *g != group);
+
+        match inner.bind_groups.iter_mut().find(|(g, _)| *g == group) {
+            Some(existing) => existing.1 = bind_group.bind_group.clone(),
+            None => inner
+                .bind_groups
+                .push((group, bind_group.bind_group.clone())),
+        }
+    }
+
     #[inline]
     pub fn set_viewport(&mut self, _viewport: Option<RectF>, min_depth: f32, max_depth: f32) {
         let mut inner = self.inner.borrow_mut();
@@ -525,7 +653,7 @@ impl RenderPass {
         // check msaa count
         #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
         {
-            let msaa_count = texture.inner.borrow().sample_count.into();
+            let msaa_count: u32 = texture.inner.borrow().sample_count.into();
             if inner.multi_sample_count.unwrap() != msaa_count {
                 panic!("Multi sample texture count must match render target count");
             }
@@ -589,59 +717,74 @@ impl RenderPass {
         }
     }
 
+    /// Sets the push constant block used by the following draws, replacing it from offset 0.
+    ///
+    /// Each stage receives the part of the block it declares, vertex and fragment shaders
+    /// may use blocks of different sizes.
     #[inline]
-    pub fn set_push_constants(&mut self, _data: Option<&[u8]>) {
-        let mut inner = self.inner.borrow_mut();
-
-        match _data {
+    pub fn set_push_constants(&mut self, data: Option<&[u8]>) {
+        match data {
             Some(data) => {
-                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
-                {
-                    if inner.shader.is_none() {
-                        panic!("Shader is not set");
-                    }
+                self.inner.borrow_mut().push_constant = None;
+                self.set_push_constants_at(0, data);
+            }
+            None => {
+                self.inner.borrow_mut().push_constant = None;
+            }
+        }
+    }
 
-                    let size = inner
-                        .shader_reflection
-                        .as_ref()
-                        .unwrap()
-                        .iter()
-                        .find_map(|b| {
-                            let bindings = match b {
-                                ShaderReflect::Vertex { bindings, .. }
-                                | ShaderReflect::Fragment { bindings, .. }
-                                | ShaderReflect::VertexFragment { bindings, .. } => bindings,
-                                _ => return None,
-                            };
+    /// Writes `data` into the push constant block at `offset` (in bytes, a multiple of 4),
+    /// keeping the rest of the block as is.
+    ///
+    /// This updates part of a large block between draws, eg. only the per-object transform
+    /// while the rest of the block stays the same for the whole pass.
+    #[inline]
+    pub fn set_push_constants_at(&mut self, offset: u32, data: &[u8]) {
+        let mut inner = self.inner.borrow_mut();
 
-                            bindings.iter().find_map(|binding| {
-                                if let ShaderBindingType::PushConstant(size) = binding.ty {
-                                    Some(size)
-                                } else {
-                                    None
-                                }
-                            })
-                        })
-                        .unwrap_or(0);
+        let start = offset as usize;
+        let end = start + data.len().next_multiple_of(wgpu::PUSH_CONSTANT_ALIGNMENT as usize);
 
-                    if data.len() > size as usize {
-                        panic!("Data size must be less or equal to the push constant size");
-                    }
-                }
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        {
+            if inner.shader.is_none() {
+                panic!("Shader is not set");
+            }
 
-                let mut data = data.to_vec();
-                if data.len() % 4 != 0 {
-                    let padding = 4 - (data.len() % 4);
-                    data.extend(vec![0; padding]);
-                }
+            if !offset.is_multiple_of(wgpu::PUSH_CONSTANT_ALIGNMENT) {
+                panic!(
+                    "Push constant offset {} must be a multiple of {}",
+                    offset,
+                    wgpu::PUSH_CONSTANT_ALIGNMENT
+                );
+            }
+
+            let size = inner
+                .push_constant_ranges()
+                .iter()
+                .map(|range| range.range.end)
+                .max()
+                .unwrap_or(0);
 
-                inner.push_constant = Some(data);
+            if size == 0 {
+                panic!("Shader does not have push constants");
             }
-            None => {
-                inner.push_constant = None;
-                return;
+
+            if end > size as usize {
+                panic!(
+                    "Push constant data ({}..{}) exceeds the push constant size ({})",
+                    start, end, size
+                );
             }
         }
+
+        let block = inner.push_constant.get_or_insert_with(Vec::new);
+        if block.len() < end {
+            block.resize(end, 0);
+        }
+
+        block[start..start + data.len()].copy_from_slice(data);
     }
 
     #[inline]
@@ -675,6 +818,16 @@ impl RenderPass {
         }
     }
 
+    /// Same as [RenderPass::set_push_constants_at], from a slice of plain data.
+    #[inline]
+    pub fn set_push_constants_raw_at<T: bytemuck::Pod + bytemuck::Zeroable>(
+        &mut self,
+        offset: u32,
+        data: &[T],
+    ) {
+        self.set_push_constants_at(offset, bytemuck::cast_slice(data));
+    }
+
     #[inline]
     pub fn set_push_constants_struct_raw<T: bytemuck::Pod + bytemuck::Zeroable>(
         &mut self,
@@ -715,6 +868,9 @@ impl RenderPass {
     ) {
         match sampler {
             Some(sampler) => {
+                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+                self.validate_sampler(group, binding, sampler);
+
                 let inner = self.graphics.borrow();
                 let attachment = BindGroupAttachment {
                     group,
@@ -736,11 +892,18 @@ impl RenderPass {
     pub fn set_attachment_texture(&mut self, group: u32, binding: u32, texture: Option<&Texture>) {
         match texture {
             Some(texture) => {
+                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+                self.validate_texture_alpha(texture);
+
                 let inner = texture.inner.borrow();
+
+                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+                self.validate_texture(group, binding, &inner, inner.format);
+
                 let attachment = BindGroupAttachment {
                     group,
                     binding,
-                    attachment: BindGroupType::Texture(inner.wgpu_view.clone()),
+                    attachment: BindGroupType::Texture(inner.sample_view().clone()),
                 };
 
                 drop(inner);
@@ -753,6 +916,126 @@ impl RenderPass {
         }
     }
 
+    /// Binds the texture at `binding` and its default sampler, see
+    /// [TextureBuilder::set_sampler](crate::gpu::texture::TextureBuilder::set_sampler), at
+    /// `sampler_binding` of the same group.
+    #[inline]
+    pub fn set_attachment_texture_sampled(
+        &mut self,
+        group: u32,
+        binding: u32,
+        sampler_binding: u32,
+        texture: Option<&Texture>,
+    ) {
+        let sampler = texture.map(|texture| texture.sampler());
+
+        self.set_attachment_texture(group, binding, texture);
+        self.set_attachment_sampler(group, sampler_binding, sampler.as_ref());
+    }
+
+    /// Warns once per texture when straight alpha data is sampled while a render target
+    /// uses a premultiplied blend state (`One`, `OneMinusSrcAlpha`).
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    fn validate_texture_alpha(&self, texture: &Texture) {
+        let mut texture_inner = texture.inner.borrow_mut();
+        if !texture_inner.straight_alpha || texture_inner.straight_alpha_warned {
+            return;
+        }
+
+        let inner = self.inner.borrow();
+        let premultiplied = inner.render_targets.iter().any(|target| {
+            target.blend.is_some_and(|blend| {
+                blend.color.src_factor == wgpu::BlendFactor::One
+                    && blend.color.dst_factor == wgpu::BlendFactor::OneMinusSrcAlpha
+            })
+        });
+
+        if premultiplied {
+            texture_inner.straight_alpha_warned = true;
+            crate::warn_log!(
+                "Texture ({}x{} {:?}) contains non-premultiplied alpha but is blended with a premultiplied blend state",
+                texture_inner.size.x,
+                texture_inner.size.y,
+                texture_inner.format
+            );
+        }
+    }
+
+    /// Panics when a comparison sampler is bound to a filtering sampler binding, or the
+    /// opposite, the device would only report it once the bind group is created.
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    fn validate_sampler(&self, group: u32, binding: u32, sampler: &TextureSampler) {
+        let comparison = match self.binding_type(group, binding) {
+            Some(ShaderBindingType::Sampler(comparison)) => Some(comparison),
+            _ => None,
+        };
+
+        if comparison.is_some_and(|comparison| comparison != sampler.compare.is_some()) {
+            panic!(
+                "Sampler at group: {} binding: {}: {}",
+                group,
+                binding,
+                super::super::texture::SamplerError::CompareMismatch
+            );
+        }
+    }
+
+    /// Panics when a sampled texture doesn't match the binding declared by the shader, eg. a
+    /// depth texture bound to a `texture_2d<f32>` instead of a `texture_depth_2d`.
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    fn validate_texture(
+        &self,
+        group: u32,
+        binding: u32,
+        texture: &TextureInner,
+        format: TextureFormat,
+    ) {
+        if !texture.usages.contains(TextureUsage::Sampler) {
+            panic!(
+                "Texture at group: {} binding: {} was not created with TextureUsage::Sampler",
+                group, binding
+            );
+        }
+
+        let Some(ty) = self.binding_type(group, binding) else {
+            return;
+        };
+
+        let filterable = self
+            .graphics
+            .borrow()
+            .format_features(format.into())
+            .flags
+            .contains(wgpu::TextureFormatFeatureFlags::FILTERABLE);
+
+        if let Err(reason) = ty.validate_texture(
+            format,
+            texture.sample_count != SampleCount::SampleCount1,
+            filterable,
+        ) {
+            panic!("Texture at group: {} binding: {} is invalid: {}", group, binding, reason);
+        }
+    }
+
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    fn binding_type(&self, group: u32, binding: u32) -> Option<ShaderBindingType> {
+        let inner = self.inner.borrow();
+
+        inner.shader_reflection.iter().flatten().find_map(|reflect| {
+            let bindings = match reflect {
+                ShaderReflect::Vertex { bindings, .. }
+                | ShaderReflect::Fragment { bindings, .. }
+                | ShaderReflect::VertexFragment { bindings, .. } => bindings,
+                _ => return None,
+            };
+
+            bindings
+                .iter()
+                .find(|b| b.group == group && b.binding == binding)
+                .map(|b| b.ty)
+        })
+    }
+
     #[inline]
     pub fn set_attachment_texture_storage(
         &mut self,
@@ -763,10 +1046,48 @@ impl RenderPass {
         match texture {
             Some(texture) => {
                 let inner = texture.inner.borrow();
+
+                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+                inner.validate_storage_binding();
+
+                let attachment = BindGroupAttachment {
+                    group,
+                    binding,
+                    attachment: BindGroupType::TextureStorage(inner.wgpu_view.clone(), inner.format),
+                };
+
+                self.insert_or_replace_attachment(group, binding, attachment);
+            }
+            None => {
+                self.remove_attachment(group, binding);
+            }
+        }
+    }
+
+    /// Binds a [TextureView] of a single array layer, eg. one layer of an array texture.
+    #[inline]
+    pub fn set_attachment_texture_view(
+        &mut self,
+        group: u32,
+        binding: u32,
+        view: Option<&TextureView>,
+    ) {
+        match view {
+            Some(view) => {
+                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+                {
+                    if !view.is_single_layer() {
+                        panic!("Texture views bound to a shader must see a single array layer");
+                    }
+
+                    let texture = view.texture.inner.borrow();
+                    self.validate_texture(group, binding, &texture, view.format());
+                }
+
                 let attachment = BindGroupAttachment {
                     group,
                     binding,
-                    attachment: BindGroupType::TextureStorage(inner.wgpu_view.clone()),
+                    attachment: BindGroupType::Texture(view.view.clone()),
                 };
 
                 self.insert_or_replace_attachment(group, binding, attachment);
@@ -785,7 +1106,7 @@ impl RenderPass {
                 let attachment = BindGroupAttachment {
                     group,
                     binding,
-                    attachment: BindGroupType::Uniform(inner.buffer.clone()),
+                    attachment: BindGroupType::Uniform(inner.range()),
                 };
 
                 self.insert_or_replace_attachment(group, binding, attachment);
@@ -796,6 +1117,21 @@ impl RenderPass {
         }
     }
 
+    /// Binds the buffer of `uniform`, recording the upload of its value ahead of the pass if it
+    /// changed, see [Uniform].
+    #[inline]
+    pub fn set_attachment_uniform_value<T>(&mut self, group: u32, binding: u32, uniform: &Uniform<T>)
+    where
+        T: bytemuck::Pod,
+    {
+        {
+            let inner = self.inner.borrow();
+            uniform.upload_cmd(&mut inner.cmd.borrow_mut());
+        }
+
+        self.set_attachment_uniform(group, binding, Some(uniform.buffer()));
+    }
+
     #[inline]
     pub fn set_attachment_uniform_vec<T>(&mut self, group: u32, binding: u32, buffer: Option<Vec<T>>)
     where
@@ -809,7 +1145,7 @@ impl RenderPass {
                 let attachment = BindGroupAttachment {
                     group,
                     binding,
-                    attachment: BindGroupType::Uniform(buffer),
+                    attachment: BindGroupType::Uniform(buffer.into()),
                 };
 
                 drop(inner);
@@ -835,7 +1171,7 @@ impl RenderPass {
                 let attachment = BindGroupAttachment {
                     group,
                     binding,
-                    attachment: BindGroupType::Uniform(buffer),
+                    attachment: BindGroupType::Uniform(buffer.into()),
                 };
 
                 drop(inner);
@@ -857,7 +1193,7 @@ impl RenderPass {
                 let attachment = BindGroupAttachment {
                     group,
                     binding,
-                    attachment: BindGroupType::Storage(inner.buffer.clone()),
+                    attachment: BindGroupType::Storage(inner.range()),
                 };
 
                 self.insert_or_replace_attachment(group, binding, attachment);
@@ -868,6 +1204,94 @@ impl RenderPass {
         }
     }
 
+    /// Binds a uniform buffer declared with `GraphicsShaderBuilder::set_dynamic_offset`, reading
+    /// the shader data at `offset` bytes into the buffer.
+    ///
+    /// Changing only the offset between draws reuses the same bind group. The offset must be a
+    /// multiple of [GPU::uniform_offset_alignment](crate::gpu::GPU::uniform_offset_alignment).
+    #[inline]
+    pub fn set_attachment_uniform_dynamic(&mut self, group: u32, binding: u32, buffer: &Buffer, offset: u32) {
+        let attachment = BindGroupAttachment {
+            group,
+            binding,
+            attachment: BindGroupType::Uniform(buffer.inner.borrow().range()),
+        };
+
+        self.insert_or_replace_attachment(group, binding, attachment);
+        self.set_dynamic_offset(group, binding, buffer, offset);
+    }
+
+    /// Binds a storage buffer declared with `GraphicsShaderBuilder::set_dynamic_offset`, reading
+    /// the shader data at `offset` bytes into the buffer.
+    ///
+    /// The offset must be a multiple of
+    /// [GPU::storage_offset_alignment](crate::gpu::GPU::storage_offset_alignment).
+    #[inline]
+    pub fn set_attachment_storage_dynamic(&mut self, group: u32, binding: u32, buffer: &Buffer, offset: u32) {
+        let attachment = BindGroupAttachment {
+            group,
+            binding,
+            attachment: BindGroupType::Storage(buffer.inner.borrow().range()),
+        };
+
+        self.insert_or_replace_attachment(group, binding, attachment);
+        self.set_dynamic_offset(group, binding, buffer, offset);
+    }
+
+    fn set_dynamic_offset(&mut self, group: u32, binding: u32, buffer: &Buffer, offset: u32) {
+        let mut inner = self.inner.borrow_mut();
+
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        {
+            let Some(RenderShaderBinding::Intermediate(shader_binding)) = &inner.shader else {
+                panic!("Dynamic offsets require a shader set with set_shader");
+            };
+
+            let size = shader_binding
+                .layout
+                .iter()
+                .find(|l| l.group == group)
+                .and_then(|l| l.dynamic_binding_size(binding))
+                .unwrap_or_else(|| {
+                    panic!(
+                        "Binding group: {} binding: {} does not use dynamic offsets, declare it with GraphicsShaderBuilder::set_dynamic_offset",
+                        group, binding
+                    )
+                });
+
+            let limits = self.graphics.borrow().limits();
+            let alignment = match inner.attachments.iter().find(|a| a.group == group && a.binding == binding) {
+                Some(BindGroupAttachment {
+                    attachment: BindGroupType::Storage(_),
+                    ..
+                }) => limits.min_storage_buffer_offset_alignment,
+                _ => limits.min_uniform_buffer_offset_alignment,
+            };
+
+            if !offset.is_multiple_of(alignment) {
+                panic!(
+                    "Dynamic offset {} of group: {} binding: {} is not a multiple of {}",
+                    offset, group, binding, alignment
+                );
+            }
+
+            let buffer_size = buffer.inner.borrow().size;
+            if offset as u64 + size.get() > buffer_size {
+                panic!(
+                    "Dynamic offset {} with binding size {} is out of bounds of the buffer ({} bytes)",
+                    offset,
+                    size.get(),
+                    buffer_size
+                );
+            }
+        }
+
+        #[cfg(not(any(debug_assertions, feature = "enable-release-validation")))]
+        let _ = buffer;
+
+        inner.dynamic_offsets.push((group, binding, offset));
+    }
+
     #[inline]
     pub fn set_attachment_storage_raw<T>(&mut self, group: u32, binding: u32, buffer: Option<&[T]>)
     where
@@ -881,7 +1305,7 @@ impl RenderPass {
                 let attachment = BindGroupAttachment {
                     group,
                     binding,
-                    attachment: BindGroupType::Storage(buffer),
+                    attachment: BindGroupType::Storage(buffer.into()),
                 };
 
                 drop(inner);
@@ -907,7 +1331,7 @@ impl RenderPass {
                 let attachment = BindGroupAttachment {
                     group,
                     binding,
-                    attachment: BindGroupType::Storage(buffer),
+                    attachment: BindGroupType::Storage(buffer.into()),
                 };
 
                 drop(inner);
@@ -967,8 +1391,8 @@ impl RenderPass {
         #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
         {
             let inner = self.inner.borrow();
-            if inner.vertex.is_none() {
-                panic!("Vertex buffer is not set");
+            if let Some(slot) = inner.missing_vertex_buffer() {
+                panic!("Vertex buffer is not set for slot {}", slot);
             }
 
             if use_index_buffer && inner.index.is_none() {
@@ -977,7 +1401,7 @@ impl RenderPass {
         }
 
         // Preparing the pipeline and bind group
-        let (pipeline, bind_group, index_format) = self.prepare_pipeline();
+        let (pipeline, bind_group, index_format, dynamic_offsets) = self.prepare_pipeline();
 
         // Validating the index format, if it is required
         #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
@@ -994,7 +1418,9 @@ impl RenderPass {
         let queue = RenderPassQueue {
             pipeline,
             bind_group,
+            dynamic_offsets,
             vbo: inner.vertex.clone(),
+            vbo_slots: inner.vertex_slots.clone(),
             ibo: if use_index_buffer {
                 inner.index.clone()
             } else {
@@ -1007,84 +1433,251 @@ impl RenderPass {
             },
             viewport: inner.viewport.clone(),
             scissor: inner.scissor.clone(),
+            stencil_reference: inner.stencil_reference,
+            topology: inner.shader.as_ref().map_or(
+                wgpu::PrimitiveTopology::TriangleList,
+                |shader| shader.topology(),
+            ),
             ty: DrawCallType::Direct {
                 ranges,
                 vertex_offset,
                 num_of_instances,
             },
             push_constant: inner.push_constant.clone(),
+            push_constant_ranges: match inner.push_constant {
+                Some(_) => inner.push_constant_ranges().to_vec(),
+                None => Vec::new(),
+            },
         };
 
         inner.queues.push(queue);
     }
 
+    /// Draws with the arguments read from `buffer` at `offset`, a [DrawIndirectArgs].
     #[inline]
     pub fn draw_indirect(&mut self, buffer: &Buffer, offset: u64) {
         #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
-        if buffer.inner.borrow().usage.contains(BufferUsage::INDIRECT) {
-            panic!("Buffer must have INDIRECT usage");
-        }
+        Self::validate_indirect_buffer(buffer, offset, DrawIndirectArgs::SIZE, 1);
 
-        self.prepare_draw_indirect(buffer, offset, false);
+        let (buffer, offset) = indirect_range(buffer, offset);
+        self.prepare_draw_indirect(DrawCallType::InDirect { buffer, offset }, false);
     }
 
+    /// Draws with the arguments read from `buffer` at `offset`, a [DrawIndexedIndirectArgs].
     #[inline]
     pub fn draw_indexed_indirect(&mut self, buffer: &Buffer, offset: u64) {
         #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
-        if buffer.inner.borrow().usage.contains(BufferUsage::INDIRECT) {
-            panic!("Buffer must have INDIRECT usage");
-        }
+        Self::validate_indirect_buffer(buffer, offset, DrawIndexedIndirectArgs::SIZE, 1);
 
-        self.prepare_draw_indirect(buffer, offset, true);
+        let (buffer, offset) = indirect_range(buffer, offset);
+        self.prepare_draw_indirect(DrawCallType::InDirect { buffer, offset }, true);
     }
 
+    /// Issues `count` draws with the [DrawIndirectArgs] packed in `buffer` from `offset`.
+    ///
+    /// Requires [GPU::supports_multi_draw_indirect](crate::gpu::GPU::supports_multi_draw_indirect).
     #[inline]
-    fn prepare_draw_indirect(&mut self, buffer: &Buffer, offset: u64, use_index_buffer: bool) {
+    pub fn multi_draw_indirect(&mut self, buffer: &Buffer, offset: u64, count: u32) {
         #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
         {
-            let inner = self.inner.borrow();
-            if inner.vertex.is_none() {
-                panic!("Vertex buffer is not set");
-            }
-
-            if use_index_buffer && inner.index.is_none() {
-                panic!("Index buffer is not set");
-            }
+            self.validate_feature(wgpu::Features::MULTI_DRAW_INDIRECT, "Multi draw indirect");
+            Self::validate_indirect_buffer(buffer, offset, DrawIndirectArgs::SIZE, count);
         }
 
-        let (pipeline, bind_group, index_format) = self.prepare_pipeline();
+        let (buffer, offset) = indirect_range(buffer, offset);
+        self.prepare_draw_indirect(
+            DrawCallType::MultiInDirect {
+                buffer,
+                offset,
+                count,
+            },
+            false,
+        );
+    }
 
+    /// Issues `count` indexed draws with the [DrawIndexedIndirectArgs] packed in `buffer`
+    /// from `offset`.
+    ///
+    /// Requires [GPU::supports_multi_draw_indirect](crate::gpu::GPU::supports_multi_draw_indirect).
+    #[inline]
+    pub fn multi_draw_indexed_indirect(&mut self, buffer: &Buffer, offset: u64, count: u32) {
         #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
         {
-            if index_format.is_none() && use_index_buffer {
-                panic!(
-                    "Index format is not set, setup with shader.set_index_format() or render_pass.set_shader_ex()"
-                );
-            }
+            self.validate_feature(wgpu::Features::MULTI_DRAW_INDIRECT, "Multi draw indirect");
+            Self::validate_indirect_buffer(buffer, offset, DrawIndexedIndirectArgs::SIZE, count);
         }
 
-        let mut inner = self.inner.borrow_mut();
-        let queue = RenderPassQueue {
-            pipeline,
-            bind_group,
-            vbo: inner.vertex.clone(),
-            ibo: if use_index_buffer {
-                inner.index.clone()
-            } else {
-                None
-            },
-            itype: if use_index_buffer {
-                Some(index_format.unwrap().into())
-            } else {
-                None
+        let (buffer, offset) = indirect_range(buffer, offset);
+        self.prepare_draw_indirect(
+            DrawCallType::MultiInDirect {
+                buffer,
+                offset,
+                count,
             },
-            viewport: inner.viewport.clone(),
-            scissor: inner.scissor.clone(),
-            ty: DrawCallType::InDirect {
-                buffer: buffer.inner.borrow().buffer.clone(),
+            true,
+        );
+    }
+
+    /// Same as [RenderPass::multi_draw_indirect], with the number of draws read as a `u32`
+    /// from `count_buffer` at `count_offset`, clamped to `max_count`.
+    ///
+    /// This lets a compute pass cull the draws and write how many are left, without reading
+    /// it back on the CPU.
+    ///
+    /// Requires [GPU::supports_multi_draw_indirect_count](crate::gpu::GPU::supports_multi_draw_indirect_count).
+    #[inline]
+    pub fn multi_draw_indirect_count(
+        &mut self,
+        buffer: &Buffer,
+        offset: u64,
+        count_buffer: &Buffer,
+        count_offset: u64,
+        max_count: u32,
+    ) {
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        {
+            self.validate_feature(
+                wgpu::Features::MULTI_DRAW_INDIRECT_COUNT,
+                "Multi draw indirect count",
+            );
+            Self::validate_indirect_buffer(buffer, offset, DrawIndirectArgs::SIZE, max_count);
+            Self::validate_indirect_buffer(count_buffer, count_offset, 4, 1);
+        }
+
+        let (buffer, offset) = indirect_range(buffer, offset);
+        let (count_buffer, count_offset) = indirect_range(count_buffer, count_offset);
+        self.prepare_draw_indirect(
+            DrawCallType::MultiInDirectCount {
+                buffer,
                 offset,
+                count_buffer,
+                count_offset,
+                max_count,
+            },
+            false,
+        );
+    }
+
+    /// Same as [RenderPass::multi_draw_indexed_indirect], with the number of draws read as a
+    /// `u32` from `count_buffer` at `count_offset`, clamped to `max_count`.
+    ///
+    /// Requires [GPU::supports_multi_draw_indirect_count](crate::gpu::GPU::supports_multi_draw_indirect_count).
+    #[inline]
+    pub fn multi_draw_indexed_indirect_count(
+        &mut self,
+        buffer: &Buffer,
+        offset: u64,
+        count_buffer: &Buffer,
+        count_offset: u64,
+        max_count: u32,
+    ) {
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        {
+            self.validate_feature(
+                wgpu::Features::MULTI_DRAW_INDIRECT_COUNT,
+                "Multi draw indirect count",
+            );
+            Self::validate_indirect_buffer(buffer, offset, DrawIndexedIndirectArgs::SIZE, max_count);
+            Self::validate_indirect_buffer(count_buffer, count_offset, 4, 1);
+        }
+
+        let (buffer, offset) = indirect_range(buffer, offset);
+        let (count_buffer, count_offset) = indirect_range(count_buffer, count_offset);
+        self.prepare_draw_indirect(
+            DrawCallType::MultiInDirectCount {
+                buffer,
+                offset,
+                count_buffer,
+                count_offset,
+                max_count,
+            },
+            true,
+        );
+    }
+
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    fn validate_feature(&self, feature: wgpu::Features, name: &str) {
+        if !self.graphics.borrow().device().features().contains(feature) {
+            panic!("{} is not supported by the adapter", name);
+        }
+    }
+
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    fn validate_indirect_buffer(buffer: &Buffer, offset: u64, stride: u64, count: u32) {
+        let buffer_inner = buffer.inner.borrow();
+
+        if !buffer_inner.usage.contains(BufferUsage::INDIRECT) {
+            panic!("Buffer must have INDIRECT usage");
+        }
+
+        if !offset.is_multiple_of(4) {
+            panic!("Indirect buffer offset {} must be a multiple of 4", offset);
+        }
+
+        let end = offset + stride * count as u64;
+        if end > buffer_inner.size {
+            panic!(
+                "Indirect buffer is too small, {} bytes are read but the buffer is {} bytes",
+                end, buffer_inner.size
+            );
+        }
+    }
+
+    #[inline]
+    fn prepare_draw_indirect(&mut self, ty: DrawCallType, use_index_buffer: bool) {
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        {
+            let inner = self.inner.borrow();
+            if let Some(slot) = inner.missing_vertex_buffer() {
+                panic!("Vertex buffer is not set for slot {}", slot);
+            }
+
+            if use_index_buffer && inner.index.is_none() {
+                panic!("Index buffer is not set");
+            }
+        }
+
+        let (pipeline, bind_group, index_format, dynamic_offsets) = self.prepare_pipeline();
+
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        {
+            if index_format.is_none() && use_index_buffer {
+                panic!(
+                    "Index format is not set, setup with shader.set_index_format() or render_pass.set_shader_ex()"
+                );
+            }
+        }
+
+        let mut inner = self.inner.borrow_mut();
+        let queue = RenderPassQueue {
+            pipeline,
+            bind_group,
+            dynamic_offsets,
+            vbo: inner.vertex.clone(),
+            vbo_slots: inner.vertex_slots.clone(),
+            ibo: if use_index_buffer {
+                inner.index.clone()
+            } else {
+                None
             },
+            itype: if use_index_buffer {
+                Some(index_format.unwrap().into())
+            } else {
+                None
+            },
+            viewport: inner.viewport.clone(),
+            scissor: inner.scissor.clone(),
+            stencil_reference: inner.stencil_reference,
+            topology: inner.shader.as_ref().map_or(
+                wgpu::PrimitiveTopology::TriangleList,
+                |shader| shader.topology(),
+            ),
+            ty,
             push_constant: inner.push_constant.clone(),
+            push_constant_ranges: match inner.push_constant {
+                Some(_) => inner.push_constant_ranges().to_vec(),
+                None => Vec::new(),
+            },
         };
 
         inner.queues.push(queue);
@@ -1096,35 +1689,39 @@ impl RenderPass {
         wgpu::RenderPipeline,
         Vec<(u32, wgpu::BindGroup)>,
         Option<IndexBufferSize>,
+        DynamicOffsets,
     ) {
         let inner = self.inner.borrow();
 
-        match &inner.shader {
+        let (pipeline, mut bind_groups, index_format, layouts) = match &inner.shader {
             Some(RenderShaderBinding::Intermediate(shader_binding)) => {
-                let bind_group_hash_key = {
-                    let mut hasher = DefaultHasher::new();
-                    hasher.write_u64(0u64); // Graphics shader hash id
-
-                    for attachment in &inner.attachments {
-                        attachment.group.hash(&mut hasher);
-                        attachment.binding.hash(&mut hasher);
-                        match &attachment.attachment {
-                            BindGroupType::Uniform(uniform) => {
-                                uniform.hash(&mut hasher);
-                            }
-                            BindGroupType::Texture(texture) => {
-                                texture.hash(&mut hasher);
+                let bind_group_attachments = if inner.attachments.is_empty() {
+                    // Only persistent bind groups are used, nothing to look up
+                    Vec::new()
+                } else {
+                    let bind_group_hash_key = {
+                        let mut hasher = DefaultHasher::new();
+                        hasher.write_u64(0u64); // Graphics shader hash id
+
+                        for attachment in &inner.attachments {
+                            attachment.group.hash(&mut hasher);
+                            attachment.binding.hash(&mut hasher);
+                            match &attachment.attachment {
+                                BindGroupType::Uniform(uniform) => {
+                                    uniform.hash(&mut hasher);
+                                }
+                                BindGroupType::Texture(texture) => {
+                                    texture.hash(&mut hasher);
+                                }
+                                BindGroupType::TextureStorage(texture, _) => texture.hash(&mut hasher),
+                                BindGroupType::Sampler(sampler) => sampler.hash(&mut hasher),
+                                BindGroupType::Storage(storage) => storage.hash(&mut hasher),
                             }
-                            BindGroupType::TextureStorage(texture) => texture.hash(&mut hasher),
-                            BindGroupType::Sampler(sampler) => sampler.hash(&mut hasher),
-                            BindGroupType::Storage(storage) => storage.hash(&mut hasher),
                         }
-                    }
 
-                    hasher.finish()
-                };
+                        hasher.finish()
+                    };
 
-                let bind_group_attachments = {
                     let mut gpu_inner = self.graphics.borrow_mut();
 
                     match gpu_inner.get_bind_group(bind_group_hash_key) {
@@ -1137,16 +1734,18 @@ impl RenderPass {
                                 let (group, binding, attachment) =
                                     (e.group, e.binding, &e.attachment);
 
+                                // Dynamic bindings are bound with their size, the offset is
+                                // applied when the bind group is set
+                                let size = shader_binding
+                                    .layout
+                                    .iter()
+                                    .find(|l| l.group == group)
+                                    .and_then(|l| l.dynamic_binding_size(binding));
+
                                 let entry = match attachment {
                                     BindGroupType::Uniform(buffer) => wgpu::BindGroupEntry {
                                         binding,
-                                        resource: wgpu::BindingResource::Buffer(
-                                            wgpu::BufferBinding {
-                                                buffer,
-                                                offset: 0,
-                                                size: None,
-                                            },
-                                        ),
+                                        resource: wgpu::BindingResource::Buffer(buffer.binding(size)),
                                     },
                                     BindGroupType::Texture(texture) => wgpu::BindGroupEntry {
                                         binding,
@@ -1158,15 +1757,9 @@ impl RenderPass {
                                     },
                                     BindGroupType::Storage(buffer) => wgpu::BindGroupEntry {
                                         binding,
-                                        resource: wgpu::BindingResource::Buffer(
-                                            wgpu::BufferBinding {
-                                                buffer,
-                                                offset: 0,
-                                                size: None,
-                                            },
-                                        ),
+                                        resource: wgpu::BindingResource::Buffer(buffer.binding(size)),
                                     },
-                                    BindGroupType::TextureStorage(texture) => {
+                                    BindGroupType::TextureStorage(texture, _) => {
                                         wgpu::BindGroupEntry {
                                             binding,
                                             resource: wgpu::BindingResource::TextureView(texture),
@@ -1218,23 +1811,24 @@ impl RenderPass {
                     }
 
                     inner.depth_target_format.hash(&mut hasher);
+                    inner.depth_test.hash(&mut hasher);
+                    inner.stencil.hash(&mut hasher);
                     inner.multi_sample_count.hash(&mut hasher);
 
                     hasher.finish()
                 };
 
+                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+                inner.validate_stencil(
+                    &inner.stencil.clone().unwrap_or_default(),
+                    shader_binding.cull_mode.map(|c| c.into()),
+                );
+
                 let pipeline = {
                     let mut graphics_inner = self.graphics.borrow_mut();
                     match graphics_inner.get_graphics_pipeline(pipeline_hash_key) {
                         Some(pipeline) => pipeline,
                         None => {
-                            let attribute = &shader_binding.vertex_attribute;
-                            let vertex_desc = VertexAttributeLayout {
-                                stride: attribute.0 as wgpu::BufferAddress,
-                                step_mode: wgpu::VertexStepMode::Vertex,
-                                attributes: attribute.1.clone(),
-                            };
-
                             let primitive_state = wgpu::PrimitiveState {
                                 topology: shader_binding.topology.into(),
                                 strip_index_format: None,
@@ -1256,10 +1850,14 @@ impl RenderPass {
                                 entry_point: shader_binding.shader_entry.clone(),
                                 render_target: Vec::with_capacity(inner.render_targets.len()),
                                 depth_stencil: inner.depth_target_format,
-                                vertex_desc,
+                                depth_test: inner.depth_test,
+                                stencil: inner.stencil.clone().unwrap_or_default(),
+                                vertex_buffers: shader_binding.vertex_buffers.clone(),
                                 primitive_state,
                                 bind_group_layout: layout,
+                                push_constant_ranges: shader_binding.push_constant_ranges.clone(),
                                 msaa_count: inner.multi_sample_count.unwrap_or(1),
+                                label: None,
                             };
 
                             for target in &inner.render_targets {
@@ -1280,6 +1878,7 @@ impl RenderPass {
                     pipeline,
                     bind_group_attachments,
                     shader_binding.index_format,
+                    shader_binding.layout.as_slice(),
                 )
             }
             Some(RenderShaderBinding::Pipeline(pipeline)) => {
@@ -1296,6 +1895,9 @@ impl RenderPass {
                 pipeline_desc.depth_stencil = inner.depth_target_format;
                 pipeline_desc.msaa_count = inner.multi_sample_count.unwrap_or(1);
 
+                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+                inner.validate_stencil(&pipeline_desc.stencil, pipeline_desc.primitive_state.cull_mode);
+
                 let pipeline_hash_key = {
                     let mut hasher = DefaultHasher::new();
                     pipeline_desc.hash(&mut hasher);
@@ -1324,12 +1926,47 @@ impl RenderPass {
                 let bind_group_attachments = pipeline.bind_group.clone();
                 let index_format = pipeline.index_format.clone();
 
-                (wgpu_pipeline, bind_group_attachments, index_format)
+                (
+                    wgpu_pipeline,
+                    bind_group_attachments,
+                    index_format,
+                    pipeline.bind_group_layouts.as_slice(),
+                )
             }
             None => {
                 panic!("Shader is not set");
             }
+        };
+
+        for (group, bind_group) in &inner.bind_groups {
+            match bind_groups.iter_mut().find(|(g, _)| g == group) {
+                Some(existing) => existing.1 = bind_group.clone(),
+                None => bind_groups.push((*group, bind_group.clone())),
+            }
         }
+
+        // Every binding using dynamic offsets needs one, bindings without an offset set use 0
+        let dynamic_offsets = layouts
+            .iter()
+            .filter(|layout| !layout.dynamic_bindings.is_empty())
+            .map(|layout| {
+                let offsets = layout
+                    .dynamic_bindings
+                    .iter()
+                    .map(|(binding, _)| {
+                        inner
+                            .dynamic_offsets
+                            .iter()
+                            .find(|(g, b, _)| *g == layout.group && b == binding)
+                            .map_or(0, |(_, _, offset)| *offset)
+                    })
+                    .collect();
+
+                (layout.group, offsets)
+            })
+            .collect();
+
+        (pipeline, bind_groups, index_format, dynamic_offsets)
     }
 
     #[inline]
@@ -1373,6 +2010,16 @@ impl RenderPass {
                 &inner.render_targets[i].view
             };
 
+            let load = match inner.render_targets[i].load_op {
+                Some(target_load_op) => target_load_op.into_wgpu(|color| wgpu::Color {
+                    r: color.r as f64,
+                    g: color.g as f64,
+                    b: color.b as f64,
+                    a: color.a as f64,
+                }),
+                None => load_op,
+            };
+
             color_attachments.push(Some(wgpu::RenderPassColorAttachment {
                 view: target_view,
                 resolve_target: if has_msaa {
@@ -1381,7 +2028,7 @@ impl RenderPass {
                     None
                 },
                 ops: wgpu::Operations {
-                    load: load_op,
+                    load,
                     store: wgpu::StoreOp::Store,
                 },
             }));
@@ -1389,13 +2036,18 @@ impl RenderPass {
 
         let mut depth_stencil_attachment = None;
         if let Some(depth_target) = inner.depth_target.as_ref() {
+            let depth_ops = inner.depth_ops;
+
             depth_stencil_attachment = Some(wgpu::RenderPassDepthStencilAttachment {
                 view: depth_target,
                 depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: wgpu::StoreOp::Store,
+                    load: depth_ops.depth_load.into_wgpu(|value| value),
+                    store: depth_ops.depth_store.into(),
+                }),
+                stencil_ops: depth_ops.stencil.map(|(load, store)| wgpu::Operations {
+                    load: load.into_wgpu(|value| value),
+                    store: store.into(),
                 }),
-                stencil_ops: None,
             });
         }
 
@@ -1406,22 +2058,61 @@ impl RenderPass {
             ..Default::default()
         });
 
-        for queue in &inner.queues {
+        let mut stats = DrawStats::default();
+
+        let mut index = 0;
+        while index < inner.queues.len() {
+            let queue = &inner.queues[index];
+
+            // Fold the following draws continuing this one's range into a single draw call,
+            // eg. one draw per sprite from the same vertex buffer.
+            let mut merged_end = match &queue.ty {
+                DrawCallType::Direct { ranges, .. } => ranges.end,
+                _ => 0,
+            };
+
+            index += 1;
+            while let Some(next) = inner.queues.get(index)
+                && queue.can_merge(next, merged_end)
+            {
+                if let DrawCallType::Direct { ranges, .. } = &next.ty {
+                    merged_end = ranges.end;
+                }
+
+                index += 1;
+            }
+
             render_pass.set_pipeline(&queue.pipeline);
+            render_pass.set_stencil_reference(queue.stencil_reference);
 
             for (group, bind) in &queue.bind_group {
-                render_pass.set_bind_group(*group, bind, &[]);
+                let offsets = queue
+                    .dynamic_offsets
+                    .iter()
+                    .find(|(g, _)| g == group)
+                    .map_or(&[][..], |(_, offsets)| offsets.as_slice());
+
+                render_pass.set_bind_group(*group, bind, offsets);
             }
 
             if let Some(vbo) = &queue.vbo {
-                render_pass.set_vertex_buffer(0, vbo.slice(..));
+                render_pass.set_vertex_buffer(0, vbo.slice());
+            }
+
+            for (slot, vbo) in &queue.vbo_slots {
+                render_pass.set_vertex_buffer(*slot, vbo.slice());
             }
 
             #[cfg(not(target_arch = "wasm32"))]
             if let Some(pc) = &queue.push_constant {
-                use wgpu::ShaderStages;
+                for (stages, range) in push_constant_segments(&queue.push_constant_ranges) {
+                    let end = (range.end as usize).min(pc.len());
+                    if range.start as usize >= end {
+                        continue;
+                    }
 
-                render_pass.set_push_constants(ShaderStages::all(), 0, pc);
+                    render_pass.set_push_constants(stages, range.start, &pc[range.start as usize..end]);
+                }
             }
 
             if let Some(scissor) = queue.scissor.as_ref() {
@@ -1455,28 +2146,76 @@ impl RenderPass {
                     vertex_offset,
                     num_of_instances,
                 } => {
+                    let ranges = ranges.start..merged_end;
+
+                    stats.draw_calls += 1;
+                    stats.triangles += triangle_count(queue.topology, ranges.len() as u32)
+                        * *num_of_instances as u64;
+
                     if let Some(ibo) = &queue.ibo {
-                        render_pass.set_index_buffer(ibo.slice(..), queue.itype.unwrap());
-                        render_pass.draw_indexed(
-                            ranges.clone(),
-                            *vertex_offset,
-                            0..*num_of_instances,
-                        );
+                        render_pass.set_index_buffer(ibo.slice(), queue.itype.unwrap());
+                        render_pass.draw_indexed(ranges, *vertex_offset, 0..*num_of_instances);
                     } else {
-                        render_pass.draw(ranges.clone(), 0..*num_of_instances);
+                        render_pass.draw(ranges, 0..*num_of_instances);
                     }
                 }
                 DrawCallType::InDirect { buffer, offset } => {
+                    stats.draw_calls += 1;
+
                     if let Some(ibo) = &queue.ibo {
-                        render_pass.set_index_buffer(ibo.slice(..), queue.itype.unwrap());
+                        render_pass.set_index_buffer(ibo.slice(), queue.itype.unwrap());
                         render_pass.draw_indexed_indirect(buffer, *offset);
                     } else {
                         render_pass.draw_indirect(buffer, *offset);
                     }
                 }
+                DrawCallType::MultiInDirect {
+                    buffer,
+                    offset,
+                    count,
+                } => {
+                    stats.draw_calls += *count;
+
+                    if let Some(ibo) = &queue.ibo {
+                        render_pass.set_index_buffer(ibo.slice(), queue.itype.unwrap());
+                        render_pass.multi_draw_indexed_indirect(buffer, *offset, *count);
+                    } else {
+                        render_pass.multi_draw_indirect(buffer, *offset, *count);
+                    }
+                }
+                DrawCallType::MultiInDirectCount {
+                    buffer,
+                    offset,
+                    count_buffer,
+                    count_offset,
+                    max_count,
+                } => {
+                    stats.draw_calls += 1;
+
+                    if let Some(ibo) = &queue.ibo {
+                        render_pass.set_index_buffer(ibo.slice(), queue.itype.unwrap());
+                        render_pass.multi_draw_indexed_indirect_count(
+                            buffer,
+                            *offset,
+                            count_buffer,
+                            *count_offset,
+                            *max_count,
+                        );
+                    } else {
+                        render_pass.multi_draw_indirect_count(
+                            buffer,
+                            *offset,
+                            count_buffer,
+                            *count_offset,
+                            *max_count,
+                        );
+                    }
+                }
             }
         }
 
+        self.graphics.borrow().frame_stats.lock().add(stats);
+
         inner.atomic_pass.store(false, Ordering::Relaxed);
     }
 }
@@ -1497,6 +2236,60 @@ pub(crate) struct RenderpassRenderTarget {
     pub format: wgpu::TextureFormat,
     pub blend: Option<wgpu::BlendState>,
     pub write_mask: Option<wgpu::ColorWrites>,
+    pub load_op: Option<LoadOp<Color>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DepthStencilOps {
+    pub depth_load: LoadOp<f32>,
+    pub depth_store: StoreOp,
+    pub stencil: Option<(LoadOp<u32>, StoreOp)>,
+}
+
+impl Default for DepthStencilOps {
+    fn default() -> Self {
+        Self {
+            depth_load: LoadOp::Clear(1.0),
+            depth_store: StoreOp::Store,
+            stencil: None,
+        }
+    }
+}
+
+/// Operation performed on an attachment at the start of a render pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadOp<T> {
+    /// Keep the existing contents of the attachment.
+    Load,
+    /// Clear the attachment with the given value.
+    Clear(T),
+}
+
+impl<T> LoadOp<T> {
+    pub(crate) fn into_wgpu<V>(self, f: impl FnOnce(T) -> V) -> wgpu::LoadOp<V> {
+        match self {
+            LoadOp::Load => wgpu::LoadOp::Load,
+            LoadOp::Clear(value) => wgpu::LoadOp::Clear(f(value)),
+        }
+    }
+}
+
+/// Operation performed on an attachment at the end of a render pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StoreOp {
+    /// Write the results of the render pass to the attachment.
+    Store,
+    /// Discard the results, eg. for a depth buffer only used within the pass.
+    Discard,
+}
+
+impl From<StoreOp> for wgpu::StoreOp {
+    fn from(value: StoreOp) -> Self {
+        match value {
+            StoreOp::Store => wgpu::StoreOp::Store,
+            StoreOp::Discard => wgpu::StoreOp::Discard,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1507,6 +2300,8 @@ pub(crate) struct RenderPassInner {
     pub render_targets: Vec<RenderpassRenderTarget>,
     pub depth_target: Option<wgpu::TextureView>,
     pub depth_target_format: Option<wgpu::TextureFormat>,
+    pub depth_ops: DepthStencilOps,
+    pub depth_test: bool,
 
     pub surface_size: Point2,
 
@@ -1516,24 +2311,92 @@ pub(crate) struct RenderPassInner {
     pub clear_color: Option<Color>,
     pub viewport: Option<(RectF, f32, f32)>,
     pub scissor: Option<RectF>,
+    pub stencil: Option<wgpu::StencilState>,
+    pub stencil_reference: u32,
 
-    pub vertex: Option<wgpu::Buffer>,
-    pub index: Option<wgpu::Buffer>,
+    pub vertex: Option<BufferRange>,
+    /// Vertex buffers bound to the slots after the first, sorted by slot.
+    pub vertex_slots: Vec<(u32, BufferRange)>,
+    pub index: Option<BufferRange>,
 
     pub shader: Option<RenderShaderBinding>,
     #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
     pub shader_reflection: Option<Vec<ShaderReflect>>,
 
     pub attachments: Vec<BindGroupAttachment>,
+    pub bind_groups: Vec<(u32, wgpu::BindGroup)>,
+    /// Group, binding and offset of the buffers bound with dynamic offsets.
+    pub dynamic_offsets: Vec<(u32, u32, u32)>,
     pub push_constant: Option<Vec<u8>>,
 
     pub queues: Vec<RenderPassQueue>,
 }
 
+impl RenderPassInner {
+    /// Push constant ranges of the pipeline layout of the current shader or pipeline.
+    pub fn push_constant_ranges(&self) -> &[wgpu::PushConstantRange] {
+        match &self.shader {
+            Some(RenderShaderBinding::Intermediate(binding)) => &binding.push_constant_ranges,
+            Some(RenderShaderBinding::Pipeline(pipeline)) => {
+                &pipeline.pipeline_desc.push_constant_ranges
+            }
+            None => &[],
+        }
+    }
+
+    /// Vertex buffer layouts of the current shader or pipeline, empty when the shader has no
+    /// vertex inputs, eg. vertex pulling from a storage buffer.
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    pub fn vertex_buffer_layouts(&self) -> &[VertexAttributeLayout] {
+        match &self.shader {
+            Some(RenderShaderBinding::Intermediate(binding)) => &binding.vertex_buffers,
+            Some(RenderShaderBinding::Pipeline(pipeline)) => {
+                &pipeline.pipeline_desc.vertex_buffers
+            }
+            None => &[],
+        }
+    }
+
+    /// Returns the first vertex buffer slot used by the current shader without a buffer bound.
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    pub fn missing_vertex_buffer(&self) -> Option<u32> {
+        if self.shader.is_none() {
+            return self.vertex.is_none().then_some(0);
+        }
+
+        (0..self.vertex_buffer_layouts().len() as u32).find(|&slot| match slot {
+            0 => self.vertex.is_none(),
+            _ => self
+                .vertex_slots
+                .binary_search_by_key(&slot, |(s, _)| *s)
+                .is_err(),
+        })
+    }
+
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    pub fn validate_stencil(&self, stencil: &wgpu::StencilState, cull_mode: Option<wgpu::Face>) {
+        if !stencil.is_enabled() {
+            return;
+        }
+
+        match self.depth_target_format {
+            Some(format) if format.has_stencil_aspect() => {}
+            _ => panic!("Stencil state requires a depth target with a stencil aspect"),
+        }
+
+        if !stencil.is_read_only(cull_mode) && self.depth_ops.stencil.is_none() {
+            panic!(
+                "Stencil state writes to the stencil buffer, but stencil ops are not set, use RenderpassBuilder::set_stencil_ops"
+            );
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) enum RenderpassAttachment<'a> {
     SurfaceTexture(&'a SurfaceTexture),
     Texture(&'a Texture),
+    TextureView(&'a TextureView),
 }
 
 #[derive(Clone, Debug)]
@@ -1543,8 +2406,12 @@ pub struct RenderpassBuilder<'a> {
     atomic_pass: Arc<AtomicBool>,
 
     color_attachments: Vec<(RenderpassAttachment<'a>, Option<BlendState>)>,
+    color_load_ops: Vec<(usize, LoadOp<Color>)>,
     msaa_attachments: Vec<&'a Texture>,
+    msaa_count: Option<SampleCount>,
+    msaa_targets: Option<MsaaTargets>,
     depth_attachment: Option<&'a Texture>,
+    depth_ops: DepthStencilOps,
 }
 
 impl<'a> RenderpassBuilder<'a> {
@@ -1559,8 +2426,12 @@ impl<'a> RenderpassBuilder<'a> {
             atomic_pass,
 
             color_attachments: Vec::new(),
+            color_load_ops: Vec::new(),
             msaa_attachments: Vec::new(),
+            msaa_count: None,
+            msaa_targets: None,
             depth_attachment: None,
+            depth_ops: DepthStencilOps::default(),
         }
     }
 
@@ -1589,18 +2460,103 @@ impl<'a> RenderpassBuilder<'a> {
         self
     }
 
+    /// Renders into a [TextureView] of a single mip level and array layer, eg. one layer of an
+    /// array texture.
+    pub fn add_color_attachment_view(
+        mut self,
+        view: &'a TextureView,
+        blend: Option<&BlendState>,
+    ) -> Self {
+        self.color_attachments
+            .push((RenderpassAttachment::TextureView(view), blend.cloned()));
+
+        self
+    }
+
     pub fn add_msaa_attachment(mut self, texture: &'a Texture) -> Self {
         self.msaa_attachments.push(texture);
 
         self
     }
 
+    /// Enable MSAA with automatically managed multisampled textures.
+    ///
+    /// A multisampled texture is allocated for each color attachment and cached by size, textures not
+    /// used for a few frames are released, eg. after the surface is resized. The color attachments are
+    /// resolved automatically at the end of the pass.
+    ///
+    /// The depth attachment, if any, is used as is and must be created with the same sample count,
+    /// building fails with [RenderPassBuildError::MismatchedAttachmentSampleCount] otherwise.
+    ///
+    /// Cannot be combined with [RenderpassBuilder::add_msaa_attachment].
+    pub fn with_msaa(mut self, sample_count: SampleCount) -> Self {
+        self.msaa_count = Some(sample_count);
+
+        self
+    }
+
+    /// Enable MSAA using multisampled targets owned by the caller, see [MsaaTargets].
+    ///
+    /// The targets are resized to the attachments if needed. Like [RenderpassBuilder::with_msaa],
+    /// the color attachments are resolved at the end of the pass and the depth is not.
+    ///
+    /// Cannot be combined with [RenderpassBuilder::add_msaa_attachment] or [RenderpassBuilder::with_msaa].
+    pub fn with_msaa_targets(mut self, targets: &MsaaTargets) -> Self {
+        self.msaa_targets = Some(targets.clone());
+
+        self
+    }
+
     pub fn set_depth_attachment(mut self, texture: &'a Texture) -> Self {
         self.depth_attachment = Some(texture);
 
         self
     }
 
+    /// Set the load operation of the color attachment at `index`.
+    ///
+    /// By default, the attachment is cleared with [RenderPass::set_clear_color], or loaded if
+    /// no clear color is set.
+    pub fn set_color_load_op(mut self, index: usize, load_op: LoadOp<Color>) -> Self {
+        self.color_load_ops.retain(|(i, _)| *i != index);
+        self.color_load_ops.push((index, load_op));
+
+        self
+    }
+
+    /// Set the load operation of the depth attachment, the default is `LoadOp::Clear(1.0)`.
+    ///
+    /// Use `LoadOp::Load` to keep the depth from a previous pass, eg. after a depth pre-pass.
+    pub fn set_depth_load_op(mut self, load_op: LoadOp<f32>) -> Self {
+        self.depth_ops.depth_load = load_op;
+
+        self
+    }
+
+    /// Set the value the depth attachment is cleared with.
+    pub fn set_depth_clear_value(mut self, value: f32) -> Self {
+        self.depth_ops.depth_load = LoadOp::Clear(value);
+
+        self
+    }
+
+    /// Set the store operation of the depth attachment, the default is `StoreOp::Store`.
+    pub fn set_depth_store_op(mut self, store_op: StoreOp) -> Self {
+        self.depth_ops.depth_store = store_op;
+
+        self
+    }
+
+    /// Set the stencil operations of the depth attachment.
+    ///
+    /// Only valid with a depth format that has a stencil aspect, eg. `Depth24PlusStencil8`.
+    /// If not set, the stencil aspect is left untouched (read-only).
+    pub fn set_stencil_ops(mut self, load_op: LoadOp<u32>, store_op: StoreOp) -> Self {
+        self.depth_ops.stencil = Some((load_op, store_op));
+
+        self
+    }
+
     pub fn build(self) -> Result<RenderPass, RenderPassBuildError> {
         let mut surface_size = None;
 
@@ -1641,6 +2597,26 @@ impl<'a> RenderpassBuilder<'a> {
                         texture_inner.size,
                     )
                 }
+                RenderpassAttachment::TextureView(view) => {
+                    let texture_inner = view.texture.inner.borrow();
+
+                    if !texture_inner
+                        .usages
+                        .contains(TextureUsage::RenderAttachment)
+                    {
+                        return Err(RenderPassBuildError::ColorAttachmentNotRenderTarget);
+                    }
+
+                    if texture_inner.sample_count != SampleCount::SampleCount1 {
+                        return Err(RenderPassBuildError::ColorAttachmentMultiSampled);
+                    }
+
+                    if !view.is_single_layer() || view.mip_level_count() != 1 {
+                        return Err(RenderPassBuildError::ColorAttachmentInvalidView);
+                    }
+
+                    (view.view.clone(), view.format().into(), view.size())
+                }
             };
 
             if surface_size.is_some() {
@@ -1662,6 +2638,7 @@ impl<'a> RenderpassBuilder<'a> {
                 format,
                 blend: blend.map(|b| b.create_wgpu_blend_state()),
                 write_mask: blend.map(|b| b.create_wgpu_color_write_mask()),
+                load_op: None,
             });
         }
 
@@ -1716,6 +2693,7 @@ impl<'a> RenderpassBuilder<'a> {
 
         let mut depth_view = None;
         let mut depth_format = None;
+        let mut depth_sample_count = 1;
 
         if let Some(depth_texture) = self.depth_attachment {
             let texture_inner = depth_texture.inner.borrow();
@@ -1757,12 +2735,103 @@ impl<'a> RenderpassBuilder<'a> {
 
             depth_view = Some(texture_inner.wgpu_view.clone());
             depth_format = Some(texture_inner.format.into());
+            depth_sample_count = texture_inner.sample_count.into();
         }
 
         if surface_size.is_none() {
             return Err(RenderPassBuildError::NoColorOrDepthAttachment);
         }
 
+        for (index, load_op) in self.color_load_ops {
+            if index >= color_attachments.len() {
+                return Err(RenderPassBuildError::MismatchedAttachmentCount(
+                    index + 1,
+                    color_attachments.len(),
+                ));
+            }
+
+            color_attachments[index].load_op = Some(load_op);
+        }
+
+        if self.depth_ops.stencil.is_some() {
+            let has_stencil = depth_format
+                .map(|format: wgpu::TextureFormat| format.has_stencil_aspect())
+                .unwrap_or(false);
+
+            if !has_stencil {
+                return Err(RenderPassBuildError::DepthTextureNoStencil);
+            }
+        }
+
+        if let Some(sample_count) = self.msaa_count {
+            if !multi_sample_target.is_empty() {
+                return Err(RenderPassBuildError::MsaaAttachmentConflict);
+            }
+
+            let sample_count: u32 = sample_count.into();
+            if sample_count > 1 {
+                let size = surface_size.unwrap();
+                let mut gpu_inner = self.gpu.borrow_mut();
+
+                for (index, target) in color_attachments.iter().enumerate() {
+                    multi_sample_target.push(gpu_inner.get_msaa_texture(
+                        index,
+                        size,
+                        target.format,
+                        sample_count,
+                    ));
+                }
+
+                // The depth attachment is rendered to directly, it can't be swapped for a
+                // cached texture without losing what is drawn to it
+                if depth_view.is_some() && depth_sample_count != sample_count {
+                    return Err(RenderPassBuildError::MismatchedAttachmentSampleCount(
+                        sample_count,
+                        depth_sample_count,
+                    ));
+                }
+
+                multi_sample_count = Some(sample_count);
+            }
+        }
+
+        if let Some(targets) = self.msaa_targets {
+            if !multi_sample_target.is_empty() || self.msaa_count.is_some() {
+                return Err(RenderPassBuildError::MsaaAttachmentConflict);
+            }
+
+            let formats = color_attachments
+                .iter()
+                .map(|target| target.format)
+                .collect::<Vec<_>>();
+
+            let (colors, depth, sample_count) =
+                targets.prepare(surface_size.unwrap(), &formats, depth_format);
+
+            if sample_count > 1 {
+                multi_sample_target = colors;
+                depth_view = depth.or(depth_view);
+                multi_sample_count = Some(sample_count);
+            }
+        }
+
+        {
+            let size = surface_size.unwrap();
+            let formats = color_attachments
+                .iter()
+                .map(|target| format!("{:?}", target.format))
+                .collect::<Vec<_>>();
+
+            self.gpu.borrow().diagnostics.lock().record_pass(format!(
+                "Render pass {}x{}: color [{}], depth {:?}, msaa {}",
+                size.x,
+                size.y,
+                formats.join(", "),
+                depth_format,
+                multi_sample_count.unwrap_or(1),
+            ));
+        }
+
         let renderpass = RenderPass::new(self.gpu, self.cmd, self.atomic_pass);
         {
             let mut inner = renderpass.inner.borrow_mut();
@@ -1772,6 +2841,7 @@ impl<'a> RenderpassBuilder<'a> {
             inner.multi_sample_count = multi_sample_count;
             inner.depth_target = depth_view;
             inner.depth_target_format = depth_format;
+            inner.depth_ops = self.depth_ops;
             inner.surface_size = surface_size.unwrap();
         }
 
@@ -1779,10 +2849,13 @@ impl<'a> RenderpassBuilder<'a> {
     }
 }
 
+#[derive(Clone, Debug)]
 pub enum RenderPassBuildError {
     NoColorOrDepthAttachment,
     ColorAttachmentNotRenderTarget,
     ColorAttachmentMultiSampled,
+    /// The view sees more than one mip level or array layer.
+    ColorAttachmentInvalidView,
     MismatchedAttachmentCount(usize, usize),
     MismatchedAttachmentSize(Point2, Point2),
     MismatchedAttachmentSampleCount(u32, u32),
@@ -1790,9 +2863,11 @@ pub enum RenderPassBuildError {
     MsaaTextureNotMultiSampled,
     MsaaTextureNotRenderAttachment,
     MsaaTextureInvalidSize(Point2),
+    MsaaAttachmentConflict,
     DepthTextureNotRenderAttachment,
     DepthTextureInvalidSize(Point2),
     DepthTextureFormatNotSupported(TextureFormat),
+    DepthTextureNoStencil,
     SwapchainError(String),
 }
 
@@ -1806,6 +2881,9 @@ impl std::fmt::Display for RenderPassBuildError {
             RenderPassBuildError::ColorAttachmentMultiSampled => {
                 write!(f, "Color attachment is multi-sampled")
             }
+            RenderPassBuildError::ColorAttachmentInvalidView => {
+                write!(f, "Color attachment view must see a single mip level and layer")
+            }
             RenderPassBuildError::MismatchedAttachmentCount(expected, actual) => {
                 write!(f, "Expected {} attachments, but got {}", expected, actual)
             }
@@ -1829,6 +2907,9 @@ impl std::fmt::Display for RenderPassBuildError {
             RenderPassBuildError::MsaaTextureInvalidSize(size) => {
                 write!(f, "MSAA texture has invalid size {:?}", size)
             }
+            RenderPassBuildError::MsaaAttachmentConflict => {
+                write!(f, "MSAA attachments, with_msaa and with_msaa_targets cannot be combined")
+            }
             RenderPassBuildError::DepthTextureNotRenderAttachment => {
                 write!(f, "Depth texture is not a render attachment")
             }
@@ -1838,6 +2919,9 @@ impl std::fmt::Display for RenderPassBuildError {
             RenderPassBuildError::DepthTextureFormatNotSupported(format) => {
                 write!(f, "Depth texture format {:?} is not supported", format)
             }
+            RenderPassBuildError::DepthTextureNoStencil => {
+                write!(f, "Stencil operations set, but the depth texture has no stencil aspect")
+            }
             RenderPassBuildError::SwapchainError(err) => write!(f, "Swapchain error: {}", err),
         }
     }
@@ -1846,9 +2930,10 @@ impl std::fmt::Display for RenderPassBuildError {
 #[derive(Clone, Debug, Hash)]
 pub(crate) struct IntermediateRenderPipeline {
     pub shader: (wgpu::ShaderModule, wgpu::ShaderModule),
-    pub vertex_attribute: (u64, Vec<wgpu::VertexAttribute>),
+    pub vertex_buffers: Vec<VertexAttributeLayout>,
     pub shader_entry: (String, String),
     pub layout: Vec<BindGroupLayout>,
+    pub push_constant_ranges: Vec<wgpu::PushConstantRange>,
     pub topology: ShaderTopology,
     pub cull_mode: Option<ShaderCullMode>,
     pub front_face: ShaderFrontFace,
@@ -1856,26 +2941,139 @@ pub(crate) struct IntermediateRenderPipeline {
     pub index_format: Option<IndexBufferSize>,
 }
 
+/// Splits the push constant ranges at every range boundary, so each piece is uploaded with
+/// exactly the stages of the ranges covering it, as wgpu requires when the ranges of the
+/// stages differ in size.
+#[cfg(not(target_arch = "wasm32"))]
+fn push_constant_segments(
+    ranges: &[wgpu::PushConstantRange],
+) -> Vec<(wgpu::ShaderStages, Range<u32>)> {
+    let mut bounds = ranges
+        .iter()
+        .flat_map(|range| [range.range.start, range.range.end])
+        .collect::<Vec<_>>();
+
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    bounds
+        .windows(2)
+        .filter_map(|bound| {
+            let stages = ranges
+                .iter()
+                .filter(|range| range.range.start <= bound[0] && bound[1] <= range.range.end)
+                .fold(wgpu::ShaderStages::NONE, |stages, range| stages | range.stages);
+
+            (!stages.is_empty()).then_some((stages, bound[0]..bound[1]))
+        })
+        .collect()
+}
+
+/// Dynamic offsets of each bind group, in binding order.
+pub(crate) type DynamicOffsets = Vec<(u32, Vec<u32>)>;
+
 #[derive(Debug, Clone)]
 pub(crate) struct RenderPassQueue {
     pub pipeline: wgpu::RenderPipeline,
     pub bind_group: Vec<(u32, wgpu::BindGroup)>,
+    pub dynamic_offsets: DynamicOffsets,
 
-    pub vbo: Option<wgpu::Buffer>,
-    pub ibo: Option<wgpu::Buffer>,
+    pub vbo: Option<BufferRange>,
+    pub vbo_slots: Vec<(u32, BufferRange)>,
+    pub ibo: Option<BufferRange>,
     pub itype: Option<wgpu::IndexFormat>,
 
     pub viewport: Option<(RectF, f32, f32)>,
     pub scissor: Option<RectF>,
+    pub stencil_reference: u32,
+    pub topology: wgpu::PrimitiveTopology,
 
     pub ty: DrawCallType,
     pub push_constant: Option<Vec<u8>>,
+    pub push_constant_ranges: Vec<wgpu::PushConstantRange>,
+}
+
+impl RenderPassQueue {
+    /// Returns true if `next` is a direct draw starting at `end` with the same state as this
+    /// one, so both can be issued as a single draw call.
+    ///
+    /// Only list topologies are merged, joining two strips would connect their last and first
+    /// vertices.
+    pub fn can_merge(&self, next: &RenderPassQueue, end: u32) -> bool {
+        let is_list = matches!(
+            self.topology,
+            wgpu::PrimitiveTopology::PointList
+                | wgpu::PrimitiveTopology::LineList
+                | wgpu::PrimitiveTopology::TriangleList
+        );
+
+        if !is_list {
+            return false;
+        }
+
+        let continues = match (&self.ty, &next.ty) {
+            (
+                DrawCallType::Direct {
+                    vertex_offset,
+                    num_of_instances,
+                    ..
+                },
+                DrawCallType::Direct {
+                    ranges: next_ranges,
+                    vertex_offset: next_vertex_offset,
+                    num_of_instances: next_num_of_instances,
+                },
+            ) => {
+                next_ranges.start == end
+                    && vertex_offset == next_vertex_offset
+                    && num_of_instances == next_num_of_instances
+            }
+            _ => false,
+        };
+
+        continues
+            && self.pipeline == next.pipeline
+            && self.bind_group == next.bind_group
+            && self.dynamic_offsets == next.dynamic_offsets
+            && self.vbo == next.vbo
+            && self.vbo_slots == next.vbo_slots
+            && self.ibo == next.ibo
+            && self.itype == next.itype
+            && self.viewport == next.viewport
+            && self.scissor == next.scissor
+            && self.stencil_reference == next.stencil_reference
+            && self.push_constant == next.push_constant
+    }
 }
 
 #[derive(Clone, Debug)]
 pub(crate) enum RenderShaderBinding {
     Intermediate(IntermediateRenderPipeline),
-    Pipeline(RenderPipeline),
+    Pipeline(RenderPipelineInner),
+}
+
+impl RenderShaderBinding {
+    pub fn topology(&self) -> wgpu::PrimitiveTopology {
+        match self {
+            RenderShaderBinding::Intermediate(shader) => shader.topology.into(),
+            RenderShaderBinding::Pipeline(pipeline) => pipeline.pipeline_desc.primitive_state.topology,
+        }
+    }
+}
+
+/// Returns the triangles drawn from `count` vertices or indices.
+/// The wgpu buffer holding `buffer` and `offset` within it, pooled buffers start inside a slab.
+fn indirect_range(buffer: &Buffer, offset: u64) -> (wgpu::Buffer, u64) {
+    let inner = buffer.inner.borrow();
+    (inner.buffer.clone(), inner.offset + offset)
+}
+
+fn triangle_count(topology: wgpu::PrimitiveTopology, count: u32) -> u64 {
+    match topology {
+        wgpu::PrimitiveTopology::TriangleList => (count / 3) as u64,
+        wgpu::PrimitiveTopology::TriangleStrip => count.saturating_sub(2) as u64,
+        _ => 0,
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1890,4 +3088,76 @@ pub enum DrawCallType {
         buffer: wgpu::Buffer,
         offset: u64,
     },
+
+    MultiInDirect {
+        buffer: wgpu::Buffer,
+        offset: u64,
+        count: u32,
+    },
+
+    MultiInDirectCount {
+        buffer: wgpu::Buffer,
+        offset: u64,
+        count_buffer: wgpu::Buffer,
+        count_offset: u64,
+        max_count: u32,
+    },
+}
+
+/// Arguments of a non-indexed indirect draw, as read by [RenderPass::draw_indirect] and
+/// [RenderPass::multi_draw_indirect].
+///
+/// To write it from a compute shader, use this WGSL struct:
+/// ```wgsl
+/// struct DrawIndirectArgs {
+///     vertex_count: u32,
+///     instance_count: u32,
+///     first_vertex: u32,
+///     first_instance: u32,
+/// };
+/// ```
+///
+/// `first_instance` must be 0 unless the adapter supports `INDIRECT_FIRST_INSTANCE`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct DrawIndirectArgs {
+    pub vertex_count: u32,
+    pub instance_count: u32,
+    pub first_vertex: u32,
+    pub first_instance: u32,
+}
+
+impl DrawIndirectArgs {
+    /// Size in bytes, the stride between draws in an indirect buffer.
+    pub const SIZE: u64 = std::mem::size_of::<Self>() as u64;
+}
+
+/// Arguments of an indexed indirect draw, as read by [RenderPass::draw_indexed_indirect]
+/// and [RenderPass::multi_draw_indexed_indirect].
+///
+/// To write it from a compute shader, use this WGSL struct:
+/// ```wgsl
+/// struct DrawIndexedIndirectArgs {
+///     index_count: u32,
+///     instance_count: u32,
+///     first_index: u32,
+///     base_vertex: i32,
+///     first_instance: u32,
+/// };
+/// ```
+///
+/// `first_instance` must be 0 unless the adapter supports `INDIRECT_FIRST_INSTANCE`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct DrawIndexedIndirectArgs {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub base_vertex: i32,
+    pub first_instance: u32,
+}
+
+impl DrawIndexedIndirectArgs {
+    /// Size in bytes, the stride between draws in an indirect buffer.
+    pub const SIZE: u64 = std::mem::size_of::<Self>() as u64;
 }
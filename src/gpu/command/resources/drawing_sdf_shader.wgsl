@@ -0,0 +1,63 @@
+// Vertex Shader
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) texCoord: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) texCoord: vec2<f32>,
+};
+
+@vertex
+fn main_vertex(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = vec4<f32>(input.position, 1.0);
+    output.color = input.color;
+    output.texCoord = input.texCoord;
+    return output;
+}
+
+// Fragment Shader
+struct ShapeParams {
+    // Size of the drawn quad in pixels, including the anti-aliasing padding.
+    size: vec2<f32>,
+    // Half extents of the shape in pixels.
+    half_extents: vec2<f32>,
+    radius: f32,
+    // Outline thickness in pixels, zero means filled.
+    thickness: f32,
+    _padding: vec2<f32>,
+};
+
+@group(0) @binding(0) var myTexture: texture_2d<f32>;
+@group(0) @binding(1) var mySampler: sampler;
+@group(0) @binding(2) var<uniform> shape: ShapeParams;
+
+struct FragmentInput {
+    @location(0) color: vec4<f32>,
+    @location(1) texCoord: vec2<f32>,
+};
+
+fn sd_rounded_box(p: vec2<f32>, half_extents: vec2<f32>, radius: f32) -> f32 {
+    let q = abs(p) - half_extents + vec2<f32>(radius);
+    return length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+}
+
+@fragment
+fn main_fragment(input: FragmentInput) -> @location(0) vec4<f32> {
+    let textureColor = textureSample(myTexture, mySampler, input.texCoord);
+
+    let p = (input.texCoord - vec2<f32>(0.5)) * shape.size;
+    var d = sd_rounded_box(p, shape.half_extents, shape.radius);
+    if (shape.thickness > 0.0) {
+        d = abs(d + shape.thickness * 0.5) - shape.thickness * 0.5;
+    }
+
+    let aa = max(fwidth(d), 0.0001);
+    let coverage = 1.0 - smoothstep(-aa * 0.5, aa * 0.5, d);
+
+    return vec4<f32>(input.color.rgb, input.color.a * coverage) * textureColor;
+}
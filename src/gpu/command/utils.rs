@@ -1,11 +1,15 @@
+use std::num::NonZeroU64;
+
+use crate::gpu::texture::TextureFormat;
+
 #[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub enum BindGroupType {
-    Uniform(wgpu::Buffer),
+    Uniform(BufferRange),
     Texture(wgpu::TextureView),
-    TextureStorage(wgpu::TextureView),
+    TextureStorage(wgpu::TextureView, TextureFormat),
     Sampler(wgpu::Sampler),
-    Storage(wgpu::Buffer),
+    Storage(BufferRange),
 }
 
 impl std::fmt::Display for BindGroupType {
@@ -13,9 +17,53 @@ impl std::fmt::Display for BindGroupType {
         match self {
             BindGroupType::Uniform(_) => write!(f, "Uniform"),
             BindGroupType::Texture(_) => write!(f, "Texture"),
-            BindGroupType::TextureStorage(_) => write!(f, "TextureStorage"),
+            BindGroupType::TextureStorage(_, format) => write!(f, "TextureStorage({:?})", format),
             BindGroupType::Sampler(_) => write!(f, "Sampler"),
             BindGroupType::Storage(_) => write!(f, "Storage"),
         }
     }
-}
\ No newline at end of file
+}
+
+/// The part of a wgpu buffer used by a [Buffer](crate::gpu::buffer::Buffer), the whole buffer
+/// unless it was sub-allocated from a [BufferPool](crate::gpu::buffer::pool::BufferPool).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BufferRange {
+    pub buffer: wgpu::Buffer,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl BufferRange {
+    fn is_whole(&self) -> bool {
+        self.offset == 0 && self.size == self.buffer.size()
+    }
+
+    /// Binding of the range, or of `size` bytes from its start for dynamic offset bindings.
+    pub fn binding(&self, size: Option<NonZeroU64>) -> wgpu::BufferBinding<'_> {
+        let size = match size {
+            Some(size) => Some(size),
+            None if self.is_whole() => None,
+            None => NonZeroU64::new(self.size),
+        };
+
+        wgpu::BufferBinding {
+            buffer: &self.buffer,
+            offset: self.offset,
+            size,
+        }
+    }
+
+    pub fn slice(&self) -> wgpu::BufferSlice<'_> {
+        self.buffer.slice(self.offset..self.offset + self.size)
+    }
+}
+
+impl From<wgpu::Buffer> for BufferRange {
+    fn from(buffer: wgpu::Buffer) -> Self {
+        Self {
+            offset: 0,
+            size: buffer.size(),
+            buffer,
+        }
+    }
+}
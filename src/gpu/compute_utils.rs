@@ -0,0 +1,550 @@
+//! Ready-made compute passes reducing a texture, to build auto-exposure or LOD tooling without
+//! writing WGSL.
+//!
+//! - [MipGenerator] fills the mip chain of a texture from its first level.
+//! - [Histogram] counts the pixels of a texture by luminance.
+//! - [MinMaxReduction] finds the smallest and largest value of each channel of a texture.
+//!
+//! The sources are read with `textureLoad`, they must be filterable float textures created
+//! with [TextureUsage::Sampler]. The results of [Histogram] and [MinMaxReduction] stay on the
+//! GPU in a storage buffer, which can be bound to a later pass or read back once the command
+//! buffer was submitted.
+//!
+//! ```rust,ignore
+//! let mut histogram = gpu::compute_utils::Histogram::new(&mut gpu)?;
+//! histogram.set_range(-8.0, 4.0);
+//!
+//! let mut cmd = gpu.begin_command()?;
+//! histogram.record(&mut cmd, &hdr_target)?;
+//! cmd.end(false);
+//!
+//! let bins = histogram.read()?;
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{math::Point2, utils::ArcRef};
+
+use super::{
+    GPU, GPUInner,
+    buffer::{Buffer, BufferBuilder, BufferError, BufferUsage},
+    command::{
+        CommandBuffer,
+        computepass::{ComputePassBuildError, ComputePassError},
+    },
+    shader::compute::{ComputeShader, ComputeShaderBuilder},
+    texture::{Texture, TextureError, TextureFormat, TextureUsage, view::TextureViewDesc},
+};
+
+/// Number of bins of a [Histogram].
+pub const HISTOGRAM_BINS: usize = 256;
+
+const WORKGROUP_SIZE: u32 = 8;
+const REDUCTION_WORKGROUP_SIZE: u32 = 16;
+
+/// Fills the mip levels of a texture by averaging 2x2 blocks of the level above.
+///
+/// The texture must be created with a mip count, [TextureUsage::Sampler] and
+/// [TextureBuilder::set_storage_binding](super::texture::TextureBuilder::set_storage_binding),
+/// in a format writable by a storage binding: `Rgba8Unorm`, `Rgba8Snorm` or `Rgba16Float`.
+/// Every array layer is processed.
+///
+/// ```rust,ignore
+/// let mut mips = gpu::compute_utils::MipGenerator::new(&mut gpu);
+///
+/// texture.write_region(&pixels, rect)?;
+/// mips.generate(&mut cmd, &texture)?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct MipGenerator {
+    graphics: ArcRef<GPUInner>,
+    /// One shader per storage format, compiled the first time a texture of that format is used.
+    shaders: HashMap<TextureFormat, ComputeShader>,
+}
+
+impl MipGenerator {
+    pub fn new(gpu: &mut GPU) -> Self {
+        Self {
+            graphics: ArcRef::clone(&gpu.inner),
+            shaders: HashMap::new(),
+        }
+    }
+
+    /// Returns true if the mip levels of a texture in this format can be generated.
+    pub fn is_format_supported(format: TextureFormat) -> bool {
+        storage_format_name(format).is_some()
+    }
+
+    /// Records the generation of every mip level of `texture` from its first level.
+    ///
+    /// Does nothing for a texture with a single mip level.
+    pub fn generate(
+        &mut self,
+        cmd: &mut CommandBuffer,
+        texture: &Texture,
+    ) -> Result<(), ComputeUtilsError> {
+        let usages = texture.usages();
+        if !usages.contains(TextureUsage::Sampler) || !usages.contains(TextureUsage::StorageBinding)
+        {
+            return Err(ComputeUtilsError::InvalidTexture);
+        }
+
+        let format = texture.format();
+        let Some(format_name) = storage_format_name(format) else {
+            return Err(ComputeUtilsError::UnsupportedFormat(format));
+        };
+
+        let mip_level_count = texture.mip_level_count();
+        if mip_level_count <= 1 {
+            return Ok(());
+        }
+
+        if !self.shaders.contains_key(&format) {
+            let shader = ComputeShaderBuilder::new(ArcRef::clone(&self.graphics))
+                .set_source(&mip_source(format_name))
+                .set_label("Mip Generator Shader")
+                .build()
+                .map_err(ComputeUtilsError::Shader)?;
+
+            self.shaders.insert(format, shader);
+        }
+
+        let shader = &self.shaders[&format];
+
+        let mut pass = cmd
+            .begin_computepass()
+            .map_err(ComputeUtilsError::ComputePass)?;
+
+        pass.set_shader(Some(shader));
+
+        for layer in 0..texture.array_layer_count() {
+            for level in 1..mip_level_count {
+                let src = texture
+                    .create_view(mip_view(level - 1, layer))
+                    .map_err(ComputeUtilsError::Texture)?;
+                let dst = texture
+                    .create_view(mip_view(level, layer))
+                    .map_err(ComputeUtilsError::Texture)?;
+
+                pass.set_attachment_texture_view(0, 0, Some(&src))
+                    .map_err(ComputeUtilsError::Pass)?;
+                pass.set_attachment_texture_storage_view(0, 1, Some(&dst))
+                    .map_err(ComputeUtilsError::Pass)?;
+
+                let (x, y) = workgroups(dst.size(), WORKGROUP_SIZE);
+                pass.dispatch(x, y, 1);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Uniform parameters of the histogram pass, must match `HistogramParams` in
+/// `HISTOGRAM_SOURCE`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct HistogramParams {
+    min_log2: f32,
+    inv_log2_range: f32,
+    padding: [f32; 2],
+}
+
+/// Counts the pixels of a texture in [HISTOGRAM_BINS] bins by their luminance, eg. to compute
+/// the average brightness of a frame for auto-exposure.
+///
+/// Bin 0 holds the black pixels, bins 1 to 255 split the `log2` luminance range given to
+/// [Histogram::set_range] evenly. Pixels outside of the range go to the first or last bin.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    shader: ComputeShader,
+    bins: Buffer,
+
+    min_log2: f32,
+    max_log2: f32,
+}
+
+impl Histogram {
+    /// Creates a histogram over the `log2` luminance range `-10.0..2.0`.
+    pub fn new(gpu: &mut GPU) -> Result<Self, ComputeUtilsError> {
+        let graphics = ArcRef::clone(&gpu.inner);
+
+        let shader = ComputeShaderBuilder::new(ArcRef::clone(&graphics))
+            .set_source(HISTOGRAM_SOURCE)
+            .set_label("Histogram Shader")
+            .build()
+            .map_err(ComputeUtilsError::Shader)?;
+
+        let bins = BufferBuilder::<u32>::new(ArcRef::clone(&graphics))
+            .set_data_slice(&[0; HISTOGRAM_BINS])
+            .set_usage(BufferUsage::STORAGE | BufferUsage::COPY_SRC | BufferUsage::COPY_DST)
+            .set_label("Histogram Bins")
+            .build()
+            .map_err(ComputeUtilsError::Buffer)?;
+
+        Ok(Self {
+            shader,
+            bins,
+            min_log2: -10.0,
+            max_log2: 2.0,
+        })
+    }
+
+    /// Sets the `log2` luminance range split by the bins, `max_log2` must be above `min_log2`.
+    pub fn set_range(&mut self, min_log2: f32, max_log2: f32) {
+        self.min_log2 = min_log2;
+        self.max_log2 = max_log2.max(min_log2 + 0.001);
+    }
+
+    pub fn range(&self) -> (f32, f32) {
+        (self.min_log2, self.max_log2)
+    }
+
+    /// The storage buffer holding the [HISTOGRAM_BINS] `u32` counts of the last record.
+    pub fn buffer(&self) -> &Buffer {
+        &self.bins
+    }
+
+    /// Records the histogram of the first mip level of `src`, replacing the previous counts.
+    pub fn record(
+        &mut self,
+        cmd: &mut CommandBuffer,
+        src: &Texture,
+    ) -> Result<(), ComputeUtilsError> {
+        validate_source(src)?;
+
+        let params = HistogramParams {
+            min_log2: self.min_log2,
+            inv_log2_range: 1.0 / (self.max_log2 - self.min_log2),
+            padding: [0.0; 2],
+        };
+
+        cmd.write_buffer_raw(&[0u32; HISTOGRAM_BINS], &self.bins);
+
+        let mut pass = cmd
+            .begin_computepass()
+            .map_err(ComputeUtilsError::ComputePass)?;
+
+        pass.set_shader(Some(&self.shader));
+        pass.set_attachment_texture(0, 0, Some(src))
+            .map_err(ComputeUtilsError::Pass)?;
+        pass.set_attachment_storage(0, 1, Some(&self.bins))
+            .map_err(ComputeUtilsError::Pass)?;
+        pass.set_attachment_uniform_raw(0, 2, Some(&[params]))
+            .map_err(ComputeUtilsError::Pass)?;
+
+        let (x, y) = workgroups(src.size(), REDUCTION_WORKGROUP_SIZE);
+        pass.dispatch(x, y, 1);
+
+        Ok(())
+    }
+
+    /// Reads the counts back, blocking until the GPU is done.
+    ///
+    /// The command buffer of the last [Histogram::record] must have been submitted.
+    pub fn read(&self) -> Result<Vec<u32>, ComputeUtilsError> {
+        self.bins.read::<u32>().map_err(ComputeUtilsError::Buffer)
+    }
+
+    /// Luminance at the center of a bin, 0.0 for the first bin.
+    pub fn bin_luminance(&self, bin: usize) -> f32 {
+        if bin == 0 {
+            return 0.0;
+        }
+
+        let t = (bin as f32 - 0.5) / (HISTOGRAM_BINS - 1) as f32;
+        (self.min_log2 + t * (self.max_log2 - self.min_log2)).exp2()
+    }
+}
+
+/// Smallest and largest values of each channel of a texture, see [MinMaxReduction].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MinMax {
+    pub min: [f32; 4],
+    pub max: [f32; 4],
+}
+
+/// Finds the smallest and largest value of each channel of a texture, eg. the depth range of a
+/// linear depth target or the brightest pixel of an HDR frame.
+///
+/// The result is kept in a storage buffer of 8 `u32`, the minimums of the 4 channels then the
+/// maximums. The floats are stored with their bits reordered so they compare as integers, use
+/// [MinMaxReduction::read] to decode them.
+#[derive(Clone, Debug)]
+pub struct MinMaxReduction {
+    shader: ComputeShader,
+    result: Buffer,
+}
+
+impl MinMaxReduction {
+    pub fn new(gpu: &mut GPU) -> Result<Self, ComputeUtilsError> {
+        let graphics = ArcRef::clone(&gpu.inner);
+
+        let shader = ComputeShaderBuilder::new(ArcRef::clone(&graphics))
+            .set_source(MIN_MAX_SOURCE)
+            .set_label("Min Max Reduction Shader")
+            .build()
+            .map_err(ComputeUtilsError::Shader)?;
+
+        let result = BufferBuilder::<u32>::new(ArcRef::clone(&graphics))
+            .set_data_slice(&MIN_MAX_CLEAR)
+            .set_usage(BufferUsage::STORAGE | BufferUsage::COPY_SRC | BufferUsage::COPY_DST)
+            .set_label("Min Max Reduction Result")
+            .build()
+            .map_err(ComputeUtilsError::Buffer)?;
+
+        Ok(Self { shader, result })
+    }
+
+    /// The storage buffer holding the encoded result of the last record.
+    pub fn buffer(&self) -> &Buffer {
+        &self.result
+    }
+
+    /// Records the reduction of the first mip level of `src`, replacing the previous result.
+    pub fn record(
+        &mut self,
+        cmd: &mut CommandBuffer,
+        src: &Texture,
+    ) -> Result<(), ComputeUtilsError> {
+        validate_source(src)?;
+
+        cmd.write_buffer_raw(&MIN_MAX_CLEAR, &self.result);
+
+        let mut pass = cmd
+            .begin_computepass()
+            .map_err(ComputeUtilsError::ComputePass)?;
+
+        pass.set_shader(Some(&self.shader));
+        pass.set_attachment_texture(0, 0, Some(src))
+            .map_err(ComputeUtilsError::Pass)?;
+        pass.set_attachment_storage(0, 1, Some(&self.result))
+            .map_err(ComputeUtilsError::Pass)?;
+
+        let (x, y) = workgroups(src.size(), REDUCTION_WORKGROUP_SIZE);
+        pass.dispatch(x, y, 1);
+
+        Ok(())
+    }
+
+    /// Reads the result back, blocking until the GPU is done.
+    ///
+    /// The command buffer of the last [MinMaxReduction::record] must have been submitted.
+    pub fn read(&self) -> Result<MinMax, ComputeUtilsError> {
+        let data = self
+            .result
+            .read::<u32>()
+            .map_err(ComputeUtilsError::Buffer)?;
+
+        let mut result = MinMax::default();
+        for channel in 0..4 {
+            result.min[channel] = decode_ordered_float(data[channel]);
+            result.max[channel] = decode_ordered_float(data[channel + 4]);
+        }
+
+        Ok(result)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ComputeUtilsError {
+    /// The texture was not created with [TextureUsage::Sampler], or for a [MipGenerator]
+    /// without [TextureUsage::StorageBinding].
+    InvalidTexture,
+    /// The texture format can't be written by a storage binding, see
+    /// [MipGenerator::is_format_supported].
+    UnsupportedFormat(TextureFormat),
+    Shader(String),
+    ComputePass(ComputePassBuildError),
+    Pass(ComputePassError),
+    Texture(TextureError),
+    Buffer(BufferError),
+}
+
+impl std::fmt::Display for ComputeUtilsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComputeUtilsError::InvalidTexture => {
+                write!(f, "Texture is not sampleable or not a storage texture")
+            }
+            ComputeUtilsError::UnsupportedFormat(format) => {
+                write!(f, "Unsupported texture format: {:?}", format)
+            }
+            ComputeUtilsError::Shader(e) => write!(f, "Shader error: {}", e),
+            ComputeUtilsError::ComputePass(e) => write!(f, "Compute pass error: {:?}", e),
+            ComputeUtilsError::Pass(e) => write!(f, "Compute pass error: {}", e),
+            ComputeUtilsError::Texture(e) => write!(f, "Texture error: {}", e),
+            ComputeUtilsError::Buffer(e) => write!(f, "Buffer error: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for ComputeUtilsError {}
+
+/// Minimums at their largest and maximums at their smallest encoded value.
+const MIN_MAX_CLEAR: [u32; 8] = [u32::MAX, u32::MAX, u32::MAX, u32::MAX, 0, 0, 0, 0];
+
+/// Inverse of `encode` in `MIN_MAX_SOURCE`.
+fn decode_ordered_float(value: u32) -> f32 {
+    if value & 0x8000_0000 != 0 {
+        f32::from_bits(value & 0x7FFF_FFFF)
+    } else {
+        f32::from_bits(!value)
+    }
+}
+
+fn storage_format_name(format: TextureFormat) -> Option<&'static str> {
+    match format {
+        TextureFormat::Rgba8Unorm => Some("rgba8unorm"),
+        TextureFormat::Rgba8Snorm => Some("rgba8snorm"),
+        TextureFormat::Rgba16Float => Some("rgba16float"),
+        _ => None,
+    }
+}
+
+fn mip_view(level: u32, layer: u32) -> TextureViewDesc {
+    TextureViewDesc {
+        base_mip_level: level,
+        mip_level_count: Some(1),
+        base_array_layer: layer,
+        array_layer_count: Some(1),
+        ..Default::default()
+    }
+}
+
+fn validate_source(src: &Texture) -> Result<(), ComputeUtilsError> {
+    if !src.usages().contains(TextureUsage::Sampler) {
+        return Err(ComputeUtilsError::InvalidTexture);
+    }
+
+    Ok(())
+}
+
+fn workgroups(size: Point2, workgroup_size: u32) -> (u32, u32) {
+    (
+        (size.x as u32).div_ceil(workgroup_size),
+        (size.y as u32).div_ceil(workgroup_size),
+    )
+}
+
+fn mip_source(format: &str) -> String {
+    format!(
+        r#"
+@group(0) @binding(0) var src: texture_2d<f32>;
+@group(0) @binding(1) var dst: texture_storage_2d<{format}, write>;
+
+@compute @workgroup_size({workgroup}, {workgroup}, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {{
+    let size = vec2<i32>(textureDimensions(dst));
+    let pos = vec2<i32>(id.xy);
+    if (pos.x >= size.x || pos.y >= size.y) {{
+        return;
+    }}
+
+    // Odd sized levels clamp the last row and column of the block
+    let last = vec2<i32>(textureDimensions(src)) - vec2<i32>(1, 1);
+    let base = pos * 2;
+
+    var color = textureLoad(src, base, 0);
+    color += textureLoad(src, min(base + vec2<i32>(1, 0), last), 0);
+    color += textureLoad(src, min(base + vec2<i32>(0, 1), last), 0);
+    color += textureLoad(src, min(base + vec2<i32>(1, 1), last), 0);
+
+    textureStore(dst, pos, color * 0.25);
+}}
+"#,
+        format = format,
+        workgroup = WORKGROUP_SIZE,
+    )
+}
+
+const HISTOGRAM_SOURCE: &str = r#"
+struct HistogramParams {
+    min_log2: f32,
+    inv_log2_range: f32,
+    padding: vec2<f32>,
+};
+
+@group(0) @binding(0) var src: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> bins: array<atomic<u32>, 256>;
+@group(0) @binding(2) var<uniform> params: HistogramParams;
+
+// Counted per workgroup first, so the global bins only see one add per workgroup
+var<workgroup> local_bins: array<atomic<u32>, 256>;
+
+@compute @workgroup_size(16, 16, 1)
+fn main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) index: u32,
+) {
+    atomicStore(&local_bins[index], 0u);
+    workgroupBarrier();
+
+    let size = textureDimensions(src);
+    if (id.x < size.x && id.y < size.y) {
+        let color = textureLoad(src, vec2<i32>(id.xy), 0);
+        let luminance = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+
+        var bin = 0u;
+        if (luminance > 0.00001) {
+            let t = clamp((log2(luminance) - params.min_log2) * params.inv_log2_range, 0.0, 1.0);
+            bin = u32(t * 254.0 + 1.0);
+        }
+
+        atomicAdd(&local_bins[bin], 1u);
+    }
+
+    workgroupBarrier();
+
+    let count = atomicLoad(&local_bins[index]);
+    if (count > 0u) {
+        atomicAdd(&bins[index], count);
+    }
+}
+"#;
+
+const MIN_MAX_SOURCE: &str = r#"
+@group(0) @binding(0) var src: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> result: array<atomic<u32>, 8>;
+
+var<workgroup> local_min: array<atomic<u32>, 4>;
+var<workgroup> local_max: array<atomic<u32>, 4>;
+
+// Maps the float bits to an unsigned integer with the same ordering
+fn encode(value: f32) -> u32 {
+    let bits = bitcast<u32>(value);
+    if ((bits & 0x80000000u) != 0u) {
+        return ~bits;
+    }
+    return bits | 0x80000000u;
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) index: u32,
+) {
+    if (index < 4u) {
+        atomicStore(&local_min[index], 0xFFFFFFFFu);
+        atomicStore(&local_max[index], 0u);
+    }
+    workgroupBarrier();
+
+    let size = textureDimensions(src);
+    if (id.x < size.x && id.y < size.y) {
+        let color = textureLoad(src, vec2<i32>(id.xy), 0);
+        for (var channel = 0u; channel < 4u; channel++) {
+            let value = encode(color[channel]);
+            atomicMin(&local_min[channel], value);
+            atomicMax(&local_max[channel], value);
+        }
+    }
+
+    workgroupBarrier();
+
+    if (index < 4u) {
+        atomicMin(&result[index], atomicLoad(&local_min[index]));
+        atomicMax(&result[index + 4u], atomicLoad(&local_max[index]));
+    }
+}
+"#;
@@ -0,0 +1,299 @@
+//! Immediate-mode 3D debug lines, to visualize bounds, transforms and camera setups while
+//! working on a scene.
+//!
+//! [DebugDraw3D] collects line primitives during the frame and draws them all at once with
+//! [DebugDraw3D::flush], in a pass with a depth target to hide the lines behind the scene or
+//! on top of it, see [DebugDraw3D::set_depth_test].
+//!
+//! ```rust,ignore
+//! let mut debug = gpu::debug_draw::DebugDraw3D::new(&mut gpu)?;
+//!
+//! // Every frame
+//! debug.grid(20.0, 20, Color::DARKGRAY);
+//! debug.aabb(mesh_min, mesh_max, Color::YELLOW);
+//!
+//! debug.set_depth_test(false);
+//! debug.axes(&player.transform, 1.0);
+//!
+//! debug.flush(&mut rp, &camera)?;
+//! ```
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::math::{Camera3D, CameraUniform, Color, Transform, Vector3};
+
+use super::{
+    GPU,
+    buffer::{BufferError, BufferUsage, gpu_vec::GpuVec, uniform::Uniform},
+    command::renderpass::RenderPass,
+    shader::{GraphicsShader, GraphicsShaderBuilder, ShaderTopology},
+};
+
+/// Number of segments of each circle of [DebugDraw3D::sphere].
+pub const SPHERE_SEGMENTS: u32 = 32;
+
+const SOURCE: &str = r#"
+struct Camera {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    view_projection: mat4x4<f32>,
+    position: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn main_vertex(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = camera.view_projection * vec4<f32>(input.position, 1.0);
+    output.color = input.color;
+    return output;
+}
+
+struct FragmentInput {
+    @location(0) color: vec4<f32>,
+};
+
+@fragment
+fn main_fragment(input: FragmentInput) -> @location(0) vec4<f32> {
+    return input.color;
+}
+"#;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+struct DebugVertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+impl DebugVertex {
+    fn new(position: Vector3, color: Color) -> Self {
+        Self {
+            position: [position.x, position.y, position.z],
+            color: [color.r, color.g, color.b, color.a],
+        }
+    }
+}
+
+/// Collects world space lines and draws them in one draw call per depth mode.
+///
+/// The lines drawn since the last [DebugDraw3D::flush] are kept in two vertex buffers, one
+/// for lines hidden by the scene and one for lines drawn over it, selected by
+/// [DebugDraw3D::set_depth_test] when a primitive is added.
+#[derive(Debug)]
+pub struct DebugDraw3D {
+    shader: GraphicsShader,
+    camera: Uniform<CameraUniform>,
+    /// Line vertices tested against the depth target.
+    depth_lines: GpuVec<DebugVertex>,
+    /// Line vertices drawn over the scene.
+    overlay_lines: GpuVec<DebugVertex>,
+    depth_test: bool,
+}
+
+impl DebugDraw3D {
+    pub fn new(gpu: &mut GPU) -> Result<Self, DebugDrawError> {
+        let mut shader = GraphicsShaderBuilder::new(gpu.inner.clone())
+            .set_source(SOURCE)
+            .set_label("Debug Draw Shader")
+            .build()
+            .map_err(DebugDrawError::Shader)?;
+
+        shader
+            .set_topology(ShaderTopology::LineList)
+            .map_err(DebugDrawError::Shader)?;
+
+        Ok(Self {
+            shader,
+            camera: gpu.create_uniform(CameraUniform::default())?,
+            depth_lines: gpu.create_gpu_vec(BufferUsage::VERTEX)?,
+            overlay_lines: gpu.create_gpu_vec(BufferUsage::VERTEX)?,
+            depth_test: true,
+        })
+    }
+
+    /// Set whether the following primitives are hidden behind the scene, enabled by default.
+    ///
+    /// Without a depth target in the pass, every line is drawn over the scene.
+    pub fn set_depth_test(&mut self, enabled: bool) {
+        self.depth_test = enabled;
+    }
+
+    pub fn depth_test(&self) -> bool {
+        self.depth_test
+    }
+
+    /// Number of lines collected since the last flush.
+    pub fn len(&self) -> usize {
+        (self.depth_lines.len() + self.overlay_lines.len()) / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.depth_lines.is_empty() && self.overlay_lines.is_empty()
+    }
+
+    /// Drops the lines collected since the last flush.
+    pub fn clear(&mut self) {
+        self.depth_lines.clear();
+        self.overlay_lines.clear();
+    }
+
+    pub fn line(&mut self, a: Vector3, b: Vector3, color: Color) {
+        let lines = if self.depth_test {
+            &mut self.depth_lines
+        } else {
+            &mut self.overlay_lines
+        };
+
+        lines.extend_from_slice(&[DebugVertex::new(a, color), DebugVertex::new(b, color)]);
+    }
+
+    /// Draws the edges of the axis aligned box between `min` and `max`.
+    pub fn aabb(&mut self, min: Vector3, max: Vector3, color: Color) {
+        let corner = |i: usize| {
+            Vector3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        };
+
+        // Corners differing by one bit share an edge
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    self.line(corner(i), corner(i | bit), color);
+                }
+            }
+        }
+    }
+
+    /// Draws three circles of the sphere, one around each axis.
+    pub fn sphere(&mut self, center: Vector3, radius: f32, color: Color) {
+        let point = |axis: usize, angle: f32| {
+            let (sin, cos) = angle.sin_cos();
+            let (sin, cos) = (sin * radius, cos * radius);
+
+            match axis {
+                0 => Vector3::new(center.x, center.y + cos, center.z + sin),
+                1 => Vector3::new(center.x + cos, center.y, center.z + sin),
+                _ => Vector3::new(center.x + cos, center.y + sin, center.z),
+            }
+        };
+
+        let step = std::f32::consts::TAU / SPHERE_SEGMENTS as f32;
+        for axis in 0..3 {
+            for i in 0..SPHERE_SEGMENTS {
+                let a = point(axis, i as f32 * step);
+                let b = point(axis, (i + 1) as f32 * step);
+                self.line(a, b, color);
+            }
+        }
+    }
+
+    /// Draws the X, Y and Z axes of `transform` in red, green and blue, `size` units long
+    /// before scaling.
+    pub fn axes(&mut self, transform: &Transform, size: f32) {
+        let origin = transform.transform_point(Vector3::ZERO);
+
+        let axes = [
+            (Vector3::new(size, 0.0, 0.0), Color::RED),
+            (Vector3::new(0.0, size, 0.0), Color::LIME),
+            (Vector3::new(0.0, 0.0, size), Color::BLUE),
+        ];
+
+        for (axis, color) in axes {
+            self.line(origin, transform.transform_point(axis), color);
+        }
+    }
+
+    /// Draws a grid of `cells` by `cells` squares on the XZ plane, `size` units wide and
+    /// centered on the origin.
+    pub fn grid(&mut self, size: f32, cells: u32, color: Color) {
+        let cells = cells.max(1);
+        let half = size * 0.5;
+        let step = size / cells as f32;
+
+        for i in 0..=cells {
+            let offset = -half + i as f32 * step;
+
+            self.line(
+                Vector3::new(offset, 0.0, -half),
+                Vector3::new(offset, 0.0, half),
+                color,
+            );
+            self.line(
+                Vector3::new(-half, 0.0, offset),
+                Vector3::new(half, 0.0, offset),
+                color,
+            );
+        }
+    }
+
+    /// Draws the lines collected since the last flush as seen by `camera`, then clears them.
+    ///
+    /// The shader and vertex buffer of `rp` are replaced, its depth test setting is kept.
+    pub fn flush(&mut self, rp: &mut RenderPass, camera: &Camera3D) -> Result<(), DebugDrawError> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        self.depth_lines.flush()?;
+        self.overlay_lines.flush()?;
+        self.camera.replace(camera.uniform());
+
+        let depth_test = rp.get_depth_test();
+
+        rp.set_shader(Some(&self.shader));
+        rp.set_attachment_uniform_value(0, 0, &self.camera);
+
+        for (lines, depth_test) in [(&self.depth_lines, true), (&self.overlay_lines, false)] {
+            if lines.is_empty() {
+                continue;
+            }
+
+            rp.set_depth_test(depth_test);
+            rp.set_gpu_buffer(Some(lines.buffer()), None);
+            rp.draw(0..lines.len() as u32, 1);
+        }
+
+        rp.set_depth_test(depth_test);
+        self.clear();
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum DebugDrawError {
+    Shader(String),
+    Buffer(BufferError),
+}
+
+impl std::fmt::Display for DebugDrawError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DebugDrawError::Shader(e) => write!(f, "Shader error: {}", e),
+            DebugDrawError::Buffer(e) => write!(f, "Buffer error: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for DebugDrawError {}
+
+impl From<BufferError> for DebugDrawError {
+    fn from(e: BufferError) -> Self {
+        DebugDrawError::Buffer(e)
+    }
+}
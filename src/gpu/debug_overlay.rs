@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+// The clock of std is not available in the browser
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+use crate::{
+    font::Font,
+    math::{Color, Vector2},
+    utils::ArcRef,
+};
+
+use super::{GPUInner, command::renderpass::RenderPass, stats::GpuStats};
+
+/// Number of frames shown in the frame time graph.
+pub const FRAME_HISTORY: usize = 120;
+
+const PADDING: f32 = 8.0;
+const GRAPH_HEIGHT: f32 = 48.0;
+const BAR_WIDTH: f32 = 2.0;
+
+/// Frame times above this many milliseconds are drawn as slow frames, 60 FPS with some slack.
+const SLOW_FRAME_MS: f32 = 17.5;
+
+const BACKGROUND: Color = Color::new_const(0.0, 0.0, 0.0, 0.7);
+const TEXT: Color = Color::new_const(1.0, 1.0, 1.0, 1.0);
+const BAR: Color = Color::new_const(0.3, 0.85, 0.4, 1.0);
+const SLOW_BAR: Color = Color::new_const(0.95, 0.3, 0.25, 1.0);
+const TARGET_LINE: Color = Color::new_const(1.0, 1.0, 1.0, 0.35);
+
+/// A panel showing the FPS, a graph of the last frame times and the [GpuStats] of the GPU,
+/// drawn with the drawing context of a render pass.
+///
+/// Frame times are measured between calls to [DebugOverlay::draw], so it must be drawn once
+/// per frame, usually last in the pass rendering to the window. The draw calls shown are those
+/// of the previous frame and include the overlay itself.
+///
+/// ```rust,ignore
+/// let mut overlay = gpu.create_debug_overlay();
+///
+/// // Every frame
+/// if input.key_pressed_once("F3") {
+///     overlay.toggle();
+/// }
+///
+/// overlay.draw(&mut rp);
+/// ```
+#[derive(Debug)]
+pub struct DebugOverlay {
+    graphics: ArcRef<GPUInner>,
+    /// Frame times in milliseconds, oldest first.
+    frame_times: VecDeque<f32>,
+    last_frame: Option<Instant>,
+    visible: bool,
+    position: Vector2,
+    font: Option<Font>,
+}
+
+impl DebugOverlay {
+    pub(crate) fn new(graphics: ArcRef<GPUInner>) -> Self {
+        Self {
+            graphics,
+            frame_times: VecDeque::with_capacity(FRAME_HISTORY),
+            last_frame: None,
+            visible: true,
+            position: Vector2::new(PADDING, PADDING),
+            font: None,
+        }
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows the overlay if hidden, hides it otherwise.
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Sets the top left corner of the panel, in pixels of the render target.
+    pub fn set_position(&mut self, position: Vector2) {
+        self.position = position;
+    }
+
+    /// Sets the font of the text, `None` to use the current font of the drawing context.
+    pub fn set_font(&mut self, font: Option<&Font>) {
+        self.font = font.cloned();
+    }
+
+    /// Average frame time in milliseconds over the graph history.
+    pub fn frame_time(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+
+        self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+    }
+
+    /// Frames per second, from the average frame time.
+    pub fn fps(&self) -> f32 {
+        let frame_time = self.frame_time();
+        if frame_time <= 0.0 {
+            return 0.0;
+        }
+
+        1000.0 / frame_time
+    }
+
+    /// Records the time since the last call, then draws the panel into `rp` if visible.
+    ///
+    /// Frame times are recorded even while hidden, the graph is up to date once shown.
+    pub fn draw(&mut self, rp: &mut RenderPass) {
+        self.record_frame();
+
+        if !self.visible {
+            return;
+        }
+
+        let stats = self.graphics.borrow().stats();
+        let lines = self.lines(&stats);
+
+        let Some(mut drawing) = rp.begin_drawing() else {
+            return;
+        };
+
+        if let Some(font) = &self.font {
+            drawing.set_font(font);
+        }
+
+        let font = drawing.get_font();
+        let line_height = font.line_height();
+
+        let text_width = lines
+            .iter()
+            .map(|line| font.calculate_text_size(line, None).x)
+            .fold(0.0, f32::max);
+        let graph_width = FRAME_HISTORY as f32 * BAR_WIDTH;
+
+        let width = text_width.max(graph_width) + PADDING * 2.0;
+        let height = line_height * lines.len() as f32 + GRAPH_HEIGHT + PADDING * 3.0;
+
+        drawing.draw_rect_filled(self.position, Vector2::new(width, height), BACKGROUND);
+
+        let mut pos = self.position + Vector2::new(PADDING, PADDING);
+        for line in &lines {
+            drawing.draw_text(line, pos, TEXT);
+            pos.y += line_height;
+        }
+
+        // Bars grow upwards from the bottom of the graph, scaled to the slowest frame
+        let graph_bottom = pos.y + PADDING + GRAPH_HEIGHT;
+        let max_time = self
+            .frame_times
+            .iter()
+            .copied()
+            .fold(SLOW_FRAME_MS * 2.0, f32::max);
+
+        for (i, time) in self.frame_times.iter().enumerate() {
+            let bar_height = (time / max_time * GRAPH_HEIGHT).max(1.0);
+            let color = if *time > SLOW_FRAME_MS { SLOW_BAR } else { BAR };
+
+            drawing.draw_rect_filled(
+                Vector2::new(pos.x + i as f32 * BAR_WIDTH, graph_bottom - bar_height),
+                Vector2::new(BAR_WIDTH, bar_height),
+                color,
+            );
+        }
+
+        let target_y = graph_bottom - SLOW_FRAME_MS / max_time * GRAPH_HEIGHT;
+        drawing.draw_line(
+            Vector2::new(pos.x, target_y),
+            Vector2::new(pos.x + graph_width, target_y),
+            1.0,
+            TARGET_LINE,
+        );
+    }
+
+    fn record_frame(&mut self) {
+        let now = Instant::now();
+
+        if let Some(last_frame) = self.last_frame.replace(now) {
+            if self.frame_times.len() == FRAME_HISTORY {
+                self.frame_times.pop_front();
+            }
+
+            let frame_time = now.duration_since(last_frame).as_secs_f32() * 1000.0;
+            self.frame_times.push_back(frame_time);
+        }
+    }
+
+    fn lines(&self, stats: &GpuStats) -> Vec<String> {
+        let (min, max) = self
+            .frame_times
+            .iter()
+            .fold((f32::MAX, 0.0f32), |(min, max), time| {
+                (min.min(*time), max.max(*time))
+            });
+        let min = if self.frame_times.is_empty() { 0.0 } else { min };
+
+        vec![
+            format!("{:.0} FPS  {:.2} ms", self.fps(), self.frame_time()),
+            format!("min {:.2} ms  max {:.2} ms", min, max),
+            format!(
+                "{} draw calls  {} triangles",
+                stats.last_frame.draw_calls, stats.last_frame.triangles
+            ),
+            format!(
+                "{} textures  {}",
+                stats.textures,
+                format_bytes(stats.texture_bytes)
+            ),
+            format!(
+                "{} buffers  {}",
+                stats.buffers,
+                format_bytes(stats.buffer_bytes)
+            ),
+            format!(
+                "{} pipelines  {} bind groups",
+                stats.graphics_pipelines + stats.compute_pipelines,
+                stats.bind_groups
+            ),
+        ]
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    const MIB: f64 = KIB * 1024.0;
+
+    let bytes = bytes as f64;
+    if bytes >= MIB {
+        format!("{:.1} MiB", bytes / MIB)
+    } else {
+        format!("{:.1} KiB", bytes / KIB)
+    }
+}
@@ -0,0 +1,68 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use super::buffer::pool::PoolAllocation;
+
+/// A texture or buffer dropped by the application while the GPU may still be using it.
+#[derive(Clone, Debug)]
+pub(crate) enum DeferredResource {
+    Texture(#[expect(dead_code, reason = "held to keep the texture alive")] wgpu::Texture),
+    Buffer(#[expect(dead_code, reason = "held to keep the buffer alive")] wgpu::Buffer),
+    /// Range of a buffer pool slab, recycled by the pool once released.
+    PoolAllocation(
+        #[expect(dead_code, reason = "held to return the range to its pool once released")]
+        Arc<PoolAllocation>,
+    ),
+}
+
+/// Keeps dropped resources alive until the submissions that may use them completed.
+///
+/// Every submission of a command buffer gets an increasing index, a resource dropped is released
+/// once the queue reports the submission following the drop as done. Releasing drops the last
+/// handle of the crate, the memory is freed unless a cached bind group still references it.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DeletionQueue {
+    submitted: u64,
+    completed: Arc<AtomicU64>,
+    pending: Vec<(u64, DeferredResource)>,
+}
+
+impl DeletionQueue {
+    /// Queues a resource until the next submission completed, a command buffer recording when
+    /// the resource was dropped may still use it.
+    pub fn defer(&mut self, resource: DeferredResource) {
+        self.pending.push((self.submitted + 1, resource));
+    }
+
+    /// Assigns the next index to a submission that was just made on the queue.
+    pub fn track_submission(&mut self, queue: &wgpu::Queue) {
+        self.submitted += 1;
+
+        let index = self.submitted;
+        let completed = Arc::clone(&self.completed);
+
+        queue.on_submitted_work_done(move || {
+            completed.fetch_max(index, Ordering::AcqRel);
+        });
+    }
+
+    /// Releases the resources whose submissions completed, the completion is only noticed once
+    /// the device was polled.
+    pub fn collect(&mut self) {
+        let completed = self.completed.load(Ordering::Acquire);
+        self.pending.retain(|(index, _)| *index > completed);
+    }
+
+    /// Releases every queued resource, only valid once the device is idle.
+    pub fn release_all(&mut self) {
+        self.completed.fetch_max(self.submitted, Ordering::AcqRel);
+        self.pending.clear();
+    }
+
+    /// Returns the number of resources waiting for their submission.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    error::{GpuError, GpuErrorKind},
+    pipeline::manager::{ComputePipelineDesc, GraphicsPipelineDesc},
+};
+
+/// Number of pipeline descriptors kept for the bundles.
+const PIPELINE_HISTORY: usize = 16;
+
+/// Number of passes recorded per frame, for applications that never end a frame.
+const MAX_FRAME_PASSES: usize = 256;
+
+/// Validation errors after which a bundle is written, a single one is often harmless.
+const VALIDATION_ERROR_THRESHOLD: u32 = 3;
+
+/// State of the GPU gathered to investigate a crash or a rendering bug, see
+/// [GPU::generate_diagnostics](super::GPU::generate_diagnostics).
+///
+/// The [Display](std::fmt::Display) implementation formats the report as the text written in
+/// the bundles.
+#[derive(Clone, Debug, Default)]
+pub struct DiagnosticsReport {
+    /// Why the report was generated, eg. the device lost message.
+    pub reason: String,
+    /// Seconds since the unix epoch when the report was generated.
+    pub timestamp: u64,
+
+    pub adapter_name: String,
+    pub backend: String,
+    pub device_type: String,
+    pub driver: String,
+    pub limits: String,
+    pub features: String,
+
+    pub validation_errors: u32,
+    /// Descriptors of the last pipelines created, oldest first.
+    pub recent_pipelines: Vec<String>,
+    /// Passes recorded during the last finished frame.
+    pub last_frame_passes: Vec<String>,
+    /// Passes recorded so far in the current frame.
+    pub current_frame_passes: Vec<String>,
+    /// Last lines logged by the crate, oldest first.
+    pub log_tail: Vec<String>,
+}
+
+impl DiagnosticsReport {
+    /// Writes the report to `path`, or to a new file in `path` if it is a directory.
+    ///
+    /// Returns the path of the written file.
+    pub fn write_to(&self, path: impl AsRef<Path>) -> std::io::Result<PathBuf> {
+        let path = path.as_ref();
+
+        let file = if path.is_dir() {
+            path.join(format!("est-render-diagnostics-{}.txt", self.timestamp))
+        } else {
+            path.to_path_buf()
+        };
+
+        std::fs::write(&file, self.to_string())?;
+        Ok(file)
+    }
+}
+
+impl std::fmt::Display for DiagnosticsReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn section(
+            f: &mut std::fmt::Formatter<'_>,
+            title: &str,
+            lines: &[String],
+        ) -> std::fmt::Result {
+            writeln!(f)?;
+            writeln!(f, "== {} ({}) ==", title, lines.len())?;
+            for line in lines {
+                writeln!(f, "{}", line)?;
+            }
+            Ok(())
+        }
+
+        writeln!(f, "est-render {} diagnostics", env!("CARGO_PKG_VERSION"))?;
+        writeln!(f, "Reason: {}", self.reason)?;
+        writeln!(f, "Timestamp: {}", self.timestamp)?;
+
+        writeln!(f)?;
+        writeln!(f, "== Adapter ==")?;
+        writeln!(f, "Name: {}", self.adapter_name)?;
+        writeln!(f, "Backend: {}", self.backend)?;
+        writeln!(f, "Device type: {}", self.device_type)?;
+        writeln!(f, "Driver: {}", self.driver)?;
+        writeln!(f, "Features: {}", self.features)?;
+        writeln!(f, "Limits: {}", self.limits)?;
+        writeln!(f, "Validation errors: {}", self.validation_errors)?;
+
+        section(f, "Recent pipelines", &self.recent_pipelines)?;
+        section(f, "Last frame passes", &self.last_frame_passes)?;
+        section(f, "Current frame passes", &self.current_frame_passes)?;
+        section(f, "Log", &self.log_tail)
+    }
+}
+
+/// Keeps the state included in the diagnostic bundles, shared with the device callbacks.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DiagnosticsRecorder {
+    /// Where bundles are written automatically, [None] disables them.
+    pub path: Option<PathBuf>,
+
+    adapter: Option<wgpu::AdapterInfo>,
+    limits: Option<wgpu::Limits>,
+    features: Option<wgpu::Features>,
+
+    validation_errors: u32,
+    pipelines: VecDeque<String>,
+    passes: Vec<String>,
+    last_passes: Vec<String>,
+}
+
+impl DiagnosticsRecorder {
+    pub fn set_device(&mut self, adapter: &wgpu::Adapter, device: &wgpu::Device) {
+        self.adapter = Some(adapter.get_info());
+        self.limits = Some(device.limits());
+        self.features = Some(device.features());
+    }
+
+    pub fn record_graphics_pipeline(&mut self, desc: &GraphicsPipelineDesc) {
+        let targets = desc
+            .render_target
+            .iter()
+            .map(|(format, blend, _)| {
+                format!(
+                    "{:?}{}",
+                    format,
+                    if blend.is_some() { " (blend)" } else { "" }
+                )
+            })
+            .collect::<Vec<_>>();
+
+        self.record_pipeline(format!(
+            "Render pipeline '{}': entry ({}, {}), targets [{}], depth {:?}, topology {:?}, cull {:?}, msaa {}, {} bind groups, {} push constant ranges",
+            desc.label.as_deref().unwrap_or("unnamed"),
+            desc.entry_point.0,
+            desc.entry_point.1,
+            targets.join(", "),
+            desc.depth_stencil,
+            desc.primitive_state.topology,
+            desc.primitive_state.cull_mode,
+            desc.msaa_count,
+            desc.bind_group_layout.len(),
+            desc.push_constant_ranges.len(),
+        ));
+    }
+
+    pub fn record_compute_pipeline(&mut self, desc: &ComputePipelineDesc) {
+        self.record_pipeline(format!(
+            "Compute pipeline '{}': entry {}, {} bind groups, {} push constant ranges",
+            desc.label.as_deref().unwrap_or("unnamed"),
+            desc.entry_point,
+            desc.bind_group_layout.len(),
+            desc.push_constant_ranges.len(),
+        ));
+    }
+
+    fn record_pipeline(&mut self, pipeline: String) {
+        if self.pipelines.len() == PIPELINE_HISTORY {
+            self.pipelines.pop_front();
+        }
+
+        self.pipelines.push_back(pipeline);
+    }
+
+    pub fn record_pass(&mut self, pass: String) {
+        if self.passes.len() < MAX_FRAME_PASSES {
+            self.passes.push(pass);
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        self.last_passes = std::mem::take(&mut self.passes);
+    }
+
+    /// Counts the error, and writes a bundle when validation keeps failing or when the crate
+    /// is about to panic on it.
+    pub fn record_error(&mut self, error: &GpuError, panicking: bool) {
+        crate::utils::record_log("GPU ERROR", &error.to_string());
+
+        if error.kind == GpuErrorKind::Validation {
+            self.validation_errors += 1;
+        }
+
+        if panicking {
+            self.write_bundle(&format!("Uncaptured GPU error: {}", error));
+        } else if error.kind == GpuErrorKind::Validation
+            && self.validation_errors == VALIDATION_ERROR_THRESHOLD
+        {
+            self.write_bundle(&format!(
+                "Repeated validation errors, last: {}",
+                error.message
+            ));
+        }
+    }
+
+    pub fn record_device_lost(&mut self, message: &str) {
+        self.write_bundle(&format!("Device lost: {}", message));
+    }
+
+    pub fn report(&self, reason: &str) -> DiagnosticsReport {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+
+        let mut report = DiagnosticsReport {
+            reason: reason.to_string(),
+            timestamp,
+            limits: self
+                .limits
+                .as_ref()
+                .map(|l| format!("{:?}", l))
+                .unwrap_or_default(),
+            features: self
+                .features
+                .map(|f| format!("{:?}", f))
+                .unwrap_or_default(),
+            validation_errors: self.validation_errors,
+            recent_pipelines: self.pipelines.iter().cloned().collect(),
+            last_frame_passes: self.last_passes.clone(),
+            current_frame_passes: self.passes.clone(),
+            log_tail: crate::utils::log_tail(),
+            ..Default::default()
+        };
+
+        if let Some(adapter) = &self.adapter {
+            report.adapter_name = adapter.name.clone();
+            report.backend = format!("{:?}", adapter.backend);
+            report.device_type = format!("{:?}", adapter.device_type);
+            report.driver = format!("{} {}", adapter.driver, adapter.driver_info);
+        }
+
+        report
+    }
+
+    fn write_bundle(&self, reason: &str) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        match self.report(reason).write_to(path) {
+            Ok(file) => {
+                crate::error_log!("GPU diagnostics written to {}", file.display());
+            }
+            Err(err) => {
+                crate::error_log!(
+                    "Failed to write GPU diagnostics to {}: {}",
+                    path.display(),
+                    err
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,691 @@
+//! Ready-made compute passes for common post-processing effects.
+//!
+//! The effects read any float texture created with [TextureUsage::Sampler] and write into a
+//! `Rgba8Unorm` texture created with [TextureBuilder::set_storage_binding](super::texture::TextureBuilder::set_storage_binding),
+//! which can be made with [create_target]. [Tonemap] is the exception, it draws to the window
+//! surface.
+//!
+//! ```rust,ignore
+//! let mut blur = gpu::effects::Blur::new(&mut gpu, 8)?;
+//! let shadow = gpu::effects::create_target(&mut gpu, texture.size())?;
+//!
+//! let mut cmd = gpu.begin_command()?;
+//! blur.apply(&mut cmd, &texture, &shadow)?;
+//! ```
+
+use crate::{math::Point2, utils::ArcRef};
+
+use super::{
+    GPU, GPUInner,
+    buffer::{Buffer, BufferBuilder, BufferError, BufferUsage},
+    command::{CommandBuffer, computepass::ComputePassBuildError, renderpass::RenderPassBuildError},
+    pipeline::compute::{CompuitePipelineError, ComputePipeline, ComputePipelineBuilder},
+    shader::{
+        compute::{ComputeShader, ComputeShaderBuilder},
+        graphics::{GraphicsShader, GraphicsShaderBuilder},
+    },
+    texture::{Texture, TextureBuilder, TextureError, TextureFormat, TextureSampler, TextureUsage},
+};
+
+/// Largest radius accepted by [Blur::new].
+pub const MAX_BLUR_RADIUS: u32 = 64;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Creates a texture that can be used as the destination of an effect.
+pub fn create_target(gpu: &mut GPU, size: Point2) -> Result<Texture, EffectError> {
+    create_effect_texture(&gpu.inner, size, "Effect Target")
+}
+
+/// Separable gaussian blur, done as a horizontal and a vertical pass.
+#[derive(Clone, Debug)]
+pub struct Blur {
+    graphics: ArcRef<GPUInner>,
+    radius: u32,
+
+    horizontal: ComputeShader,
+    vertical: ComputeShader,
+    intermediate: Option<Texture>,
+}
+
+impl Blur {
+    /// Creates a blur sampling `radius` pixels on each side, up to [MAX_BLUR_RADIUS].
+    ///
+    /// A radius of zero copies the source unchanged.
+    pub fn new(gpu: &mut GPU, radius: u32) -> Result<Self, EffectError> {
+        if radius > MAX_BLUR_RADIUS {
+            return Err(EffectError::InvalidRadius(radius));
+        }
+
+        let graphics = ArcRef::clone(&gpu.inner);
+        let weights = gaussian_weights(radius);
+
+        let horizontal = ComputeShaderBuilder::new(ArcRef::clone(&graphics))
+            .set_source(&blur_source(&weights, "vec2<i32>(1, 0)"))
+            .set_label("Blur Horizontal Shader")
+            .build()
+            .map_err(EffectError::Shader)?;
+
+        let vertical = ComputeShaderBuilder::new(ArcRef::clone(&graphics))
+            .set_source(&blur_source(&weights, "vec2<i32>(0, 1)"))
+            .set_label("Blur Vertical Shader")
+            .build()
+            .map_err(EffectError::Shader)?;
+
+        Ok(Self {
+            graphics,
+            radius,
+            horizontal,
+            vertical,
+            intermediate: None,
+        })
+    }
+
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+
+    /// Records the blur of `src` into `dst`, both textures must have the same size.
+    ///
+    /// `src` and `dst` may be the same texture to blur it in place.
+    pub fn apply(
+        &mut self,
+        cmd: &mut CommandBuffer,
+        src: &Texture,
+        dst: &Texture,
+    ) -> Result<(), EffectError> {
+        validate_source(src)?;
+        validate_target(dst)?;
+
+        let size = src.size();
+        if dst.size() != size {
+            return Err(EffectError::SizeMismatch);
+        }
+
+        let intermediate = match &self.intermediate {
+            Some(texture) if texture.size() == size => texture.clone(),
+            _ => {
+                let texture = create_effect_texture(&self.graphics, size, "Blur Intermediate")?;
+                self.intermediate.insert(texture).clone()
+            }
+        };
+
+        let horizontal =
+            self.build_pipeline(&self.horizontal, src, &intermediate, "Blur Horizontal")?;
+        let vertical = self.build_pipeline(&self.vertical, &intermediate, dst, "Blur Vertical")?;
+
+        let mut pass = cmd
+            .begin_computepass()
+            .map_err(EffectError::ComputePass)?;
+
+        let (x, y) = workgroups(size);
+
+        pass.set_pipeline(Some(&horizontal));
+        pass.dispatch(x, y, 1);
+
+        pass.set_pipeline(Some(&vertical));
+        pass.dispatch(x, y, 1);
+
+        Ok(())
+    }
+
+    fn build_pipeline(
+        &self,
+        shader: &ComputeShader,
+        src: &Texture,
+        dst: &Texture,
+        label: &str,
+    ) -> Result<ComputePipeline, EffectError> {
+        ComputePipelineBuilder::new(ArcRef::clone(&self.graphics))
+            .set_shader(Some(shader))
+            .set_attachment_texture(0, 0, Some(src))
+            .set_attachment_texture_storage(0, 1, Some(dst))
+            .set_label(label)
+            .build()
+            .map_err(EffectError::Pipeline)
+    }
+}
+
+/// Glow effect, the parts of the source brighter than the threshold are blurred and added back
+/// on top of it.
+#[derive(Clone, Debug)]
+pub struct Bloom {
+    graphics: ArcRef<GPUInner>,
+    blur: Blur,
+
+    threshold_shader: ComputeShader,
+    composite_shader: ComputeShader,
+    params: Buffer,
+
+    threshold: f32,
+    intensity: f32,
+    bright: Option<Texture>,
+}
+
+impl Bloom {
+    /// Creates a bloom with a glow of `radius` pixels, a threshold of `0.8` and an intensity of
+    /// `1.0`.
+    pub fn new(gpu: &mut GPU, radius: u32) -> Result<Self, EffectError> {
+        let blur = Blur::new(gpu, radius)?;
+        let graphics = ArcRef::clone(&gpu.inner);
+
+        let threshold_shader = ComputeShaderBuilder::new(ArcRef::clone(&graphics))
+            .set_source(BLOOM_THRESHOLD_SOURCE)
+            .set_label("Bloom Threshold Shader")
+            .build()
+            .map_err(EffectError::Shader)?;
+
+        let composite_shader = ComputeShaderBuilder::new(ArcRef::clone(&graphics))
+            .set_source(BLOOM_COMPOSITE_SOURCE)
+            .set_label("Bloom Composite Shader")
+            .build()
+            .map_err(EffectError::Shader)?;
+
+        let params = BufferBuilder::<f32>::new(ArcRef::clone(&graphics))
+            .set_data_slice(&[0.8, 1.0, 0.0, 0.0])
+            .set_usage(BufferUsage::UNIFORM | BufferUsage::COPY_DST)
+            .set_label("Bloom Params")
+            .build()
+            .map_err(EffectError::Buffer)?;
+
+        Ok(Self {
+            graphics,
+            blur,
+            threshold_shader,
+            composite_shader,
+            params,
+            threshold: 0.8,
+            intensity: 1.0,
+            bright: None,
+        })
+    }
+
+    /// Sets the luminance, from `0.0` to `1.0`, above which the source starts to glow.
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = threshold.max(0.0);
+    }
+
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// Sets how much of the blurred glow is added on top of the source.
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity.max(0.0);
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn radius(&self) -> u32 {
+        self.blur.radius()
+    }
+
+    /// Records the bloom of `src` into `dst`, both textures must have the same size.
+    ///
+    /// Unlike [Blur::apply], `dst` must be a different texture than `src`.
+    pub fn apply(
+        &mut self,
+        cmd: &mut CommandBuffer,
+        src: &Texture,
+        dst: &Texture,
+    ) -> Result<(), EffectError> {
+        validate_source(src)?;
+        validate_target(dst)?;
+
+        let size = src.size();
+        if dst.size() != size {
+            return Err(EffectError::SizeMismatch);
+        }
+
+        if src.inner.ptr_eq(&dst.inner) {
+            return Err(EffectError::InvalidTarget);
+        }
+
+        let bright = match &self.bright {
+            Some(texture) if texture.size() == size => texture.clone(),
+            _ => {
+                let texture = create_effect_texture(&self.graphics, size, "Bloom Bright")?;
+                self.bright.insert(texture).clone()
+            }
+        };
+
+        cmd.write_buffer_raw(&[self.threshold, self.intensity, 0.0, 0.0], &self.params);
+
+        let threshold = ComputePipelineBuilder::new(ArcRef::clone(&self.graphics))
+            .set_shader(Some(&self.threshold_shader))
+            .set_attachment_texture(0, 0, Some(src))
+            .set_attachment_texture_storage(0, 1, Some(&bright))
+            .set_attachment_uniform(0, 2, Some(&self.params))
+            .set_label("Bloom Threshold")
+            .build()
+            .map_err(EffectError::Pipeline)?;
+
+        let composite = ComputePipelineBuilder::new(ArcRef::clone(&self.graphics))
+            .set_shader(Some(&self.composite_shader))
+            .set_attachment_texture(0, 0, Some(src))
+            .set_attachment_texture(0, 1, Some(&bright))
+            .set_attachment_texture_storage(0, 2, Some(dst))
+            .set_attachment_uniform(0, 3, Some(&self.params))
+            .set_label("Bloom Composite")
+            .build()
+            .map_err(EffectError::Pipeline)?;
+
+        let (x, y) = workgroups(size);
+
+        {
+            let mut pass = cmd
+                .begin_computepass()
+                .map_err(EffectError::ComputePass)?;
+
+            pass.set_pipeline(Some(&threshold));
+            pass.dispatch(x, y, 1);
+        }
+
+        self.blur.apply(cmd, &bright, &bright)?;
+
+        let mut pass = cmd
+            .begin_computepass()
+            .map_err(EffectError::ComputePass)?;
+
+        pass.set_pipeline(Some(&composite));
+        pass.dispatch(x, y, 1);
+
+        Ok(())
+    }
+}
+
+/// Curve used by [Tonemap] to compress the HDR range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TonemapOperator {
+    /// No compression, everything above the peak brightness is clipped.
+    Clamp,
+    /// Simple curve keeping the colors close to the source.
+    Reinhard,
+    /// Filmic curve approximating the ACES reference, with more contrast.
+    Aces,
+}
+
+/// Uniform parameters of the tonemap pass, must match `TonemapParams` in `TONEMAP_SOURCE`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct TonemapParams {
+    exposure: f32,
+    peak: f32,
+    curve: u32,
+    encode_srgb: u32,
+}
+
+/// Presents a linear HDR texture (eg. a `Rgba16Float` render target) to the window, compressing
+/// its range to what the surface can show.
+///
+/// On an SDR surface the colors are mapped to `[0, 1]`. On an HDR surface, see
+/// [GPU::set_surface_format], they are mapped up to the peak brightness instead, so the bright
+/// parts stay brighter than SDR white.
+#[derive(Clone, Debug)]
+pub struct Tonemap {
+    graphics: ArcRef<GPUInner>,
+    shader: GraphicsShader,
+
+    operator: TonemapOperator,
+    exposure: f32,
+    peak_brightness: f32,
+}
+
+impl Tonemap {
+    /// Creates a tonemap pass using [TonemapOperator::Aces], an exposure of `1.0` and a peak
+    /// brightness of `4.0`.
+    pub fn new(gpu: &mut GPU) -> Result<Self, EffectError> {
+        let graphics = ArcRef::clone(&gpu.inner);
+
+        let shader = GraphicsShaderBuilder::new(ArcRef::clone(&graphics))
+            .set_source(TONEMAP_SOURCE)
+            .set_label("Tonemap Shader")
+            .build()
+            .map_err(EffectError::Shader)?;
+
+        Ok(Self {
+            graphics,
+            shader,
+            operator: TonemapOperator::Aces,
+            exposure: 1.0,
+            peak_brightness: 4.0,
+        })
+    }
+
+    pub fn set_operator(&mut self, operator: TonemapOperator) {
+        self.operator = operator;
+    }
+
+    pub fn operator(&self) -> TonemapOperator {
+        self.operator
+    }
+
+    /// Sets the multiplier applied to the source before the curve.
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure.max(0.0);
+    }
+
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    /// Sets the brightest color of an HDR surface, relative to SDR white, eg. `4.0` for a
+    /// 1000 nits monitor with an 250 nits SDR white. Ignored on SDR surfaces.
+    pub fn set_peak_brightness(&mut self, peak_brightness: f32) {
+        self.peak_brightness = peak_brightness.max(1.0);
+    }
+
+    pub fn peak_brightness(&self) -> f32 {
+        self.peak_brightness
+    }
+
+    /// Records the tone mapped `src` to the window surface, stretched to the surface size.
+    pub fn present(&self, cmd: &mut CommandBuffer, src: &Texture) -> Result<(), EffectError> {
+        validate_source(src)?;
+
+        let format = self.graphics.borrow().config.as_ref().map(|config| config.format);
+        let Some(format) = format else {
+            return Err(EffectError::InvalidTarget);
+        };
+
+        let params = TonemapParams {
+            exposure: self.exposure,
+            peak: if format == wgpu::TextureFormat::Rgba16Float {
+                self.peak_brightness
+            } else {
+                1.0
+            },
+            curve: match self.operator {
+                TonemapOperator::Clamp => 0,
+                TonemapOperator::Reinhard => 1,
+                TonemapOperator::Aces => 2,
+            },
+            // sRGB surfaces encode on write, and float surfaces stay linear
+            encode_srgb: (!format.is_srgb() && format != wgpu::TextureFormat::Rgba16Float) as u32,
+        };
+
+        let mut pass = cmd.begin_renderpass().map_err(EffectError::RenderPass)?;
+
+        pass.set_shader(Some(&self.shader));
+        pass.set_attachment_texture(0, 0, Some(src));
+        pass.set_attachment_sampler(0, 1, Some(&TextureSampler::DEFAULT));
+        pass.set_attachment_uniform_raw(0, 2, Some(&[params]));
+        pass.draw(0..3, 1);
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum EffectError {
+    InvalidRadius(u32),
+    /// The source texture was not created with [TextureUsage::Sampler].
+    InvalidSource,
+    /// The destination texture is not a `Rgba8Unorm` storage texture, or is the source texture
+    /// of a [Bloom]. For a [Tonemap], the GPU has no window surface.
+    InvalidTarget,
+    SizeMismatch,
+    Shader(String),
+    Pipeline(CompuitePipelineError),
+    ComputePass(ComputePassBuildError),
+    RenderPass(RenderPassBuildError),
+    Texture(TextureError),
+    Buffer(BufferError),
+}
+
+impl std::fmt::Display for EffectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EffectError::InvalidRadius(radius) => write!(
+                f,
+                "Invalid radius: {}, the maximum is {}",
+                radius, MAX_BLUR_RADIUS
+            ),
+            EffectError::InvalidSource => write!(f, "Source texture is not sampleable"),
+            EffectError::InvalidTarget => {
+                write!(f, "Destination texture is not a Rgba8Unorm storage texture")
+            }
+            EffectError::SizeMismatch => {
+                write!(f, "Source and destination textures have different sizes")
+            }
+            EffectError::Shader(e) => write!(f, "Shader error: {}", e),
+            EffectError::Pipeline(e) => write!(f, "Pipeline error: {:?}", e),
+            EffectError::ComputePass(e) => write!(f, "Compute pass error: {:?}", e),
+            EffectError::RenderPass(e) => write!(f, "Render pass error: {:?}", e),
+            EffectError::Texture(e) => write!(f, "Texture error: {}", e),
+            EffectError::Buffer(e) => write!(f, "Buffer error: {:?}", e),
+        }
+    }
+}
+
+fn create_effect_texture(
+    graphics: &ArcRef<GPUInner>,
+    size: Point2,
+    label: &str,
+) -> Result<Texture, EffectError> {
+    if size.x <= 0 || size.y <= 0 {
+        return Err(EffectError::Texture(TextureError::InvalidTextureSize));
+    }
+
+    TextureBuilder::new(ArcRef::clone(graphics))
+        .set_render_target(size, Some(TextureFormat::Rgba8Unorm))
+        .set_usage(TextureUsage::Sampler)
+        .set_storage_binding()
+        .set_label(label)
+        .build()
+        .map_err(EffectError::Texture)
+}
+
+fn validate_source(src: &Texture) -> Result<(), EffectError> {
+    if !src.usages().contains(TextureUsage::Sampler) {
+        return Err(EffectError::InvalidSource);
+    }
+
+    Ok(())
+}
+
+fn validate_target(dst: &Texture) -> Result<(), EffectError> {
+    if dst.format() != TextureFormat::Rgba8Unorm
+        || !dst.usages().contains(TextureUsage::StorageBinding)
+    {
+        return Err(EffectError::InvalidTarget);
+    }
+
+    Ok(())
+}
+
+fn workgroups(size: Point2) -> (u32, u32) {
+    (
+        (size.x as u32).div_ceil(WORKGROUP_SIZE),
+        (size.y as u32).div_ceil(WORKGROUP_SIZE),
+    )
+}
+
+/// Normalized weights of the center texel and each offset up to `radius`.
+fn gaussian_weights(radius: u32) -> Vec<f32> {
+    let sigma = (radius as f32 / 2.0).max(0.5);
+
+    let mut weights = (0..=radius)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect::<Vec<_>>();
+
+    let sum = weights[0] + 2.0 * weights[1..].iter().sum::<f32>();
+    for weight in &mut weights {
+        *weight /= sum;
+    }
+
+    weights
+}
+
+fn blur_source(weights: &[f32], direction: &str) -> String {
+    let weights_list = weights
+        .iter()
+        .map(|w| format!("{:?}", w))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        r#"
+const RADIUS: i32 = {radius};
+const DIRECTION: vec2<i32> = {direction};
+
+var<private> WEIGHTS: array<f32, {count}> = array<f32, {count}>({weights_list});
+
+@group(0) @binding(0) var src: texture_2d<f32>;
+@group(0) @binding(1) var dst: texture_storage_2d<rgba8unorm, write>;
+
+@compute @workgroup_size({workgroup}, {workgroup}, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {{
+    let size = vec2<i32>(textureDimensions(dst));
+    let pos = vec2<i32>(id.xy);
+    if (pos.x >= size.x || pos.y >= size.y) {{
+        return;
+    }}
+
+    let last = vec2<i32>(textureDimensions(src)) - vec2<i32>(1, 1);
+    var color = textureLoad(src, pos, 0) * WEIGHTS[0];
+    for (var i = 1; i <= RADIUS; i++) {{
+        let offset = DIRECTION * i;
+        color += textureLoad(src, clamp(pos + offset, vec2<i32>(0, 0), last), 0) * WEIGHTS[i];
+        color += textureLoad(src, clamp(pos - offset, vec2<i32>(0, 0), last), 0) * WEIGHTS[i];
+    }}
+
+    textureStore(dst, pos, color);
+}}
+"#,
+        radius = weights.len() - 1,
+        count = weights.len(),
+        workgroup = WORKGROUP_SIZE,
+    )
+}
+
+const BLOOM_THRESHOLD_SOURCE: &str = r#"
+struct BloomParams {
+    threshold: f32,
+    intensity: f32,
+    padding: vec2<f32>,
+};
+
+@group(0) @binding(0) var src: texture_2d<f32>;
+@group(0) @binding(1) var dst: texture_storage_2d<rgba8unorm, write>;
+@group(0) @binding(2) var<uniform> params: BloomParams;
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = vec2<i32>(textureDimensions(dst));
+    let pos = vec2<i32>(id.xy);
+    if (pos.x >= size.x || pos.y >= size.y) {
+        return;
+    }
+
+    let color = textureLoad(src, pos, 0);
+    let luminance = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let factor = max(luminance - params.threshold, 0.0) / max(luminance, 0.0001);
+
+    textureStore(dst, pos, vec4<f32>(color.rgb * factor, color.a * factor));
+}
+"#;
+
+const BLOOM_COMPOSITE_SOURCE: &str = r#"
+struct BloomParams {
+    threshold: f32,
+    intensity: f32,
+    padding: vec2<f32>,
+};
+
+@group(0) @binding(0) var src: texture_2d<f32>;
+@group(0) @binding(1) var glow: texture_2d<f32>;
+@group(0) @binding(2) var dst: texture_storage_2d<rgba8unorm, write>;
+@group(0) @binding(3) var<uniform> params: BloomParams;
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = vec2<i32>(textureDimensions(dst));
+    let pos = vec2<i32>(id.xy);
+    if (pos.x >= size.x || pos.y >= size.y) {
+        return;
+    }
+
+    let color = textureLoad(src, pos, 0);
+    let glow_color = textureLoad(glow, pos, 0) * params.intensity;
+
+    textureStore(dst, pos, clamp(color + glow_color, vec4<f32>(0.0), vec4<f32>(1.0)));
+}
+"#;
+
+const TONEMAP_SOURCE: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) texCoord: vec2<f32>,
+};
+
+@vertex
+fn main_vertex(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // Fullscreen triangle, no vertex buffer needed
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    output.texCoord = uv;
+    return output;
+}
+
+struct TonemapParams {
+    exposure: f32,
+    peak: f32,
+    // 0 = clamp, 1 = Reinhard, 2 = ACES
+    curve: u32,
+    encode_srgb: u32,
+};
+
+@group(0) @binding(0) var src: texture_2d<f32>;
+@group(0) @binding(1) var srcSampler: sampler;
+@group(0) @binding(2) var<uniform> params: TonemapParams;
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve
+fn aces(x: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+@fragment
+fn main_fragment(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(src, srcSampler, input.texCoord);
+
+    // Map relative to the peak, so HDR surfaces keep the range above SDR white
+    let hdr = max(color.rgb * params.exposure / params.peak, vec3<f32>(0.0));
+
+    var mapped: vec3<f32>;
+    switch params.curve {
+        case 1u: {
+            mapped = hdr / (vec3<f32>(1.0) + hdr);
+        }
+        case 2u: {
+            mapped = aces(hdr);
+        }
+        default: {
+            mapped = clamp(hdr, vec3<f32>(0.0), vec3<f32>(1.0));
+        }
+    }
+
+    mapped = mapped * params.peak;
+
+    if (params.encode_srgb == 1u) {
+        mapped = linear_to_srgb(mapped);
+    }
+
+    return vec4<f32>(mapped, color.a);
+}
+"#;
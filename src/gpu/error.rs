@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use crate::utils::ArcMut;
+
+use super::diagnostics::DiagnosticsRecorder;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpuErrorKind {
+    /// Invalid usage of the GPU API, eg. a resource used with the wrong usage flags.
+    Validation,
+    OutOfMemory,
+    /// Failures not caused by the application, eg. backend or driver limits.
+    Internal,
+}
+
+/// An error reported by the GPU device.
+#[derive(Clone, Debug)]
+pub struct GpuError {
+    pub kind: GpuErrorKind,
+    pub message: String,
+}
+
+impl std::fmt::Display for GpuError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            GpuErrorKind::Validation => write!(f, "Validation error: {}", self.message),
+            GpuErrorKind::OutOfMemory => write!(f, "Out of memory: {}", self.message),
+            GpuErrorKind::Internal => write!(f, "Internal error: {}", self.message),
+        }
+    }
+}
+
+impl From<wgpu::Error> for GpuError {
+    fn from(error: wgpu::Error) -> Self {
+        match error {
+            wgpu::Error::Validation { description, .. } => GpuError {
+                kind: GpuErrorKind::Validation,
+                message: description,
+            },
+            wgpu::Error::OutOfMemory { source } => GpuError {
+                kind: GpuErrorKind::OutOfMemory,
+                message: source.to_string(),
+            },
+            wgpu::Error::Internal { description, .. } => GpuError {
+                kind: GpuErrorKind::Internal,
+                message: description,
+            },
+        }
+    }
+}
+
+type PanicCallback = Arc<dyn Fn(&str) + Send + Sync>;
+type ErrorCallback = Arc<dyn Fn(&GpuError) + Send + Sync>;
+
+/// Routes the errors the device reports outside of an error scope to the user callbacks.
+#[derive(Clone, Default)]
+pub(crate) struct ErrorHandler {
+    pub panic_callback: Option<PanicCallback>,
+    pub error_callback: Option<ErrorCallback>,
+}
+
+impl std::fmt::Debug for ErrorHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ErrorHandler")
+            .field("panic_callback", &self.panic_callback.is_some())
+            .field("error_callback", &self.error_callback.is_some())
+            .finish()
+    }
+}
+
+impl ErrorHandler {
+    /// Replaces the default wgpu handler, which panics on every uncaptured error.
+    ///
+    /// Errors are also recorded in `diagnostics`, which writes a bundle before the crate panics.
+    pub fn install(
+        device: &wgpu::Device,
+        handler: &ArcMut<ErrorHandler>,
+        diagnostics: &ArcMut<DiagnosticsRecorder>,
+    ) {
+        let handler = handler.clone();
+        let diagnostics = diagnostics.clone();
+
+        device.on_uncaptured_error(Box::new(move |error| {
+            // Don't hold the lock while the callbacks run, they may replace the callbacks.
+            let handler = handler.lock().clone();
+            let error = GpuError::from(error);
+
+            diagnostics
+                .lock()
+                .record_error(&error, handler.error_callback.is_none());
+
+            handler.dispatch(error);
+        }));
+    }
+
+    fn dispatch(&self, error: GpuError) {
+        if let Some(callback) = &self.error_callback {
+            callback(&error);
+            return;
+        }
+
+        let message = format!("Uncaptured GPU error: {}", error);
+
+        if let Some(callback) = &self.panic_callback {
+            callback(&message);
+        }
+
+        panic!("{}", message);
+    }
+}
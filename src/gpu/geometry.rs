@@ -0,0 +1,268 @@
+//! Vertex and index data for common shapes, in the [Vertex] layout.
+//!
+//! Meshes are centered on the origin with Y up. Triangles are wound clockwise when seen from
+//! outside the shape, matching the default front face of
+//! [GraphicsShader](crate::gpu::shader::graphics::GraphicsShader), so back face culling works
+//! without changing it. Texture coordinates start at the top-left corner of each face.
+//!
+//! The vertex color is white, use [Mesh::set_color] to tint the whole mesh.
+//!
+//! # Example
+//! ```ignore
+//! let sphere = geometry::uv_sphere(1.0, 32, 16);
+//!
+//! // Every frame
+//! render_pass.set_gpu_buffer_raw(Some(&sphere.vertices), Some(&sphere.indices));
+//! render_pass.draw_indexed(0..sphere.indices.len() as u32, 0, 1);
+//! ```
+
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use crate::math::{Color, Vector2, Vector3, Vertex};
+
+/// Vertices and 32-bit triangle list indices of a shape.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    /// Surface normal of each vertex, in the same order as [Mesh::vertices].
+    pub normals: Vec<Vector3>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Sets the color of every vertex.
+    pub fn set_color(&mut self, color: Color) {
+        for vertex in &mut self.vertices {
+            vertex.color = color;
+        }
+    }
+
+    /// Number of triangles in the mesh.
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    fn push_vertex(&mut self, position: Vector3, normal: Vector3, texcoord: Vector2) -> u32 {
+        let index = self.vertices.len() as u32;
+
+        self.vertices.push(Vertex::new(position, Color::WHITE, texcoord));
+        self.normals.push(normal);
+
+        index
+    }
+
+    /// Adds a `columns` x `rows` grid of quads spanning `right` and `up` around `center`,
+    /// facing `right x up`.
+    fn push_grid(&mut self, center: Vector3, right: Vector3, up: Vector3, columns: u32, rows: u32) {
+        let normal = right.cross(&up).normalize();
+        let first = self.vertices.len() as u32;
+
+        for row in 0..=rows {
+            let v = row as f32 / rows as f32;
+
+            for column in 0..=columns {
+                let u = column as f32 / columns as f32;
+                let position = center + right * (u * 2.0 - 1.0) + up * (1.0 - v * 2.0);
+
+                self.push_vertex(position, normal, Vector2::new(u, v));
+            }
+        }
+
+        let stride = columns + 1;
+        for row in 0..rows {
+            for column in 0..columns {
+                let top_left = first + row * stride + column;
+                let bottom_left = top_left + stride;
+
+                self.indices.extend_from_slice(&[
+                    top_left,
+                    top_left + 1,
+                    bottom_left + 1,
+                    top_left,
+                    bottom_left + 1,
+                    bottom_left,
+                ]);
+            }
+        }
+    }
+
+    /// Revolves a profile around the Y axis. Each entry is the height, the distance from the
+    /// axis and the normal of the profile (radial, vertical), from the top to the bottom.
+    fn push_lathe(&mut self, profile: &[(f32, f32, Vector2)], segments: u32) {
+        let first = self.vertices.len() as u32;
+
+        let top = profile.first().map_or(0.0, |p| p.0);
+        let bottom = profile.last().map_or(0.0, |p| p.0);
+        let height = (top - bottom).max(f32::EPSILON);
+
+        for &(y, radius, normal) in profile {
+            let v = (top - y) / height;
+
+            for segment in 0..=segments {
+                let u = segment as f32 / segments as f32;
+                let (sin, cos) = (u * TAU).sin_cos();
+
+                self.push_vertex(
+                    Vector3::new(radius * sin, y, radius * cos),
+                    Vector3::new(normal.x * sin, normal.y, normal.x * cos),
+                    Vector2::new(u, v),
+                );
+            }
+        }
+
+        let stride = segments + 1;
+        for row in 0..profile.len() as u32 - 1 {
+            // Rings collapsed to a point (the poles) only need one triangle per segment
+            let top_is_pole = profile[row as usize].1 == 0.0;
+            let bottom_is_pole = profile[row as usize + 1].1 == 0.0;
+
+            for segment in 0..segments {
+                let top_left = first + row * stride + segment;
+                let bottom_left = top_left + stride;
+
+                if !top_is_pole {
+                    self.indices
+                        .extend_from_slice(&[top_left, top_left + 1, bottom_left + 1]);
+                }
+
+                if !bottom_is_pole {
+                    self.indices
+                        .extend_from_slice(&[top_left, bottom_left + 1, bottom_left]);
+                }
+            }
+        }
+    }
+}
+
+/// A 1x1 quad on the XY plane, facing +Z.
+pub fn quad() -> Mesh {
+    let mut mesh = Mesh::default();
+    mesh.push_grid(
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.5, 0.0, 0.0),
+        Vector3::new(0.0, 0.5, 0.0),
+        1,
+        1,
+    );
+
+    mesh
+}
+
+/// A cube with sides of `size`. Each face has its own vertices, so normals and texture
+/// coordinates are not shared across edges.
+pub fn cube(size: f32) -> Mesh {
+    let h = size * 0.5;
+
+    // (center, right, up) of each face, seen from outside
+    let faces = [
+        ([0.0, 0.0, h], [h, 0.0, 0.0], [0.0, h, 0.0]),
+        ([0.0, 0.0, -h], [-h, 0.0, 0.0], [0.0, h, 0.0]),
+        ([h, 0.0, 0.0], [0.0, 0.0, -h], [0.0, h, 0.0]),
+        ([-h, 0.0, 0.0], [0.0, 0.0, h], [0.0, h, 0.0]),
+        ([0.0, h, 0.0], [h, 0.0, 0.0], [0.0, 0.0, -h]),
+        ([0.0, -h, 0.0], [h, 0.0, 0.0], [0.0, 0.0, h]),
+    ];
+
+    let mut mesh = Mesh::default();
+    for (center, right, up) in faces {
+        mesh.push_grid(center.into(), right.into(), up.into(), 1, 1);
+    }
+
+    mesh
+}
+
+/// A `size` x `size` grid on the XZ plane facing +Y, split in `subdivisions` quads along
+/// each side.
+pub fn plane_grid(size: f32, subdivisions: u32) -> Mesh {
+    let h = size * 0.5;
+    let subdivisions = subdivisions.max(1);
+
+    let mut mesh = Mesh::default();
+    mesh.push_grid(
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(h, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, -h),
+        subdivisions,
+        subdivisions,
+    );
+
+    mesh
+}
+
+/// A sphere made of `segments` slices around the Y axis and `rings` stacks from pole to
+/// pole.
+///
+/// The texture coordinates wrap once around the sphere (equirectangular), so the seam
+/// vertices are duplicated.
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Mesh {
+    let segments = segments.max(3);
+    let rings = rings.max(2);
+
+    let profile = (0..=rings)
+        .map(|ring| {
+            let angle = ring as f32 / rings as f32 * PI;
+            let (sin, cos) = angle.sin_cos();
+
+            (radius * cos, ring_radius(radius, sin), Vector2::new(sin, cos))
+        })
+        .collect::<Vec<_>>();
+
+    let mut mesh = Mesh::default();
+    mesh.push_lathe(&profile, segments);
+
+    mesh
+}
+
+/// A capsule along the Y axis, a cylinder closed by two hemispheres.
+///
+/// `height` is the total height including the hemispheres, it is clamped to at least
+/// `radius * 2` (a sphere). `rings` is the number of stacks of each hemisphere.
+pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> Mesh {
+    let segments = segments.max(3);
+    let rings = rings.max(1);
+    let half_cylinder = (height * 0.5 - radius).max(0.0);
+
+    let hemisphere = |offset: f32, start: f32| {
+        (0..=rings).map(move |ring| {
+            let angle = start + ring as f32 / rings as f32 * FRAC_PI_2;
+            let (sin, cos) = angle.sin_cos();
+
+            (
+                offset + radius * cos,
+                ring_radius(radius, sin),
+                Vector2::new(sin, cos),
+            )
+        })
+    };
+
+    // The last ring of the top and the first ring of the bottom hemisphere form the cylinder
+    let profile = hemisphere(half_cylinder, 0.0)
+        .chain(hemisphere(-half_cylinder, FRAC_PI_2))
+        .collect::<Vec<_>>();
+
+    let mut mesh = Mesh::default();
+    mesh.push_lathe(&profile, segments);
+
+    mesh
+}
+
+/// A single triangle covering the whole screen, in clip space.
+///
+/// Draw it with an identity transform, the texture coordinates map the visible area to
+/// `0..1`. It is cheaper than a quad since no pixels are shaded twice along the diagonal.
+pub fn fullscreen_triangle() -> Mesh {
+    let normal = Vector3::new(0.0, 0.0, 1.0);
+
+    let mut mesh = Mesh::default();
+    mesh.push_vertex(Vector3::new(-1.0, -1.0, 0.0), normal, Vector2::new(0.0, 1.0));
+    mesh.push_vertex(Vector3::new(-1.0, 3.0, 0.0), normal, Vector2::new(0.0, -1.0));
+    mesh.push_vertex(Vector3::new(3.0, -1.0, 0.0), normal, Vector2::new(2.0, 1.0));
+    mesh.indices.extend_from_slice(&[0, 1, 2]);
+
+    mesh
+}
+
+/// Snaps the poles to exactly zero, `sin(PI)` is not.
+fn ring_radius(radius: f32, sin: f32) -> f32 {
+    if sin.abs() < 1e-6 { 0.0 } else { radius * sin }
+}
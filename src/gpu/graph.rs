@@ -0,0 +1,949 @@
+//! Render graph, declares the passes of a frame and the resources they read and write.
+//!
+//! The graph orders the passes from their dependencies, skips the passes whose results are never
+//! used, allocates the transient textures and buffers, and picks the load and store operations
+//! of the attachments, so multi-pass frames don't depend on the order the passes are recorded in.
+//!
+//! ```rust,ignore
+//! let mut graph = gpu.create_render_graph();
+//!
+//! let scene = graph.create_texture("scene", size, TextureFormat::Rgba8Unorm);
+//! let depth = graph.create_texture("depth", size, TextureFormat::Depth32Float);
+//! let surface = graph.import_surface();
+//!
+//! graph
+//!     .add_pass("composite")
+//!     .read_texture(scene)
+//!     .write_texture(surface)
+//!     .render(|pass, resources| {
+//!         pass.set_shader(Some(&composite_shader));
+//!         pass.set_attachment_texture(0, 0, resources.texture(scene));
+//!         pass.draw(0..3, 1);
+//!     });
+//!
+//! graph
+//!     .add_pass("scene")
+//!     .write_texture(scene)
+//!     .set_depth_texture(depth)
+//!     .render(|pass, _| {
+//!         // ...
+//!     });
+//!
+//! let mut cmd = gpu.begin_command()?;
+//! graph.execute(&mut cmd)?;
+//! ```
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{
+    math::{Color, Point2},
+    utils::ArcRef,
+};
+
+use super::{
+    GPUInner,
+    buffer::{Buffer, BufferBuilder, BufferError, BufferInner, BufferMapMode, BufferUsage},
+    command::{
+        CommandBuffer, SurfaceTexture, SurfaceTextureError,
+        computepass::{ComputePass, ComputePassBuildError},
+        renderpass::{LoadOp, RenderPass, RenderPassBuildError, StoreOp},
+    },
+    texture::{
+        Texture, TextureBuilder, TextureError, TextureFormat, TextureInner, TextureMappedType,
+        TextureUsage,
+    },
+};
+
+/// Number of frames an unused transient resource is kept in the pool.
+const POOL_LIFETIME_FRAMES: u32 = 60;
+
+/// Handle of a texture declared in a [RenderGraph].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GraphTexture(usize);
+
+/// Handle of a buffer declared in a [RenderGraph].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GraphBuffer(usize);
+
+type RenderCallback<'a> = Box<dyn FnOnce(&mut RenderPass, &GraphResources) + 'a>;
+type ComputeCallback<'a> = Box<dyn FnOnce(&mut ComputePass, &GraphResources) + 'a>;
+
+#[derive(Clone, Debug)]
+enum TextureSource {
+    Transient { size: Point2, format: TextureFormat },
+    Imported(Texture),
+    Surface,
+}
+
+#[derive(Clone, Debug)]
+struct TextureEntry {
+    name: String,
+    source: TextureSource,
+    clear: Option<Color>,
+}
+
+#[derive(Clone, Debug)]
+enum BufferSource {
+    Transient { size: u64, usage: BufferUsage },
+    Imported(Buffer),
+}
+
+#[derive(Clone, Debug)]
+struct BufferEntry {
+    name: String,
+    source: BufferSource,
+}
+
+enum PassCallback<'a> {
+    Render(RenderCallback<'a>),
+    Compute(ComputeCallback<'a>),
+}
+
+struct GraphPass<'a> {
+    name: String,
+    callback: PassCallback<'a>,
+
+    /// Color attachments of a render pass, or storage textures of a compute pass.
+    write_textures: Vec<GraphTexture>,
+    depth: Option<GraphTexture>,
+    read_textures: Vec<GraphTexture>,
+    write_buffers: Vec<GraphBuffer>,
+    read_buffers: Vec<GraphBuffer>,
+}
+
+impl GraphPass<'_> {
+    fn is_render(&self) -> bool {
+        matches!(self.callback, PassCallback::Render(_))
+    }
+
+    fn writes_texture(&self, texture: usize) -> bool {
+        self.write_textures.iter().any(|t| t.0 == texture)
+            || self.depth.is_some_and(|t| t.0 == texture)
+    }
+
+    fn uses_texture(&self, texture: usize) -> bool {
+        self.writes_texture(texture) || self.read_textures.iter().any(|t| t.0 == texture)
+    }
+
+    fn uses_buffer(&self, buffer: usize) -> bool {
+        self.write_buffers.iter().any(|b| b.0 == buffer)
+            || self.read_buffers.iter().any(|b| b.0 == buffer)
+    }
+}
+
+/// Frame graph of render and compute passes, created with
+/// [GPU::create_render_graph](super::GPU::create_render_graph).
+///
+/// The graph is meant to be built every frame, the transient resources it allocates are pooled
+/// by the [GPU] and reused by the next graphs.
+pub struct RenderGraph<'a> {
+    graphics: ArcRef<GPUInner>,
+
+    textures: Vec<TextureEntry>,
+    buffers: Vec<BufferEntry>,
+    passes: Vec<GraphPass<'a>>,
+}
+
+impl<'a> RenderGraph<'a> {
+    pub(crate) fn new(graphics: ArcRef<GPUInner>) -> Self {
+        Self {
+            graphics,
+            textures: Vec::new(),
+            buffers: Vec::new(),
+            passes: Vec::new(),
+        }
+    }
+
+    /// Declares a texture owned by the graph, allocated for the passes using it.
+    ///
+    /// The texture is cleared to transparent (or `1.0` for depth formats) by the first pass
+    /// writing it, its content doesn't persist between frames.
+    pub fn create_texture(
+        &mut self,
+        name: &str,
+        size: Point2,
+        format: TextureFormat,
+    ) -> GraphTexture {
+        self.textures.push(TextureEntry {
+            name: name.to_string(),
+            source: TextureSource::Transient { size, format },
+            clear: None,
+        });
+
+        GraphTexture(self.textures.len() - 1)
+    }
+
+    /// Declares an existing texture, its content is loaded by the first pass writing it unless
+    /// a clear color is set with [RenderGraph::set_clear_color].
+    pub fn import_texture(&mut self, name: &str, texture: &Texture) -> GraphTexture {
+        self.textures.push(TextureEntry {
+            name: name.to_string(),
+            source: TextureSource::Imported(texture.clone()),
+            clear: None,
+        });
+
+        GraphTexture(self.textures.len() - 1)
+    }
+
+    /// Declares the surface of the window, cleared to black by the first pass writing it.
+    ///
+    /// The surface can only be written by render passes, and is not available through
+    /// [GraphResources::texture].
+    pub fn import_surface(&mut self) -> GraphTexture {
+        self.textures.push(TextureEntry {
+            name: "surface".to_string(),
+            source: TextureSource::Surface,
+            clear: Some(Color::BLACK),
+        });
+
+        GraphTexture(self.textures.len() - 1)
+    }
+
+    /// Sets the color a color texture is cleared with by the first pass writing it, [None]
+    /// loads its content instead.
+    pub fn set_clear_color(&mut self, texture: GraphTexture, color: Option<Color>) {
+        self.textures[texture.0].clear = color;
+    }
+
+    /// Declares a buffer owned by the graph, allocated for the passes using it.
+    ///
+    /// The content of the buffer is undefined until a pass writes it.
+    pub fn create_buffer(&mut self, name: &str, size: u64, usage: BufferUsage) -> GraphBuffer {
+        self.buffers.push(BufferEntry {
+            name: name.to_string(),
+            source: BufferSource::Transient { size, usage },
+        });
+
+        GraphBuffer(self.buffers.len() - 1)
+    }
+
+    /// Declares an existing buffer.
+    pub fn import_buffer(&mut self, name: &str, buffer: &Buffer) -> GraphBuffer {
+        self.buffers.push(BufferEntry {
+            name: name.to_string(),
+            source: BufferSource::Imported(buffer.clone()),
+        });
+
+        GraphBuffer(self.buffers.len() - 1)
+    }
+
+    /// Declares a pass, finished with [GraphPassBuilder::render] or [GraphPassBuilder::compute].
+    pub fn add_pass(&mut self, name: &str) -> GraphPassBuilder<'_, 'a> {
+        GraphPassBuilder {
+            graph: self,
+            name: name.to_string(),
+            write_textures: Vec::new(),
+            depth: None,
+            read_textures: Vec::new(),
+            write_buffers: Vec::new(),
+            read_buffers: Vec::new(),
+        }
+    }
+
+    /// Orders the passes, allocates the transient resources and records the passes into `cmd`.
+    pub fn execute(self, cmd: &mut CommandBuffer) -> Result<(), RenderGraphError> {
+        let order = self.compile()?;
+
+        let RenderGraph {
+            graphics,
+            textures,
+            buffers,
+            passes,
+        } = self;
+
+        // Position in the execution order of the first and last pass using each resource
+        let texture_uses = (0..textures.len())
+            .map(|texture| {
+                let mut uses = order
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &pass)| passes[pass].uses_texture(texture))
+                    .map(|(position, _)| position);
+
+                let first = uses.next();
+                first.map(|first| (first, uses.next_back().unwrap_or(first)))
+            })
+            .collect::<Vec<_>>();
+
+        let buffer_uses = (0..buffers.len())
+            .map(|buffer| {
+                let mut uses = order
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &pass)| passes[pass].uses_buffer(buffer))
+                    .map(|(position, _)| position);
+
+                let first = uses.next();
+                first.map(|first| (first, uses.next_back().unwrap_or(first)))
+            })
+            .collect::<Vec<_>>();
+
+        let texture_keys = (0..textures.len())
+            .map(|texture| {
+                let TextureSource::Transient { size, format } = textures[texture].source else {
+                    return None;
+                };
+
+                let mut usage = TextureUsage::None;
+                for pass in order.iter().map(|&pass| &passes[pass]) {
+                    if pass.read_textures.iter().any(|t| t.0 == texture) {
+                        usage |= TextureUsage::Sampler;
+                    }
+
+                    if !pass.is_render() && pass.write_textures.iter().any(|t| t.0 == texture) {
+                        usage |= TextureUsage::StorageBinding;
+                    }
+                }
+
+                Some(TransientTextureKey {
+                    size: (size.x, size.y),
+                    format,
+                    usage,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut passes = passes.into_iter().map(Some).collect::<Vec<_>>();
+
+        let mut resources = GraphResources {
+            textures: textures
+                .iter()
+                .map(|entry| match &entry.source {
+                    TextureSource::Imported(texture) => Some(texture.clone()),
+                    _ => None,
+                })
+                .collect(),
+            buffers: buffers
+                .iter()
+                .map(|entry| match &entry.source {
+                    BufferSource::Imported(buffer) => Some(buffer.clone()),
+                    _ => None,
+                })
+                .collect(),
+        };
+
+        let mut written = vec![false; textures.len()];
+        let mut surface: Option<SurfaceTexture> = None;
+
+        for (position, &index) in order.iter().enumerate() {
+            for (texture, key) in texture_keys.iter().enumerate() {
+                if let Some(key) = key
+                    && texture_uses[texture].is_some_and(|(first, _)| first == position)
+                {
+                    let label = format!("RenderGraph {}", textures[texture].name);
+                    resources.textures[texture] = Some(acquire_texture(&graphics, key, &label)?);
+                }
+            }
+
+            for (buffer, entry) in buffers.iter().enumerate() {
+                if let BufferSource::Transient { size, usage } = entry.source
+                    && buffer_uses[buffer].is_some_and(|(first, _)| first == position)
+                {
+                    let label = format!("RenderGraph {}", entry.name);
+                    resources.buffers[buffer] =
+                        Some(acquire_buffer(&graphics, size, usage, &label)?);
+                }
+            }
+
+            let pass = passes[index].take().unwrap();
+
+            match pass.callback {
+                PassCallback::Render(callback) => {
+                    if pass
+                        .write_textures
+                        .iter()
+                        .any(|t| matches!(textures[t.0].source, TextureSource::Surface))
+                        && surface.is_none()
+                    {
+                        surface = Some(
+                            cmd.get_surface_texture()
+                                .map_err(RenderGraphError::Surface)?,
+                        );
+                    }
+
+                    let mut builder = cmd.renderpass_builder();
+
+                    for (attachment, texture) in pass.write_textures.iter().enumerate() {
+                        let entry = &textures[texture.0];
+
+                        builder = match &entry.source {
+                            TextureSource::Surface => builder
+                                .add_surface_color_attachment(surface.as_ref().unwrap(), None),
+                            _ => builder.add_color_attachment(
+                                resources.textures[texture.0].as_ref().unwrap(),
+                                None,
+                            ),
+                        };
+
+                        let load_op = match (written[texture.0], &entry.source, entry.clear) {
+                            (true, _, _) => LoadOp::Load,
+                            (false, _, Some(color)) => LoadOp::Clear(color),
+                            (false, TextureSource::Transient { .. }, None) => {
+                                LoadOp::Clear(Color::TRANSPARENT)
+                            }
+                            (false, _, None) => LoadOp::Load,
+                        };
+
+                        builder = builder.set_color_load_op(attachment, load_op);
+                        written[texture.0] = true;
+                    }
+
+                    if let Some(depth) = pass.depth {
+                        builder = builder
+                            .set_depth_attachment(resources.textures[depth.0].as_ref().unwrap());
+
+                        let load_op = match (written[depth.0], &textures[depth.0].source) {
+                            (false, TextureSource::Transient { .. }) => LoadOp::Clear(1.0),
+                            _ => LoadOp::Load,
+                        };
+
+                        // The depth is only kept if a later pass uses it or it's owned by the user
+                        let store_op = match &textures[depth.0].source {
+                            TextureSource::Transient { .. }
+                                if texture_uses[depth.0]
+                                    .is_some_and(|(_, last)| last == position) =>
+                            {
+                                StoreOp::Discard
+                            }
+                            _ => StoreOp::Store,
+                        };
+
+                        builder = builder
+                            .set_depth_load_op(load_op)
+                            .set_depth_store_op(store_op);
+                        written[depth.0] = true;
+                    }
+
+                    let mut render_pass = builder.build().map_err(RenderGraphError::RenderPass)?;
+                    callback(&mut render_pass, &resources);
+                }
+                PassCallback::Compute(callback) => {
+                    let mut compute_pass = cmd
+                        .begin_computepass()
+                        .map_err(RenderGraphError::ComputePass)?;
+
+                    callback(&mut compute_pass, &resources);
+
+                    for texture in &pass.write_textures {
+                        written[texture.0] = true;
+                    }
+                }
+            }
+
+            // Return the resources to the pool, so later passes of the graph can reuse them
+            for (texture, key) in texture_keys.iter().enumerate() {
+                if let Some(key) = key
+                    && texture_uses[texture].is_some_and(|(_, last)| last == position)
+                    && let Some(released) = resources.textures[texture].take()
+                {
+                    graphics
+                        .borrow_mut()
+                        .graph_pool
+                        .release_texture(*key, released.inner);
+                }
+            }
+
+            for (buffer, entry) in buffers.iter().enumerate() {
+                if let BufferSource::Transient { size, usage } = entry.source
+                    && buffer_uses[buffer].is_some_and(|(_, last)| last == position)
+                    && let Some(released) = resources.buffers[buffer].take()
+                {
+                    graphics
+                        .borrow_mut()
+                        .graph_pool
+                        .release_buffer((size, usage), released.inner);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validates the passes and returns the index of the passes to record, in order.
+    fn compile(&self) -> Result<Vec<usize>, RenderGraphError> {
+        for pass in &self.passes {
+            self.validate_pass(pass)?;
+        }
+
+        let mut edges: Vec<Vec<usize>> = vec![Vec::new(); self.passes.len()];
+        let mut add_edge = |from: usize, to: usize| {
+            if from != to && !edges[from].contains(&to) {
+                edges[from].push(to);
+            }
+        };
+
+        for texture in 0..self.textures.len() {
+            let writers = (0..self.passes.len())
+                .filter(|&pass| self.passes[pass].writes_texture(texture))
+                .collect::<Vec<_>>();
+
+            for pair in writers.windows(2) {
+                add_edge(pair[0], pair[1]);
+            }
+
+            for (reader, pass) in self.passes.iter().enumerate() {
+                if !pass.read_textures.iter().any(|t| t.0 == texture) {
+                    continue;
+                }
+
+                if writers.is_empty()
+                    && matches!(
+                        self.textures[texture].source,
+                        TextureSource::Transient { .. }
+                    )
+                {
+                    return Err(RenderGraphError::ReadBeforeWrite(
+                        self.textures[texture].name.clone(),
+                    ));
+                }
+
+                for &writer in &writers {
+                    add_edge(writer, reader);
+                }
+            }
+        }
+
+        for buffer in 0..self.buffers.len() {
+            let writers = (0..self.passes.len())
+                .filter(|&pass| {
+                    self.passes[pass]
+                        .write_buffers
+                        .iter()
+                        .any(|b| b.0 == buffer)
+                })
+                .collect::<Vec<_>>();
+
+            for pair in writers.windows(2) {
+                add_edge(pair[0], pair[1]);
+            }
+
+            for (reader, pass) in self.passes.iter().enumerate() {
+                if !pass.read_buffers.iter().any(|b| b.0 == buffer) || writers.contains(&reader) {
+                    continue;
+                }
+
+                if writers.is_empty()
+                    && matches!(self.buffers[buffer].source, BufferSource::Transient { .. })
+                {
+                    return Err(RenderGraphError::ReadBeforeWrite(
+                        self.buffers[buffer].name.clone(),
+                    ));
+                }
+
+                for &writer in &writers {
+                    add_edge(writer, reader);
+                }
+            }
+        }
+
+        // Passes are kept if they write a resource visible outside of the graph, or a resource
+        // read by a pass that is kept. Compute passes without declared writes are always kept.
+        let mut live = self
+            .passes
+            .iter()
+            .map(|pass| {
+                pass.write_textures
+                    .iter()
+                    .chain(pass.depth.iter())
+                    .any(|t| !matches!(self.textures[t.0].source, TextureSource::Transient { .. }))
+                    || pass
+                        .write_buffers
+                        .iter()
+                        .any(|b| matches!(self.buffers[b.0].source, BufferSource::Imported(_)))
+                    || (!pass.is_render()
+                        && pass.write_textures.is_empty()
+                        && pass.write_buffers.is_empty())
+            })
+            .collect::<Vec<_>>();
+
+        let mut stack = (0..self.passes.len())
+            .filter(|&pass| live[pass])
+            .collect::<Vec<_>>();
+        while let Some(pass) = stack.pop() {
+            for from in 0..self.passes.len() {
+                if !live[from] && edges[from].contains(&pass) {
+                    live[from] = true;
+                    stack.push(from);
+                }
+            }
+        }
+
+        for (index, _pass) in self.passes.iter().enumerate() {
+            if !live[index] {
+                crate::dbg_log!("Render graph pass '{}' is not used, skipping it", _pass.name);
+            }
+        }
+
+        // Topological sort, ties are recorded in declaration order
+        let mut incoming = vec![0usize; self.passes.len()];
+        for (from, targets) in edges.iter().enumerate() {
+            if live[from] {
+                for &to in targets {
+                    incoming[to] += 1;
+                }
+            }
+        }
+
+        let mut ready = (0..self.passes.len())
+            .filter(|&pass| live[pass] && incoming[pass] == 0)
+            .map(Reverse)
+            .collect::<BinaryHeap<_>>();
+
+        let mut order = Vec::new();
+        while let Some(Reverse(pass)) = ready.pop() {
+            order.push(pass);
+
+            for &to in &edges[pass] {
+                incoming[to] -= 1;
+                if incoming[to] == 0 && live[to] {
+                    ready.push(Reverse(to));
+                }
+            }
+        }
+
+        let live_count = live.iter().filter(|&&live| live).count();
+        if order.len() != live_count {
+            let passes = (0..self.passes.len())
+                .filter(|&pass| live[pass] && !order.contains(&pass))
+                .map(|pass| self.passes[pass].name.clone())
+                .collect();
+
+            return Err(RenderGraphError::Cycle(passes));
+        }
+
+        Ok(order)
+    }
+
+    fn validate_pass(&self, pass: &GraphPass) -> Result<(), RenderGraphError> {
+        let invalid = |texture: &GraphTexture| RenderGraphError::InvalidAttachment {
+            pass: pass.name.clone(),
+            resource: self.textures[texture.0].name.clone(),
+        };
+
+        if pass.is_render() && pass.write_textures.is_empty() && pass.depth.is_none() {
+            return Err(RenderGraphError::NoAttachments(pass.name.clone()));
+        }
+
+        for texture in &pass.write_textures {
+            let entry = &self.textures[texture.0];
+            let is_depth = self.texture_format(texture).is_some_and(|f| f.is_depth());
+            let is_surface = matches!(entry.source, TextureSource::Surface);
+
+            if is_depth || (is_surface && !pass.is_render()) {
+                return Err(invalid(texture));
+            }
+
+            if pass.read_textures.contains(texture) {
+                return Err(RenderGraphError::ReadWriteConflict {
+                    pass: pass.name.clone(),
+                    resource: entry.name.clone(),
+                });
+            }
+        }
+
+        if let Some(depth) = &pass.depth {
+            if !pass.is_render() || !self.texture_format(depth).is_some_and(|f| f.is_depth()) {
+                return Err(invalid(depth));
+            }
+
+            if pass.read_textures.contains(depth) {
+                return Err(RenderGraphError::ReadWriteConflict {
+                    pass: pass.name.clone(),
+                    resource: self.textures[depth.0].name.clone(),
+                });
+            }
+        }
+
+        for texture in &pass.read_textures {
+            if matches!(self.textures[texture.0].source, TextureSource::Surface) {
+                return Err(invalid(texture));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn texture_format(&self, texture: &GraphTexture) -> Option<TextureFormat> {
+        match &self.textures[texture.0].source {
+            TextureSource::Transient { format, .. } => Some(*format),
+            TextureSource::Imported(texture) => Some(texture.format()),
+            TextureSource::Surface => None,
+        }
+    }
+}
+
+/// Declares the resources used by a pass of a [RenderGraph].
+pub struct GraphPassBuilder<'g, 'a> {
+    graph: &'g mut RenderGraph<'a>,
+    name: String,
+
+    write_textures: Vec<GraphTexture>,
+    depth: Option<GraphTexture>,
+    read_textures: Vec<GraphTexture>,
+    write_buffers: Vec<GraphBuffer>,
+    read_buffers: Vec<GraphBuffer>,
+}
+
+impl<'a> GraphPassBuilder<'_, 'a> {
+    /// The pass samples the texture.
+    pub fn read_texture(mut self, texture: GraphTexture) -> Self {
+        if !self.read_textures.contains(&texture) {
+            self.read_textures.push(texture);
+        }
+        self
+    }
+
+    /// The pass writes the texture, as the next color attachment of a render pass or as a
+    /// storage texture of a compute pass.
+    pub fn write_texture(mut self, texture: GraphTexture) -> Self {
+        if !self.write_textures.contains(&texture) {
+            self.write_textures.push(texture);
+        }
+        self
+    }
+
+    /// Sets the depth attachment of a render pass.
+    pub fn set_depth_texture(mut self, texture: GraphTexture) -> Self {
+        self.depth = Some(texture);
+        self
+    }
+
+    pub fn read_buffer(mut self, buffer: GraphBuffer) -> Self {
+        if !self.read_buffers.contains(&buffer) {
+            self.read_buffers.push(buffer);
+        }
+        self
+    }
+
+    pub fn write_buffer(mut self, buffer: GraphBuffer) -> Self {
+        if !self.write_buffers.contains(&buffer) {
+            self.write_buffers.push(buffer);
+        }
+        self
+    }
+
+    /// Adds the pass as a render pass, `f` records its draws.
+    pub fn render<F>(self, f: F)
+    where
+        F: FnOnce(&mut RenderPass, &GraphResources) + 'a,
+    {
+        self.finish(PassCallback::Render(Box::new(f)));
+    }
+
+    /// Adds the pass as a compute pass, `f` records its dispatches.
+    pub fn compute<F>(self, f: F)
+    where
+        F: FnOnce(&mut ComputePass, &GraphResources) + 'a,
+    {
+        self.finish(PassCallback::Compute(Box::new(f)));
+    }
+
+    fn finish(self, callback: PassCallback<'a>) {
+        self.graph.passes.push(GraphPass {
+            name: self.name,
+            callback,
+            write_textures: self.write_textures,
+            depth: self.depth,
+            read_textures: self.read_textures,
+            write_buffers: self.write_buffers,
+            read_buffers: self.read_buffers,
+        });
+    }
+}
+
+/// Resources of a [RenderGraph] available to the pass being recorded.
+#[derive(Clone, Debug)]
+pub struct GraphResources {
+    textures: Vec<Option<Texture>>,
+    buffers: Vec<Option<Buffer>>,
+}
+
+impl GraphResources {
+    /// Returns the texture, [None] for the surface or a transient texture the current pass
+    /// doesn't declare.
+    pub fn texture(&self, texture: GraphTexture) -> Option<&Texture> {
+        self.textures.get(texture.0)?.as_ref()
+    }
+
+    /// Returns the buffer, [None] for a transient buffer the current pass doesn't declare.
+    pub fn buffer(&self, buffer: GraphBuffer) -> Option<&Buffer> {
+        self.buffers.get(buffer.0)?.as_ref()
+    }
+}
+
+type TransientBufferKey = (u64, BufferUsage);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct TransientTextureKey {
+    size: (i32, i32),
+    format: TextureFormat,
+    usage: TextureUsage,
+}
+
+/// Transient textures and buffers of the render graphs, kept between frames.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GraphResourcePool {
+    textures: HashMap<TransientTextureKey, Vec<(ArcRef<TextureInner>, u32)>>,
+    buffers: HashMap<TransientBufferKey, Vec<(ArcRef<BufferInner>, u32)>>,
+}
+
+impl GraphResourcePool {
+    fn take_texture(&mut self, key: &TransientTextureKey) -> Option<ArcRef<TextureInner>> {
+        self.textures
+            .get_mut(key)?
+            .pop()
+            .map(|(texture, _)| texture)
+    }
+
+    fn release_texture(&mut self, key: TransientTextureKey, texture: ArcRef<TextureInner>) {
+        self.textures.entry(key).or_default().push((texture, 0));
+    }
+
+    fn take_buffer(&mut self, key: &TransientBufferKey) -> Option<ArcRef<BufferInner>> {
+        self.buffers.get_mut(key)?.pop().map(|(buffer, _)| buffer)
+    }
+
+    fn release_buffer(&mut self, key: TransientBufferKey, buffer: ArcRef<BufferInner>) {
+        self.buffers.entry(key).or_default().push((buffer, 0));
+    }
+
+    pub fn clear(&mut self) {
+        self.textures.clear();
+        self.buffers.clear();
+    }
+
+    pub fn cycle(&mut self) {
+        for entries in self.textures.values_mut() {
+            entries.retain_mut(|(_, lifetime)| {
+                *lifetime += 1;
+                *lifetime < POOL_LIFETIME_FRAMES
+            });
+        }
+
+        for entries in self.buffers.values_mut() {
+            entries.retain_mut(|(_, lifetime)| {
+                *lifetime += 1;
+                *lifetime < POOL_LIFETIME_FRAMES
+            });
+        }
+
+        self.textures.retain(|_, entries| !entries.is_empty());
+        self.buffers.retain(|_, entries| !entries.is_empty());
+    }
+}
+
+fn acquire_texture(
+    graphics: &ArcRef<GPUInner>,
+    key: &TransientTextureKey,
+    label: &str,
+) -> Result<Texture, RenderGraphError> {
+    let pooled = graphics.borrow_mut().graph_pool.take_texture(key);
+    if let Some(inner) = pooled {
+        return Ok(Texture {
+            graphics: ArcRef::clone(graphics),
+            inner,
+            mapped_buffer: vec![],
+            mapped_type: TextureMappedType::Write,
+        });
+    }
+
+    let size = Point2::new(key.size.0, key.size.1);
+    let builder = TextureBuilder::new(ArcRef::clone(graphics));
+    let builder = if key.format.is_depth() {
+        builder.set_depth_stencil(size, Some(key.format))
+    } else {
+        builder.set_render_target(size, Some(key.format))
+    };
+
+    builder
+        .set_usage(key.usage)
+        .set_label(label)
+        .build()
+        .map_err(RenderGraphError::Texture)
+}
+
+fn acquire_buffer(
+    graphics: &ArcRef<GPUInner>,
+    size: u64,
+    usage: BufferUsage,
+    label: &str,
+) -> Result<Buffer, RenderGraphError> {
+    let pooled = graphics.borrow_mut().graph_pool.take_buffer(&(size, usage));
+    if let Some(inner) = pooled {
+        return Ok(Buffer {
+            graphics: ArcRef::clone(graphics),
+            inner,
+            mapped_buffer: vec![],
+            mapped_type: BufferMapMode::Write,
+        });
+    }
+
+    BufferBuilder::<u8>::new(ArcRef::clone(graphics))
+        .set_data_empty(size as usize)
+        .set_usage(usage)
+        .set_label(label)
+        .build()
+        .map_err(RenderGraphError::Buffer)
+}
+
+#[derive(Debug)]
+pub enum RenderGraphError {
+    /// The passes depend on each other.
+    Cycle(Vec<String>),
+    /// A transient resource is read but no pass writes it.
+    ReadBeforeWrite(String),
+    /// A render pass has no color or depth attachment.
+    NoAttachments(String),
+    /// The texture can't be used this way by the pass, eg. a depth format as a color
+    /// attachment or the surface in a compute pass.
+    InvalidAttachment {
+        pass: String,
+        resource: String,
+    },
+    /// The pass reads a texture it also writes.
+    ReadWriteConflict {
+        pass: String,
+        resource: String,
+    },
+    Texture(TextureError),
+    Buffer(BufferError),
+    RenderPass(RenderPassBuildError),
+    ComputePass(ComputePassBuildError),
+    Surface(SurfaceTextureError),
+}
+
+impl std::fmt::Display for RenderGraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderGraphError::Cycle(passes) => {
+                write!(
+                    f,
+                    "Render graph passes depend on each other: {}",
+                    passes.join(", ")
+                )
+            }
+            RenderGraphError::ReadBeforeWrite(resource) => {
+                write!(f, "Resource '{}' is read but never written", resource)
+            }
+            RenderGraphError::NoAttachments(pass) => {
+                write!(f, "Render pass '{}' has no color or depth attachment", pass)
+            }
+            RenderGraphError::InvalidAttachment { pass, resource } => write!(
+                f,
+                "Texture '{}' cannot be used this way by pass '{}'",
+                resource, pass
+            ),
+            RenderGraphError::ReadWriteConflict { pass, resource } => {
+                write!(f, "Pass '{}' reads and writes texture '{}'", pass, resource)
+            }
+            RenderGraphError::Texture(e) => write!(f, "Texture error: {}", e),
+            RenderGraphError::Buffer(e) => write!(f, "Buffer error: {:?}", e),
+            RenderGraphError::RenderPass(e) => write!(f, "Render pass error: {}", e),
+            RenderGraphError::ComputePass(e) => write!(f, "Compute pass error: {:?}", e),
+            RenderGraphError::Surface(e) => write!(f, "Surface error: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,1031 @@
+//! GPU meshes made of a vertex buffer, a 32-bit index buffer, submeshes and materials.
+//!
+//! A [Mesh] is built with [GPU::create_mesh](super::GPU::create_mesh), either from a
+//! [geometry::Mesh](super::geometry::Mesh), from raw [MeshVertex] data, from a Wavefront OBJ
+//! file or, with the `gltf` feature, from a glTF 2.0 file.
+//!
+//! Loaded files keep their counter-clockwise winding, set
+//! [ShaderFrontFace::CounterClockwise](super::shader::ShaderFrontFace::CounterClockwise) on the
+//! shader when culling back faces.
+//!
+//! The glTF loader flattens the default scene into a single mesh: node transforms are baked into
+//! the vertices, every triangle primitive becomes a [Submesh] and every glTF material a
+//! [Material]. Missing normals are computed from the triangles, missing tangents default to
+//! `(1, 0, 0, 1)`. Primitives that aren't triangle lists are skipped.
+//!
+//! The OBJ loader turns every `usemtl` run of faces into a [Submesh], polygons are triangulated as
+//! fans. Materials are read from the `mtllib` files, their textures aren't loaded, only their
+//! paths are set in [Material::base_color_texture_path] and [Material::normal_texture_path].
+//!
+//! # Example
+//! ```ignore
+//! let mesh = gpu.create_mesh().set_gltf_file("assets/helmet.glb").build()?;
+//!
+//! // Every frame, with a shader using IndexBufferSize::U32
+//! for (index, submesh) in mesh.submeshes.iter().enumerate() {
+//!     if let Some(material) = submesh.material.map(|m| &mesh.materials[m]) {
+//!         // bind material.base_color_texture, material.normal_texture...
+//!     }
+//!
+//!     mesh.draw_submesh(&mut render_pass, index);
+//! }
+//! ```
+
+use std::{borrow::Cow, ops::Range, path::PathBuf};
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::math::{Color, Vector2, Vector3, Vector4};
+
+use super::{
+    ArcRef, GPU, GPUInner,
+    buffer::{Buffer, BufferBuilder, BufferError, BufferUsage},
+    command::renderpass::RenderPass,
+    geometry,
+    texture::{Texture, TextureError},
+};
+
+#[cfg(feature = "gltf")]
+use super::texture::{
+    AddressMode, FilterMode, TextureBuilder, TextureFormat, TextureSampler, TextureUsage,
+};
+
+#[cfg(feature = "gltf")]
+use crate::math::Point2;
+
+/// To use this vertex struct in your shader, you need to use this WGSL code as your vertex type:
+/// ```wgsl
+/// struct VertexInput {
+///     @location(0) position: vec3<f32>,
+///     @location(1) normal: vec3<f32>,
+///     @location(2) texCoord: vec2<f32>,
+///     @location(3) tangent: vec4<f32>,
+/// };
+/// ```
+///
+/// The `w` component of the tangent is the handedness of the bitangent,
+/// `bitangent = cross(normal, tangent.xyz) * tangent.w`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Pod, Zeroable)]
+pub struct MeshVertex {
+    pub position: Vector3,
+    pub normal: Vector3,
+    pub texcoord: Vector2,
+    pub tangent: Vector4,
+}
+
+impl MeshVertex {
+    pub fn new(position: Vector3, normal: Vector3, texcoord: Vector2, tangent: Vector4) -> Self {
+        Self {
+            position,
+            normal,
+            texcoord,
+            tangent,
+        }
+    }
+}
+
+/// Range of the index buffer drawn with one material.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submesh {
+    pub index_range: Range<u32>,
+    /// Index in [Mesh::materials], [None] when the primitive has no material.
+    pub material: Option<usize>,
+}
+
+/// Metallic-roughness material of a mesh.
+///
+/// The textures are created with [TextureUsage::Sampler](super::texture::TextureUsage::Sampler),
+/// the base color texture is sRGB and the others are linear.
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub name: Option<String>,
+    pub base_color: Color,
+    pub base_color_texture: Option<Texture>,
+    pub normal_texture: Option<Texture>,
+    /// Roughness in the green channel and metalness in the blue channel.
+    pub metallic_roughness_texture: Option<Texture>,
+    pub metallic: f32,
+    pub roughness: f32,
+    /// Path of the base color texture, set by the OBJ loader.
+    pub base_color_texture_path: Option<PathBuf>,
+    /// Path of the normal map, set by the OBJ loader.
+    pub normal_texture_path: Option<PathBuf>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            name: None,
+            base_color: Color::WHITE,
+            base_color_texture: None,
+            normal_texture: None,
+            metallic_roughness_texture: None,
+            metallic: 1.0,
+            roughness: 1.0,
+            base_color_texture_path: None,
+            normal_texture_path: None,
+        }
+    }
+}
+
+/// Vertex and index buffers of a mesh, with its submeshes and materials.
+///
+/// The vertex buffer holds [MeshVertex] and the index buffer 32-bit indices, the shader used to
+/// draw it must be set up with `IndexBufferSize::U32`.
+#[derive(Debug, Clone)]
+pub struct Mesh {
+    pub vertex_buffer: Buffer,
+    pub index_buffer: Buffer,
+    pub vertex_count: u32,
+    pub index_count: u32,
+    pub submeshes: Vec<Submesh>,
+    pub materials: Vec<Material>,
+}
+
+impl Mesh {
+    /// Loads a Wavefront OBJ file and its materials, see [MeshBuilder::set_obj_file].
+    pub fn from_obj(gpu: &mut GPU, path: &str) -> Result<Mesh, MeshError> {
+        gpu.create_mesh().set_obj_file(path).build()
+    }
+
+    /// Binds the buffers and draws the whole mesh in a single draw call.
+    pub fn draw(&self, pass: &mut RenderPass) {
+        pass.set_gpu_buffer(Some(&self.vertex_buffer), Some(&self.index_buffer));
+        pass.draw_indexed(0..self.index_count, 0, 1);
+    }
+
+    /// Binds the buffers and draws a single submesh, its material must be bound beforehand.
+    ///
+    /// Panics if `index` is out of range.
+    pub fn draw_submesh(&self, pass: &mut RenderPass, index: usize) {
+        let submesh = &self.submeshes[index];
+
+        pass.set_gpu_buffer(Some(&self.vertex_buffer), Some(&self.index_buffer));
+        pass.draw_indexed(submesh.index_range.clone(), 0, 1);
+    }
+
+    /// Material of a submesh, if it has one.
+    pub fn submesh_material(&self, index: usize) -> Option<&Material> {
+        self.submeshes
+            .get(index)
+            .and_then(|submesh| submesh.material)
+            .and_then(|material| self.materials.get(material))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum MeshError {
+    /// No source was set on the builder.
+    NoSource,
+    /// The source didn't contain any triangle.
+    NoGeometry,
+    /// The glTF file couldn't be read or parsed.
+    Gltf(String),
+    /// The OBJ file couldn't be read or parsed.
+    Obj(String),
+    Buffer(BufferError),
+    Texture(TextureError),
+}
+
+impl std::fmt::Display for MeshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeshError::NoSource => write!(f, "No mesh source was set"),
+            MeshError::NoGeometry => write!(f, "Mesh has no triangles"),
+            MeshError::Gltf(e) => write!(f, "Failed to load glTF: {}", e),
+            MeshError::Obj(e) => write!(f, "Failed to load OBJ: {}", e),
+            MeshError::Buffer(e) => write!(f, "Failed to create mesh buffer: {:?}", e),
+            MeshError::Texture(e) => write!(f, "Failed to create mesh texture: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+enum MeshBuilderData<'a> {
+    None,
+    Vertices(Cow<'a, [MeshVertex]>, Cow<'a, [u32]>),
+    ObjFile(&'a str),
+    #[cfg(feature = "gltf")]
+    GltfFile(&'a str),
+    #[cfg(feature = "gltf")]
+    GltfData(&'a [u8]),
+}
+
+/// Builder for creating meshes from geometry, raw vertices or glTF files.
+pub struct MeshBuilder<'a> {
+    graphics: ArcRef<GPUInner>,
+    data: MeshBuilderData<'a>,
+    materials: Vec<Material>,
+    label: Option<&'a str>,
+}
+
+impl<'a> MeshBuilder<'a> {
+    pub(crate) fn new(graphics: ArcRef<GPUInner>) -> Self {
+        Self {
+            graphics,
+            data: MeshBuilderData::None,
+            materials: Vec::new(),
+            label: None,
+        }
+    }
+
+    /// Creates the mesh from a generated shape, as a single submesh.
+    ///
+    /// The vertex colors are dropped and the tangents default to `(1, 0, 0, 1)`.
+    pub fn set_geometry(mut self, mesh: &geometry::Mesh) -> Self {
+        let vertices = mesh
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, vertex)| {
+                MeshVertex::new(
+                    vertex.position,
+                    mesh.normals
+                        .get(i)
+                        .copied()
+                        .unwrap_or(Vector3::new(0.0, 0.0, 1.0)),
+                    vertex.texcoord,
+                    DEFAULT_TANGENT,
+                )
+            })
+            .collect();
+
+        self.data =
+            MeshBuilderData::Vertices(Cow::Owned(vertices), Cow::Owned(mesh.indices.clone()));
+        self
+    }
+
+    /// Creates the mesh from raw vertices and triangle list indices, as a single submesh.
+    pub fn set_vertices(mut self, vertices: &'a [MeshVertex], indices: &'a [u32]) -> Self {
+        self.data = MeshBuilderData::Vertices(Cow::Borrowed(vertices), Cow::Borrowed(indices));
+        self
+    }
+
+    /// Sets the material of the submesh created by [MeshBuilder::set_geometry] or
+    /// [MeshBuilder::set_vertices], ignored for glTF files.
+    pub fn set_material(mut self, material: Material) -> Self {
+        self.materials = vec![material];
+        self
+    }
+
+    /// Loads the mesh from a Wavefront OBJ file, the `mtllib` files are resolved relative to it.
+    pub fn set_obj_file(mut self, path: &'a str) -> Self {
+        self.data = MeshBuilderData::ObjFile(path);
+        self
+    }
+
+    /// Loads the mesh from a `.gltf` or `.glb` file, external buffers and images are resolved
+    /// relative to it.
+    #[cfg(feature = "gltf")]
+    pub fn set_gltf_file(mut self, path: &'a str) -> Self {
+        self.data = MeshBuilderData::GltfFile(path);
+        self
+    }
+
+    /// Loads the mesh from `.glb` data, or `.gltf` data with embedded buffers and images.
+    #[cfg(feature = "gltf")]
+    pub fn set_gltf_data(mut self, data: &'a [u8]) -> Self {
+        self.data = MeshBuilderData::GltfData(data);
+        self
+    }
+
+    pub fn set_label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub fn build(self) -> Result<Mesh, MeshError> {
+        let (vertices, indices, submeshes, materials) = match self.data {
+            MeshBuilderData::None => return Err(MeshError::NoSource),
+            MeshBuilderData::Vertices(vertices, indices) => {
+                let submesh = Submesh {
+                    index_range: 0..indices.len() as u32,
+                    material: if self.materials.is_empty() {
+                        None
+                    } else {
+                        Some(0)
+                    },
+                };
+
+                (
+                    vertices.into_owned(),
+                    indices.into_owned(),
+                    vec![submesh],
+                    self.materials,
+                )
+            }
+            MeshBuilderData::ObjFile(path) => obj_loader::load(path)?,
+            #[cfg(feature = "gltf")]
+            MeshBuilderData::GltfFile(path) => {
+                let import = gltf::import(path).map_err(|e| MeshError::Gltf(e.to_string()))?;
+                gltf_loader::load(&self.graphics, import)?
+            }
+            #[cfg(feature = "gltf")]
+            MeshBuilderData::GltfData(data) => {
+                let import =
+                    gltf::import_slice(data).map_err(|e| MeshError::Gltf(e.to_string()))?;
+                gltf_loader::load(&self.graphics, import)?
+            }
+        };
+
+        if vertices.is_empty() || indices.len() < 3 {
+            return Err(MeshError::NoGeometry);
+        }
+
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        {
+            if let Some(index) = indices.iter().find(|&&i| i as usize >= vertices.len()) {
+                panic!(
+                    "Mesh index {} is out of range, the mesh has {} vertices",
+                    index,
+                    vertices.len()
+                );
+            }
+        }
+
+        let label = self.label.unwrap_or("Mesh");
+
+        let vertex_buffer = BufferBuilder::<MeshVertex>::new(self.graphics.clone())
+            .set_data_slice(&vertices)
+            .set_usage(BufferUsage::VERTEX)
+            .set_label(&format!("{} Vertex Buffer", label))
+            .build()
+            .map_err(MeshError::Buffer)?;
+
+        let index_buffer = BufferBuilder::<u32>::new(self.graphics.clone())
+            .set_data_slice(&indices)
+            .set_usage(BufferUsage::INDEX)
+            .set_label(&format!("{} Index Buffer", label))
+            .build()
+            .map_err(MeshError::Buffer)?;
+
+        Ok(Mesh {
+            vertex_buffer,
+            index_buffer,
+            vertex_count: vertices.len() as u32,
+            index_count: indices.len() as u32,
+            submeshes,
+            materials,
+        })
+    }
+}
+
+const DEFAULT_TANGENT: Vector4 = Vector4 {
+    x: 1.0,
+    y: 0.0,
+    z: 0.0,
+    w: 1.0,
+};
+
+type Loaded = (Vec<MeshVertex>, Vec<u32>, Vec<Submesh>, Vec<Material>);
+
+/// Sets the normals of the vertices without one to the area weighted face normals of their
+/// triangles.
+fn compute_normals(vertices: &mut [MeshVertex], indices: &[u32]) {
+    let zero = Vector3::new(0.0, 0.0, 0.0);
+    let missing = vertices
+        .iter()
+        .map(|vertex| vertex.normal == zero)
+        .collect::<Vec<_>>();
+
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize].position);
+        let normal = (b - a).cross(&(c - a));
+
+        for &i in triangle {
+            if missing[i as usize] {
+                vertices[i as usize].normal += normal;
+            }
+        }
+    }
+
+    for (vertex, _) in vertices
+        .iter_mut()
+        .zip(missing)
+        .filter(|(_, missing)| *missing)
+    {
+        if vertex.normal.length() > f32::EPSILON {
+            vertex.normal = vertex.normal.normalize();
+        } else {
+            vertex.normal = Vector3::new(0.0, 0.0, 1.0);
+        }
+    }
+}
+
+#[cfg(feature = "gltf")]
+mod gltf_loader {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::math::{Matrix4, Quat, Transform};
+
+    struct Loader<'a> {
+        graphics: &'a ArcRef<GPUInner>,
+        buffers: &'a [gltf::buffer::Data],
+        images: &'a [gltf::image::Data],
+        /// Textures already created, by image index and sRGB.
+        textures: HashMap<(usize, bool), Texture>,
+
+        vertices: Vec<MeshVertex>,
+        indices: Vec<u32>,
+        submeshes: Vec<Submesh>,
+    }
+
+    pub(super) fn load(
+        graphics: &ArcRef<GPUInner>,
+        (document, buffers, images): (
+            gltf::Document,
+            Vec<gltf::buffer::Data>,
+            Vec<gltf::image::Data>,
+        ),
+    ) -> Result<Loaded, MeshError> {
+        let mut loader = Loader {
+            graphics,
+            buffers: &buffers,
+            images: &images,
+            textures: HashMap::new(),
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            submeshes: Vec::new(),
+        };
+
+        let materials = document
+            .materials()
+            .map(|material| loader.load_material(&material))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+        {
+            Some(scene) => {
+                for node in scene.nodes() {
+                    loader.load_node(&node, &Matrix4::identity());
+                }
+            }
+            // Files without scenes only hold meshes, they're loaded untransformed.
+            None => {
+                for mesh in document.meshes() {
+                    loader.load_mesh(&mesh, &Matrix4::identity());
+                }
+            }
+        }
+
+        Ok((loader.vertices, loader.indices, loader.submeshes, materials))
+    }
+
+    impl Loader<'_> {
+        fn load_node(&mut self, node: &gltf::Node, parent: &Matrix4) {
+            let (translation, [x, y, z, w], scale) = node.transform().decomposed();
+            let local = Transform::new(
+                Vector3::from(translation),
+                Quat::new(x, y, z, w),
+                Vector3::from(scale),
+            );
+            let transform = *parent * local.to_matrix();
+
+            if let Some(mesh) = node.mesh() {
+                self.load_mesh(&mesh, &transform);
+            }
+
+            for child in node.children() {
+                self.load_node(&child, &transform);
+            }
+        }
+
+        fn load_mesh(&mut self, mesh: &gltf::Mesh, transform: &Matrix4) {
+            let normal_matrix = transform.inverse().transpose();
+            let flip_winding = determinant3(transform) < 0.0;
+
+            for primitive in mesh.primitives() {
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    crate::warn_log!(
+                        "Skipping glTF primitive {} of mesh {}, only triangle lists are supported (got {:?})",
+                        primitive.index(),
+                        mesh.index(),
+                        primitive.mode()
+                    );
+                    continue;
+                }
+
+                let reader = primitive.reader(|buffer| Some(&self.buffers[buffer.index()]));
+
+                let Some(positions) = reader.read_positions() else {
+                    crate::warn_log!(
+                        "Skipping glTF primitive {} of mesh {}, it has no positions",
+                        primitive.index(),
+                        mesh.index()
+                    );
+                    continue;
+                };
+
+                let mut vertices = positions
+                    .map(|p| MeshVertex {
+                        position: *transform * Vector3::from(p),
+                        tangent: DEFAULT_TANGENT,
+                        ..Default::default()
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut indices = match reader.read_indices() {
+                    Some(indices) => indices.into_u32().collect::<Vec<_>>(),
+                    None => (0..vertices.len() as u32).collect(),
+                };
+                indices.truncate(indices.len() - indices.len() % 3);
+
+                if let Some(index) = indices.iter().find(|&&i| i as usize >= vertices.len()) {
+                    crate::warn_log!(
+                        "Skipping glTF primitive {} of mesh {}, index {} is out of range",
+                        primitive.index(),
+                        mesh.index(),
+                        index
+                    );
+                    continue;
+                }
+
+                if flip_winding {
+                    for triangle in indices.chunks_exact_mut(3) {
+                        triangle.swap(1, 2);
+                    }
+                }
+
+                match reader.read_normals() {
+                    Some(normals) => {
+                        for (vertex, n) in vertices.iter_mut().zip(normals) {
+                            vertex.normal = transform_direction(&normal_matrix, n);
+                        }
+                    }
+                    None => compute_normals(&mut vertices, &indices),
+                }
+
+                if let Some(texcoords) = reader.read_tex_coords(0) {
+                    for (vertex, uv) in vertices.iter_mut().zip(texcoords.into_f32()) {
+                        vertex.texcoord = Vector2::new(uv[0], uv[1]);
+                    }
+                }
+
+                if let Some(tangents) = reader.read_tangents() {
+                    for (vertex, t) in vertices.iter_mut().zip(tangents) {
+                        let tangent = transform_direction(transform, [t[0], t[1], t[2]]);
+                        let w = if flip_winding { -t[3] } else { t[3] };
+
+                        vertex.tangent = Vector4::new(tangent.x, tangent.y, tangent.z, w);
+                    }
+                }
+
+                let base_vertex = self.vertices.len() as u32;
+                let start = self.indices.len() as u32;
+
+                self.vertices.extend(vertices);
+                self.indices
+                    .extend(indices.into_iter().map(|i| i + base_vertex));
+                self.submeshes.push(Submesh {
+                    index_range: start..self.indices.len() as u32,
+                    material: primitive.material().index(),
+                });
+            }
+        }
+
+        fn load_material(&mut self, material: &gltf::Material) -> Result<Material, MeshError> {
+            let pbr = material.pbr_metallic_roughness();
+            let [r, g, b, a] = pbr.base_color_factor();
+
+            Ok(Material {
+                name: material.name().map(|name| name.to_string()),
+                base_color: Color::new(r, g, b, a),
+                base_color_texture: pbr
+                    .base_color_texture()
+                    .map(|info| self.load_texture(&info.texture(), true))
+                    .transpose()?,
+                normal_texture: material
+                    .normal_texture()
+                    .map(|info| self.load_texture(&info.texture(), false))
+                    .transpose()?,
+                metallic_roughness_texture: pbr
+                    .metallic_roughness_texture()
+                    .map(|info| self.load_texture(&info.texture(), false))
+                    .transpose()?,
+                metallic: pbr.metallic_factor(),
+                roughness: pbr.roughness_factor(),
+                ..Default::default()
+            })
+        }
+
+        fn load_texture(
+            &mut self,
+            texture: &gltf::Texture,
+            srgb: bool,
+        ) -> Result<Texture, MeshError> {
+            let image_index = texture.source().index();
+
+            if let Some(texture) = self.textures.get(&(image_index, srgb)) {
+                return Ok(texture.clone());
+            }
+
+            let image = &self.images[image_index];
+            let pixels = to_rgba8(image);
+            let format = if srgb {
+                TextureFormat::Rgba8UnormSrgb
+            } else {
+                TextureFormat::Rgba8Unorm
+            };
+
+            let result = TextureBuilder::new(self.graphics.clone())
+                .set_raw_image(
+                    &pixels,
+                    Point2::new(image.width as i32, image.height as i32),
+                    format,
+                )
+                .set_usage(TextureUsage::Sampler)
+                .set_sampler(to_sampler(&texture.sampler()))
+                .build()
+                .map_err(MeshError::Texture)?;
+
+            self.textures.insert((image_index, srgb), result.clone());
+            Ok(result)
+        }
+    }
+
+    fn to_rgba8(image: &gltf::image::Data) -> Vec<u8> {
+        use gltf::image::Format;
+
+        let pixels = &image.pixels;
+
+        match image.format {
+            Format::R8G8B8A8 => pixels.clone(),
+            Format::R8G8B8 => pixels
+                .chunks_exact(3)
+                .flat_map(|p| [p[0], p[1], p[2], 255])
+                .collect(),
+            Format::R8G8 => pixels
+                .chunks_exact(2)
+                .flat_map(|p| [p[0], p[1], 0, 255])
+                .collect(),
+            Format::R8 => pixels.iter().flat_map(|&p| [p, p, p, 255]).collect(),
+            // 16-bit channels are little endian, the high byte is kept.
+            Format::R16G16B16A16 => pixels
+                .chunks_exact(8)
+                .flat_map(|p| [p[1], p[3], p[5], p[7]])
+                .collect(),
+            Format::R16G16B16 => pixels
+                .chunks_exact(6)
+                .flat_map(|p| [p[1], p[3], p[5], 255])
+                .collect(),
+            Format::R16G16 => pixels
+                .chunks_exact(4)
+                .flat_map(|p| [p[1], p[3], 0, 255])
+                .collect(),
+            Format::R16 => pixels
+                .chunks_exact(2)
+                .flat_map(|p| [p[1], p[1], p[1], 255])
+                .collect(),
+            Format::R32G32B32FLOAT => pixels
+                .chunks_exact(12)
+                .flat_map(|p| {
+                    let c = |i: usize| float_to_u8(&p[i * 4..i * 4 + 4]);
+                    [c(0), c(1), c(2), 255]
+                })
+                .collect(),
+            Format::R32G32B32A32FLOAT => pixels
+                .chunks_exact(16)
+                .flat_map(|p| {
+                    let c = |i: usize| float_to_u8(&p[i * 4..i * 4 + 4]);
+                    [c(0), c(1), c(2), c(3)]
+                })
+                .collect(),
+        }
+    }
+
+    fn float_to_u8(bytes: &[u8]) -> u8 {
+        let value = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+
+    fn to_sampler(sampler: &gltf::texture::Sampler) -> TextureSampler {
+        use gltf::texture::{MagFilter, WrappingMode};
+
+        let address_mode = |mode: WrappingMode| match mode {
+            WrappingMode::ClampToEdge => AddressMode::ClampToEdge,
+            WrappingMode::MirroredRepeat => AddressMode::MirrorRepeat,
+            WrappingMode::Repeat => AddressMode::Repeat,
+        };
+
+        let filter = match sampler.mag_filter() {
+            Some(MagFilter::Nearest) => FilterMode::Nearest,
+            _ => FilterMode::Linear,
+        };
+
+        let mut result = TextureSampler::DEFAULT.set_filter(filter);
+        result.address_mode_u = address_mode(sampler.wrap_s());
+        result.address_mode_v = address_mode(sampler.wrap_t());
+        result
+    }
+
+    /// Rotates and scales a direction by the upper 3x3 of `m`, then normalizes it.
+    fn transform_direction(m: &Matrix4, v: [f32; 3]) -> Vector3 {
+        // Matrix4::transform_point ignores the translation.
+        let v = m.transform_point(Vector3::from(v));
+
+        if v.length() > f32::EPSILON {
+            v.normalize()
+        } else {
+            v
+        }
+    }
+
+    fn determinant3(m: &Matrix4) -> f32 {
+        let m = &m.m;
+
+        m[0][0] * (m[1][1] * m[2][2] - m[2][1] * m[1][2])
+            - m[1][0] * (m[0][1] * m[2][2] - m[2][1] * m[0][2])
+            + m[2][0] * (m[0][1] * m[1][2] - m[1][1] * m[0][2])
+    }
+}
+
+mod obj_loader {
+    use std::{collections::HashMap, path::Path};
+
+    use super::*;
+
+    /// Position, texture coordinate and normal indices of a face corner.
+    type Corner = (usize, Option<usize>, Option<usize>);
+
+    #[derive(Default)]
+    struct Loader {
+        positions: Vec<[f32; 3]>,
+        texcoords: Vec<[f32; 2]>,
+        normals: Vec<[f32; 3]>,
+
+        materials: Vec<Material>,
+        /// Vertex index of every corner already added.
+        corners: HashMap<Corner, u32>,
+
+        vertices: Vec<MeshVertex>,
+        indices: Vec<u32>,
+        submeshes: Vec<Submesh>,
+        material: Option<usize>,
+    }
+
+    pub(super) fn load(path: &str) -> Result<Loaded, MeshError> {
+        let path = Path::new(path);
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| MeshError::Obj(format!("{}: {}", path.display(), e)))?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+
+        let mut loader = Loader::default();
+
+        for (number, line) in source.lines().enumerate() {
+            loader
+                .parse_line(line, directory)
+                .map_err(|e| MeshError::Obj(format!("{}:{}: {}", path.display(), number + 1, e)))?;
+        }
+
+        loader.end_submesh();
+        compute_normals(&mut loader.vertices, &loader.indices);
+
+        Ok((
+            loader.vertices,
+            loader.indices,
+            loader.submeshes,
+            loader.materials,
+        ))
+    }
+
+    impl Loader {
+        fn parse_line(&mut self, line: &str, directory: &Path) -> Result<(), String> {
+            let line = line.split('#').next().unwrap_or("");
+            let mut tokens = line.split_whitespace();
+
+            match tokens.next() {
+                Some("v") => {
+                    let [x, y, z] = parse_floats(tokens)?;
+                    self.positions.push([x, y, z]);
+                }
+                Some("vt") => {
+                    let [u, v] = parse_floats(tokens)?;
+                    // OBJ texture coordinates start at the bottom-left corner.
+                    self.texcoords.push([u, 1.0 - v]);
+                }
+                Some("vn") => {
+                    let [x, y, z] = parse_floats(tokens)?;
+                    self.normals.push([x, y, z]);
+                }
+                Some("f") => {
+                    let corners = tokens
+                        .map(|corner| self.add_corner(corner))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    if corners.len() < 3 {
+                        return Err("Face with less than 3 vertices".to_string());
+                    }
+
+                    for i in 1..corners.len() - 1 {
+                        self.indices
+                            .extend([corners[0], corners[i], corners[i + 1]]);
+                    }
+                }
+                Some("usemtl") => {
+                    let name = tokens.collect::<Vec<_>>().join(" ");
+
+                    self.end_submesh();
+                    self.material = self
+                        .materials
+                        .iter()
+                        .position(|material| material.name.as_deref() == Some(name.as_str()));
+
+                    if self.material.is_none() {
+                        crate::warn_log!(
+                            "OBJ material {} not found in the material libraries",
+                            name
+                        );
+                    }
+                }
+                Some("mtllib") => {
+                    for file in tokens {
+                        let path = directory.join(file);
+
+                        match std::fs::read_to_string(&path) {
+                            Ok(source) => {
+                                self.parse_mtl(&source, path.parent().unwrap_or(directory))
+                            }
+                            Err(e) => {
+                                crate::warn_log!(
+                                    "Failed to read OBJ material library {}: {}",
+                                    path.display(),
+                                    e
+                                );
+                            }
+                        }
+                    }
+                }
+                // Objects, groups and smoothing groups don't change the mesh.
+                _ => {}
+            }
+
+            Ok(())
+        }
+
+        /// Returns the vertex index of a `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner, adding
+        /// the vertex the first time the corner is used.
+        fn add_corner(&mut self, corner: &str) -> Result<u32, String> {
+            let mut parts = corner.split('/');
+
+            let position = resolve_index(parts.next(), self.positions.len())?
+                .ok_or_else(|| format!("Face corner {} has no position", corner))?;
+            let texcoord = resolve_index(parts.next(), self.texcoords.len())?;
+            let normal = resolve_index(parts.next(), self.normals.len())?;
+
+            let key = (position, texcoord, normal);
+            if let Some(&index) = self.corners.get(&key) {
+                return Ok(index);
+            }
+
+            let [x, y, z] = self.positions[position];
+            let vertex = MeshVertex {
+                position: Vector3::new(x, y, z),
+                normal: normal
+                    .map(|i| {
+                        Vector3::new(self.normals[i][0], self.normals[i][1], self.normals[i][2])
+                    })
+                    .unwrap_or_default(),
+                texcoord: texcoord
+                    .map(|i| Vector2::new(self.texcoords[i][0], self.texcoords[i][1]))
+                    .unwrap_or_default(),
+                tangent: DEFAULT_TANGENT,
+            };
+
+            let index = self.vertices.len() as u32;
+            self.vertices.push(vertex);
+            self.corners.insert(key, index);
+
+            Ok(index)
+        }
+
+        fn end_submesh(&mut self) {
+            let start = self
+                .submeshes
+                .last()
+                .map_or(0, |submesh| submesh.index_range.end);
+            let end = self.indices.len() as u32;
+
+            if end > start {
+                self.submeshes.push(Submesh {
+                    index_range: start..end,
+                    material: self.material,
+                });
+            }
+        }
+
+        fn parse_mtl(&mut self, source: &str, directory: &Path) {
+            for line in source.lines() {
+                let line = line.split('#').next().unwrap_or("");
+                let mut tokens = line.split_whitespace();
+
+                let Some(keyword) = tokens.next() else {
+                    continue;
+                };
+
+                if keyword == "newmtl" {
+                    self.materials.push(Material {
+                        name: Some(tokens.collect::<Vec<_>>().join(" ")),
+                        metallic: 0.0,
+                        ..Default::default()
+                    });
+                    continue;
+                }
+
+                let Some(material) = self.materials.last_mut() else {
+                    continue;
+                };
+
+                // Texture options such as `-s 1 1 1` come before the file name.
+                let texture_path = |tokens: std::str::SplitWhitespace| {
+                    tokens.last().map(|file| directory.join(file))
+                };
+
+                match keyword {
+                    "Kd" => {
+                        if let Ok([r, g, b]) = parse_floats(tokens) {
+                            material.base_color = Color::new(r, g, b, material.base_color.a);
+                        }
+                    }
+                    "d" => {
+                        if let Ok([alpha]) = parse_floats(tokens) {
+                            material.base_color.a = alpha.clamp(0.0, 1.0);
+                        }
+                    }
+                    "Tr" => {
+                        if let Ok([transparency]) = parse_floats(tokens) {
+                            material.base_color.a = (1.0 - transparency).clamp(0.0, 1.0);
+                        }
+                    }
+                    // Blinn-Phong exponent to roughness, as the square root of 2 / (Ns + 2).
+                    "Ns" => {
+                        if let Ok([exponent]) = parse_floats(tokens) {
+                            material.roughness = (2.0 / (exponent.max(0.0) + 2.0)).sqrt();
+                        }
+                    }
+                    "Pr" => {
+                        if let Ok([roughness]) = parse_floats(tokens) {
+                            material.roughness = roughness.clamp(0.0, 1.0);
+                        }
+                    }
+                    "Pm" => {
+                        if let Ok([metallic]) = parse_floats(tokens) {
+                            material.metallic = metallic.clamp(0.0, 1.0);
+                        }
+                    }
+                    "map_Kd" => material.base_color_texture_path = texture_path(tokens),
+                    "norm" | "bump" | "map_Bump" | "map_bump" => {
+                        material.normal_texture_path = texture_path(tokens)
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Parses the first `N` floats, extra values such as the `w` of positions are ignored.
+    fn parse_floats<'a, const N: usize>(
+        mut tokens: impl Iterator<Item = &'a str>,
+    ) -> Result<[f32; N], String> {
+        let mut result = [0.0; N];
+
+        for value in result.iter_mut() {
+            let token = tokens
+                .next()
+                .ok_or_else(|| format!("Expected {} values", N))?;
+
+            *value = token
+                .parse()
+                .map_err(|_| format!("Invalid number {}", token))?;
+        }
+
+        Ok(result)
+    }
+
+    /// Converts a 1-based, or negative relative, OBJ index to a 0-based index.
+    fn resolve_index(token: Option<&str>, len: usize) -> Result<Option<usize>, String> {
+        let Some(token) = token.filter(|token| !token.is_empty()) else {
+            return Ok(None);
+        };
+
+        let index = token
+            .parse::<i64>()
+            .map_err(|_| format!("Invalid index {}", token))?;
+
+        let resolved = if index < 0 {
+            len as i64 + index
+        } else {
+            index - 1
+        };
+
+        if resolved < 0 || resolved >= len as i64 {
+            return Err(format!("Index {} is out of range", index));
+        }
+
+        Ok(Some(resolved as usize))
+    }
+}
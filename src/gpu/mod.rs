@@ -1,10 +1,13 @@
-use std::sync::Arc;
+use std::{
+    path::PathBuf,
+    sync::{Arc, atomic::AtomicBool},
+};
 
 use wgpu::{PipelineCache, Surface};
 use winit::dpi::PhysicalSize;
 
 use crate::{
-    runner::Handle, utils::{ArcMut, ArcRef}, window::Window
+    runner::Handle, utils::{ArcMut, ArcRef, LockRank}, window::Window
 };
 
 use pipeline::{
@@ -14,33 +17,77 @@ use pipeline::{
 };
 
 use shader::{
+    bind_group::BindGroupBuilder,
     bind_group_manager::{BindGroupManager, BindGroupCreateInfo},
-    graphics::GraphicsShaderBuilder,
+    graphics::{GraphicsShader, GraphicsShaderBuilder},
     compute::ComputeShaderBuilder,
 };
 
 use command::{
-    CommandBuffer, CommandBufferBuildError,
+    CommandBuffer, CommandBufferBuildError, FinishedCommand,
     SurfaceTexture,
     drawing::DrawingGlobalState
 };
 
 use texture::{
-    TextureBuilder, TextureFormat,
-    atlas::TextureAtlasBuilder
+    TextureBuilder, TextureError, TextureFormat,
+    animated::AnimatedTextureBuilder,
+    atlas::{TextureAtlas, TextureAtlasBuilder, TextureAtlasBuilderError},
+    embedded::EmbeddedTarget,
+    pingpong::PingPongTargetBuilder,
+    scaled::{PresentScaling, ScaledTarget},
+    streaming::{StreamingFormat, StreamingTexture, StreamingTextureError},
+    msaa::{MsaaTargets, MsaaTextureCache},
+    yuv::YuvGlobalState,
 };
 
 use pipeline::manager::{ComputePipelineDesc, GraphicsPipelineDesc};
 
+use error::{ErrorHandler, GpuError};
+use diagnostics::{DiagnosticsRecorder, DiagnosticsReport};
+use recovery::ResourceRegistry;
+use deletion::DeletionQueue;
+
 use buffer::{
+    Buffer,
     BufferBuilder,
+    BufferError,
+    BufferUsage,
+    gpu_vec::GpuVec,
+    pool::BufferPool,
+    uniform::Uniform,
+    uniform_arena::{UniformArena, UniformArenaError},
     staging_buffer::StagingBuffer,
 };
 
+use profiler::{GpuProfiler, ProfilerError};
+use debug_overlay::DebugOverlay;
+
+use budget::{BudgetExceeded, FrameBudget, FrameBudgetTracker, FrameUsage};
+use stats::{FrameStatsTracker, GpuStats};
+use cache::CachePolicy;
+
+use graph::{GraphResourcePool, RenderGraph};
+
+pub mod budget;
 pub mod buffer;
+pub mod cache;
 pub mod command;
+pub mod compute_utils;
+pub mod debug_draw;
+pub mod debug_overlay;
+pub(crate) mod deletion;
+pub mod diagnostics;
+pub mod effects;
+pub mod error;
+pub mod geometry;
+pub mod graph;
+pub mod mesh;
 pub mod pipeline;
+pub mod profiler;
+pub(crate) mod recovery;
 pub mod shader;
+pub mod stats;
 pub mod texture;
 
 /// Creates a new [GPU] instance.
@@ -63,6 +110,9 @@ pub fn new<'a>(window: Option<&'a mut crate::window::Window>) -> GPUBuilder<'a>
 /// graphics APIs, allowing you to choose the best GPU and graphics API for your application.
 ///
 /// This function can be called from any thread.
+///
+/// On the web this always returns an empty list, the browser only picks the adapter when
+/// the [GPU] is built.
 pub fn query_gpu_adapter(window: Option<&crate::window::Window>) -> Vec<GPUAdapter> {
     let mut window_arc = None;
     if let Some(window) = window {
@@ -80,6 +130,47 @@ pub fn query_gpu_adapter(window: Option<&crate::window::Window>) -> Vec<GPUAdapt
     GPU::query_gpu(window_arc)
 }
 
+/// Copies the content of `src` into `dst`, which may belong to another [GPU], eg. one created
+/// with [GPU::create_offload_gpu].
+///
+/// Between different devices the data goes through CPU memory: `src` is read back, then written
+/// to `dst`, and both devices are waited on. Between buffers of the same device this is a plain
+/// GPU copy.
+///
+/// `src` must have [BufferUsage::COPY_SRC] or [BufferUsage::MAP_READ], `dst` must have
+/// [BufferUsage::COPY_DST] and be at least as large as `src`.
+pub fn copy_buffer_between_gpus(src: &Buffer, dst: &Buffer) -> Result<(), BufferError> {
+    let src_usage = src.usage();
+    if !src_usage.contains(BufferUsage::COPY_SRC) && !src_usage.contains(BufferUsage::MAP_READ) {
+        return Err(BufferError::BufferNotReadable);
+    }
+
+    if !dst.usage().contains(BufferUsage::COPY_DST) {
+        return Err(BufferError::BufferNotWritable);
+    }
+
+    if dst.size() < src.size() {
+        return Err(BufferError::InvalidSize);
+    }
+
+    let same_device = ArcRef::ptr_eq(&src.graphics, &dst.graphics)
+        || src.graphics.borrow().device == dst.graphics.borrow().device;
+
+    if same_device {
+        if !src_usage.contains(BufferUsage::COPY_SRC) {
+            return Err(BufferError::BufferNotReadable);
+        }
+
+        dst.write(src);
+        return Ok(());
+    }
+
+    let data = src.read::<u8>()?;
+    dst.write_raw(&data);
+
+    Ok(())
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AdapterBackend {
     None,
@@ -90,6 +181,158 @@ pub enum AdapterBackend {
     BrowserWebGpu,
 }
 
+/// How frames are queued for presentation to the window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PresentMode {
+    /// Vsync, frames wait for the vertical blank. Always supported.
+    Fifo,
+    /// Vsync, but a late frame is shown immediately, which may tear.
+    FifoRelaxed,
+    /// No vsync, frames are shown immediately and may tear.
+    Immediate,
+    /// No tearing, the most recent frame replaces the queued one instead of waiting.
+    Mailbox,
+    /// Vsync, picks [PresentMode::FifoRelaxed] when supported, otherwise [PresentMode::Fifo].
+    /// Always supported.
+    AutoVsync,
+    /// No vsync, picks [PresentMode::Immediate], then [PresentMode::Mailbox], then
+    /// [PresentMode::Fifo] depending on what is supported. Always supported.
+    AutoNoVsync,
+}
+
+impl PresentMode {
+    fn from_wgpu(mode: wgpu::PresentMode) -> Option<Self> {
+        match mode {
+            wgpu::PresentMode::Fifo => Some(PresentMode::Fifo),
+            wgpu::PresentMode::FifoRelaxed => Some(PresentMode::FifoRelaxed),
+            wgpu::PresentMode::Immediate => Some(PresentMode::Immediate),
+            wgpu::PresentMode::Mailbox => Some(PresentMode::Mailbox),
+            wgpu::PresentMode::AutoVsync => Some(PresentMode::AutoVsync),
+            wgpu::PresentMode::AutoNoVsync => Some(PresentMode::AutoNoVsync),
+        }
+    }
+
+    /// Whether the mode waits for the vertical blank.
+    pub fn is_vsync(&self) -> bool {
+        matches!(
+            self,
+            PresentMode::Fifo | PresentMode::FifoRelaxed | PresentMode::AutoVsync
+        )
+    }
+}
+
+impl From<PresentMode> for wgpu::PresentMode {
+    fn from(mode: PresentMode) -> Self {
+        match mode {
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::FifoRelaxed => wgpu::PresentMode::FifoRelaxed,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::AutoVsync => wgpu::PresentMode::AutoVsync,
+            PresentMode::AutoNoVsync => wgpu::PresentMode::AutoNoVsync,
+        }
+    }
+}
+
+/// How the alpha channel of the presented frames is composited with the desktop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SurfaceAlphaMode {
+    /// The alpha channel is ignored, the window is opaque.
+    Opaque,
+    /// The colors are expected to be premultiplied by alpha.
+    PreMultiplied,
+    /// The colors are multiplied by alpha by the compositor.
+    PostMultiplied,
+    /// Decided by the platform, set through native window APIs.
+    Inherit,
+}
+
+impl SurfaceAlphaMode {
+    fn from_wgpu(mode: wgpu::CompositeAlphaMode) -> Option<Self> {
+        match mode {
+            wgpu::CompositeAlphaMode::Opaque => Some(SurfaceAlphaMode::Opaque),
+            wgpu::CompositeAlphaMode::PreMultiplied => Some(SurfaceAlphaMode::PreMultiplied),
+            wgpu::CompositeAlphaMode::PostMultiplied => Some(SurfaceAlphaMode::PostMultiplied),
+            wgpu::CompositeAlphaMode::Inherit => Some(SurfaceAlphaMode::Inherit),
+            _ => None,
+        }
+    }
+}
+
+impl From<SurfaceAlphaMode> for wgpu::CompositeAlphaMode {
+    fn from(mode: SurfaceAlphaMode) -> Self {
+        match mode {
+            SurfaceAlphaMode::Opaque => wgpu::CompositeAlphaMode::Opaque,
+            SurfaceAlphaMode::PreMultiplied => wgpu::CompositeAlphaMode::PreMultiplied,
+            SurfaceAlphaMode::PostMultiplied => wgpu::CompositeAlphaMode::PostMultiplied,
+            SurfaceAlphaMode::Inherit => wgpu::CompositeAlphaMode::Inherit,
+        }
+    }
+}
+
+/// The kind of format used by the window surface, see [GPU::set_surface_format].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SurfaceFormatPreference {
+    /// 8 bit sRGB, the default.
+    Sdr,
+    /// 10 bit `Rgb10a2Unorm`, more precision for wide gamut content. Falls back to
+    /// [SurfaceFormatPreference::Sdr].
+    WideGamut,
+    /// 16 bit float `Rgba16Float` in linear space, values above `1.0` are brighter than SDR
+    /// white on HDR monitors. Falls back to [SurfaceFormatPreference::WideGamut].
+    Hdr,
+}
+
+impl SurfaceFormatPreference {
+    /// Formats to try in order, the first supported one is used.
+    fn candidates(&self) -> &'static [wgpu::TextureFormat] {
+        match self {
+            SurfaceFormatPreference::Sdr => &[],
+            SurfaceFormatPreference::WideGamut => &[wgpu::TextureFormat::Rgb10a2Unorm],
+            SurfaceFormatPreference::Hdr => &[
+                wgpu::TextureFormat::Rgba16Float,
+                wgpu::TextureFormat::Rgb10a2Unorm,
+            ],
+        }
+    }
+
+    fn select(&self, formats: &[wgpu::TextureFormat]) -> wgpu::TextureFormat {
+        self.candidates()
+            .iter()
+            .copied()
+            .find(|format| formats.contains(format))
+            .or_else(|| formats.iter().copied().find(|format| format.is_srgb()))
+            .unwrap_or(formats[0])
+    }
+}
+
+/// What the window surface supports on the current adapter, see [GPU::surface_capabilities].
+///
+/// The first entry of each list is the preferred one. Formats without an equivalent
+/// [TextureFormat] are left out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SurfaceCaps {
+    pub formats: Vec<TextureFormat>,
+    pub present_modes: Vec<PresentMode>,
+    pub alpha_modes: Vec<SurfaceAlphaMode>,
+    pub usages: texture::TextureUsage,
+}
+
+impl SurfaceCaps {
+    pub fn supports_format(&self, format: TextureFormat) -> bool {
+        self.formats.contains(&format)
+    }
+
+    pub fn supports_present_mode(&self, mode: PresentMode) -> bool {
+        matches!(mode, PresentMode::AutoVsync | PresentMode::AutoNoVsync)
+            || self.present_modes.contains(&mode)
+    }
+
+    pub fn supports_alpha_mode(&self, mode: SurfaceAlphaMode) -> bool {
+        self.alpha_modes.contains(&mode)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum GPUWaitType {
     Wait,
@@ -126,6 +369,48 @@ pub struct GPUAdapter {
     pub is_high_performance: bool,
 }
 
+impl GPUAdapter {
+    pub(crate) fn from_info(info: wgpu::AdapterInfo) -> Self {
+        let vendor_name = match info.vendor {
+            0x1002 => "AMD",
+            0x10DE => "NVIDIA",
+            0x8086 => "Intel",
+            0x13B5 => "ARM",
+            _ => "Unknown",
+        };
+
+        let backend_string = match info.backend {
+            wgpu::Backend::Vulkan => "Vulkan",
+            wgpu::Backend::Metal => "Metal",
+            wgpu::Backend::Dx12 => "DirectX 12",
+            wgpu::Backend::Gl => "OpenGL",
+            wgpu::Backend::BrowserWebGpu => "WebGPU",
+            _ => "Unknown",
+        };
+
+        let is_high_performance = matches!(info.device_type, wgpu::DeviceType::DiscreteGpu);
+
+        let backend = match info.backend {
+            wgpu::Backend::Vulkan => AdapterBackend::Vulkan,
+            wgpu::Backend::Metal => AdapterBackend::Metal,
+            wgpu::Backend::Dx12 => AdapterBackend::Dx12,
+            wgpu::Backend::Gl => AdapterBackend::Gl,
+            wgpu::Backend::BrowserWebGpu => AdapterBackend::BrowserWebGpu,
+            _ => AdapterBackend::None,
+        };
+
+        GPUAdapter {
+            name: info.name,
+            vendor: vendor_name.to_string(),
+            vendor_id: info.vendor,
+
+            backend: backend_string.to_string(),
+            backend_enum: backend,
+            is_high_performance,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GPU {
     pub(crate) inner: ArcRef<GPUInner>,
@@ -136,8 +421,11 @@ impl GPU {
         window: ArcMut<Handle>,
         adapter: Option<&GPUAdapter>,
         limits: Option<Limits>,
-    ) -> Result<GPU, String> {
-        let inner = ArcRef::new(GPUInner::new(window, adapter, limits).await?);
+        features: FeatureRequest,
+        pipeline_cache_path: Option<PathBuf>,
+    ) -> Result<GPU, GpuBuildError> {
+        let inner = GPUInner::new(window, adapter, limits, features, pipeline_cache_path).await?;
+        let inner = ArcRef::new_ranked(inner, LockRank::Graphics);
 
         Ok(GPU { inner })
     }
@@ -145,59 +433,116 @@ impl GPU {
     pub(crate) async fn new_headless(
         adapter: Option<&GPUAdapter>,
         limits: Option<Limits>,
-    ) -> Result<GPU, String> {
-        let inner = ArcRef::new(GPUInner::new_headless(adapter, limits).await?);
+        features: FeatureRequest,
+        pipeline_cache_path: Option<PathBuf>,
+    ) -> Result<GPU, GpuBuildError> {
+        let inner = GPUInner::new_headless(adapter, limits, features, pipeline_cache_path).await?;
+        let inner = ArcRef::new_ranked(inner, LockRank::Graphics);
 
         Ok(GPU { inner })
     }
 
+    pub(crate) fn new_shared(
+        window: Option<ArcMut<Handle>>,
+        shared: &GPU,
+    ) -> Result<GPU, GpuBuildError> {
+        let inner = GPUInner::new_shared(&shared.inner.borrow());
+        let inner = match window {
+            Some(window) => GPUInner::with_window(inner, window)?,
+            None => inner,
+        };
+
+        Ok(GPU {
+            inner: ArcRef::new_ranked(inner, LockRank::Graphics),
+        })
+    }
+
     pub(crate) fn query_gpu(window: Option<ArcMut<Handle>>) -> Vec<GPUAdapter> {
         let adapter = GPUInner::query_gpu(window);
 
         adapter
             .into_iter()
-            .map(|adapter| {
-                let info = adapter.get_info();
-
-                let vendor_name = match info.vendor {
-                    0x1002 => "AMD",
-                    0x10DE => "NVIDIA",
-                    0x8086 => "Intel",
-                    0x13B5 => "ARM",
-                    _ => "Unknown",
-                };
+            .map(|adapter| GPUAdapter::from_info(adapter.get_info()))
+            .collect()
+    }
 
-                let backend_string = match info.backend {
-                    wgpu::Backend::Vulkan => "Vulkan",
-                    wgpu::Backend::Metal => "Metal",
-                    wgpu::Backend::Dx12 => "DirectX 12",
-                    wgpu::Backend::Gl => "OpenGL",
-                    wgpu::Backend::BrowserWebGpu => "WebGPU",
-                    _ => "Unknown",
-                };
+    /// Returns the adapter this GPU runs on.
+    pub fn adapter(&self) -> GPUAdapter {
+        let inner = self.inner.borrow();
 
-                let is_high_performance = matches!(info.device_type, wgpu::DeviceType::DiscreteGpu);
+        GPUAdapter::from_info(inner.adapter.as_ref().unwrap().get_info())
+    }
 
-                let backend = match info.backend {
-                    wgpu::Backend::Vulkan => AdapterBackend::Vulkan,
-                    wgpu::Backend::Metal => AdapterBackend::Metal,
-                    wgpu::Backend::Dx12 => AdapterBackend::Dx12,
-                    wgpu::Backend::Gl => AdapterBackend::Gl,
-                    wgpu::Backend::BrowserWebGpu => AdapterBackend::BrowserWebGpu,
-                    _ => AdapterBackend::None,
-                };
+    /// Returns whether both instances share the same device, eg. clones of the same [GPU] or
+    /// instances built with [GPUBuilder::set_shared_device].
+    pub fn is_same_device(&self, other: &GPU) -> bool {
+        ArcRef::ptr_eq(&self.inner, &other.inner)
+            || self.inner.borrow().device == other.inner.borrow().device
+    }
 
-                GPUAdapter {
-                    name: info.name,
-                    vendor: vendor_name.to_string(),
-                    vendor_id: info.vendor,
+    /// Creates a headless GPU on another adapter than this one, to offload compute work such as
+    /// bake jobs, eg. to the integrated GPU while the discrete GPU renders.
+    ///
+    /// Adapters of the same backend are preferred, and a discrete GPU picks an integrated one
+    /// first and the other way around. Fails if the system has a single adapter.
+    ///
+    /// Resources can't be shared between devices, use [copy_buffer_between_gpus] to move the
+    /// results back.
+    ///
+    /// Not available on the web, where the browser exposes a single adapter.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn create_offload_gpu(&self) -> Result<GPU, GpuBuildError> {
+        let current = self.adapter();
 
-                    backend: backend_string.to_string(),
-                    backend_enum: backend,
-                    is_high_performance,
-                }
+        let mut adapters = GPU::query_gpu(None)
+            .into_iter()
+            .filter(|adapter| {
+                adapter.name != current.name
+                    || adapter.vendor_id != current.vendor_id
+                    || adapter.backend_enum != current.backend_enum
             })
-            .collect()
+            .collect::<Vec<_>>();
+
+        // Stable sort, keeping the enumeration order between equally ranked adapters.
+        adapters.sort_by_key(|adapter| {
+            (
+                adapter.backend_enum != current.backend_enum,
+                adapter.is_high_performance == current.is_high_performance,
+            )
+        });
+
+        let Some(adapter) = adapters.first() else {
+            return Err(GpuBuildError::NoAdapter);
+        };
+
+        crate::dbg_log!(
+            "Creating offload GPU on {} ({}), rendering on {} ({})",
+            adapter.name,
+            adapter.backend,
+            current.name,
+            current.backend
+        );
+
+        let features = FeatureRequest {
+            optional: self.features(),
+            required: GPUFeatures::empty(),
+        };
+
+        // The cache of another adapter would be discarded on load, keep it in memory only
+        futures::executor::block_on(GPU::new_headless(Some(adapter), None, features, None))
+    }
+
+    /// Returns the features enabled on the device.
+    pub fn features(&self) -> GPUFeatures {
+        let inner = self.inner.borrow();
+        GPUFeatures::from_wgpu(inner.device().features())
+    }
+
+    /// Returns the features the adapter supports, which can be requested with
+    /// [GPUBuilder::request_features].
+    pub fn supported_features(&self) -> GPUFeatures {
+        let inner = self.inner.borrow();
+        GPUFeatures::from_wgpu(inner.adapter.as_ref().unwrap().features())
     }
 
     /// Return the swapchain's format.
@@ -208,7 +553,58 @@ impl GPU {
         format.into()
     }
 
+    /// Returns the formats, present modes, alpha modes and usages supported by the window
+    /// surface, eg. to list the valid choices in a graphics settings menu.
+    ///
+    /// Every list is empty for a GPU created without a window.
+    pub fn surface_capabilities(&self) -> SurfaceCaps {
+        let inner = self.inner.borrow();
+        let (Some(surface), Some(adapter)) = (inner.surface.as_ref(), inner.adapter.as_ref()) else {
+            return SurfaceCaps::default();
+        };
+
+        let capabilities = surface.get_capabilities(adapter);
+
+        SurfaceCaps {
+            formats: capabilities
+                .formats
+                .into_iter()
+                .filter_map(TextureFormat::from_wgpu)
+                .collect(),
+            present_modes: capabilities
+                .present_modes
+                .into_iter()
+                .filter_map(PresentMode::from_wgpu)
+                .collect(),
+            alpha_modes: capabilities
+                .alpha_modes
+                .into_iter()
+                .filter_map(SurfaceAlphaMode::from_wgpu)
+                .collect(),
+            usages: capabilities.usages.into(),
+        }
+    }
+
+    /// Makes [DrawingContext](command::drawing::DrawingContext) coordinates logical instead of
+    /// physical pixels, scaled by the window's scale factor so the UI keeps the same visual size
+    /// on HiDPI displays.
+    ///
+    /// With a scale factor of `2.0`, a 100x100 rectangle covers 200x200 pixels. Scissors and
+    /// viewports are scaled too. Disabled by default.
+    pub fn set_dpi_scaling(&mut self, enabled: bool) {
+        self.inner.borrow_mut().dpi_scaling = enabled;
+    }
+
+    /// Returns whether drawing coordinates are scaled by the window's scale factor, see
+    /// [GPU::set_dpi_scaling].
+    pub fn is_dpi_scaling(&self) -> bool {
+        self.inner.borrow().dpi_scaling
+    }
+
     /// Set the swapchain vsync.
+    ///
+    /// This picks [PresentMode::Fifo] or [PresentMode::AutoNoVsync], use
+    /// [GPU::set_present_mode] for the other modes.
     pub fn set_vsync(&mut self, vsync: bool) {
         let mut inner = self.inner.borrow_mut();
         inner.set_vsync(vsync);
@@ -220,6 +616,78 @@ impl GPU {
         inner.is_vsync()
     }
 
+    /// Sets how frames are queued for presentation.
+    ///
+    /// Returns false and keeps the current mode if the surface doesn't support `mode`, see
+    /// [GPU::supported_present_modes]. [PresentMode::Mailbox] is usually the best choice for
+    /// low latency without tearing, with [PresentMode::AutoNoVsync] as a fallback.
+    pub fn set_present_mode(&mut self, mode: PresentMode) -> bool {
+        if !self.surface_capabilities().supports_present_mode(mode) {
+            return false;
+        }
+
+        let mut inner = self.inner.borrow_mut();
+        inner.set_present_mode(mode.into());
+
+        true
+    }
+
+    /// Returns the present mode of the swapchain.
+    pub fn present_mode(&self) -> PresentMode {
+        let inner = self.inner.borrow();
+        inner.present_mode()
+    }
+
+    /// Returns the present modes supported by the window surface, the automatic modes are
+    /// always supported and not listed.
+    pub fn supported_present_modes(&self) -> Vec<PresentMode> {
+        self.surface_capabilities().present_modes
+    }
+
+    /// Sets how many frames can be queued ahead of the one being presented, lower values reduce
+    /// the input latency at the cost of throughput. The default is 2.
+    ///
+    /// This is a hint, the platform may clamp it.
+    pub fn set_frame_latency(&mut self, frames: u32) {
+        let mut inner = self.inner.borrow_mut();
+        inner.set_frame_latency(frames.max(1));
+    }
+
+    /// Returns the maximum number of frames queued ahead, see [GPU::set_frame_latency].
+    pub fn frame_latency(&self) -> u32 {
+        let inner = self.inner.borrow();
+        inner.config.as_ref().map_or(0, |config| config.desired_maximum_frame_latency)
+    }
+
+    /// Switches the window surface to the best supported format for `preference`, and returns
+    /// the selected format.
+    ///
+    /// With an HDR format, render to a `Rgba16Float` texture and present it with
+    /// [Tonemap](effects::Tonemap) so bright content isn't clipped to SDR white. Non sRGB formats
+    /// expect gamma encoded colors, which the tonemap pass takes care of.
+    pub fn set_surface_format(&mut self, preference: SurfaceFormatPreference) -> TextureFormat {
+        let mut inner = self.inner.borrow_mut();
+        let (Some(surface), Some(adapter)) = (inner.surface.as_ref(), inner.adapter.as_ref()) else {
+            panic!("Graphics not initialized with window");
+        };
+
+        let formats = surface.get_capabilities(adapter).formats;
+        let format = preference.select(&formats);
+
+        inner.set_surface_format(format);
+
+        format.into()
+    }
+
+    /// Returns whether the swapchain uses a float format able to show colors brighter than
+    /// SDR white, see [GPU::set_surface_format].
+    pub fn is_surface_hdr(&self) -> bool {
+        let inner = self.inner.borrow();
+        inner.config.as_ref().is_some_and(|config| {
+            config.format == wgpu::TextureFormat::Rgba16Float
+        })
+    }
+
     /// Check if the swapchain is using sRGB format.
     ///
     /// This is useful for determining if you want to use sRGB textures or not.
@@ -228,11 +696,243 @@ impl GPU {
         inner.is_srgb()
     }
 
-    pub fn set_panic_callback<F>(&mut self, _callback: F)
+    /// Sets a callback invoked with the error message right before the crate panics on a GPU error.
+    ///
+    /// This is only reached when no [GPU::on_validation_error] callback is set, and the error
+    /// happened outside of [GPU::error_scope]. Useful to log or save state before aborting.
+    pub fn set_panic_callback<F>(&mut self, callback: F)
     where
         F: Fn(&str) + Send + Sync + 'static,
     {
-        // self.inner.borrow().set_panic_callback(callback);
+        let inner = self.inner.borrow();
+        inner.error_handler.lock().panic_callback = Some(Arc::new(callback));
+    }
+
+    /// Sets a callback receiving the validation, out of memory and internal errors reported by
+    /// the device, instead of panicking on them.
+    ///
+    /// The callback runs on the thread that made the failing call, it must not call back into
+    /// the [GPU] or its resources.
+    pub fn on_validation_error<F>(&mut self, callback: F)
+    where
+        F: Fn(&GpuError) + Send + Sync + 'static,
+    {
+        let inner = self.inner.borrow();
+        inner.error_handler.lock().error_callback = Some(Arc::new(callback));
+    }
+
+    /// Sets the limits on the resources created during a frame, [None] removes them.
+    ///
+    /// A frame ends when a command buffer is presented, or with [GPU::end_frame] for headless
+    /// GPUs. Exceeded limits are logged as warnings, or passed to the callback set with
+    /// [GPU::on_budget_exceeded].
+    pub fn set_frame_budget(&mut self, budget: Option<FrameBudget>) {
+        let inner = self.inner.borrow();
+        inner.frame_budget.lock().budget = budget;
+    }
+
+    /// Sets a callback receiving the limits of the frame budget that were exceeded, instead of
+    /// logging them.
+    ///
+    /// The callback runs while the resource is being created, it must not call back into the
+    /// [GPU] or its resources.
+    pub fn on_budget_exceeded<F>(&mut self, callback: F)
+    where
+        F: Fn(&BudgetExceeded) + Send + Sync + 'static,
+    {
+        let inner = self.inner.borrow();
+        inner.frame_budget.lock().callback = Some(Arc::new(callback));
+    }
+
+    /// Returns the resources created during the last finished frame.
+    pub fn last_frame_usage(&self) -> FrameUsage {
+        let inner = self.inner.borrow();
+        inner.frame_budget.lock().last_usage
+    }
+
+    /// Returns the live resources, the cached pipelines and bind groups, and the draws recorded
+    /// during the last finished frame.
+    ///
+    /// ```rust,ignore
+    /// let stats = gpu.stats();
+    /// println!(
+    ///     "{} textures ({} KiB), {} draw calls",
+    ///     stats.textures,
+    ///     stats.texture_bytes / 1024,
+    ///     stats.last_frame.draw_calls
+    /// );
+    /// ```
+    pub fn stats(&self) -> GpuStats {
+        let inner = self.inner.borrow();
+        inner.stats()
+    }
+
+    /// Sets the eviction policy of the render and compute pipeline caches, the limits apply to
+    /// each cache separately. By default pipelines unused for 50 frames are evicted.
+    pub fn set_pipeline_cache_policy(&mut self, policy: CachePolicy) {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(pipeline_manager) = inner.pipeline_manager.as_mut() {
+            pipeline_manager.set_policy(policy);
+        }
+    }
+
+    /// Returns the eviction policy of the pipeline caches.
+    pub fn pipeline_cache_policy(&self) -> CachePolicy {
+        let inner = self.inner.borrow();
+        inner
+            .pipeline_manager
+            .as_ref()
+            .map(|manager| manager.policy)
+            .unwrap_or_default()
+    }
+
+    /// Sets the eviction policy of the bind group cache. By default bind groups unused for 100
+    /// frames are evicted.
+    pub fn set_bind_group_cache_policy(&mut self, policy: CachePolicy) {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(bind_group_manager) = inner.bind_group_manager.as_mut() {
+            bind_group_manager.set_policy(policy);
+        }
+    }
+
+    /// Returns the eviction policy of the bind group cache.
+    pub fn bind_group_cache_policy(&self) -> CachePolicy {
+        let inner = self.inner.borrow();
+        inner
+            .bind_group_manager
+            .as_ref()
+            .map(|manager| manager.policy)
+            .unwrap_or_default()
+    }
+
+    /// Drops every cached pipeline and bind group, eg. after a level change. They are created
+    /// again the next time they are used.
+    pub fn trim_caches(&mut self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.trim_caches();
+    }
+
+    /// Ends the current frame of the frame budget and the draw statistics, this is done
+    /// automatically when a command buffer is presented.
+    pub fn end_frame(&mut self) {
+        let inner = self.inner.borrow();
+        inner.frame_budget.lock().end_frame();
+        inner.frame_stats.lock().end_frame();
+        inner.diagnostics.lock().end_frame();
+    }
+
+    /// Sets where diagnostic bundles are written when the device is lost, when validation
+    /// errors keep happening, or right before the crate panics on a GPU error.
+    ///
+    /// `path` is either a directory, in which a new file is created for every bundle, or a
+    /// file overwritten by each bundle. [None] disables the bundles, which is the default.
+    pub fn set_diagnostics_path(&mut self, path: Option<PathBuf>) {
+        let inner = self.inner.borrow();
+        inner.diagnostics.lock().path = path;
+    }
+
+    /// Gathers the adapter, its limits and features, the last pipelines created, the passes of
+    /// the last frame and the tail of the log, eg. to attach to a bug report.
+    ///
+    /// ```rust,ignore
+    /// let report = gpu.generate_diagnostics();
+    /// report.write_to("diagnostics.txt")?;
+    /// ```
+    pub fn generate_diagnostics(&self) -> DiagnosticsReport {
+        let inner = self.inner.borrow();
+        inner.diagnostics.lock().report("Requested by the application")
+    }
+
+    /// Runs `f` inside a device error scope, returning the first error it caused instead of
+    /// reporting it to the callbacks.
+    ///
+    /// Only errors from work recorded within `f` are caught, eg. a render pass must be ended
+    /// and its command buffer submitted inside the scope for its errors to be included.
+    ///
+    /// Blocks until the device reported the errors, on the web use [GPU::error_scope_async].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn error_scope<R>(&mut self, f: impl FnOnce(&mut GPU) -> R) -> Result<R, GpuError> {
+        futures::executor::block_on(self.error_scope_async(f))
+    }
+
+    /// Asynchronous [GPU::error_scope].
+    pub async fn error_scope_async<R>(
+        &mut self,
+        f: impl FnOnce(&mut GPU) -> R,
+    ) -> Result<R, GpuError> {
+        const FILTERS: [wgpu::ErrorFilter; 3] = [
+            wgpu::ErrorFilter::Internal,
+            wgpu::ErrorFilter::OutOfMemory,
+            wgpu::ErrorFilter::Validation,
+        ];
+
+        {
+            let inner = self.inner.borrow();
+            for filter in FILTERS {
+                inner.device().push_error_scope(filter);
+            }
+        }
+
+        let result = f(self);
+
+        // Pop every scope even after an error, so the scope stack stays balanced
+        let scopes = {
+            let inner = self.inner.borrow();
+            FILTERS.map(|_| inner.device().pop_error_scope())
+        };
+
+        let mut error = None;
+        for scope in scopes {
+            let scope_error = scope.await;
+            if error.is_none() {
+                error = scope_error.map(GpuError::from);
+            }
+        }
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(result),
+        }
+    }
+
+    /// Returns true when the device was lost, eg. after a driver reset or update.
+    ///
+    /// Windowed applications don't need to poll this, the device is recovered automatically
+    /// and [Event::DeviceRestored](crate::runner::Event::DeviceRestored) is emitted.
+    pub fn is_device_lost(&self) -> bool {
+        self.inner.borrow().is_device_lost()
+    }
+
+    /// Re-creates a lost device, for headless GPU instances that are not recovered by the runner.
+    ///
+    /// Textures, buffers and shaders created from this GPU are re-created in place, so existing
+    /// handles stay valid, but their contents are lost. Pipelines built before the loss reference
+    /// the old device and must be built again.
+    ///
+    /// Blocks until the new device is ready, on the web use [GPU::recover_device_async].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn recover_device(&mut self) -> Result<(), String> {
+        GPUInner::recover_device(&self.inner)
+    }
+
+    /// Re-creates a lost device asynchronously, see [GPU::recover_device].
+    pub async fn recover_device_async(&mut self) -> Result<(), String> {
+        GPUInner::recover_device_async(&self.inner).await
+    }
+
+    /// Captures the next frame with the attached graphics debugger.
+    ///
+    /// The capture starts at the next [GPU::begin_command] and stops once a command buffer
+    /// presents to the surface, so the exact frame showing a glitch can be inspected. A GPU
+    /// without a surface stops the capture when that command buffer is submitted instead.
+    /// This uses the in-app API of RenderDoc (Vulkan, OpenGL) or Xcode (Metal), and does nothing
+    /// if the application is not launched from one of them.
+    #[cfg(feature = "gpu-capture")]
+    pub fn trigger_capture(&mut self) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.capture_state == CaptureState::Idle {
+            inner.capture_state = CaptureState::Pending;
+        }
     }
 
     /// Begins a new command buffer.
@@ -240,6 +940,25 @@ impl GPU {
         CommandBuffer::new(self.inner.clone())
     }
 
+    /// Begins a command buffer meant to be recorded on another thread, then finished with
+    /// [CommandBuffer::finish] and submitted with [GPU::submit] on the thread owning the surface.
+    ///
+    /// The [GPU] and the resources created from it can be sent to worker threads, secondary
+    /// command buffers have no access to the surface texture and never end the frame.
+    pub fn create_secondary_command(&mut self) -> Result<CommandBuffer, CommandBufferBuildError> {
+        CommandBuffer::new_secondary(self.inner.clone())
+    }
+
+    /// Submits command buffers finished with [CommandBuffer::finish], in order and in a single
+    /// queue submission.
+    pub fn submit<I>(&mut self, commands: I)
+    where
+        I: IntoIterator<Item = FinishedCommand>,
+    {
+        let inner = self.inner.borrow();
+        inner.submit(commands.into_iter().map(|command| command.command));
+    }
+
     /// Begins a new command buffer with a surface texture.
     ///
     /// This is useful if you reuse the surface texture from previous command buffer, but
@@ -254,14 +973,125 @@ impl GPU {
         )
     }
 
-    /// Create a new texture.
-    pub fn create_texture(&mut self) -> TextureBuilder {
-        TextureBuilder::new(self.inner.clone())
+    /// Create a new texture.
+    pub fn create_texture(&mut self) -> TextureBuilder {
+        TextureBuilder::new(self.inner.clone())
+    }
+
+    /// Create a new texture atlas.
+    pub fn create_texture_atlas(&mut self) -> TextureAtlasBuilder {
+        TextureAtlasBuilder::new(self.inner.clone())
+    }
+
+    /// Create a texture playing an animated GIF or PNG, or a sprite sheet.
+    pub fn create_animated_texture(&mut self) -> AnimatedTextureBuilder {
+        AnimatedTextureBuilder::new(self.inner.clone())
+    }
+
+    /// Create a texture updated with a new video or camera frame every frame.
+    pub fn create_streaming_texture(
+        &mut self,
+        size: crate::math::Point2,
+        format: StreamingFormat,
+    ) -> Result<StreamingTexture, StreamingTextureError> {
+        StreamingTexture::new(self.inner.clone(), size, format)
+    }
+
+    /// Loads a texture atlas baked with [bake::pack_atlas](crate::bake::pack_atlas).
+    pub fn load_atlas(&mut self, path: &str) -> Result<TextureAtlas, TextureAtlasBuilderError> {
+        let file = crate::bake::AtlasFile::load(path).map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => TextureAtlasBuilderError::FileNotFound(path.to_string()),
+            _ => TextureAtlasBuilderError::InvalidData(format!("{}: {}", path, err)),
+        })?;
+
+        file.create_texture_atlas(self)
+    }
+
+    /// Create a swapchain-less render target for rendering inside a host owned view.
+    pub fn create_embedded_target(
+        &mut self,
+        size: crate::math::Point2,
+        format: TextureFormat,
+    ) -> Result<EmbeddedTarget, TextureError> {
+        EmbeddedTarget::new(self.inner.clone(), size, format)
+    }
+
+    /// Create an [EmbeddedTarget] whose textures are exported as `handle_type`, so the host
+    /// displays them without reading them back.
+    ///
+    /// Requires the Vulkan backend, returns [TextureError::External] if the driver can't export
+    /// `format` images.
+    #[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+    pub fn create_shared_embedded_target(
+        &mut self,
+        size: crate::math::Point2,
+        format: TextureFormat,
+        handle_type: texture::external::ExternalHandleType,
+    ) -> Result<EmbeddedTarget, TextureError> {
+        EmbeddedTarget::new_shared(self.inner.clone(), size, format, handle_type)
+    }
+
+    /// Create a render target with a fixed internal resolution, scaled to the window when presented.
+    pub fn create_scaled_target(
+        &mut self,
+        size: crate::math::Point2,
+        scaling: PresentScaling,
+    ) -> Result<ScaledTarget, TextureError> {
+        ScaledTarget::new(self.inner.clone(), size, scaling)
+    }
+
+    /// Create a pair of render targets swapped between the steps of a feedback effect.
+    pub fn create_pingpong_target(&mut self) -> PingPongTargetBuilder {
+        PingPongTargetBuilder::new(self.inner.clone())
+    }
+
+    /// Create multisampled render targets managed for the render passes they are attached to.
+    pub fn create_msaa_targets(&mut self, sample_count: texture::SampleCount) -> MsaaTargets {
+        MsaaTargets::new(self.inner.clone(), sample_count)
+    }
+
+    /// Returns the highest MSAA sample count the adapter supports when rendering to `format`.
+    pub fn max_sample_count(&self, format: TextureFormat) -> texture::SampleCount {
+        let inner = self.inner.borrow();
+        let count = texture::msaa::max_sample_count(inner.adapter.as_ref().unwrap(), format.into());
+
+        texture::msaa::sample_count_from_u32(count)
+    }
+
+    /// Returns the alignment, in bytes, required for uniform buffer dynamic offsets.
+    pub fn uniform_offset_alignment(&self) -> u32 {
+        self.inner.borrow().limits().min_uniform_buffer_offset_alignment
+    }
+
+    /// Returns the alignment, in bytes, required for storage buffer dynamic offsets.
+    pub fn storage_offset_alignment(&self) -> u32 {
+        self.inner.borrow().limits().min_storage_buffer_offset_alignment
+    }
+
+    /// Returns true if [RenderPass::multi_draw_indirect](command::renderpass::RenderPass::multi_draw_indirect)
+    /// is supported.
+    pub fn supports_multi_draw_indirect(&self) -> bool {
+        self.inner.borrow().device().features().contains(wgpu::Features::MULTI_DRAW_INDIRECT)
+    }
+
+    /// Returns true if [RenderPass::multi_draw_indirect_count](command::renderpass::RenderPass::multi_draw_indirect_count)
+    /// is supported.
+    pub fn supports_multi_draw_indirect_count(&self) -> bool {
+        self.inner
+            .borrow()
+            .device()
+            .features()
+            .contains(wgpu::Features::MULTI_DRAW_INDIRECT_COUNT)
     }
 
-    /// Create a new texture atlas.
-    pub fn create_texture_atlas(&mut self) -> TextureAtlasBuilder {
-        TextureAtlasBuilder::new(self.inner.clone())
+    /// Returns true if samplers can use [AddressMode::ClampToBorder](texture::AddressMode::ClampToBorder),
+    /// eg. with [TextureSampler::set_border_color](texture::TextureSampler::set_border_color).
+    pub fn supports_clamp_to_border(&self) -> bool {
+        self.inner
+            .borrow()
+            .device()
+            .features()
+            .contains(wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER)
     }
 
     /// Create a new graphics shader.
@@ -274,6 +1104,19 @@ impl GPU {
         ComputeShaderBuilder::new(self.inner.clone())
     }
 
+    /// Create a watcher reloading shaders when their WGSL files change.
+    #[cfg(feature = "hot-reload")]
+    pub fn create_shader_watcher(
+        &mut self,
+    ) -> Result<shader::watcher::ShaderWatcher, shader::watcher::ShaderWatcherError> {
+        shader::watcher::ShaderWatcher::new(self.inner.clone())
+    }
+
+    /// Create a new mesh, from geometry, raw vertices or a glTF file with the `gltf` feature.
+    pub fn create_mesh(&mut self) -> mesh::MeshBuilder<'_> {
+        mesh::MeshBuilder::new(self.inner.clone())
+    }
+
     /// Create a new buffer.
     pub fn create_buffer<T: bytemuck::Pod + bytemuck::Zeroable>(
         &mut self,
@@ -281,6 +1124,37 @@ impl GPU {
         BufferBuilder::new(self.inner.clone())
     }
 
+    /// Create a growable array of `T` backed by a buffer with the given usage, see [GpuVec].
+    pub fn create_gpu_vec<T: bytemuck::Pod>(
+        &mut self,
+        usage: BufferUsage,
+    ) -> Result<GpuVec<T>, BufferError> {
+        GpuVec::new(self.inner.clone(), usage)
+    }
+
+    /// Create a uniform buffer holding `value`, uploaded again whenever it changes, see
+    /// [Uniform].
+    pub fn create_uniform<T: bytemuck::Pod>(&mut self, value: T) -> Result<Uniform<T>, BufferError> {
+        Uniform::new(self.inner.clone(), value)
+    }
+
+    /// Create an arena sub-allocating per-draw uniform data out of one buffer of `capacity`
+    /// bytes, see [UniformArena].
+    pub fn create_uniform_arena(&mut self, capacity: u32) -> Result<UniformArena, UniformArenaError> {
+        UniformArena::new(self.inner.clone(), capacity)
+    }
+
+    /// Create a pool sub-allocating buffers of the given usage out of slabs of `slab_size`
+    /// bytes, see [BufferPool]. [DEFAULT_SLAB_SIZE](buffer::pool::DEFAULT_SLAB_SIZE) suits most
+    /// meshes.
+    pub fn create_buffer_pool(
+        &mut self,
+        usage: BufferUsage,
+        slab_size: u64,
+    ) -> Result<BufferPool, BufferError> {
+        BufferPool::new(self.inner.clone(), usage, slab_size)
+    }
+
     /// Create a render pipeline.
     pub fn create_render_pipeline(&mut self) -> RenderPipelineBuilder {
         RenderPipelineBuilder::new(self.inner.clone())
@@ -291,6 +1165,31 @@ impl GPU {
         ComputePipelineBuilder::new(self.inner.clone())
     }
 
+    /// Create a reusable bind group for the given group of the shader layout.
+    pub fn create_bind_group(&mut self, shader: &GraphicsShader, group: u32) -> BindGroupBuilder {
+        BindGroupBuilder::new(self.inner.clone(), shader, group)
+    }
+
+    /// Create a profiler measuring up to `capacity` scopes per frame.
+    ///
+    /// Returns [ProfilerError::Unsupported] if the adapter lacks timestamp query support.
+    pub fn create_profiler(&mut self, capacity: u32) -> Result<GpuProfiler, ProfilerError> {
+        GpuProfiler::new(self.inner.clone(), capacity)
+    }
+
+    /// Create an overlay showing the FPS, frame times and resource stats, see [DebugOverlay].
+    pub fn create_debug_overlay(&mut self) -> DebugOverlay {
+        DebugOverlay::new(self.inner.clone())
+    }
+
+    /// Create a render graph for the passes of a frame.
+    ///
+    /// Transient textures and buffers of the graph are pooled and reused by the graphs of the
+    /// next frames.
+    pub fn create_render_graph<'a>(&mut self) -> RenderGraph<'a> {
+        RenderGraph::new(self.inner.clone())
+    }
+
     /// Wait for the GPU to finish processing commands.
     pub fn wait(&mut self, wait_type: GPUWaitType) {
         let inner = self.inner.borrow();
@@ -299,10 +1198,136 @@ impl GPU {
             GPUWaitType::Poll => wgpu::PollType::Poll,
         };
 
+        let waited = matches!(poll_type, wgpu::PollType::Wait);
         _ = inner.device().poll(poll_type);
+
+        // Resources dropped while still in use are released once the GPU is done with them
+        let mut deletion_queue = inner.deletion_queue.lock();
+        if waited {
+            deletion_queue.release_all();
+        } else {
+            deletion_queue.collect();
+        }
+    }
+}
+
+/// Optional device features, see [GPUBuilder::request_features] and [GPU::supported_features].
+///
+/// A subset of the wgpu features, the ones which change what the crate or the shaders can do.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct GPUFeatures(u64);
+
+bitflags::bitflags! {
+    impl GPUFeatures: u64 {
+        /// Disabling depth clipping, eg. for shadow maps.
+        const DEPTH_CLIP_CONTROL = 1 << 0;
+        const DEPTH32FLOAT_STENCIL8 = 1 << 1;
+        /// BC (DXT) compressed textures, usually available on desktop.
+        const TEXTURE_COMPRESSION_BC = 1 << 2;
+        /// ETC2 compressed textures, usually available on mobile.
+        const TEXTURE_COMPRESSION_ETC2 = 1 << 3;
+        /// ASTC compressed textures, usually available on mobile.
+        const TEXTURE_COMPRESSION_ASTC = 1 << 4;
+        const TEXTURE_FORMAT_16BIT_NORM = 1 << 5;
+        const TIMESTAMP_QUERY = 1 << 6;
+        const TIMESTAMP_QUERY_INSIDE_ENCODERS = 1 << 7;
+        const INDIRECT_FIRST_INSTANCE = 1 << 8;
+        /// `f16` in shaders.
+        const SHADER_F16 = 1 << 9;
+        /// `f64` in shaders.
+        const SHADER_F64 = 1 << 10;
+        /// `i64` and `u64` in shaders.
+        const SHADER_INT64 = 1 << 11;
+        /// `@builtin(primitive_index)` in fragment shaders.
+        const SHADER_PRIMITIVE_INDEX = 1 << 12;
+        /// Subgroup operations in shaders.
+        const SUBGROUP = 1 << 13;
+        /// Linear filtering of 32 bit float textures.
+        const FLOAT32_FILTERABLE = 1 << 14;
+        const DUAL_SOURCE_BLENDING = 1 << 15;
+        /// Rendering to several layers of an array texture in one pass, eg. for VR.
+        const MULTIVIEW = 1 << 16;
+        /// Rasterizing every pixel touched by a triangle, not only the covered centers.
+        const CONSERVATIVE_RASTERIZATION = 1 << 17;
+        /// Wireframe rendering.
+        const POLYGON_MODE_LINE = 1 << 18;
+        const POLYGON_MODE_POINT = 1 << 19;
+        const PUSH_CONSTANTS = 1 << 20;
+        const MULTI_DRAW_INDIRECT = 1 << 21;
+        const MULTI_DRAW_INDIRECT_COUNT = 1 << 22;
+        /// Arrays of textures bound to a single binding.
+        const TEXTURE_BINDING_ARRAY = 1 << 23;
+        /// Writing to storage buffers and textures from vertex shaders.
+        const VERTEX_WRITABLE_STORAGE = 1 << 24;
+        const CLEAR_TEXTURE = 1 << 25;
+        const RG11B10UFLOAT_RENDERABLE = 1 << 26;
+        const BGRA8UNORM_STORAGE = 1 << 27;
+        const ADDRESS_MODE_CLAMP_TO_BORDER = 1 << 28;
+        /// Format capabilities beyond the WebGPU baseline, eg. more MSAA sample counts.
+        const TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES = 1 << 29;
+        const PIPELINE_CACHE = 1 << 30;
+    }
+}
+
+const FEATURE_MAP: &[(GPUFeatures, wgpu::Features)] = &[
+    (GPUFeatures::DEPTH_CLIP_CONTROL, wgpu::Features::DEPTH_CLIP_CONTROL),
+    (GPUFeatures::DEPTH32FLOAT_STENCIL8, wgpu::Features::DEPTH32FLOAT_STENCIL8),
+    (GPUFeatures::TEXTURE_COMPRESSION_BC, wgpu::Features::TEXTURE_COMPRESSION_BC),
+    (GPUFeatures::TEXTURE_COMPRESSION_ETC2, wgpu::Features::TEXTURE_COMPRESSION_ETC2),
+    (GPUFeatures::TEXTURE_COMPRESSION_ASTC, wgpu::Features::TEXTURE_COMPRESSION_ASTC),
+    (GPUFeatures::TEXTURE_FORMAT_16BIT_NORM, wgpu::Features::TEXTURE_FORMAT_16BIT_NORM),
+    (GPUFeatures::TIMESTAMP_QUERY, wgpu::Features::TIMESTAMP_QUERY),
+    (GPUFeatures::TIMESTAMP_QUERY_INSIDE_ENCODERS, wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+    (GPUFeatures::INDIRECT_FIRST_INSTANCE, wgpu::Features::INDIRECT_FIRST_INSTANCE),
+    (GPUFeatures::SHADER_F16, wgpu::Features::SHADER_F16),
+    (GPUFeatures::SHADER_F64, wgpu::Features::SHADER_F64),
+    (GPUFeatures::SHADER_INT64, wgpu::Features::SHADER_INT64),
+    (GPUFeatures::SHADER_PRIMITIVE_INDEX, wgpu::Features::SHADER_PRIMITIVE_INDEX),
+    (GPUFeatures::SUBGROUP, wgpu::Features::SUBGROUP),
+    (GPUFeatures::FLOAT32_FILTERABLE, wgpu::Features::FLOAT32_FILTERABLE),
+    (GPUFeatures::DUAL_SOURCE_BLENDING, wgpu::Features::DUAL_SOURCE_BLENDING),
+    (GPUFeatures::MULTIVIEW, wgpu::Features::MULTIVIEW),
+    (GPUFeatures::CONSERVATIVE_RASTERIZATION, wgpu::Features::CONSERVATIVE_RASTERIZATION),
+    (GPUFeatures::POLYGON_MODE_LINE, wgpu::Features::POLYGON_MODE_LINE),
+    (GPUFeatures::POLYGON_MODE_POINT, wgpu::Features::POLYGON_MODE_POINT),
+    (GPUFeatures::PUSH_CONSTANTS, wgpu::Features::PUSH_CONSTANTS),
+    (GPUFeatures::MULTI_DRAW_INDIRECT, wgpu::Features::MULTI_DRAW_INDIRECT),
+    (GPUFeatures::MULTI_DRAW_INDIRECT_COUNT, wgpu::Features::MULTI_DRAW_INDIRECT_COUNT),
+    (GPUFeatures::TEXTURE_BINDING_ARRAY, wgpu::Features::TEXTURE_BINDING_ARRAY),
+    (GPUFeatures::VERTEX_WRITABLE_STORAGE, wgpu::Features::VERTEX_WRITABLE_STORAGE),
+    (GPUFeatures::CLEAR_TEXTURE, wgpu::Features::CLEAR_TEXTURE),
+    (GPUFeatures::RG11B10UFLOAT_RENDERABLE, wgpu::Features::RG11B10UFLOAT_RENDERABLE),
+    (GPUFeatures::BGRA8UNORM_STORAGE, wgpu::Features::BGRA8UNORM_STORAGE),
+    (GPUFeatures::ADDRESS_MODE_CLAMP_TO_BORDER, wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER),
+    (GPUFeatures::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES, wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES),
+    (GPUFeatures::PIPELINE_CACHE, wgpu::Features::PIPELINE_CACHE),
+];
+
+impl GPUFeatures {
+    pub(crate) fn from_wgpu(features: wgpu::Features) -> Self {
+        FEATURE_MAP
+            .iter()
+            .filter(|(_, wgpu_feature)| features.contains(*wgpu_feature))
+            .fold(GPUFeatures::empty(), |acc, (feature, _)| acc | *feature)
+    }
+
+    pub(crate) fn to_wgpu(self) -> wgpu::Features {
+        FEATURE_MAP
+            .iter()
+            .filter(|(feature, _)| self.contains(*feature))
+            .fold(wgpu::Features::empty(), |acc, (_, wgpu_feature)| acc | *wgpu_feature)
     }
 }
 
+/// Features requested through [GPUBuilder], on top of the ones the crate enables by itself.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct FeatureRequest {
+    /// Enabled when the adapter supports them, skipped with a warning otherwise.
+    pub optional: GPUFeatures,
+    /// The device creation fails when one is missing.
+    pub required: GPUFeatures,
+}
+
 #[derive(Clone, Debug)]
 pub struct Limits {
     pub max_texture_dimension_1d: u32,
@@ -390,6 +1415,9 @@ pub struct GPUBuilder<'a> {
     window: Option<&'a mut Window>,
     adapter: Option<&'a GPUAdapter>,
     limits: Option<Limits>,
+    features: FeatureRequest,
+    pipeline_cache_path: Option<PathBuf>,
+    shared: Option<&'a GPU>,
 }
 
 impl<'a> GPUBuilder<'a> {
@@ -398,6 +1426,9 @@ impl<'a> GPUBuilder<'a> {
             window: None,
             adapter: None,
             limits: None,
+            features: FeatureRequest::default(),
+            pipeline_cache_path: default_pipeline_cache_path(),
+            shared: None,
         }
     }
 
@@ -424,33 +1455,183 @@ impl<'a> GPUBuilder<'a> {
         self
     }
 
-    pub fn build(self) -> Result<GPU, String> {
+    /// Requests optional device features, enabled when the adapter supports them.
+    ///
+    /// Unsupported features are skipped with a warning, check [GPU::features] for the ones
+    /// which were enabled.
+    pub fn request_features(mut self, features: GPUFeatures) -> Self {
+        self.features.optional |= features;
+        self
+    }
+
+    /// Requires device features, building fails with [GpuBuildError::MissingFeatures] if the
+    /// adapter doesn't support all of them.
+    pub fn require_features(mut self, features: GPUFeatures) -> Self {
+        self.features.required |= features;
+        self
+    }
+
+    /// Sets the file the pipeline cache is loaded from and saved to when the [GPU] is dropped,
+    /// [None] keeps the cache in memory only.
+    ///
+    /// Defaults to `pipeline_cache.wgpu` in a folder named after the executable, in the cache
+    /// directory of the platform (eg. `~/.cache` on Linux or `%LOCALAPPDATA%` on Windows).
+    /// The cache is only used by the Vulkan backend.
+    pub fn set_pipeline_cache_path(mut self, path: Option<PathBuf>) -> Self {
+        self.pipeline_cache_path = path;
+        self
+    }
+
+    /// Uses the device of an existing [GPU] instead of creating a new one.
+    ///
+    /// Textures, buffers, shaders and pipelines created from either instance can be used with
+    /// the other, eg. assets loaded by a headless [GPU] before the window exists don't have to
+    /// be uploaded again. The adapter, limits and features of this builder are ignored.
+    ///
+    /// After a device loss, the instances must be created again to share a device.
+    pub fn set_shared_device(mut self, gpu: &'a GPU) -> Self {
+        self.shared = Some(gpu);
+        self
+    }
+
+    /// Builds the [GPU], blocking the current thread until the device is ready.
+    ///
+    /// On the web use [GPUBuilder::build_async] instead, the browser cannot be blocked on.
+    pub fn build(self) -> Result<GPU, GpuBuildError> {
+        futures::executor::block_on(self.build_async())
+    }
+
+    /// Builds the [GPU] asynchronously.
+    ///
+    /// This is required on the web, where the adapter and device are requested through the
+    /// browser. The future can be driven with `wasm_bindgen_futures::spawn_local`.
+    pub async fn build_async(self) -> Result<GPU, GpuBuildError> {
         let gpu;
 
         if self.window.is_some() {
             let window_ref = self.window.unwrap();
-            let mut window_inner = window_ref.inner.borrow_mut();
+            let window_cloned = {
+                let window_inner = window_ref.inner.borrow();
 
-            #[cfg(feature = "software")]
-            if window_inner.pixelbuffer.is_some() {
-                return Err(
-                    "GPU cannot be created along side PixelBuffer (software rendering)".to_string(),
-                );
-            }
+                #[cfg(feature = "software")]
+                if window_inner.pixelbuffer.is_some() {
+                    return Err(GpuBuildError::PixelBufferAttached);
+                }
 
-            let window_cloned = window_inner.window_pointer.as_ref().unwrap().clone();
+                window_inner.window_pointer.as_ref().unwrap().clone()
+            };
 
-            gpu = futures::executor::block_on(GPU::new(window_cloned, self.adapter, self.limits))?;
+            gpu = match self.shared {
+                Some(shared) => GPU::new_shared(Some(window_cloned), shared)?,
+                None => {
+                    GPU::new(
+                        window_cloned,
+                        self.adapter,
+                        self.limits,
+                        self.features,
+                        self.pipeline_cache_path,
+                    )
+                    .await?
+                }
+            };
 
-            window_inner.graphics = Some(gpu.inner.clone());
+            window_ref.inner.borrow_mut().graphics = Some(gpu.inner.clone());
+        } else if let Some(shared) = self.shared {
+            gpu = GPU::new_shared(None, shared)?;
         } else {
-            gpu = futures::executor::block_on(GPU::new_headless(self.adapter, self.limits))?;
+            gpu = GPU::new_headless(
+                self.adapter,
+                self.limits,
+                self.features,
+                self.pipeline_cache_path,
+            )
+            .await?;
         }
 
         Ok(gpu)
     }
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GpuBuildError {
+    /// No adapter is available, or none is compatible with the window surface.
+    NoAdapter,
+    /// The adapter given to [GPUBuilder::set_adapter] is no longer available.
+    AdapterNotFound,
+    /// The window is closed.
+    WindowClosed,
+    /// The window is already used by another [GPU].
+    WindowPinned,
+    /// The window is already used by a [PixelBuffer](crate::software::PixelBuffer).
+    PixelBufferAttached,
+    SurfaceCreation(String),
+    /// The adapter can't present to the window, eg. a shared device of another adapter.
+    SurfaceUnsupported,
+    DeviceRequest(String),
+    /// The adapter doesn't support the features given to [GPUBuilder::require_features].
+    MissingFeatures(GPUFeatures),
+}
+
+impl std::fmt::Display for GpuBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuBuildError::NoAdapter => write!(f, "No compatible adapter available"),
+            GpuBuildError::AdapterNotFound => write!(f, "Adapter not found"),
+            GpuBuildError::WindowClosed => write!(f, "Window is closed"),
+            GpuBuildError::WindowPinned => {
+                write!(f, "Window is already pinned to existing softbuffer/gpu")
+            }
+            GpuBuildError::PixelBufferAttached => write!(
+                f,
+                "GPU cannot be created along side PixelBuffer (software rendering)"
+            ),
+            GpuBuildError::SurfaceCreation(e) => write!(f, "Failed to create surface: {}", e),
+            GpuBuildError::SurfaceUnsupported => {
+                write!(f, "Adapter can't present to the window surface")
+            }
+            GpuBuildError::DeviceRequest(e) => write!(f, "Failed to request device: {}", e),
+            GpuBuildError::MissingFeatures(features) => {
+                write!(f, "Adapter doesn't support the required features: {:?}", features)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GpuBuildError {}
+
+/// `pipeline_cache.wgpu` in the cache directory of the platform, in a folder named after the
+/// executable.
+#[cfg(not(target_arch = "wasm32"))]
+fn default_pipeline_cache_path() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let app_name = exe.file_stem()?.to_os_string();
+
+    let env_path = |name: &str| std::env::var_os(name).map(PathBuf::from);
+
+    #[cfg(target_os = "windows")]
+    let cache_dir = env_path("LOCALAPPDATA");
+
+    #[cfg(target_os = "macos")]
+    let cache_dir = env_path("HOME").map(|home| home.join("Library/Caches"));
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let cache_dir = env_path("XDG_CACHE_HOME")
+        .or_else(|| env_path("HOME").map(|home| home.join(".cache")));
+
+    // Next to the executable, like before the platform directories were used
+    let cache_dir = match cache_dir {
+        Some(cache_dir) => cache_dir.join(app_name),
+        None => exe.parent()?.join("cache"),
+    };
+
+    Some(cache_dir.join("pipeline_cache.wgpu"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn default_pipeline_cache_path() -> Option<PathBuf> {
+    None
+}
+
 lazy_static::lazy_static! {
     pub(crate) static ref INSTANCE_ID: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
 }
@@ -470,16 +1651,48 @@ pub(crate) struct GPUInner {
     pub adapter: Option<wgpu::Adapter>,
     pub config: Option<wgpu::SurfaceConfiguration>,
     pub pipeline_cache: Option<PipelineCache>,
+    pub pipeline_cache_path: Option<PathBuf>,
 
     pub pipeline_manager: Option<PipelineManager>,
     pub bind_group_manager: Option<BindGroupManager>,
     pub staging_buffer: Option<StagingBuffer>,
+    pub msaa_cache: Option<MsaaTextureCache>,
+    pub graph_pool: GraphResourcePool,
+
+    pub error_handler: ArcMut<ErrorHandler>,
+    pub frame_budget: ArcMut<FrameBudgetTracker>,
+    pub frame_stats: ArcMut<FrameStatsTracker>,
+    pub diagnostics: ArcMut<DiagnosticsRecorder>,
+    pub device_lost: Arc<AtomicBool>,
+    pub resources: ResourceRegistry,
+    pub deletion_queue: ArcMut<DeletionQueue>,
 
     pub drawing_state: Option<ArcRef<DrawingGlobalState>>,
+    pub yuv_state: Option<ArcRef<YuvGlobalState>>,
+    pub dpi_scaling: bool,
+
+    #[cfg(feature = "gpu-capture")]
+    pub capture_state: CaptureState,
+}
+
+/// State of a programmatic graphics debugger capture, see [GPU::trigger_capture].
+#[cfg(feature = "gpu-capture")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CaptureState {
+    Idle,
+    Pending,
+    Capturing,
 }
 
 #[allow(unused)]
 impl GPUInner {
+    // Adapters can only be requested asynchronously in the browser, one at a time
+    #[cfg(target_arch = "wasm32")]
+    pub fn query_gpu(_window: Option<ArcMut<Handle>>) -> Vec<wgpu::Adapter> {
+        Vec::new()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn query_gpu(window: Option<ArcMut<Handle>>) -> Vec<wgpu::Adapter> {
         let instance_descriptor = wgpu::InstanceDescriptor {
             backends: wgpu::Backends::PRIMARY,
@@ -517,19 +1730,70 @@ impl GPUInner {
         window: ArcMut<Handle>,
         adapter: Option<&GPUAdapter>,
         limits: Option<Limits>,
-    ) -> Result<Self, String> {
+        features: FeatureRequest,
+        pipeline_cache_path: Option<PathBuf>,
+    ) -> Result<Self, GpuBuildError> {
+        let instance =
+            Self::new_headless(adapter, limits, features, pipeline_cache_path).await?;
+
+        Self::with_window(instance, window)
+    }
+
+    /// Creates a headless instance on the device of `shared`.
+    ///
+    /// Error reporting and device loss are shared too, but the caches are not, since they are
+    /// cycled per instance.
+    pub fn new_shared(shared: &GPUInner) -> Self {
+        let id = INSTANCE_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        Self {
+            is_invalid: false,
+            instance_id: id,
+
+            instance: shared.instance.clone(),
+            window: None,
+            surface: None,
+            config: None,
+
+            device: shared.device.clone(),
+            queue: shared.queue.clone(),
+            adapter: shared.adapter.clone(),
+            pipeline_cache: shared.pipeline_cache.clone(),
+            pipeline_cache_path: shared.pipeline_cache_path.clone(),
+            pipeline_manager: Some(PipelineManager::new()),
+            bind_group_manager: Some(BindGroupManager::new()),
+            staging_buffer: Some(StagingBuffer::new()),
+            msaa_cache: Some(MsaaTextureCache::new()),
+            graph_pool: GraphResourcePool::default(),
+            error_handler: shared.error_handler.clone(),
+            frame_budget: ArcMut::new(FrameBudgetTracker::default()),
+            frame_stats: ArcMut::new(FrameStatsTracker::default()),
+            diagnostics: shared.diagnostics.clone(),
+            device_lost: shared.device_lost.clone(),
+            resources: ResourceRegistry::default(),
+            deletion_queue: ArcMut::new(DeletionQueue::default()),
+
+            drawing_state: None,
+            yuv_state: None,
+            dpi_scaling: false,
+
+            #[cfg(feature = "gpu-capture")]
+            capture_state: CaptureState::Idle,
+        }
+    }
+
+    /// Binds a headless instance to the window, creating the surface it presents to.
+    pub fn with_window(mut instance: Self, window: ArcMut<Handle>) -> Result<Self, GpuBuildError> {
         let mut window_lock = window.lock();
 
         if window_lock.is_closed() {
-            return Err("Window is closed".to_string());
+            return Err(GpuBuildError::WindowClosed);
         }
 
         if window_lock.is_pinned() {
-            return Err("Window is already pinned to existing softbuffer/gpu".to_string());
+            return Err(GpuBuildError::WindowPinned);
         }
 
-        let mut instance = Self::new_headless(adapter.clone(), limits).await?;
-
         let surface = instance
             .instance
             .as_ref()
@@ -537,17 +1801,32 @@ impl GPUInner {
             .create_surface(Arc::clone(window_lock.get_window()));
 
         if let Err(e) = surface {
-            return Err(format!("Failed to create surface: {:?}", e));
+            return Err(GpuBuildError::SurfaceCreation(e.to_string()));
         }
 
         let surface = surface.unwrap();
+
+        // A shared device may come from an adapter which can't present to this window
+        if !instance.adapter.as_ref().unwrap().is_surface_supported(&surface) {
+            return Err(GpuBuildError::SurfaceUnsupported);
+        }
+
         let surface_capabilities = surface.get_capabilities(instance.adapter.as_ref().unwrap());
-        let surface_format = surface_capabilities
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_capabilities.formats[0]);
+        let surface_format = SurfaceFormatPreference::Sdr.select(&surface_capabilities.formats);
+
+        // A transparent window only shows through with a composite mode that uses alpha
+        let alpha_mode = if window_lock.transparent {
+            [
+                wgpu::CompositeAlphaMode::PreMultiplied,
+                wgpu::CompositeAlphaMode::PostMultiplied,
+                wgpu::CompositeAlphaMode::Inherit,
+            ]
+            .into_iter()
+            .find(|mode| surface_capabilities.alpha_modes.contains(mode))
+            .unwrap_or(surface_capabilities.alpha_modes[0])
+        } else {
+            surface_capabilities.alpha_modes[0]
+        };
 
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -556,7 +1835,7 @@ impl GPUInner {
             height: 0,
             present_mode: surface_capabilities.present_modes[0],
             view_formats: vec![],
-            alpha_mode: surface_capabilities.alpha_modes[0],
+            alpha_mode,
             desired_maximum_frame_latency: 2,
         };
 
@@ -574,7 +1853,9 @@ impl GPUInner {
     pub async fn new_headless(
         adapter: Option<&GPUAdapter>,
         limits: Option<Limits>,
-    ) -> Result<Self, String> {
+        features: FeatureRequest,
+        pipeline_cache_path: Option<PathBuf>,
+    ) -> Result<Self, GpuBuildError> {
         let instance_descriptor = wgpu::InstanceDescriptor {
             backends: wgpu::Backends::PRIMARY,
             ..Default::default()
@@ -592,8 +1873,9 @@ impl GPUInner {
 
                 let adapter = instance.request_adapter(&adapter_descriptor).await;
 
-                if adapter.is_err() {
-                    return Err(format!("Failed to request adapter: {:?}", adapter.err()));
+                if let Err(_e) = &adapter {
+                    crate::dbg_log!("Failed to request adapter: {:?}", _e);
+                    return Err(GpuBuildError::NoAdapter);
                 }
 
                 adapter.unwrap()
@@ -601,7 +1883,16 @@ impl GPUInner {
                 let gpu_adapter = adapter.unwrap();
 
                 // query again
+                #[cfg(not(target_arch = "wasm32"))]
                 let adapters = instance.enumerate_adapters(wgpu::Backends::PRIMARY);
+
+                // The browser only gives one adapter, check it is the requested one
+                #[cfg(target_arch = "wasm32")]
+                let adapters = instance
+                    .request_adapter(&wgpu::RequestAdapterOptions::default())
+                    .await
+                    .into_iter()
+                    .collect::<Vec<_>>();
                 let mut found = false;
 
                 let desired_backend = match gpu_adapter.backend_enum {
@@ -627,7 +1918,7 @@ impl GPUInner {
                 }
 
                 if !found {
-                    return Err("Adapter not found".to_string());
+                    return Err(GpuBuildError::AdapterNotFound);
                 }
 
                 adapter.unwrap()
@@ -697,11 +1988,17 @@ impl GPUInner {
         let mut optional_features = vec![
             wgpu::Features::DEPTH32FLOAT_STENCIL8,
             wgpu::Features::VERTEX_WRITABLE_STORAGE,
+            wgpu::Features::TIMESTAMP_QUERY,
+            wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS,
+            wgpu::Features::INDIRECT_FIRST_INSTANCE,
+            wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER,
         ];
 
         #[cfg(not(target_arch = "wasm32"))]
         {
             optional_features.push(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+            optional_features.push(wgpu::Features::MULTI_DRAW_INDIRECT);
+            optional_features.push(wgpu::Features::MULTI_DRAW_INDIRECT_COUNT);
         }
 
         for feature in optional_features.iter() {
@@ -710,6 +2007,21 @@ impl GPUInner {
             }
         }
 
+        let supported = GPUFeatures::from_wgpu(adapter.features());
+
+        let missing = features.required - supported;
+        if !missing.is_empty() {
+            return Err(GpuBuildError::MissingFeatures(missing));
+        }
+
+        let skipped = features.optional - supported;
+        if !skipped.is_empty() {
+            crate::warn_log!("Requested GPU features not supported by the adapter: {:?}", skipped);
+        }
+
+        let requested = (features.optional | features.required) & supported;
+        device_descriptor.required_features |= requested.to_wgpu();
+
         #[cfg(not(target_arch = "wasm32"))]
         if adapter.get_info().backend == wgpu::Backend::Vulkan {
             device_descriptor.required_features |=
@@ -718,20 +2030,29 @@ impl GPUInner {
 
         let req_dev = adapter.request_device(&device_descriptor).await;
 
-        if req_dev.is_err() {
-            return Err(format!("Failed to request device: {:?}", req_dev.err()));
+        if let Err(e) = &req_dev {
+            return Err(GpuBuildError::DeviceRequest(e.to_string()));
         }
 
         let (device, queue) = req_dev.unwrap();
 
+        let diagnostics = ArcMut::new(DiagnosticsRecorder::default());
+        diagnostics.lock().set_device(&adapter, &device);
+
+        let error_handler = ArcMut::new(ErrorHandler::default());
+        ErrorHandler::install(&device, &error_handler, &diagnostics);
+
+        let device_lost = Arc::new(AtomicBool::new(false));
+        Self::install_device_lost_callback(&device, &device_lost, &diagnostics);
+
         let mut pipeline_cache: Option<PipelineCache> = None;
 
         #[cfg(not(target_arch = "wasm32"))]
         if adapter.get_info().backend == wgpu::Backend::Vulkan {
-            let path = std::env::current_exe().unwrap();
-            let path = path.parent().unwrap();
-
-            let data = std::fs::read(path.join("cache/pipeline_cache.wgpu")).unwrap_or_default();
+            let data = pipeline_cache_path
+                .as_ref()
+                .and_then(|path| std::fs::read(path).ok())
+                .unwrap_or_default();
 
             let pipeline_cache_desc = wgpu::PipelineCacheDescriptor {
                 label: Some("Pipeline_cache"),
@@ -765,11 +2086,26 @@ impl GPUInner {
             queue: Some(queue),
             adapter: Some(adapter),
             pipeline_cache,
+            pipeline_cache_path,
             pipeline_manager: Some(pipeline_manager),
             bind_group_manager: Some(bind_group_manager),
             staging_buffer: Some(staging_buffer),
-            
+            msaa_cache: Some(MsaaTextureCache::new()),
+            graph_pool: GraphResourcePool::default(),
+            error_handler,
+            frame_budget: ArcMut::new(FrameBudgetTracker::default()),
+            frame_stats: ArcMut::new(FrameStatsTracker::default()),
+            diagnostics,
+            device_lost,
+            resources: ResourceRegistry::default(),
+            deletion_queue: ArcMut::new(DeletionQueue::default()),
+
             drawing_state: None,
+            yuv_state: None,
+            dpi_scaling: false,
+
+            #[cfg(feature = "gpu-capture")]
+            capture_state: CaptureState::Idle,
         })
     }
 
@@ -789,6 +2125,20 @@ impl GPUInner {
         if let Some(ref mut staging_buffer) = self.staging_buffer {
             staging_buffer.cycle();
         }
+
+        if let Some(ref mut msaa_cache) = self.msaa_cache {
+            msaa_cache.cycle();
+        }
+
+        self.graph_pool.cycle();
+
+        let mut deletion_queue = self.deletion_queue.lock();
+        self.resources.prune(&mut deletion_queue);
+
+        if deletion_queue.len() > 0 {
+            _ = self.device().poll(wgpu::PollType::Poll);
+            deletion_queue.collect();
+        }
     }
 
     pub fn is_srgb(&self) -> bool {
@@ -812,7 +2162,19 @@ impl GPUInner {
             panic!("GPU config not initialized");
         }
 
-        self.config.as_ref().unwrap().present_mode == wgpu::PresentMode::Fifo
+        self.present_mode().is_vsync()
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.is_invalid {
+            panic!("Invalid GPU context");
+        }
+
+        if self.config.is_none() {
+            panic!("GPU config not initialized");
+        }
+
+        PresentMode::from_wgpu(self.config.as_ref().unwrap().present_mode).unwrap()
     }
 
     pub fn get_swapchain(&self) -> Result<wgpu::SurfaceTexture, SwapchainError> {
@@ -857,6 +2219,21 @@ impl GPUInner {
         self.queue.as_ref().unwrap()
     }
 
+    /// Capabilities of `format` on this device, the adapter specific ones are only used when
+    /// the device was created with them enabled.
+    pub fn format_features(&self, format: wgpu::TextureFormat) -> wgpu::TextureFormatFeatures {
+        let features = self.device().features();
+
+        match &self.adapter {
+            Some(adapter)
+                if features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) =>
+            {
+                adapter.get_texture_format_features(format)
+            }
+            _ => format.guaranteed_format_features(features),
+        }
+    }
+
     pub fn surface(&self) -> &Surface<'static> {
         if self.is_invalid {
             panic!("Invalid GPU context");
@@ -917,7 +2294,38 @@ impl GPUInner {
             .configure(self.device.as_ref().unwrap(), config);
     }
 
+    /// Configures the surface again even if its size didn't change, eg. after switching the
+    /// display mode of the monitor it's presented on.
+    pub fn reconfigure(&mut self, size: PhysicalSize<u32>) {
+        if self.is_invalid || self.surface.is_none() {
+            return;
+        }
+
+        let config = self.config.as_mut().unwrap();
+        config.width = 0;
+        config.height = 0;
+
+        self.resize(size);
+    }
+
+    /// The scale factor of the window, `1.0` for a windowless GPU.
+    pub fn scale_factor(&self) -> f64 {
+        self.window
+            .as_ref()
+            .and_then(|window| window.lock().window.as_ref().map(|w| w.scale_factor()))
+            .unwrap_or(1.0)
+    }
+
     pub fn set_vsync(&mut self, vsync: bool) {
+        // Immediate isn't available everywhere, eg. on Wayland and the web
+        self.set_present_mode(if vsync {
+            wgpu::PresentMode::Fifo
+        } else {
+            wgpu::PresentMode::AutoNoVsync
+        });
+    }
+
+    pub fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) {
         if self.is_invalid {
             return;
         }
@@ -926,13 +2334,35 @@ impl GPUInner {
             panic!("Graphics not initialized with window");
         }
 
-        let config = self.config.as_mut().unwrap();
-        config.present_mode = if vsync {
-            wgpu::PresentMode::Fifo
-        } else {
-            wgpu::PresentMode::Immediate
-        };
+        self.config.as_mut().unwrap().present_mode = present_mode;
+        self.reconfigure_surface();
+    }
+
+    pub fn set_surface_format(&mut self, format: wgpu::TextureFormat) {
+        if self.is_invalid {
+            return;
+        }
+
+        self.config.as_mut().unwrap().format = format;
+        self.reconfigure_surface();
+    }
+
+    pub fn set_frame_latency(&mut self, frames: u32) {
+        if self.is_invalid {
+            return;
+        }
+
+        if self.window.is_none() || self.surface.is_none() {
+            panic!("Graphics not initialized with window");
+        }
+
+        self.config.as_mut().unwrap().desired_maximum_frame_latency = frames;
+        self.reconfigure_surface();
+    }
 
+    /// Applies the surface configuration, unless the window is minimized.
+    fn reconfigure_surface(&mut self) {
+        let config = self.config.as_ref().unwrap();
         if config.width == 0 || config.height == 0 {
             return;
         }
@@ -948,6 +2378,16 @@ impl GPUInner {
         size: wgpu::BufferAddress,
         usage: wgpu::BufferUsages,
         mapped_at_creation: bool,
+    ) -> wgpu::Buffer {
+        self.create_buffer_labeled(size, usage, mapped_at_creation, None)
+    }
+
+    pub fn create_buffer_labeled(
+        &mut self,
+        size: wgpu::BufferAddress,
+        usage: wgpu::BufferUsages,
+        mapped_at_creation: bool,
+        label: Option<&str>,
     ) -> wgpu::Buffer {
         if self.is_invalid {
             panic!("Invalid GPU context");
@@ -957,7 +2397,7 @@ impl GPUInner {
             panic!("Buffer size must be greater than 0");
         }
 
-        let buffer = self.internal_make_buffer(size, usage, mapped_at_creation);
+        let buffer = self.internal_make_buffer(size, usage, mapped_at_creation, label);
 
         buffer
     }
@@ -966,6 +2406,15 @@ impl GPUInner {
         &mut self,
         data: &[T],
         usage: wgpu::BufferUsages,
+    ) -> wgpu::Buffer {
+        self.create_buffer_with_labeled(data, usage, None)
+    }
+
+    pub fn create_buffer_with_labeled<T: bytemuck::Pod + bytemuck::Zeroable>(
+        &mut self,
+        data: &[T],
+        usage: wgpu::BufferUsages,
+        label: Option<&str>,
     ) -> wgpu::Buffer {
         if self.is_invalid {
             panic!("Invalid GPU context");
@@ -979,6 +2428,7 @@ impl GPUInner {
             (data.len() * std::mem::size_of::<T>()) as wgpu::BufferAddress,
             usage,
             true,
+            label,
         );
 
         let mut mapped_range = buffer.slice(..).get_mapped_range_mut();
@@ -997,6 +2447,7 @@ impl GPUInner {
         size: wgpu::BufferAddress,
         usage: wgpu::BufferUsages,
         mapped_at_creation: bool,
+        label: Option<&str>,
     ) -> wgpu::Buffer {
         if size == 0 {
             panic!("Buffer size must be greater than 0");
@@ -1008,18 +2459,64 @@ impl GPUInner {
         let unaligned_size = wgpu::COPY_BUFFER_ALIGNMENT - 1;
         let size = ((size + unaligned_size) & !unaligned_size).max(wgpu::COPY_BUFFER_ALIGNMENT);
 
+        let default_label = format!("Internal Buffer, usage: {}, size: {}", usage.bits(), size);
         let buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some(
-                format!("Internal Buffer, usage: {}, size: {}", usage.bits(), size).as_str(),
-            ),
+            label: Some(label.unwrap_or(default_label.as_str())),
             size,
             usage,
             mapped_at_creation,
         });
 
+        let exceeded = self.frame_budget.lock().add_buffer_bytes(size);
+        FrameBudgetTracker::report(exceeded);
+
         buffer
     }
 
+    pub fn stats(&self) -> GpuStats {
+        let mut stats = GpuStats {
+            last_frame: self.frame_stats.lock().last,
+            pending_deletions: self.deletion_queue.lock().len() as u32,
+            ..Default::default()
+        };
+
+        self.resources.collect_stats(&mut stats);
+
+        if let Some(pipeline_manager) = self.pipeline_manager.as_ref() {
+            stats.graphics_pipelines = pipeline_manager.graphics_pipelines.len() as u32;
+            stats.compute_pipelines = pipeline_manager.compute_pipelines.len() as u32;
+            stats.pipeline_cache = pipeline_manager.stats;
+        }
+
+        if let Some(bind_group_manager) = self.bind_group_manager.as_ref() {
+            stats.bind_groups = bind_group_manager.bind_groups.len() as u32;
+            stats.bind_group_cache = bind_group_manager.stats;
+        }
+
+        stats
+    }
+
+    pub fn trim_caches(&mut self) {
+        if let Some(pipeline_manager) = self.pipeline_manager.as_mut() {
+            pipeline_manager.clear();
+        }
+
+        if let Some(bind_group_manager) = self.bind_group_manager.as_mut() {
+            bind_group_manager.clear();
+        }
+    }
+
+    /// Submits command buffers to the queue and tracks the submission for the deletion queue.
+    pub fn submit<I>(&self, commands: I)
+    where
+        I: IntoIterator<Item = wgpu::CommandBuffer>,
+    {
+        let queue = self.queue();
+        queue.submit(commands);
+
+        self.deletion_queue.lock().track_submission(queue);
+    }
+
     pub fn get_graphics_pipeline(&mut self, key: u64) -> Option<wgpu::RenderPipeline> {
         if self.is_invalid {
             panic!("Invalid GPU context");
@@ -1039,6 +2536,11 @@ impl GPUInner {
             panic!("Invalid GPU context");
         }
 
+        let exceeded = self.frame_budget.lock().add_pipeline();
+        FrameBudgetTracker::report(exceeded);
+
+        self.diagnostics.lock().record_graphics_pipeline(&desc);
+
         let device_ref = self.device.as_ref().unwrap();
         let pipeline_manager_ref = self.pipeline_manager.as_mut().unwrap();
 
@@ -1069,6 +2571,11 @@ impl GPUInner {
             panic!("Invalid GPU context");
         }
 
+        let exceeded = self.frame_budget.lock().add_pipeline();
+        FrameBudgetTracker::report(exceeded);
+
+        self.diagnostics.lock().record_compute_pipeline(&desc);
+
         let device_ref = self.device.as_ref().unwrap();
         let pipeline_manager_ref = self.pipeline_manager.as_mut().unwrap();
 
@@ -1089,6 +2596,12 @@ impl GPUInner {
             panic!("Invalid GPU context");
         }
 
+        let exceeded = self
+            .frame_budget
+            .lock()
+            .add_bind_groups(attachment.entries.len() as u32);
+        FrameBudgetTracker::report(exceeded);
+
         let device_ref = self.device.as_ref().unwrap();
         let bind_group_manager_ref = self.bind_group_manager.as_mut().unwrap();
 
@@ -1105,6 +2618,23 @@ impl GPUInner {
         bind_group_manager_ref.get(key as usize)
     }
 
+    pub fn get_msaa_texture(
+        &mut self,
+        index: usize,
+        size: crate::math::Point2,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> wgpu::TextureView {
+        if self.is_invalid {
+            panic!("Invalid GPU context");
+        }
+
+        let device = self.device.as_ref().unwrap();
+        let msaa_cache = self.msaa_cache.as_mut().unwrap();
+
+        msaa_cache.get_or_create(device, index, size, format, sample_count)
+    }
+
     pub fn create_staging_buffer(
         &mut self,
         data: &[u8],
@@ -1125,18 +2655,20 @@ impl GPUInner {
 impl Drop for GPUInner {
     fn drop(&mut self) {
         #[cfg(not(target_arch = "wasm32"))]
-        if let Some(pipeline_cache) = &self.pipeline_cache {
-            let data = pipeline_cache.get_data();
-            if let Some(data) = data {
-                let path = std::env::current_exe().unwrap();
-                let path = path.parent().unwrap();
-
-                std::fs::create_dir_all(path.join("cache")).unwrap();
-                let pipeline_cache_path = path.join("cache/pipeline_cache.wgpu");
+        if let (Some(pipeline_cache), Some(path)) = (&self.pipeline_cache, &self.pipeline_cache_path)
+            && let Some(data) = pipeline_cache.get_data()
+        {
+            crate::dbg_log!("Saving pipeline cache to {:?}", path);
 
-                std::fs::write(&pipeline_cache_path, data).unwrap();
+            let result = match path.parent() {
+                Some(parent) => std::fs::create_dir_all(parent),
+                None => Ok(()),
+            }
+            .and_then(|_| std::fs::write(path, data));
 
-                crate::dbg_log!("Saving pipeline cache to {:?}", pipeline_cache_path);
+            // Eg. a read-only install directory, the cache is only an optimization
+            if let Err(err) = result {
+                crate::warn_log!("Failed to save pipeline cache to {:?}: {}", path, err);
             }
         }
 
@@ -1155,3 +2687,16 @@ impl PartialEq for GPUInner {
             && self.bind_group_manager == other.bind_group_manager
     }
 }
+
+// The GPU and its resources are shared with worker threads, see GPU::create_secondary_command.
+#[cfg(not(target_arch = "wasm32"))]
+const _: () = {
+    const fn is_send_sync<T: Send + Sync>() {}
+
+    is_send_sync::<GPU>();
+    is_send_sync::<texture::Texture>();
+    is_send_sync::<buffer::Buffer>();
+    is_send_sync::<shader::graphics::GraphicsShader>();
+    is_send_sync::<shader::compute::ComputeShader>();
+    is_send_sync::<CommandBuffer>();
+};
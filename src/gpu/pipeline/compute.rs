@@ -3,9 +3,10 @@ use std::{
     hash::{DefaultHasher, Hash, Hasher},
 };
 
-use crate::utils::ArcRef;
+use crate::utils::{ArcRef, LockRank};
 
 use super::{
+    AttachmentSource,
     manager::ComputePipelineDesc,
     super::{
         GPUInner,
@@ -19,23 +20,40 @@ use super::{
         shader::{
             bind_group_manager::BindGroupCreateInfo,
             types::{ShaderReflect, ShaderBindingType},
-            compute::ComputeShader,
+            compute::{ComputeShader, ComputeShaderInner},
         },
     },
 };
 
+/// A compute pipeline, rebuilt in place when the device is re-created after a loss.
 #[derive(Debug, Clone, Hash)]
 pub struct ComputePipeline {
+    pub(crate) inner: ArcRef<ComputePipelineInner>,
+}
+
+#[derive(Debug, Clone, Hash)]
+pub(crate) struct ComputePipelineInner {
     pub(crate) bind_group: Vec<(u32, wgpu::BindGroup)>,
     pub(crate) pipeline_desc: ComputePipelineDesc,
 }
 
+/// What a compute pipeline was built from, kept to build it again after a device loss.
+#[derive(Debug, Clone)]
+pub(crate) struct ComputePipelineSource {
+    shader: Option<ArcRef<ComputeShaderInner>>,
+    attachments: Vec<(u32, u32, AttachmentSource)>,
+    label: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ComputePipelineBuilder {
     pub(crate) gpu: ArcRef<GPUInner>,
     pub(crate) attachments: Vec<BindGroupAttachment>,
+    pub(crate) sources: Vec<(u32, u32, AttachmentSource)>,
     pub(crate) shader: Option<IntermediateComputeBinding>,
+    pub(crate) shader_source: Option<ArcRef<ComputeShaderInner>>,
     pub(crate) shader_reflection: Option<ShaderReflect>,
+    pub(crate) label: Option<String>,
 }
 
 impl ComputePipelineBuilder {
@@ -43,36 +61,41 @@ impl ComputePipelineBuilder {
         Self {
             gpu,
             attachments: Vec::new(),
+            sources: Vec::new(),
             shader: None,
+            shader_source: None,
             shader_reflection: None,
+            label: None,
+        }
+    }
+
+    /// A builder binding the resources of `source` as they are on the current device.
+    pub(crate) fn from_source(gpu: ArcRef<GPUInner>, source: &ComputePipelineSource) -> Self {
+        let mut builder = Self::new(gpu);
+        builder.label = source.label.clone();
+
+        if let Some(shader) = &source.shader {
+            builder.apply_shader(shader);
+            builder.shader_source = Some(ArcRef::clone(shader));
         }
+
+        for (group, binding, attachment) in &source.attachments {
+            builder.attach(*group, *binding, attachment.clone());
+        }
+
+        builder
     }
 
     #[inline]
     pub fn set_shader(mut self, shader: Option<&ComputeShader>) -> Self {
         match shader {
             Some(shader) => {
-                let shader_inner = shader.inner.borrow();
-                let shader_module = shader_inner.shader.clone();
-                let layout = shader_inner.bind_group_layouts.clone();
-
-                let shader_reflect = shader_inner.reflection.clone();
-                let entry_point = match &shader_reflect {
-                    ShaderReflect::Compute { entry_point, .. } => entry_point.clone(),
-                    _ => panic!("Shader must be a compute shader"),
-                };
-
-                let shader_binding = IntermediateComputeBinding {
-                    shader: shader_module,
-                    entry_point,
-                    layout,
-                };
-
-                self.shader = Some(shader_binding);
-                self.shader_reflection = Some(shader_reflect);
+                self.apply_shader(&shader.inner);
+                self.shader_source = Some(ArcRef::clone(&shader.inner));
             }
             None => {
                 self.shader = None;
+                self.shader_source = None;
                 self.shader_reflection = None;
             }
         }
@@ -80,6 +103,28 @@ impl ComputePipelineBuilder {
         self
     }
 
+    fn apply_shader(&mut self, shader: &ArcRef<ComputeShaderInner>) {
+        let shader_inner = shader.borrow();
+        let shader_module = shader_inner.shader.clone();
+        let layout = shader_inner.bind_group_layouts.clone();
+
+        let shader_reflect = shader_inner.reflection.clone();
+        let entry_point = match &shader_reflect {
+            ShaderReflect::Compute { entry_point, .. } => entry_point.clone(),
+            _ => panic!("Shader must be a compute shader"),
+        };
+
+        let shader_binding = IntermediateComputeBinding {
+            shader: shader_module,
+            entry_point,
+            layout,
+            push_constant_ranges: shader_inner.push_constant_ranges.clone(),
+        };
+
+        self.shader = Some(shader_binding);
+        self.shader_reflection = Some(shader_reflect);
+    }
+
     #[inline]
     pub fn set_attachment_sampler(
         mut self,
@@ -89,19 +134,7 @@ impl ComputePipelineBuilder {
     ) -> Self {
         match sampler {
             Some(sampler) => {
-                let attachment = {
-                    let gpu_inner = self.gpu.borrow();
-
-                    BindGroupAttachment {
-                        group,
-                        binding,
-                        attachment: BindGroupType::Sampler(
-                            sampler.make_wgpu(gpu_inner.device()),
-                        ),
-                    }
-                };
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                self.attach(group, binding, AttachmentSource::Sampler(*sampler));
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -120,17 +153,8 @@ impl ComputePipelineBuilder {
     ) -> Self {
         match texture {
             Some(texture) => {
-                let attachment = {
-                    BindGroupAttachment {
-                        group,
-                        binding,
-                        attachment: BindGroupType::Texture(
-                            texture.inner.borrow().wgpu_view.clone(),
-                        ),
-                    }
-                };
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::Texture(ArcRef::clone(&texture.inner));
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -149,14 +173,11 @@ impl ComputePipelineBuilder {
     ) -> Self {
         match texture {
             Some(texture) => {
-                let inner = texture.inner.borrow();
-                let attachment = BindGroupAttachment {
-                    group,
-                    binding,
-                    attachment: BindGroupType::TextureStorage(inner.wgpu_view.clone()),
-                };
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+                texture.inner.borrow().validate_storage_binding();
+
+                let source = AttachmentSource::TextureStorage(ArcRef::clone(&texture.inner));
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -175,14 +196,8 @@ impl ComputePipelineBuilder {
     ) -> Self {
         match buffer {
             Some(buffer) => {
-                let inner = buffer.inner.borrow();
-                let attachment = BindGroupAttachment {
-                    group,
-                    binding,
-                    attachment: BindGroupType::Uniform(inner.buffer.clone()),
-                };
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::Uniform(ArcRef::clone(&buffer.inner));
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -204,18 +219,8 @@ impl ComputePipelineBuilder {
     {
         match buffer {
             Some(buffer) => {
-                let attachment = {
-                    let mut inner = self.gpu.borrow_mut();
-
-                    let buffer = inner.create_buffer_with(&buffer, wgpu::BufferUsages::COPY_DST);
-                    BindGroupAttachment {
-                        group,
-                        binding,
-                        attachment: BindGroupType::Uniform(buffer),
-                    }
-                };
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::UniformData(bytemuck::cast_slice(&buffer).to_vec());
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -237,18 +242,8 @@ impl ComputePipelineBuilder {
     {
         match buffer {
             Some(buffer) => {
-                let mut inner = self.gpu.borrow_mut();
-
-                let buffer = inner.create_buffer_with(&buffer, wgpu::BufferUsages::COPY_DST);
-                let attachment = BindGroupAttachment {
-                    group,
-                    binding,
-                    attachment: BindGroupType::Uniform(buffer),
-                };
-
-                drop(inner);
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::UniformData(bytemuck::cast_slice(buffer).to_vec());
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -267,14 +262,8 @@ impl ComputePipelineBuilder {
     ) -> Self {
         match buffer {
             Some(buffer) => {
-                let inner = buffer.inner.borrow();
-                let attachment = BindGroupAttachment {
-                    group,
-                    binding,
-                    attachment: BindGroupType::Storage(inner.buffer.clone()),
-                };
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::Storage(ArcRef::clone(&buffer.inner));
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -296,18 +285,8 @@ impl ComputePipelineBuilder {
     {
         match buffer {
             Some(buffer) => {
-                let mut inner = self.gpu.borrow_mut();
-
-                let buffer = inner.create_buffer_with(&buffer, wgpu::BufferUsages::COPY_DST);
-                let attachment = BindGroupAttachment {
-                    group,
-                    binding,
-                    attachment: BindGroupType::Storage(buffer),
-                };
-
-                drop(inner);
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::StorageData(bytemuck::cast_slice(buffer).to_vec());
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -329,18 +308,8 @@ impl ComputePipelineBuilder {
     {
         match buffer {
             Some(buffer) => {
-                let mut inner = self.gpu.borrow_mut();
-
-                let buffer = inner.create_buffer_with(&buffer, wgpu::BufferUsages::COPY_DST);
-                let attachment = BindGroupAttachment {
-                    group,
-                    binding,
-                    attachment: BindGroupType::Storage(buffer),
-                };
-
-                drop(inner);
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::StorageData(bytemuck::cast_slice(&buffer).to_vec());
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -350,10 +319,25 @@ impl ComputePipelineBuilder {
         self
     }
 
+    /// Binds the resource of `source`, which is kept to bind it again when the pipeline is
+    /// rebuilt after a device loss.
+    pub(crate) fn attach(&mut self, group: u32, binding: u32, source: AttachmentSource) {
+        let attachment = BindGroupAttachment {
+            group,
+            binding,
+            attachment: source.resolve(&self.gpu),
+        };
+
+        self.insert_or_replace_attachment(group, binding, attachment);
+        self.sources.retain(|(g, b, _)| *g != group || *b != binding);
+        self.sources.push((group, binding, source));
+    }
+
     #[inline]
     pub(crate) fn remove_attachment(&mut self, group: u32, binding: u32) {
         self.attachments
             .retain(|a| a.group != group || a.binding != binding);
+        self.sources.retain(|(g, b, _)| *g != group || *b != binding);
     }
 
     pub(crate) fn insert_or_replace_attachment(
@@ -374,7 +358,32 @@ impl ComputePipelineBuilder {
         }
     }
 
+    /// Sets the debug label of the pipeline, shown in graphics debuggers.
+    pub fn set_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
     pub fn build(self) -> Result<ComputePipeline, CompuitePipelineError> {
+        let gpu = ArcRef::clone(&self.gpu);
+        let source = ComputePipelineSource {
+            shader: self.shader_source.clone(),
+            attachments: self.sources.clone(),
+            label: self.label.clone(),
+        };
+
+        let pipeline = ComputePipeline {
+            inner: ArcRef::new_ranked(self.build_inner()?, LockRank::Resource),
+        };
+
+        gpu.borrow_mut()
+            .resources
+            .register_compute_pipeline(&pipeline.inner, source);
+
+        Ok(pipeline)
+    }
+
+    pub(crate) fn build_inner(self) -> Result<ComputePipelineInner, CompuitePipelineError> {
         if self.shader.is_none() {
             return Err(CompuitePipelineError::ShaderNotSet);
         }
@@ -408,13 +417,13 @@ impl ComputePipelineBuilder {
                 ShaderBindingType::StorageBuffer(_, _) => {
                     matches!(attachment.attachment, BindGroupType::Storage(_))
                 }
-                ShaderBindingType::StorageTexture(_) => {
-                    matches!(attachment.attachment, BindGroupType::TextureStorage(_))
+                ShaderBindingType::StorageTexture(_, format) => {
+                    matches!(attachment.attachment, BindGroupType::TextureStorage(_, f) if f == format)
                 }
                 ShaderBindingType::Sampler(_) => {
                     matches!(attachment.attachment, BindGroupType::Sampler(_))
                 }
-                ShaderBindingType::Texture(_) => {
+                ShaderBindingType::Texture(_) | ShaderBindingType::DepthTexture(_) => {
                     matches!(attachment.attachment, BindGroupType::Texture(_))
                 }
                 ShaderBindingType::PushConstant(_) => {
@@ -443,7 +452,7 @@ impl ComputePipelineBuilder {
                     BindGroupType::Texture(texture) => {
                         texture.hash(&mut hasher);
                     }
-                    BindGroupType::TextureStorage(texture) => texture.hash(&mut hasher),
+                    BindGroupType::TextureStorage(texture, _) => texture.hash(&mut hasher),
                     BindGroupType::Sampler(sampler) => sampler.hash(&mut hasher),
                     BindGroupType::Storage(storage) => storage.hash(&mut hasher),
                 }
@@ -464,11 +473,7 @@ impl ComputePipelineBuilder {
                             let entry = match attachment {
                                 BindGroupType::Uniform(buffer) => wgpu::BindGroupEntry {
                                     binding,
-                                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                                        buffer,
-                                        offset: 0,
-                                        size: None,
-                                    }),
+                                    resource: wgpu::BindingResource::Buffer(buffer.binding(None)),
                                 },
                                 BindGroupType::Texture(texture) => wgpu::BindGroupEntry {
                                     binding,
@@ -480,13 +485,9 @@ impl ComputePipelineBuilder {
                                 },
                                 BindGroupType::Storage(buffer) => wgpu::BindGroupEntry {
                                     binding,
-                                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                                        buffer,
-                                        offset: 0,
-                                        size: None,
-                                    }),
+                                    resource: wgpu::BindingResource::Buffer(buffer.binding(None)),
                                 },
-                                BindGroupType::TextureStorage(texture) => wgpu::BindGroupEntry {
+                                BindGroupType::TextureStorage(texture, _) => wgpu::BindGroupEntry {
                                     binding,
                                     resource: wgpu::BindingResource::TextureView(texture),
                                 },
@@ -535,14 +536,14 @@ impl ComputePipelineBuilder {
             shader_module: shader_binding.shader,
             entry_point: shader_binding.entry_point,
             bind_group_layout: layout,
+            push_constant_ranges: shader_binding.push_constant_ranges,
+            label: self.label,
         };
 
-        let pipeline = ComputePipeline {
+        Ok(ComputePipelineInner {
             bind_group: bind_group_attachments,
             pipeline_desc,
-        };
-
-        Ok(pipeline)
+        })
     }
 }
 
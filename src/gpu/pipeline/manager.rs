@@ -2,15 +2,21 @@ use std::{collections::HashMap, hash::Hash};
 
 use crate::dbg_log;
 
+use super::super::cache::{self, CacheEntries, CachePolicy, CacheStats};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct PipelineManager {
-    pub graphics_pipelines: HashMap<usize, (wgpu::RenderPipeline, usize)>,
-    pub compute_pipelines: HashMap<usize, (wgpu::ComputePipeline, usize)>,
+    pub graphics_pipelines: CacheEntries<wgpu::RenderPipeline>,
+    pub compute_pipelines: CacheEntries<wgpu::ComputePipeline>,
+
+    pub policy: CachePolicy,
+    pub stats: CacheStats,
 }
 
 const PIPELINE_LIFETIME_FRAMES: usize = 50;
 
-#[derive(Debug, Clone, Hash)]
+/// Layout of a vertex buffer slot, the slot is the index in the pipeline vertex buffers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct VertexAttributeLayout {
     pub stride: wgpu::BufferAddress,
     pub step_mode: wgpu::VertexStepMode,
@@ -27,10 +33,15 @@ pub(crate) struct GraphicsPipelineDesc {
         Option<wgpu::ColorWrites>,
     )>,
     pub depth_stencil: Option<wgpu::TextureFormat>,
-    pub vertex_desc: VertexAttributeLayout,
+    /// When false, fragments are never rejected by nor written to the depth target.
+    pub depth_test: bool,
+    pub stencil: wgpu::StencilState,
+    pub vertex_buffers: Vec<VertexAttributeLayout>,
     pub primitive_state: wgpu::PrimitiveState,
     pub bind_group_layout: Vec<wgpu::BindGroupLayout>,
+    pub push_constant_ranges: Vec<wgpu::PushConstantRange>,
     pub msaa_count: u32,
+    pub label: Option<String>,
 }
 
 #[derive(Debug, Clone, Hash)]
@@ -38,6 +49,8 @@ pub(crate) struct ComputePipelineDesc {
     pub shader_module: wgpu::ShaderModule,
     pub entry_point: String,
     pub bind_group_layout: Vec<wgpu::BindGroupLayout>,
+    pub push_constant_ranges: Vec<wgpu::PushConstantRange>,
+    pub label: Option<String>,
 }
 
 impl PipelineManager {
@@ -45,17 +58,21 @@ impl PipelineManager {
         Self {
             graphics_pipelines: HashMap::new(),
             compute_pipelines: HashMap::new(),
+
+            policy: CachePolicy::new().set_max_age_frames(PIPELINE_LIFETIME_FRAMES),
+            stats: CacheStats::default(),
         }
     }
 
     pub fn get_graphics_pipeline(&mut self, key: usize) -> Option<wgpu::RenderPipeline> {
-        if let Some((pipeline, lifetime)) = self.graphics_pipelines.get_mut(&key) {
+        let pipeline = self.graphics_pipelines.get_mut(&key).map(|(pipeline, lifetime)| {
             // reset lifetime
             *lifetime = 0;
-            Some(pipeline.clone())
-        } else {
-            None
-        }
+            pipeline.clone()
+        });
+
+        self.stats.record_lookup(pipeline.is_some());
+        pipeline
     }
 
     pub fn create_graphics_pipeline(
@@ -69,16 +86,20 @@ impl PipelineManager {
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some(format!("PipelineLayout {}", key).as_str()),
             bind_group_layouts: bind_group_layout_refs.as_slice(),
-            push_constant_ranges: &[],
+            push_constant_ranges: desc.push_constant_ranges.as_slice(),
         });
 
         let mut depth_stencil_desc = None;
         if let Some(format) = desc.depth_stencil {
             depth_stencil_desc = Some(wgpu::DepthStencilState {
                 format,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
+                depth_write_enabled: desc.depth_test,
+                depth_compare: if desc.depth_test {
+                    wgpu::CompareFunction::Less
+                } else {
+                    wgpu::CompareFunction::Always
+                },
+                stencil: desc.stencil,
                 bias: wgpu::DepthBiasState::default(),
             });
         }
@@ -94,13 +115,20 @@ impl PipelineManager {
             }));
         }
 
-        let label = format!("RenderPipeline {}", key);
-
-        let vertex_attribute_layout = wgpu::VertexBufferLayout {
-            array_stride: desc.vertex_desc.stride,
-            step_mode: desc.vertex_desc.step_mode,
-            attributes: desc.vertex_desc.attributes.as_slice(),
-        };
+        let label = desc
+            .label
+            .clone()
+            .unwrap_or_else(|| format!("RenderPipeline {}", key));
+
+        let vertex_buffer_layouts = desc
+            .vertex_buffers
+            .iter()
+            .map(|layout| wgpu::VertexBufferLayout {
+                array_stride: layout.stride,
+                step_mode: layout.step_mode,
+                attributes: layout.attributes.as_slice(),
+            })
+            .collect::<Vec<_>>();
 
         let render_pipeline_desc = wgpu::RenderPipelineDescriptor {
             label: Some(label.as_str()),
@@ -108,7 +136,7 @@ impl PipelineManager {
             vertex: wgpu::VertexState {
                 module: &desc.shaders.0,
                 entry_point: Some(desc.entry_point.0.as_str()),
-                buffers: &[vertex_attribute_layout],
+                buffers: vertex_buffer_layouts.as_slice(),
                 compilation_options: Default::default(),
             },
             fragment: Some(wgpu::FragmentState {
@@ -130,6 +158,7 @@ impl PipelineManager {
 
         let pipeline = device.create_render_pipeline(&render_pipeline_desc);
         self.graphics_pipelines.insert(key, (pipeline.clone(), 0));
+        self.stats.evictions += cache::trim_entries(&mut self.graphics_pipelines, self.policy.max_entries);
 
         dbg_log!("Inserted new graphics pipeline with key: {}", key);
 
@@ -137,13 +166,14 @@ impl PipelineManager {
     }
 
     pub fn get_compute_pipeline(&mut self, key: usize) -> Option<wgpu::ComputePipeline> {
-        if let Some((pipeline, lifetime)) = self.compute_pipelines.get_mut(&key) {
+        let pipeline = self.compute_pipelines.get_mut(&key).map(|(pipeline, lifetime)| {
             // reset lifetime
             *lifetime = 0;
-            Some(pipeline.clone())
-        } else {
-            None
-        }
+            pipeline.clone()
+        });
+
+        self.stats.record_lookup(pipeline.is_some());
+        pipeline
     }
 
     pub fn create_compute_pipeline(
@@ -158,10 +188,13 @@ impl PipelineManager {
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some(format!("PipelineLayout {}", key).as_str()),
             bind_group_layouts: bind_group_layout_refs.as_slice(),
-            push_constant_ranges: &[],
+            push_constant_ranges: &desc.push_constant_ranges,
         });
 
-        let label = format!("ComputePipeline {}", key);
+        let label = desc
+            .label
+            .clone()
+            .unwrap_or_else(|| format!("ComputePipeline {}", key));
 
         let compute_pipeline_desc = wgpu::ComputePipelineDescriptor {
             label: Some(label.as_str()),
@@ -174,23 +207,26 @@ impl PipelineManager {
 
         let pipeline = device.create_compute_pipeline(&compute_pipeline_desc);
         self.compute_pipelines.insert(key, (pipeline.clone(), 0));
+        self.stats.evictions += cache::trim_entries(&mut self.compute_pipelines, self.policy.max_entries);
 
         pipeline
     }
 
     pub fn cycle(&mut self) {
-        self.graphics_pipelines
-            .retain(|_, value| value.1 < PIPELINE_LIFETIME_FRAMES);
-
-        for (_, value) in self.graphics_pipelines.iter_mut() {
-            value.1 += 1;
-        }
+        self.stats.evictions += cache::cycle_entries(&mut self.graphics_pipelines, &self.policy);
+        self.stats.evictions += cache::cycle_entries(&mut self.compute_pipelines, &self.policy);
+    }
 
-        self.compute_pipelines
-            .retain(|_, value| value.1 < PIPELINE_LIFETIME_FRAMES);
+    /// Applies a new policy, evicting the entries over its limits right away.
+    pub fn set_policy(&mut self, policy: CachePolicy) {
+        self.policy = policy;
+        self.stats.evictions += cache::apply_policy(&mut self.graphics_pipelines, &self.policy);
+        self.stats.evictions += cache::apply_policy(&mut self.compute_pipelines, &self.policy);
+    }
 
-        for (_, value) in self.compute_pipelines.iter_mut() {
-            value.1 += 1;
-        }
+    pub fn clear(&mut self) {
+        self.stats.evictions += (self.graphics_pipelines.len() + self.compute_pipelines.len()) as u64;
+        self.graphics_pipelines.clear();
+        self.compute_pipelines.clear();
     }
 }
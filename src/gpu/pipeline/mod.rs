@@ -1,3 +1,74 @@
 pub mod render;
 pub mod compute;
 pub(crate) mod manager;
+
+use crate::utils::ArcRef;
+
+use super::{
+    GPUInner,
+    buffer::BufferInner,
+    command::utils::BindGroupType,
+    texture::{TextureInner, TextureSampler},
+};
+
+/// A resource bound to a pipeline, kept to bind it again when the pipeline is rebuilt after a
+/// device loss.
+#[derive(Clone)]
+pub(crate) enum AttachmentSource {
+    Sampler(TextureSampler),
+    Texture(ArcRef<TextureInner>),
+    TextureStorage(ArcRef<TextureInner>),
+    Uniform(ArcRef<BufferInner>),
+    Storage(ArcRef<BufferInner>),
+    /// Data given to the builder directly, uploaded to a buffer owned by the pipeline.
+    UniformData(Vec<u8>),
+    StorageData(Vec<u8>),
+}
+
+impl AttachmentSource {
+    /// The attachment bound from the resource as it is on the current device.
+    pub(crate) fn resolve(&self, gpu: &ArcRef<GPUInner>) -> BindGroupType {
+        match self {
+            AttachmentSource::Sampler(sampler) => {
+                BindGroupType::Sampler(sampler.make_wgpu(gpu.borrow().device()))
+            }
+            AttachmentSource::Texture(texture) => {
+                BindGroupType::Texture(texture.borrow().sample_view().clone())
+            }
+            AttachmentSource::TextureStorage(texture) => {
+                let inner = texture.borrow();
+                BindGroupType::TextureStorage(inner.wgpu_view.clone(), inner.format)
+            }
+            AttachmentSource::Uniform(buffer) => BindGroupType::Uniform(buffer.borrow().range()),
+            AttachmentSource::Storage(buffer) => BindGroupType::Storage(buffer.borrow().range()),
+            AttachmentSource::UniformData(data) => {
+                let buffer = gpu
+                    .borrow_mut()
+                    .create_buffer_with(data, wgpu::BufferUsages::COPY_DST);
+
+                BindGroupType::Uniform(buffer.into())
+            }
+            AttachmentSource::StorageData(data) => {
+                let buffer = gpu
+                    .borrow_mut()
+                    .create_buffer_with(data, wgpu::BufferUsages::COPY_DST);
+
+                BindGroupType::Storage(buffer.into())
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for AttachmentSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttachmentSource::Sampler(_) => write!(f, "Sampler"),
+            AttachmentSource::Texture(_) => write!(f, "Texture"),
+            AttachmentSource::TextureStorage(_) => write!(f, "TextureStorage"),
+            AttachmentSource::Uniform(_) => write!(f, "Uniform"),
+            AttachmentSource::Storage(_) => write!(f, "Storage"),
+            AttachmentSource::UniformData(data) => write!(f, "UniformData({} bytes)", data.len()),
+            AttachmentSource::StorageData(data) => write!(f, "StorageData({} bytes)", data.len()),
+        }
+    }
+}
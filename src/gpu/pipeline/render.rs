@@ -3,15 +3,17 @@ use std::{
     hash::{DefaultHasher, Hash, Hasher},
 };
 
-use crate::utils::ArcRef;
+use crate::utils::{ArcRef, LockRank};
 
 use super::{
-    manager::{GraphicsPipelineDesc, VertexAttributeLayout},
+    AttachmentSource,
+    manager::GraphicsPipelineDesc,
     super::{
         GPUInner,
-        texture::{Texture, TextureSampler, BlendState},
+        texture::{Texture, TextureSampler, BlendState, StencilState},
         shader::{
             bind_group_manager::BindGroupCreateInfo,
+            BindGroupLayout,
             GraphicsShader,
             IndexBufferSize,
             ShaderBindingType,
@@ -19,8 +21,8 @@ use super::{
             ShaderFrontFace,
             ShaderPollygonMode,
             ShaderTopology,
-            graphics::GraphicsShaderType,
-            types::ShaderReflect
+            graphics::{GraphicsShaderInner, GraphicsShaderType, VertexInputDescription},
+            types::ShaderReflect,
         },
         buffer::Buffer,
         command::{
@@ -31,21 +33,55 @@ use super::{
     }
 };
 
+/// A render pipeline, rebuilt in place when the device is re-created after a loss.
 #[derive(Debug, Clone, Hash)]
 pub struct RenderPipeline {
+    pub(crate) inner: ArcRef<RenderPipelineInner>,
+}
+
+#[derive(Debug, Clone, Hash)]
+pub(crate) struct RenderPipelineInner {
     pub(crate) bind_group: Vec<(u32, wgpu::BindGroup)>,
+    pub(crate) bind_group_layouts: Vec<BindGroupLayout>,
     pub(crate) pipeline_desc: GraphicsPipelineDesc,
     pub(crate) index_format: Option<IndexBufferSize>,
 }
 
+/// The shader and options given to [RenderPipelineBuilder::set_shader_with_options].
+#[derive(Debug, Clone)]
+pub(crate) struct RenderShaderSource {
+    shader: ArcRef<GraphicsShaderInner>,
+    attrib: ArcRef<VertexInputDescription>,
+    topology: Option<ShaderTopology>,
+    cull_mode: Option<ShaderCullMode>,
+    front_face: Option<ShaderFrontFace>,
+    polygon_mode: Option<ShaderPollygonMode>,
+    index_format: Option<IndexBufferSize>,
+}
+
+/// What a render pipeline was built from, kept to build it again after a device loss.
+#[derive(Debug, Clone)]
+pub(crate) struct RenderPipelineSource {
+    shader: Option<RenderShaderSource>,
+    attachments: Vec<(u32, u32, AttachmentSource)>,
+    blend: Option<wgpu::BlendState>,
+    color_write_mask: Option<wgpu::ColorWrites>,
+    stencil: Option<wgpu::StencilState>,
+    label: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct RenderPipelineBuilder {
     pub(crate) gpu: ArcRef<GPUInner>,
     pub(crate) attachments: Vec<BindGroupAttachment>,
+    pub(crate) sources: Vec<(u32, u32, AttachmentSource)>,
     pub(crate) shader: Option<IntermediateRenderPipeline>,
+    pub(crate) shader_source: Option<RenderShaderSource>,
     pub(crate) blend: Option<wgpu::BlendState>,
     pub(crate) color_write_mask: Option<wgpu::ColorWrites>,
+    pub(crate) stencil: Option<wgpu::StencilState>,
     pub(crate) shader_reflection: Option<Vec<ShaderReflect>>,
+    pub(crate) label: Option<String>,
 }
 
 impl RenderPipelineBuilder {
@@ -53,13 +89,37 @@ impl RenderPipelineBuilder {
         Self {
             gpu,
             attachments: Vec::new(),
+            sources: Vec::new(),
             shader: None,
+            shader_source: None,
             blend: None,
             color_write_mask: None,
+            stencil: None,
             shader_reflection: None,
+            label: None,
         }
     }
 
+    /// A builder binding the resources of `source` as they are on the current device.
+    pub(crate) fn from_source(gpu: ArcRef<GPUInner>, source: &RenderPipelineSource) -> Self {
+        let mut builder = Self::new(gpu);
+        builder.blend = source.blend;
+        builder.color_write_mask = source.color_write_mask;
+        builder.stencil = source.stencil.clone();
+        builder.label = source.label.clone();
+
+        if let Some(shader) = &source.shader {
+            builder.apply_shader(shader);
+            builder.shader_source = Some(shader.clone());
+        }
+
+        for (group, binding, attachment) in &source.attachments {
+            builder.attach(*group, *binding, attachment.clone());
+        }
+
+        builder
+    }
+
     #[inline]
     pub fn set_blend(mut self, blend: Option<&BlendState>) -> Self {
         match blend {
@@ -76,6 +136,17 @@ impl RenderPipelineBuilder {
         self
     }
 
+    /// Set the stencil state used by this pipeline.
+    ///
+    /// Requires a depth target with a stencil aspect, eg. `TextureFormat::Depth24PlusStencil8`,
+    /// and stencil ops configured with `RenderpassBuilder::set_stencil_ops` if the state writes
+    /// to the stencil buffer.
+    #[inline]
+    pub fn set_stencil(mut self, stencil: Option<&StencilState>) -> Self {
+        self.stencil = stencil.map(|stencil| stencil.create_wgpu_stencil_state());
+        self
+    }
+
     #[inline]
     pub fn set_shader(self, shader: Option<&GraphicsShader>) -> Self {
         self.set_shader_with_options(shader, None, None, None, None, None)
@@ -93,81 +164,98 @@ impl RenderPipelineBuilder {
     ) -> Self {
         match shader {
             Some(shader) => {
-                let shader_inner = shader.inner.borrow();
-                let (vertex_shader, fragment_shader) = match &shader_inner.ty {
-                    GraphicsShaderType::GraphicsSplit {
-                        vertex_module,
-                        fragment_module,
-                    } => (vertex_module.clone(), fragment_module.clone()),
-                    GraphicsShaderType::GraphicsSingle { module } => (module.clone(), module.clone()),
+                let source = RenderShaderSource {
+                    shader: ArcRef::clone(&shader.inner),
+                    attrib: ArcRef::clone(&shader.attrib),
+                    topology,
+                    cull_mode,
+                    front_face,
+                    polygon_mode,
+                    index_format,
                 };
 
-                let layout = shader_inner.bind_group_layouts.clone();
+                self.apply_shader(&source);
+                self.shader_source = Some(source);
+            }
+            None => {
+                self.shader = None;
+                self.shader_source = None;
+                self.shader_reflection = None;
+            }
+        }
 
-                let vertex_reflect = shader_inner.reflection.iter().find(|r| {
-                    matches!(r, ShaderReflect::Vertex { .. })
-                        || matches!(r, ShaderReflect::VertexFragment { .. })
-                });
+        self
+    }
 
-                let fragment_reflect = shader_inner.reflection.iter().find(|r| {
-                    matches!(r, ShaderReflect::Fragment { .. })
-                        || matches!(r, ShaderReflect::VertexFragment { .. })
-                });
+    fn apply_shader(&mut self, source: &RenderShaderSource) {
+        let shader_inner = source.shader.borrow();
+        let (vertex_shader, fragment_shader) = match &shader_inner.ty {
+            GraphicsShaderType::GraphicsSplit {
+                vertex_module,
+                fragment_module,
+            } => (vertex_module.clone(), fragment_module.clone()),
+            GraphicsShaderType::GraphicsSingle { module } => (module.clone(), module.clone()),
+        };
 
-                let vertex_entry_point = match vertex_reflect {
-                    Some(ShaderReflect::Vertex { entry_point, .. }) => Some(entry_point),
-                    Some(ShaderReflect::VertexFragment {
-                        vertex_entry_point, ..
-                    }) => Some(vertex_entry_point),
-                    _ => None,
-                };
+        let layout = shader_inner.bind_group_layouts.clone();
 
-                let fragment_entry_point = match fragment_reflect {
-                    Some(ShaderReflect::Fragment { entry_point, .. }) => Some(entry_point),
-                    Some(ShaderReflect::VertexFragment {
-                        fragment_entry_point,
-                        ..
-                    }) => Some(fragment_entry_point),
-                    _ => None,
-                };
+        let vertex_reflect = shader_inner.reflection.iter().find(|r| {
+            matches!(r, ShaderReflect::Vertex { .. })
+                || matches!(r, ShaderReflect::VertexFragment { .. })
+        });
 
-                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
-                {
-                    if vertex_entry_point.is_none() {
-                        panic!("Vertex shader entry point is not found in shader reflection");
-                    }
+        let fragment_reflect = shader_inner.reflection.iter().find(|r| {
+            matches!(r, ShaderReflect::Fragment { .. })
+                || matches!(r, ShaderReflect::VertexFragment { .. })
+        });
 
-                    if fragment_entry_point.is_none() {
-                        panic!("Fragment shader entry point is not found in shader reflection");
-                    }
-                }
+        let vertex_entry_point = match vertex_reflect {
+            Some(ShaderReflect::Vertex { entry_point, .. }) => Some(entry_point),
+            Some(ShaderReflect::VertexFragment {
+                vertex_entry_point, ..
+            }) => Some(vertex_entry_point),
+            _ => None,
+        };
 
-                let vertex_entry_point = vertex_entry_point.unwrap();
-                let fragment_entry_point = fragment_entry_point.unwrap();
-
-                let attrib_inner = shader.attrib.borrow();
-                let shader_binding = IntermediateRenderPipeline {
-                    shader: (vertex_shader, fragment_shader),
-                    vertex_attribute: (attrib_inner.stride, attrib_inner.attributes.clone()),
-                    shader_entry: (vertex_entry_point.clone(), fragment_entry_point.clone()),
-                    layout: layout,
-                    topology: topology.unwrap_or(attrib_inner.topology),
-                    cull_mode: cull_mode.into(),
-                    front_face: front_face.unwrap_or(attrib_inner.front_face),
-                    polygon_mode: polygon_mode.unwrap_or(attrib_inner.polygon_mode),
-                    index_format: index_format.or_else(|| attrib_inner.index.clone()),
-                };
+        let fragment_entry_point = match fragment_reflect {
+            Some(ShaderReflect::Fragment { entry_point, .. }) => Some(entry_point),
+            Some(ShaderReflect::VertexFragment {
+                fragment_entry_point,
+                ..
+            }) => Some(fragment_entry_point),
+            _ => None,
+        };
 
-                self.shader = Some(shader_binding);
-                self.shader_reflection = Some(shader_inner.reflection.clone());
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        {
+            if vertex_entry_point.is_none() {
+                panic!("Vertex shader entry point is not found in shader reflection");
             }
-            None => {
-                self.shader = None;
-                self.shader_reflection = None;
+
+            if fragment_entry_point.is_none() {
+                panic!("Fragment shader entry point is not found in shader reflection");
             }
         }
 
-        self
+        let vertex_entry_point = vertex_entry_point.unwrap();
+        let fragment_entry_point = fragment_entry_point.unwrap();
+
+        let attrib_inner = source.attrib.borrow();
+        let shader_binding = IntermediateRenderPipeline {
+            shader: (vertex_shader, fragment_shader),
+            vertex_buffers: attrib_inner.buffers.clone(),
+            shader_entry: (vertex_entry_point.clone(), fragment_entry_point.clone()),
+            layout: layout,
+            push_constant_ranges: shader_inner.push_constant_ranges.clone(),
+            topology: source.topology.unwrap_or(attrib_inner.topology),
+            cull_mode: source.cull_mode.into(),
+            front_face: source.front_face.unwrap_or(attrib_inner.front_face),
+            polygon_mode: source.polygon_mode.unwrap_or(attrib_inner.polygon_mode),
+            index_format: source.index_format.or_else(|| attrib_inner.index.clone()),
+        };
+
+        self.shader = Some(shader_binding);
+        self.shader_reflection = Some(shader_inner.reflection.clone());
     }
 
     #[inline]
@@ -179,19 +267,7 @@ impl RenderPipelineBuilder {
     ) -> Self {
         match sampler {
             Some(sampler) => {
-                let attachment = {
-                    let gpu_inner = self.gpu.borrow();
-
-                    BindGroupAttachment {
-                        group,
-                        binding,
-                        attachment: BindGroupType::Sampler(
-                            sampler.make_wgpu(gpu_inner.device()),
-                        ),
-                    }
-                };
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                self.attach(group, binding, AttachmentSource::Sampler(*sampler));
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -210,17 +286,8 @@ impl RenderPipelineBuilder {
     ) -> Self {
         match texture {
             Some(texture) => {
-                let attachment = {
-                    BindGroupAttachment {
-                        group,
-                        binding,
-                        attachment: BindGroupType::Texture(
-                            texture.inner.borrow().wgpu_view.clone(),
-                        ),
-                    }
-                };
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::Texture(ArcRef::clone(&texture.inner));
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -239,14 +306,11 @@ impl RenderPipelineBuilder {
     ) -> Self {
         match texture {
             Some(texture) => {
-                let inner = texture.inner.borrow();
-                let attachment = BindGroupAttachment {
-                    group,
-                    binding,
-                    attachment: BindGroupType::TextureStorage(inner.wgpu_view.clone()),
-                };
+                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+                texture.inner.borrow().validate_storage_binding();
 
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::TextureStorage(ArcRef::clone(&texture.inner));
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -265,14 +329,8 @@ impl RenderPipelineBuilder {
     ) -> Self {
         match buffer {
             Some(buffer) => {
-                let inner = buffer.inner.borrow();
-                let attachment = BindGroupAttachment {
-                    group,
-                    binding,
-                    attachment: BindGroupType::Uniform(inner.buffer.clone()),
-                };
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::Uniform(ArcRef::clone(&buffer.inner));
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -294,18 +352,8 @@ impl RenderPipelineBuilder {
     {
         match buffer {
             Some(buffer) => {
-                let attachment = {
-                    let mut inner = self.gpu.borrow_mut();
-
-                    let buffer = inner.create_buffer_with(&buffer, wgpu::BufferUsages::COPY_DST);
-                    BindGroupAttachment {
-                        group,
-                        binding,
-                        attachment: BindGroupType::Uniform(buffer),
-                    }
-                };
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::UniformData(bytemuck::cast_slice(&buffer).to_vec());
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -327,18 +375,8 @@ impl RenderPipelineBuilder {
     {
         match buffer {
             Some(buffer) => {
-                let mut inner = self.gpu.borrow_mut();
-
-                let buffer = inner.create_buffer_with(&buffer, wgpu::BufferUsages::COPY_DST);
-                let attachment = BindGroupAttachment {
-                    group,
-                    binding,
-                    attachment: BindGroupType::Uniform(buffer),
-                };
-
-                drop(inner);
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::UniformData(bytemuck::cast_slice(buffer).to_vec());
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -357,14 +395,8 @@ impl RenderPipelineBuilder {
     ) -> Self {
         match buffer {
             Some(buffer) => {
-                let inner = buffer.inner.borrow();
-                let attachment = BindGroupAttachment {
-                    group,
-                    binding,
-                    attachment: BindGroupType::Storage(inner.buffer.clone()),
-                };
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::Storage(ArcRef::clone(&buffer.inner));
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -386,18 +418,8 @@ impl RenderPipelineBuilder {
     {
         match buffer {
             Some(buffer) => {
-                let mut inner = self.gpu.borrow_mut();
-
-                let buffer = inner.create_buffer_with(&buffer, wgpu::BufferUsages::COPY_DST);
-                let attachment = BindGroupAttachment {
-                    group,
-                    binding,
-                    attachment: BindGroupType::Storage(buffer),
-                };
-
-                drop(inner);
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::StorageData(bytemuck::cast_slice(buffer).to_vec());
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -419,18 +441,8 @@ impl RenderPipelineBuilder {
     {
         match buffer {
             Some(buffer) => {
-                let mut inner = self.gpu.borrow_mut();
-
-                let buffer = inner.create_buffer_with(&buffer, wgpu::BufferUsages::COPY_DST);
-                let attachment = BindGroupAttachment {
-                    group,
-                    binding,
-                    attachment: BindGroupType::Storage(buffer),
-                };
-
-                drop(inner);
-
-                self.insert_or_replace_attachment(group, binding, attachment);
+                let source = AttachmentSource::StorageData(bytemuck::cast_slice(&buffer).to_vec());
+                self.attach(group, binding, source);
             }
             None => {
                 self.remove_attachment(group, binding);
@@ -440,10 +452,25 @@ impl RenderPipelineBuilder {
         self
     }
 
+    /// Binds the resource of `source`, which is kept to bind it again when the pipeline is
+    /// rebuilt after a device loss.
+    pub(crate) fn attach(&mut self, group: u32, binding: u32, source: AttachmentSource) {
+        let attachment = BindGroupAttachment {
+            group,
+            binding,
+            attachment: source.resolve(&self.gpu),
+        };
+
+        self.insert_or_replace_attachment(group, binding, attachment);
+        self.sources.retain(|(g, b, _)| *g != group || *b != binding);
+        self.sources.push((group, binding, source));
+    }
+
     #[inline]
     pub(crate) fn remove_attachment(&mut self, group: u32, binding: u32) {
         self.attachments
             .retain(|a| a.group != group || a.binding != binding);
+        self.sources.retain(|(g, b, _)| *g != group || *b != binding);
     }
 
     pub(crate) fn insert_or_replace_attachment(
@@ -464,7 +491,35 @@ impl RenderPipelineBuilder {
         }
     }
 
+    /// Sets the debug label of the pipeline, shown in graphics debuggers.
+    pub fn set_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
     pub fn build(self) -> Result<RenderPipeline, RenderPipelineError> {
+        let gpu = ArcRef::clone(&self.gpu);
+        let source = RenderPipelineSource {
+            shader: self.shader_source.clone(),
+            attachments: self.sources.clone(),
+            blend: self.blend,
+            color_write_mask: self.color_write_mask,
+            stencil: self.stencil.clone(),
+            label: self.label.clone(),
+        };
+
+        let pipeline = RenderPipeline {
+            inner: ArcRef::new_ranked(self.build_inner()?, LockRank::Resource),
+        };
+
+        gpu.borrow_mut()
+            .resources
+            .register_render_pipeline(&pipeline.inner, source);
+
+        Ok(pipeline)
+    }
+
+    pub(crate) fn build_inner(self) -> Result<RenderPipelineInner, RenderPipelineError> {
         if self.shader.is_none() {
             return Err(RenderPipelineError::ShaderNotSet);
         }
@@ -511,13 +566,13 @@ impl RenderPipelineBuilder {
                 ShaderBindingType::StorageBuffer(_, _) => {
                     matches!(attachment.attachment, BindGroupType::Storage(_))
                 }
-                ShaderBindingType::StorageTexture(_) => {
-                    matches!(attachment.attachment, BindGroupType::TextureStorage(_))
+                ShaderBindingType::StorageTexture(_, format) => {
+                    matches!(attachment.attachment, BindGroupType::TextureStorage(_, f) if f == format)
                 }
                 ShaderBindingType::Sampler(_) => {
                     matches!(attachment.attachment, BindGroupType::Sampler(_))
                 }
-                ShaderBindingType::Texture(_) => {
+                ShaderBindingType::Texture(_) | ShaderBindingType::DepthTexture(_) => {
                     matches!(attachment.attachment, BindGroupType::Texture(_))
                 }
                 ShaderBindingType::PushConstant(_) => {
@@ -546,7 +601,7 @@ impl RenderPipelineBuilder {
                     BindGroupType::Texture(texture) => {
                         texture.hash(&mut hasher);
                     }
-                    BindGroupType::TextureStorage(texture) => texture.hash(&mut hasher),
+                    BindGroupType::TextureStorage(texture, _) => texture.hash(&mut hasher),
                     BindGroupType::Sampler(sampler) => sampler.hash(&mut hasher),
                     BindGroupType::Storage(storage) => storage.hash(&mut hasher),
                 }
@@ -564,14 +619,16 @@ impl RenderPipelineBuilder {
                     let mut bind_group_attachments: HashMap<u32, Vec<wgpu::BindGroupEntry>> =
                         self.attachments.iter().fold(HashMap::new(), |mut map, e| {
                             let (group, binding, attachment) = (e.group, e.binding, &e.attachment);
+                            let size = shader_binding
+                                .layout
+                                .iter()
+                                .find(|l| l.group == group)
+                                .and_then(|l| l.dynamic_binding_size(binding));
+
                             let entry = match attachment {
                                 BindGroupType::Uniform(buffer) => wgpu::BindGroupEntry {
                                     binding,
-                                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                                        buffer,
-                                        offset: 0,
-                                        size: None,
-                                    }),
+                                    resource: wgpu::BindingResource::Buffer(buffer.binding(size)),
                                 },
                                 BindGroupType::Texture(texture) => wgpu::BindGroupEntry {
                                     binding,
@@ -583,13 +640,9 @@ impl RenderPipelineBuilder {
                                 },
                                 BindGroupType::Storage(buffer) => wgpu::BindGroupEntry {
                                     binding,
-                                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                                        buffer,
-                                        offset: 0,
-                                        size: None,
-                                    }),
+                                    resource: wgpu::BindingResource::Buffer(buffer.binding(size)),
                                 },
-                                BindGroupType::TextureStorage(texture) => wgpu::BindGroupEntry {
+                                BindGroupType::TextureStorage(texture, _) => wgpu::BindGroupEntry {
                                     binding,
                                     resource: wgpu::BindingResource::TextureView(texture),
                                 },
@@ -628,13 +681,6 @@ impl RenderPipelineBuilder {
             }
         };
 
-        let attribute = &shader_binding.vertex_attribute;
-        let vertex_desc = VertexAttributeLayout {
-            stride: attribute.0 as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: attribute.1.clone(),
-        };
-
         let primitive_state = wgpu::PrimitiveState {
             topology: shader_binding.topology.into(),
             strip_index_format: None,
@@ -660,14 +706,19 @@ impl RenderPipelineBuilder {
                 self.color_write_mask.clone(),
             )],
             depth_stencil: None,
-            vertex_desc,
+            depth_test: true,
+            stencil: self.stencil.unwrap_or_default(),
+            vertex_buffers: shader_binding.vertex_buffers.clone(),
             primitive_state,
             bind_group_layout: layout,
+            push_constant_ranges: shader_binding.push_constant_ranges.clone(),
             msaa_count: 1,
+            label: self.label,
         };
 
-        Ok(RenderPipeline {
+        Ok(RenderPipelineInner {
             bind_group: bind_group_attachments,
+            bind_group_layouts: shader_binding.layout.clone(),
             pipeline_desc,
             index_format: shader_binding.index_format,
         })
@@ -0,0 +1,299 @@
+use std::time::Duration;
+
+use crate::utils::{ArcMut, ArcRef};
+
+use super::{GPUInner, command::CommandBuffer};
+
+/// GPU timing of a profiler scope.
+#[derive(Clone, Debug)]
+pub struct ProfilerTiming {
+    pub label: String,
+    pub duration: Duration,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum ProfilerError {
+    /// The adapter doesn't support timestamp queries inside command encoders.
+    Unsupported,
+    InvalidCapacity,
+}
+
+impl std::fmt::Display for ProfilerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfilerError::Unsupported => {
+                write!(f, "Timestamp queries are not supported by the adapter")
+            }
+            ProfilerError::InvalidCapacity => {
+                write!(f, "Profiler capacity must be greater than zero")
+            }
+        }
+    }
+}
+
+/// Measures the GPU time spent in labeled scopes of a command buffer using timestamp queries.
+///
+/// Each frame, record work inside [GpuProfiler::scope], call [GpuProfiler::resolve] before
+/// ending the command buffer and [GpuProfiler::read_timings] after it has been submitted.
+///
+/// The timestamps are read back without waiting on the GPU, so the timings are the ones of a
+/// previous frame, usually one or two frames behind.
+///
+/// ```rust,ignore
+/// let mut profiler = gpu.create_profiler(16)?;
+///
+/// let mut cmd = gpu.begin_command()?;
+/// profiler.scope(&mut cmd, "shadow pass", |cmd| {
+///     let mut pass = cmd.begin_depth_texture(&shadow_map).unwrap();
+///     // ...
+/// });
+///
+/// profiler.resolve(&mut cmd);
+/// cmd.end(true);
+///
+/// for timing in profiler.read_timings() {
+///     println!("{}: {:?}", timing.label, timing.duration);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct GpuProfiler {
+    graphics: ArcRef<GPUInner>,
+
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback: Vec<ReadbackSlot>,
+    next_readback: usize,
+    capacity: u32,
+
+    scopes: Vec<String>,
+    timings: Vec<ProfilerTiming>,
+}
+
+/// Number of frames whose timestamps can be in flight at once.
+const READBACK_FRAMES: usize = 3;
+
+/// Buffer the timestamps of one frame are copied to, free once both fields are [None].
+#[derive(Debug)]
+struct ReadbackSlot {
+    buffer: wgpu::Buffer,
+    /// Labels of the scopes copied to the buffer.
+    labels: Option<Vec<String>>,
+    /// Result of the mapping, set by its callback once the GPU finished the frame.
+    mapped: Option<ArcMut<Option<Result<(), wgpu::BufferAsyncError>>>>,
+}
+
+impl GpuProfiler {
+    pub(crate) fn new(graphics: ArcRef<GPUInner>, capacity: u32) -> Result<Self, ProfilerError> {
+        if capacity == 0 {
+            return Err(ProfilerError::InvalidCapacity);
+        }
+
+        let graphics_ref = graphics.borrow();
+        let device = graphics_ref.device();
+
+        let required = wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS;
+        if !device.features().contains(required) {
+            return Err(ProfilerError::Unsupported);
+        }
+
+        let query_count = capacity * 2;
+        let buffer_size = query_count as u64 * wgpu::QUERY_SIZE as u64;
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Profiler QuerySet"),
+            ty: wgpu::QueryType::Timestamp,
+            count: query_count,
+        });
+
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Profiler Resolve Buffer"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let readback = (0..READBACK_FRAMES)
+            .map(|_| ReadbackSlot {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Profiler Readback Buffer"),
+                    size: buffer_size,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                labels: None,
+                mapped: None,
+            })
+            .collect();
+
+        drop(graphics_ref);
+
+        Ok(Self {
+            graphics,
+            query_set,
+            resolve_buffer,
+            readback,
+            next_readback: 0,
+            capacity,
+            scopes: Vec::new(),
+            timings: Vec::new(),
+        })
+    }
+
+    /// Runs `f` and measures the GPU time of the passes and copies it records.
+    ///
+    /// Passes must be finished (dropped) before `f` returns to be included in the scope.
+    /// Scopes past the profiler capacity are run without being measured.
+    pub fn scope<R>(
+        &mut self,
+        cmd: &mut CommandBuffer,
+        label: &str,
+        f: impl FnOnce(&mut CommandBuffer) -> R,
+    ) -> R {
+        let scope = self.begin_scope(cmd, label);
+        let result = f(cmd);
+
+        if let Some(scope) = scope {
+            self.end_scope(cmd, scope);
+        }
+
+        result
+    }
+
+    /// Starts measuring a scope, [None] when the profiler capacity is exceeded.
+    pub(crate) fn begin_scope(&mut self, cmd: &mut CommandBuffer, label: &str) -> Option<u32> {
+        if self.scopes.len() as u32 >= self.capacity {
+            crate::dbg_log!(
+                "Profiler capacity of {} scopes exceeded, '{}' is not measured",
+                self.capacity,
+                label
+            );
+
+            return None;
+        }
+
+        let index = self.scopes.len() as u32 * 2;
+        self.scopes.push(label.to_string());
+
+        self.write_timestamp(cmd, index);
+        Some(index)
+    }
+
+    pub(crate) fn end_scope(&mut self, cmd: &mut CommandBuffer, scope: u32) {
+        self.write_timestamp(cmd, scope + 1);
+    }
+
+    /// Resolves the timestamps recorded this frame, must be called before the command buffer ends.
+    pub fn resolve(&mut self, cmd: &mut CommandBuffer) {
+        if self.scopes.is_empty() {
+            return;
+        }
+
+        let slot = &mut self.readback[self.next_readback];
+        if slot.labels.is_some() {
+            crate::dbg_log!("Profiler readback buffers are all in flight, dropping the timings of this frame");
+            self.scopes.clear();
+            return;
+        }
+
+        let query_count = self.scopes.len() as u32 * 2;
+        let size = query_count as u64 * wgpu::QUERY_SIZE as u64;
+
+        let mut encoder = cmd.command.as_ref().unwrap().borrow_mut();
+        encoder.resolve_query_set(&self.query_set, 0..query_count, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &slot.buffer, 0, size);
+
+        slot.labels = Some(std::mem::take(&mut self.scopes));
+        self.next_readback = (self.next_readback + 1) % self.readback.len();
+    }
+
+    /// Reads back the timings of the most recent frame the GPU has finished.
+    ///
+    /// Must be called after the command buffer has been submitted, this doesn't wait for the
+    /// GPU. Returns the previous timings if no frame finished since the last call.
+    pub fn read_timings(&mut self) -> &[ProfilerTiming] {
+        let graphics_ref = self.graphics.borrow();
+
+        // The buffers can only be mapped once the copies to them were submitted
+        for slot in self.readback.iter_mut() {
+            let Some(labels) = &slot.labels else {
+                continue;
+            };
+
+            if slot.mapped.is_some() {
+                continue;
+            }
+
+            let mapped = ArcMut::new(None);
+            let result = mapped.clone();
+
+            let size = labels.len() as u64 * 2 * wgpu::QUERY_SIZE as u64;
+            slot.buffer
+                .slice(..size)
+                .map_async(wgpu::MapMode::Read, move |r| *result.lock() = Some(r));
+
+            slot.mapped = Some(mapped);
+        }
+
+        _ = graphics_ref.device().poll(wgpu::PollType::Poll);
+
+        let period = graphics_ref.queue().get_timestamp_period() as f64;
+
+        // Oldest first, so the newest finished frame is the one kept
+        for offset in 0..self.readback.len() {
+            let index = (self.next_readback + offset) % self.readback.len();
+            let slot = &mut self.readback[index];
+
+            let Some(result) = slot.mapped.as_ref().and_then(|mapped| mapped.lock().take()) else {
+                continue;
+            };
+
+            slot.mapped = None;
+            let labels = slot.labels.take().unwrap_or_default();
+
+            if let Err(err) = result {
+                crate::error_log!("Failed to read back the profiler timestamps: {}", err);
+                continue;
+            }
+
+            {
+                let size = labels.len() as u64 * 2 * wgpu::QUERY_SIZE as u64;
+                let data = slot.buffer.slice(..size).get_mapped_range();
+                let timestamps: &[u64] = bytemuck::cast_slice(&data);
+
+                self.timings = labels
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, label)| {
+                        let start = timestamps[i * 2];
+                        let end = timestamps[i * 2 + 1];
+                        let nanos = end.saturating_sub(start) as f64 * period;
+
+                        ProfilerTiming {
+                            label,
+                            duration: Duration::from_nanos(nanos as u64),
+                        }
+                    })
+                    .collect();
+            }
+
+            slot.buffer.unmap();
+        }
+
+        &self.timings
+    }
+
+    /// Returns the timings from the last [GpuProfiler::read_timings].
+    pub fn timings(&self) -> &[ProfilerTiming] {
+        &self.timings
+    }
+
+    fn write_timestamp(&self, cmd: &mut CommandBuffer, index: u32) {
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        if cmd.command.is_none() {
+            panic!("Command buffer has already ended");
+        }
+
+        let mut encoder = cmd.command.as_ref().unwrap().borrow_mut();
+        encoder.write_timestamp(&self.query_set, index);
+    }
+}
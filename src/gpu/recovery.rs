@@ -0,0 +1,508 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use crate::utils::{ArcMut, ArcRef, WeakRef};
+
+use super::{
+    GPUInner,
+    buffer::{BufferInner, pool::PoolAllocation, staging_buffer::StagingBuffer},
+    deletion::{DeferredResource, DeletionQueue},
+    diagnostics::DiagnosticsRecorder,
+    error::ErrorHandler,
+    pipeline::{
+        compute::{ComputePipelineBuilder, ComputePipelineInner, ComputePipelineSource},
+        manager::PipelineManager,
+        render::{RenderPipelineBuilder, RenderPipelineInner, RenderPipelineSource},
+    },
+    shader::{
+        bind_group_manager::BindGroupManager,
+        compute::{ComputeShader, ComputeShaderInner},
+        graphics::{GraphicsShader, GraphicsShaderInner, GraphicsShaderSource},
+    },
+    stats::{GpuStats, texture_bytes},
+    texture::{TextureInner, msaa::MsaaTextureCache},
+};
+
+/// Resources re-created after a device loss, along with what is needed to re-create them.
+///
+/// Only weak references are kept, dropped resources are pruned every frame.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ResourceRegistry {
+    // The wgpu handles outlive the resources, dropped ones are handed to the deletion queue
+    textures: Vec<(WeakRef<TextureInner>, String, wgpu::Texture)>,
+    buffers: Vec<(WeakRef<BufferInner>, wgpu::Buffer, Option<Arc<PoolAllocation>>)>,
+    graphics_shaders: Vec<(WeakRef<GraphicsShaderInner>, GraphicsShaderSource, Option<String>)>,
+    compute_shaders: Vec<(WeakRef<ComputeShaderInner>, String, Option<String>)>,
+    render_pipelines: Vec<(WeakRef<RenderPipelineInner>, RenderPipelineSource)>,
+    compute_pipelines: Vec<(WeakRef<ComputePipelineInner>, ComputePipelineSource)>,
+}
+
+impl ResourceRegistry {
+    /// The handle is taken by the caller, the graphics state is borrowed while registering.
+    pub fn register_texture(
+        &mut self,
+        texture: &ArcRef<TextureInner>,
+        handle: wgpu::Texture,
+        label: &str,
+    ) {
+        self.textures
+            .push((ArcRef::downgrade(texture), label.to_string(), handle));
+    }
+
+    pub fn register_buffer(
+        &mut self,
+        buffer: &ArcRef<BufferInner>,
+        handle: wgpu::Buffer,
+        allocation: Option<Arc<PoolAllocation>>,
+    ) {
+        self.buffers
+            .push((ArcRef::downgrade(buffer), handle, allocation));
+    }
+
+    /// Replaces the handle kept for a buffer after it was resized.
+    pub fn update_buffer(&mut self, buffer: &ArcRef<BufferInner>, handle: wgpu::Buffer) {
+        let buffer = ArcRef::downgrade(buffer);
+        let entry = self
+            .buffers
+            .iter_mut()
+            .find(|(weak, _, _)| weak.ptr_eq(&buffer));
+
+        if let Some((_, old_handle, _)) = entry {
+            *old_handle = handle;
+        }
+    }
+
+    pub fn register_graphics_shader(
+        &mut self,
+        shader: &ArcRef<GraphicsShaderInner>,
+        source: GraphicsShaderSource,
+        label: Option<String>,
+    ) {
+        self.graphics_shaders
+            .push((ArcRef::downgrade(shader), source, label));
+    }
+
+    pub fn register_compute_shader(
+        &mut self,
+        shader: &ArcRef<ComputeShaderInner>,
+        source: String,
+        label: Option<String>,
+    ) {
+        self.compute_shaders
+            .push((ArcRef::downgrade(shader), source, label));
+    }
+
+    pub fn register_render_pipeline(
+        &mut self,
+        pipeline: &ArcRef<RenderPipelineInner>,
+        source: RenderPipelineSource,
+    ) {
+        self.render_pipelines
+            .push((ArcRef::downgrade(pipeline), source));
+    }
+
+    pub fn register_compute_pipeline(
+        &mut self,
+        pipeline: &ArcRef<ComputePipelineInner>,
+        source: ComputePipelineSource,
+    ) {
+        self.compute_pipelines
+            .push((ArcRef::downgrade(pipeline), source));
+    }
+
+    /// Replaces the source a shader is re-created from, after it was hot reloaded.
+    #[cfg(feature = "hot-reload")]
+    pub fn update_graphics_shader_source(
+        &mut self,
+        shader: &ArcRef<GraphicsShaderInner>,
+        source: GraphicsShaderSource,
+    ) {
+        let entry = self
+            .graphics_shaders
+            .iter_mut()
+            .find(|(weak, _, _)| weak.upgrade().is_some_and(|s| s.ptr_eq(shader)));
+
+        if let Some((_, old_source, _)) = entry {
+            *old_source = source;
+        }
+    }
+
+    #[cfg(feature = "hot-reload")]
+    pub fn update_compute_shader_source(&mut self, shader: &ArcRef<ComputeShaderInner>, source: String) {
+        let entry = self
+            .compute_shaders
+            .iter_mut()
+            .find(|(weak, _, _)| weak.upgrade().is_some_and(|s| s.ptr_eq(shader)));
+
+        if let Some((_, old_source, _)) = entry {
+            *old_source = source;
+        }
+    }
+
+    /// Counts from the kept handles, the resources can't be borrowed while the graphics state is.
+    pub fn collect_stats(&self, stats: &mut GpuStats) {
+        for (_, _, handle) in self.textures.iter().filter(|(texture, _, _)| texture.is_alive()) {
+            stats.textures += 1;
+            stats.texture_bytes += texture_bytes(handle);
+        }
+
+        for (_, handle, allocation) in self.buffers.iter().filter(|(buffer, _, _)| buffer.is_alive()) {
+            stats.buffers += 1;
+            stats.buffer_bytes += allocation
+                .as_ref()
+                .map_or_else(|| handle.size(), |allocation| allocation.size());
+        }
+
+        stats.graphics_shaders = self
+            .graphics_shaders
+            .iter()
+            .filter(|(shader, _, _)| shader.is_alive())
+            .count() as u32;
+        stats.compute_shaders = self
+            .compute_shaders
+            .iter()
+            .filter(|(shader, _, _)| shader.is_alive())
+            .count() as u32;
+    }
+
+    /// Updates the kept handles after the resources were re-created on a new device, from
+    /// the copy of the registry they were re-created from.
+    fn refresh_handles(&mut self, recreated: &ResourceRegistry) {
+        for (texture, _, handle) in &mut self.textures {
+            if let Some((_, _, recreated)) =
+                recreated.textures.iter().find(|(other, _, _)| other.ptr_eq(texture))
+            {
+                *handle = recreated.clone();
+            }
+        }
+
+        for (buffer, handle, allocation) in &mut self.buffers {
+            if let Some((_, recreated, _)) =
+                recreated.buffers.iter().find(|(other, _, _)| other.ptr_eq(buffer))
+            {
+                *handle = recreated.clone();
+                *allocation = None;
+            }
+        }
+    }
+
+    /// Drops the entries of the resources dropped by the application, their handles are kept
+    /// alive by the deletion queue until the GPU is done with them.
+    pub fn prune(&mut self, deletion_queue: &mut DeletionQueue) {
+        self.textures.retain(|(texture, _, handle)| {
+            if !texture.is_alive() {
+                deletion_queue.defer(DeferredResource::Texture(handle.clone()));
+            }
+
+            texture.is_alive()
+        });
+
+        self.buffers.retain(|(buffer, handle, allocation)| {
+            if !buffer.is_alive() {
+                deletion_queue.defer(DeferredResource::Buffer(handle.clone()));
+
+                // The range goes back to its pool once the GPU is done with it
+                if let Some(allocation) = allocation {
+                    deletion_queue.defer(DeferredResource::PoolAllocation(Arc::clone(allocation)));
+                }
+            }
+
+            buffer.is_alive()
+        });
+
+        self.graphics_shaders.retain(|(shader, _, _)| shader.is_alive());
+        self.compute_shaders.retain(|(shader, _, _)| shader.is_alive());
+        self.render_pipelines.retain(|(pipeline, _)| pipeline.is_alive());
+        self.compute_pipelines.retain(|(pipeline, _)| pipeline.is_alive());
+    }
+}
+
+impl GPUInner {
+    /// Flags the device as lost when the driver reports it, eg. after a GPU reset or driver update.
+    pub(crate) fn install_device_lost_callback(
+        device: &wgpu::Device,
+        lost: &Arc<AtomicBool>,
+        diagnostics: &ArcMut<DiagnosticsRecorder>,
+    ) {
+        let lost = Arc::clone(lost);
+        let diagnostics = diagnostics.clone();
+
+        device.set_device_lost_callback(move |reason, message| {
+            // Destroyed is reported for devices we replaced or dropped ourselves
+            if reason == wgpu::DeviceLostReason::Destroyed {
+                return;
+            }
+
+            crate::error_log!("GPU device lost: {}", message);
+            diagnostics.lock().record_device_lost(&message);
+            lost.store(true, Ordering::SeqCst);
+        });
+    }
+
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::SeqCst)
+    }
+
+    /// Re-creates the device after it was lost, then the registered textures, buffers, shaders
+    /// and pipelines in place, so the handles held by the application stay valid.
+    ///
+    /// The contents of textures and buffers are lost. Pipelines are rebuilt last, from the
+    /// re-created shaders and the resources they were built with.
+    ///
+    /// Blocks until the new device is ready, on the web use [GPUInner::recover_device_async].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn recover_device(graphics: &ArcRef<GPUInner>) -> Result<(), String> {
+        futures::executor::block_on(Self::recover_device_async(graphics))
+    }
+
+    /// Asynchronous [GPUInner::recover_device], the graphics state isn't borrowed while the
+    /// new device is requested.
+    pub(crate) async fn recover_device_async(graphics: &ArcRef<GPUInner>) -> Result<(), String> {
+        let request = graphics.borrow().device_request()?;
+        let (adapter, device, queue) = request.request().await?;
+
+        let mut registry = {
+            let mut inner = graphics.borrow_mut();
+            inner.replace_device(adapter, device, queue);
+
+            // The dropped resources belonged to the lost device, nothing to wait for
+            let mut deletion_queue = DeletionQueue::default();
+            inner.resources.prune(&mut deletion_queue);
+            inner.resources.clone()
+        };
+
+        // Resources are borrowed before the graphics state, see the lock order on ArcRef
+        let device = graphics.borrow().device().clone();
+
+        for (texture, label, handle) in &mut registry.textures {
+            if let Some(texture) = texture.upgrade() {
+                let mut texture = texture.borrow_mut();
+                texture.recreate(&device, label);
+                *handle = texture.wgpu_texture.clone();
+            }
+        }
+
+        for (buffer, handle, _) in &mut registry.buffers {
+            if let Some(buffer) = buffer.upgrade() {
+                let mut buffer = buffer.borrow_mut();
+                buffer.recreate(&device);
+                *handle = buffer.buffer.clone();
+            }
+        }
+
+        graphics.borrow_mut().resources.refresh_handles(&registry);
+
+        for (shader, source, label) in &registry.graphics_shaders {
+            if let Some(shader) = shader.upgrade() {
+                let recreated = GraphicsShader::new(
+                    ArcRef::clone(graphics),
+                    source.clone(),
+                    label.as_deref(),
+                )?;
+
+                let dynamic_offsets = shader.borrow().dynamic_offsets.clone();
+                *shader.borrow_mut() = recreated.inner.borrow().clone();
+
+                if !dynamic_offsets.is_empty() {
+                    shader
+                        .borrow_mut()
+                        .set_dynamic_offsets(graphics.borrow().device(), dynamic_offsets);
+                }
+            }
+        }
+
+        for (shader, source, label) in &registry.compute_shaders {
+            if let Some(shader) = shader.upgrade() {
+                let recreated =
+                    ComputeShader::new(ArcRef::clone(graphics), source, label.as_deref())?;
+
+                let mut shader = shader.borrow_mut();
+                let recreated = recreated.inner.borrow();
+
+                shader.shader = recreated.shader.clone();
+                shader.reflection = recreated.reflection.clone();
+                shader.uniform_layouts = recreated.uniform_layouts.clone();
+                shader.bind_group_layouts = recreated.bind_group_layouts.clone();
+            }
+        }
+
+        for (pipeline, source) in &registry.render_pipelines {
+            if let Some(pipeline) = pipeline.upgrade() {
+                let rebuilt = RenderPipelineBuilder::from_source(ArcRef::clone(graphics), source)
+                    .build_inner()
+                    .map_err(|e| format!("Failed to rebuild render pipeline: {:?}", e))?;
+
+                *pipeline.borrow_mut() = rebuilt;
+            }
+        }
+
+        for (pipeline, source) in &registry.compute_pipelines {
+            if let Some(pipeline) = pipeline.upgrade() {
+                let rebuilt = ComputePipelineBuilder::from_source(ArcRef::clone(graphics), source)
+                    .build_inner()
+                    .map_err(|e| format!("Failed to rebuild compute pipeline: {:?}", e))?;
+
+                *pipeline.borrow_mut() = rebuilt;
+            }
+        }
+
+        crate::log!(
+            "GPU device restored, re-created {} textures, {} buffers, {} shaders and {} pipelines",
+            registry.textures.len(),
+            registry.buffers.len(),
+            registry.graphics_shaders.len() + registry.compute_shaders.len(),
+            registry.render_pipelines.len() + registry.compute_pipelines.len()
+        );
+
+        Ok(())
+    }
+
+    /// What is needed to request a device like the lost one.
+    fn device_request(&self) -> Result<DeviceRequest, String> {
+        let old_device = self.device.as_ref().ok_or("Missing device")?;
+
+        Ok(DeviceRequest {
+            instance: self.instance.clone(),
+            adapter: self.adapter.clone().ok_or("Missing adapter")?,
+            surface: self.surface.clone(),
+            descriptor: wgpu::DeviceDescriptor {
+                label: Some("Device"),
+                required_features: old_device.features(),
+                required_limits: old_device.limits(),
+                memory_hints: Default::default(),
+                trace: Default::default(),
+            },
+        })
+    }
+
+    /// Switches to a device created from [GPUInner::device_request].
+    fn replace_device(&mut self, adapter: wgpu::Adapter, device: wgpu::Device, queue: wgpu::Queue) {
+        ErrorHandler::install(&device, &self.error_handler, &self.diagnostics);
+        Self::install_device_lost_callback(&device, &self.device_lost, &self.diagnostics);
+        self.diagnostics.lock().set_device(&adapter, &device);
+
+        if let (Some(surface), Some(config)) = (&self.surface, &self.config)
+            && config.width > 0
+            && config.height > 0
+        {
+            surface.configure(&device, config);
+        }
+
+        self.device = Some(device);
+        self.queue = Some(queue);
+        self.adapter = Some(adapter);
+
+        // Everything cached was created from the old device
+        self.pipeline_cache = None;
+        self.pipeline_manager = Some(PipelineManager::new());
+        self.bind_group_manager = Some(BindGroupManager::new());
+        self.staging_buffer = Some(StagingBuffer::new());
+        self.msaa_cache = Some(MsaaTextureCache::new());
+        self.graph_pool.clear();
+        self.deletion_queue = ArcMut::new(DeletionQueue::default());
+        self.drawing_state = None;
+        self.yuv_state = None;
+
+        self.device_lost.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Request of a new device after a device loss, made without borrowing the graphics state.
+struct DeviceRequest {
+    instance: Option<wgpu::Instance>,
+    adapter: wgpu::Adapter,
+    surface: Option<Arc<wgpu::Surface<'static>>>,
+    descriptor: wgpu::DeviceDescriptor<'static>,
+}
+
+impl DeviceRequest {
+    async fn request(self) -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue), String> {
+        // The adapter usually survives a device loss (eg. a driver reset), otherwise
+        // look for a new one compatible with the surface.
+        match self.adapter.request_device(&self.descriptor).await {
+            Ok((device, queue)) => Ok((self.adapter, device, queue)),
+            Err(_err) => {
+                crate::dbg_log!("Failed to re-create device on the same adapter: {:?}", _err);
+
+                let instance = self.instance.as_ref().ok_or("Missing instance")?;
+                let adapter = instance
+                    .request_adapter(&wgpu::RequestAdapterOptions {
+                        power_preference: wgpu::PowerPreference::default(),
+                        compatible_surface: self.surface.as_deref(),
+                        force_fallback_adapter: false,
+                    })
+                    .await
+                    .map_err(|e| format!("Failed to request adapter: {:?}", e))?;
+
+                let (device, queue) = adapter
+                    .request_device(&self.descriptor)
+                    .await
+                    .map_err(|e| format!("Failed to request device: {:?}", e))?;
+
+                Ok((adapter, device, queue))
+            }
+        }
+    }
+}
+
+impl TextureInner {
+    fn recreate(&mut self, device: &wgpu::Device, label: &str) {
+        let old = &self.wgpu_texture;
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: old.size(),
+            mip_level_count: old.mip_level_count(),
+            sample_count: old.sample_count(),
+            dimension: old.dimension(),
+            format: old.format(),
+            usage: old.usage(),
+            view_formats: &self
+                .view_formats
+                .iter()
+                .map(|format| (*format).into())
+                .collect::<Vec<wgpu::TextureFormat>>(),
+        });
+
+        let view_label = format!("{} View", label);
+        self.wgpu_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some(view_label.as_str()),
+            ..Default::default()
+        });
+
+        if self.wgpu_depth_view.is_some() {
+            let depth_view_label = format!("{} Depth View", label);
+            self.wgpu_depth_view = Some(texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some(depth_view_label.as_str()),
+                aspect: wgpu::TextureAspect::DepthOnly,
+                ..Default::default()
+            }));
+        }
+
+        self.wgpu_texture = texture;
+        self.mapped = false;
+    }
+}
+
+impl BufferInner {
+    fn recreate(&mut self, device: &wgpu::Device) {
+        // Pooled buffers get a buffer of their own, the slab belonged to the lost device
+        let size = match self.allocation {
+            Some(_) => self.size,
+            None => self.buffer.size(),
+        };
+
+        self.buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: self.label.as_deref(),
+            size,
+            usage: self.buffer.usage(),
+            mapped_at_creation: false,
+        });
+
+        self.mapped = false;
+        self.offset = 0;
+        self.allocation = None;
+    }
+}
@@ -0,0 +1,310 @@
+use crate::utils::ArcRef;
+
+use super::{
+    super::{
+        GPUInner,
+        buffer::Buffer,
+        command::utils::BindGroupType,
+        texture::{Texture, TextureSampler},
+    },
+    graphics::GraphicsShader,
+    types::{BindGroupLayout, ShaderBindingInfo, ShaderBindingType, ShaderReflect},
+};
+
+/// A bind group created once and reused across frames, see [BindGroupBuilder].
+///
+/// Attaching it with [RenderPass::set_bind_group](crate::gpu::command::renderpass::RenderPass::set_bind_group)
+/// skips hashing the attachments and looking up the bind group cache on every draw.
+///
+/// The resources are captured when the bind group is built, recreating a texture or a buffer
+/// (eg. resizing it) requires building the bind group again. This also applies after a device
+/// loss.
+#[derive(Debug, Clone)]
+pub struct BindGroup {
+    pub(crate) group: u32,
+    pub(crate) bind_group: wgpu::BindGroup,
+}
+
+impl BindGroup {
+    /// The group index of the shader layout this bind group was built for.
+    pub fn group(&self) -> u32 {
+        self.group
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum BindGroupError {
+    /// The shader has no bindings in the group.
+    InvalidGroup(u32),
+    /// The shader has no binding at this index in the group.
+    AttachmentNotFound(u32),
+    /// The attachment doesn't match the binding type in the shader.
+    InvalidAttachmentType(u32, ShaderBindingType),
+    /// A binding of the group has no attachment.
+    AttachmentNotSet(u32),
+}
+
+impl std::fmt::Display for BindGroupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BindGroupError::InvalidGroup(group) => {
+                write!(f, "Shader does not have bind group {}", group)
+            }
+            BindGroupError::AttachmentNotFound(binding) => {
+                write!(f, "Shader does not have binding {}", binding)
+            }
+            BindGroupError::InvalidAttachmentType(binding, ty) => {
+                write!(f, "Attachment at binding {} does not match shader type {}", binding, ty)
+            }
+            BindGroupError::AttachmentNotSet(binding) => {
+                write!(f, "Binding {} has no attachment", binding)
+            }
+        }
+    }
+}
+
+/// Builds a [BindGroup] for one group of a shader layout.
+///
+/// # Example
+/// ```ignore
+/// let bind_group = gpu.create_bind_group(&shader, 0)
+///     .set_attachment_texture(0, Some(&texture))
+///     .set_attachment_sampler(1, Some(&TextureSampler::DEFAULT))
+///     .build()
+///     .expect("Failed to create bind group");
+///
+/// // Every frame
+/// render_pass.set_shader(Some(&shader));
+/// render_pass.set_bind_group(0, &bind_group);
+/// render_pass.draw(0..3, 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BindGroupBuilder {
+    gpu: ArcRef<GPUInner>,
+    group: u32,
+    layout: Option<BindGroupLayout>,
+    bindings: Vec<ShaderBindingInfo>,
+    attachments: Vec<(u32, BindGroupType)>,
+    label: Option<String>,
+}
+
+impl BindGroupBuilder {
+    pub(crate) fn new(gpu: ArcRef<GPUInner>, shader: &GraphicsShader, group: u32) -> Self {
+        let shader_inner = shader.inner.borrow();
+
+        let layout = shader_inner
+            .bind_group_layouts
+            .iter()
+            .find(|layout| layout.group == group)
+            .cloned();
+
+        let mut bindings: Vec<ShaderBindingInfo> = Vec::new();
+        for reflect in &shader_inner.reflection {
+            let reflect_bindings = match reflect {
+                ShaderReflect::Vertex { bindings, .. }
+                | ShaderReflect::Fragment { bindings, .. }
+                | ShaderReflect::VertexFragment { bindings, .. } => bindings,
+                _ => continue,
+            };
+
+            // Split shaders may declare the same binding in both stages
+            for binding in reflect_bindings.iter().filter(|b| b.group == group) {
+                if !bindings.iter().any(|b| b.binding == binding.binding) {
+                    bindings.push(binding.clone());
+                }
+            }
+        }
+
+        Self {
+            gpu,
+            group,
+            layout,
+            bindings,
+            attachments: Vec::new(),
+            label: None,
+        }
+    }
+
+    #[inline]
+    pub fn set_attachment_sampler(mut self, binding: u32, sampler: Option<&TextureSampler>) -> Self {
+        match sampler {
+            Some(sampler) => {
+                let sampler = sampler.make_wgpu(self.gpu.borrow().device());
+                self.insert_or_replace_attachment(binding, BindGroupType::Sampler(sampler));
+            }
+            None => self.remove_attachment(binding),
+        }
+
+        self
+    }
+
+    #[inline]
+    pub fn set_attachment_texture(mut self, binding: u32, texture: Option<&Texture>) -> Self {
+        match texture {
+            Some(texture) => {
+                let view = texture.inner.borrow().sample_view().clone();
+                self.insert_or_replace_attachment(binding, BindGroupType::Texture(view));
+            }
+            None => self.remove_attachment(binding),
+        }
+
+        self
+    }
+
+    /// Sets the texture at `binding` and its default sampler, see
+    /// [TextureBuilder::set_sampler](crate::gpu::texture::TextureBuilder::set_sampler), at
+    /// `sampler_binding`.
+    #[inline]
+    pub fn set_attachment_texture_sampled(
+        self,
+        binding: u32,
+        sampler_binding: u32,
+        texture: Option<&Texture>,
+    ) -> Self {
+        let sampler = texture.map(|texture| texture.sampler());
+
+        self.set_attachment_texture(binding, texture)
+            .set_attachment_sampler(sampler_binding, sampler.as_ref())
+    }
+
+    #[inline]
+    pub fn set_attachment_texture_storage(mut self, binding: u32, texture: Option<&Texture>) -> Self {
+        match texture {
+            Some(texture) => {
+                let inner = texture.inner.borrow();
+
+                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+                inner.validate_storage_binding();
+
+                let attachment = BindGroupType::TextureStorage(inner.wgpu_view.clone(), inner.format);
+                drop(inner);
+
+                self.insert_or_replace_attachment(binding, attachment);
+            }
+            None => self.remove_attachment(binding),
+        }
+
+        self
+    }
+
+    #[inline]
+    pub fn set_attachment_uniform(mut self, binding: u32, buffer: Option<&Buffer>) -> Self {
+        match buffer {
+            Some(buffer) => {
+                let buffer = buffer.inner.borrow().range();
+                self.insert_or_replace_attachment(binding, BindGroupType::Uniform(buffer));
+            }
+            None => self.remove_attachment(binding),
+        }
+
+        self
+    }
+
+    #[inline]
+    pub fn set_attachment_storage(mut self, binding: u32, buffer: Option<&Buffer>) -> Self {
+        match buffer {
+            Some(buffer) => {
+                let buffer = buffer.inner.borrow().range();
+                self.insert_or_replace_attachment(binding, BindGroupType::Storage(buffer));
+            }
+            None => self.remove_attachment(binding),
+        }
+
+        self
+    }
+
+    /// Sets the debug label of the bind group, shown in graphics debuggers.
+    pub fn set_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    fn remove_attachment(&mut self, binding: u32) {
+        self.attachments.retain(|(b, _)| *b != binding);
+    }
+
+    fn insert_or_replace_attachment(&mut self, binding: u32, attachment: BindGroupType) {
+        match self.attachments.iter_mut().find(|(b, _)| *b == binding) {
+            Some(existing) => existing.1 = attachment,
+            None => self.attachments.push((binding, attachment)),
+        }
+    }
+
+    pub fn build(mut self) -> Result<BindGroup, BindGroupError> {
+        let layout = self
+            .layout
+            .as_ref()
+            .ok_or(BindGroupError::InvalidGroup(self.group))?;
+
+        for (binding, attachment) in &self.attachments {
+            let shader_binding = self
+                .bindings
+                .iter()
+                .find(|b| b.binding == *binding)
+                .ok_or(BindGroupError::AttachmentNotFound(*binding))?;
+
+            if !match shader_binding.ty {
+                ShaderBindingType::UniformBuffer(_) | ShaderBindingType::PushConstant(_) => {
+                    matches!(attachment, BindGroupType::Uniform(_))
+                }
+                ShaderBindingType::StorageBuffer(_, _) => {
+                    matches!(attachment, BindGroupType::Storage(_))
+                }
+                ShaderBindingType::StorageTexture(_, format) => {
+                    matches!(attachment, BindGroupType::TextureStorage(_, f) if *f == format)
+                }
+                ShaderBindingType::Sampler(_) => matches!(attachment, BindGroupType::Sampler(_)),
+                ShaderBindingType::Texture(_) | ShaderBindingType::DepthTexture(_) => {
+                    matches!(attachment, BindGroupType::Texture(_))
+                }
+            } {
+                return Err(BindGroupError::InvalidAttachmentType(
+                    *binding,
+                    shader_binding.ty,
+                ));
+            }
+        }
+
+        if let Some(missing) = layout
+            .bindings
+            .iter()
+            .find(|binding| !self.attachments.iter().any(|(b, _)| b == *binding))
+        {
+            return Err(BindGroupError::AttachmentNotSet(*missing));
+        }
+
+        // Entries must be in binding order
+        self.attachments.sort_by_key(|(binding, _)| *binding);
+
+        let entries = self
+            .attachments
+            .iter()
+            .map(|(binding, attachment)| wgpu::BindGroupEntry {
+                binding: *binding,
+                resource: match attachment {
+                    BindGroupType::Uniform(buffer) | BindGroupType::Storage(buffer) => {
+                        wgpu::BindingResource::Buffer(buffer.binding(layout.dynamic_binding_size(*binding)))
+                    }
+                    BindGroupType::Texture(view) | BindGroupType::TextureStorage(view, _) => {
+                        wgpu::BindingResource::TextureView(view)
+                    }
+                    BindGroupType::Sampler(sampler) => wgpu::BindingResource::Sampler(sampler),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let gpu_inner = self.gpu.borrow();
+        let bind_group = gpu_inner
+            .device()
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: self.label.as_deref(),
+                layout: &layout.layout,
+                entries: &entries,
+            });
+
+        Ok(BindGroup {
+            group: self.group,
+            bind_group,
+        })
+    }
+}
@@ -1,9 +1,13 @@
 use std::collections::HashMap;
 use super::BindGroupLayout;
+use super::super::cache::{self, CacheEntries, CachePolicy, CacheStats};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BindGroupManager {
-    pub bind_groups: HashMap<usize, (Vec<(u32, wgpu::BindGroup)>, usize)>,
+    pub bind_groups: CacheEntries<Vec<(u32, wgpu::BindGroup)>>,
+
+    pub policy: CachePolicy,
+    pub stats: CacheStats,
 }
 
 const BIND_GROUP_LIFETIME: usize = 100;
@@ -17,18 +21,22 @@ impl BindGroupManager {
     pub fn new() -> Self {
         Self {
             bind_groups: HashMap::new(),
+
+            policy: CachePolicy::new().set_max_age_frames(BIND_GROUP_LIFETIME),
+            stats: CacheStats::default(),
         }
     }
 
     pub fn get(&mut self, key: usize) -> Option<Vec<(u32, wgpu::BindGroup)>> {
-        if let Some((bind_groups, lifetime)) = self.bind_groups.get_mut(&key) {
+        let bind_groups = self.bind_groups.get_mut(&key).map(|(bind_groups, lifetime)| {
             // reset lifetime
             *lifetime = 0;
 
-            Some(bind_groups.clone())
-        } else {
-            None
-        }
+            bind_groups.clone()
+        });
+
+        self.stats.record_lookup(bind_groups.is_some());
+        bind_groups
     }
 
     pub fn create(
@@ -50,16 +58,23 @@ impl BindGroupManager {
         }
 
         self.bind_groups.insert(key, (bind_groups.clone(), 0));
+        self.stats.evictions += cache::trim_entries(&mut self.bind_groups, self.policy.max_entries);
 
         bind_groups
     }
 
     pub fn cycle(&mut self) {
-        self.bind_groups
-            .retain(|_, value| value.1 < BIND_GROUP_LIFETIME);
+        self.stats.evictions += cache::cycle_entries(&mut self.bind_groups, &self.policy);
+    }
 
-        for (_, value) in self.bind_groups.iter_mut() {
-            value.1 += 1;
-        }
+    /// Applies a new policy, evicting the entries over its limits right away.
+    pub fn set_policy(&mut self, policy: CachePolicy) {
+        self.policy = policy;
+        self.stats.evictions += cache::apply_policy(&mut self.bind_groups, &self.policy);
+    }
+
+    pub fn clear(&mut self) {
+        self.stats.evictions += self.bind_groups.len() as u64;
+        self.bind_groups.clear();
     }
 }
@@ -2,18 +2,20 @@ use std::collections::HashMap;
 
 use wgpu::{BindingType, SamplerBindingType, naga::front::wgsl};
 
-use crate::utils::ArcRef;
+use crate::utils::{ArcRef, LockRank};
 use super::{
     super::GPUInner,
     types::{
         ShaderReflect, BindGroupLayout,
         ShaderBindingType, StorageAccess,
-    }
+    },
+    uniform::UniformLayout,
 };
 
 pub struct ComputeShaderBuilder {
     pub(crate) graphics: ArcRef<GPUInner>,
     pub(crate) wgls_data: String,
+    pub(crate) label: Option<String>,
 }
 
 impl ComputeShaderBuilder {
@@ -21,6 +23,7 @@ impl ComputeShaderBuilder {
         Self {
             graphics,
             wgls_data: String::new(),
+            label: None,
         }
     }
 
@@ -39,16 +42,36 @@ impl ComputeShaderBuilder {
         self
     }
 
+    /// Sets the debug label of the shader module, shown in graphics debuggers.
+    pub fn set_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
     pub fn build(self) -> Result<ComputeShader, String> {
-        ComputeShader::new(self.graphics, &self.wgls_data)
+        let shader = ComputeShader::new(
+            ArcRef::clone(&self.graphics),
+            &self.wgls_data,
+            self.label.as_deref(),
+        )?;
+
+        self.graphics.borrow_mut().resources.register_compute_shader(
+            &shader.inner,
+            self.wgls_data,
+            self.label,
+        );
+
+        Ok(shader)
     }
 }
 
 pub(crate) struct ComputeShaderInner {
     pub shader: wgpu::ShaderModule,
     pub reflection: ShaderReflect,
+    pub uniform_layouts: Vec<UniformLayout>,
 
     pub bind_group_layouts: Vec<BindGroupLayout>,
+    pub push_constant_ranges: Vec<wgpu::PushConstantRange>,
 }
 
 #[allow(unused)]
@@ -59,7 +82,11 @@ pub struct ComputeShader {
 }
 
 impl ComputeShader {
-    pub(crate) fn new(graphics: ArcRef<GPUInner>, wgls_data: &str) -> Result<Self, String> {
+    pub(crate) fn new(
+        graphics: ArcRef<GPUInner>,
+        wgls_data: &str,
+        label: Option<&str>,
+    ) -> Result<Self, String> {
         if graphics.borrow().is_invalid {
             panic!("Graphics context is invalid");
         }
@@ -70,6 +97,7 @@ impl ComputeShader {
         }
 
         let module = module.unwrap();
+        let uniform_layouts = super::uniform::parse_layouts(&module);
         let reflect = super::reflection::parse(module);
 
         if reflect.is_err() {
@@ -81,22 +109,30 @@ impl ComputeShader {
         let graphics_ref = graphics.borrow();
         let device_ref = graphics_ref.device();
 
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        super::reflection::validate_storage_textures(&graphics_ref, &reflect)?;
+
         let shader = device_ref.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: None,
+            label,
             source: wgpu::ShaderSource::Wgsl(wgls_data.into()),
         });
 
         let bind_group_layouts = Self::make_group_layout(device_ref, &[reflect.clone()]);
+        let push_constant_ranges = super::reflection::make_push_constant_ranges(
+            &super::reflection::reflect_push_constants(&reflect),
+        );
 
         let inner = ComputeShaderInner {
             shader,
             reflection: reflect,
+            uniform_layouts,
             bind_group_layouts,
+            push_constant_ranges,
         };
 
         Ok(Self {
             graphics: ArcRef::clone(&graphics),
-            inner: ArcRef::new(inner),
+            inner: ArcRef::new_ranked(inner, LockRank::Resource),
         })
     }
 
@@ -116,6 +152,11 @@ impl ComputeShader {
                 view_dimension: wgpu::TextureViewDimension::D2,
                 multisampled,
             },
+            ShaderBindingType::DepthTexture(multisampled) => BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Depth,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled,
+            },
             ShaderBindingType::Sampler(comparison) => BindingType::Sampler(if comparison {
                 SamplerBindingType::Comparison
             } else {
@@ -133,21 +174,9 @@ impl ComputeShader {
                     wgpu::BufferSize::new(size as u64)
                 },
             },
-            ShaderBindingType::StorageTexture(access) => BindingType::StorageTexture {
-                access: if access.contains(StorageAccess::READ)
-                    && access.contains(StorageAccess::WRITE)
-                {
-                    wgpu::StorageTextureAccess::ReadWrite
-                } else if access.contains(StorageAccess::READ) {
-                    wgpu::StorageTextureAccess::ReadOnly
-                } else if access.contains(StorageAccess::WRITE) {
-                    wgpu::StorageTextureAccess::WriteOnly
-                } else if access.contains(StorageAccess::ATOMIC) {
-                    wgpu::StorageTextureAccess::Atomic
-                } else {
-                    panic!("Invalid storage texture access")
-                },
-                format: wgpu::TextureFormat::Rgba8Unorm,
+            ShaderBindingType::StorageTexture(access, format) => BindingType::StorageTexture {
+                access: access.texture_access(),
+                format: format.into(),
                 view_dimension: wgpu::TextureViewDimension::D2,
             },
             _ => unreachable!(),
@@ -160,10 +189,14 @@ impl ComputeShader {
     ) -> Vec<BindGroupLayout> {
         let mut layouts: HashMap<u32, Vec<wgpu::BindGroupLayoutEntry>> = HashMap::new();
 
+        // Push constants are declared in the pipeline layout instead, see push_constant_ranges
         for reflect in reflects {
             match reflect {
                 ShaderReflect::Compute { bindings, .. } => {
-                    for binding in bindings.iter() {
+                    for binding in bindings
+                        .iter()
+                        .filter(|b| !matches!(b.ty, ShaderBindingType::PushConstant(_)))
+                    {
                         let ty = Self::create_layout_ty(binding.ty.clone());
 
                         // Push new layout entry
@@ -209,6 +242,7 @@ impl ComputeShader {
                 BindGroupLayout {
                     group,
                     bindings: layout.iter().map(|entry| entry.binding).collect(),
+                    dynamic_bindings: Vec::new(),
                     layout: bind_group_layout,
                 }
             })
@@ -229,4 +263,28 @@ impl ComputeShader {
             _ => None,
         }
     }
+
+    /// Returns the group and binding of a resource declared by the shader, whatever its type.
+    pub fn get_binding_location(&self, name: &str) -> Option<(u32, u32)> {
+        let inner = self.inner.borrow();
+        match &inner.reflection {
+            ShaderReflect::Compute { bindings, .. } => bindings
+                .iter()
+                .find(|binding| {
+                    binding.name == name && !matches!(binding.ty, ShaderBindingType::PushConstant(_))
+                })
+                .map(|binding| (binding.group, binding.binding)),
+            _ => None,
+        }
+    }
+
+    /// Returns the memory layout of a uniform buffer, to check or pack a Rust struct against it.
+    pub fn get_uniform_layout(&self, name: &str) -> Option<UniformLayout> {
+        self.inner
+            .borrow()
+            .uniform_layouts
+            .iter()
+            .find(|layout| layout.name == name)
+            .cloned()
+    }
 }
@@ -4,7 +4,7 @@ use std::{borrow::Cow, collections::HashMap, hash::Hash};
 use wgpu::{BindingType, SamplerBindingType, ShaderRuntimeChecks, ShaderStages, naga::front::wgsl};
 
 use crate::{
-    utils::ArcRef,
+    utils::{ArcRef, LockRank},
 };
 
 use super::{
@@ -14,11 +14,16 @@ use super::{
         ShaderFrontFace, ShaderPollygonMode, 
         ShaderReflect, ShaderTopology, 
         StorageAccess, VertexInputType,
-        VertexInputReflection,
+        VertexInputReflection, VertexStepMode,
+    },
+    uniform::UniformLayout,
+    super::{
+        GPUInner,
+        pipeline::manager::VertexAttributeLayout,
     },
-    super::GPUInner,
 };
 
+#[derive(Clone, Debug)]
 pub(crate) enum GraphicsShaderSource {
     None,
     Source(String),
@@ -34,6 +39,8 @@ pub(crate) enum GraphicsShaderSource {
 pub struct GraphicsShaderBuilder {
     pub(crate) graphics: ArcRef<GPUInner>,
     pub(crate) source: GraphicsShaderSource,
+    pub(crate) label: Option<String>,
+    pub(crate) dynamic_offsets: Vec<(u32, u32)>,
 }
 
 impl GraphicsShaderBuilder {
@@ -41,6 +48,8 @@ impl GraphicsShaderBuilder {
         Self {
             graphics,
             source: GraphicsShaderSource::None,
+            label: None,
+            dynamic_offsets: Vec::new(),
         }
     }
 
@@ -195,11 +204,92 @@ impl GraphicsShaderBuilder {
         self
     }
 
+    /// Sets the debug label of the shader modules, shown in graphics debuggers.
+    pub fn set_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Declares the uniform or storage buffer at `group` and `binding` as using dynamic offsets.
+    ///
+    /// The buffer is then bound with `RenderPass::set_attachment_uniform_dynamic` or
+    /// `RenderPass::set_attachment_storage_dynamic`, so many objects can read their data from
+    /// one large buffer at different offsets while sharing the same bind group. The binding must
+    /// have a fixed size, runtime sized arrays are not supported.
+    pub fn set_dynamic_offset(mut self, group: u32, binding: u32) -> Self {
+        if !self.dynamic_offsets.contains(&(group, binding)) {
+            self.dynamic_offsets.push((group, binding));
+        }
+
+        self
+    }
+
     pub fn build(self) -> Result<GraphicsShader, String> {
-        GraphicsShader::new(self.graphics, self.source)
+        let shader = GraphicsShader::new(
+            ArcRef::clone(&self.graphics),
+            self.source.clone(),
+            self.label.as_deref(),
+        )?;
+
+        if !self.dynamic_offsets.is_empty() {
+            let mut inner = shader.inner.borrow_mut();
+
+            for (group, binding) in &self.dynamic_offsets {
+                let binding_ty = inner.reflection.iter().find_map(|reflect| {
+                    let bindings = match reflect {
+                        ShaderReflect::Vertex { bindings, .. }
+                        | ShaderReflect::Fragment { bindings, .. }
+                        | ShaderReflect::VertexFragment { bindings, .. } => bindings,
+                        _ => return None,
+                    };
+
+                    bindings
+                        .iter()
+                        .find(|b| b.group == *group && b.binding == *binding)
+                        .map(|b| b.ty)
+                });
+
+                match binding_ty {
+                    Some(ShaderBindingType::UniformBuffer(size))
+                    | Some(ShaderBindingType::StorageBuffer(size, _))
+                        if size != u32::MAX => {}
+                    Some(ty) => {
+                        return Err(format!(
+                            "Binding group: {} binding: {} type: {} cannot use dynamic offsets",
+                            group, binding, ty
+                        ));
+                    }
+                    None => {
+                        return Err(format!(
+                            "Shader does not have binding group: {} binding: {}",
+                            group, binding
+                        ));
+                    }
+                }
+            }
+
+            let graphics = self.graphics.borrow();
+            inner.set_dynamic_offsets(graphics.device(), self.dynamic_offsets.clone());
+        }
+
+        self.graphics.borrow_mut().resources.register_graphics_shader(
+            &shader.inner,
+            self.source,
+            self.label,
+        );
+
+        Ok(shader)
     }
 }
 
+/// Module, reflection, uniform layouts and push constant blocks of a WGSL source.
+type SourceShader = (
+    wgpu::ShaderModule,
+    ShaderReflect,
+    Vec<UniformLayout>,
+    Vec<(ShaderStages, u32)>,
+);
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum GraphicsShaderType {
     GraphicsSingle {
@@ -232,10 +322,22 @@ impl Hash for GraphicsShaderType {
 pub(crate) struct GraphicsShaderInner {
     pub ty: GraphicsShaderType,
     pub reflection: Vec<ShaderReflect>,
+    pub uniform_layouts: Vec<UniformLayout>,
+    pub push_constant_ranges: Vec<wgpu::PushConstantRange>,
+    pub dynamic_offsets: Vec<(u32, u32)>,
 
     pub bind_group_layouts: Vec<BindGroupLayout>,
 }
 
+impl GraphicsShaderInner {
+    /// Recreates the bind group layouts with the given bindings using dynamic offsets.
+    pub fn set_dynamic_offsets(&mut self, device: &wgpu::Device, dynamic_offsets: Vec<(u32, u32)>) {
+        self.bind_group_layouts =
+            GraphicsShader::make_group_layout(device, &self.reflection, &dynamic_offsets);
+        self.dynamic_offsets = dynamic_offsets;
+    }
+}
+
 impl PartialEq for GraphicsShaderInner {
     fn eq(&self, other: &Self) -> bool {
         let ty_equal = self.ty == other.ty;
@@ -267,8 +369,8 @@ pub(crate) struct VertexInputDescription {
     pub cull_mode: Option<ShaderCullMode>,
     pub polygon_mode: ShaderPollygonMode,
     pub front_face: ShaderFrontFace,
-    pub stride: wgpu::BufferAddress,
-    pub attributes: Vec<wgpu::VertexAttribute>,
+    /// Layout of each vertex buffer slot, one per struct argument of the vertex entry point.
+    pub buffers: Vec<VertexAttributeLayout>,
 }
 
 impl PartialEq for VertexInputDescription {
@@ -278,8 +380,7 @@ impl PartialEq for VertexInputDescription {
             && self.cull_mode == other.cull_mode
             && self.polygon_mode == other.polygon_mode
             && self.front_face == other.front_face
-            && self.stride == other.stride
-            && self.attributes == other.attributes
+            && self.buffers == other.buffers
     }
 }
 
@@ -296,6 +397,7 @@ impl GraphicsShader {
     pub(crate) fn new(
         graphics: ArcRef<GPUInner>,
         wgls_data: GraphicsShaderSource,
+        label: Option<&str>,
     ) -> Result<Self, String> {
         let graphics_ref = graphics.borrow();
         let device_ref = graphics_ref.device.as_ref().ok_or("Missing device")?;
@@ -313,23 +415,29 @@ impl GraphicsShader {
         }
 
         fn create_input_desc(reflection: &ShaderReflect) -> Result<VertexInputDescription, String> {
-            let (vertex_input, stride) = match reflection {
+            let vertex_inputs = match reflection {
                 ShaderReflect::Vertex { input, .. }
                 | ShaderReflect::VertexFragment {
                     vertex_input: input,
                     ..
-                } => {
-                    let input = input.as_ref().ok_or("Missing vertex input")?;
-                    (input, input.stride as wgpu::BufferAddress)
-                }
+                } => input,
                 _ => return Err("Invalid shader type for vertex input".to_string()),
             };
 
-            let attributes = create_vertex_input_attrib(vertex_input);
+            // No vertex input means the shader pulls its vertices itself (eg. from a storage buffer
+            // with `@builtin(vertex_index)`), so the pipeline doesn't need any vertex buffer.
+            let buffers = vertex_inputs
+                .iter()
+                .map(|input| VertexAttributeLayout {
+                    stride: input.stride as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: create_vertex_input_attrib(input),
+                })
+                .collect();
+
             Ok(VertexInputDescription {
                 index: Some(IndexBufferSize::U16),
-                stride,
-                attributes,
+                buffers,
                 topology: ShaderTopology::TriangleList,
                 cull_mode: None,
                 polygon_mode: ShaderPollygonMode::Fill,
@@ -340,21 +448,27 @@ impl GraphicsShader {
         fn build_single_shader(
             device: &wgpu::Device,
             source: &str,
-        ) -> Result<(wgpu::ShaderModule, ShaderReflect), String> {
+            label: Option<&str>,
+        ) -> Result<SourceShader, String> {
             let module = wgsl::parse_str(source).map_err(|e| format!("Parse error: {e:?}"))?;
+            let uniform_layouts = super::uniform::parse_layouts(&module);
+            let push_constants = super::reflection::parse_push_constants(&module);
             let reflection = super::reflection::parse(module).map_err(|e| format!("Reflect error: {e:?}"))?;
             Ok((
                 device.create_shader_module(wgpu::ShaderModuleDescriptor {
-                    label: None,
+                    label,
                     source: wgpu::ShaderSource::Wgsl(source.into()),
                 }),
                 reflection,
+                uniform_layouts,
+                push_constants,
             ))
         }
 
         fn build_binary_shader(
             device: &wgpu::Device,
             binary: &[u8],
+            label: Option<&str>,
         ) -> Result<(wgpu::ShaderModule, ShaderReflect), String> {
             let binary_shader = super::reflection::load_binary_shader(binary)
                 .map_err(|e| format!("Binary load error: {e:?}"))?;
@@ -364,7 +478,7 @@ impl GraphicsShader {
                 // This used for fast shader loading, so we assume that the binary shader is valid.
                 unsafe {
                     let desc = wgpu::ShaderModuleDescriptor {
-                        label: None,
+                        label,
                         source: wgpu::ShaderSource::SpirV(spirv_u32),
                     };
 
@@ -379,23 +493,29 @@ impl GraphicsShader {
             ))
         }
 
-        match wgls_data {
+        let shader = match wgls_data {
             GraphicsShaderSource::None => Err("No shader source provided".to_string()),
 
             GraphicsShaderSource::Source(source) => {
-                let (module, reflection) = build_single_shader(device_ref, &source)?;
+                let (module, reflection, uniform_layouts, push_constants) =
+                    build_single_shader(device_ref, &source, label)?;
                 match reflection {
                     ShaderReflect::VertexFragment { .. } => {
-                        let layout = Self::make_group_layout(device_ref, &[reflection.clone()]);
+                        let layout = Self::make_group_layout(device_ref, std::slice::from_ref(&reflection), &[]);
                         let input_desc = create_input_desc(&reflection)?;
                         Ok(Self {
                             graphics: ArcRef::clone(&graphics),
-                            inner: ArcRef::new(GraphicsShaderInner {
+                            inner: ArcRef::new_ranked(GraphicsShaderInner {
                                 ty: GraphicsShaderType::GraphicsSingle { module },
                                 reflection: vec![reflection],
+                                uniform_layouts,
+                                push_constant_ranges: super::reflection::make_push_constant_ranges(
+                                    &push_constants,
+                                ),
+                                dynamic_offsets: Vec::new(),
                                 bind_group_layouts: layout,
-                            }),
-                            attrib: ArcRef::new(input_desc),
+                            }, LockRank::Resource),
+                            attrib: ArcRef::new_ranked(input_desc, LockRank::Resource),
                         })
                     }
                     _ => Err("Shader source is not VertexFragment shader!".to_string()),
@@ -403,28 +523,47 @@ impl GraphicsShader {
             }
 
             GraphicsShaderSource::SplitSource(vertex_src, fragment_src) => {
-                let (vertex_module, vertex_reflect) = build_single_shader(device_ref, &vertex_src)?;
-                let (fragment_module, fragment_reflect) =
-                    build_single_shader(device_ref, &fragment_src)?;
+                let (vertex_module, vertex_reflect, mut uniform_layouts, mut push_constants) =
+                    build_single_shader(device_ref, &vertex_src, label)?;
+                let (fragment_module, fragment_reflect, fragment_layouts, fragment_push_constants) =
+                    build_single_shader(device_ref, &fragment_src, label)?;
+
+                push_constants.extend(fragment_push_constants);
+
+                // Both stages may declare the same uniform, keep it once
+                for layout in fragment_layouts {
+                    if !uniform_layouts
+                        .iter()
+                        .any(|l| l.group == layout.group && l.binding == layout.binding)
+                    {
+                        uniform_layouts.push(layout);
+                    }
+                }
 
                 match (&vertex_reflect, &fragment_reflect) {
                     (ShaderReflect::Vertex { .. }, ShaderReflect::Fragment { .. }) => {
                         let layout = Self::make_group_layout(
                             device_ref,
                             &[vertex_reflect.clone(), fragment_reflect.clone()],
+                            &[],
                         );
                         let input_desc = create_input_desc(&vertex_reflect)?;
                         Ok(Self {
                             graphics: ArcRef::clone(&graphics),
-                            inner: ArcRef::new(GraphicsShaderInner {
+                            inner: ArcRef::new_ranked(GraphicsShaderInner {
                                 ty: GraphicsShaderType::GraphicsSplit {
                                     vertex_module,
                                     fragment_module,
                                 },
                                 reflection: vec![vertex_reflect, fragment_reflect],
+                                uniform_layouts,
+                                push_constant_ranges: super::reflection::make_push_constant_ranges(
+                                    &push_constants,
+                                ),
+                                dynamic_offsets: Vec::new(),
                                 bind_group_layouts: layout,
-                            }),
-                            attrib: ArcRef::new(input_desc),
+                            }, LockRank::Resource),
+                            attrib: ArcRef::new_ranked(input_desc, LockRank::Resource),
                         })
                     }
                     _ => Err("Invalid shader pair for SplitSource".to_string()),
@@ -432,19 +571,24 @@ impl GraphicsShader {
             }
 
             GraphicsShaderSource::BinarySource(binary) => {
-                let (module, reflection) = build_binary_shader(device_ref, &binary)?;
+                let (module, reflection) = build_binary_shader(device_ref, &binary, label)?;
                 match reflection {
                     ShaderReflect::VertexFragment { .. } => {
-                        let layout = Self::make_group_layout(device_ref, &[reflection.clone()]);
+                        let layout = Self::make_group_layout(device_ref, std::slice::from_ref(&reflection), &[]);
                         let input_desc = create_input_desc(&reflection)?;
                         Ok(Self {
                             graphics: ArcRef::clone(&graphics),
-                            inner: ArcRef::new(GraphicsShaderInner {
+                            inner: ArcRef::new_ranked(GraphicsShaderInner {
+                                push_constant_ranges: super::reflection::make_push_constant_ranges(
+                                    &super::reflection::reflect_push_constants(&reflection),
+                                ),
                                 ty: GraphicsShaderType::GraphicsSingle { module },
                                 reflection: vec![reflection],
+                                uniform_layouts: Vec::new(),
+                                dynamic_offsets: Vec::new(),
                                 bind_group_layouts: layout,
-                            }),
-                            attrib: ArcRef::new(input_desc),
+                            }, LockRank::Resource),
+                            attrib: ArcRef::new_ranked(input_desc, LockRank::Resource),
                         })
                     }
                     _ => Err("Binary shader is not VertexFragment shader!".to_string()),
@@ -452,39 +596,57 @@ impl GraphicsShader {
             }
 
             GraphicsShaderSource::BinarySplitSource(vertex_bin, fragment_bin) => {
-                let (vertex_module, vertex_reflect) = build_binary_shader(device_ref, &vertex_bin)?;
+                let (vertex_module, vertex_reflect) = build_binary_shader(device_ref, &vertex_bin, label)?;
                 let (fragment_module, fragment_reflect) =
-                    build_binary_shader(device_ref, &fragment_bin)?;
+                    build_binary_shader(device_ref, &fragment_bin, label)?;
 
                 match (&vertex_reflect, &fragment_reflect) {
                     (ShaderReflect::Vertex { .. }, ShaderReflect::Fragment { .. }) => {
                         let layout = Self::make_group_layout(
                             device_ref,
                             &[vertex_reflect.clone(), fragment_reflect.clone()],
+                            &[],
                         );
                         let input_desc = create_input_desc(&vertex_reflect)?;
                         Ok(Self {
                             graphics: ArcRef::clone(&graphics),
-                            inner: ArcRef::new(GraphicsShaderInner {
+                            inner: ArcRef::new_ranked(GraphicsShaderInner {
                                 ty: GraphicsShaderType::GraphicsSplit {
                                     vertex_module,
                                     fragment_module,
                                 },
+                                push_constant_ranges: super::reflection::make_push_constant_ranges(
+                                    &[
+                                        super::reflection::reflect_push_constants(&vertex_reflect),
+                                        super::reflection::reflect_push_constants(&fragment_reflect),
+                                    ]
+                                    .concat(),
+                                ),
                                 reflection: vec![vertex_reflect, fragment_reflect],
+                                uniform_layouts: Vec::new(),
+                                dynamic_offsets: Vec::new(),
                                 bind_group_layouts: layout,
-                            }),
-                            attrib: ArcRef::new(input_desc),
+                            }, LockRank::Resource),
+                            attrib: ArcRef::new_ranked(input_desc, LockRank::Resource),
                         })
                     }
                     _ => Err("Invalid binary shader pair for BinarySplitSource".to_string()),
                 }
             }
+        }?;
+
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        for reflect in shader.inner.borrow().reflection.iter() {
+            super::reflection::validate_storage_textures(&graphics_ref, reflect)?;
         }
+
+        Ok(shader)
     }
 
     fn make_group_layout(
         device: &wgpu::Device,
         reflects: &[ShaderReflect],
+        dynamic_offsets: &[(u32, u32)],
     ) -> Vec<BindGroupLayout> {
         let mut layouts: HashMap<u32, Vec<wgpu::BindGroupLayoutEntry>> = HashMap::new();
 
@@ -517,6 +679,11 @@ impl GraphicsShader {
                     view_dimension: wgpu::TextureViewDimension::D2,
                     multisampled,
                 },
+                ShaderBindingType::DepthTexture(multisampled) => BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled,
+                },
                 ShaderBindingType::Sampler(comparison) => BindingType::Sampler(if comparison {
                     SamplerBindingType::Comparison
                 } else {
@@ -534,31 +701,23 @@ impl GraphicsShader {
                         wgpu::BufferSize::new(size as u64)
                     },
                 },
-                ShaderBindingType::StorageTexture(access) => BindingType::StorageTexture {
-                    access: if access.contains(StorageAccess::READ)
-                        && access.contains(StorageAccess::WRITE)
-                    {
-                        wgpu::StorageTextureAccess::ReadWrite
-                    } else if access.contains(StorageAccess::READ) {
-                        wgpu::StorageTextureAccess::ReadOnly
-                    } else if access.contains(StorageAccess::WRITE) {
-                        wgpu::StorageTextureAccess::WriteOnly
-                    } else if access.contains(StorageAccess::ATOMIC) {
-                        wgpu::StorageTextureAccess::Atomic
-                    } else {
-                        panic!("Invalid storage texture access")
-                    },
-                    format: wgpu::TextureFormat::Rgba8Unorm,
+                ShaderBindingType::StorageTexture(access, format) => BindingType::StorageTexture {
+                    access: access.texture_access(),
+                    format: format.into(),
                     view_dimension: wgpu::TextureViewDimension::D2,
                 },
                 _ => unreachable!(),
             }
         }
 
+        // Push constants are declared in the pipeline layout instead, see push_constant_ranges
         for reflect in reflects {
             match reflect {
                 ShaderReflect::Vertex { bindings, .. } => {
-                    for binding in bindings.iter() {
+                    for binding in bindings
+                        .iter()
+                        .filter(|b| !matches!(b.ty, ShaderBindingType::PushConstant(_)))
+                    {
                         let ty = create_layout_ty(binding.ty.clone());
                         let existing =
                             find_existing(&mut layouts, binding.group, binding.binding, ty);
@@ -592,7 +751,10 @@ impl GraphicsShader {
                     }
                 }
                 ShaderReflect::Fragment { bindings, .. } => {
-                    for binding in bindings.iter() {
+                    for binding in bindings
+                        .iter()
+                        .filter(|b| !matches!(b.ty, ShaderBindingType::PushConstant(_)))
+                    {
                         let ty = create_layout_ty(binding.ty.clone());
                         let existing =
                             find_existing(&mut layouts, binding.group, binding.binding, ty);
@@ -626,7 +788,10 @@ impl GraphicsShader {
                     }
                 }
                 ShaderReflect::VertexFragment { bindings, .. } => {
-                    for binding in bindings.iter() {
+                    for binding in bindings
+                        .iter()
+                        .filter(|b| !matches!(b.ty, ShaderBindingType::PushConstant(_)))
+                    {
                         let ty = create_layout_ty(binding.ty.clone());
 
                         // Push new layout entry
@@ -656,7 +821,26 @@ impl GraphicsShader {
         layout_vec.sort_by_key(|(group, _)| *group);
         layout_vec
             .into_iter()
-            .map(|(group, layout)| {
+            .map(|(group, mut layout)| {
+                let mut dynamic_bindings = Vec::new();
+                for entry in layout.iter_mut() {
+                    if !dynamic_offsets.contains(&(group, entry.binding)) {
+                        continue;
+                    }
+
+                    if let BindingType::Buffer {
+                        has_dynamic_offset,
+                        min_binding_size: Some(size),
+                        ..
+                    } = &mut entry.ty
+                    {
+                        *has_dynamic_offset = true;
+                        dynamic_bindings.push((entry.binding, size.get()));
+                    }
+                }
+
+                dynamic_bindings.sort_by_key(|(binding, _)| *binding);
+
                 // Label: "BindGroupLayout for group {group}, binding: {binding} (ex: 0, 1, 2)"
                 let label = if !layout.is_empty() {
                     let mut s = format!("BindGroupLayout for group {}, binding: ", group);
@@ -686,6 +870,7 @@ impl GraphicsShader {
                 BindGroupLayout {
                     group,
                     bindings: layout.iter().map(|entry| entry.binding).collect(),
+                    dynamic_bindings,
                     layout: bind_group_layout,
                 }
             })
@@ -726,6 +911,18 @@ impl GraphicsShader {
         None
     }
 
+    /// Returns the memory layout of a uniform buffer, to check or pack a Rust struct against it.
+    ///
+    /// Only shaders built from WGSL source are reflected, binary shaders return [None].
+    pub fn get_uniform_layout(&self, name: &str) -> Option<UniformLayout> {
+        self.inner
+            .borrow()
+            .uniform_layouts
+            .iter()
+            .find(|layout| layout.name == name)
+            .cloned()
+    }
+
     pub fn get_uniform_size(&self, group: u32, binding: u32) -> Option<u32> {
         let inner = self.inner.borrow();
 
@@ -794,6 +991,43 @@ impl GraphicsShader {
         Ok(())
     }
 
+    /// Sets whether the vertex buffer at `slot` advances per vertex or per instance.
+    ///
+    /// Each struct argument of the vertex entry point is a slot, in argument order, eg.
+    /// `fn vs_main(vertex: VertexInput, instance: InstanceInput)` reads the vertices from slot 0
+    /// and the instances from slot 1, bound with `RenderPass::set_vertex_buffer`.
+    pub fn set_vertex_step_mode(
+        &mut self,
+        slot: u32,
+        step_mode: VertexStepMode,
+    ) -> Result<(), String> {
+        let mut attrib = self.attrib.borrow_mut();
+        let buffer_count = attrib.buffers.len();
+
+        match attrib.buffers.get_mut(slot as usize) {
+            Some(buffer) => {
+                buffer.step_mode = step_mode.into();
+                Ok(())
+            }
+            None => Err(format!(
+                "Vertex buffer slot {} not found, shader has {} vertex buffers",
+                slot, buffer_count
+            )),
+        }
+    }
+
+    /// Number of vertex buffer slots of the vertex entry point.
+    pub fn vertex_buffer_count(&self) -> u32 {
+        self.attrib.borrow().buffers.len() as u32
+    }
+
+    /// Sets the format of the vertex input at `location` in the vertex buffer, eg.
+    /// [VertexInputType::Unorm8x4] for a `vec4<f32>` color or [VertexInputType::Float16x2]
+    /// for a `vec2<f32>` texture coordinate.
+    ///
+    /// The offsets and stride of the vertex buffer are recomputed, the attributes are packed
+    /// in declaration order, each aligned to its size up to 4 bytes, with the stride rounded
+    /// up to 4 bytes.
     pub fn set_vertex_input(
         &mut self,
         location: u32,
@@ -801,21 +1035,19 @@ impl GraphicsShader {
     ) -> Result<(), String> {
         let inner = self.inner.borrow_mut();
 
-        let vertex_input = match inner.reflection.first() {
-            Some(ShaderReflect::Vertex { input, .. }) => input.as_ref(),
-            Some(ShaderReflect::VertexFragment { vertex_input, .. }) => vertex_input.as_ref(),
-            _ => None,
+        let vertex_inputs = match inner.reflection.first() {
+            Some(ShaderReflect::Vertex { input, .. }) => input.as_slice(),
+            Some(ShaderReflect::VertexFragment { vertex_input, .. }) => vertex_input.as_slice(),
+            _ => &[],
         };
 
-        if vertex_input.is_none() {
+        if vertex_inputs.is_empty() {
             return Err("Shader does not have vertex input".to_string());
         }
 
-        let vertex_input = vertex_input.unwrap();
-
-        let input = vertex_input
-            .attributes
+        let input = vertex_inputs
             .iter()
+            .flat_map(|input| input.attributes.iter())
             .find(|attr| attr.0 == location);
         if input.is_none() {
             return Err(format!("Vertex input location {} not found", location));
@@ -830,20 +1062,38 @@ impl GraphicsShader {
         }
 
         let mut attrib = self.attrib.borrow_mut();
-        let vertex_input_attrib = attrib
-            .attributes
-            .iter_mut()
-            .find(|attr| attr.shader_location == *location);
+        let buffer = attrib.buffers.iter_mut().find(|buffer| {
+            buffer
+                .attributes
+                .iter()
+                .any(|attr| attr.shader_location == *location)
+        });
 
-        if vertex_input_attrib.is_none() {
+        if buffer.is_none() {
             return Err(format!(
                 "Vertex input location {} not found in shader attributes",
                 location
             ));
         }
 
-        let vertex_input_attrib = vertex_input_attrib.unwrap();
-        vertex_input_attrib.format = vtype.into();
+        let buffer = buffer.unwrap();
+        for attr in buffer.attributes.iter_mut() {
+            if attr.shader_location == *location {
+                attr.format = vtype.into();
+            }
+        }
+
+        // Smaller formats shrink the vertex, the attributes stay in declaration order
+        let mut offset: wgpu::BufferAddress = 0;
+        for attr in buffer.attributes.iter_mut() {
+            let size = attr.format.size();
+
+            offset = offset.next_multiple_of(size.min(4));
+            attr.offset = offset;
+            offset += size;
+        }
+
+        buffer.stride = offset.next_multiple_of(4);
 
         Ok(())
     }
@@ -861,59 +1111,45 @@ impl std::hash::Hash for GraphicsShader {
 // example: if origin is Float32 and target is Unorm32, then it is supported
 #[inline]
 fn is_format_conversion_supported(origin: VertexInputType, target: VertexInputType) -> bool {
+    use VertexInputType::*;
+
     match origin {
-        VertexInputType::Float32 => match target {
-            VertexInputType::Float32 => true,
-            VertexInputType::Snorm8 => true,
-            VertexInputType::Unorm8 => true,
-            VertexInputType::Snorm16 => true,
-            _ => false,
-        },
-        VertexInputType::Float32x2 => match target {
-            VertexInputType::Float32x2 => true,
-            VertexInputType::Snorm8x2 => true,
-            VertexInputType::Unorm8x2 => true,
-            VertexInputType::Snorm16x2 => true,
-            _ => false,
-        },
-        VertexInputType::Float32x3 => {
-            match target {
-                VertexInputType::Float32x3 => true,
-                // normalized types are not supported for 3-component vectors
-                _ => false,
-            }
-        }
-        VertexInputType::Float32x4 => match target {
-            VertexInputType::Float32x4 => true,
-            VertexInputType::Snorm8x4 => true,
-            VertexInputType::Unorm8x4 => true,
-            VertexInputType::Snorm16x4 => true,
-            _ => false,
-        },
-        VertexInputType::Uint32 => match target {
-            VertexInputType::Uint32 => true,
-            VertexInputType::Uint16 => true,
-            VertexInputType::Uint8 => true,
-            _ => false,
-        },
-        VertexInputType::Uint32x2 => match target {
-            VertexInputType::Uint32x2 => true,
-            VertexInputType::Uint16x2 => true,
-            VertexInputType::Uint8x2 => true,
-            _ => false,
-        },
-        VertexInputType::Uint32x3 => match target {
-            VertexInputType::Uint32x3 => true,
-            VertexInputType::Uint16x4 => true,
-            VertexInputType::Uint8x4 => true,
-            _ => false,
-        },
-        VertexInputType::Uint32x4 => match target {
-            VertexInputType::Uint32x4 => true,
-            VertexInputType::Uint16x4 => true,
-            VertexInputType::Uint8x4 => true,
-            _ => false,
-        },
+        Float32 => matches!(target, Float32 | Float16 | Snorm8 | Unorm8 | Snorm16 | Unorm16),
+        Float32x2 => matches!(
+            target,
+            Float32x2 | Float16x2 | Snorm8x2 | Unorm8x2 | Snorm16x2 | Unorm16x2
+        ),
+        // There are no 3 components 8 and 16-bit formats, the 4th component is ignored
+        Float32x3 => matches!(
+            target,
+            Float32x3
+                | Float16x4
+                | Snorm8x4
+                | Unorm8x4
+                | Snorm16x4
+                | Unorm16x4
+                | Unorm10_10_10_2
+                | Unorm8x4Bgra
+        ),
+        Float32x4 => matches!(
+            target,
+            Float32x4
+                | Float16x4
+                | Snorm8x4
+                | Unorm8x4
+                | Snorm16x4
+                | Unorm16x4
+                | Unorm10_10_10_2
+                | Unorm8x4Bgra
+        ),
+        Sint32 => matches!(target, Sint32 | Sint16 | Sint8),
+        Sint32x2 => matches!(target, Sint32x2 | Sint16x2 | Sint8x2),
+        Sint32x3 => matches!(target, Sint32x3 | Sint16x4 | Sint8x4),
+        Sint32x4 => matches!(target, Sint32x4 | Sint16x4 | Sint8x4),
+        Uint32 => matches!(target, Uint32 | Uint16 | Uint8),
+        Uint32x2 => matches!(target, Uint32x2 | Uint16x2 | Uint8x2),
+        Uint32x3 => matches!(target, Uint32x3 | Uint16x4 | Uint8x4),
+        Uint32x4 => matches!(target, Uint32x4 | Uint16x4 | Uint8x4),
         _ => origin == target,
     }
 }
@@ -1,8 +1,18 @@
+pub(crate) mod bind_group;
 pub(crate) mod bind_group_manager;
 pub(crate) mod compute;
 pub(crate) mod graphics;
 pub(crate) mod reflection;
 pub(crate) mod types;
+pub mod uniform;
+#[cfg(feature = "hot-reload")]
+pub mod watcher;
+
+pub use bind_group::{
+    BindGroup,
+    BindGroupBuilder,
+    BindGroupError,
+};
 
 pub use compute::{
     ComputeShader,
@@ -19,6 +29,7 @@ pub use types::{
     ShaderCullMode,
     ShaderPollygonMode,
     ShaderFrontFace,
+    VertexStepMode,
     StorageAccess,
     ShaderBindingType,
     IndexBufferSize,
@@ -29,4 +40,12 @@ pub use types::{
     BindGroupLayout,
 };
 
+pub use uniform::{
+    GpuUniform,
+    UniformField,
+    UniformLayout,
+    UniformLayoutError,
+    UniformMember,
+};
+
 pub use reflection::is_shader_valid;
@@ -3,10 +3,18 @@ use std::io::{Cursor, Read};
 
 use byteorder_lite::{LittleEndian, ReadBytesExt};
 use wgpu::naga::{
-    AddressSpace, ArraySize, Binding, Module, Scalar, ScalarKind, ShaderStage, TypeInner,
-    VectorSize,
+    AddressSpace, ArraySize, Binding, Module, Scalar, ScalarKind, ShaderStage, StorageFormat,
+    TypeInner, VectorSize,
+    valid::{Capabilities, ValidationFlags, Validator},
 };
 
+#[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+use wgpu::TextureFormatFeatureFlags;
+
+#[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+use crate::gpu::GPUInner;
+use crate::gpu::texture::TextureFormat;
+
 use super::types::{
     ShaderBindingInfo, ShaderBindingType, ShaderReflect, StorageAccess, VertexInputReflection,
     VertexInputType,
@@ -93,11 +101,13 @@ pub fn load_binary_shader(data: &[u8]) -> Result<BinaryShader, String> {
             2 => {
                 let access = StorageAccess::from_bits(read_u32(&mut cursor)?)
                     .ok_or("Invalid storage texture access")?;
-                ShaderBindingType::StorageTexture(access)
+                // Binary shaders do not record the texel format
+                ShaderBindingType::StorageTexture(access, TextureFormat::Rgba8Unorm)
             }
             3 => ShaderBindingType::Sampler(read_u32(&mut cursor)? != 0),
             4 => ShaderBindingType::Texture(read_u32(&mut cursor)? != 0),
             5 => ShaderBindingType::PushConstant(read_u32(&mut cursor)?),
+            6 => ShaderBindingType::DepthTexture(read_u32(&mut cursor)? != 0),
             t => return Err(format!("Unknown binding type ID: {}", t)),
         };
 
@@ -138,13 +148,18 @@ pub fn load_binary_shader(data: &[u8]) -> Result<BinaryShader, String> {
             attributes.push((location, offset, ty));
         }
 
-        Some(VertexInputReflection {
-            name,
-            stride,
-            attributes,
-        })
+        // Binary shaders only record a single vertex buffer
+        if attributes.is_empty() {
+            Vec::new()
+        } else {
+            vec![VertexInputReflection {
+                name,
+                stride,
+                attributes,
+            }]
+        }
     } else {
-        None
+        Vec::new()
     };
 
     let reflect = match shader_type_id {
@@ -182,6 +197,112 @@ pub fn load_binary_shader(data: &[u8]) -> Result<BinaryShader, String> {
     Ok(BinaryShader { spirv, reflect })
 }
 
+fn storage_access(access: wgpu::naga::StorageAccess) -> StorageAccess {
+    let mut result = StorageAccess::empty();
+    if access.contains(wgpu::naga::StorageAccess::LOAD) {
+        result |= StorageAccess::READ
+    }
+
+    if access.contains(wgpu::naga::StorageAccess::STORE) {
+        result |= StorageAccess::WRITE;
+    }
+
+    if access.contains(wgpu::naga::StorageAccess::ATOMIC) {
+        result |= StorageAccess::ATOMIC;
+    }
+
+    result
+}
+
+fn storage_format(format: StorageFormat) -> Option<TextureFormat> {
+    Some(match format {
+        StorageFormat::R8Unorm => TextureFormat::R8Unorm,
+        StorageFormat::R8Snorm => TextureFormat::R8Snorm,
+        StorageFormat::R8Uint => TextureFormat::R8Uint,
+        StorageFormat::R8Sint => TextureFormat::R8Sint,
+        StorageFormat::R16Uint => TextureFormat::R16Uint,
+        StorageFormat::R16Sint => TextureFormat::R16Sint,
+        StorageFormat::R16Float => TextureFormat::R16Float,
+        StorageFormat::Rg8Unorm => TextureFormat::Rg8Unorm,
+        StorageFormat::Rg8Snorm => TextureFormat::Rg8Snorm,
+        StorageFormat::Rg8Uint => TextureFormat::Rg8Uint,
+        StorageFormat::Rg8Sint => TextureFormat::Rg8Sint,
+        StorageFormat::R32Uint => TextureFormat::R32Uint,
+        StorageFormat::R32Sint => TextureFormat::R32Sint,
+        StorageFormat::R32Float => TextureFormat::R32Float,
+        StorageFormat::Rg16Uint => TextureFormat::Rg16Uint,
+        StorageFormat::Rg16Sint => TextureFormat::Rg16Sint,
+        StorageFormat::Rg16Float => TextureFormat::Rg16Float,
+        StorageFormat::Rgba8Unorm => TextureFormat::Rgba8Unorm,
+        StorageFormat::Rgba8Snorm => TextureFormat::Rgba8Snorm,
+        StorageFormat::Rgba8Uint => TextureFormat::Rgba8Uint,
+        StorageFormat::Rgba8Sint => TextureFormat::Rgba8Sint,
+        StorageFormat::Bgra8Unorm => TextureFormat::Bgra8Unorm,
+        StorageFormat::Rgb10a2Uint => TextureFormat::Rgb10a2Uint,
+        StorageFormat::Rgb10a2Unorm => TextureFormat::Rgb10a2Unorm,
+        StorageFormat::Rg11b10Ufloat => TextureFormat::Rg11b10Ufloat,
+        StorageFormat::Rg32Uint => TextureFormat::Rg32Uint,
+        StorageFormat::Rg32Sint => TextureFormat::Rg32Sint,
+        StorageFormat::Rg32Float => TextureFormat::Rg32Float,
+        StorageFormat::Rgba16Uint => TextureFormat::Rgba16Uint,
+        StorageFormat::Rgba16Sint => TextureFormat::Rgba16Sint,
+        StorageFormat::Rgba16Float => TextureFormat::Rgba16Float,
+        StorageFormat::Rgba32Uint => TextureFormat::Rgba32Uint,
+        StorageFormat::Rgba32Sint => TextureFormat::Rgba32Sint,
+        StorageFormat::Rgba32Float => TextureFormat::Rgba32Float,
+        _ => return None,
+    })
+}
+
+/// Checks that the device supports the access of every storage texture in the shader for
+/// its texel format, eg. `read_write` is only guaranteed for `r32float`, `r32uint` and
+/// `r32sint`.
+#[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+pub(crate) fn validate_storage_textures(
+    graphics: &GPUInner,
+    reflect: &ShaderReflect,
+) -> Result<(), String> {
+    if graphics.device.is_none() {
+        return Ok(());
+    }
+
+    let bindings = match reflect {
+        ShaderReflect::Vertex { bindings, .. }
+        | ShaderReflect::Fragment { bindings, .. }
+        | ShaderReflect::VertexFragment { bindings, .. }
+        | ShaderReflect::Compute { bindings, .. } => bindings,
+    };
+
+    for binding in bindings {
+        let ShaderBindingType::StorageTexture(access, format) = binding.ty else {
+            continue;
+        };
+
+        let features = graphics.format_features(format.into());
+
+        let access = access.texture_access();
+        let required = match access {
+            wgpu::StorageTextureAccess::ReadOnly => TextureFormatFeatureFlags::STORAGE_READ_ONLY,
+            wgpu::StorageTextureAccess::WriteOnly => TextureFormatFeatureFlags::STORAGE_WRITE_ONLY,
+            wgpu::StorageTextureAccess::ReadWrite => TextureFormatFeatureFlags::STORAGE_READ_WRITE,
+            wgpu::StorageTextureAccess::Atomic => TextureFormatFeatureFlags::STORAGE_ATOMIC,
+        };
+
+        if !features
+            .allowed_usages
+            .contains(wgpu::TextureUsages::STORAGE_BINDING)
+            || !features.flags.contains(required)
+        {
+            return Err(format!(
+                "Storage texture '{}' (group: {} binding: {}) uses {:?} access with format {:?}, which is not supported by the device",
+                binding.name, binding.group, binding.binding, access, format
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 pub(crate) fn parse(module: Module) -> Result<ShaderReflect, String> {
     let mut bindings = Vec::new();
     for (handle, var) in module.global_variables.iter() {
@@ -239,18 +360,7 @@ pub(crate) fn parse(module: Module) -> Result<ShaderReflect, String> {
                         .clone()
                         .unwrap_or_else(|| format!("unnamed_{:?}", handle));
 
-                    let mut access = StorageAccess::empty();
-                    if _access.contains(wgpu::naga::StorageAccess::LOAD) {
-                        access |= StorageAccess::READ
-                    }
-
-                    if _access.contains(wgpu::naga::StorageAccess::STORE) {
-                        access |= StorageAccess::WRITE;
-                    }
-
-                    if _access.contains(wgpu::naga::StorageAccess::ATOMIC) {
-                        access |= StorageAccess::ATOMIC;
-                    }
+                    let access = storage_access(_access);
 
                     match &ty.inner {
                         TypeInner::Struct {
@@ -269,21 +379,6 @@ pub(crate) fn parse(module: Module) -> Result<ShaderReflect, String> {
                             bindings.push(binding_info);
                         }
 
-                        TypeInner::Image {
-                            dim: _,
-                            arrayed: _,
-                            class: _,
-                        } => {
-                            let binding_info = ShaderBindingInfo {
-                                binding: binding.binding as u32,
-                                group: binding.group as u32,
-                                name: var_name,
-                                ty: ShaderBindingType::StorageTexture(access),
-                            };
-
-                            bindings.push(binding_info);
-                        }
-
                         TypeInner::Array {
                             base: _,
                             size,
@@ -334,22 +429,33 @@ pub(crate) fn parse(module: Module) -> Result<ShaderReflect, String> {
                             arrayed: _,
                             class,
                         } => {
+                            let ty = match class {
+                                wgpu::naga::ImageClass::Sampled { kind: _, multi } => {
+                                    ShaderBindingType::Texture(multi)
+                                }
+                                wgpu::naga::ImageClass::Depth { multi } => {
+                                    ShaderBindingType::DepthTexture(multi)
+                                }
+                                wgpu::naga::ImageClass::Storage { format, access } => {
+                                    let texture_format = storage_format(format).ok_or_else(|| {
+                                        format!(
+                                            "Storage texture '{}' has unsupported format {:?}",
+                                            var_name, format
+                                        )
+                                    })?;
+
+                                    ShaderBindingType::StorageTexture(
+                                        storage_access(access),
+                                        texture_format,
+                                    )
+                                }
+                            };
+
                             let binding_info = ShaderBindingInfo {
                                 binding: binding.binding as u32,
                                 group: binding.group as u32,
                                 name: var_name,
-                                ty: ShaderBindingType::Texture(match class {
-                                    wgpu::naga::ImageClass::Sampled { kind: _, multi } => multi,
-                                    wgpu::naga::ImageClass::Depth { multi } => multi,
-                                    wgpu::naga::ImageClass::Storage {
-                                        format: _,
-                                        access: _,
-                                    } => {
-                                        // panic!("Storage image should be handled separately")
-                                        return Err("Storage image should be handled separately"
-                                            .to_string());
-                                    }
-                                }),
+                                ty,
                             };
 
                             bindings.push(binding_info);
@@ -382,7 +488,8 @@ pub(crate) fn parse(module: Module) -> Result<ShaderReflect, String> {
     let mut fragment_entry_point = String::new();
     let mut compute_entry_point = String::new();
 
-    let mut vertex_struct_input = None;
+    // One vertex buffer slot per struct argument, in argument order
+    let mut vertex_struct_inputs = Vec::new();
 
     #[allow(unused)]
     for entry_point in module.entry_points.iter() {
@@ -400,6 +507,11 @@ pub(crate) fn parse(module: Module) -> Result<ShaderReflect, String> {
                  * };
                  */
                 for vertex_input in entry_point.function.arguments.iter() {
+                    // Builtins such as `vertex_index` or `instance_index` are not part of the vertex buffer
+                    if matches!(vertex_input.binding, Some(Binding::BuiltIn(_))) {
+                        continue;
+                    }
+
                     let ty = &module.types[vertex_input.ty];
 
                     let struct_name = ty
@@ -413,6 +525,10 @@ pub(crate) fn parse(module: Module) -> Result<ShaderReflect, String> {
                     match &ty.inner {
                         TypeInner::Struct { members, span } => {
                             for member in members.iter() {
+                                if matches!(member.binding, Some(Binding::BuiltIn(_))) {
+                                    continue;
+                                }
+
                                 let attribute_name = member
                                     .name
                                     .clone()
@@ -441,13 +557,17 @@ pub(crate) fn parse(module: Module) -> Result<ShaderReflect, String> {
                                         if let Some(vertex_input_type) =
                                             mapping_to_vertex_input(scalar, None)
                                         {
+                                            total_size = align_to(
+                                                total_size,
+                                                vertex_input_type.alignment() as u32,
+                                            );
                                             attributes.push((
                                                 location,
                                                 total_size as u64,
                                                 vertex_input_type,
                                             ));
 
-                                            total_size += scalar_size(scalar);
+                                            total_size += vertex_input_type.size() as u32;
                                         } else {
                                             // #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
                                             // panic!(
@@ -465,14 +585,17 @@ pub(crate) fn parse(module: Module) -> Result<ShaderReflect, String> {
                                         if let Some(vertex_input_type) =
                                             mapping_to_vertex_input(scalar, Some(size))
                                         {
+                                            total_size = align_to(
+                                                total_size,
+                                                vertex_input_type.alignment() as u32,
+                                            );
                                             attributes.push((
                                                 location,
                                                 total_size as u64,
                                                 vertex_input_type,
                                             ));
 
-                                            total_size +=
-                                                vectorsize_as_u32(size) * scalar_size(scalar);
+                                            total_size += vertex_input_type.size() as u32;
                                         } else {
                                             // #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
                                             // panic!(
@@ -503,9 +626,14 @@ pub(crate) fn parse(module: Module) -> Result<ShaderReflect, String> {
                         _ => {}
                     }
 
-                    vertex_struct_input = Some(VertexInputReflection {
+                    if attributes.is_empty() {
+                        continue;
+                    }
+
+                    vertex_struct_inputs.push(VertexInputReflection {
                         name: struct_name,
-                        stride: total_size as u64,
+                        // wgpu requires strides to be a multiple of 4, eg. after a single f16
+                        stride: align_to(total_size, 4) as u64,
                         attributes,
                     });
                 }
@@ -523,7 +651,7 @@ pub(crate) fn parse(module: Module) -> Result<ShaderReflect, String> {
     if !vertex_entry_point.is_empty() && !fragment_entry_point.is_empty() {
         return Ok(ShaderReflect::VertexFragment {
             vertex_entry_point,
-            vertex_input: vertex_struct_input,
+            vertex_input: vertex_struct_inputs,
             fragment_entry_point,
             bindings,
         });
@@ -532,7 +660,7 @@ pub(crate) fn parse(module: Module) -> Result<ShaderReflect, String> {
     if !vertex_entry_point.is_empty() {
         return Ok(ShaderReflect::Vertex {
             entry_point: vertex_entry_point,
-            input: vertex_struct_input,
+            input: vertex_struct_inputs,
             bindings,
         });
     }
@@ -554,11 +682,119 @@ pub(crate) fn parse(module: Module) -> Result<ShaderReflect, String> {
     Err("No valid entry point found in shader module".to_string())
 }
 
+/// Finds the push constant block read by each entry point, as the stage and the size of the
+/// block in bytes.
+///
+/// Entry points that don't access a block are left out, so a block declared in a shared
+/// source only gets a range for the stages reading it.
+pub(crate) fn parse_push_constants(module: &Module) -> Vec<(wgpu::ShaderStages, u32)> {
+    if !module
+        .global_variables
+        .iter()
+        .any(|(_, var)| var.space == AddressSpace::PushConstant)
+    {
+        return Vec::new();
+    }
+
+    // The validator tracks which globals each entry point reaches, including through calls
+    let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(module)
+        .ok();
+
+    let mut stages = Vec::new();
+    for (index, entry_point) in module.entry_points.iter().enumerate() {
+        let stage = match entry_point.stage {
+            ShaderStage::Vertex => wgpu::ShaderStages::VERTEX,
+            ShaderStage::Fragment => wgpu::ShaderStages::FRAGMENT,
+            ShaderStage::Compute => wgpu::ShaderStages::COMPUTE,
+            _ => continue,
+        };
+
+        let block = module.global_variables.iter().find(|(handle, var)| {
+            var.space == AddressSpace::PushConstant
+                && match &info {
+                    Some(info) => !info.get_entry_point(index)[*handle].is_empty(),
+                    // Invalid module, wgpu reports the error when creating it
+                    None => true,
+                }
+        });
+
+        if let Some((_, var)) = block {
+            let size = module.types[var.ty].inner.size(module.to_ctx());
+            stages.push((stage, align_to(size, wgpu::PUSH_CONSTANT_ALIGNMENT)));
+        }
+    }
+
+    stages
+}
+
+/// Push constant blocks of a reflected binary shader, as the stage and the size in bytes.
+pub(crate) fn reflect_push_constants(reflect: &ShaderReflect) -> Vec<(wgpu::ShaderStages, u32)> {
+    let (stage, bindings) = match reflect {
+        ShaderReflect::Vertex { bindings, .. } => (wgpu::ShaderStages::VERTEX, bindings),
+        ShaderReflect::Fragment { bindings, .. } => (wgpu::ShaderStages::FRAGMENT, bindings),
+        ShaderReflect::VertexFragment { bindings, .. } => {
+            (wgpu::ShaderStages::VERTEX_FRAGMENT, bindings)
+        }
+        ShaderReflect::Compute { bindings, .. } => (wgpu::ShaderStages::COMPUTE, bindings),
+    };
+
+    bindings
+        .iter()
+        .filter_map(|binding| match binding.ty {
+            ShaderBindingType::PushConstant(size) => {
+                Some((stage, align_to(size, wgpu::PUSH_CONSTANT_ALIGNMENT)))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Builds the push constant ranges of a pipeline layout from the blocks of each stage.
+///
+/// Every range starts at offset 0, wgpu only allows a stage in a single range, so a stage with
+/// several entry points gets the largest block. Stages with blocks of the same size share a
+/// range.
+pub(crate) fn make_push_constant_ranges(
+    stages: &[(wgpu::ShaderStages, u32)],
+) -> Vec<wgpu::PushConstantRange> {
+    let mut sizes: Vec<(wgpu::ShaderStages, u32)> = Vec::new();
+    for &(stage, size) in stages {
+        for single in stage.iter() {
+            match sizes.iter_mut().find(|(s, _)| *s == single) {
+                Some(existing) => existing.1 = existing.1.max(size),
+                None => sizes.push((single, size)),
+            }
+        }
+    }
+
+    let mut ranges: Vec<wgpu::PushConstantRange> = Vec::new();
+    for (stage, size) in sizes {
+        match ranges.iter_mut().find(|range| range.range.end == size) {
+            Some(range) => range.stages |= stage,
+            None => ranges.push(wgpu::PushConstantRange {
+                stages: stage,
+                range: 0..size,
+            }),
+        }
+    }
+
+    ranges
+}
+
 pub(crate) fn mapping_to_vertex_input(
     scalar: &Scalar,
     vector: Option<&VectorSize>,
 ) -> Option<VertexInputType> {
     match scalar.kind {
+        // `f16` inputs, which require the `enable f16;` directive
+        ScalarKind::Float if scalar.width == 2 => match vector {
+            Some(VectorSize::Bi) => Some(VertexInputType::Float16x2),
+            // There is no 3 components 16-bit vertex format
+            Some(VectorSize::Tri) => None,
+            Some(VectorSize::Quad) => Some(VertexInputType::Float16x4),
+            None => Some(VertexInputType::Float16),
+        },
         ScalarKind::Float => {
             if let Some(vector_size) = vector {
                 match vector_size {
@@ -1,3 +1,5 @@
+use crate::gpu::texture::TextureFormat;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ShaderTopology {
     PointList,
@@ -66,6 +68,25 @@ impl Into<wgpu::FrontFace> for ShaderFrontFace {
     }
 }
 
+/// How often a vertex buffer advances, set per slot with
+/// [GraphicsShader::set_vertex_step_mode](super::GraphicsShader::set_vertex_step_mode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VertexStepMode {
+    /// Advances once per vertex.
+    Vertex,
+    /// Advances once per instance, eg. a per-instance transform or color.
+    Instance,
+}
+
+impl From<VertexStepMode> for wgpu::VertexStepMode {
+    fn from(value: VertexStepMode) -> Self {
+        match value {
+            VertexStepMode::Vertex => wgpu::VertexStepMode::Vertex,
+            VertexStepMode::Instance => wgpu::VertexStepMode::Instance,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct StorageAccess(u32);
 
@@ -77,13 +98,33 @@ bitflags::bitflags! {
     }
 }
 
+impl StorageAccess {
+    /// The access of a storage texture declared with these flags, `read`, `write` or
+    /// `read_write` in WGSL.
+    pub(crate) fn texture_access(&self) -> wgpu::StorageTextureAccess {
+        if self.contains(StorageAccess::ATOMIC) {
+            wgpu::StorageTextureAccess::Atomic
+        } else if self.contains(StorageAccess::READ | StorageAccess::WRITE) {
+            wgpu::StorageTextureAccess::ReadWrite
+        } else if self.contains(StorageAccess::READ) {
+            wgpu::StorageTextureAccess::ReadOnly
+        } else if self.contains(StorageAccess::WRITE) {
+            wgpu::StorageTextureAccess::WriteOnly
+        } else {
+            panic!("Invalid storage texture access")
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ShaderBindingType {
     UniformBuffer(u32),
     StorageBuffer(u32, StorageAccess),
-    StorageTexture(StorageAccess),
+    /// Access declared by the shader and the texel format of the texture.
+    StorageTexture(StorageAccess, TextureFormat),
     Sampler(bool),
     Texture(bool),
+    DepthTexture(bool),
     PushConstant(u32),
 }
 
@@ -94,8 +135,8 @@ impl std::fmt::Display for ShaderBindingType {
             ShaderBindingType::StorageBuffer(size, access) => {
                 write!(f, "StorageBuffer({}, {:?})", size, access)
             }
-            ShaderBindingType::StorageTexture(access) => {
-                write!(f, "StorageTexture({:?})", access)
+            ShaderBindingType::StorageTexture(access, format) => {
+                write!(f, "StorageTexture({:?}, {:?})", access, format)
             }
             ShaderBindingType::Sampler(is_compare) => {
                 write!(f, "Sampler({})", is_compare)
@@ -103,11 +144,69 @@ impl std::fmt::Display for ShaderBindingType {
             ShaderBindingType::Texture(is_storage) => {
                 write!(f, "Texture({})", is_storage)
             }
+            ShaderBindingType::DepthTexture(multisampled) => {
+                write!(f, "DepthTexture({})", multisampled)
+            }
             ShaderBindingType::PushConstant(size) => write!(f, "PushConstant({})", size),
         }
     }
 }
 
+impl ShaderBindingType {
+    /// Checks that a sampled texture matches the binding declared by the shader, the device
+    /// would only report it once the bind group is created.
+    ///
+    /// Depth textures must be declared as `texture_depth_2d`, and `texture_2d<f32>` bindings
+    /// require a filterable format.
+    pub(crate) fn validate_texture(
+        &self,
+        format: TextureFormat,
+        multisampled: bool,
+        filterable: bool,
+    ) -> Result<(), String> {
+        let declared_multisampled = match *self {
+            ShaderBindingType::Texture(multisampled) => {
+                if format.is_depth() || format.has_stencil() {
+                    return Err(format!(
+                        "{:?} is a depth format, declare the binding as texture_depth_2d",
+                        format
+                    ));
+                }
+
+                if !filterable && !multisampled {
+                    return Err(format!(
+                        "{:?} cannot be filtered, the binding requires a filterable format",
+                        format
+                    ));
+                }
+
+                multisampled
+            }
+            ShaderBindingType::DepthTexture(multisampled) => {
+                if !format.is_depth() {
+                    return Err(format!(
+                        "{:?} is not a depth format, declare the binding as texture_2d<f32>",
+                        format
+                    ));
+                }
+
+                multisampled
+            }
+            _ => return Ok(()),
+        };
+
+        if declared_multisampled != multisampled {
+            return Err(format!(
+                "the binding is {}multisampled but the texture is {}multisampled",
+                if declared_multisampled { "" } else { "not " },
+                if multisampled { "" } else { "not " }
+            ));
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum IndexBufferSize {
     U16,
@@ -172,6 +271,23 @@ pub enum VertexInputType {
     Float32x2,
     Float32x3,
     Float32x4,
+    /// Four unsigned normalized values packed as 10, 10, 10 and 2 bits, read as `vec4<f32>`.
+    Unorm10_10_10_2,
+    /// Four unsigned normalized bytes in BGRA order, read as `vec4<f32>` in RGBA order.
+    Unorm8x4Bgra,
+}
+
+impl VertexInputType {
+    /// Size of the attribute in the vertex buffer, in bytes.
+    pub fn size(&self) -> u64 {
+        let format: wgpu::VertexFormat = (*self).into();
+        format.size()
+    }
+
+    /// Alignment of the attribute offset in the vertex buffer, in bytes.
+    pub fn alignment(&self) -> u64 {
+        self.size().min(4)
+    }
 }
 
 impl Into<wgpu::VertexFormat> for VertexInputType {
@@ -216,6 +332,8 @@ impl Into<wgpu::VertexFormat> for VertexInputType {
             VertexInputType::Float32x2 => wgpu::VertexFormat::Float32x2,
             VertexInputType::Float32x3 => wgpu::VertexFormat::Float32x3,
             VertexInputType::Float32x4 => wgpu::VertexFormat::Float32x4,
+            VertexInputType::Unorm10_10_10_2 => wgpu::VertexFormat::Unorm10_10_10_2,
+            VertexInputType::Unorm8x4Bgra => wgpu::VertexFormat::Unorm8x4Bgra,
         }
     }
 }
@@ -237,7 +355,7 @@ pub struct VertexInputDesc {
 pub enum ShaderReflect {
     Vertex {
         entry_point: String,
-        input: Option<VertexInputReflection>,
+        input: Vec<VertexInputReflection>,
         bindings: Vec<ShaderBindingInfo>,
     },
     Fragment {
@@ -246,7 +364,7 @@ pub enum ShaderReflect {
     },
     VertexFragment {
         vertex_entry_point: String,
-        vertex_input: Option<VertexInputReflection>,
+        vertex_input: Vec<VertexInputReflection>,
         fragment_entry_point: String,
         bindings: Vec<ShaderBindingInfo>,
     },
@@ -323,9 +441,22 @@ impl PartialEq for ShaderReflect {
 pub struct BindGroupLayout {
     pub group: u32,
     pub bindings: Vec<u32>,
+    /// Bindings using dynamic offsets and their binding size, sorted by binding.
+    pub dynamic_bindings: Vec<(u32, u64)>,
     pub layout: wgpu::BindGroupLayout,
 }
 
+impl BindGroupLayout {
+    /// Size of a binding using dynamic offsets, which must be bound with an explicit size
+    /// so the offset stays inside the buffer.
+    pub(crate) fn dynamic_binding_size(&self, binding: u32) -> Option<wgpu::BufferSize> {
+        self.dynamic_bindings
+            .iter()
+            .find(|(b, _)| *b == binding)
+            .and_then(|(_, size)| wgpu::BufferSize::new(*size))
+    }
+}
+
 #[derive(Debug, Clone, Eq, Hash)]
 pub struct VertexInputReflection {
     pub name: String,
@@ -0,0 +1,263 @@
+use wgpu::naga::{AddressSpace, Module, TypeInner};
+
+/// A field of a Rust struct uploaded as a uniform, see [GpuUniform].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UniformField {
+    pub name: &'static str,
+    pub offset: u32,
+    pub size: u32,
+}
+
+/// Describes the fields of a struct uploaded as a uniform, so it can be checked against the
+/// layout the shader expects with [UniformLayout::validate] or packed into it with
+/// [UniformLayout::pack].
+///
+/// Implement it with the [impl_gpu_uniform](crate::impl_gpu_uniform) macro:
+///
+/// ```ignore
+/// #[repr(C)]
+/// #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+/// struct Light {
+///     position: [f32; 3],
+///     intensity: f32,
+///     color: [f32; 4],
+/// }
+///
+/// est_render::impl_gpu_uniform!(Light { position, intensity, color });
+/// ```
+///
+/// Fields are matched to the shader struct members by name.
+pub trait GpuUniform: bytemuck::Pod {
+    fn fields() -> Vec<UniformField>;
+}
+
+/// Implements [GpuUniform](crate::gpu::shader::uniform::GpuUniform) for a struct, listing the
+/// fields that are matched against the shader by name.
+#[macro_export]
+macro_rules! impl_gpu_uniform {
+    ($ty:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::gpu::shader::uniform::GpuUniform for $ty {
+            fn fields() -> Vec<$crate::gpu::shader::uniform::UniformField> {
+                vec![$(
+                    $crate::gpu::shader::uniform::UniformField {
+                        name: stringify!($field),
+                        offset: ::std::mem::offset_of!($ty, $field) as u32,
+                        size: $crate::gpu::shader::uniform::field_size(|value: &$ty| &value.$field),
+                    },
+                )*]
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+pub fn field_size<T, F>(_: impl Fn(&T) -> &F) -> u32 {
+    std::mem::size_of::<F>() as u32
+}
+
+/// A member of a uniform block, as laid out by the shader.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UniformMember {
+    pub name: String,
+    pub offset: u32,
+    pub size: u32,
+}
+
+/// The memory layout of a uniform buffer binding, reflected from the shader source.
+///
+/// A uniform that isn't a struct is reported as a single member at offset 0 named after
+/// the variable.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UniformLayout {
+    pub name: String,
+    pub group: u32,
+    pub binding: u32,
+    /// Size of the buffer the binding expects, in bytes.
+    pub size: u32,
+    pub members: Vec<UniformMember>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UniformLayoutError {
+    /// The shader member has no field with the same name in the Rust struct.
+    MissingField(String),
+    /// The field and the shader member have a different size.
+    SizeMismatch {
+        field: String,
+        expected: u32,
+        actual: u32,
+    },
+    /// The field is not at the offset the shader reads it from.
+    OffsetMismatch {
+        field: String,
+        expected: u32,
+        actual: u32,
+    },
+    /// The struct is smaller than the uniform buffer.
+    StructSizeMismatch { expected: u32, actual: u32 },
+}
+
+impl std::fmt::Display for UniformLayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UniformLayoutError::MissingField(field) => {
+                write!(f, "Uniform member '{}' has no matching field", field)
+            }
+            UniformLayoutError::SizeMismatch {
+                field,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Field '{}' is {} bytes, the shader expects {} bytes",
+                field, actual, expected
+            ),
+            UniformLayoutError::OffsetMismatch {
+                field,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Field '{}' is at offset {}, the shader expects offset {}",
+                field, actual, expected
+            ),
+            UniformLayoutError::StructSizeMismatch { expected, actual } => write!(
+                f,
+                "Struct is {} bytes, the uniform buffer is {} bytes",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl UniformLayout {
+    pub fn member(&self, name: &str) -> Option<&UniformMember> {
+        self.members.iter().find(|member| member.name == name)
+    }
+
+    /// Checks that `T` can be uploaded as is, every member must be matched by a field of the
+    /// same size at the same offset and the struct must cover the whole buffer.
+    ///
+    /// Fields the shader doesn't use are ignored.
+    pub fn validate<T: GpuUniform>(&self) -> Result<(), UniformLayoutError> {
+        let fields = T::fields();
+
+        for member in &self.members {
+            let field = Self::find_field(&fields, member)?;
+
+            if field.offset != member.offset {
+                return Err(UniformLayoutError::OffsetMismatch {
+                    field: member.name.clone(),
+                    expected: member.offset,
+                    actual: field.offset,
+                });
+            }
+        }
+
+        let struct_size = std::mem::size_of::<T>() as u32;
+        if struct_size < self.size {
+            return Err(UniformLayoutError::StructSizeMismatch {
+                expected: self.size,
+                actual: struct_size,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Copies the fields of `value` to the offsets the shader reads them from, padding
+    /// between them as needed, so a tightly packed struct can be uploaded without manual
+    /// padding fields.
+    ///
+    /// The result is [UniformLayout::size] bytes long.
+    pub fn pack<T: GpuUniform>(&self, value: &T) -> Result<Vec<u8>, UniformLayoutError> {
+        let fields = T::fields();
+        let bytes = bytemuck::bytes_of(value);
+
+        let mut data = vec![0u8; self.size as usize];
+
+        for member in &self.members {
+            let field = Self::find_field(&fields, member)?;
+
+            let src = field.offset as usize..(field.offset + field.size) as usize;
+            let dst = member.offset as usize..(member.offset + member.size) as usize;
+
+            data[dst].copy_from_slice(&bytes[src]);
+        }
+
+        Ok(data)
+    }
+
+    fn find_field<'a>(
+        fields: &'a [UniformField],
+        member: &UniformMember,
+    ) -> Result<&'a UniformField, UniformLayoutError> {
+        let field = fields
+            .iter()
+            .find(|field| field.name == member.name)
+            .ok_or_else(|| UniformLayoutError::MissingField(member.name.clone()))?;
+
+        if field.size != member.size {
+            return Err(UniformLayoutError::SizeMismatch {
+                field: member.name.clone(),
+                expected: member.size,
+                actual: field.size,
+            });
+        }
+
+        Ok(field)
+    }
+}
+
+pub(crate) fn parse_layouts(module: &Module) -> Vec<UniformLayout> {
+    let mut layouts = Vec::new();
+
+    for (handle, var) in module.global_variables.iter() {
+        let Some(binding) = &var.binding else {
+            continue;
+        };
+
+        if var.space != AddressSpace::Uniform {
+            continue;
+        }
+
+        let ty = &module.types[var.ty];
+        let name = var
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("unnamed_{:?}", handle));
+
+        let members = match &ty.inner {
+            TypeInner::Struct { members, .. } => members
+                .iter()
+                .enumerate()
+                .map(|(index, member)| UniformMember {
+                    name: member
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| format!("member_{}", index)),
+                    offset: member.offset,
+                    size: module.types[member.ty].inner.size(module.to_ctx()),
+                })
+                .collect(),
+            inner => vec![UniformMember {
+                name: name.clone(),
+                offset: 0,
+                size: inner.size(module.to_ctx()),
+            }],
+        };
+
+        // Same size as the binding reflection, the buffer must be at least this large
+        let size = super::reflection::get_size(module, &ty.inner).max(0) as u32;
+
+        layouts.push(UniformLayout {
+            name,
+            group: binding.group,
+            binding: binding.binding,
+            size: size.max(ty.inner.size(module.to_ctx())),
+            members,
+        });
+    }
+
+    layouts
+}
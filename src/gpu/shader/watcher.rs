@@ -0,0 +1,343 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::mpsc::{Receiver, channel},
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::utils::{ArcRef, WeakRef};
+
+use super::{
+    super::GPUInner,
+    compute::{ComputeShader, ComputeShaderInner},
+    graphics::{GraphicsShader, GraphicsShaderInner, GraphicsShaderSource, VertexInputDescription},
+};
+
+#[derive(Debug)]
+pub enum ShaderWatcherError {
+    /// The file doesn't exist or can't be read.
+    InvalidPath(String),
+    /// The platform file watcher failed.
+    WatchFailed(String),
+}
+
+impl std::fmt::Display for ShaderWatcherError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderWatcherError::InvalidPath(path) => write!(f, "Invalid shader path: {}", path),
+            ShaderWatcherError::WatchFailed(err) => write!(f, "Failed to watch shader: {}", err),
+        }
+    }
+}
+
+/// Result of a shader reload, returned by [ShaderWatcher::poll].
+#[derive(Debug, Clone)]
+pub enum ShaderReload {
+    /// The shader was recompiled and swapped in, the next draw or dispatch uses it.
+    Reloaded { path: PathBuf },
+    /// The new source failed to compile, the previous shader is kept.
+    Failed { path: PathBuf, error: String },
+}
+
+enum WatchedFiles {
+    Single(PathBuf),
+    Split(PathBuf, PathBuf),
+}
+
+impl WatchedFiles {
+    fn contains(&self, path: &Path) -> bool {
+        match self {
+            WatchedFiles::Single(file) => file == path,
+            WatchedFiles::Split(vertex, fragment) => vertex == path || fragment == path,
+        }
+    }
+}
+
+enum WatchedShader {
+    Graphics {
+        inner: WeakRef<GraphicsShaderInner>,
+        attrib: WeakRef<VertexInputDescription>,
+        files: WatchedFiles,
+    },
+    Compute {
+        inner: WeakRef<ComputeShaderInner>,
+        file: PathBuf,
+    },
+}
+
+impl WatchedShader {
+    fn is_alive(&self) -> bool {
+        match self {
+            WatchedShader::Graphics { inner, .. } => inner.is_alive(),
+            WatchedShader::Compute { inner, .. } => inner.is_alive(),
+        }
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        match self {
+            WatchedShader::Graphics { files, .. } => files.contains(path),
+            WatchedShader::Compute { file, .. } => file == path,
+        }
+    }
+}
+
+/// Watches WGSL files and reloads the shaders created from them when they change.
+///
+/// The new source is compiled and reflected like a newly built shader, then swapped inside
+/// the existing [GraphicsShader] or [ComputeShader], so every clone of it picks up the change.
+/// Render pipelines are rebuilt on the next draw since the shader hash changes, pipelines built
+/// with [RenderPipelineBuilder](crate::gpu::pipeline::render::RenderPipelineBuilder) or
+/// [ComputePipelineBuilder](crate::gpu::pipeline::compute::ComputePipelineBuilder) must be
+/// built again.
+///
+/// The reloads happen in [ShaderWatcher::poll], which should be called once per frame.
+pub struct ShaderWatcher {
+    graphics: ArcRef<GPUInner>,
+    watcher: RecommendedWatcher,
+    receiver: Receiver<PathBuf>,
+    directories: HashSet<PathBuf>,
+    shaders: Vec<WatchedShader>,
+}
+
+impl std::fmt::Debug for ShaderWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShaderWatcher")
+            .field("directories", &self.directories)
+            .field("shaders", &self.shaders.len())
+            .finish()
+    }
+}
+
+impl ShaderWatcher {
+    pub(crate) fn new(graphics: ArcRef<GPUInner>) -> Result<Self, ShaderWatcherError> {
+        let (sender, receiver) = channel();
+
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+
+            if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
+                for path in event.paths {
+                    _ = sender.send(path);
+                }
+            }
+        })
+        .map_err(|e| ShaderWatcherError::WatchFailed(e.to_string()))?;
+
+        Ok(Self {
+            graphics,
+            watcher,
+            receiver,
+            directories: HashSet::new(),
+            shaders: Vec::new(),
+        })
+    }
+
+    /// Reloads `shader` whenever the WGSL file at `path` changes.
+    pub fn watch_graphics(
+        &mut self,
+        shader: &GraphicsShader,
+        path: &str,
+    ) -> Result<(), ShaderWatcherError> {
+        let path = self.watch_file(path)?;
+
+        self.shaders.push(WatchedShader::Graphics {
+            inner: ArcRef::downgrade(&shader.inner),
+            attrib: ArcRef::downgrade(&shader.attrib),
+            files: WatchedFiles::Single(path),
+        });
+
+        Ok(())
+    }
+
+    /// Reloads `shader` whenever one of its separate vertex or fragment files changes.
+    pub fn watch_graphics_split(
+        &mut self,
+        shader: &GraphicsShader,
+        vertex_path: &str,
+        fragment_path: &str,
+    ) -> Result<(), ShaderWatcherError> {
+        let vertex_path = self.watch_file(vertex_path)?;
+        let fragment_path = self.watch_file(fragment_path)?;
+
+        self.shaders.push(WatchedShader::Graphics {
+            inner: ArcRef::downgrade(&shader.inner),
+            attrib: ArcRef::downgrade(&shader.attrib),
+            files: WatchedFiles::Split(vertex_path, fragment_path),
+        });
+
+        Ok(())
+    }
+
+    /// Reloads `shader` whenever the WGSL file at `path` changes.
+    pub fn watch_compute(
+        &mut self,
+        shader: &ComputeShader,
+        path: &str,
+    ) -> Result<(), ShaderWatcherError> {
+        let path = self.watch_file(path)?;
+
+        self.shaders.push(WatchedShader::Compute {
+            inner: ArcRef::downgrade(&shader.inner),
+            file: path,
+        });
+
+        Ok(())
+    }
+
+    fn watch_file(&mut self, path: &str) -> Result<PathBuf, ShaderWatcherError> {
+        let path = std::fs::canonicalize(path)
+            .map_err(|_| ShaderWatcherError::InvalidPath(path.to_string()))?;
+
+        // Editors often save by replacing the file, which drops a watch on the file itself,
+        // so the parent directory is watched instead.
+        let directory = path
+            .parent()
+            .ok_or_else(|| ShaderWatcherError::InvalidPath(path.display().to_string()))?
+            .to_path_buf();
+
+        if !self.directories.contains(&directory) {
+            self.watcher
+                .watch(&directory, RecursiveMode::NonRecursive)
+                .map_err(|e| ShaderWatcherError::WatchFailed(e.to_string()))?;
+
+            self.directories.insert(directory);
+        }
+
+        Ok(path)
+    }
+
+    /// Reloads the shaders whose files changed since the last call.
+    ///
+    /// A shader that fails to compile keeps running its previous version, the error is
+    /// returned so it can be shown to the user.
+    pub fn poll(&mut self) -> Vec<ShaderReload> {
+        self.shaders.retain(|shader| shader.is_alive());
+
+        // A single save usually emits several events, reload each file once
+        let mut changed = HashSet::new();
+        while let Ok(path) = self.receiver.try_recv() {
+            changed.insert(path);
+        }
+
+        let mut results = Vec::new();
+
+        for path in changed {
+            for shader in self.shaders.iter().filter(|shader| shader.contains(&path)) {
+                let result = match shader {
+                    WatchedShader::Graphics {
+                        inner,
+                        attrib,
+                        files,
+                    } => self.reload_graphics(inner, attrib, files),
+                    WatchedShader::Compute { inner, file } => self.reload_compute(inner, file),
+                };
+
+                match result {
+                    Ok(()) => {
+                        crate::log!("Reloaded shader {}", path.display());
+                        results.push(ShaderReload::Reloaded { path: path.clone() });
+                    }
+                    Err(error) => {
+                        crate::error_log!("Failed to reload shader {}: {}", path.display(), error);
+                        results.push(ShaderReload::Failed {
+                            path: path.clone(),
+                            error,
+                        });
+                    }
+                }
+            }
+        }
+
+        results
+    }
+
+    fn reload_graphics(
+        &self,
+        inner: &WeakRef<GraphicsShaderInner>,
+        attrib: &WeakRef<VertexInputDescription>,
+        files: &WatchedFiles,
+    ) -> Result<(), String> {
+        let (Some(inner), Some(attrib)) = (inner.upgrade(), attrib.upgrade()) else {
+            return Ok(());
+        };
+
+        let read = |path: &Path| {
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+        };
+
+        let (source, label) = match files {
+            WatchedFiles::Single(path) => (GraphicsShaderSource::Source(read(path)?), path),
+            WatchedFiles::Split(vertex, fragment) => (
+                GraphicsShaderSource::SplitSource(read(vertex)?, read(fragment)?),
+                vertex,
+            ),
+        };
+
+        let label = label.display().to_string();
+        let recreated =
+            GraphicsShader::new(ArcRef::clone(&self.graphics), source.clone(), Some(&label))?;
+
+        let dynamic_offsets = inner.borrow().dynamic_offsets.clone();
+        *inner.borrow_mut() = recreated.inner.borrow().clone();
+
+        // Dynamic offsets are set on the builder, not in the source
+        if !dynamic_offsets.is_empty() {
+            inner
+                .borrow_mut()
+                .set_dynamic_offsets(self.graphics.borrow().device(), dynamic_offsets);
+        }
+
+        // Keep the primitive state and step modes set on the shader, only the vertex layout
+        // follows the source
+        {
+            let recreated_attrib = recreated.attrib.borrow();
+            let mut attrib = attrib.borrow_mut();
+
+            let mut buffers = recreated_attrib.buffers.clone();
+            for (buffer, old) in buffers.iter_mut().zip(&attrib.buffers) {
+                buffer.step_mode = old.step_mode;
+            }
+
+            attrib.buffers = buffers;
+        }
+
+        self.graphics
+            .borrow_mut()
+            .resources
+            .update_graphics_shader_source(&inner, source);
+
+        Ok(())
+    }
+
+    fn reload_compute(&self, inner: &WeakRef<ComputeShaderInner>, file: &Path) -> Result<(), String> {
+        let Some(inner) = inner.upgrade() else {
+            return Ok(());
+        };
+
+        let source = std::fs::read_to_string(file)
+            .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+
+        let label = file.display().to_string();
+        let recreated = ComputeShader::new(ArcRef::clone(&self.graphics), &source, Some(&label))?;
+
+        {
+            let mut shader = inner.borrow_mut();
+            let recreated = recreated.inner.borrow();
+
+            shader.shader = recreated.shader.clone();
+            shader.reflection = recreated.reflection.clone();
+            shader.uniform_layouts = recreated.uniform_layouts.clone();
+            shader.bind_group_layouts = recreated.bind_group_layouts.clone();
+        }
+
+        self.graphics
+            .borrow_mut()
+            .resources
+            .update_compute_shader_source(&inner, source);
+
+        Ok(())
+    }
+}
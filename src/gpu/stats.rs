@@ -0,0 +1,71 @@
+use super::cache::CacheStats;
+
+/// Snapshot of the resources owned by a [GPU](super::GPU), see
+/// [GPU::stats](super::GPU::stats).
+///
+/// Byte sizes are the theoretical footprint of the resources, the driver may use more memory
+/// for alignment and padding.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GpuStats {
+    pub textures: u32,
+    pub texture_bytes: u64,
+    pub buffers: u32,
+    pub buffer_bytes: u64,
+    pub graphics_shaders: u32,
+    pub compute_shaders: u32,
+    /// Textures and buffers dropped while the GPU may still use them, released once their
+    /// submission completed.
+    pub pending_deletions: u32,
+
+    pub graphics_pipelines: u32,
+    pub compute_pipelines: u32,
+    pub bind_groups: u32,
+    pub pipeline_cache: CacheStats,
+    pub bind_group_cache: CacheStats,
+
+    /// Draws recorded during the last finished frame.
+    pub last_frame: DrawStats,
+}
+
+/// Draws recorded by render passes during a frame.
+///
+/// Indirect draws are counted as draw calls, but their triangles are unknown on the CPU and are
+/// not counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawStats {
+    pub draw_calls: u32,
+    pub triangles: u64,
+}
+
+/// Accumulates the draws of the current frame.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct FrameStatsTracker {
+    pub current: DrawStats,
+    pub last: DrawStats,
+}
+
+impl FrameStatsTracker {
+    pub fn add(&mut self, stats: DrawStats) {
+        self.current.draw_calls += stats.draw_calls;
+        self.current.triangles += stats.triangles;
+    }
+
+    pub fn end_frame(&mut self) {
+        self.last = std::mem::take(&mut self.current);
+    }
+}
+
+/// Returns the bytes used by all mip levels and samples of a texture.
+pub(crate) fn texture_bytes(texture: &wgpu::Texture) -> u64 {
+    let format = texture.format();
+    let size = texture.size();
+
+    let bytes: u64 = (0..texture.mip_level_count())
+        .map(|level| {
+            let mip_size = size.mip_level_size(level, texture.dimension());
+            format.theoretical_memory_footprint(mip_size)
+        })
+        .sum();
+
+    bytes * texture.sample_count() as u64
+}
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+
+use crate::{
+    math::{Point2, Rect},
+    utils::ArcRef,
+};
+
+use super::{
+    super::GPUInner,
+    TextureBuilder,
+    TextureError,
+    TextureUsage,
+    atlas::{AtlasRegion, AtlasSource, TextureAtlas, TextureAtlasBuilder, TextureAtlasBuilderError},
+};
+
+/// Delay used by frames without one, browsers do the same for GIFs.
+const DEFAULT_FRAME_DELAY: f32 = 0.1;
+
+/// Largest page created for the decoded frames.
+const MAX_PAGE_SIZE: i32 = 2048;
+
+/// A frame of an [AnimatedTexture].
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationFrame {
+    pub region: AtlasRegion,
+    /// Time the frame is shown, in seconds.
+    pub duration: f32,
+}
+
+/// A texture playing a sequence of frames, decoded from an animated GIF or PNG, or sliced
+/// from a sprite sheet.
+///
+/// ```rust,ignore
+/// let mut explosion = gpu
+///     .create_animated_texture()
+///     .set_file("assets/explosion.gif")
+///     .set_looping(false)
+///     .build()?;
+///
+/// explosion.update(dt);
+/// drawing.draw_animated_texture(&explosion, pos, size, Color::WHITE);
+/// ```
+#[derive(Debug, Clone)]
+pub struct AnimatedTexture {
+    atlas: TextureAtlas,
+    frames: Vec<AnimationFrame>,
+
+    index: usize,
+    elapsed: f32,
+    looping: bool,
+    speed: f32,
+}
+
+impl AnimatedTexture {
+    /// Advances the animation by `dt` seconds and returns the frame to draw.
+    pub fn current_frame(&mut self, dt: f32) -> AtlasRegion {
+        self.update(dt);
+        self.frame()
+    }
+
+    /// Advances the animation by `dt` seconds, frames shorter than `dt` are skipped.
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed += dt * self.speed;
+
+        // Whole loops end on the same frame
+        let total = self.duration();
+        if self.looping && self.elapsed >= total {
+            self.elapsed %= total;
+        }
+
+        while self.elapsed >= self.frames[self.index].duration {
+            if !self.looping && self.index + 1 == self.frames.len() {
+                self.elapsed = self.frames[self.index].duration;
+                return;
+            }
+
+            self.elapsed -= self.frames[self.index].duration;
+            self.index = (self.index + 1) % self.frames.len();
+        }
+    }
+
+    /// Returns the frame to draw, without advancing the animation.
+    pub fn frame(&self) -> AtlasRegion {
+        self.frames[self.index].region
+    }
+
+    pub fn frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+
+    pub fn frame_index(&self) -> usize {
+        self.index
+    }
+
+    /// Jumps to a frame, the index wraps around the frame count.
+    pub fn set_frame_index(&mut self, index: usize) {
+        self.index = index % self.frames.len();
+        self.elapsed = 0.0;
+    }
+
+    pub fn reset(&mut self) {
+        self.set_frame_index(0);
+    }
+
+    /// Returns true once a non looping animation reached the end of its last frame.
+    pub fn is_finished(&self) -> bool {
+        !self.looping
+            && self.index + 1 == self.frames.len()
+            && self.elapsed >= self.frames[self.index].duration
+    }
+
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Sets the playback speed, 1.0 being the speed of the source.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.0);
+    }
+
+    /// Returns the total length of the animation, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+
+    /// Returns the atlas holding the frames, to draw them with the DrawingContext.
+    pub fn atlas(&self) -> &TextureAtlas {
+        &self.atlas
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum AnimatedTextureError {
+    FileNotFound(String),
+    /// The data is not an animated GIF or PNG, or it failed to decode.
+    InvalidData(String),
+    /// The animation has no frame, or the sprite sheet grid is empty.
+    NoFrames,
+    Atlas(TextureAtlasBuilderError),
+    Texture(TextureError),
+}
+
+impl std::fmt::Display for AnimatedTextureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnimatedTextureError::FileNotFound(file) => write!(f, "Animation file not found: {}", file),
+            AnimatedTextureError::InvalidData(reason) => write!(f, "Invalid animation data: {}", reason),
+            AnimatedTextureError::NoFrames => write!(f, "Animation has no frames"),
+            AnimatedTextureError::Atlas(err) => write!(f, "Failed to pack animation frames: {}", err),
+            AnimatedTextureError::Texture(err) => write!(f, "Failed to create animation texture: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for AnimatedTextureError {}
+
+/// Source of the frames of an [AnimatedTexture].
+#[derive(Debug, Clone)]
+enum AnimationSource {
+    File(String),
+    Memory(Vec<u8>),
+    SpriteSheet {
+        source: AtlasSource,
+        frame_size: Point2,
+        frame_count: Option<usize>,
+        frame_duration: f32,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimatedTextureBuilder {
+    gpu: ArcRef<GPUInner>,
+    source: Option<AnimationSource>,
+    looping: bool,
+}
+
+impl AnimatedTextureBuilder {
+    pub(crate) fn new(gpu: ArcRef<GPUInner>) -> Self {
+        Self {
+            gpu,
+            source: None,
+            looping: true,
+        }
+    }
+
+    /// Decodes an animated GIF or PNG file.
+    pub fn set_file(mut self, path: &str) -> Self {
+        self.source = Some(AnimationSource::File(path.to_string()));
+        self
+    }
+
+    /// Decodes animated GIF or PNG file data.
+    pub fn set_file_data(mut self, data: &[u8]) -> Self {
+        self.source = Some(AnimationSource::Memory(data.to_vec()));
+        self
+    }
+
+    /// Slices a sprite sheet into frames of `frame_size`, left to right then top to bottom.
+    ///
+    /// `frame_count` limits the frames when the last row is not full, every frame is shown for
+    /// `frame_duration` seconds.
+    pub fn set_sprite_sheet(
+        mut self,
+        source: AtlasSource,
+        frame_size: Point2,
+        frame_count: Option<usize>,
+        frame_duration: f32,
+    ) -> Self {
+        self.source = Some(AnimationSource::SpriteSheet {
+            source,
+            frame_size,
+            frame_count,
+            frame_duration,
+        });
+        self
+    }
+
+    /// Whether the animation starts over after its last frame, true by default.
+    pub fn set_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn build(self) -> Result<AnimatedTexture, AnimatedTextureError> {
+        let source = self.source.ok_or(AnimatedTextureError::NoFrames)?;
+
+        let (atlas, frames) = match source {
+            AnimationSource::File(path) => {
+                let data = std::fs::read(&path)
+                    .map_err(|_| AnimatedTextureError::FileNotFound(path.clone()))?;
+
+                pack_frames(&self.gpu, decode_frames(&data)?)?
+            }
+            AnimationSource::Memory(data) => pack_frames(&self.gpu, decode_frames(&data)?)?,
+            AnimationSource::SpriteSheet {
+                source,
+                frame_size,
+                frame_count,
+                frame_duration,
+            } => slice_sprite_sheet(
+                &self.gpu,
+                source,
+                frame_size,
+                frame_count,
+                frame_duration.max(f32::EPSILON),
+            )?,
+        };
+
+        if frames.is_empty() {
+            return Err(AnimatedTextureError::NoFrames);
+        }
+
+        Ok(AnimatedTexture {
+            atlas,
+            frames,
+            index: 0,
+            elapsed: 0.0,
+            looping: self.looping,
+            speed: 1.0,
+        })
+    }
+}
+
+/// Decoded RGBA8 frame, with its size and duration in seconds.
+type DecodedFrame = (Vec<u8>, Point2, f32);
+
+fn decode_frames(data: &[u8]) -> Result<Vec<DecodedFrame>, AnimatedTextureError> {
+    use image::AnimationDecoder;
+
+    let cursor = std::io::Cursor::new(data);
+    let invalid = |err: image::ImageError| AnimatedTextureError::InvalidData(err.to_string());
+
+    let frames = match image::guess_format(data).map_err(invalid)? {
+        image::ImageFormat::Gif => image::codecs::gif::GifDecoder::new(cursor)
+            .map_err(invalid)?
+            .into_frames()
+            .collect_frames()
+            .map_err(invalid)?,
+        image::ImageFormat::Png => {
+            let decoder = image::codecs::png::PngDecoder::new(cursor).map_err(invalid)?;
+            if !decoder.is_apng().map_err(invalid)? {
+                return Err(AnimatedTextureError::InvalidData(
+                    "PNG file is not animated".to_string(),
+                ));
+            }
+
+            decoder
+                .apng()
+                .map_err(invalid)?
+                .into_frames()
+                .collect_frames()
+                .map_err(invalid)?
+        }
+        format => {
+            return Err(AnimatedTextureError::InvalidData(format!(
+                "{:?} images are not animated",
+                format
+            )));
+        }
+    };
+
+    let frames = frames
+        .into_iter()
+        .map(|frame| {
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            let delay = if numer == 0 {
+                DEFAULT_FRAME_DELAY
+            } else {
+                numer as f32 / denom.max(1) as f32 / 1000.0
+            };
+
+            let buffer = frame.into_buffer();
+            let size = Point2::new(buffer.width() as i32, buffer.height() as i32);
+
+            (buffer.into_raw(), size, delay)
+        })
+        .collect();
+
+    Ok(frames)
+}
+
+/// Packs the decoded frames into pages just large enough to hold them.
+fn pack_frames(
+    gpu: &ArcRef<GPUInner>,
+    frames: Vec<DecodedFrame>,
+) -> Result<(TextureAtlas, Vec<AnimationFrame>), AnimatedTextureError> {
+    let Some((_, frame_size, _)) = frames.first() else {
+        return Err(AnimatedTextureError::NoFrames);
+    };
+
+    // Every frame has the size of the canvas, with one pixel of padding around it
+    let cell = Point2::new(frame_size.x + 1, frame_size.y + 1);
+    let area = cell.x as i64 * cell.y as i64 * frames.len() as i64;
+
+    let mut side = 64;
+    while (side as i64 * side as i64) < area * 5 / 4 && side < MAX_PAGE_SIZE {
+        side *= 2;
+    }
+
+    let page_size = Point2::new(side.max(cell.x + 1), side.max(cell.y + 1));
+
+    let mut atlas = TextureAtlasBuilder::new(gpu.clone())
+        .set_page_size(page_size)
+        .set_max_pages(usize::MAX)
+        .build()
+        .map_err(AnimatedTextureError::Atlas)?;
+
+    let mut regions = Vec::with_capacity(frames.len());
+    for (pixels, size, duration) in frames {
+        let region = atlas
+            .insert(AtlasSource::Raw(pixels, size.x as u32, size.y as u32))
+            .map_err(AnimatedTextureError::Atlas)?;
+
+        regions.push(AnimationFrame { region, duration });
+    }
+
+    Ok((atlas, regions))
+}
+
+/// Uploads the sprite sheet as is and maps its grid cells to regions.
+fn slice_sprite_sheet(
+    gpu: &ArcRef<GPUInner>,
+    source: AtlasSource,
+    frame_size: Point2,
+    frame_count: Option<usize>,
+    frame_duration: f32,
+) -> Result<(TextureAtlas, Vec<AnimationFrame>), AnimatedTextureError> {
+    let builder = TextureBuilder::new(gpu.clone()).set_usage(TextureUsage::Sampler);
+    let texture = match &source {
+        AtlasSource::File(path) => builder.set_file(path).build(),
+        AtlasSource::Memory(data) => builder.set_file_data(data).build(),
+        AtlasSource::Raw(data, width, height) => {
+            let format = if gpu.borrow().is_srgb() {
+                super::TextureFormat::Rgba8UnormSrgb
+            } else {
+                super::TextureFormat::Rgba8Unorm
+            };
+
+            builder
+                .set_raw_image(data, Point2::new(*width as i32, *height as i32), format)
+                .build()
+        }
+    }
+    .map_err(AnimatedTextureError::Texture)?;
+
+    let sheet_size = texture.size();
+    if frame_size.x <= 0 || frame_size.y <= 0 {
+        return Err(AnimatedTextureError::NoFrames);
+    }
+
+    let columns = (sheet_size.x / frame_size.x) as usize;
+    let rows = (sheet_size.y / frame_size.y) as usize;
+    let count = frame_count.unwrap_or(columns * rows).min(columns * rows);
+
+    let frames = (0..count)
+        .map(|index| {
+            let rect = Rect::new(
+                (index % columns) as i32 * frame_size.x,
+                (index / columns) as i32 * frame_size.y,
+                frame_size.x,
+                frame_size.y,
+            );
+
+            AnimationFrame {
+                region: AtlasRegion::from_rect(0, rect, sheet_size, None),
+                duration: frame_duration,
+            }
+        })
+        .collect();
+
+    Ok((TextureAtlas::new(texture, HashMap::new()), frames))
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::{math::{Point2, RectF}, utils::ArcRef};
+use crate::{math::{Point2, Rect, RectF}, utils::ArcRef};
 
 use super::{
     super::GPUInner,
@@ -13,60 +13,359 @@ use super::{
 
 /// Represents a texture atlas containing multiple textures
 /// and their UV coordinates
+///
+/// Images can be inserted and removed at runtime with [TextureAtlas::insert] and
+/// [TextureAtlas::remove]. When the pages are full a new page is created, up to the limit set
+/// with [TextureAtlasBuilder::set_max_pages].
 #[derive(Debug, Clone)]
 pub struct TextureAtlas {
-    pub(crate) texture: Texture,
-    pub(crate) items: HashMap<String, TextureAtlasCoord>,
+    pub(crate) pages: Vec<AtlasPage>,
+    pub(crate) items: HashMap<String, AtlasRegion>,
+
+    pub(crate) page_size: Point2,
+    pub(crate) max_pages: usize,
 }
 
+/// A texture of the atlas, along with its free space when images can be inserted in it.
 #[derive(Debug, Clone)]
-pub(crate) struct TextureAtlasCoord {
-    pub rect_uv: RectF,
+pub(crate) struct AtlasPage {
+    pub texture: Texture,
+    /// [None] for the pages packed by the builder, which have no free space.
+    pub allocator: Option<ShelfAllocator>,
+}
+
+/// Border insets of a nine-slice region, in pixels.
+///
+/// The corners keep their size when the region is stretched, the edges stretch along one
+/// axis and the center along both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NineSlice {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl NineSlice {
+    pub fn new(left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        Self { left, top, right, bottom }
+    }
+
+    /// Same inset on every side.
+    pub fn uniform(inset: i32) -> Self {
+        Self::new(inset, inset, inset, inset)
+    }
+
+    /// Whether the insets fit inside a region of the given size.
+    pub(crate) fn fits(&self, size: Point2) -> bool {
+        self.left >= 0
+            && self.top >= 0
+            && self.right >= 0
+            && self.bottom >= 0
+            && self.left + self.right <= size.x
+            && self.top + self.bottom <= size.y
+    }
+}
+
+/// A region of a texture atlas.
+#[derive(Debug, Clone, Copy)]
+pub struct AtlasRegion {
+    /// UV rectangle of the region, as (x0, y0, x1, y1).
+    pub uv: RectF,
+    /// Size of the region in pixels.
     pub size: Point2,
+    pub nine_slice: Option<NineSlice>,
+    /// Page of the atlas holding the region, see [TextureAtlas::page_texture].
+    pub page: usize,
+    /// Placement of the region in its page, in pixels.
+    pub rect: Rect,
+}
+
+impl AtlasRegion {
+    /// Computes the UV rectangle of a packed item, inset by half a texel to avoid bleeding.
+    pub(crate) fn from_rect(
+        page: usize,
+        rect: Rect,
+        atlas_size: Point2,
+        nine_slice: Option<NineSlice>,
+    ) -> Self {
+        let atlas_w = atlas_size.x as f32;
+        let atlas_h = atlas_size.y as f32;
+        let half_texel_x = 0.5 / atlas_w;
+        let half_texel_y = 0.5 / atlas_h;
+
+        let rect_uv = RectF::new(
+            (rect.x as f32 + half_texel_x) / atlas_w,
+            (rect.y as f32 + half_texel_y) / atlas_h,
+            (rect.x as f32 + rect.w as f32 - half_texel_x) / atlas_w,
+            (rect.y as f32 + rect.h as f32 - half_texel_y) / atlas_h,
+        );
+
+        Self {
+            uv: rect_uv,
+            size: Point2::new(rect.w, rect.h),
+            nine_slice,
+            page,
+            rect,
+        }
+    }
+
+    fn same_placement(&self, other: &AtlasRegion) -> bool {
+        self.page == other.page
+            && self.rect.x == other.rect.x
+            && self.rect.y == other.rect.y
+            && self.rect.w == other.rect.w
+            && self.rect.h == other.rect.h
+    }
 }
 
 impl TextureAtlas {
-    pub(crate) fn new(texture: Texture, items: HashMap<String, TextureAtlasCoord>) -> Self {
-        Self { texture, items }
+    pub(crate) fn new(texture: Texture, items: HashMap<String, AtlasRegion>) -> Self {
+        Self {
+            pages: vec![AtlasPage {
+                texture,
+                allocator: None,
+            }],
+            items,
+            page_size: DEFAULT_PAGE_SIZE,
+            max_pages: DEFAULT_MAX_PAGES,
+        }
     }
 
     /// Retrieves the UV rectangle and size for a given texture ID
     pub fn get_id(&self, id: &str) -> Option<(RectF, Point2)> {
-        self.items.get(id).map(|coord| (coord.rect_uv, coord.size))
+        self.items.get(id).map(|region| (region.uv, region.size))
+    }
+
+    /// Retrieves a region by name, including its nine-slice insets.
+    ///
+    /// The region can be drawn with [DrawingContext::draw_atlas_region](crate::gpu::command::drawing::DrawingContext::draw_atlas_region).
+    pub fn region(&self, name: &str) -> Option<AtlasRegion> {
+        self.items.get(name).copied()
     }
 
-    /// Get the texture associated with this atlas
+    /// Get the texture associated with this atlas, the first page when the atlas grew.
     pub fn get_texture(&self) -> &Texture {
-        &self.texture
+        &self.pages[0].texture
     }
 
-    /// Get the size of the texture atlas
+    /// Get the size of the texture atlas, the size of its first page.
     pub fn get_texture_size(&self) -> Point2 {
-        let inner = self.texture.inner.borrow();
+        let inner = self.pages[0].texture.inner.borrow();
 
         Point2::new(inner.size.x as i32, inner.size.y as i32)
     }
+
+    /// Returns the number of textures of the atlas.
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Returns the texture of a page.
+    pub fn page_texture(&self, page: usize) -> Option<&Texture> {
+        self.pages.get(page).map(|page| &page.texture)
+    }
+
+    /// Returns the texture holding a region.
+    pub fn region_texture(&self, region: &AtlasRegion) -> &Texture {
+        &self.pages[region.page].texture
+    }
+
+    /// Packs an image into the atlas, creating a new page when the current ones are full.
+    ///
+    /// The returned region is drawn with
+    /// [DrawingContext::draw_region](crate::gpu::command::drawing::DrawingContext::draw_region)
+    /// and freed with [TextureAtlas::remove].
+    ///
+    /// ```rust,ignore
+    /// let avatar = atlas.insert(AtlasSource::Memory(downloaded_png))?;
+    /// drawing.draw_region(&atlas, &avatar, Vector2::new(8.0, 8.0), Vector2::new(64.0, 64.0), Color::WHITE);
+    /// atlas.remove(&avatar);
+    /// ```
+    pub fn insert(&mut self, source: AtlasSource) -> Result<AtlasRegion, TextureAtlasBuilderError> {
+        let (data, size) = decode_source("<inserted>", source)?;
+        self.insert_pixels(&data, size)
+    }
+
+    /// Packs an image into the atlas under a name, replacing the region previously inserted
+    /// with that name.
+    pub fn insert_named(
+        &mut self,
+        name: &str,
+        source: AtlasSource,
+    ) -> Result<AtlasRegion, TextureAtlasBuilderError> {
+        let (data, size) = decode_source(name, source)?;
+        let region = self.insert_pixels(&data, size)?;
+
+        if let Some(previous) = self.items.insert(name.to_string(), region) {
+            self.free(&previous);
+        }
+
+        Ok(region)
+    }
+
+    /// Frees the space of a region, along with the names pointing to it. Returns false when
+    /// the region is not part of the atlas.
+    ///
+    /// The space of the regions packed by [TextureAtlasBuilder::build] without a page size is
+    /// not reused.
+    pub fn remove(&mut self, region: &AtlasRegion) -> bool {
+        let names = self.items.len();
+        self.items.retain(|_, item| !item.same_placement(region));
+
+        self.free(region) || self.items.len() != names
+    }
+
+    fn free(&mut self, region: &AtlasRegion) -> bool {
+        let Some(allocator) = self
+            .pages
+            .get_mut(region.page)
+            .and_then(|page| page.allocator.as_mut())
+        else {
+            return false;
+        };
+
+        allocator.deallocate(region.rect)
+    }
+
+    fn insert_pixels(&mut self, data: &[u8], size: Point2) -> Result<AtlasRegion, TextureAtlasBuilderError> {
+        if size.x > self.page_size.x - PAGE_PADDING * 2 || size.y > self.page_size.y - PAGE_PADDING * 2 {
+            return Err(TextureAtlasBuilderError::ExceedsMaxSize(size.x, size.y));
+        }
+
+        let placement = self.pages.iter_mut().enumerate().find_map(|(index, page)| {
+            let rect = page.allocator.as_mut()?.allocate(size)?;
+            Some((index, rect))
+        });
+
+        let (page, rect) = match placement {
+            Some(placement) => placement,
+            None => {
+                if self.pages.len() >= self.max_pages {
+                    return Err(TextureAtlasBuilderError::AtlasFull);
+                }
+
+                let mut allocator = ShelfAllocator::new(self.page_size);
+                let rect = allocator
+                    .allocate(size)
+                    .ok_or(TextureAtlasBuilderError::ExceedsMaxSize(size.x, size.y))?;
+
+                let texture = create_page_texture(&self.pages[0].texture.graphics, self.page_size)?;
+                self.pages.push(AtlasPage {
+                    texture,
+                    allocator: Some(allocator),
+                });
+
+                (self.pages.len() - 1, rect)
+            }
+        };
+
+        let texture = &self.pages[page].texture;
+        texture.write_rect_raw(rect, data);
+
+        Ok(AtlasRegion::from_rect(page, rect, texture.size(), None))
+    }
+}
+
+/// Creates an empty RGBA8 page for the runtime insertions.
+fn create_page_texture(
+    gpu: &ArcRef<GPUInner>,
+    size: Point2,
+) -> Result<Texture, TextureAtlasBuilderError> {
+    let pixels = vec![0u8; (size.x * size.y * 4) as usize];
+
+    TextureBuilder::new(gpu.clone())
+        .set_raw_image(&pixels, size, atlas_format(gpu))
+        .set_usage(TextureUsage::Sampler)
+        .set_label("Texture Atlas Page")
+        .build()
+        .map_err(TextureAtlasBuilderError::TextureCreationError)
 }
 
-const MAX_WIDTH_SIZE: i32 = 2048;
+fn atlas_format(gpu: &ArcRef<GPUInner>) -> TextureFormat {
+    if gpu.borrow().is_srgb() {
+        TextureFormat::Rgba8UnormSrgb
+    } else {
+        TextureFormat::Rgba8Unorm
+    }
+}
+
+pub(crate) const MAX_WIDTH_SIZE: i32 = 2048;
+
+const DEFAULT_PAGE_SIZE: Point2 = Point2 { x: 1024, y: 1024 };
+const DEFAULT_MAX_PAGES: usize = 2;
+
+/// Empty pixels around the page and between the regions inserted at runtime.
+const PAGE_PADDING: i32 = 1;
 
 #[derive(Debug, Clone)]
 pub struct TextureAtlasBuilder {
     pub(crate) gpu: ArcRef<GPUInner>,
-    pub(crate) items: HashMap<String, ItemQueue>,
+    pub(crate) items: HashMap<String, AtlasSource>,
+    pub(crate) nine_slices: HashMap<String, NineSlice>,
+    pub(crate) page_size: Option<Point2>,
+    pub(crate) max_pages: usize,
 }
 
+/// Source image of an item packed into a texture atlas.
 #[derive(Debug, Clone)]
-pub(crate) enum ItemQueue {
-    File(String),           // id, file path
-    Memory(Vec<u8>),        // id, raw data
-    Raw(Vec<u8>, u32, u32), // id, raw data, width, height
+pub enum AtlasSource {
+    /// Path to an image file.
+    File(String),
+    /// Encoded image file data (PNG, JPEG, etc).
+    Memory(Vec<u8>),
+    /// RGBA8 pixels, with the width and height of the image.
+    Raw(Vec<u8>, u32, u32),
+}
+
+/// Pixels and item placements of a packed atlas, before uploading it to the GPU.
+#[derive(Debug, Clone)]
+pub(crate) struct PackedAtlas {
+    /// RGBA8 pixels of the whole atlas.
+    pub pixels: Vec<u8>,
+    pub size: Point2,
+    /// Items sorted by id.
+    pub items: Vec<PackedItem>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PackedItem {
+    pub id: String,
+    /// Placement in pixels.
+    pub rect: Rect,
+    pub nine_slice: Option<NineSlice>,
+}
+
+impl PackedAtlas {
+    pub fn set_nine_slice(
+        &mut self,
+        id: &str,
+        nine_slice: NineSlice,
+    ) -> Result<(), TextureAtlasBuilderError> {
+        let item = self
+            .items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| TextureAtlasBuilderError::InvalidData(format!("Missing data for id: {}", id)))?;
+
+        if !nine_slice.fits(Point2::new(item.rect.w, item.rect.h)) {
+            return Err(TextureAtlasBuilderError::InvalidData(format!(
+                "Nine-slice insets exceed the size of id: {}",
+                id
+            )));
+        }
+
+        item.nine_slice = Some(nine_slice);
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum TextureAtlasBuilderError {
     EmptyAtlas,
     ExceedsMaxSize(i32, i32),
+    /// Every page is full and the atlas cannot grow anymore.
+    AtlasFull,
     FileNotFound(String),
     InvalidData(String),
     TextureCreationError(TextureError),
@@ -81,6 +380,9 @@ impl std::fmt::Display for TextureAtlasBuilderError {
                 "Texture atlas exceeds maximum size: {}x{}",
                 width, height
             ),
+            TextureAtlasBuilderError::AtlasFull => {
+                write!(f, "Texture atlas is full and reached its maximum page count")
+            }
             TextureAtlasBuilderError::FileNotFound(file) => {
                 write!(f, "Texture file not found: {}", file)
             }
@@ -98,165 +400,372 @@ impl TextureAtlasBuilder {
     pub(crate) fn new(gpu: ArcRef<GPUInner>) -> Self {
         Self {
             items: HashMap::new(),
+            nine_slices: HashMap::new(),
+            page_size: None,
+            max_pages: DEFAULT_MAX_PAGES,
             gpu,
         }
     }
 
     pub fn add_texture_file(mut self, id: &str, file: &str) -> Self {
         self.items
-            .insert(id.to_string(), ItemQueue::File(file.to_string()));
+            .insert(id.to_string(), AtlasSource::File(file.to_string()));
         self
     }
 
     pub fn add_texture_file_buf(mut self, id: &str, data: &[u8]) -> Self {
         self.items
-            .insert(id.to_string(), ItemQueue::Memory(data.to_vec()));
+            .insert(id.to_string(), AtlasSource::Memory(data.to_vec()));
         self
     }
 
     pub fn add_texture_raw(mut self, id: &str, data: Vec<u8>, width: u32, height: u32) -> Self {
         self.items
-            .insert(id.to_string(), ItemQueue::Raw(data, width, height));
+            .insert(id.to_string(), AtlasSource::Raw(data, width, height));
+        self
+    }
+
+    /// Marks a texture as a nine-slice region, see [NineSlice].
+    pub fn set_nine_slice(mut self, id: &str, nine_slice: NineSlice) -> Self {
+        self.nine_slices.insert(id.to_string(), nine_slice);
+        self
+    }
+
+    /// Packs the textures into pages of the given size, keeping their free space for
+    /// [TextureAtlas::insert]. The atlas may then be built without any texture.
+    ///
+    /// Without a page size the textures are packed as tightly as possible, and the runtime
+    /// insertions go to new pages of 1024x1024.
+    pub fn set_page_size(mut self, size: Point2) -> Self {
+        self.page_size = Some(size);
+        self
+    }
+
+    /// Limits the number of pages the atlas may grow to, 2 by default.
+    pub fn set_max_pages(mut self, count: usize) -> Self {
+        self.max_pages = count.max(1);
         self
     }
 
     pub fn build(self) -> Result<TextureAtlas, TextureAtlasBuilderError> {
-        if self.items.is_empty() {
-            return Err(TextureAtlasBuilderError::EmptyAtlas);
-        }
+        let Some(page_size) = self.page_size else {
+            let mut packed = pack_items(self.items)?;
 
-        let mut texture_items = HashMap::new();
+            for (id, nine_slice) in self.nine_slices {
+                packed.set_nine_slice(&id, nine_slice)?;
+            }
 
-        for (id, item) in self.items {
-            use image::GenericImageView;
+            let mut atlas = TextureAtlas::from_packed(self.gpu, &packed)?;
+            atlas.max_pages = self.max_pages;
+            return Ok(atlas);
+        };
 
-            let (texture_data, size) = match item {
-                ItemQueue::File(file) => {
-                    if !std::path::Path::new(&file).exists() {
-                        return Err(TextureAtlasBuilderError::FileNotFound(file));
-                    }
+        if page_size.x <= PAGE_PADDING * 2
+            || page_size.y <= PAGE_PADDING * 2
+            || page_size.x > MAX_WIDTH_SIZE
+            || page_size.y > MAX_WIDTH_SIZE
+        {
+            return Err(TextureAtlasBuilderError::ExceedsMaxSize(page_size.x, page_size.y));
+        }
 
-                    let canonical_path = std::fs::canonicalize(&file)
-                        .map_err(|_| TextureAtlasBuilderError::FileNotFound(file.clone()))?;
+        let texture = create_page_texture(&self.gpu, page_size)?;
+        let mut atlas = TextureAtlas {
+            pages: vec![AtlasPage {
+                texture,
+                allocator: Some(ShelfAllocator::new(page_size)),
+            }],
+            items: HashMap::new(),
+            page_size,
+            max_pages: self.max_pages,
+        };
 
-                    let image = image::open(&canonical_path)
-                        .map_err(|_| TextureAtlasBuilderError::InvalidData(file.clone()))?;
+        let mut items = self.items.into_iter().collect::<Vec<_>>();
+        items.sort_by(|a, b| a.0.cmp(&b.0));
 
-                    let (width, height) = image.dimensions();
-                    let data = image.to_rgba8();
+        for (id, source) in items {
+            let region = atlas.insert_named(&id, source)?;
 
-                    (data.to_vec(), Point2::new(width as i32, height as i32))
+            if let Some(nine_slice) = self.nine_slices.get(&id) {
+                if !nine_slice.fits(region.size) {
+                    return Err(TextureAtlasBuilderError::InvalidData(format!(
+                        "Nine-slice insets exceed the size of id: {}",
+                        id
+                    )));
                 }
-                ItemQueue::Memory(data) => {
-                    let image = image::load_from_memory(&data)
-                        .map_err(|_| TextureAtlasBuilderError::InvalidData(id.clone()))?;
 
-                    let (width, height) = image.dimensions();
-                    let data = image.to_rgba8();
+                atlas.items.get_mut(&id).unwrap().nine_slice = Some(*nine_slice);
+            }
+        }
 
-                    (data.to_vec(), Point2::new(width as i32, height as i32))
-                }
-                ItemQueue::Raw(data, width, height) => {
-                    if data.len() != (width * height * 4) as usize {
-                        return Err(TextureAtlasBuilderError::InvalidData(id.clone()));
-                    }
+        Ok(atlas)
+    }
+}
 
-                    let size = Point2::new(width as i32, height as i32);
-                    (data, size)
-                }
-            };
+impl TextureAtlas {
+    pub(crate) fn from_packed(
+        gpu: ArcRef<GPUInner>,
+        packed: &PackedAtlas,
+    ) -> Result<Self, TextureAtlasBuilderError> {
+        let items = packed
+            .items
+            .iter()
+            .map(|item| {
+                let region = AtlasRegion::from_rect(0, item.rect, packed.size, item.nine_slice);
+                (item.id.clone(), region)
+            })
+            .collect();
+
+        let format = atlas_format(&gpu);
+
+        let texture = TextureBuilder::new(gpu)
+            .set_raw_image(&packed.pixels, packed.size, format)
+            .set_usage(TextureUsage::Sampler)
+            .build()
+            .map_err(TextureAtlasBuilderError::TextureCreationError)?;
+
+        Ok(TextureAtlas::new(texture, items))
+    }
+}
+
+/// Decodes and packs the items into a single RGBA8 image.
+///
+/// Items are packed in id order, so the same inputs always produce the same atlas.
+pub(crate) fn pack_items<I>(items: I) -> Result<PackedAtlas, TextureAtlasBuilderError>
+where
+    I: IntoIterator<Item = (String, AtlasSource)>,
+{
+    let mut items: Vec<(String, AtlasSource)> = items.into_iter().collect();
+    if items.is_empty() {
+        return Err(TextureAtlasBuilderError::EmptyAtlas);
+    }
 
-            texture_items.insert(id.to_string(), (texture_data, size));
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut texture_items = Vec::with_capacity(items.len());
+
+    for (id, item) in items {
+        let (texture_data, size) = decode_source(&id, item)?;
+        texture_items.push((id, texture_data, size));
+    }
+
+    let rect_config = rect_packer::Config {
+        width: MAX_WIDTH_SIZE,
+        height: MAX_WIDTH_SIZE,
+        border_padding: 1,
+        rectangle_padding: 1,
+    };
+
+    let mut packer = rect_packer::Packer::new(rect_config);
+    let mut placements = Vec::with_capacity(texture_items.len());
+    let mut atlas_size = Point2::new(0, 0);
+
+    for (id, _, size) in &texture_items {
+        if size.x > MAX_WIDTH_SIZE || size.y > MAX_WIDTH_SIZE {
+            return Err(TextureAtlasBuilderError::ExceedsMaxSize(
+                size.x,
+                size.y,
+            ));
         }
 
-        let rect_config = rect_packer::Config {
-            width: MAX_WIDTH_SIZE as i32,
-            height: MAX_WIDTH_SIZE as i32,
-            border_padding: 1,
-            rectangle_padding: 1,
-        };
+        let rect = packer.pack(size.x, size.y, false)
+            .ok_or_else(|| {
+            TextureAtlasBuilderError::InvalidData(format!(
+                "Failed to pack texture with id: {}",
+                id
+            ))
+        })?;
+
+        placements.push(Rect::new(rect.x, rect.y, rect.width, rect.height));
+        atlas_size.x = atlas_size.x.max(rect.x + rect.width);
+        atlas_size.y = atlas_size.y.max(rect.y + rect.height);
+    }
+
+    if atlas_size.x > MAX_WIDTH_SIZE || atlas_size.y > MAX_WIDTH_SIZE {
+        return Err(TextureAtlasBuilderError::ExceedsMaxSize(atlas_size.x, atlas_size.y));
+    }
 
-        let mut packer = rect_packer::Packer::new(rect_config);
-        let mut placemenets = HashMap::new();
-        let mut atlas_size = Point2::new(0, 0);
+    let mut pixels = vec![0; (atlas_size.x * atlas_size.y * 4) as usize];
+    let mut items = Vec::with_capacity(texture_items.len());
+    for ((id, data, size), rect) in texture_items.into_iter().zip(placements) {
+        for j in 0..size.y {
+            for i in 0..size.x {
+                let src_index = ((j * size.x + i) * 4) as usize;
+                let dst_index = (((rect.y + j) * atlas_size.x + (rect.x + i)) * 4) as usize;
 
-        for (id, (_, size)) in &texture_items {
-            if size.x > MAX_WIDTH_SIZE || size.y > MAX_WIDTH_SIZE {
-                return Err(TextureAtlasBuilderError::ExceedsMaxSize(
-                    size.x,
-                    size.y,
-                ));
+                pixels[dst_index..dst_index + 4]
+                    .copy_from_slice(&data[src_index..src_index + 4]);
             }
+        }
+
+        items.push(PackedItem {
+            id,
+            rect,
+            nine_slice: None,
+        });
+    }
+
+    Ok(PackedAtlas {
+        pixels,
+        size: atlas_size,
+        items,
+    })
+}
+
+/// Decodes an item into RGBA8 pixels and its size.
+fn decode_source(id: &str, source: AtlasSource) -> Result<(Vec<u8>, Point2), TextureAtlasBuilderError> {
+    use image::GenericImageView;
+
+    match source {
+        AtlasSource::File(file) => {
+            if !std::path::Path::new(&file).exists() {
+                return Err(TextureAtlasBuilderError::FileNotFound(file));
+            }
+
+            let canonical_path = std::fs::canonicalize(&file)
+                .map_err(|_| TextureAtlasBuilderError::FileNotFound(file.clone()))?;
+
+            let image = image::open(&canonical_path)
+                .map_err(|_| TextureAtlasBuilderError::InvalidData(file.clone()))?;
 
-            let rect = packer.pack(size.x, size.y, false)
-                .ok_or_else(|| {
-                TextureAtlasBuilderError::InvalidData(format!(
-                    "Failed to pack texture with id: {}",
-                    id
-                ))
-            })?;
-
-            placemenets.insert(id.clone(), rect);
-            atlas_size.x = atlas_size.x.max(rect.x + rect.width);
-            atlas_size.y = atlas_size.y.max(rect.y + rect.height);
+            let (width, height) = image.dimensions();
+            let data = image.to_rgba8();
+
+            Ok((data.to_vec(), Point2::new(width as i32, height as i32)))
         }
+        AtlasSource::Memory(data) => {
+            let image = image::load_from_memory(&data)
+                .map_err(|_| TextureAtlasBuilderError::InvalidData(id.to_string()))?;
+
+            let (width, height) = image.dimensions();
+            let data = image.to_rgba8();
 
-        if atlas_size.x > MAX_WIDTH_SIZE || atlas_size.y > MAX_WIDTH_SIZE {
-            return Err(TextureAtlasBuilderError::ExceedsMaxSize(atlas_size.x, atlas_size.y));
+            Ok((data.to_vec(), Point2::new(width as i32, height as i32)))
         }
+        AtlasSource::Raw(data, width, height) => {
+            if data.len() != (width * height * 4) as usize {
+                return Err(TextureAtlasBuilderError::InvalidData(id.to_string()));
+            }
 
-        let mut texture_data = vec![0; (atlas_size.x * atlas_size.y * 4) as usize];
-        let mut items = HashMap::new();
-        for (id, rect) in placemenets {
-            let (data, size) = texture_items.get(&id).ok_or_else(|| {
-                TextureAtlasBuilderError::InvalidData(format!("Missing data for id: {}", id))
-            })?;
-
-            let atlas_w = atlas_size.x as f32;
-            let atlas_h = atlas_size.y as f32;
-            let half_texel_x = 0.5 / atlas_w;
-            let half_texel_y = 0.5 / atlas_h;
-
-            let rect_uv = RectF::new(
-                (rect.x as f32 + half_texel_x) / atlas_w,
-                (rect.y as f32 + half_texel_y) / atlas_h,
-                (rect.x as f32 + rect.width as f32 - half_texel_x) / atlas_w,
-                (rect.y as f32 + rect.height as f32 - half_texel_y) / atlas_h,
-            );
-
-            let size = Point2::new(size.x, size.y);
-
-            for j in 0..size.y {
-                for i in 0..size.x {
-                    let src_index = ((j * size.x + i) * 4) as usize;
-                    let dst_index = (((rect.y + j) * atlas_size.x + (rect.x + i)) * 4) as usize;
-
-                    texture_data[dst_index..dst_index + 4]
-                        .copy_from_slice(&data[src_index..src_index + 4]);
+            Ok((data, Point2::new(width as i32, height as i32)))
+        }
+    }
+}
+
+/// Shelf packing with free space reuse, for the pages filled at runtime.
+///
+/// Regions are placed left to right on horizontal shelves, a freed region becomes a free span
+/// of its shelf, and the empty shelves at the bottom of the page are dropped.
+#[derive(Debug, Clone)]
+pub(crate) struct ShelfAllocator {
+    size: Point2,
+    shelves: Vec<Shelf>,
+}
+
+#[derive(Debug, Clone)]
+struct Shelf {
+    y: i32,
+    height: i32,
+    /// Free spans as (x, width), sorted by x.
+    free: Vec<(i32, i32)>,
+}
+
+impl ShelfAllocator {
+    pub fn new(size: Point2) -> Self {
+        Self {
+            size,
+            shelves: Vec::new(),
+        }
+    }
+
+    /// Returns the placement of a region of the given size, or [None] when the page is full.
+    pub fn allocate(&mut self, size: Point2) -> Option<Rect> {
+        let width = size.x + PAGE_PADDING;
+        let height = size.y + PAGE_PADDING;
+
+        // The lowest shelf fitting the region wastes the least space
+        let best = self
+            .shelves
+            .iter()
+            .enumerate()
+            .filter(|(_, shelf)| shelf.height >= height)
+            .filter_map(|(index, shelf)| {
+                let span = shelf.free.iter().position(|(_, w)| *w >= width)?;
+                Some((index, span, shelf.height))
+            })
+            .min_by_key(|(_, _, shelf_height)| *shelf_height);
+
+        let (shelf_index, span_index) = match best {
+            Some((shelf_index, span_index, _)) => (shelf_index, span_index),
+            None => {
+                let y = self
+                    .shelves
+                    .last()
+                    .map_or(PAGE_PADDING, |shelf| shelf.y + shelf.height);
+
+                if y + height > self.size.y || PAGE_PADDING + width > self.size.x {
+                    return None;
                 }
+
+                self.shelves.push(Shelf {
+                    y,
+                    height,
+                    free: vec![(PAGE_PADDING, self.size.x - PAGE_PADDING)],
+                });
+
+                (self.shelves.len() - 1, 0)
             }
+        };
 
-            items.insert(
-                id,
-                TextureAtlasCoord {
-                    rect_uv,
-                    size,
-                },
-            );
-        }
+        let shelf = &mut self.shelves[shelf_index];
+        let (x, span_width) = shelf.free[span_index];
 
-        let format = if self.gpu.borrow().is_srgb() {
-            TextureFormat::Rgba8UnormSrgb
+        if span_width == width {
+            shelf.free.remove(span_index);
         } else {
-            TextureFormat::Rgba8Unorm
+            shelf.free[span_index] = (x + width, span_width - width);
+        }
+
+        Some(Rect::new(x, shelf.y, size.x, size.y))
+    }
+
+    /// Frees a region returned by [ShelfAllocator::allocate]. Returns false if the region does
+    /// not belong to this page.
+    pub fn deallocate(&mut self, rect: Rect) -> bool {
+        let Some(shelf) = self.shelves.iter_mut().find(|shelf| shelf.y == rect.y) else {
+            return false;
         };
 
-        let texture = TextureBuilder::new(self.gpu)
-            .set_raw_image(&texture_data, atlas_size, format)
-            .set_usage(TextureUsage::Sampler)
-            .build()
-            .map_err(TextureAtlasBuilderError::TextureCreationError)?;
+        let width = rect.w + PAGE_PADDING;
+        let index = shelf.free.partition_point(|(x, _)| *x < rect.x);
 
-        Ok(TextureAtlas::new(texture, items))
+        let overlaps_next = shelf.free.get(index).is_some_and(|(x, _)| *x < rect.x + width);
+        let overlaps_prev = index > 0 && {
+            let (x, w) = shelf.free[index - 1];
+            x + w > rect.x
+        };
+
+        if overlaps_next || overlaps_prev {
+            return false;
+        }
+
+        shelf.free.insert(index, (rect.x, width));
+
+        // Merge with the following span, then the previous one
+        if index + 1 < shelf.free.len() && shelf.free[index].0 + shelf.free[index].1 == shelf.free[index + 1].0 {
+            shelf.free[index].1 += shelf.free[index + 1].1;
+            shelf.free.remove(index + 1);
+        }
+
+        if index > 0 && shelf.free[index - 1].0 + shelf.free[index - 1].1 == shelf.free[index].0 {
+            shelf.free[index - 1].1 += shelf.free[index].1;
+            shelf.free.remove(index);
+        }
+
+        let full_span = (PAGE_PADDING, self.size.x - PAGE_PADDING);
+        while self.shelves.last().is_some_and(|shelf| shelf.free == [full_span]) {
+            self.shelves.pop();
+        }
+
+        true
     }
-}
\ No newline at end of file
+}
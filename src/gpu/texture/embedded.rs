@@ -0,0 +1,287 @@
+use crate::{math::Point2, utils::ArcRef};
+
+#[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+use std::sync::Arc;
+
+#[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+use super::external::{self, ExternalHandle, ExternalHandleType, ExternalTextureError};
+
+use super::{
+    super::GPUInner,
+    Texture,
+    TextureBuilder,
+    TextureError,
+    TextureFormat,
+    TextureUsage,
+};
+
+/// Number of textures cycled by an [EmbeddedTarget], one rendered to while the host reads the other.
+const EMBEDDED_TARGET_BUFFER_COUNT: usize = 2;
+
+/// A swapchain-less render target for rendering inside views owned by a host application.
+///
+/// The target cycles between two textures: [EmbeddedTarget::next_frame] returns the texture to
+/// render to, and [EmbeddedTarget::front] the last finished frame the host should display,
+/// eg. by reading it back with [EmbeddedTarget::read_front] into a host owned buffer. A frame
+/// is finished once [EmbeddedTarget::present] is called, or the next frame is started.
+///
+/// When the previous frame is preserved, only the changed regions need to be redrawn, using
+/// [LoadOp::Load](crate::gpu::command::renderpass::LoadOp::Load) on the render pass.
+///
+/// With the `external-memory` feature on Linux and Android, a target created with
+/// [GPU::create_shared_embedded_target](crate::gpu::GPU::create_shared_embedded_target) exports
+/// its textures as dmabufs or opaque file descriptors, so the host displays them without a copy,
+/// see [EmbeddedTarget::export_handles].
+///
+/// ```rust,ignore
+/// let mut target = gpu.create_shared_embedded_target(size, format, ExternalHandleType::DmaBuf)?;
+/// host.import(target.export_handles()?);
+///
+/// // Every frame
+/// let texture = target.next_frame(false);
+/// // ... render to `texture`
+/// target.present();
+/// host.display(target.front_index().unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct EmbeddedTarget {
+    graphics: ArcRef<GPUInner>,
+    textures: Vec<Texture>,
+    current: usize,
+    /// Index of the last finished frame.
+    front: Option<usize>,
+    frame_count: u64,
+    size: Point2,
+    format: TextureFormat,
+
+    #[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+    handle_type: Option<ExternalHandleType>,
+    /// Handles of the textures, in the same order.
+    #[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+    handles: Arc<Vec<ExternalHandle>>,
+}
+
+impl EmbeddedTarget {
+    pub(crate) fn new(
+        graphics: ArcRef<GPUInner>,
+        size: Point2,
+        format: TextureFormat,
+    ) -> Result<Self, TextureError> {
+        let textures = Self::create_textures(&graphics, size, format)?;
+
+        Ok(Self {
+            graphics,
+            textures,
+            current: 0,
+            front: None,
+            frame_count: 0,
+            size,
+            format,
+
+            #[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+            handle_type: None,
+            #[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+            handles: Arc::new(Vec::new()),
+        })
+    }
+
+    #[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+    pub(crate) fn new_shared(
+        graphics: ArcRef<GPUInner>,
+        size: Point2,
+        format: TextureFormat,
+        handle_type: ExternalHandleType,
+    ) -> Result<Self, TextureError> {
+        let (textures, handles) = Self::create_shared_textures(&graphics, size, format, handle_type)?;
+
+        Ok(Self {
+            graphics,
+            textures,
+            current: 0,
+            front: None,
+            frame_count: 0,
+            size,
+            format,
+
+            handle_type: Some(handle_type),
+            handles: Arc::new(handles),
+        })
+    }
+
+    fn create_textures(
+        graphics: &ArcRef<GPUInner>,
+        size: Point2,
+        format: TextureFormat,
+    ) -> Result<Vec<Texture>, TextureError> {
+        if size.x <= 0 || size.y <= 0 {
+            return Err(TextureError::InvalidTextureSize);
+        }
+
+        (0..EMBEDDED_TARGET_BUFFER_COUNT)
+            .map(|_| {
+                TextureBuilder::new(ArcRef::clone(graphics))
+                    .set_render_target(size, Some(format))
+                    .set_usage(TextureUsage::Sampler)
+                    .build()
+            })
+            .collect()
+    }
+
+    #[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+    fn create_shared_textures(
+        graphics: &ArcRef<GPUInner>,
+        size: Point2,
+        format: TextureFormat,
+        handle_type: ExternalHandleType,
+    ) -> Result<(Vec<Texture>, Vec<ExternalHandle>), TextureError> {
+        if size.x <= 0 || size.y <= 0 {
+            return Err(TextureError::InvalidTextureSize);
+        }
+
+        (0..EMBEDDED_TARGET_BUFFER_COUNT)
+            .map(|index| {
+                let label = format!("Shared Embedded Target {}", index);
+                external::export_render_target(graphics, size, format, handle_type, &label)
+                    .map_err(TextureError::External)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|textures| textures.into_iter().unzip())
+    }
+
+    pub fn size(&self) -> Point2 {
+        self.size
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Number of frames started with [EmbeddedTarget::next_frame].
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Recreates the textures with a new size, eg. when the host view is resized.
+    ///
+    /// The contents are lost, so the next frame must be fully redrawn. The textures of a shared
+    /// target are exported again, the host must import the new [EmbeddedTarget::export_handles].
+    pub fn resize(&mut self, size: Point2) -> Result<(), TextureError> {
+        if size == self.size {
+            return Ok(());
+        }
+
+        #[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+        if let Some(handle_type) = self.handle_type {
+            let (textures, handles) =
+                Self::create_shared_textures(&self.graphics, size, self.format, handle_type)?;
+
+            self.textures = textures;
+            self.handles = Arc::new(handles);
+        } else {
+            self.textures = Self::create_textures(&self.graphics, size, self.format)?;
+        }
+
+        #[cfg(not(all(feature = "external-memory", any(target_os = "linux", target_os = "android"))))]
+        {
+            self.textures = Self::create_textures(&self.graphics, size, self.format)?;
+        }
+
+        self.size = size;
+        self.current = 0;
+        self.front = None;
+
+        Ok(())
+    }
+
+    /// Starts a new frame and returns the texture to render it to, the previous frame becomes
+    /// the front one if it wasn't presented.
+    ///
+    /// When `preserve` is true the previous frame is copied into the returned texture,
+    /// so it can be updated incrementally instead of being redrawn.
+    pub fn next_frame(&mut self, preserve: bool) -> Texture {
+        let previous = self.current;
+        if self.frame_count > 0 {
+            self.front = Some(previous);
+        }
+
+        self.current = (self.current + 1) % self.textures.len();
+        self.frame_count += 1;
+
+        if preserve && self.frame_count > 1 {
+            self.copy_texture(previous, self.current);
+        }
+
+        self.textures[self.current].clone()
+    }
+
+    /// Finishes the frame started with [EmbeddedTarget::next_frame], once its commands were
+    /// submitted. Waits for the GPU to complete them, so the host never reads a partial frame.
+    pub fn present(&mut self) {
+        if self.frame_count == 0 {
+            return;
+        }
+
+        _ = self.graphics.borrow().device().poll(wgpu::PollType::Wait);
+        self.front = Some(self.current);
+    }
+
+    /// Index of the texture holding the last finished frame, in the order of
+    /// [EmbeddedTarget::export_handles]. [None] until a frame is finished.
+    pub fn front_index(&self) -> Option<usize> {
+        self.front
+    }
+
+    /// The texture of the last finished frame, to be displayed by the host.
+    pub fn front(&self) -> Option<Texture> {
+        self.front.map(|index| self.textures[index].clone())
+    }
+
+    /// Reads the pixels of the front texture, for hosts that upload it into their own surface.
+    pub fn read_front(&self) -> Result<Vec<u8>, TextureError> {
+        let index = self.front.ok_or(TextureError::FailedToRead)?;
+        self.textures[index].read::<u8>()
+    }
+
+    /// The kind of handles the textures are exported as, [None] if the target isn't shared.
+    #[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+    pub fn handle_type(&self) -> Option<ExternalHandleType> {
+        self.handle_type
+    }
+
+    /// Duplicates the handles of the textures, one per buffered frame, for the host to import.
+    #[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+    pub fn export_handles(&self) -> Result<Vec<ExternalHandle>, TextureError> {
+        if self.handle_type.is_none() {
+            return Err(TextureError::External(ExternalTextureError::Unsupported));
+        }
+
+        self.handles
+            .iter()
+            .map(|handle| handle.try_clone().map_err(TextureError::External))
+            .collect()
+    }
+
+    fn copy_texture(&self, src: usize, dst: usize) {
+        let src_inner = self.textures[src].inner.borrow();
+        let dst_inner = self.textures[dst].inner.borrow();
+        let graphics = self.graphics.borrow();
+
+        let mut encoder = graphics
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Embedded target copy encoder"),
+            });
+
+        encoder.copy_texture_to_texture(
+            src_inner.wgpu_texture.as_image_copy(),
+            dst_inner.wgpu_texture.as_image_copy(),
+            wgpu::Extent3d {
+                width: self.size.x as u32,
+                height: self.size.y as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        graphics.queue().submit(Some(encoder.finish()));
+    }
+}
@@ -0,0 +1,548 @@
+//! Texture memory shared with other APIs and processes through platform handles.
+//!
+//! Textures are backed by Vulkan external memory and exported as dmabufs or opaque file
+//! descriptors, so a host application (a Wayland compositor, a Qt or Electron view) can display
+//! what the crate renders without owning its swapchain, see
+//! [GPU::create_shared_embedded_target](crate::gpu::GPU::create_shared_embedded_target).
+//!
+//! This is only available with the Vulkan backend on Linux and Android. DXGI shared handles and
+//! IOSurfaces are not supported, creating a shared target on another backend returns
+//! [ExternalTextureError::Unsupported].
+
+use std::os::fd::{FromRawFd, OwnedFd};
+
+use ash::vk;
+use wgpu::hal::{api::Vulkan, vulkan};
+
+use crate::{math::Point2, utils::ArcRef};
+
+use super::{super::GPUInner, Texture, TextureFormat, TextureUsage};
+
+/// `DRM_FORMAT_MOD_LINEAR`, rows stored one after the other with a fixed stride.
+pub const DRM_FORMAT_MOD_LINEAR: u64 = 0;
+
+/// Kind of platform handle texture memory is shared with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalHandleType {
+    /// A dmabuf of a linear image, importable by EGL, Wayland compositors, VA-API or another GPU.
+    DmaBuf,
+    /// An opaque file descriptor of Vulkan memory, importable by Vulkan or OpenGL
+    /// (`GL_EXT_memory_object_fd`) running on the same GPU and driver.
+    OpaqueFd,
+}
+
+/// A single plane dmabuf and the layout of the image in it.
+#[derive(Debug)]
+pub struct DmaBufPlane {
+    pub fd: OwnedFd,
+    /// DRM fourcc code of the pixel format, see [drm_fourcc].
+    pub fourcc: u32,
+    /// DRM format modifier, always [DRM_FORMAT_MOD_LINEAR].
+    pub modifier: u64,
+    /// Offset of the first row in bytes.
+    pub offset: u64,
+    /// Bytes between the start of two rows.
+    pub stride: u64,
+}
+
+/// A platform handle to the memory of a shared texture.
+///
+/// The file descriptors are owned, dropping the handle closes them without affecting the
+/// texture. Use [ExternalHandle::try_clone] to hand out a handle while keeping this one.
+#[derive(Debug)]
+pub enum ExternalHandle {
+    DmaBuf(DmaBufPlane),
+    /// Opaque Vulkan memory, along with the size of the allocation the importer must use.
+    OpaqueFd {
+        fd: OwnedFd,
+        size: u64,
+    },
+}
+
+impl ExternalHandle {
+    pub fn handle_type(&self) -> ExternalHandleType {
+        match self {
+            ExternalHandle::DmaBuf(_) => ExternalHandleType::DmaBuf,
+            ExternalHandle::OpaqueFd { .. } => ExternalHandleType::OpaqueFd,
+        }
+    }
+
+    /// Duplicates the file descriptor, eg. to send it to another process.
+    pub fn try_clone(&self) -> Result<Self, ExternalTextureError> {
+        let handle = match self {
+            ExternalHandle::DmaBuf(plane) => ExternalHandle::DmaBuf(DmaBufPlane {
+                fd: plane
+                    .fd
+                    .try_clone()
+                    .map_err(ExternalTextureError::from_io)?,
+                ..*plane
+            }),
+            ExternalHandle::OpaqueFd { fd, size } => ExternalHandle::OpaqueFd {
+                fd: fd.try_clone().map_err(ExternalTextureError::from_io)?,
+                size: *size,
+            },
+        };
+
+        Ok(handle)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalTextureError {
+    /// The GPU doesn't use the Vulkan backend, or its driver lacks the external memory extensions.
+    Unsupported,
+    /// The format can't be shared with this handle type on this GPU.
+    UnsupportedFormat(TextureFormat),
+    /// A Vulkan call failed with this result code.
+    Vulkan(i32),
+    /// Duplicating a file descriptor failed with this OS error code.
+    Io(i32),
+}
+
+impl ExternalTextureError {
+    fn from_io(error: std::io::Error) -> Self {
+        ExternalTextureError::Io(error.raw_os_error().unwrap_or(0))
+    }
+}
+
+impl From<vk::Result> for ExternalTextureError {
+    fn from(result: vk::Result) -> Self {
+        ExternalTextureError::Vulkan(result.as_raw())
+    }
+}
+
+impl std::fmt::Display for ExternalTextureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExternalTextureError::Unsupported => {
+                write!(f, "External memory is not supported by the GPU backend")
+            }
+            ExternalTextureError::UnsupportedFormat(format) => {
+                write!(f, "Format {:?} cannot be shared", format)
+            }
+            ExternalTextureError::Vulkan(code) => write!(f, "Vulkan error {}", code),
+            ExternalTextureError::Io(code) => write!(f, "I/O error {}", code),
+        }
+    }
+}
+
+impl std::error::Error for ExternalTextureError {}
+
+const fn fourcc(code: &[u8; 4]) -> u32 {
+    (code[0] as u32) | (code[1] as u32) << 8 | (code[2] as u32) << 16 | (code[3] as u32) << 24
+}
+
+/// The DRM fourcc code of a format, [None] if it can't be shared.
+///
+/// DRM codes name the channels from the most significant bits of a little endian pixel, so
+/// [TextureFormat::Rgba8Unorm] is `DRM_FORMAT_ABGR8888`.
+pub fn drm_fourcc(format: TextureFormat) -> Option<u32> {
+    let code = match format {
+        TextureFormat::R8Unorm => fourcc(b"R8  "),
+        TextureFormat::Rg8Unorm => fourcc(b"GR88"),
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => fourcc(b"AB24"),
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => fourcc(b"AR24"),
+        TextureFormat::Rgb10a2Unorm => fourcc(b"AB30"),
+        TextureFormat::Rgba16Float => fourcc(b"AB4H"),
+        _ => return None,
+    };
+
+    Some(code)
+}
+
+fn vk_format(format: TextureFormat) -> Option<vk::Format> {
+    let format = match format {
+        TextureFormat::R8Unorm => vk::Format::R8_UNORM,
+        TextureFormat::Rg8Unorm => vk::Format::R8G8_UNORM,
+        TextureFormat::Rgba8Unorm => vk::Format::R8G8B8A8_UNORM,
+        TextureFormat::Rgba8UnormSrgb => vk::Format::R8G8B8A8_SRGB,
+        TextureFormat::Bgra8Unorm => vk::Format::B8G8R8A8_UNORM,
+        TextureFormat::Bgra8UnormSrgb => vk::Format::B8G8R8A8_SRGB,
+        TextureFormat::Rgb10a2Unorm => vk::Format::A2B10G10R10_UNORM_PACK32,
+        TextureFormat::Rgba16Float => vk::Format::R16G16B16A16_SFLOAT,
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+impl ExternalHandleType {
+    fn vk_handle_type(self) -> vk::ExternalMemoryHandleTypeFlags {
+        match self {
+            ExternalHandleType::DmaBuf => vk::ExternalMemoryHandleTypeFlags::DMA_BUF_EXT,
+            ExternalHandleType::OpaqueFd => vk::ExternalMemoryHandleTypeFlags::OPAQUE_FD,
+        }
+    }
+
+    // Without VK_EXT_image_drm_format_modifier, only linear images have a layout known to
+    // the other side of a dmabuf
+    fn tiling(self) -> vk::ImageTiling {
+        match self {
+            ExternalHandleType::DmaBuf => vk::ImageTiling::LINEAR,
+            ExternalHandleType::OpaqueFd => vk::ImageTiling::OPTIMAL,
+        }
+    }
+}
+
+/// A Vulkan image and its dedicated memory, destroyed on drop.
+struct RawImage {
+    device: ash::Device,
+    image: vk::Image,
+    memory: vk::DeviceMemory,
+}
+
+impl Drop for RawImage {
+    fn drop(&mut self) {
+        // SAFETY: Both handles were created from `device`, wgpu calls the drop callback owning
+        // this once the GPU no longer uses the texture.
+        unsafe {
+            if self.image != vk::Image::null() {
+                self.device.destroy_image(self.image, None);
+            }
+
+            if self.memory != vk::DeviceMemory::null() {
+                self.device.free_memory(self.memory, None);
+            }
+        }
+    }
+}
+
+/// The Vulkan objects needed to create shareable images.
+struct ExternalContext<'a> {
+    hal: &'a vulkan::Device,
+    instance: &'a ash::Instance,
+    memory_fd: ash::khr::external_memory_fd::Device,
+}
+
+impl<'a> ExternalContext<'a> {
+    fn new(
+        hal: &'a vulkan::Device,
+        handle_type: ExternalHandleType,
+    ) -> Result<Self, ExternalTextureError> {
+        let shared_instance = hal.shared_instance();
+        let instance = shared_instance.raw_instance();
+
+        // The external image queries are core in Vulkan 1.1
+        let device_version = unsafe {
+            instance
+                .get_physical_device_properties(hal.raw_physical_device())
+                .api_version
+        };
+
+        if shared_instance.instance_api_version() < vk::API_VERSION_1_1
+            || device_version < vk::API_VERSION_1_1
+        {
+            return Err(ExternalTextureError::Unsupported);
+        }
+
+        let extensions = hal.enabled_device_extensions();
+        let mut required = vec![ash::khr::external_memory_fd::NAME];
+        if handle_type == ExternalHandleType::DmaBuf {
+            required.push(ash::ext::external_memory_dma_buf::NAME);
+        }
+
+        if !required.iter().all(|name| extensions.contains(name)) {
+            return Err(ExternalTextureError::Unsupported);
+        }
+
+        Ok(Self {
+            hal,
+            instance,
+            memory_fd: ash::khr::external_memory_fd::Device::new(instance, hal.raw_device()),
+        })
+    }
+
+    fn device(&self) -> &ash::Device {
+        self.hal.raw_device()
+    }
+
+    /// Checks the format can be used with `usage` in memory of `handle_type`.
+    fn check_format(
+        &self,
+        format: TextureFormat,
+        usage: vk::ImageUsageFlags,
+        handle_type: ExternalHandleType,
+        feature: vk::ExternalMemoryFeatureFlags,
+    ) -> Result<vk::Format, ExternalTextureError> {
+        let vk_format = vk_format(format).ok_or(ExternalTextureError::UnsupportedFormat(format))?;
+
+        let mut external_info = vk::PhysicalDeviceExternalImageFormatInfo::default()
+            .handle_type(handle_type.vk_handle_type());
+        let format_info = vk::PhysicalDeviceImageFormatInfo2::default()
+            .format(vk_format)
+            .ty(vk::ImageType::TYPE_2D)
+            .tiling(handle_type.tiling())
+            .usage(usage)
+            .push_next(&mut external_info);
+
+        let mut external_properties = vk::ExternalImageFormatProperties::default();
+        let mut properties =
+            vk::ImageFormatProperties2::default().push_next(&mut external_properties);
+
+        let supported = unsafe {
+            self.instance.get_physical_device_image_format_properties2(
+                self.hal.raw_physical_device(),
+                &format_info,
+                &mut properties,
+            )
+        };
+
+        let features = external_properties
+            .external_memory_properties
+            .external_memory_features;
+        if supported.is_err() || !features.contains(feature) {
+            return Err(ExternalTextureError::UnsupportedFormat(format));
+        }
+
+        Ok(vk_format)
+    }
+
+    fn create_image(
+        &self,
+        size: Point2,
+        format: vk::Format,
+        usage: vk::ImageUsageFlags,
+        handle_type: ExternalHandleType,
+    ) -> Result<RawImage, ExternalTextureError> {
+        let mut external_info =
+            vk::ExternalMemoryImageCreateInfo::default().handle_types(handle_type.vk_handle_type());
+
+        let image_info = vk::ImageCreateInfo::default()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(format)
+            .extent(vk::Extent3D {
+                width: size.x as u32,
+                height: size.y as u32,
+                depth: 1,
+            })
+            .mip_levels(1)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(handle_type.tiling())
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .push_next(&mut external_info);
+
+        let image = unsafe { self.device().create_image(&image_info, None)? };
+
+        Ok(RawImage {
+            device: self.device().clone(),
+            image,
+            memory: vk::DeviceMemory::null(),
+        })
+    }
+
+    /// Index of a memory type allowed by `type_bits`, device local ones first.
+    fn memory_type(&self, type_bits: u32) -> Option<u32> {
+        let properties = unsafe {
+            self.instance
+                .get_physical_device_memory_properties(self.hal.raw_physical_device())
+        };
+
+        let types = &properties.memory_types[..properties.memory_type_count as usize];
+        let allowed = |index: &usize| type_bits & (1 << index) != 0;
+
+        (0..types.len())
+            .filter(allowed)
+            .find(|index| {
+                types[*index]
+                    .property_flags
+                    .contains(vk::MemoryPropertyFlags::DEVICE_LOCAL)
+            })
+            .or_else(|| (0..types.len()).find(allowed))
+            .map(|index| index as u32)
+    }
+
+    /// Creates an image in freshly allocated memory, and exports that memory.
+    fn export_image(
+        &self,
+        size: Point2,
+        format: TextureFormat,
+        usage: vk::ImageUsageFlags,
+        handle_type: ExternalHandleType,
+    ) -> Result<(RawImage, ExternalHandle), ExternalTextureError> {
+        let vk_format = self.check_format(
+            format,
+            usage,
+            handle_type,
+            vk::ExternalMemoryFeatureFlags::EXPORTABLE,
+        )?;
+
+        let mut image = self.create_image(size, vk_format, usage, handle_type)?;
+        let requirements = unsafe { self.device().get_image_memory_requirements(image.image) };
+        let memory_type = self
+            .memory_type(requirements.memory_type_bits)
+            .ok_or(ExternalTextureError::UnsupportedFormat(format))?;
+
+        // Importers of dmabufs and opaque fds commonly require a dedicated allocation
+        let mut export_info =
+            vk::ExportMemoryAllocateInfo::default().handle_types(handle_type.vk_handle_type());
+        let mut dedicated_info = vk::MemoryDedicatedAllocateInfo::default().image(image.image);
+        let allocate_info = vk::MemoryAllocateInfo::default()
+            .allocation_size(requirements.size)
+            .memory_type_index(memory_type)
+            .push_next(&mut export_info)
+            .push_next(&mut dedicated_info);
+
+        image.memory = unsafe { self.device().allocate_memory(&allocate_info, None)? };
+        unsafe {
+            self.device()
+                .bind_image_memory(image.image, image.memory, 0)?;
+        }
+
+        let fd_info = vk::MemoryGetFdInfoKHR::default()
+            .memory(image.memory)
+            .handle_type(handle_type.vk_handle_type());
+
+        // SAFETY: vkGetMemoryFdKHR returns a new file descriptor owned by the caller.
+        let fd = unsafe { OwnedFd::from_raw_fd(self.memory_fd.get_memory_fd(&fd_info)?) };
+
+        let handle = match handle_type {
+            ExternalHandleType::DmaBuf => {
+                let layout = unsafe {
+                    self.device().get_image_subresource_layout(
+                        image.image,
+                        vk::ImageSubresource {
+                            aspect_mask: vk::ImageAspectFlags::COLOR,
+                            mip_level: 0,
+                            array_layer: 0,
+                        },
+                    )
+                };
+
+                ExternalHandle::DmaBuf(DmaBufPlane {
+                    fd,
+                    fourcc: drm_fourcc(format)
+                        .ok_or(ExternalTextureError::UnsupportedFormat(format))?,
+                    modifier: DRM_FORMAT_MOD_LINEAR,
+                    offset: layout.offset,
+                    stride: layout.row_pitch,
+                })
+            }
+            ExternalHandleType::OpaqueFd => ExternalHandle::OpaqueFd {
+                fd,
+                size: requirements.size,
+            },
+        };
+
+        Ok((image, handle))
+    }
+}
+
+/// Wraps an image with memory bound in a wgpu texture, which destroys it once dropped.
+fn wrap_image(
+    graphics: &ArcRef<GPUInner>,
+    image: RawImage,
+    size: Point2,
+    format: TextureFormat,
+    hal_usage: wgpu::TextureUses,
+    usage: wgpu::TextureUsages,
+    label: &str,
+) -> wgpu::Texture {
+    let extent = wgpu::Extent3d {
+        width: size.x as u32,
+        height: size.y as u32,
+        depth_or_array_layers: 1,
+    };
+
+    let raw = image.image;
+    let hal_texture = unsafe {
+        vulkan::Device::texture_from_raw(
+            raw,
+            &wgpu::hal::TextureDescriptor {
+                label: Some(label),
+                size: extent,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: format.into(),
+                usage: hal_usage,
+                memory_flags: wgpu::hal::MemoryFlags::empty(),
+                view_formats: Vec::new(),
+            },
+            Some(Box::new(move || drop(image))),
+        )
+    };
+
+    let graphics_ref = graphics.borrow();
+
+    // SAFETY: The image was created from this device with the same description.
+    unsafe {
+        graphics_ref.device().create_texture_from_hal::<Vulkan>(
+            hal_texture,
+            &wgpu::TextureDescriptor {
+                label: Some(label),
+                size: extent,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: format.into(),
+                usage,
+                view_formats: &[],
+            },
+        )
+    }
+}
+
+/// Creates a render target whose memory is exported as `handle_type`.
+pub(crate) fn export_render_target(
+    graphics: &ArcRef<GPUInner>,
+    size: Point2,
+    format: TextureFormat,
+    handle_type: ExternalHandleType,
+    label: &str,
+) -> Result<(Texture, ExternalHandle), ExternalTextureError> {
+    let usage = vk::ImageUsageFlags::COLOR_ATTACHMENT
+        | vk::ImageUsageFlags::SAMPLED
+        | vk::ImageUsageFlags::TRANSFER_SRC
+        | vk::ImageUsageFlags::TRANSFER_DST;
+
+    let exported = {
+        let graphics_ref = graphics.borrow();
+
+        // SAFETY: The raw device is only used to create objects handed back to wgpu.
+        unsafe {
+            graphics_ref
+                .device()
+                .as_hal::<Vulkan, _, _>(|hal| match hal {
+                    Some(hal) => ExternalContext::new(hal, handle_type)?.export_image(
+                        size,
+                        format,
+                        usage,
+                        handle_type,
+                    ),
+                    None => Err(ExternalTextureError::Unsupported),
+                })
+        }
+    };
+
+    let (image, handle) = exported?;
+
+    let texture = wrap_image(
+        graphics,
+        image,
+        size,
+        format,
+        wgpu::TextureUses::COLOR_TARGET
+            | wgpu::TextureUses::RESOURCE
+            | wgpu::TextureUses::COPY_SRC
+            | wgpu::TextureUses::COPY_DST,
+        wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::COPY_DST,
+        label,
+    );
+
+    let texture = Texture::from_wgpu(
+        graphics,
+        texture,
+        size,
+        format,
+        TextureUsage::RenderAttachment | TextureUsage::Sampler,
+        label,
+    );
+
+    Ok((texture, handle))
+}
@@ -1,11 +1,21 @@
+pub mod animated;
 pub mod atlas;
 pub mod sprite;
+pub mod embedded;
+#[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+pub mod external;
+pub mod pingpong;
+pub mod scaled;
+pub mod streaming;
+pub mod view;
+pub mod yuv;
+pub mod msaa;
 
 mod types;
 pub use types::*;
 
 use std::sync::atomic::AtomicUsize;
-use crate::{gpu::ArcRef, math::Point2};
+use crate::{gpu::ArcRef, math::{Point2, Rect}, utils::LockRank};
 
 use super::{
     GPUInner,
@@ -30,6 +40,15 @@ impl Texture {
             return Err(TextureError::InvalidGPUContext);
         }
 
+        if let Some(sampler) = &builder.sampler {
+            let features = builder.graphics.borrow().device().features();
+            sampler
+                .validate_features(features)
+                .map_err(TextureError::InvalidSampler)?;
+        }
+
+        let label = builder.label;
+        let sampler = builder.sampler;
         let texture = match builder.data {
             TextureBuilderData::Data(data) => {
                 let image = image::load_from_memory(data).map_err(|e| e.to_string());
@@ -52,9 +71,12 @@ impl Texture {
                     size,
                     builder.sample_count,
                     builder.mip_level_count,
+                    builder.array_layers,
+                    &builder.view_formats,
                     wgpu::TextureDimension::D2,
                     TextureFormat::Rgba8Unorm,
                     builder.usage,
+                    label,
                 );
 
                 if texture.is_err() {
@@ -95,9 +117,12 @@ impl Texture {
                     size,
                     builder.sample_count,
                     builder.mip_level_count,
+                    builder.array_layers,
+                    &builder.view_formats,
                     wgpu::TextureDimension::D2,
                     TextureFormat::Rgba8Unorm,
                     builder.usage,
+                    label,
                 );
 
                 if texture.is_err() {
@@ -124,9 +149,12 @@ impl Texture {
                     size,
                     builder.sample_count,
                     builder.mip_level_count,
+                    builder.array_layers,
+                    &builder.view_formats,
                     wgpu::TextureDimension::D2,
                     format,
                     builder.usage,
+                    label,
                 );
 
                 if texture.is_err() {
@@ -152,9 +180,12 @@ impl Texture {
                     size,
                     builder.sample_count,
                     builder.mip_level_count,
+                    builder.array_layers,
+                    &builder.view_formats,
                     wgpu::TextureDimension::D2,
                     format.unwrap(),
                     builder.usage | TextureUsage::RenderAttachment,
+                    label,
                 );
 
                 if texture.is_err() {
@@ -196,9 +227,12 @@ impl Texture {
                     size,
                     builder.sample_count,
                     builder.mip_level_count,
+                    builder.array_layers,
+                    &builder.view_formats,
                     wgpu::TextureDimension::D2,
                     TextureFormat::from(format),
                     builder.usage | TextureUsage::RenderAttachment,
+                    label,
                 );
 
                 if texture.is_err() {
@@ -217,31 +251,86 @@ impl Texture {
             }
         };
 
+        if let Ok(texture) = &texture {
+            texture.inner.borrow_mut().sampler = sampler;
+        }
+
         texture
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn create_texture(
         graphics: ArcRef<GPUInner>,
         size: Point2,
         sample_count: SampleCount,
         mip_level_count: u32,
+        array_layers: u32,
+        view_formats: &[TextureFormat],
         dimension: wgpu::TextureDimension,
         format: TextureFormat,
         usages: TextureUsage,
+        label: Option<&str>,
     ) -> Result<Self, TextureError> {
-        if size.x == 0 || size.y == 0 {
+        if size.x == 0 || size.y == 0 || array_layers == 0 {
+            return Err(TextureError::InvalidTextureSize);
+        }
+
+        let wgpu_format: wgpu::TextureFormat = format.into();
+        for view_format in view_formats {
+            // Only the sRGB and linear variants of a format can be reinterpreted
+            let view_format: wgpu::TextureFormat = (*view_format).into();
+            if view_format.remove_srgb_suffix() != wgpu_format.remove_srgb_suffix() {
+                crate::error_log!(
+                    "Texture format {:?} cannot be viewed as {:?}",
+                    format,
+                    view_format
+                );
+                return Err(TextureError::InvalidTextureFormat);
+            }
+        }
+
+        let wgpu_view_formats = view_formats
+            .iter()
+            .map(|format| (*format).into())
+            .collect::<Vec<wgpu::TextureFormat>>();
+
+        let max_mip_level_count = (size.x.max(size.y) as u32).ilog2() + 1;
+        if mip_level_count == 0 || mip_level_count > max_mip_level_count {
+            crate::error_log!(
+                "Invalid mip level count {} for texture of size {}x{}, expected 1..={}",
+                mip_level_count,
+                size.x,
+                size.y,
+                max_mip_level_count
+            );
             return Err(TextureError::InvalidTextureSize);
         }
 
+        if usages.contains(TextureUsage::StorageBinding)
+            && !graphics
+                .borrow()
+                .format_features(format.into())
+                .allowed_usages
+                .contains(wgpu::TextureUsages::STORAGE_BINDING)
+        {
+            crate::error_log!("Texture format {:?} cannot be used as a storage texture", format);
+            return Err(TextureError::InvalidTextureFormat);
+        }
+
         let texture_size = wgpu::Extent3d {
             width: size.x as u32,
             height: size.y as u32,
-            depth_or_array_layers: 1,
+            depth_or_array_layers: array_layers,
         };
 
         let ref_id_label = TEXTURE_REF_ID.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-        let tex_label = format!("Texture {}", ref_id_label);
-        let view_label = format!("Texture View {}", ref_id_label);
+        let (tex_label, view_label) = match label {
+            Some(label) => (label.to_string(), format!("{} View", label)),
+            None => (
+                format!("Texture {}", ref_id_label),
+                format!("Texture View {}", ref_id_label),
+            ),
+        };
 
         let texture_create_info = wgpu::TextureDescriptor {
             size: texture_size,
@@ -252,7 +341,7 @@ impl Texture {
             usage: (wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC)
                 | usages.clone().into(),
             label: Some(tex_label.as_str()),
-            view_formats: &[],
+            view_formats: &wgpu_view_formats,
         };
 
         let graphics_ref = graphics.borrow();
@@ -265,26 +354,108 @@ impl Texture {
             ..Default::default()
         });
 
+        // Combined depth/stencil textures can only be sampled through a single aspect,
+        // so keep a depth-only view around for binding it as `texture_depth_2d`.
+        let depth_view = if format.is_depth() && format.has_stencil() && usages.contains(TextureUsage::Sampler) {
+            let depth_view_label = match label {
+                Some(label) => format!("{} Depth View", label),
+                None => format!("Texture Depth View {}", ref_id_label),
+            };
+            Some(texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some(depth_view_label.as_str()),
+                aspect: wgpu::TextureAspect::DepthOnly,
+                ..Default::default()
+            }))
+        } else {
+            None
+        };
+
         let inner = TextureInner {
             wgpu_texture: texture,
             wgpu_view: view,
+            wgpu_depth_view: depth_view,
 
             sample_count,
             usages,
             size,
             format,
+            view_formats: view_formats.to_vec(),
 
             mapped: false,
+            sampler: None,
+
+            #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+            straight_alpha: false,
+            #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+            straight_alpha_warned: false,
         };
 
+        drop(graphics_ref);
+
+        let handle = inner.wgpu_texture.clone();
+        let inner = ArcRef::new_ranked(inner, LockRank::Resource);
+        graphics
+            .borrow_mut()
+            .resources
+            .register_texture(&inner, handle, &tex_label);
+
         Ok(Self {
             graphics: ArcRef::clone(&graphics),
-            inner: ArcRef::new(inner),
+            inner,
             mapped_buffer: vec![],
             mapped_type: TextureMappedType::Write,
         })
     }
 
+    /// Wraps a texture created outside of [TextureBuilder], eg. over shared memory, with a
+    /// single mip level and sample.
+    #[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+    pub(crate) fn from_wgpu(
+        graphics: &ArcRef<GPUInner>,
+        texture: wgpu::Texture,
+        size: Point2,
+        format: TextureFormat,
+        usages: TextureUsage,
+        label: &str,
+    ) -> Self {
+        let view_label = format!("{} View", label);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some(view_label.as_str()),
+            ..Default::default()
+        });
+
+        let handle = texture.clone();
+        let inner = TextureInner {
+            wgpu_texture: texture,
+            wgpu_view: view,
+            wgpu_depth_view: None,
+
+            sample_count: SampleCount::SampleCount1,
+            usages,
+            size,
+            format,
+            view_formats: Vec::new(),
+
+            mapped: false,
+            sampler: None,
+
+            #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+            straight_alpha: false,
+            #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+            straight_alpha_warned: false,
+        };
+
+        let inner = ArcRef::new_ranked(inner, LockRank::Resource);
+        graphics.borrow_mut().resources.register_texture(&inner, handle, label);
+
+        Self {
+            graphics: ArcRef::clone(graphics),
+            inner,
+            mapped_buffer: vec![],
+            mapped_type: TextureMappedType::Write,
+        }
+    }
+
     pub fn size(&self) -> Point2 {
         self.inner.borrow().size
     }
@@ -301,14 +472,56 @@ impl Texture {
         self.inner.borrow().usages
     }
 
+    /// The sampler used when drawing this texture without an explicit one, set with
+    /// [TextureBuilder::set_sampler] or [TextureSampler::DEFAULT] otherwise.
+    pub fn sampler(&self) -> TextureSampler {
+        self.inner
+            .borrow()
+            .sampler
+            .unwrap_or(TextureSampler::DEFAULT)
+    }
+
+    /// Changes the sampler used when drawing this texture without an explicit one, [None]
+    /// restores [TextureSampler::DEFAULT].
+    ///
+    /// This is shared by every clone of the texture.
+    pub fn set_sampler(&mut self, sampler: Option<TextureSampler>) {
+        self.inner.borrow_mut().sampler = sampler;
+    }
+
     pub fn write<T: bytemuck::Pod>(&mut self, data: &[T]) -> Result<(), TextureError> {
         if data.is_empty() {
             return Err(TextureError::InvalidTextureData);
         }
 
+        let data: Vec<u8> = bytemuck::cast_slice(data).to_vec();
+
+        {
+            let inner = self.inner.borrow();
+
+            let expected_len =
+                inner.format.get_size() as usize * inner.size.x as usize * inner.size.y as usize;
+            if data.len() != expected_len {
+                crate::error_log!(
+                    "Texture data size mismatch: expected {} bytes for {}x{} {:?}, got {} bytes",
+                    expected_len,
+                    inner.size.x,
+                    inner.size.y,
+                    inner.format,
+                    data.len()
+                );
+                return Err(TextureError::InvalidTextureData);
+            }
+        }
+
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        {
+            let mut inner = self.inner.borrow_mut();
+            inner.straight_alpha = Self::has_straight_alpha(inner.format, &data);
+        }
+
         let inner = self.inner.borrow();
 
-        let data: Vec<u8> = bytemuck::cast_slice(data).to_vec();
         let bytes_per_pixel = inner.format.get_size();
         let unpadded_bytes_per_row = bytes_per_pixel * inner.size.x as u32;
         let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
@@ -379,13 +592,27 @@ impl Texture {
         Ok(())
     }
 
+    /// Returns true if any pixel has a color channel brighter than its alpha, which can
+    /// only happen when the data is not premultiplied.
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    fn has_straight_alpha(format: TextureFormat, data: &[u8]) -> bool {
+        match format {
+            TextureFormat::Rgba8Unorm
+            | TextureFormat::Rgba8UnormSrgb
+            | TextureFormat::Bgra8Unorm
+            | TextureFormat::Bgra8UnormSrgb => data
+                .chunks_exact(4)
+                .any(|px| px[0] > px[3] || px[1] > px[3] || px[2] > px[3]),
+            _ => false,
+        }
+    }
+
     pub fn read<T: bytemuck::Pod>(&self) -> Result<Vec<T>, TextureError> {
         if self.inner.borrow().size.x == 0 || self.inner.borrow().size.y == 0 {
             return Err(TextureError::InvalidTextureSize);
         }
 
         let inner = self.inner.borrow();
-        let inner_graphics = self.graphics.borrow();
 
         let bytes_per_pixel = 4; // For RGBA8/BGRA8, etc. Adjust if needed.
         let unpadded_bytes_per_row = bytes_per_pixel * inner.size.x as u32;
@@ -402,6 +629,8 @@ impl Texture {
         }
 
         let buffer = buffer.unwrap();
+        let buffer_inner = buffer.inner.borrow();
+        let inner_graphics = self.graphics.borrow();
 
         let mut encoder =
             inner_graphics
@@ -418,7 +647,7 @@ impl Texture {
                 aspect: wgpu::TextureAspect::All,
             },
             wgpu::TexelCopyBufferInfo {
-                buffer: &buffer.inner.borrow().buffer,
+                buffer: &buffer_inner.buffer,
                 layout: wgpu::TexelCopyBufferLayout {
                     offset: 0,
                     bytes_per_row: Some(padded_bytes_per_row),
@@ -432,6 +661,7 @@ impl Texture {
         _ = inner_graphics.device().poll(wgpu::PollType::Wait);
 
         drop(inner_graphics);
+        drop(buffer_inner);
 
         // Remove row padding
         let raw = buffer.read::<u8>();
@@ -523,6 +753,156 @@ impl Texture {
 
         Ok(())
     }
+
+    /// Uploads tightly packed pixels into a rectangle of the first mip level, leaving the rest
+    /// of the texture untouched.
+    ///
+    /// `data` must hold `rect.w * rect.h` pixels. Unlike [Texture::write], the upload doesn't
+    /// wait for the GPU, it's applied before the next submitted command buffer. Use
+    /// [CommandBuffer::write_texture_region](crate::gpu::command::CommandBuffer::write_texture_region)
+    /// to order the upload between passes of a command buffer.
+    pub fn write_region<T: bytemuck::Pod>(
+        &mut self,
+        data: &[T],
+        rect: Rect,
+    ) -> Result<(), TextureError> {
+        let data: &[u8] = bytemuck::cast_slice(data);
+        self.validate_region(data, rect)?;
+
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        {
+            let mut inner = self.inner.borrow_mut();
+            inner.straight_alpha |= Self::has_straight_alpha(inner.format, data);
+        }
+
+        self.write_rect_raw(rect, data);
+
+        Ok(())
+    }
+
+    /// Checks that the rectangle lies inside the texture and that `data` holds exactly its pixels.
+    pub(crate) fn validate_region(&self, data: &[u8], rect: Rect) -> Result<(), TextureError> {
+        let inner = self.inner.borrow();
+
+        if rect.x < 0
+            || rect.y < 0
+            || rect.w <= 0
+            || rect.h <= 0
+            || rect.x + rect.w > inner.size.x
+            || rect.y + rect.h > inner.size.y
+        {
+            crate::dbg_log!(
+                "Texture region {:?} is outside of the {}x{} texture",
+                rect,
+                inner.size.x,
+                inner.size.y
+            );
+            return Err(TextureError::InvalidTextureSize);
+        }
+
+        if inner.sample_count != SampleCount::SampleCount1 || inner.wgpu_depth_view.is_some() {
+            crate::dbg_log!("Texture regions can only be written to single sampled color textures");
+            return Err(TextureError::InvalidTextureFormat);
+        }
+
+        let expected_len = inner.format.get_size() as usize * rect.w as usize * rect.h as usize;
+        if data.len() != expected_len {
+            crate::dbg_log!(
+                "Texture region data size mismatch: expected {} bytes, got {}",
+                expected_len,
+                data.len()
+            );
+            return Err(TextureError::InvalidTextureData);
+        }
+
+        Ok(())
+    }
+
+    /// Records the upload of a rectangle into the command encoder, through a staging buffer with
+    /// rows padded to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+    pub(crate) fn write_region_cmd(
+        &self,
+        data: &[u8],
+        rect: Rect,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let inner = self.inner.borrow();
+
+        let unpadded_bytes_per_row = inner.format.get_size() * rect.w as u32;
+        let padded_bytes_per_row =
+            unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let mut padded_data = vec![0u8; (padded_bytes_per_row * rect.h as u32) as usize];
+        for (row, src) in data
+            .chunks_exact(unpadded_bytes_per_row as usize)
+            .enumerate()
+        {
+            let start = row * padded_bytes_per_row as usize;
+            padded_data[start..start + src.len()].copy_from_slice(src);
+        }
+
+        let buffer = self.graphics.borrow_mut().create_staging_buffer(
+            &padded_data,
+            wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+        );
+
+        encoder.copy_buffer_to_texture(
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(rect.h as u32),
+                },
+            },
+            wgpu::TexelCopyTextureInfo {
+                texture: &inner.wgpu_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: rect.x as u32,
+                    y: rect.y as u32,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::Extent3d {
+                width: rect.w as u32,
+                height: rect.h as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Uploads tightly packed pixels into a rectangle of the first mip level, without waiting
+    /// for the GPU.
+    pub(crate) fn write_rect_raw(&self, rect: Rect, data: &[u8]) {
+        let inner = self.inner.borrow();
+        let bytes_per_pixel = inner.format.get_size();
+
+        self.graphics.borrow().queue().write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &inner.wgpu_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: rect.x as u32,
+                    y: rect.y as u32,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_pixel * rect.w as u32),
+                rows_per_image: Some(rect.h as u32),
+            },
+            wgpu::Extent3d {
+                width: rect.w as u32,
+                height: rect.h as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
 }
 
 impl PartialEq for Texture {
@@ -548,16 +928,45 @@ impl PartialEq for TextureInner {
 
 impl Eq for TextureInner {}
 
+impl TextureInner {
+    /// The view used when binding this texture as a sampled shader input.
+    pub(crate) fn sample_view(&self) -> &wgpu::TextureView {
+        self.wgpu_depth_view.as_ref().unwrap_or(&self.wgpu_view)
+    }
+
+    /// Panics if the texture cannot be bound as a storage texture.
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    pub(crate) fn validate_storage_binding(&self) {
+        if !self.usages.contains(TextureUsage::StorageBinding) {
+            panic!(
+                "Texture ({}x{} {:?}) must be created with TextureUsage::StorageBinding to be bound as a storage texture",
+                self.size.x, self.size.y, self.format
+            );
+        }
+    }
+}
+
 pub struct TextureInner {
     pub(crate) wgpu_texture: wgpu::Texture,
     pub(crate) wgpu_view: wgpu::TextureView,
+    pub(crate) wgpu_depth_view: Option<wgpu::TextureView>,
 
     pub(crate) size: Point2,
     pub(crate) usages: TextureUsage,
     pub(crate) sample_count: SampleCount,
     pub(crate) format: TextureFormat,
+    /// Formats the texture can be reinterpreted as by a [view::TextureView].
+    pub(crate) view_formats: Vec<TextureFormat>,
 
     pub(crate) mapped: bool,
+    /// Sampler used when none is given at draw time.
+    pub(crate) sampler: Option<TextureSampler>,
+
+    /// Set when the uploaded data is known to not be premultiplied.
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    pub(crate) straight_alpha: bool,
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    pub(crate) straight_alpha_warned: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -585,6 +994,10 @@ pub enum TextureError {
     FailedToRead,
     AlreadyMapped,
     NotMapped,
+    InvalidSampler(SamplerError),
+    InvalidTextureView,
+    #[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+    External(external::ExternalTextureError),
 }
 
 impl std::fmt::Display for TextureError {
@@ -598,6 +1011,10 @@ impl std::fmt::Display for TextureError {
             TextureError::FailedToRead => write!(f, "Failed to read from texture"),
             TextureError::AlreadyMapped => write!(f, "Texture is already mapped"),
             TextureError::NotMapped => write!(f, "Texture is not mapped"),
+            TextureError::InvalidSampler(e) => write!(f, "Invalid sampler: {}", e),
+            TextureError::InvalidTextureView => write!(f, "Invalid texture view"),
+            #[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+            TextureError::External(e) => write!(f, "External memory error: {}", e),
         }
     }
 }
@@ -611,12 +1028,22 @@ pub enum TextureBuilderData<'a> {
     RenderTarget(Point2, Option<TextureFormat>),
 }
 
+/// Builder for creating textures from files, raw pixels or as render targets.
+///
+/// **NOTE:** Importing externally produced surfaces (dmabuf, DXGI shared handles, IOSurface) is not
+/// supported. wgpu starts tracking a wrapped texture in an undefined layout, so the content of the
+/// producer isn't kept, and it has no way to acquire the image from the external queue family.
+/// Decoded frames must be uploaded with [TextureBuilder::set_raw_image] or [Texture::write] instead.
 pub struct TextureBuilder<'a> {
     pub(crate) graphics: ArcRef<GPUInner>,
     pub(crate) sample_count: SampleCount,
     pub(crate) mip_level_count: u32,
+    pub(crate) array_layers: u32,
+    pub(crate) view_formats: Vec<TextureFormat>,
     pub(crate) usage: TextureUsage,
     pub(crate) data: TextureBuilderData<'a>,
+    pub(crate) sampler: Option<TextureSampler>,
+    pub(crate) label: Option<&'a str>,
 }
 
 impl<'a> TextureBuilder<'a> {
@@ -629,8 +1056,12 @@ impl<'a> TextureBuilder<'a> {
             graphics,
             sample_count: SampleCount::SampleCount1,
             mip_level_count: 1,
+            array_layers: 1,
+            view_formats: Vec::new(),
             usage: TextureUsage::None,
             data: TextureBuilderData::None,
+            sampler: None,
+            label: None,
         }
     }
 
@@ -680,6 +1111,11 @@ impl<'a> TextureBuilder<'a> {
     }
 
     /// Initializes a texture as a depth stencil texture.
+    ///
+    /// Combine with `set_usage(TextureUsage::Sampler)` to sample the depth buffer in a later pass
+    /// as a `texture_depth_2d` shader input, eg. for SSAO or soft particles. Shadow maps are read
+    /// with `textureSampleCompare`, which needs a `sampler_comparison` bound to a
+    /// [TextureSampler] with a compare function.
     pub fn set_depth_stencil(mut self, size: Point2, format: Option<TextureFormat>) -> Self {
         if size.x == 0 || size.y == 0 {
             panic!("Depth stencil texture must have a size");
@@ -698,6 +1134,27 @@ impl<'a> TextureBuilder<'a> {
         self
     }
 
+    /// Sets the number of array layers of the texture, 1 by default.
+    ///
+    /// Shaders only bind 2D textures, each layer is bound or rendered to through a
+    /// [TextureView](view::TextureView) created with [TextureViewDesc::layer](view::TextureViewDesc::layer).
+    /// Data written to the texture only fills the first layer.
+    pub fn set_array_layers(mut self, count: u32) -> Self {
+        self.array_layers = count;
+        self
+    }
+
+    /// Allows views of the texture to reinterpret it in other formats, see
+    /// [Texture::create_view].
+    ///
+    /// Only the sRGB and linear variants of the texture format are allowed, eg. `Rgba8Unorm`
+    /// for a `Rgba8UnormSrgb` texture. Building fails with [TextureError::InvalidTextureFormat]
+    /// otherwise.
+    pub fn set_view_formats(mut self, formats: &[TextureFormat]) -> Self {
+        self.view_formats = formats.to_vec();
+        self
+    }
+
     /// Sets the usage of the texture.
     ///
     /// This method allows you to specify the usage of the texture. However it cannot set the texture as
@@ -711,6 +1168,34 @@ impl<'a> TextureBuilder<'a> {
         self
     }
 
+    /// Allows the texture to be bound with `set_attachment_texture_storage`, in addition to the
+    /// usage set with [TextureBuilder::set_usage].
+    ///
+    /// The texel format must match the format declared by the shader, eg. `rgba8unorm` for
+    /// `texture_storage_2d<rgba8unorm, write>`. Building fails with
+    /// [TextureError::InvalidTextureFormat] if the device cannot use the format as a storage
+    /// texture.
+    pub fn set_storage_binding(mut self) -> Self {
+        self.usage |= TextureUsage::StorageBinding;
+        self
+    }
+
+    /// Sets the sampler used when the texture is drawn without an explicit one, eg. with
+    /// [DrawingContext::set_texture](crate::gpu::command::drawing::DrawingContext::set_texture)
+    /// or [RenderPass::set_attachment_texture_sampled](crate::gpu::command::renderpass::RenderPass::set_attachment_texture_sampled).
+    ///
+    /// The default is [TextureSampler::DEFAULT].
+    pub fn set_sampler(mut self, sampler: TextureSampler) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+
+    /// Sets the debug label of the texture, shown in graphics debuggers.
+    pub fn set_label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
     pub fn build(self) -> Result<Texture, TextureError> {
         Texture::from_builder(self)
     }
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use crate::{math::Point2, utils::ArcRef};
+
+use super::{super::GPUInner, SampleCount};
+
+/// Index used for the multisampled depth target, color targets use their attachment index.
+const MSAA_DEPTH_INDEX: usize = usize::MAX;
+
+/// Frames a texture of [MsaaTextureCache] is kept without being used.
+const MSAA_TEXTURE_LIFETIME: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct MsaaTextureKey {
+    index: usize,
+    width: i32,
+    height: i32,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+}
+
+/// Cache of the multisampled textures used by `RenderpassBuilder::with_msaa`.
+///
+/// Textures not used for a few frames are dropped, eg. the ones of the previous size after the
+/// surface is resized.
+#[derive(Debug, Clone)]
+pub(crate) struct MsaaTextureCache {
+    textures: HashMap<MsaaTextureKey, (wgpu::TextureView, usize)>,
+}
+
+impl MsaaTextureCache {
+    pub fn new() -> Self {
+        Self {
+            textures: HashMap::new(),
+        }
+    }
+
+    pub fn get_or_create(
+        &mut self,
+        device: &wgpu::Device,
+        index: usize,
+        size: Point2,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> wgpu::TextureView {
+        let key = MsaaTextureKey {
+            index,
+            width: size.x,
+            height: size.y,
+            format,
+            sample_count,
+        };
+
+        if let Some((view, lifetime)) = self.textures.get_mut(&key) {
+            *lifetime = 0;
+            return view.clone();
+        }
+
+        let label = format!("MSAA Texture {} ({:?}, {}x)", index, format, sample_count);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label.as_str()),
+            size: wgpu::Extent3d {
+                width: size.x as u32,
+                height: size.y as u32,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        crate::dbg_log!(
+            "Created MSAA texture {} with size {:?}, format {:?} and {} samples",
+            index,
+            size,
+            format,
+            sample_count
+        );
+
+        self.textures.insert(key, (view.clone(), 0));
+
+        view
+    }
+
+    /// Drops the textures not used during the last [MSAA_TEXTURE_LIFETIME] frames.
+    pub fn cycle(&mut self) {
+        self.textures.retain(|_, (_, lifetime)| {
+            *lifetime += 1;
+            *lifetime <= MSAA_TEXTURE_LIFETIME
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.textures.clear();
+    }
+}
+
+/// Returns the highest sample count the adapter supports for rendering to `format`.
+pub(crate) fn max_sample_count(adapter: &wgpu::Adapter, format: wgpu::TextureFormat) -> u32 {
+    let flags = adapter.get_texture_format_features(format).flags;
+
+    [8, 4, 2]
+        .into_iter()
+        .find(|count| flags.sample_count_supported(*count))
+        .unwrap_or(1)
+}
+
+pub(crate) fn sample_count_from_u32(count: u32) -> SampleCount {
+    match count {
+        8.. => SampleCount::SampleCount8,
+        4..=7 => SampleCount::SampleCount4,
+        2..=3 => SampleCount::SampleCount2,
+        _ => SampleCount::SampleCount1,
+    }
+}
+
+/// Multisampled color and depth targets owned by the user, attached to a render pass with
+/// [RenderpassBuilder::with_msaa_targets](crate::gpu::command::renderpass::RenderpassBuilder::with_msaa_targets).
+///
+/// The targets are created on first use, matching the formats and size of the render pass
+/// attachments, and recreated when those change, eg. when the window is resized. The color
+/// targets are resolved into the pass attachments at the end of the pass.
+///
+/// The sample count is lowered to the highest count the adapter supports for the attachment
+/// formats, see [GPU::max_sample_count](crate::gpu::GPU::max_sample_count).
+///
+/// Unlike [RenderpassBuilder::with_msaa](crate::gpu::command::renderpass::RenderpassBuilder::with_msaa),
+/// the memory is released as soon as the targets are dropped or [MsaaTargets::release] is called.
+#[derive(Debug, Clone)]
+pub struct MsaaTargets {
+    graphics: ArcRef<GPUInner>,
+    inner: ArcRef<MsaaTargetsInner>,
+}
+
+#[derive(Debug)]
+struct MsaaTargetsInner {
+    sample_count: SampleCount,
+    size: Point2,
+    device: Option<wgpu::Device>,
+    cache: MsaaTextureCache,
+}
+
+impl MsaaTargets {
+    pub(crate) fn new(graphics: ArcRef<GPUInner>, sample_count: SampleCount) -> Self {
+        Self {
+            graphics,
+            inner: ArcRef::new(MsaaTargetsInner {
+                sample_count,
+                size: Point2::new(0, 0),
+                device: None,
+                cache: MsaaTextureCache::new(),
+            }),
+        }
+    }
+
+    /// The requested sample count, before it is lowered to what the formats support.
+    pub fn sample_count(&self) -> SampleCount {
+        self.inner.borrow().sample_count
+    }
+
+    /// Changes the sample count, the targets are recreated on the next render pass.
+    pub fn set_sample_count(&mut self, sample_count: SampleCount) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.sample_count != sample_count {
+            inner.sample_count = sample_count;
+            inner.cache.clear();
+        }
+    }
+
+    /// Size of the targets, (0, 0) until they are used in a render pass.
+    pub fn size(&self) -> Point2 {
+        self.inner.borrow().size
+    }
+
+    /// Frees the multisampled textures, they are created again on the next render pass.
+    pub fn release(&mut self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.cache.clear();
+        inner.size = Point2::new(0, 0);
+    }
+
+    /// Returns the multisampled views for the given attachments and the sample count used,
+    /// creating or resizing them when needed.
+    pub(crate) fn prepare(
+        &self,
+        size: Point2,
+        color_formats: &[wgpu::TextureFormat],
+        depth_format: Option<wgpu::TextureFormat>,
+    ) -> (Vec<wgpu::TextureView>, Option<wgpu::TextureView>, u32) {
+        let graphics = self.graphics.borrow();
+        let mut inner = self.inner.borrow_mut();
+
+        let device = graphics.device();
+
+        // Views from a lost device can't be used anymore
+        if inner.device.as_ref() != Some(device) {
+            inner.cache.clear();
+            inner.device = Some(device.clone());
+        }
+
+        let requested: u32 = inner.sample_count.into();
+        let adapter = graphics.adapter.as_ref().unwrap();
+        let sample_count = color_formats
+            .iter()
+            .chain(depth_format.iter())
+            .map(|format| max_sample_count(adapter, *format))
+            .fold(requested, u32::min);
+
+        if inner.size != size {
+            if sample_count != requested {
+                crate::warn_log!(
+                    "MSAA sample count {}x is not supported by the attachment formats, using {}x",
+                    requested,
+                    sample_count
+                );
+            }
+
+            // The targets are only ever used at one size
+            inner.cache.clear();
+            inner.size = size;
+        }
+
+        if sample_count <= 1 {
+            return (Vec::new(), None, 1);
+        }
+
+        let colors = color_formats
+            .iter()
+            .enumerate()
+            .map(|(index, format)| {
+                inner
+                    .cache
+                    .get_or_create(device, index, size, *format, sample_count)
+            })
+            .collect();
+
+        let depth = depth_format.map(|format| {
+            inner
+                .cache
+                .get_or_create(device, MSAA_DEPTH_INDEX, size, format, sample_count)
+        });
+
+        (colors, depth, sample_count)
+    }
+}
@@ -0,0 +1,261 @@
+use crate::{math::Point2, utils::ArcRef};
+
+use super::{
+    super::GPUInner,
+    Texture,
+    TextureBuilder,
+    TextureError,
+    TextureFormat,
+    TextureSampler,
+    TextureUsage,
+};
+
+/// Size of the textures of a [PingPongTarget].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PingPongSize {
+    Fixed(Point2),
+    /// A fraction of the surface size, eg. 0.5 for a half resolution blur chain. The textures
+    /// follow the window when [PingPongTarget::update] is called.
+    Surface(f32),
+}
+
+/// Two render targets of the same size and format, for effects feeding their previous output
+/// back as input, eg. blur chains or simulations.
+///
+/// Each step reads [PingPongTarget::source] and renders to [PingPongTarget::destination], then
+/// [PingPongTarget::swap] makes the result the source of the next step.
+///
+/// ```rust,ignore
+/// let mut target = gpu.create_pingpong_target().set_surface_scale(0.5).build()?;
+///
+/// target.update()?;
+/// for _ in 0..4 {
+///     let mut pass = cmd.begin_texture(target.destination())?;
+///     pass.set_shader(Some(&blur));
+///     pass.set_attachment_texture_sampled(0, 0, 1, Some(target.source()));
+///     pass.draw(0..3, 1);
+///     drop(pass);
+///
+///     target.swap();
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PingPongTarget {
+    graphics: ArcRef<GPUInner>,
+    textures: [Texture; 2],
+    /// Index of the texture holding the last result.
+    source: usize,
+    size: Point2,
+    mode: PingPongSize,
+    format: TextureFormat,
+    storage_binding: bool,
+}
+
+impl PingPongTarget {
+    /// The texture holding the result of the last step.
+    pub fn source(&self) -> &Texture {
+        &self.textures[self.source]
+    }
+
+    /// The texture the next step renders to.
+    pub fn destination(&self) -> &Texture {
+        &self.textures[1 - self.source]
+    }
+
+    /// Makes the destination the source of the next step.
+    pub fn swap(&mut self) {
+        self.source = 1 - self.source;
+    }
+
+    pub fn size(&self) -> Point2 {
+        self.size
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    pub fn size_mode(&self) -> PingPongSize {
+        self.mode
+    }
+
+    /// Follows the surface size when the textures are sized after the window, call it once per
+    /// frame before rendering.
+    ///
+    /// Returns true when the textures were recreated, their contents are lost, eg. a
+    /// simulation must be seeded again.
+    pub fn update(&mut self) -> Result<bool, TextureError> {
+        let size = resolve_size(&self.graphics, self.mode)?;
+        if size == self.size {
+            return Ok(false);
+        }
+
+        self.recreate(size)?;
+
+        Ok(true)
+    }
+
+    /// Switches to a fixed size, recreating the textures if it changed. Their contents are lost.
+    pub fn resize(&mut self, size: Point2) -> Result<(), TextureError> {
+        self.mode = PingPongSize::Fixed(size);
+
+        if size != self.size {
+            self.recreate(size)?;
+        }
+
+        Ok(())
+    }
+
+    fn recreate(&mut self, size: Point2) -> Result<(), TextureError> {
+        let sampler = self.textures[0].inner.borrow().sampler;
+
+        self.textures = create_textures(
+            &self.graphics,
+            size,
+            self.format,
+            self.storage_binding,
+            sampler,
+        )?;
+        self.source = 0;
+        self.size = size;
+
+        crate::dbg_log!("Ping-pong target resized to {}x{}", size.x, size.y);
+
+        Ok(())
+    }
+}
+
+pub struct PingPongTargetBuilder {
+    graphics: ArcRef<GPUInner>,
+    mode: PingPongSize,
+    format: Option<TextureFormat>,
+    storage_binding: bool,
+    sampler: Option<TextureSampler>,
+}
+
+impl PingPongTargetBuilder {
+    pub(crate) fn new(graphics: ArcRef<GPUInner>) -> Self {
+        Self {
+            graphics,
+            mode: PingPongSize::Surface(1.0),
+            format: None,
+            storage_binding: false,
+            sampler: None,
+        }
+    }
+
+    /// Uses textures of a fixed size, instead of the surface size.
+    pub fn set_size(mut self, size: Point2) -> Self {
+        self.mode = PingPongSize::Fixed(size);
+        self
+    }
+
+    /// Sizes the textures after the surface scaled by `scale`, the default with a scale of 1.0.
+    pub fn set_surface_scale(mut self, scale: f32) -> Self {
+        self.mode = PingPongSize::Surface(scale);
+        self
+    }
+
+    /// Sets the texture format, the swapchain format by default.
+    pub fn set_format(mut self, format: TextureFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Allows the textures to be written by compute passes, see
+    /// [TextureBuilder::set_storage_binding].
+    pub fn set_storage_binding(mut self) -> Self {
+        self.storage_binding = true;
+        self
+    }
+
+    /// Sets the sampler the textures are drawn with, see [TextureBuilder::set_sampler].
+    pub fn set_sampler(mut self, sampler: TextureSampler) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+
+    pub fn build(self) -> Result<PingPongTarget, TextureError> {
+        let size = resolve_size(&self.graphics, self.mode)?;
+
+        let format = match self.format {
+            Some(format) => format,
+            None => {
+                let graphics_ref = self.graphics.borrow();
+                match graphics_ref.config.as_ref() {
+                    Some(config) => config.format.into(),
+                    None => TextureFormat::Rgba8UnormSrgb,
+                }
+            }
+        };
+
+        let textures = create_textures(
+            &self.graphics,
+            size,
+            format,
+            self.storage_binding,
+            self.sampler,
+        )?;
+
+        Ok(PingPongTarget {
+            graphics: self.graphics,
+            textures,
+            source: 0,
+            size,
+            mode: self.mode,
+            format,
+            storage_binding: self.storage_binding,
+        })
+    }
+}
+
+fn resolve_size(graphics: &ArcRef<GPUInner>, mode: PingPongSize) -> Result<Point2, TextureError> {
+    let size = match mode {
+        PingPongSize::Fixed(size) => size,
+        PingPongSize::Surface(scale) => {
+            let graphics_ref = graphics.borrow();
+            let Some(config) = graphics_ref.config.as_ref() else {
+                crate::dbg_log!("Ping-pong target sized after the surface, but there is no surface");
+                return Err(TextureError::InvalidTextureSize);
+            };
+
+            Point2::new(
+                ((config.width as f32 * scale).round() as i32).max(1),
+                ((config.height as f32 * scale).round() as i32).max(1),
+            )
+        }
+    };
+
+    if size.x <= 0 || size.y <= 0 {
+        return Err(TextureError::InvalidTextureSize);
+    }
+
+    Ok(size)
+}
+
+fn create_textures(
+    graphics: &ArcRef<GPUInner>,
+    size: Point2,
+    format: TextureFormat,
+    storage_binding: bool,
+    sampler: Option<TextureSampler>,
+) -> Result<[Texture; 2], TextureError> {
+    let create = |label: &str| {
+        let mut builder = TextureBuilder::new(ArcRef::clone(graphics))
+            .set_render_target(size, Some(format))
+            .set_usage(TextureUsage::Sampler)
+            .set_label(label);
+
+        if storage_binding {
+            builder = builder.set_storage_binding();
+        }
+
+        if let Some(sampler) = sampler {
+            builder = builder.set_sampler(sampler);
+        }
+
+        builder.build()
+    };
+
+    Ok([create("Ping-Pong Target A")?, create("Ping-Pong Target B")?])
+}
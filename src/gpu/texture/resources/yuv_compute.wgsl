@@ -0,0 +1,50 @@
+// Compute variant of yuv_shader.wgsl, used by StreamingTexture
+struct YuvParams {
+    // Color matrix coefficients: R = Y + cr_r * Cr, G = Y + cb_g * Cb + cr_g * Cr, B = Y + cb_b * Cb
+    cr_r: f32,
+    cb_g: f32,
+    cr_g: f32,
+    cb_b: f32,
+    luma_scale: f32,
+    luma_offset: f32,
+    // 0 = NV12, 1 = I420
+    format: u32,
+    _pad: u32,
+};
+
+@group(0) @binding(0) var yPlane: texture_2d<f32>;
+@group(0) @binding(1) var uPlane: texture_2d<f32>;
+@group(0) @binding(2) var vPlane: texture_2d<f32>;
+@group(0) @binding(3) var<uniform> params: YuvParams;
+@group(0) @binding(4) var outputTexture: texture_storage_2d<rgba8unorm, write>;
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(outputTexture);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+
+    let position = vec2<i32>(id.xy);
+    let chroma_position = position / 2;
+
+    let y = textureLoad(yPlane, position, 0).r;
+    let u_sample = textureLoad(uPlane, chroma_position, 0);
+    let v_sample = textureLoad(vPlane, chroma_position, 0);
+
+    // NV12 interleaves UV in one plane, I420 stores them in separate planes
+    let u = u_sample.r;
+    let v = select(v_sample.r, u_sample.g, params.format == 0u);
+
+    let luma = params.luma_scale * (y - params.luma_offset);
+    let cb = u - 0.5;
+    let cr = v - 0.5;
+
+    let rgb = vec3<f32>(
+        luma + params.cr_r * cr,
+        luma + params.cb_g * cb + params.cr_g * cr,
+        luma + params.cb_b * cb,
+    );
+
+    textureStore(outputTexture, position, vec4<f32>(clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0));
+}
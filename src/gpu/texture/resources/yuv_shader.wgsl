@@ -0,0 +1,63 @@
+// Vertex Shader
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) texCoord: vec2<f32>,
+};
+
+@vertex
+fn main_vertex(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // Fullscreen triangle, no vertex buffer needed
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    output.texCoord = uv;
+    return output;
+}
+
+// Fragment Shader
+struct YuvParams {
+    // Color matrix coefficients: R = Y + cr_r * Cr, G = Y + cb_g * Cb + cr_g * Cr, B = Y + cb_b * Cb
+    cr_r: f32,
+    cb_g: f32,
+    cr_g: f32,
+    cb_b: f32,
+    luma_scale: f32,
+    luma_offset: f32,
+    // 0 = NV12, 1 = I420
+    format: u32,
+    _pad: u32,
+};
+
+@group(0) @binding(0) var yPlane: texture_2d<f32>;
+@group(0) @binding(1) var uPlane: texture_2d<f32>;
+@group(0) @binding(2) var vPlane: texture_2d<f32>;
+@group(0) @binding(3) var planeSampler: sampler;
+@group(0) @binding(4) var<uniform> params: YuvParams;
+
+struct FragmentInput {
+    @location(0) texCoord: vec2<f32>,
+};
+
+@fragment
+fn main_fragment(input: FragmentInput) -> @location(0) vec4<f32> {
+    let y = textureSample(yPlane, planeSampler, input.texCoord).r;
+    let u_sample = textureSample(uPlane, planeSampler, input.texCoord);
+    let v_sample = textureSample(vPlane, planeSampler, input.texCoord);
+
+    // NV12 interleaves UV in one plane, I420 stores them in separate planes
+    let u = u_sample.r;
+    let v = select(v_sample.r, u_sample.g, params.format == 0u);
+
+    let luma = params.luma_scale * (y - params.luma_offset);
+    let cb = u - 0.5;
+    let cr = v - 0.5;
+
+    let rgb = vec3<f32>(
+        luma + params.cr_r * cr,
+        luma + params.cb_g * cb + params.cr_g * cr,
+        luma + params.cb_b * cb,
+    );
+
+    return vec4<f32>(clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0);
+}
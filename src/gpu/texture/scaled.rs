@@ -0,0 +1,172 @@
+use crate::{
+    math::{Color, Point2, RectF, Vector2},
+    utils::ArcRef,
+};
+
+use super::{
+    super::{
+        GPUInner,
+        command::{CommandBuffer, renderpass::RenderPassBuildError},
+    },
+    Texture,
+    TextureBuilder,
+    TextureError,
+    TextureSampler,
+    TextureUsage,
+};
+
+/// How a [ScaledTarget] is scaled to the window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresentScaling {
+    /// Largest whole multiple of the target size that fits the window, so every pixel is drawn
+    /// as a square of the same size. The remaining area is letterboxed.
+    Integer,
+    /// Largest size that fits the window keeping the aspect ratio, pixels may differ in size.
+    Fit,
+    /// Fills the whole window, ignoring the aspect ratio.
+    Stretch,
+}
+
+/// A render target with a fixed internal resolution, scaled to the window when presented.
+///
+/// This is meant for pixel-art, where the frame is drawn at a low resolution and upscaled
+/// with nearest filtering, instead of letting the surface blur it with bilinear filtering.
+///
+/// Render the frame into [ScaledTarget::texture] with [CommandBuffer::begin_texture], then
+/// call [ScaledTarget::present] to draw it to the window.
+#[derive(Debug, Clone)]
+pub struct ScaledTarget {
+    graphics: ArcRef<GPUInner>,
+    texture: Texture,
+    size: Point2,
+    scaling: PresentScaling,
+    letterbox_color: Color,
+}
+
+impl ScaledTarget {
+    pub(crate) fn new(
+        graphics: ArcRef<GPUInner>,
+        size: Point2,
+        scaling: PresentScaling,
+    ) -> Result<Self, TextureError> {
+        let texture = Self::create_texture(&graphics, size)?;
+
+        Ok(Self {
+            graphics,
+            texture,
+            size,
+            scaling,
+            letterbox_color: Color::BLACK,
+        })
+    }
+
+    fn create_texture(graphics: &ArcRef<GPUInner>, size: Point2) -> Result<Texture, TextureError> {
+        if size.x <= 0 || size.y <= 0 {
+            return Err(TextureError::InvalidTextureSize);
+        }
+
+        TextureBuilder::new(ArcRef::clone(graphics))
+            .set_render_target(size, None)
+            .set_usage(TextureUsage::Sampler)
+            .set_label("Scaled Target")
+            .build()
+    }
+
+    /// The texture to render the frame to, at the internal resolution.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn size(&self) -> Point2 {
+        self.size
+    }
+
+    /// Recreates the texture with a new internal resolution, its contents are lost.
+    pub fn resize(&mut self, size: Point2) -> Result<(), TextureError> {
+        if size == self.size {
+            return Ok(());
+        }
+
+        self.texture = Self::create_texture(&self.graphics, size)?;
+        self.size = size;
+
+        Ok(())
+    }
+
+    pub fn scaling(&self) -> PresentScaling {
+        self.scaling
+    }
+
+    pub fn set_scaling(&mut self, scaling: PresentScaling) {
+        self.scaling = scaling;
+    }
+
+    /// Sets the color of the area around the frame, black by default.
+    pub fn set_letterbox_color(&mut self, color: Color) {
+        self.letterbox_color = color;
+    }
+
+    /// Returns where the frame is drawn in a window of the given size, in pixels.
+    pub fn dest_rect(&self, window_size: Point2) -> RectF {
+        let target = Vector2::new(self.size.x as f32, self.size.y as f32);
+        let window = Vector2::new(window_size.x as f32, window_size.y as f32);
+
+        let size = match self.scaling {
+            PresentScaling::Integer => {
+                let scale = (window_size.x / self.size.x)
+                    .min(window_size.y / self.size.y)
+                    .max(1);
+
+                target * scale as f32
+            }
+            PresentScaling::Fit => {
+                let scale = f32::min(window.x / target.x, window.y / target.y);
+
+                target * scale
+            }
+            PresentScaling::Stretch => window,
+        };
+
+        // Keep the frame on whole pixels, so the integer scaling stays exact
+        let pos = Vector2::new(
+            ((window.x - size.x) * 0.5).floor(),
+            ((window.y - size.y) * 0.5).floor(),
+        );
+
+        RectF::new(pos.x, pos.y, size.x, size.y)
+    }
+
+    /// Maps a window position (eg. the cursor) to the internal resolution.
+    ///
+    /// Returns [None] when the position is in the letterbox area.
+    pub fn window_to_target(&self, pos: Vector2, window_size: Point2) -> Option<Vector2> {
+        let rect = self.dest_rect(window_size);
+        if rect.is_empty() || !rect.is_touch(pos.x, pos.y) {
+            return None;
+        }
+
+        Some(Vector2::new(
+            (pos.x - rect.x) / rect.w * self.size.x as f32,
+            (pos.y - rect.y) / rect.h * self.size.y as f32,
+        ))
+    }
+
+    /// Draws the frame to the window surface, scaled with nearest filtering.
+    pub fn present(&self, cmd: &mut CommandBuffer) -> Result<(), RenderPassBuildError> {
+        let mut pass = cmd.begin_renderpass()?;
+        pass.set_clear_color(self.letterbox_color);
+
+        let rect = self.dest_rect(pass.surface_size());
+
+        if let Some(mut drawing) = pass.begin_drawing() {
+            drawing.set_texture_ex(Some(&self.texture), Some(TextureSampler::NEAREST));
+            drawing.draw_rect_image(
+                Vector2::new(rect.x, rect.y),
+                Vector2::new(rect.w, rect.h),
+                Color::WHITE,
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,441 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use crate::{math::Point2, utils::ArcRef};
+
+use super::{
+    super::{
+        GPUInner,
+        command::CommandBuffer,
+        pipeline::compute::{CompuitePipelineError, ComputePipeline, ComputePipelineBuilder},
+        shader::compute::ComputeShaderBuilder,
+    },
+    Texture,
+    TextureBuilder,
+    TextureError,
+    TextureFormat,
+    TextureUsage,
+    yuv::{YuvColorSpace, YuvFormat, YuvParams},
+};
+
+/// Staging buffers a [StreamingTexture] may cycle through before frames are dropped.
+const MAX_STAGING_BUFFERS: usize = 3;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Layout of the frames passed to [StreamingTexture::update].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StreamingFormat {
+    /// A single plane of tightly packed RGBA8 pixels.
+    Rgba8,
+    /// Planes of a YUV frame, converted to RGB by a compute pass after each upload.
+    Yuv(YuvFormat, YuvColorSpace),
+}
+
+impl StreamingFormat {
+    fn plane_count(&self) -> usize {
+        match self {
+            StreamingFormat::Rgba8 => 1,
+            StreamingFormat::Yuv(format, _) => format.plane_count(),
+        }
+    }
+
+    fn plane_layout(&self, index: usize, size: Point2) -> (Point2, TextureFormat) {
+        match self {
+            StreamingFormat::Rgba8 => (size, TextureFormat::Rgba8Unorm),
+            StreamingFormat::Yuv(format, _) => format.plane_layout(index, size),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum StreamingTextureError {
+    InvalidSize,
+    /// The frame doesn't match the format and size of the stream.
+    InvalidFrameData(String),
+    Texture(TextureError),
+    Shader(String),
+    Pipeline(CompuitePipelineError),
+    FailedToSubmit,
+}
+
+impl std::fmt::Display for StreamingTextureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamingTextureError::InvalidSize => write!(f, "Invalid streaming texture size"),
+            StreamingTextureError::InvalidFrameData(msg) => write!(f, "Invalid frame data: {}", msg),
+            StreamingTextureError::Texture(err) => write!(f, "Texture error: {}", err),
+            StreamingTextureError::Shader(msg) => write!(f, "Shader error: {}", msg),
+            StreamingTextureError::Pipeline(err) => write!(f, "Pipeline error: {:?}", err),
+            StreamingTextureError::FailedToSubmit => write!(f, "Failed to submit the frame upload"),
+        }
+    }
+}
+
+impl std::error::Error for StreamingTextureError {}
+
+/// Where a plane is stored in the staging buffers, rows are padded to
+/// `COPY_BYTES_PER_ROW_ALIGNMENT`.
+#[derive(Clone, Copy, Debug)]
+struct PlaneLayout {
+    size: Point2,
+    row_bytes: u32,
+    padded_row_bytes: u32,
+    offset: u64,
+}
+
+#[derive(Clone, Debug)]
+struct StagingBuffer {
+    buffer: wgpu::Buffer,
+    /// Set once the buffer is mapped again and can receive the next frame.
+    mapped: Arc<AtomicBool>,
+}
+
+/// A texture updated with a new frame every frame, eg. by a video player or a camera feed.
+///
+/// Frames are written into a ring of mapped staging buffers and copied to the texture on the
+/// GPU, so [StreamingTexture::update] never waits for the GPU like [Texture::write] does. YUV
+/// frames are converted to RGB by a compute pass right after the copy.
+///
+/// When the GPU is still busy with all the staging buffers, the frame is dropped and
+/// [StreamingTexture::update] returns `false`, a video should simply present the next one.
+///
+/// ```rust,ignore
+/// let mut stream = gpu.create_streaming_texture(
+///     Point2::new(1920, 1080),
+///     StreamingFormat::Yuv(YuvFormat::Nv12, YuvColorSpace::Bt709),
+/// )?;
+///
+/// stream.update(&[&y_plane, &uv_plane])?;
+/// drawing.set_texture(Some(stream.texture()));
+/// ```
+#[derive(Debug)]
+pub struct StreamingTexture {
+    graphics: ArcRef<GPUInner>,
+    texture: Texture,
+    /// Textures receiving the YUV planes, empty for RGBA streams.
+    planes: Vec<Texture>,
+    pipeline: Option<ComputePipeline>,
+    format: StreamingFormat,
+    size: Point2,
+    layouts: Vec<PlaneLayout>,
+    staging_size: u64,
+    staging: Vec<StagingBuffer>,
+    frame_count: u64,
+    dropped_frames: u64,
+}
+
+impl StreamingTexture {
+    pub(crate) fn new(
+        graphics: ArcRef<GPUInner>,
+        size: Point2,
+        format: StreamingFormat,
+    ) -> Result<Self, StreamingTextureError> {
+        if size.x <= 0 || size.y <= 0 {
+            return Err(StreamingTextureError::InvalidSize);
+        }
+
+        let mut layouts = Vec::with_capacity(format.plane_count());
+        let mut offset = 0;
+
+        for index in 0..format.plane_count() {
+            let (plane_size, plane_format) = format.plane_layout(index, size);
+            let row_bytes = plane_format.get_size() * plane_size.x as u32;
+            let padded_row_bytes = row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+            layouts.push(PlaneLayout {
+                size: plane_size,
+                row_bytes,
+                padded_row_bytes,
+                offset,
+            });
+
+            // Padded rows keep every plane offset aligned for the copy
+            offset += padded_row_bytes as u64 * plane_size.y as u64;
+        }
+
+        let (texture, planes, pipeline) = match format {
+            StreamingFormat::Rgba8 => {
+                let texture = TextureBuilder::new(ArcRef::clone(&graphics))
+                    .set_render_target(size, Some(TextureFormat::Rgba8Unorm))
+                    .set_usage(TextureUsage::Sampler)
+                    .set_label("Streaming Texture")
+                    .build()
+                    .map_err(StreamingTextureError::Texture)?;
+
+                (texture, Vec::new(), None)
+            }
+            StreamingFormat::Yuv(yuv_format, color_space) => {
+                let texture = TextureBuilder::new(ArcRef::clone(&graphics))
+                    .set_render_target(size, Some(TextureFormat::Rgba8Unorm))
+                    .set_usage(TextureUsage::Sampler)
+                    .set_storage_binding()
+                    .set_label("Streaming Texture")
+                    .build()
+                    .map_err(StreamingTextureError::Texture)?;
+
+                let planes = layouts
+                    .iter()
+                    .enumerate()
+                    .map(|(index, layout)| {
+                        let (_, plane_format) = format.plane_layout(index, size);
+
+                        TextureBuilder::new(ArcRef::clone(&graphics))
+                            .set_render_target(layout.size, Some(plane_format))
+                            .set_usage(TextureUsage::Sampler)
+                            .set_label("Streaming YUV Plane")
+                            .build()
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(StreamingTextureError::Texture)?;
+
+                let pipeline = Self::create_conversion_pipeline(
+                    &graphics,
+                    &texture,
+                    &planes,
+                    yuv_format,
+                    color_space,
+                )?;
+
+                (texture, planes, Some(pipeline))
+            }
+        };
+
+        Ok(Self {
+            graphics,
+            texture,
+            planes,
+            pipeline,
+            format,
+            size,
+            layouts,
+            staging_size: offset,
+            staging: Vec::with_capacity(MAX_STAGING_BUFFERS),
+            frame_count: 0,
+            dropped_frames: 0,
+        })
+    }
+
+    fn create_conversion_pipeline(
+        graphics: &ArcRef<GPUInner>,
+        texture: &Texture,
+        planes: &[Texture],
+        format: YuvFormat,
+        color_space: YuvColorSpace,
+    ) -> Result<ComputePipeline, StreamingTextureError> {
+        let shader = ComputeShaderBuilder::new(ArcRef::clone(graphics))
+            .set_source(include_str!("./resources/yuv_compute.wgsl"))
+            .set_label("YUV Streaming Conversion Shader")
+            .build()
+            .map_err(StreamingTextureError::Shader)?;
+
+        // NV12 has no third plane, the shader reads V from the interleaved plane instead
+        let v_plane = planes.get(2).unwrap_or(&planes[1]);
+
+        ComputePipelineBuilder::new(ArcRef::clone(graphics))
+            .set_shader(Some(&shader))
+            .set_attachment_texture(0, 0, Some(&planes[0]))
+            .set_attachment_texture(0, 1, Some(&planes[1]))
+            .set_attachment_texture(0, 2, Some(v_plane))
+            .set_attachment_uniform_raw(0, 3, Some(&[YuvParams::new(format, color_space)]))
+            .set_attachment_texture_storage(0, 4, Some(texture))
+            .set_label("YUV Streaming Conversion")
+            .build()
+            .map_err(StreamingTextureError::Pipeline)
+    }
+
+    /// The texture holding the last uploaded frame, in `Rgba8Unorm`.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn size(&self) -> Point2 {
+        self.size
+    }
+
+    pub fn format(&self) -> StreamingFormat {
+        self.format
+    }
+
+    /// Number of frames uploaded since the stream was created.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Number of frames dropped because every staging buffer was still in use by the GPU.
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+
+    /// Uploads a new frame to the texture without waiting for the GPU.
+    ///
+    /// `planes` must contain the tightly packed planes of the frame: one for
+    /// [StreamingFormat::Rgba8], 2 for [YuvFormat::Nv12] and 3 for [YuvFormat::I420], with the
+    /// chroma planes at half the texture resolution (rounded up).
+    ///
+    /// Returns `false` if the frame was dropped because the GPU didn't finish the previous
+    /// uploads yet.
+    pub fn update(&mut self, planes: &[&[u8]]) -> Result<bool, StreamingTextureError> {
+        self.validate_frame(planes)?;
+
+        let Some(index) = self.acquire_staging_buffer() else {
+            self.dropped_frames += 1;
+            crate::dbg_log!("Streaming texture dropped a frame, all staging buffers are in use");
+
+            return Ok(false);
+        };
+
+        let staging = self.staging[index].clone();
+
+        {
+            let mut mapped = staging.buffer.slice(..).get_mapped_range_mut();
+
+            for (layout, plane) in self.layouts.iter().zip(planes) {
+                let rows = plane.chunks_exact(layout.row_bytes as usize);
+
+                for (row_index, row) in rows.enumerate() {
+                    let start =
+                        layout.offset as usize + row_index * layout.padded_row_bytes as usize;
+                    mapped[start..start + row.len()].copy_from_slice(row);
+                }
+            }
+        }
+
+        staging.buffer.unmap();
+        staging.mapped.store(false, Ordering::Release);
+
+        let mut cmd = CommandBuffer::new(ArcRef::clone(&self.graphics))
+            .map_err(|_| StreamingTextureError::FailedToSubmit)?;
+
+        {
+            let mut encoder = cmd.command.as_ref().unwrap().borrow_mut();
+
+            for (plane_index, layout) in self.layouts.iter().enumerate() {
+                let target = self.planes.get(plane_index).unwrap_or(&self.texture);
+                let target = target.inner.borrow();
+
+                encoder.copy_buffer_to_texture(
+                    wgpu::TexelCopyBufferInfo {
+                        buffer: &staging.buffer,
+                        layout: wgpu::TexelCopyBufferLayout {
+                            offset: layout.offset,
+                            bytes_per_row: Some(layout.padded_row_bytes),
+                            rows_per_image: Some(layout.size.y as u32),
+                        },
+                    },
+                    wgpu::TexelCopyTextureInfo {
+                        texture: &target.wgpu_texture,
+                        mip_level: 0,
+                        origin: wgpu::Origin3d::ZERO,
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    wgpu::Extent3d {
+                        width: layout.size.x as u32,
+                        height: layout.size.y as u32,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            }
+        }
+
+        if let Some(pipeline) = &self.pipeline {
+            let mut pass = cmd
+                .begin_computepass()
+                .map_err(|_| StreamingTextureError::FailedToSubmit)?;
+
+            pass.set_pipeline(Some(pipeline));
+            pass.dispatch(
+                (self.size.x as u32).div_ceil(WORKGROUP_SIZE),
+                (self.size.y as u32).div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        }
+
+        cmd.end(false);
+
+        // The buffer can only be mapped again once the copy was submitted
+        let mapped = Arc::clone(&staging.mapped);
+        staging
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Write, move |result| {
+                if result.is_ok() {
+                    mapped.store(true, Ordering::Release);
+                }
+            });
+
+        self.frame_count += 1;
+
+        Ok(true)
+    }
+
+    fn validate_frame(&self, planes: &[&[u8]]) -> Result<(), StreamingTextureError> {
+        if planes.len() != self.format.plane_count() {
+            return Err(StreamingTextureError::InvalidFrameData(format!(
+                "{:?} expects {} planes, got {}",
+                self.format,
+                self.format.plane_count(),
+                planes.len()
+            )));
+        }
+
+        for (index, (layout, plane)) in self.layouts.iter().zip(planes).enumerate() {
+            let expected_len = layout.row_bytes as usize * layout.size.y as usize;
+
+            if plane.len() != expected_len {
+                return Err(StreamingTextureError::InvalidFrameData(format!(
+                    "plane {} expects {} bytes, got {}",
+                    index,
+                    expected_len,
+                    plane.len()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a mapped staging buffer, creating one while the ring isn't full.
+    fn acquire_staging_buffer(&mut self) -> Option<usize> {
+        let find_mapped = |staging: &[StagingBuffer]| {
+            staging
+                .iter()
+                .position(|buffer| buffer.mapped.load(Ordering::Acquire))
+        };
+
+        if let Some(index) = find_mapped(&self.staging) {
+            return Some(index);
+        }
+
+        // Map callbacks only run when the device is polled
+        _ = self.graphics.borrow().device().poll(wgpu::PollType::Poll);
+
+        if let Some(index) = find_mapped(&self.staging) {
+            return Some(index);
+        }
+
+        if self.staging.len() >= MAX_STAGING_BUFFERS {
+            return None;
+        }
+
+        let buffer = self
+            .graphics
+            .borrow()
+            .device()
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Streaming Texture Staging Buffer"),
+                size: self.staging_size,
+                usage: wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: true,
+            });
+
+        self.staging.push(StagingBuffer {
+            buffer,
+            mapped: Arc::new(AtomicBool::new(true)),
+        });
+
+        Some(self.staging.len() - 1)
+    }
+}
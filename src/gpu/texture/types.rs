@@ -1,4 +1,6 @@
-#[derive(Clone, Hash, Copy, PartialEq, Eq, PartialOrd, Ord)]
+use crate::gpu::GPU;
+
+#[derive(Clone, Debug, Default, Hash, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TextureUsage(u32);
 
 bitflags::bitflags! {
@@ -6,17 +8,35 @@ bitflags::bitflags! {
         const None = 0b00000000;
         const Sampler = 0b00000001;
         const Storage = 0b00000010;
+        /// Same as [TextureUsage::Storage], the texture can be bound as a storage texture.
+        const StorageBinding = 0b00000010;
         const RenderAttachment = 0b00000100;
     }
 }
 
+impl From<wgpu::TextureUsages> for TextureUsage {
+    fn from(usages: wgpu::TextureUsages) -> Self {
+        let mut usage = TextureUsage::None;
+        if usages.contains(wgpu::TextureUsages::TEXTURE_BINDING) {
+            usage |= TextureUsage::Sampler;
+        }
+        if usages.contains(wgpu::TextureUsages::STORAGE_BINDING) {
+            usage |= TextureUsage::StorageBinding;
+        }
+        if usages.contains(wgpu::TextureUsages::RENDER_ATTACHMENT) {
+            usage |= TextureUsage::RenderAttachment;
+        }
+        usage
+    }
+}
+
 impl Into<wgpu::TextureUsages> for TextureUsage {
     fn into(self) -> wgpu::TextureUsages {
         let mut usage = wgpu::TextureUsages::empty();
         if self.contains(TextureUsage::Sampler) {
             usage |= wgpu::TextureUsages::TEXTURE_BINDING;
         }
-        if self.contains(TextureUsage::Storage) {
+        if self.contains(TextureUsage::StorageBinding) {
             usage |= wgpu::TextureUsages::STORAGE_BINDING;
         }
         if self.contains(TextureUsage::RenderAttachment) {
@@ -339,7 +359,7 @@ impl Into<wgpu::FilterMode> for FilterMode {
     }
 }
 
-#[derive(Clone, Hash, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, Hash, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum CompareFunction {
     Never,
     Less,
@@ -366,6 +386,159 @@ impl Into<wgpu::CompareFunction> for CompareFunction {
     }
 }
 
+/// Operation performed on the stencil buffer value.
+#[derive(Clone, Debug, Hash, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StencilOperation {
+    Keep,
+    Zero,
+    Replace,
+    Invert,
+    IncrementClamp,
+    DecrementClamp,
+    IncrementWrap,
+    DecrementWrap,
+}
+
+impl From<StencilOperation> for wgpu::StencilOperation {
+    fn from(value: StencilOperation) -> Self {
+        match value {
+            StencilOperation::Keep => wgpu::StencilOperation::Keep,
+            StencilOperation::Zero => wgpu::StencilOperation::Zero,
+            StencilOperation::Replace => wgpu::StencilOperation::Replace,
+            StencilOperation::Invert => wgpu::StencilOperation::Invert,
+            StencilOperation::IncrementClamp => wgpu::StencilOperation::IncrementClamp,
+            StencilOperation::DecrementClamp => wgpu::StencilOperation::DecrementClamp,
+            StencilOperation::IncrementWrap => wgpu::StencilOperation::IncrementWrap,
+            StencilOperation::DecrementWrap => wgpu::StencilOperation::DecrementWrap,
+        }
+    }
+}
+
+/// Stencil test and operations for a single primitive face.
+#[derive(Clone, Debug, Hash, Copy, PartialEq, Eq)]
+pub struct StencilFaceState {
+    /// Comparison between the stencil reference and the stencil buffer value.
+    pub compare: CompareFunction,
+    /// Operation when the stencil test fails.
+    pub fail_op: StencilOperation,
+    /// Operation when the stencil test passes but the depth test fails.
+    pub depth_fail_op: StencilOperation,
+    /// Operation when both the stencil and depth tests pass.
+    pub pass_op: StencilOperation,
+}
+
+impl StencilFaceState {
+    pub fn new(
+        compare: CompareFunction,
+        fail_op: StencilOperation,
+        depth_fail_op: StencilOperation,
+        pass_op: StencilOperation,
+    ) -> Self {
+        Self {
+            compare,
+            fail_op,
+            depth_fail_op,
+            pass_op,
+        }
+    }
+
+    /// Always passes and leaves the stencil buffer untouched.
+    pub const IGNORE: Self = Self {
+        compare: CompareFunction::Always,
+        fail_op: StencilOperation::Keep,
+        depth_fail_op: StencilOperation::Keep,
+        pass_op: StencilOperation::Keep,
+    };
+}
+
+impl From<StencilFaceState> for wgpu::StencilFaceState {
+    fn from(value: StencilFaceState) -> Self {
+        wgpu::StencilFaceState {
+            compare: value.compare.into(),
+            fail_op: value.fail_op.into(),
+            depth_fail_op: value.depth_fail_op.into(),
+            pass_op: value.pass_op.into(),
+        }
+    }
+}
+
+/// Stencil state of a render pipeline.
+///
+/// The reference value used by the compare function and `StencilOperation::Replace`
+/// is set per draw with `RenderPass::set_stencil_reference`.
+#[derive(Clone, Debug, Hash, Copy, PartialEq, Eq)]
+pub struct StencilState {
+    pub front: StencilFaceState,
+    pub back: StencilFaceState,
+    pub read_mask: u32,
+    pub write_mask: u32,
+}
+
+impl StencilState {
+    /// Create a stencil state that uses the same face state for front and back faces.
+    pub fn new(face: StencilFaceState, read_mask: u32, write_mask: u32) -> Self {
+        Self {
+            front: face,
+            back: face,
+            read_mask,
+            write_mask,
+        }
+    }
+
+    /// Stencil testing disabled.
+    pub const NONE: Self = Self {
+        front: StencilFaceState::IGNORE,
+        back: StencilFaceState::IGNORE,
+        read_mask: 0,
+        write_mask: 0,
+    };
+
+    /// Writes the reference value wherever geometry is drawn, eg. to build a clip mask.
+    pub const WRITE_REFERENCE: Self = Self {
+        front: StencilFaceState {
+            compare: CompareFunction::Always,
+            fail_op: StencilOperation::Keep,
+            depth_fail_op: StencilOperation::Keep,
+            pass_op: StencilOperation::Replace,
+        },
+        back: StencilFaceState {
+            compare: CompareFunction::Always,
+            fail_op: StencilOperation::Keep,
+            depth_fail_op: StencilOperation::Keep,
+            pass_op: StencilOperation::Replace,
+        },
+        read_mask: 0xFF,
+        write_mask: 0xFF,
+    };
+
+    /// Only draws where the stencil buffer equals the reference value.
+    pub const EQUAL_REFERENCE: Self = Self {
+        front: StencilFaceState {
+            compare: CompareFunction::Equal,
+            fail_op: StencilOperation::Keep,
+            depth_fail_op: StencilOperation::Keep,
+            pass_op: StencilOperation::Keep,
+        },
+        back: StencilFaceState {
+            compare: CompareFunction::Equal,
+            fail_op: StencilOperation::Keep,
+            depth_fail_op: StencilOperation::Keep,
+            pass_op: StencilOperation::Keep,
+        },
+        read_mask: 0xFF,
+        write_mask: 0,
+    };
+
+    pub(crate) fn create_wgpu_stencil_state(&self) -> wgpu::StencilState {
+        wgpu::StencilState {
+            front: self.front.into(),
+            back: self.back.into(),
+            read_mask: self.read_mask,
+            write_mask: self.write_mask,
+        }
+    }
+}
+
 #[derive(Clone, Hash, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum SamplerBorderColor {
     TransparentBlack,
@@ -427,7 +600,97 @@ impl TextureSampler {
         }
     }
 
+    /// Sets the address mode of all three axes.
+    pub fn set_address_mode(mut self, mode: AddressMode) -> Self {
+        self.address_mode_u = mode;
+        self.address_mode_v = mode;
+        self.address_mode_w = mode;
+        self
+    }
+
+    /// Sets the magnification, minification and mipmap filters.
+    pub fn set_filter(mut self, filter: FilterMode) -> Self {
+        self.mag_filter = filter;
+        self.min_filter = filter;
+        self.mipmap_filter = filter;
+        self
+    }
+
+    /// Makes this a comparison sampler, bound to `sampler_comparison` shader bindings and used
+    /// with `textureSampleCompare`, eg. for shadow maps.
+    pub fn set_compare(mut self, compare: CompareFunction) -> Self {
+        self.compare = Some(compare);
+        self
+    }
+
+    /// Sets the maximum anisotropy, between 1 and 16.
+    ///
+    /// Values above 1 require linear magnification, minification and mipmap filters.
+    pub fn set_anisotropy(mut self, anisotropy: u16) -> Self {
+        self.anisotropy_clamp = Some(anisotropy);
+        self
+    }
+
+    /// Clamps the mip levels sampled, `min` must be positive and not above `max`.
+    pub fn set_lod_range(mut self, min: f32, max: f32) -> Self {
+        self.lod_min_clamp = min;
+        self.lod_max_clamp = max;
+        self
+    }
+
+    /// Sets the color returned outside of the texture, and sets the address mode of all three
+    /// axes to [AddressMode::ClampToBorder].
+    ///
+    /// Border clamping requires an adapter supporting it, see
+    /// [GPU::supports_clamp_to_border](crate::gpu::GPU::supports_clamp_to_border).
+    pub fn set_border_color(mut self, color: SamplerBorderColor) -> Self {
+        self.border_color = Some(color);
+        self.set_address_mode(AddressMode::ClampToBorder)
+    }
+
+    /// Checks the sampler against the features of the device.
+    pub fn validate(&self, gpu: &GPU) -> Result<(), SamplerError> {
+        self.validate_features(gpu.inner.borrow().device().features())
+    }
+
+    pub(crate) fn validate_features(&self, features: wgpu::Features) -> Result<(), SamplerError> {
+        if let Some(anisotropy) = self.anisotropy_clamp {
+            if !(1..=16).contains(&anisotropy) {
+                return Err(SamplerError::InvalidAnisotropy(anisotropy));
+            }
+
+            if anisotropy > 1
+                && (self.mag_filter != FilterMode::Linear
+                    || self.min_filter != FilterMode::Linear
+                    || self.mipmap_filter != FilterMode::Linear)
+            {
+                return Err(SamplerError::AnisotropyRequiresLinearFilter);
+            }
+        }
+
+        if !(self.lod_min_clamp >= 0.0 && self.lod_max_clamp >= self.lod_min_clamp) {
+            return Err(SamplerError::InvalidLodRange(
+                self.lod_min_clamp,
+                self.lod_max_clamp,
+            ));
+        }
+
+        let clamp_to_border = [self.address_mode_u, self.address_mode_v, self.address_mode_w]
+            .contains(&AddressMode::ClampToBorder);
+
+        if clamp_to_border && !features.contains(wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER) {
+            return Err(SamplerError::ClampToBorderUnsupported);
+        }
+
+        Ok(())
+    }
+
     pub fn make_wgpu(&self, device: &wgpu::Device) -> wgpu::Sampler {
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        if let Err(err) = self.validate_features(device.features()) {
+            panic!("Invalid texture sampler: {}", err);
+        }
+
         let desc = wgpu::SamplerDescriptor {
             label: Some("texture sampler"),
             address_mode_u: self.address_mode_u.into(),
@@ -459,6 +722,50 @@ impl TextureSampler {
         anisotropy_clamp: None,
         border_color: None,
     };
+
+    /// Same as [TextureSampler::DEFAULT] with nearest filtering, for pixel-art.
+    pub const NEAREST: Self = Self {
+        mag_filter: FilterMode::Nearest,
+        min_filter: FilterMode::Nearest,
+        ..Self::DEFAULT
+    };
+}
+
+/// Invalid [TextureSampler] settings, see [TextureSampler::validate].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SamplerError {
+    /// Anisotropy must be between 1 and 16.
+    InvalidAnisotropy(u16),
+    AnisotropyRequiresLinearFilter,
+    /// The minimum lod is negative or above the maximum lod.
+    InvalidLodRange(f32, f32),
+    /// [AddressMode::ClampToBorder] is not supported by the device.
+    ClampToBorderUnsupported,
+    /// A comparison sampler is bound to a non comparison sampler binding, or the opposite.
+    CompareMismatch,
+}
+
+impl std::fmt::Display for SamplerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SamplerError::InvalidAnisotropy(anisotropy) => {
+                write!(f, "Invalid anisotropy {}, must be between 1 and 16", anisotropy)
+            }
+            SamplerError::AnisotropyRequiresLinearFilter => {
+                write!(f, "Anisotropic filtering requires linear filters")
+            }
+            SamplerError::InvalidLodRange(min, max) => {
+                write!(f, "Invalid lod range {}..{}", min, max)
+            }
+            SamplerError::ClampToBorderUnsupported => {
+                write!(f, "Clamp to border address mode is not supported by the device")
+            }
+            SamplerError::CompareMismatch => write!(
+                f,
+                "Comparison samplers must be bound to sampler_comparison bindings"
+            ),
+        }
+    }
 }
 
 impl Eq for TextureSampler {}
@@ -630,6 +937,28 @@ impl TextureFormat {
             TextureFormat::Depth32FloatStencil8 => 5,
         }
     }
+
+    /// Returns true if the format has a depth aspect.
+    pub fn is_depth(&self) -> bool {
+        matches!(
+            self,
+            TextureFormat::Depth16Unorm
+                | TextureFormat::Depth24Plus
+                | TextureFormat::Depth24PlusStencil8
+                | TextureFormat::Depth32Float
+                | TextureFormat::Depth32FloatStencil8
+        )
+    }
+
+    /// Returns true if the format has a stencil aspect.
+    pub fn has_stencil(&self) -> bool {
+        matches!(
+            self,
+            TextureFormat::Stencil8
+                | TextureFormat::Depth24PlusStencil8
+                | TextureFormat::Depth32FloatStencil8
+        )
+    }
 }
 
 impl Into<wgpu::TextureFormat> for TextureFormat {
@@ -682,53 +1011,60 @@ impl Into<wgpu::TextureFormat> for TextureFormat {
     }
 }
 
-impl From<wgpu::TextureFormat> for TextureFormat {
-    fn from(format: wgpu::TextureFormat) -> Self {
+impl TextureFormat {
+    /// Converts a wgpu format, `None` if the format has no equivalent in this crate.
+    pub(crate) fn from_wgpu(format: wgpu::TextureFormat) -> Option<Self> {
         match format {
-            wgpu::TextureFormat::R8Unorm => TextureFormat::R8Unorm,
-            wgpu::TextureFormat::R8Snorm => TextureFormat::R8Snorm,
-            wgpu::TextureFormat::R8Uint => TextureFormat::R8Uint,
-            wgpu::TextureFormat::R8Sint => TextureFormat::R8Sint,
-            wgpu::TextureFormat::R16Uint => TextureFormat::R16Uint,
-            wgpu::TextureFormat::R16Sint => TextureFormat::R16Sint,
-            wgpu::TextureFormat::R16Float => TextureFormat::R16Float,
-            wgpu::TextureFormat::Rg8Unorm => TextureFormat::Rg8Unorm,
-            wgpu::TextureFormat::Rg8Snorm => TextureFormat::Rg8Snorm,
-            wgpu::TextureFormat::Rg8Uint => TextureFormat::Rg8Uint,
-            wgpu::TextureFormat::Rg8Sint => TextureFormat::Rg8Sint,
-            wgpu::TextureFormat::R32Uint => TextureFormat::R32Uint,
-            wgpu::TextureFormat::R32Sint => TextureFormat::R32Sint,
-            wgpu::TextureFormat::R32Float => TextureFormat::R32Float,
-            wgpu::TextureFormat::Rg16Uint => TextureFormat::Rg16Uint,
-            wgpu::TextureFormat::Rg16Sint => TextureFormat::Rg16Sint,
-            wgpu::TextureFormat::Rg16Float => TextureFormat::Rg16Float,
-            wgpu::TextureFormat::Rgba8Unorm => TextureFormat::Rgba8Unorm,
-            wgpu::TextureFormat::Rgba8UnormSrgb => TextureFormat::Rgba8UnormSrgb,
-            wgpu::TextureFormat::Rgba8Snorm => TextureFormat::Rgba8Snorm,
-            wgpu::TextureFormat::Rgba8Uint => TextureFormat::Rgba8Uint,
-            wgpu::TextureFormat::Rgba8Sint => TextureFormat::Rgba8Sint,
-            wgpu::TextureFormat::Bgra8Unorm => TextureFormat::Bgra8Unorm,
-            wgpu::TextureFormat::Bgra8UnormSrgb => TextureFormat::Bgra8UnormSrgb,
-            wgpu::TextureFormat::Rgb9e5Ufloat => TextureFormat::Rgb9e5Ufloat,
-            wgpu::TextureFormat::Rgb10a2Uint => TextureFormat::Rgb10a2Uint,
-            wgpu::TextureFormat::Rgb10a2Unorm => TextureFormat::Rgb10a2Unorm,
-            wgpu::TextureFormat::Rg11b10Ufloat => TextureFormat::Rg11b10Ufloat,
-            wgpu::TextureFormat::Rg32Uint => TextureFormat::Rg32Uint,
-            wgpu::TextureFormat::Rg32Sint => TextureFormat::Rg32Sint,
-            wgpu::TextureFormat::Rg32Float => TextureFormat::Rg32Float,
-            wgpu::TextureFormat::Rgba16Uint => TextureFormat::Rgba16Uint,
-            wgpu::TextureFormat::Rgba16Sint => TextureFormat::Rgba16Sint,
-            wgpu::TextureFormat::Rgba16Float => TextureFormat::Rgba16Float,
-            wgpu::TextureFormat::Rgba32Uint => TextureFormat::Rgba32Uint,
-            wgpu::TextureFormat::Rgba32Sint => TextureFormat::Rgba32Sint,
-            wgpu::TextureFormat::Rgba32Float => TextureFormat::Rgba32Float,
-            wgpu::TextureFormat::Stencil8 => TextureFormat::Stencil8,
-            wgpu::TextureFormat::Depth16Unorm => TextureFormat::Depth16Unorm,
-            wgpu::TextureFormat::Depth24Plus => TextureFormat::Depth24Plus,
-            wgpu::TextureFormat::Depth24PlusStencil8 => TextureFormat::Depth24PlusStencil8,
-            wgpu::TextureFormat::Depth32Float => TextureFormat::Depth32Float,
-            wgpu::TextureFormat::Depth32FloatStencil8 => TextureFormat::Depth32FloatStencil8,
-            _ => panic!("Unsupported texture format"),
+            wgpu::TextureFormat::R8Unorm => Some(TextureFormat::R8Unorm),
+            wgpu::TextureFormat::R8Snorm => Some(TextureFormat::R8Snorm),
+            wgpu::TextureFormat::R8Uint => Some(TextureFormat::R8Uint),
+            wgpu::TextureFormat::R8Sint => Some(TextureFormat::R8Sint),
+            wgpu::TextureFormat::R16Uint => Some(TextureFormat::R16Uint),
+            wgpu::TextureFormat::R16Sint => Some(TextureFormat::R16Sint),
+            wgpu::TextureFormat::R16Float => Some(TextureFormat::R16Float),
+            wgpu::TextureFormat::Rg8Unorm => Some(TextureFormat::Rg8Unorm),
+            wgpu::TextureFormat::Rg8Snorm => Some(TextureFormat::Rg8Snorm),
+            wgpu::TextureFormat::Rg8Uint => Some(TextureFormat::Rg8Uint),
+            wgpu::TextureFormat::Rg8Sint => Some(TextureFormat::Rg8Sint),
+            wgpu::TextureFormat::R32Uint => Some(TextureFormat::R32Uint),
+            wgpu::TextureFormat::R32Sint => Some(TextureFormat::R32Sint),
+            wgpu::TextureFormat::R32Float => Some(TextureFormat::R32Float),
+            wgpu::TextureFormat::Rg16Uint => Some(TextureFormat::Rg16Uint),
+            wgpu::TextureFormat::Rg16Sint => Some(TextureFormat::Rg16Sint),
+            wgpu::TextureFormat::Rg16Float => Some(TextureFormat::Rg16Float),
+            wgpu::TextureFormat::Rgba8Unorm => Some(TextureFormat::Rgba8Unorm),
+            wgpu::TextureFormat::Rgba8UnormSrgb => Some(TextureFormat::Rgba8UnormSrgb),
+            wgpu::TextureFormat::Rgba8Snorm => Some(TextureFormat::Rgba8Snorm),
+            wgpu::TextureFormat::Rgba8Uint => Some(TextureFormat::Rgba8Uint),
+            wgpu::TextureFormat::Rgba8Sint => Some(TextureFormat::Rgba8Sint),
+            wgpu::TextureFormat::Bgra8Unorm => Some(TextureFormat::Bgra8Unorm),
+            wgpu::TextureFormat::Bgra8UnormSrgb => Some(TextureFormat::Bgra8UnormSrgb),
+            wgpu::TextureFormat::Rgb9e5Ufloat => Some(TextureFormat::Rgb9e5Ufloat),
+            wgpu::TextureFormat::Rgb10a2Uint => Some(TextureFormat::Rgb10a2Uint),
+            wgpu::TextureFormat::Rgb10a2Unorm => Some(TextureFormat::Rgb10a2Unorm),
+            wgpu::TextureFormat::Rg11b10Ufloat => Some(TextureFormat::Rg11b10Ufloat),
+            wgpu::TextureFormat::Rg32Uint => Some(TextureFormat::Rg32Uint),
+            wgpu::TextureFormat::Rg32Sint => Some(TextureFormat::Rg32Sint),
+            wgpu::TextureFormat::Rg32Float => Some(TextureFormat::Rg32Float),
+            wgpu::TextureFormat::Rgba16Uint => Some(TextureFormat::Rgba16Uint),
+            wgpu::TextureFormat::Rgba16Sint => Some(TextureFormat::Rgba16Sint),
+            wgpu::TextureFormat::Rgba16Float => Some(TextureFormat::Rgba16Float),
+            wgpu::TextureFormat::Rgba32Uint => Some(TextureFormat::Rgba32Uint),
+            wgpu::TextureFormat::Rgba32Sint => Some(TextureFormat::Rgba32Sint),
+            wgpu::TextureFormat::Rgba32Float => Some(TextureFormat::Rgba32Float),
+            wgpu::TextureFormat::Stencil8 => Some(TextureFormat::Stencil8),
+            wgpu::TextureFormat::Depth16Unorm => Some(TextureFormat::Depth16Unorm),
+            wgpu::TextureFormat::Depth24Plus => Some(TextureFormat::Depth24Plus),
+            wgpu::TextureFormat::Depth24PlusStencil8 => Some(TextureFormat::Depth24PlusStencil8),
+            wgpu::TextureFormat::Depth32Float => Some(TextureFormat::Depth32Float),
+            wgpu::TextureFormat::Depth32FloatStencil8 => Some(TextureFormat::Depth32FloatStencil8),
+            _ => None,
         }
     }
 }
+
+impl From<wgpu::TextureFormat> for TextureFormat {
+    fn from(format: wgpu::TextureFormat) -> Self {
+        TextureFormat::from_wgpu(format).expect("Unsupported texture format")
+    }
+}
@@ -0,0 +1,236 @@
+use crate::math::Point2;
+
+use super::{Texture, TextureError, TextureFormat};
+
+/// Part of a depth stencil texture seen by a [TextureView].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextureAspect {
+    #[default]
+    All,
+    DepthOnly,
+    StencilOnly,
+}
+
+impl From<TextureAspect> for wgpu::TextureAspect {
+    fn from(aspect: TextureAspect) -> Self {
+        match aspect {
+            TextureAspect::All => wgpu::TextureAspect::All,
+            TextureAspect::DepthOnly => wgpu::TextureAspect::DepthOnly,
+            TextureAspect::StencilOnly => wgpu::TextureAspect::StencilOnly,
+        }
+    }
+}
+
+/// Describes the mip levels, array layers and format seen by a [TextureView], see
+/// [Texture::create_view].
+///
+/// ```rust,ignore
+/// // Bind the second mip level as the destination of a downsampling pass
+/// let view = texture.create_view(TextureViewDesc::mip(1))?;
+/// pass.set_attachment_texture_storage_view(0, 1, Some(&view))?;
+///
+/// // Sample an sRGB texture without the hardware decoding
+/// let linear = texture.create_view(TextureViewDesc {
+///     format: Some(TextureFormat::Rgba8Unorm),
+///     ..Default::default()
+/// })?;
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TextureViewDesc {
+    pub base_mip_level: u32,
+    /// Number of mip levels starting at `base_mip_level`, `None` for all the remaining ones.
+    pub mip_level_count: Option<u32>,
+    pub base_array_layer: u32,
+    /// Number of layers starting at `base_array_layer`, `None` for all the remaining ones.
+    pub array_layer_count: Option<u32>,
+    pub aspect: TextureAspect,
+    /// Reinterprets the texels in another format, which must be the texture format or one of
+    /// the formats given to
+    /// [TextureBuilder::set_view_formats](super::TextureBuilder::set_view_formats).
+    pub format: Option<TextureFormat>,
+}
+
+impl TextureViewDesc {
+    /// A view of a single mip level of the first layer.
+    pub fn mip(level: u32) -> Self {
+        Self {
+            base_mip_level: level,
+            mip_level_count: Some(1),
+            array_layer_count: Some(1),
+            ..Default::default()
+        }
+    }
+
+    /// A view of all the mip levels of a single array layer.
+    pub fn layer(layer: u32) -> Self {
+        Self {
+            base_array_layer: layer,
+            array_layer_count: Some(1),
+            ..Default::default()
+        }
+    }
+}
+
+/// A subset of a texture, bound in place of the whole texture with the `*_view` attachment
+/// methods of render and compute passes.
+///
+/// The view keeps the texture alive. Views are not re-created after the device was recovered,
+/// see [GPU::recover_device](crate::gpu::GPU::recover_device), create them again instead.
+#[derive(Clone, Debug)]
+pub struct TextureView {
+    pub(crate) texture: Texture,
+    pub(crate) view: wgpu::TextureView,
+    desc: TextureViewDesc,
+    format: TextureFormat,
+    size: Point2,
+    mip_level_count: u32,
+    array_layer_count: u32,
+}
+
+impl TextureView {
+    /// The texture this view was created from.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn desc(&self) -> TextureViewDesc {
+        self.desc
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Size of the base mip level of the view.
+    pub fn size(&self) -> Point2 {
+        self.size
+    }
+
+    pub fn mip_level_count(&self) -> u32 {
+        self.mip_level_count
+    }
+
+    pub fn array_layer_count(&self) -> u32 {
+        self.array_layer_count
+    }
+
+    /// Returns true if the view can be bound as a `texture_2d` or `texture_storage_2d`, or used
+    /// as a render target, which all see a single layer.
+    pub(crate) fn is_single_layer(&self) -> bool {
+        self.array_layer_count == 1
+    }
+}
+
+impl Texture {
+    /// Number of mip levels of the texture.
+    pub fn mip_level_count(&self) -> u32 {
+        self.inner.borrow().wgpu_texture.mip_level_count()
+    }
+
+    /// Number of array layers of the texture, see
+    /// [TextureBuilder::set_array_layers](super::TextureBuilder::set_array_layers).
+    pub fn array_layer_count(&self) -> u32 {
+        self.inner.borrow().wgpu_texture.depth_or_array_layers()
+    }
+
+    /// Creates a view of a range of mip levels and array layers of the texture, optionally
+    /// reinterpreting its format.
+    ///
+    /// Fails with [TextureError::InvalidTextureView] if the ranges are outside of the texture,
+    /// the aspect doesn't exist in the texture format, or the format wasn't allowed when the
+    /// texture was built.
+    pub fn create_view(&self, desc: TextureViewDesc) -> Result<TextureView, TextureError> {
+        let inner = self.inner.borrow();
+        let texture = &inner.wgpu_texture;
+
+        let texture_mips = texture.mip_level_count();
+        let texture_layers = texture.depth_or_array_layers();
+
+        let mip_level_count = desc
+            .mip_level_count
+            .unwrap_or(texture_mips.saturating_sub(desc.base_mip_level));
+        let array_layer_count = desc
+            .array_layer_count
+            .unwrap_or(texture_layers.saturating_sub(desc.base_array_layer));
+
+        if mip_level_count == 0 || desc.base_mip_level + mip_level_count > texture_mips {
+            crate::dbg_log!(
+                "Texture view mip levels {}..{} outside of the {} levels of the texture",
+                desc.base_mip_level,
+                desc.base_mip_level + mip_level_count,
+                texture_mips
+            );
+            return Err(TextureError::InvalidTextureView);
+        }
+
+        if array_layer_count == 0 || desc.base_array_layer + array_layer_count > texture_layers {
+            crate::dbg_log!(
+                "Texture view layers {}..{} outside of the {} layers of the texture",
+                desc.base_array_layer,
+                desc.base_array_layer + array_layer_count,
+                texture_layers
+            );
+            return Err(TextureError::InvalidTextureView);
+        }
+
+        let format = desc.format.unwrap_or(inner.format);
+        if format != inner.format && !inner.view_formats.contains(&format) {
+            crate::dbg_log!(
+                "Texture view format {:?} is not allowed for a {:?} texture, add it with set_view_formats",
+                format,
+                inner.format
+            );
+            return Err(TextureError::InvalidTextureView);
+        }
+
+        let aspect_valid = match desc.aspect {
+            TextureAspect::All => true,
+            TextureAspect::DepthOnly => inner.format.is_depth(),
+            TextureAspect::StencilOnly => inner.format.has_stencil(),
+        };
+
+        if !aspect_valid {
+            crate::dbg_log!(
+                "Texture view aspect {:?} doesn't exist in {:?}",
+                desc.aspect,
+                inner.format
+            );
+            return Err(TextureError::InvalidTextureView);
+        }
+
+        let dimension = if array_layer_count == 1 {
+            wgpu::TextureViewDimension::D2
+        } else {
+            wgpu::TextureViewDimension::D2Array
+        };
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Texture Subresource View"),
+            format: Some(format.into()),
+            dimension: Some(dimension),
+            usage: None,
+            aspect: desc.aspect.into(),
+            base_mip_level: desc.base_mip_level,
+            mip_level_count: Some(mip_level_count),
+            base_array_layer: desc.base_array_layer,
+            array_layer_count: Some(array_layer_count),
+        });
+
+        let size = Point2::new(
+            (inner.size.x >> desc.base_mip_level).max(1),
+            (inner.size.y >> desc.base_mip_level).max(1),
+        );
+
+        drop(inner);
+
+        Ok(TextureView {
+            texture: self.clone(),
+            view,
+            desc,
+            format,
+            size,
+            mip_level_count,
+            array_layer_count,
+        })
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use crate::{math::Point2, utils::ArcRef};
+
+use super::{
+    super::{
+        GPUInner,
+        command::{BindGroupAttachment, CommandBuffer, utils::BindGroupType},
+        shader::graphics::{GraphicsShader, GraphicsShaderBuilder},
+    },
+    Texture,
+    TextureBuilder,
+    TextureError,
+    TextureFormat,
+    TextureSampler,
+    TextureUsage,
+};
+
+/// Memory layout of the planes passed to [Texture::write_yuv].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum YuvFormat {
+    /// A full resolution Y plane followed by a half resolution plane of interleaved U and V samples.
+    Nv12,
+    /// A full resolution Y plane followed by separate half resolution U and V planes.
+    I420,
+}
+
+/// Color matrix used to convert the YUV samples to RGB.
+///
+/// Both expect limited (studio) range samples, as produced by most video decoders.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum YuvColorSpace {
+    /// Standard definition video.
+    Bt601,
+    /// High definition video.
+    Bt709,
+}
+
+impl YuvFormat {
+    pub(crate) fn plane_count(&self) -> usize {
+        match self {
+            YuvFormat::Nv12 => 2,
+            YuvFormat::I420 => 3,
+        }
+    }
+
+    pub(crate) fn plane_layout(&self, index: usize, size: Point2) -> (Point2, TextureFormat) {
+        let chroma_size = Point2::new((size.x + 1) / 2, (size.y + 1) / 2);
+
+        match (self, index) {
+            (_, 0) => (size, TextureFormat::R8Unorm),
+            (YuvFormat::Nv12, _) => (chroma_size, TextureFormat::Rg8Unorm),
+            (YuvFormat::I420, _) => (chroma_size, TextureFormat::R8Unorm),
+        }
+    }
+}
+
+impl YuvColorSpace {
+    /// Returns the (Cr→R, Cb→G, Cr→G, Cb→B) coefficients for limited range samples.
+    fn coefficients(&self) -> [f32; 4] {
+        match self {
+            YuvColorSpace::Bt601 => [1.596_027, -0.391_762, -0.812_968, 2.017_232],
+            YuvColorSpace::Bt709 => [1.792_741, -0.213_249, -0.532_909, 2.112_402],
+        }
+    }
+}
+
+/// Uniform parameters for the conversion, must match `YuvParams` in yuv_shader.wgsl and
+/// yuv_compute.wgsl.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct YuvParams {
+    cr_r: f32,
+    cb_g: f32,
+    cr_g: f32,
+    cb_b: f32,
+    luma_scale: f32,
+    luma_offset: f32,
+    format: u32,
+    _pad: u32,
+}
+
+impl YuvParams {
+    pub fn new(format: YuvFormat, color_space: YuvColorSpace) -> Self {
+        let [cr_r, cb_g, cr_g, cb_b] = color_space.coefficients();
+
+        Self {
+            cr_r,
+            cb_g,
+            cr_g,
+            cb_b,
+            // Limited range luma, [16, 235] mapped to [0, 1]
+            luma_scale: 255.0 / 219.0,
+            luma_offset: 16.0 / 255.0,
+            format: match format {
+                YuvFormat::Nv12 => 0,
+                YuvFormat::I420 => 1,
+            },
+            _pad: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct YuvGlobalState {
+    pub shader: GraphicsShader,
+    /// Plane textures reused between frames of the same format and size.
+    pub planes: HashMap<(YuvFormat, i32, i32), Vec<Texture>>,
+}
+
+impl YuvGlobalState {
+    pub fn new(gpu_inner: &ArcRef<GPUInner>) -> Option<Self> {
+        let shader = GraphicsShaderBuilder::new(ArcRef::clone(gpu_inner))
+            .set_source(include_str!("./resources/yuv_shader.wgsl"))
+            .set_label("YUV Conversion Shader")
+            .build()
+            .ok()?;
+
+        Some(Self {
+            shader,
+            planes: HashMap::new(),
+        })
+    }
+}
+
+impl Texture {
+    /// Uploads a YUV video frame and converts it to RGB into this texture on the GPU.
+    ///
+    /// `planes` must contain the tightly packed planes of the frame, 2 for [YuvFormat::Nv12]
+    /// and 3 for [YuvFormat::I420], with the chroma planes at half the texture resolution
+    /// (rounded up).
+    ///
+    /// The texture must be a render target, with a non sRGB format (eg. Rgba8Unorm) since the
+    /// video samples are already gamma encoded.
+    pub fn write_yuv(
+        &mut self,
+        planes: &[&[u8]],
+        format: YuvFormat,
+        color_space: YuvColorSpace,
+    ) -> Result<(), TextureError> {
+        let size = {
+            let inner = self.inner.borrow();
+
+            if !inner.usages.contains(TextureUsage::RenderAttachment) {
+                crate::dbg_log!("YUV conversion requires a render target texture");
+                return Err(TextureError::InvalidTextureFormat);
+            }
+
+            #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+            {
+                let wgpu_format: wgpu::TextureFormat = inner.format.into();
+                if wgpu_format.is_srgb() {
+                    crate::warn_log!(
+                        "YUV conversion into sRGB texture {:?}, the result will be gamma encoded twice",
+                        inner.format
+                    );
+                }
+            }
+
+            inner.size
+        };
+
+        if planes.len() != format.plane_count() {
+            crate::dbg_log!(
+                "{:?} expects {} planes, got {}",
+                format,
+                format.plane_count(),
+                planes.len()
+            );
+            return Err(TextureError::InvalidTextureData);
+        }
+
+        for (index, plane) in planes.iter().enumerate() {
+            let (plane_size, plane_format) = format.plane_layout(index, size);
+            let expected_len =
+                plane_format.get_size() as usize * plane_size.x as usize * plane_size.y as usize;
+
+            if plane.len() != expected_len {
+                crate::dbg_log!(
+                    "YUV plane {} size mismatch: expected {} bytes, got {}",
+                    index,
+                    expected_len,
+                    plane.len()
+                );
+                return Err(TextureError::InvalidTextureData);
+            }
+        }
+
+        if self.graphics.borrow().yuv_state.is_none() {
+            let state =
+                YuvGlobalState::new(&self.graphics).ok_or(TextureError::FailedToWrite)?;
+
+            self.graphics.borrow_mut().yuv_state = Some(ArcRef::new(state));
+        }
+
+        let state = ArcRef::clone(self.graphics.borrow().yuv_state.as_ref().unwrap());
+
+        let plane_textures = self.upload_yuv_planes(&state, planes, format, size)?;
+        let shader = state.borrow().shader.clone();
+
+        let params = YuvParams::new(format, color_space);
+
+        let mut cmd = CommandBuffer::new(ArcRef::clone(&self.graphics))
+            .map_err(|_| TextureError::FailedToWrite)?;
+
+        {
+            let mut pass = cmd
+                .begin_texture(self)
+                .map_err(|_| TextureError::FailedToWrite)?;
+
+            // NV12 has no third plane, the shader reads V from the interleaved plane instead
+            let v_plane = plane_textures.get(2).unwrap_or(&plane_textures[1]);
+
+            pass.set_shader(Some(&shader));
+            pass.set_attachment_texture(0, 0, Some(&plane_textures[0]));
+            pass.set_attachment_texture(0, 1, Some(&plane_textures[1]));
+            pass.set_attachment_texture(0, 2, Some(v_plane));
+            pass.set_attachment_sampler(0, 3, Some(&TextureSampler::DEFAULT));
+
+            let buffer = pass.graphics.borrow_mut().create_staging_buffer(
+                bytemuck::bytes_of(&params),
+                wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            );
+
+            pass.insert_or_replace_attachment(
+                0,
+                4,
+                BindGroupAttachment {
+                    group: 0,
+                    binding: 4,
+                    attachment: BindGroupType::Uniform(buffer.into()),
+                },
+            );
+
+            pass.draw(0..3, 1);
+        }
+
+        cmd.end(false);
+
+        Ok(())
+    }
+
+    fn upload_yuv_planes(
+        &self,
+        state: &ArcRef<YuvGlobalState>,
+        planes: &[&[u8]],
+        format: YuvFormat,
+        size: Point2,
+    ) -> Result<Vec<Texture>, TextureError> {
+        let key = (format, size.x, size.y);
+
+        let cached = state.borrow().planes.get(&key).cloned();
+        if let Some(mut textures) = cached {
+            for (texture, plane) in textures.iter_mut().zip(planes) {
+                texture.write::<u8>(plane)?;
+            }
+
+            return Ok(textures);
+        }
+
+        let textures = planes
+            .iter()
+            .enumerate()
+            .map(|(index, plane)| {
+                let (plane_size, plane_format) = format.plane_layout(index, size);
+
+                TextureBuilder::new(ArcRef::clone(&self.graphics))
+                    .set_raw_image(plane, plane_size, plane_format)
+                    .set_usage(TextureUsage::Sampler)
+                    .set_label("YUV Plane")
+                    .build()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        crate::dbg_log!(
+            "Created {:?} plane textures for {}x{} frames",
+            format,
+            size.x,
+            size.y
+        );
+
+        state.borrow_mut().planes.insert(key, textures.clone());
+
+        Ok(textures)
+    }
+}
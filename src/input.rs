@@ -2,12 +2,42 @@ use std::collections::HashMap;
 
 use smol_str::SmolStr;
 
-use crate::{math::Point2, runner::{Event, Runner}, utils::ArcRef};
+use crate::{math::{Point2, Vector2}, runner::{Event, Runner}, utils::ArcRef};
+
+mod keyboard;
+pub use keyboard::{Key, KeyCode, Modifiers, NamedKey};
 
 pub type KeyboardEvent = Box<dyn Fn(&str, bool) + Send + Sync>;
 pub type MouseEvent = Box<dyn Fn(&str, Point2, bool) + Send + Sync>;
 pub type MouseMoveEvent = Box<dyn Fn(Point2) + Send + Sync>;
 
+/// A key, mouse button or gamepad button bound to an action with [Input::bind_action].
+///
+/// The names are the same as the ones of [Input::key_pressed], [Input::mouse_pressed] and
+/// [Input::gamepad_pressed].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InputBinding {
+    Key(SmolStr),
+    /// A physical key, which stays at the same place on any keyboard layout.
+    Code(KeyCode),
+    Mouse(SmolStr),
+    Gamepad(SmolStr),
+}
+
+impl InputBinding {
+    pub fn key(key: &str) -> Self {
+        Self::Key(SmolStr::new(key))
+    }
+
+    pub fn mouse(button: &str) -> Self {
+        Self::Mouse(SmolStr::new(button))
+    }
+
+    pub fn gamepad(button: &str) -> Self {
+        Self::Gamepad(SmolStr::new(button))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Input {
     pub(crate) inner: ArcRef<InputInner>,
@@ -31,6 +61,17 @@ impl Input {
         self.inner.borrow().mouse_position
     }
 
+    /// Returns how much the mouse cursor moved in pixels during the last [Runner::pump_events].
+    pub fn mouse_delta(&self) -> Vector2 {
+        self.inner.borrow().mouse_delta
+    }
+
+    /// Returns the raw mouse motion during the last [Runner::pump_events], which keeps being
+    /// reported while the cursor is locked, see [Event::MouseMotionRaw].
+    pub fn mouse_motion_raw(&self) -> Vector2 {
+        self.inner.borrow().mouse_motion_raw
+    }
+
     /// Returns true if the mouse button is currently pressed down.
     /// 
     /// Expected button names are:
@@ -104,6 +145,126 @@ impl Input {
         false
     }
 
+    /// Returns true if the button is currently pressed down on any gamepad.
+    ///
+    /// See [Event::GamepadButton] for the button names, requires the `gamepad` feature.
+    pub fn gamepad_pressed(&self, button: &str) -> bool {
+        self.inner
+            .borrow()
+            .gamepad_buttons
+            .iter()
+            .any(|((_, name), pressed)| *pressed && name == button)
+    }
+
+    /// Returns true if the button was pressed once on any gamepad since the last call to this
+    /// method.
+    pub fn gamepad_pressed_once(&self, button: &str) -> bool {
+        let mut inner = self.inner.borrow_mut();
+        if inner.gamepad_buttons_once.get(button).copied().unwrap_or(false) {
+            inner.gamepad_buttons_once.insert(SmolStr::from(button), false);
+            return true;
+        }
+
+        false
+    }
+
+    /// Returns the value of the axis of the gamepad moved the furthest, or 0.0 without gamepads.
+    ///
+    /// See [Event::GamepadAxis] for the axis names and ranges.
+    pub fn gamepad_axis(&self, axis: &str) -> f32 {
+        self.inner
+            .borrow()
+            .gamepad_axes
+            .iter()
+            .filter(|((_, name), _)| name == axis)
+            .map(|(_, value)| *value)
+            .fold(0.0, |a: f32, b: f32| if b.abs() > a.abs() { b } else { a })
+    }
+
+    /// Binds a key or button to an action, so the game can check "jump" instead of every key
+    /// that can jump.
+    ///
+    /// An action can have any number of bindings, and is pressed while any of them is.
+    ///
+    /// ```rust,ignore
+    /// input.bind_action("jump", InputBinding::key("Space"));
+    /// input.bind_action("jump", InputBinding::gamepad("South"));
+    ///
+    /// if input.action_pressed_once("jump") {
+    ///     player.jump();
+    /// }
+    /// ```
+    pub fn bind_action(&mut self, action: &str, binding: InputBinding) {
+        let mut inner = self.inner.borrow_mut();
+        let bindings = inner.actions.entry(SmolStr::new(action)).or_default();
+
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes the bindings of an action.
+    pub fn unbind_action(&mut self, action: &str) {
+        let mut inner = self.inner.borrow_mut();
+        inner.actions.remove(action);
+        inner.actions_once.remove(action);
+    }
+
+    /// Returns the bindings of an action.
+    pub fn action_bindings(&self, action: &str) -> Vec<InputBinding> {
+        self.inner
+            .borrow()
+            .actions
+            .get(action)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns true if any of the bindings of the action is currently pressed down.
+    pub fn action_pressed(&self, action: &str) -> bool {
+        let inner = self.inner.borrow();
+
+        inner.actions.get(action).is_some_and(|bindings| {
+            bindings.iter().any(|binding| inner.binding_pressed(binding))
+        })
+    }
+
+    /// Returns true if any of the bindings of the action was pressed once since the last call
+    /// to this method.
+    pub fn action_pressed_once(&self, action: &str) -> bool {
+        let mut inner = self.inner.borrow_mut();
+        if inner.actions_once.get(action).copied().unwrap_or(false) {
+            inner.actions_once.insert(SmolStr::from(action), false);
+            return true;
+        }
+
+        false
+    }
+
+    /// Returns true if the physical key is currently pressed down.
+    ///
+    /// Unlike [Input::key_pressed], the key is the same on every keyboard layout, eg.
+    /// [KeyCode::KeyW] is Z on AZERTY keyboards, which suits movement keys.
+    pub fn key_code_pressed(&self, code: KeyCode) -> bool {
+        self.inner
+            .borrow()
+            .key_codes
+            .get(&code)
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Returns true if the physical key was pressed once since the last call to this method.
+    pub fn key_code_pressed_once(&self, code: KeyCode) -> bool {
+        let mut inner = self.inner.borrow_mut();
+        if inner.key_codes_once.get(&code).copied().unwrap_or(false) {
+            inner.key_codes_once.insert(code, false);
+            return true;
+        }
+
+        false
+    }
+
     /// Listens for mouse events.
     pub fn listen_mouse_event<F>(&mut self, event: F)
     where
@@ -134,6 +295,9 @@ pub(crate) struct InputInner {
     window_id: Option<usize>,
 
     mouse_position: Point2,
+    has_mouse_position: bool,
+    mouse_delta: Vector2,
+    mouse_motion_raw: Vector2,
 
     mouse_buttons: HashMap<SmolStr, bool>,
     mouse_buttons_once: HashMap<SmolStr, bool>,
@@ -141,12 +305,48 @@ pub(crate) struct InputInner {
     keyboard_keys: HashMap<SmolStr, bool>,
     keyboard_keys_once: HashMap<SmolStr, bool>,
 
+    key_codes: HashMap<KeyCode, bool>,
+    key_codes_once: HashMap<KeyCode, bool>,
+
+    gamepad_buttons: HashMap<(usize, SmolStr), bool>,
+    gamepad_buttons_once: HashMap<SmolStr, bool>,
+    gamepad_axes: HashMap<(usize, SmolStr), f32>,
+
+    actions: HashMap<SmolStr, Vec<InputBinding>>,
+    actions_once: HashMap<SmolStr, bool>,
+
     mouse_events: Vec<MouseEvent>,
     keyboard_events: Vec<KeyboardEvent>,
     mouse_move_events: Vec<MouseMoveEvent>,
 }
 
 impl InputInner {
+    /// Resets the per frame state, called by [Runner::pump_events] before processing events.
+    pub fn begin_frame(&mut self) {
+        self.mouse_delta = Vector2::ZERO;
+        self.mouse_motion_raw = Vector2::ZERO;
+    }
+
+    fn binding_pressed(&self, binding: &InputBinding) -> bool {
+        match binding {
+            InputBinding::Key(key) => self.keyboard_keys.get(key).copied().unwrap_or(false),
+            InputBinding::Code(code) => self.key_codes.get(code).copied().unwrap_or(false),
+            InputBinding::Mouse(button) => self.mouse_buttons.get(button).copied().unwrap_or(false),
+            InputBinding::Gamepad(button) => self
+                .gamepad_buttons
+                .iter()
+                .any(|((_, name), pressed)| *pressed && name == button),
+        }
+    }
+
+    fn trigger_actions(&mut self, binding: &InputBinding) {
+        for (action, bindings) in &self.actions {
+            if bindings.contains(binding) {
+                self.actions_once.insert(action.clone(), true);
+            }
+        }
+    }
+
     pub fn process_event(&mut self, event: &Event) {
         match event {
             Event::CursorMoved { pos, window_id } => {
@@ -154,7 +354,18 @@ impl InputInner {
                     return;
                 }
 
-                self.mouse_position = Point2::new(pos.x as f32, pos.y as f32);
+                let position = Point2::new(pos.x as f32, pos.y as f32);
+
+                // The first position isn't a movement from the default (0, 0)
+                if self.has_mouse_position {
+                    self.mouse_delta += Vector2::new(
+                        position.x - self.mouse_position.x,
+                        position.y - self.mouse_position.y,
+                    );
+                }
+
+                self.mouse_position = position;
+                self.has_mouse_position = true;
 
                 for mouse_move_event in &self.mouse_move_events {
                     mouse_move_event(self.mouse_position);
@@ -172,13 +383,25 @@ impl InputInner {
                     for mouse_event in &self.mouse_events {
                         mouse_event(button, self.mouse_position, *pressed);
                     }
+
+                    if *pressed {
+                        self.trigger_actions(&InputBinding::Mouse(button.clone()));
+                    }
                 }
             }
-            Event::KeyboardInput { key, pressed, window_id } => {
+            Event::KeyboardInput { key, code, pressed, window_id, .. } => {
                 if self.window_id.is_some() && self.window_id != Some(*window_id) {
                     return;
                 }
 
+                let last_code_state = self.key_codes.get(code).copied().unwrap_or(false);
+                self.key_codes.insert(*code, *pressed);
+                self.key_codes_once.insert(*code, *pressed);
+
+                if last_code_state != *pressed && *pressed {
+                    self.trigger_actions(&InputBinding::Code(*code));
+                }
+
                 let last_state = self.keyboard_keys.get(key).copied().unwrap_or(false);
                 self.keyboard_keys.insert(key.clone(), *pressed);
                 self.keyboard_keys_once.insert(key.clone(), *pressed);
@@ -187,8 +410,32 @@ impl InputInner {
                     for keyboard_event in &self.keyboard_events {
                         keyboard_event(key, *pressed);
                     }
+
+                    if *pressed {
+                        self.trigger_actions(&InputBinding::Key(key.clone()));
+                    }
+                }
+            }
+            Event::MouseMotionRaw { delta } => {
+                self.mouse_motion_raw += Vector2::from(*delta);
+            }
+            Event::GamepadButton { gamepad_id, button, pressed } => {
+                let key = (*gamepad_id, button.clone());
+                let last_state = self.gamepad_buttons.get(&key).copied().unwrap_or(false);
+                self.gamepad_buttons.insert(key, *pressed);
+
+                if last_state != *pressed && *pressed {
+                    self.gamepad_buttons_once.insert(button.clone(), true);
+                    self.trigger_actions(&InputBinding::Gamepad(button.clone()));
                 }
             }
+            Event::GamepadAxis { gamepad_id, axis, value } => {
+                self.gamepad_axes.insert((*gamepad_id, axis.clone()), value.0);
+            }
+            Event::GamepadDisconnected { gamepad_id } => {
+                self.gamepad_buttons.retain(|(id, _), _| id != gamepad_id);
+                self.gamepad_axes.retain(|(id, _), _| id != gamepad_id);
+            }
             _ => {}
         }
     }
@@ -0,0 +1,482 @@
+//! Typed keyboard keys of [Event::KeyboardInput](crate::runner::Event::KeyboardInput).
+//!
+//! [KeyCode] is the physical key, the same wherever the layout puts its letter, which suits game
+//! controls like WASD. [Key] is the logical key, what the key means with the current layout and
+//! modifiers, which suits shortcuts and text.
+
+use smol_str::SmolStr;
+use winit::keyboard::{
+    Key as WinitKey, KeyCode as WinitKeyCode, NamedKey as WinitNamedKey, PhysicalKey,
+};
+
+/// A physical key, named after the key at the same position on a US keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum KeyCode {
+    // Letters, by their position on a US layout
+    KeyA,
+    KeyB,
+    KeyC,
+    KeyD,
+    KeyE,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyI,
+    KeyJ,
+    KeyK,
+    KeyL,
+    KeyM,
+    KeyN,
+    KeyO,
+    KeyP,
+    KeyQ,
+    KeyR,
+    KeyS,
+    KeyT,
+    KeyU,
+    KeyV,
+    KeyW,
+    KeyX,
+    KeyY,
+    KeyZ,
+    // Digits of the main row
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    // Punctuation, by their position on a US layout
+    Backquote,
+    Backslash,
+    BracketLeft,
+    BracketRight,
+    Comma,
+    Equal,
+    Minus,
+    Period,
+    Quote,
+    Semicolon,
+    Slash,
+    IntlBackslash,
+    // Modifiers
+    ShiftLeft,
+    ShiftRight,
+    ControlLeft,
+    ControlRight,
+    AltLeft,
+    AltRight,
+    SuperLeft,
+    SuperRight,
+    // Editing and navigation
+    Backspace,
+    CapsLock,
+    ContextMenu,
+    Enter,
+    Space,
+    Tab,
+    Delete,
+    End,
+    Home,
+    Insert,
+    PageDown,
+    PageUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    Escape,
+    PrintScreen,
+    ScrollLock,
+    Pause,
+    // Numpad
+    NumLock,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+    NumpadEnter,
+    NumpadEqual,
+    // Function keys
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    // Media
+    AudioVolumeDown,
+    AudioVolumeMute,
+    AudioVolumeUp,
+    MediaPlayPause,
+    MediaStop,
+    MediaTrackNext,
+    MediaTrackPrevious,
+    /// A key without a code above, or not identified by the platform.
+    Unidentified,
+}
+
+impl KeyCode {
+    pub(crate) fn from_physical(key: &PhysicalKey) -> Self {
+        let PhysicalKey::Code(code) = key else {
+            return KeyCode::Unidentified;
+        };
+
+        match code {
+            WinitKeyCode::KeyA => KeyCode::KeyA,
+            WinitKeyCode::KeyB => KeyCode::KeyB,
+            WinitKeyCode::KeyC => KeyCode::KeyC,
+            WinitKeyCode::KeyD => KeyCode::KeyD,
+            WinitKeyCode::KeyE => KeyCode::KeyE,
+            WinitKeyCode::KeyF => KeyCode::KeyF,
+            WinitKeyCode::KeyG => KeyCode::KeyG,
+            WinitKeyCode::KeyH => KeyCode::KeyH,
+            WinitKeyCode::KeyI => KeyCode::KeyI,
+            WinitKeyCode::KeyJ => KeyCode::KeyJ,
+            WinitKeyCode::KeyK => KeyCode::KeyK,
+            WinitKeyCode::KeyL => KeyCode::KeyL,
+            WinitKeyCode::KeyM => KeyCode::KeyM,
+            WinitKeyCode::KeyN => KeyCode::KeyN,
+            WinitKeyCode::KeyO => KeyCode::KeyO,
+            WinitKeyCode::KeyP => KeyCode::KeyP,
+            WinitKeyCode::KeyQ => KeyCode::KeyQ,
+            WinitKeyCode::KeyR => KeyCode::KeyR,
+            WinitKeyCode::KeyS => KeyCode::KeyS,
+            WinitKeyCode::KeyT => KeyCode::KeyT,
+            WinitKeyCode::KeyU => KeyCode::KeyU,
+            WinitKeyCode::KeyV => KeyCode::KeyV,
+            WinitKeyCode::KeyW => KeyCode::KeyW,
+            WinitKeyCode::KeyX => KeyCode::KeyX,
+            WinitKeyCode::KeyY => KeyCode::KeyY,
+            WinitKeyCode::KeyZ => KeyCode::KeyZ,
+            WinitKeyCode::Digit0 => KeyCode::Digit0,
+            WinitKeyCode::Digit1 => KeyCode::Digit1,
+            WinitKeyCode::Digit2 => KeyCode::Digit2,
+            WinitKeyCode::Digit3 => KeyCode::Digit3,
+            WinitKeyCode::Digit4 => KeyCode::Digit4,
+            WinitKeyCode::Digit5 => KeyCode::Digit5,
+            WinitKeyCode::Digit6 => KeyCode::Digit6,
+            WinitKeyCode::Digit7 => KeyCode::Digit7,
+            WinitKeyCode::Digit8 => KeyCode::Digit8,
+            WinitKeyCode::Digit9 => KeyCode::Digit9,
+            WinitKeyCode::Backquote => KeyCode::Backquote,
+            WinitKeyCode::Backslash => KeyCode::Backslash,
+            WinitKeyCode::BracketLeft => KeyCode::BracketLeft,
+            WinitKeyCode::BracketRight => KeyCode::BracketRight,
+            WinitKeyCode::Comma => KeyCode::Comma,
+            WinitKeyCode::Equal => KeyCode::Equal,
+            WinitKeyCode::Minus => KeyCode::Minus,
+            WinitKeyCode::Period => KeyCode::Period,
+            WinitKeyCode::Quote => KeyCode::Quote,
+            WinitKeyCode::Semicolon => KeyCode::Semicolon,
+            WinitKeyCode::Slash => KeyCode::Slash,
+            WinitKeyCode::IntlBackslash => KeyCode::IntlBackslash,
+            WinitKeyCode::ShiftLeft => KeyCode::ShiftLeft,
+            WinitKeyCode::ShiftRight => KeyCode::ShiftRight,
+            WinitKeyCode::ControlLeft => KeyCode::ControlLeft,
+            WinitKeyCode::ControlRight => KeyCode::ControlRight,
+            WinitKeyCode::AltLeft => KeyCode::AltLeft,
+            WinitKeyCode::AltRight => KeyCode::AltRight,
+            WinitKeyCode::SuperLeft => KeyCode::SuperLeft,
+            WinitKeyCode::SuperRight => KeyCode::SuperRight,
+            WinitKeyCode::Backspace => KeyCode::Backspace,
+            WinitKeyCode::CapsLock => KeyCode::CapsLock,
+            WinitKeyCode::ContextMenu => KeyCode::ContextMenu,
+            WinitKeyCode::Enter => KeyCode::Enter,
+            WinitKeyCode::Space => KeyCode::Space,
+            WinitKeyCode::Tab => KeyCode::Tab,
+            WinitKeyCode::Delete => KeyCode::Delete,
+            WinitKeyCode::End => KeyCode::End,
+            WinitKeyCode::Home => KeyCode::Home,
+            WinitKeyCode::Insert => KeyCode::Insert,
+            WinitKeyCode::PageDown => KeyCode::PageDown,
+            WinitKeyCode::PageUp => KeyCode::PageUp,
+            WinitKeyCode::ArrowDown => KeyCode::ArrowDown,
+            WinitKeyCode::ArrowLeft => KeyCode::ArrowLeft,
+            WinitKeyCode::ArrowRight => KeyCode::ArrowRight,
+            WinitKeyCode::ArrowUp => KeyCode::ArrowUp,
+            WinitKeyCode::Escape => KeyCode::Escape,
+            WinitKeyCode::PrintScreen => KeyCode::PrintScreen,
+            WinitKeyCode::ScrollLock => KeyCode::ScrollLock,
+            WinitKeyCode::Pause => KeyCode::Pause,
+            WinitKeyCode::NumLock => KeyCode::NumLock,
+            WinitKeyCode::Numpad0 => KeyCode::Numpad0,
+            WinitKeyCode::Numpad1 => KeyCode::Numpad1,
+            WinitKeyCode::Numpad2 => KeyCode::Numpad2,
+            WinitKeyCode::Numpad3 => KeyCode::Numpad3,
+            WinitKeyCode::Numpad4 => KeyCode::Numpad4,
+            WinitKeyCode::Numpad5 => KeyCode::Numpad5,
+            WinitKeyCode::Numpad6 => KeyCode::Numpad6,
+            WinitKeyCode::Numpad7 => KeyCode::Numpad7,
+            WinitKeyCode::Numpad8 => KeyCode::Numpad8,
+            WinitKeyCode::Numpad9 => KeyCode::Numpad9,
+            WinitKeyCode::NumpadAdd => KeyCode::NumpadAdd,
+            WinitKeyCode::NumpadSubtract => KeyCode::NumpadSubtract,
+            WinitKeyCode::NumpadMultiply => KeyCode::NumpadMultiply,
+            WinitKeyCode::NumpadDivide => KeyCode::NumpadDivide,
+            WinitKeyCode::NumpadDecimal => KeyCode::NumpadDecimal,
+            WinitKeyCode::NumpadEnter => KeyCode::NumpadEnter,
+            WinitKeyCode::NumpadEqual => KeyCode::NumpadEqual,
+            WinitKeyCode::F1 => KeyCode::F1,
+            WinitKeyCode::F2 => KeyCode::F2,
+            WinitKeyCode::F3 => KeyCode::F3,
+            WinitKeyCode::F4 => KeyCode::F4,
+            WinitKeyCode::F5 => KeyCode::F5,
+            WinitKeyCode::F6 => KeyCode::F6,
+            WinitKeyCode::F7 => KeyCode::F7,
+            WinitKeyCode::F8 => KeyCode::F8,
+            WinitKeyCode::F9 => KeyCode::F9,
+            WinitKeyCode::F10 => KeyCode::F10,
+            WinitKeyCode::F11 => KeyCode::F11,
+            WinitKeyCode::F12 => KeyCode::F12,
+            WinitKeyCode::F13 => KeyCode::F13,
+            WinitKeyCode::F14 => KeyCode::F14,
+            WinitKeyCode::F15 => KeyCode::F15,
+            WinitKeyCode::F16 => KeyCode::F16,
+            WinitKeyCode::F17 => KeyCode::F17,
+            WinitKeyCode::F18 => KeyCode::F18,
+            WinitKeyCode::F19 => KeyCode::F19,
+            WinitKeyCode::F20 => KeyCode::F20,
+            WinitKeyCode::F21 => KeyCode::F21,
+            WinitKeyCode::F22 => KeyCode::F22,
+            WinitKeyCode::F23 => KeyCode::F23,
+            WinitKeyCode::F24 => KeyCode::F24,
+            WinitKeyCode::AudioVolumeDown => KeyCode::AudioVolumeDown,
+            WinitKeyCode::AudioVolumeMute => KeyCode::AudioVolumeMute,
+            WinitKeyCode::AudioVolumeUp => KeyCode::AudioVolumeUp,
+            WinitKeyCode::MediaPlayPause => KeyCode::MediaPlayPause,
+            WinitKeyCode::MediaStop => KeyCode::MediaStop,
+            WinitKeyCode::MediaTrackNext => KeyCode::MediaTrackNext,
+            WinitKeyCode::MediaTrackPrevious => KeyCode::MediaTrackPrevious,
+            _ => KeyCode::Unidentified,
+        }
+    }
+}
+
+/// A named key of [Key::Named], a key which doesn't produce a character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NamedKey {
+    Alt,
+    AltGraph,
+    CapsLock,
+    Control,
+    Fn,
+    FnLock,
+    NumLock,
+    ScrollLock,
+    Shift,
+    Symbol,
+    SymbolLock,
+    Meta,
+    Hyper,
+    Super,
+    Enter,
+    Tab,
+    Space,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    End,
+    Home,
+    PageDown,
+    PageUp,
+    Backspace,
+    Clear,
+    Delete,
+    Insert,
+    Escape,
+    Pause,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+}
+
+impl NamedKey {
+    pub(crate) fn from_winit(key: &WinitNamedKey) -> Option<Self> {
+        let key = match key {
+            WinitNamedKey::Alt => NamedKey::Alt,
+            WinitNamedKey::AltGraph => NamedKey::AltGraph,
+            WinitNamedKey::CapsLock => NamedKey::CapsLock,
+            WinitNamedKey::Control => NamedKey::Control,
+            WinitNamedKey::Fn => NamedKey::Fn,
+            WinitNamedKey::FnLock => NamedKey::FnLock,
+            WinitNamedKey::NumLock => NamedKey::NumLock,
+            WinitNamedKey::ScrollLock => NamedKey::ScrollLock,
+            WinitNamedKey::Shift => NamedKey::Shift,
+            WinitNamedKey::Symbol => NamedKey::Symbol,
+            WinitNamedKey::SymbolLock => NamedKey::SymbolLock,
+            WinitNamedKey::Meta => NamedKey::Meta,
+            WinitNamedKey::Hyper => NamedKey::Hyper,
+            WinitNamedKey::Super => NamedKey::Super,
+            WinitNamedKey::Enter => NamedKey::Enter,
+            WinitNamedKey::Tab => NamedKey::Tab,
+            WinitNamedKey::Space => NamedKey::Space,
+            WinitNamedKey::ArrowDown => NamedKey::ArrowDown,
+            WinitNamedKey::ArrowLeft => NamedKey::ArrowLeft,
+            WinitNamedKey::ArrowRight => NamedKey::ArrowRight,
+            WinitNamedKey::ArrowUp => NamedKey::ArrowUp,
+            WinitNamedKey::End => NamedKey::End,
+            WinitNamedKey::Home => NamedKey::Home,
+            WinitNamedKey::PageDown => NamedKey::PageDown,
+            WinitNamedKey::PageUp => NamedKey::PageUp,
+            WinitNamedKey::Backspace => NamedKey::Backspace,
+            WinitNamedKey::Clear => NamedKey::Clear,
+            WinitNamedKey::Delete => NamedKey::Delete,
+            WinitNamedKey::Insert => NamedKey::Insert,
+            WinitNamedKey::Escape => NamedKey::Escape,
+            WinitNamedKey::Pause => NamedKey::Pause,
+            WinitNamedKey::F1 => NamedKey::F1,
+            WinitNamedKey::F2 => NamedKey::F2,
+            WinitNamedKey::F3 => NamedKey::F3,
+            WinitNamedKey::F4 => NamedKey::F4,
+            WinitNamedKey::F5 => NamedKey::F5,
+            WinitNamedKey::F6 => NamedKey::F6,
+            WinitNamedKey::F7 => NamedKey::F7,
+            WinitNamedKey::F8 => NamedKey::F8,
+            WinitNamedKey::F9 => NamedKey::F9,
+            WinitNamedKey::F10 => NamedKey::F10,
+            WinitNamedKey::F11 => NamedKey::F11,
+            WinitNamedKey::F12 => NamedKey::F12,
+            _ => return None,
+        };
+
+        Some(key)
+    }
+
+    /// The name of the key, as used by the string form of the keyboard events.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NamedKey::Alt => "Alt",
+            NamedKey::AltGraph => "AltGraph",
+            NamedKey::CapsLock => "CapsLock",
+            NamedKey::Control => "Control",
+            NamedKey::Fn => "Fn",
+            NamedKey::FnLock => "FnLock",
+            NamedKey::NumLock => "NumLock",
+            NamedKey::ScrollLock => "ScrollLock",
+            NamedKey::Shift => "Shift",
+            NamedKey::Symbol => "Symbol",
+            NamedKey::SymbolLock => "SymbolLock",
+            NamedKey::Meta => "Meta",
+            NamedKey::Hyper => "Hyper",
+            NamedKey::Super => "Super",
+            NamedKey::Enter => "Enter",
+            NamedKey::Tab => "Tab",
+            NamedKey::Space => "Space",
+            NamedKey::ArrowDown => "ArrowDown",
+            NamedKey::ArrowLeft => "ArrowLeft",
+            NamedKey::ArrowRight => "ArrowRight",
+            NamedKey::ArrowUp => "ArrowUp",
+            NamedKey::End => "End",
+            NamedKey::Home => "Home",
+            NamedKey::PageDown => "PageDown",
+            NamedKey::PageUp => "PageUp",
+            NamedKey::Backspace => "Backspace",
+            NamedKey::Clear => "Clear",
+            NamedKey::Delete => "Delete",
+            NamedKey::Insert => "Insert",
+            NamedKey::Escape => "Escape",
+            NamedKey::Pause => "Pause",
+            NamedKey::F1 => "F1",
+            NamedKey::F2 => "F2",
+            NamedKey::F3 => "F3",
+            NamedKey::F4 => "F4",
+            NamedKey::F5 => "F5",
+            NamedKey::F6 => "F6",
+            NamedKey::F7 => "F7",
+            NamedKey::F8 => "F8",
+            NamedKey::F9 => "F9",
+            NamedKey::F10 => "F10",
+            NamedKey::F11 => "F11",
+            NamedKey::F12 => "F12",
+        }
+    }
+}
+
+/// A logical key, the meaning of the key with the current layout and modifiers.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Key {
+    /// A key producing a character, eg. "a", or "A" while Shift is held.
+    Character(SmolStr),
+    Named(NamedKey),
+    /// A key without a meaning known to the crate, only its [KeyCode] is available.
+    Unidentified,
+}
+
+impl Key {
+    pub(crate) fn from_winit(key: &WinitKey) -> Self {
+        match key {
+            WinitKey::Character(character) => Key::Character(character.clone()),
+            WinitKey::Named(named) => NamedKey::from_winit(named)
+                .map(Key::Named)
+                .unwrap_or(Key::Unidentified),
+            _ => Key::Unidentified,
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// The modifier keys held during a keyboard event.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Modifiers: u8 {
+        const SHIFT = 1 << 0;
+        const CONTROL = 1 << 1;
+        const ALT = 1 << 2;
+        /// The Windows key on Windows and Linux, Command on macOS.
+        const SUPER = 1 << 3;
+    }
+}
+
+impl Modifiers {
+    pub(crate) fn from_winit(modifiers: &winit::keyboard::ModifiersState) -> Self {
+        let mut result = Modifiers::empty();
+        result.set(Modifiers::SHIFT, modifiers.shift_key());
+        result.set(Modifiers::CONTROL, modifiers.control_key());
+        result.set(Modifiers::ALT, modifiers.alt_key());
+        result.set(Modifiers::SUPER, modifiers.super_key());
+        result
+    }
+}
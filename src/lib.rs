@@ -1,5 +1,9 @@
 //! Easy to use winit, softbuffer & wgpu abstractions
 
+/// Offline baking of texture atlases and font caches
+pub mod bake;
+/// Benchmark harness measuring frame timings on a headless GPU
+pub mod bench;
 /// Font rendering and text layout utilities
 pub mod font;
 /// GPU graphics rendering abstractions
@@ -8,6 +12,9 @@ pub mod gpu;
 pub mod math;
 /// Predefined types and traits for easy access
 pub mod prelude;
+/// Software fallback for presenting CPU drawn frames when no GPU is available
+#[cfg(feature = "software")]
+pub mod renderer;
 /// Runner for managing the main event loop and window lifecycle
 pub mod runner;
 /// Software rendering utilities
@@ -0,0 +1,507 @@
+use bytemuck::{Pod, Zeroable};
+
+use crate::runner::{Event, MouseScrollDelta};
+
+use super::{Matrix4, ModelViewProjection, Point2, Vector3, Vector4};
+
+/// Lines scrolled per pixel of a touchpad [MouseScrollDelta::PixelDelta].
+const LINES_PER_PIXEL: f32 = 1.0 / 40.0;
+
+/// Projection of a [Camera3D].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// Vertical field of view in degrees.
+    Perspective { fov_y: f32, near: f32, far: f32 },
+    /// Visible height in world units, the width follows the aspect ratio.
+    Orthographic { height: f32, near: f32, far: f32 },
+}
+
+/// A right-handed 3D camera with Y up, looking from `position` at `target`.
+///
+/// The matrices use the row-major convention of [Matrix4] and the `0..1` depth range of wgpu,
+/// upload them with [Camera3D::uniform] which lays them out for WGSL:
+/// ```wgsl
+/// struct Camera {
+///     view: mat4x4<f32>,
+///     projection: mat4x4<f32>,
+///     view_projection: mat4x4<f32>,
+///     position: vec4<f32>,
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera3D {
+    pub position: Vector3,
+    pub target: Vector3,
+    pub up: Vector3,
+    pub projection: Projection,
+    /// Width divided by height of the viewport.
+    pub aspect: f32,
+}
+
+impl Camera3D {
+    /// Creates a perspective camera, `fov_y` is the vertical field of view in degrees.
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Self {
+        Self::new(Projection::Perspective { fov_y, near, far }, aspect)
+    }
+
+    /// Creates an orthographic camera showing `height` world units vertically.
+    pub fn orthographic(height: f32, aspect: f32, near: f32, far: f32) -> Self {
+        Self::new(Projection::Orthographic { height, near, far }, aspect)
+    }
+
+    fn new(projection: Projection, aspect: f32) -> Self {
+        Self {
+            position: Vector3::new(0.0, 0.0, 5.0),
+            target: Vector3::new(0.0, 0.0, 0.0),
+            up: Vector3::new(0.0, 1.0, 0.0),
+            projection,
+            aspect,
+        }
+    }
+
+    pub fn look_at(&mut self, position: Vector3, target: Vector3) {
+        self.position = position;
+        self.target = target;
+    }
+
+    /// Sets the aspect ratio from the size of the viewport in pixels.
+    pub fn set_viewport_size(&mut self, size: Point2) {
+        if size.x > 0 && size.y > 0 {
+            self.aspect = size.x as f32 / size.y as f32;
+        }
+    }
+
+    /// Unit vector from the position to the target.
+    pub fn forward(&self) -> Vector3 {
+        (self.target - self.position).normalize()
+    }
+
+    /// Unit vector pointing to the right of the view.
+    pub fn right(&self) -> Vector3 {
+        self.forward().cross(&self.up).normalize()
+    }
+
+    pub fn view(&self) -> Matrix4 {
+        Matrix4::look_at(self.position, self.target, self.up)
+    }
+
+    pub fn projection(&self) -> Matrix4 {
+        let projection = match self.projection {
+            Projection::Perspective { fov_y, near, far } => {
+                Matrix4::perspective(fov_y.to_radians(), self.aspect, near, far)
+            }
+            Projection::Orthographic { height, near, far } => {
+                let half_height = height * 0.5;
+                let half_width = half_height * self.aspect;
+
+                Matrix4::orthographic(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    near,
+                    far,
+                )
+            }
+        };
+
+        Matrix4::OPENGL_TO_WGPU_MATRIX * projection
+    }
+
+    pub fn view_projection(&self) -> Matrix4 {
+        self.projection() * self.view()
+    }
+
+    /// Returns the matrices of the camera with the given model matrix.
+    pub fn mvp(&self, model: Matrix4) -> ModelViewProjection {
+        ModelViewProjection {
+            model,
+            view: self.view(),
+            projection: self.projection(),
+        }
+    }
+
+    /// Planes of the volume seen by the camera, in world space.
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&self.view_projection())
+    }
+
+    /// Matrices of the camera laid out for a WGSL uniform, see [Camera3D].
+    pub fn uniform(&self) -> CameraUniform {
+        CameraUniform {
+            view: column_major(&self.view()),
+            projection: column_major(&self.projection()),
+            view_projection: column_major(&self.view_projection()),
+            position: [self.position.x, self.position.y, self.position.z, 1.0],
+        }
+    }
+}
+
+fn column_major(matrix: &Matrix4) -> [[f32; 4]; 4] {
+    let mut result = [[0.0; 4]; 4];
+
+    for (row, values) in matrix.m.iter().enumerate() {
+        for (column, value) in values.iter().enumerate() {
+            result[column][row] = *value;
+        }
+    }
+
+    result
+}
+
+/// The matrices of a [Camera3D] in the column-major layout of WGSL `mat4x4<f32>`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct CameraUniform {
+    pub view: [[f32; 4]; 4],
+    pub projection: [[f32; 4]; 4],
+    pub view_projection: [[f32; 4]; 4],
+    pub position: [f32; 4],
+}
+
+crate::impl_gpu_uniform!(CameraUniform {
+    view,
+    projection,
+    view_projection,
+    position
+});
+
+/// The six planes bounding the volume seen by a camera, in left, right, bottom, top, near, far
+/// order.
+///
+/// Each plane is stored as `(normal, distance)` with the normal pointing inside, a point `p` is
+/// on the inner side when `normal.dot(p) + distance >= 0`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    pub planes: [Vector4; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes of a view projection matrix using the `0..1` depth range.
+    pub fn from_matrix(matrix: &Matrix4) -> Self {
+        let m = &matrix.m;
+        let combine = |row: usize, sign: f32| -> [f32; 4] {
+            std::array::from_fn(|column| m[3][column] + sign * m[row][column])
+        };
+
+        let planes = [
+            combine(0, 1.0),
+            combine(0, -1.0),
+            combine(1, 1.0),
+            combine(1, -1.0),
+            m[2],
+            combine(2, -1.0),
+        ]
+        .map(|[x, y, z, w]| {
+            let length = Vector3::new(x, y, z).length();
+            let scale = if length > f32::EPSILON {
+                1.0 / length
+            } else {
+                1.0
+            };
+
+            Vector4::new(x * scale, y * scale, z * scale, w * scale)
+        });
+
+        Self { planes }
+    }
+
+    fn distance(plane: &Vector4, point: Vector3) -> f32 {
+        plane.x * point.x + plane.y * point.y + plane.z * point.z + plane.w
+    }
+
+    pub fn contains_point(&self, point: Vector3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| Self::distance(plane, point) >= 0.0)
+    }
+
+    /// Returns false only when the sphere is fully outside, so it can be used for culling.
+    pub fn intersects_sphere(&self, center: Vector3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| Self::distance(plane, center) >= -radius)
+    }
+
+    /// Returns false only when the axis-aligned box is fully outside, so it can be used for
+    /// culling.
+    pub fn intersects_aabb(&self, min: Vector3, max: Vector3) -> bool {
+        self.planes.iter().all(|plane| {
+            // Corner of the box the furthest along the plane normal.
+            let corner = Vector3::new(
+                if plane.x >= 0.0 { max.x } else { min.x },
+                if plane.y >= 0.0 { max.y } else { min.y },
+                if plane.z >= 0.0 { max.z } else { min.z },
+            );
+
+            Self::distance(plane, corner) >= 0.0
+        })
+    }
+}
+
+/// Mouse state shared by the camera controllers.
+#[derive(Clone, Copy, Debug, Default)]
+struct PointerState {
+    last_position: Option<Point2>,
+    /// Movement since the last update, per button in left, right, middle order.
+    drag: [(f32, f32); 3],
+    pressed: [bool; 3],
+    scroll: f32,
+}
+
+impl PointerState {
+    fn process_event(&mut self, event: &Event) {
+        match event {
+            Event::CursorMoved { pos, .. } => {
+                if let Some(last) = self.last_position {
+                    let delta = ((pos.x - last.x) as f32, (pos.y - last.y) as f32);
+
+                    for (drag, _) in self
+                        .drag
+                        .iter_mut()
+                        .zip(self.pressed)
+                        .filter(|(_, pressed)| *pressed)
+                    {
+                        drag.0 += delta.0;
+                        drag.1 += delta.1;
+                    }
+                }
+
+                self.last_position = Some(*pos);
+            }
+            Event::CursorLeft { .. } => {
+                self.last_position = None;
+            }
+            Event::MouseInput {
+                button, pressed, ..
+            } => {
+                let index = match button.as_str() {
+                    "Left" => 0,
+                    "Right" => 1,
+                    "Middle" => 2,
+                    _ => return,
+                };
+
+                self.pressed[index] = *pressed;
+            }
+            Event::MouseWheel { delta, .. } => {
+                self.scroll += match delta {
+                    MouseScrollDelta::LineDelta { delta_y, .. } => *delta_y,
+                    MouseScrollDelta::PixelDelta { delta_y, .. } => *delta_y * LINES_PER_PIXEL,
+                };
+            }
+            Event::WindowFocused { focused: false, .. } => {
+                self.pressed = [false; 3];
+            }
+            _ => {}
+        }
+    }
+
+    fn take_drag(&mut self, button: usize) -> (f32, f32) {
+        std::mem::take(&mut self.drag[button])
+    }
+
+    fn take_scroll(&mut self) -> f32 {
+        std::mem::take(&mut self.scroll)
+    }
+}
+
+fn event_window_id(event: &Event) -> Option<usize> {
+    match event {
+        Event::CursorMoved { window_id, .. }
+        | Event::CursorLeft { window_id }
+        | Event::MouseInput { window_id, .. }
+        | Event::MouseWheel { window_id, .. }
+        | Event::KeyboardInput { window_id, .. }
+        | Event::WindowFocused { window_id, .. } => Some(*window_id),
+        _ => None,
+    }
+}
+
+/// Rotates a camera around a target point with the mouse.
+///
+/// Dragging with the left button orbits, dragging with the middle button pans and the wheel
+/// zooms. Feed it every runner event with [OrbitController::process_event], then apply it once
+/// per frame with [OrbitController::update].
+///
+/// # Example
+/// ```ignore
+/// let mut camera = Camera3D::perspective(60.0, 16.0 / 9.0, 0.1, 100.0);
+/// let mut controller = OrbitController::new(Vector3::new(0.0, 0.0, 0.0), 5.0);
+///
+/// // For each event
+/// controller.process_event(&event);
+///
+/// // Every frame
+/// controller.update(&mut camera);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct OrbitController {
+    pub target: Vector3,
+    pub distance: f32,
+    /// Rotation around the Y axis in degrees, 0 looks toward -Z.
+    pub yaw: f32,
+    /// Elevation in degrees, clamped to `(-89, 89)`.
+    pub pitch: f32,
+
+    /// Degrees turned per pixel dragged.
+    pub rotate_sensitivity: f32,
+    /// Fraction of the distance panned per pixel dragged.
+    pub pan_sensitivity: f32,
+    /// Fraction of the distance zoomed per wheel line.
+    pub zoom_sensitivity: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// Only events of this window are used, all windows when [None].
+    pub window_id: Option<usize>,
+
+    pointer: PointerState,
+}
+
+impl OrbitController {
+    pub fn new(target: Vector3, distance: f32) -> Self {
+        Self {
+            target,
+            distance,
+            yaw: 0.0,
+            pitch: 0.0,
+            rotate_sensitivity: 0.3,
+            pan_sensitivity: 0.002,
+            zoom_sensitivity: 0.1,
+            min_distance: 0.01,
+            max_distance: f32::MAX,
+            window_id: None,
+            pointer: PointerState::default(),
+        }
+    }
+
+    pub fn process_event(&mut self, event: &Event) {
+        if self.window_id.is_some() && event_window_id(event) != self.window_id {
+            return;
+        }
+
+        self.pointer.process_event(event);
+    }
+
+    /// Applies the input received since the last update and moves the camera.
+    pub fn update(&mut self, camera: &mut Camera3D) {
+        let (dx, dy) = self.pointer.take_drag(0);
+        self.yaw -= dx * self.rotate_sensitivity;
+        self.pitch = (self.pitch + dy * self.rotate_sensitivity).clamp(-89.0, 89.0);
+
+        let scroll = self.pointer.take_scroll();
+        self.distance = (self.distance * (1.0 - self.zoom_sensitivity).powf(scroll))
+            .clamp(self.min_distance, self.max_distance);
+
+        let (yaw_sin, yaw_cos) = self.yaw.to_radians().sin_cos();
+        let (pitch_sin, pitch_cos) = self.pitch.to_radians().sin_cos();
+        let offset = Vector3::new(yaw_sin * pitch_cos, pitch_sin, yaw_cos * pitch_cos);
+
+        let (px, py) = self.pointer.take_drag(2);
+        if px != 0.0 || py != 0.0 {
+            let forward = offset * -1.0;
+            let right = forward.cross(&camera.up).normalize();
+            let up = right.cross(&forward);
+            let scale = self.distance * self.pan_sensitivity;
+
+            self.target = self.target - right * (px * scale) + up * (py * scale);
+        }
+
+        camera.look_at(self.target + offset * self.distance, self.target);
+    }
+}
+
+/// Moves a camera freely, like a spectator in a game.
+///
+/// `W`, `A`, `S` and `D` move, `Space` and `Control` go up and down, `Shift` moves faster and
+/// dragging with the right button looks around. Feed it every runner event with
+/// [FlyController::process_event], then apply it once per frame with [FlyController::update].
+#[derive(Clone, Copy, Debug)]
+pub struct FlyController {
+    pub position: Vector3,
+    /// Rotation around the Y axis in degrees, 0 looks toward -Z.
+    pub yaw: f32,
+    /// Elevation in degrees, clamped to `(-89, 89)`.
+    pub pitch: f32,
+
+    /// World units moved per second.
+    pub speed: f32,
+    /// Speed multiplier while `Shift` is held.
+    pub boost: f32,
+    /// Degrees turned per pixel dragged.
+    pub look_sensitivity: f32,
+    /// Only events of this window are used, all windows when [None].
+    pub window_id: Option<usize>,
+
+    pointer: PointerState,
+    /// Forward, backward, left, right, up, down and boost keys.
+    keys: [bool; 7],
+}
+
+impl FlyController {
+    pub fn new(position: Vector3) -> Self {
+        Self {
+            position,
+            yaw: 0.0,
+            pitch: 0.0,
+            speed: 5.0,
+            boost: 4.0,
+            look_sensitivity: 0.2,
+            window_id: None,
+            pointer: PointerState::default(),
+            keys: [false; 7],
+        }
+    }
+
+    pub fn process_event(&mut self, event: &Event) {
+        if self.window_id.is_some() && event_window_id(event) != self.window_id {
+            return;
+        }
+
+        match event {
+            Event::KeyboardInput { key, pressed, .. } => {
+                let index = match key.to_ascii_lowercase().as_str() {
+                    "w" => 0,
+                    "s" => 1,
+                    "a" => 2,
+                    "d" => 3,
+                    "space" | " " => 4,
+                    "control" => 5,
+                    "shift" => 6,
+                    _ => return,
+                };
+
+                self.keys[index] = *pressed;
+            }
+            Event::WindowFocused { focused: false, .. } => {
+                self.keys = [false; 7];
+                self.pointer.process_event(event);
+            }
+            _ => self.pointer.process_event(event),
+        }
+    }
+
+    /// Applies the input received since the last update and moves the camera, `delta_time` is
+    /// in seconds.
+    pub fn update(&mut self, camera: &mut Camera3D, delta_time: f32) {
+        let (dx, dy) = self.pointer.take_drag(1);
+        self.yaw += dx * self.look_sensitivity;
+        self.pitch = (self.pitch - dy * self.look_sensitivity).clamp(-89.0, 89.0);
+
+        let (yaw_sin, yaw_cos) = self.yaw.to_radians().sin_cos();
+        let (pitch_sin, pitch_cos) = self.pitch.to_radians().sin_cos();
+        let forward = Vector3::new(yaw_sin * pitch_cos, pitch_sin, -yaw_cos * pitch_cos);
+        let right = forward.cross(&camera.up).normalize();
+
+        let key = |index: usize| if self.keys[index] { 1.0 } else { 0.0 };
+        let axis = |positive: usize, negative: usize| key(positive) - key(negative);
+
+        let direction = forward * axis(0, 1) + right * axis(3, 2) + camera.up * axis(4, 5);
+        if direction.length() > f32::EPSILON {
+            let boost = if self.keys[6] { self.boost } else { 1.0 };
+            self.position += direction.normalize() * (self.speed * boost * delta_time);
+        }
+
+        camera.look_at(self.position, self.position + forward);
+    }
+}
@@ -62,6 +62,163 @@ impl Color {
         self
     }
 
+    /// The color encoded in sRGB, what a swapchain or texture without an `Srgb` format expects.
+    pub fn to_srgb(&self) -> Self {
+        self.into_srgb()
+    }
+
+    /// The color decoded from sRGB to linear, the space lighting and blending happen in.
+    pub fn to_linear(&self) -> Self {
+        self.into_linear()
+    }
+
+    /// Parses `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`, the `#` is optional.
+    ///
+    /// Returns [None] if the string isn't a valid hex color.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !hex.is_ascii() {
+            return None;
+        }
+
+        let digit = |index: usize| u8::from_str_radix(&hex[index..index + 1], 16).ok();
+        let pair = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+
+        let [r, g, b, a] = match hex.len() {
+            3 | 4 => {
+                let short = |index: usize| digit(index).map(|value| value * 17);
+                let a = if hex.len() == 4 { short(3)? } else { 255 };
+
+                [short(0)?, short(1)?, short(2)?, a]
+            }
+            6 | 8 => {
+                let a = if hex.len() == 8 { pair(6)? } else { 255 };
+
+                [pair(0)?, pair(2)?, pair(4)?, a]
+            }
+            _ => return None,
+        };
+
+        Some(Self::from([r, g, b, a]))
+    }
+
+    /// Formats the color as `#rrggbbaa`.
+    pub fn to_hex(&self) -> String {
+        let [r, g, b, a] = [self.r, self.g, self.b, self.a]
+            .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+        format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+    }
+
+    /// Creates a color from a hue in degrees, and saturation and value in the range [0.0, 1.0].
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32, alpha: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+
+        let chroma = value * saturation;
+        Self::from_hue(hue, chroma, value - chroma, alpha)
+    }
+
+    /// The color as `(hue, saturation, value)`, with the hue in degrees.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, max, chroma) = self.hue();
+        let saturation = if max > 0.0 { chroma / max } else { 0.0 };
+
+        (hue, saturation, max)
+    }
+
+    /// Creates a color from a hue in degrees, and saturation and lightness in the range
+    /// [0.0, 1.0].
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32, alpha: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Self::from_hue(hue, chroma, lightness - chroma / 2.0, alpha)
+    }
+
+    /// The color as `(hue, saturation, lightness)`, with the hue in degrees.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, max, chroma) = self.hue();
+        let lightness = max - chroma / 2.0;
+        let saturation = if lightness > 0.0 && lightness < 1.0 {
+            chroma / (1.0 - (2.0 * lightness - 1.0).abs())
+        } else {
+            0.0
+        };
+
+        (hue, saturation, lightness)
+    }
+
+    /// Interpolates every channel between two colors, `t` goes from 0 to 1.
+    ///
+    /// Interpolating linear colors gives smoother gradients than sRGB ones, convert with
+    /// [Color::to_linear] first and back with [Color::to_srgb] if needed.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        *self + (*other - *self) * t
+    }
+
+    /// Multiplies the color channels by alpha, for blending with
+    /// [BlendState::PREMULTIPLIED_ALPHA_BLENDING](wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING).
+    pub fn premultiply(&self) -> Self {
+        Self {
+            r: self.r * self.a,
+            g: self.g * self.a,
+            b: self.b * self.a,
+            a: self.a,
+        }
+    }
+
+    /// Reverts [Color::premultiply], a fully transparent color stays black.
+    pub fn unpremultiply(&self) -> Self {
+        if self.a <= 0.0 {
+            return Self::new_const(0.0, 0.0, 0.0, 0.0);
+        }
+
+        Self {
+            r: (self.r / self.a).clamp(0.0, 1.0),
+            g: (self.g / self.a).clamp(0.0, 1.0),
+            b: (self.b / self.a).clamp(0.0, 1.0),
+            a: self.a,
+        }
+    }
+
+    /// Shared part of HSV and HSL, `offset` is added to every channel after placing the chroma on
+    /// the hue.
+    fn from_hue(hue: f32, chroma: f32, offset: f32, alpha: f32) -> Self {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+
+        Self::new(r + offset, g + offset, b + offset, alpha)
+    }
+
+    /// Hue in degrees, largest channel and chroma of the color.
+    fn hue(&self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let chroma = max - min;
+
+        let hue = if chroma <= 0.0 {
+            0.0
+        } else if max == self.r {
+            60.0 * ((self.g - self.b) / chroma).rem_euclid(6.0)
+        } else if max == self.g {
+            60.0 * ((self.b - self.r) / chroma + 2.0)
+        } else {
+            60.0 * ((self.r - self.g) / chroma + 4.0)
+        };
+
+        (hue, max, chroma)
+    }
+
     pub const ALICEBLUE: Color = Self::new_const(0.941, 0.973, 1.0, 1.0);
     pub const ANTIQUEWHITE: Color = Self::new_const(0.98, 0.922, 0.843, 1.0);
     pub const AQUA: Color = Self::new_const(0.0, 1.0, 1.0, 1.0);
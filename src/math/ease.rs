@@ -0,0 +1,328 @@
+//! Easing functions, interpolation and tweens for animations.
+//!
+//! Easing functions take a progress `t` in the range [0.0, 1.0] and return the eased progress,
+//! 0.0 at the start and 1.0 at the end. `back` and `elastic` overshoot in between.
+//!
+//! # Example
+//! ```ignore
+//! let mut slide = Tween::new(Vector2::new(-200.0, 0.0), Vector2::new(0.0, 0.0), 0.4)
+//!     .with_easing(ease::cubic_out);
+//!
+//! while runner.pump_events(None) {
+//!     let offset = slide.update(runner.get_frame_time());
+//!     // ...
+//! }
+//! ```
+
+use std::f32::consts::PI;
+
+use super::{Color, Quat, Timing, Vector2, Vector3, Vector4};
+
+pub fn linear(t: f32) -> f32 {
+    t
+}
+
+pub fn quad_in(t: f32) -> f32 {
+    t * t
+}
+
+pub fn quad_out(t: f32) -> f32 {
+    1.0 - (1.0 - t) * (1.0 - t)
+}
+
+pub fn quad_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        2.0 * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+    }
+}
+
+pub fn cubic_in(t: f32) -> f32 {
+    t * t * t
+}
+
+pub fn cubic_out(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+pub fn cubic_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+pub fn expo_in(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else {
+        2f32.powf(10.0 * t - 10.0)
+    }
+}
+
+pub fn expo_out(t: f32) -> f32 {
+    if t >= 1.0 {
+        1.0
+    } else {
+        1.0 - 2f32.powf(-10.0 * t)
+    }
+}
+
+pub fn expo_in_out(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else if t < 0.5 {
+        2f32.powf(20.0 * t - 10.0) / 2.0
+    } else {
+        (2.0 - 2f32.powf(-20.0 * t + 10.0)) / 2.0
+    }
+}
+
+const BACK: f32 = 1.70158;
+const BACK_IN_OUT: f32 = BACK * 1.525;
+
+/// Pulls back below 0.0 before moving to the end.
+pub fn back_in(t: f32) -> f32 {
+    (BACK + 1.0) * t * t * t - BACK * t * t
+}
+
+/// Overshoots past 1.0 before settling at the end.
+pub fn back_out(t: f32) -> f32 {
+    let t = t - 1.0;
+    1.0 + (BACK + 1.0) * t * t * t + BACK * t * t
+}
+
+pub fn back_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        (2.0 * t).powi(2) * ((BACK_IN_OUT + 1.0) * 2.0 * t - BACK_IN_OUT) / 2.0
+    } else {
+        ((2.0 * t - 2.0).powi(2) * ((BACK_IN_OUT + 1.0) * (t * 2.0 - 2.0) + BACK_IN_OUT) + 2.0)
+            / 2.0
+    }
+}
+
+const ELASTIC: f32 = 2.0 * PI / 3.0;
+const ELASTIC_IN_OUT: f32 = 2.0 * PI / 4.5;
+
+/// Oscillates with a growing amplitude before reaching the end.
+pub fn elastic_in(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else {
+        -2f32.powf(10.0 * t - 10.0) * ((t * 10.0 - 10.75) * ELASTIC).sin()
+    }
+}
+
+/// Springs past the end and oscillates around it until settling.
+pub fn elastic_out(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else {
+        2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * ELASTIC).sin() + 1.0
+    }
+}
+
+pub fn elastic_in_out(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else if t < 0.5 {
+        -(2f32.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin()) / 2.0
+    } else {
+        2f32.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin() / 2.0 + 1.0
+    }
+}
+
+/// Values that can be interpolated, used by [lerp], [smoothstep] and [Tween].
+pub trait Lerp: Copy {
+    /// The value at `t` between `self` (0.0) and `other` (1.0).
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * f64::from(t)
+    }
+}
+
+impl Lerp for Vector2 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Lerp for Vector3 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Lerp for Vector4 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            x: self.x.lerp(&other.x, t),
+            y: self.y.lerp(&other.y, t),
+            z: self.z.lerp(&other.z, t),
+            w: self.w.lerp(&other.w, t),
+        }
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Color::lerp(self, other, t)
+    }
+}
+
+/// Rotations are interpolated along the shortest arc with [Quat::slerp].
+impl Lerp for Quat {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self.slerp(other, t)
+    }
+}
+
+/// The value at `t` between `from` (0.0) and `to` (1.0).
+pub fn lerp<T: Lerp>(from: T, to: T, t: f32) -> T {
+    Lerp::lerp(&from, &to, t)
+}
+
+/// Like [lerp] but easing in and out of both ends, `t` is clamped to [0.0, 1.0].
+///
+/// `smoothstep(0.0, 1.0, inverse_lerp(edge0, edge1, x))` is the GLSL `smoothstep(edge0, edge1, x)`.
+pub fn smoothstep<T: Lerp>(from: T, to: T, t: f32) -> T {
+    let t = t.clamp(0.0, 1.0);
+    lerp(from, to, t * t * (3.0 - 2.0 * t))
+}
+
+/// Where `value` is between `from` (0.0) and `to` (1.0), the inverse of [lerp] for scalars.
+pub fn inverse_lerp(from: f32, to: f32, value: f32) -> f32 {
+    if from == to {
+        0.0
+    } else {
+        (value - from) / (to - from)
+    }
+}
+
+/// What a [Tween] does once it reaches its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TweenMode {
+    /// Stays on the end value.
+    #[default]
+    Once,
+    /// Restarts from the start value.
+    Loop,
+    /// Goes back to the start value, then forth again.
+    PingPong,
+}
+
+/// Animates a value from a start to an end over a duration, with an easing function.
+///
+/// The tween is advanced with the frame time, from [Tween::update] with the runner's
+/// `get_frame_time()` or [Tween::tick] with a [Timing].
+#[derive(Debug, Clone, Copy)]
+pub struct Tween<T: Lerp> {
+    from: T,
+    to: T,
+    duration: f32,
+    elapsed: f32,
+    easing: fn(f32) -> f32,
+    mode: TweenMode,
+}
+
+impl<T: Lerp> Tween<T> {
+    /// Creates a linear tween lasting `duration` seconds.
+    pub fn new(from: T, to: T, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+            easing: linear,
+            mode: TweenMode::Once,
+        }
+    }
+
+    /// Sets the easing function, one of this module's or any function mapping [0.0, 1.0] to the
+    /// eased progress.
+    pub fn with_easing(mut self, easing: fn(f32) -> f32) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: TweenMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Advances the tween by `delta` seconds and returns the new value.
+    pub fn update(&mut self, delta: f64) -> T {
+        self.elapsed += delta as f32;
+
+        if self.mode != TweenMode::Once && self.duration > 0.0 {
+            let period = match self.mode {
+                TweenMode::PingPong => self.duration * 2.0,
+                _ => self.duration,
+            };
+
+            self.elapsed %= period;
+        }
+
+        self.value()
+    }
+
+    /// Advances the tween by the last frame time of `timing`.
+    pub fn tick(&mut self, timing: &Timing) -> T {
+        self.update(timing.get_frame_time())
+    }
+
+    /// The current value.
+    pub fn value(&self) -> T {
+        lerp(self.from, self.to, (self.easing)(self.progress()))
+    }
+
+    /// The progress through the tween in the range [0.0, 1.0], before easing.
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+
+        let progress = self.elapsed / self.duration;
+        match self.mode {
+            TweenMode::PingPong if progress > 1.0 => 2.0 - progress,
+            _ => progress.min(1.0),
+        }
+    }
+
+    /// Whether a [TweenMode::Once] tween reached its end, looping tweens never finish.
+    pub fn is_finished(&self) -> bool {
+        self.mode == TweenMode::Once && self.elapsed >= self.duration
+    }
+
+    /// Restarts the tween from its start value.
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+
+    /// Restarts the tween from its current value towards `to`, so an animation can change target
+    /// without jumping.
+    pub fn retarget(&mut self, to: T) {
+        self.from = self.value();
+        self.to = to;
+        self.elapsed = 0.0;
+    }
+}
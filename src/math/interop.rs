@@ -0,0 +1,177 @@
+//! Conversions between the crate's math types and the ones of [glam] and [mint].
+//!
+//! [mint] is also how other math libraries are reached: nalgebra (`convert-mint` feature),
+//! cgmath (`mint` feature) and most others can convert their types into mint's and back.
+//!
+//! [Matrix4] stores rows and multiplies column vectors, so the matrices keep the same meaning
+//! across conversions: `Matrix4 * v` and `glam::Mat4 * v` give the same result.
+
+use super::{Matrix4, Quat, Vector2, Vector2I, Vector3, Vector3I, Vector4};
+
+#[cfg(feature = "glam")]
+mod glam_conversions {
+    use super::*;
+
+    impl From<glam::Vec2> for Vector2 {
+        fn from(vector: glam::Vec2) -> Self {
+            Self::new(vector.x, vector.y)
+        }
+    }
+
+    impl From<Vector2> for glam::Vec2 {
+        fn from(vector: Vector2) -> Self {
+            Self::new(vector.x, vector.y)
+        }
+    }
+
+    impl From<glam::Vec3> for Vector3 {
+        fn from(vector: glam::Vec3) -> Self {
+            Self::new(vector.x, vector.y, vector.z)
+        }
+    }
+
+    impl From<Vector3> for glam::Vec3 {
+        fn from(vector: Vector3) -> Self {
+            Self::new(vector.x, vector.y, vector.z)
+        }
+    }
+
+    impl From<glam::Vec4> for Vector4 {
+        fn from(vector: glam::Vec4) -> Self {
+            Self::new(vector.x, vector.y, vector.z, vector.w)
+        }
+    }
+
+    impl From<Vector4> for glam::Vec4 {
+        fn from(vector: Vector4) -> Self {
+            Self::new(vector.x, vector.y, vector.z, vector.w)
+        }
+    }
+
+    impl From<glam::IVec2> for Vector2I {
+        fn from(vector: glam::IVec2) -> Self {
+            Self::new(vector.x, vector.y)
+        }
+    }
+
+    impl From<Vector2I> for glam::IVec2 {
+        fn from(vector: Vector2I) -> Self {
+            Self::new(vector.x, vector.y)
+        }
+    }
+
+    impl From<glam::IVec3> for Vector3I {
+        fn from(vector: glam::IVec3) -> Self {
+            Self::new(vector.x, vector.y, vector.z)
+        }
+    }
+
+    impl From<Vector3I> for glam::IVec3 {
+        fn from(vector: Vector3I) -> Self {
+            Self::new(vector.x, vector.y, vector.z)
+        }
+    }
+
+    impl From<glam::Quat> for Quat {
+        fn from(quat: glam::Quat) -> Self {
+            Self::new(quat.x, quat.y, quat.z, quat.w)
+        }
+    }
+
+    impl From<Quat> for glam::Quat {
+        fn from(quat: Quat) -> Self {
+            Self::from_xyzw(quat.x, quat.y, quat.z, quat.w)
+        }
+    }
+
+    /// glam stores columns, the columns of the transpose are the rows of the matrix.
+    impl From<glam::Mat4> for Matrix4 {
+        fn from(matrix: glam::Mat4) -> Self {
+            Self {
+                m: matrix.transpose().to_cols_array_2d(),
+            }
+        }
+    }
+
+    impl From<Matrix4> for glam::Mat4 {
+        fn from(matrix: Matrix4) -> Self {
+            Self::from_cols_array_2d(&matrix.m).transpose()
+        }
+    }
+}
+
+#[cfg(feature = "mint")]
+mod mint_conversions {
+    use super::*;
+
+    /// Implements [From] both ways between a vector of this crate and a foreign one with the same
+    /// fields.
+    macro_rules! impl_vector_conversions {
+        ($ours:ty, $theirs:ty, $($field:ident),+) => {
+            impl From<$theirs> for $ours {
+                fn from(vector: $theirs) -> Self {
+                    Self {
+                        $($field: vector.$field),+
+                    }
+                }
+            }
+
+            impl From<$ours> for $theirs {
+                fn from(vector: $ours) -> Self {
+                    Self {
+                        $($field: vector.$field),+
+                    }
+                }
+            }
+        };
+    }
+
+    impl_vector_conversions!(Vector2, mint::Vector2<f32>, x, y);
+    impl_vector_conversions!(Vector3, mint::Vector3<f32>, x, y, z);
+    impl_vector_conversions!(Vector4, mint::Vector4<f32>, x, y, z, w);
+    impl_vector_conversions!(Vector2I, mint::Vector2<i32>, x, y);
+    impl_vector_conversions!(Vector3I, mint::Vector3<i32>, x, y, z);
+
+    impl From<mint::Quaternion<f32>> for Quat {
+        fn from(quat: mint::Quaternion<f32>) -> Self {
+            Self::new(quat.v.x, quat.v.y, quat.v.z, quat.s)
+        }
+    }
+
+    impl From<Quat> for mint::Quaternion<f32> {
+        fn from(quat: Quat) -> Self {
+            Self {
+                v: mint::Vector3 {
+                    x: quat.x,
+                    y: quat.y,
+                    z: quat.z,
+                },
+                s: quat.w,
+            }
+        }
+    }
+
+    impl From<mint::RowMatrix4<f32>> for Matrix4 {
+        fn from(matrix: mint::RowMatrix4<f32>) -> Self {
+            Self { m: matrix.into() }
+        }
+    }
+
+    impl From<Matrix4> for mint::RowMatrix4<f32> {
+        fn from(matrix: Matrix4) -> Self {
+            matrix.m.into()
+        }
+    }
+
+    impl From<mint::ColumnMatrix4<f32>> for Matrix4 {
+        fn from(matrix: mint::ColumnMatrix4<f32>) -> Self {
+            Self::from(mint::RowMatrix4::from(matrix))
+        }
+    }
+
+    impl From<Matrix4> for mint::ColumnMatrix4<f32> {
+        fn from(matrix: Matrix4) -> Self {
+            mint::RowMatrix4::from(matrix).into()
+        }
+    }
+}
@@ -205,6 +205,18 @@ impl Matrix4 {
         result
     }
 
+    pub fn transpose(&self) -> Matrix4 {
+        let mut result = Self::new();
+
+        for i in 0..4 {
+            for j in 0..4 {
+                result.m[i][j] = self.m[j][i];
+            }
+        }
+
+        result
+    }
+
     pub unsafe fn address_of(&self) -> *const f32 {
         &self.m[0][0] as *const f32
     }
@@ -223,7 +235,12 @@ impl Matrix4 {
         (2.0 * self.m[3][2]) / (self.m[2][2] - nf)
     }
 
+    /// Inverse of the matrix, or the identity if it isn't invertible.
     pub fn inverse(&self) -> Matrix4 {
+        if cfg!(feature = "simd") {
+            return super::simd::inverse(self);
+        }
+
         let m = &self.m;
 
         let mut inv = [[0.0; 4]; 4];
@@ -363,6 +380,10 @@ impl Mul for Matrix4 {
     type Output = Self;
 
     fn mul(self, rhs: Self) -> Self {
+        if cfg!(feature = "simd") {
+            return super::simd::mul_matrix(&self, &rhs);
+        }
+
         let mut result = Self::new();
 
         for i in 0..4 {
@@ -444,6 +465,10 @@ impl Mul<Vector4> for Matrix4 {
     type Output = Vector4;
 
     fn mul(self, rhs: Vector4) -> Vector4 {
+        if cfg!(feature = "simd") {
+            return super::simd::mul_vector(&self, &rhs);
+        }
+
         let mut result = Vector4::new(0.0, 0.0, 0.0, 0.0);
 
         result.x = self.m[0][0] * rhs.x
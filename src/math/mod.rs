@@ -1,23 +1,36 @@
+mod camera;
 mod color;
+pub mod ease;
+#[cfg(any(feature = "glam", feature = "mint"))]
+mod interop;
 mod matrix;
 mod mvp;
+pub mod noise;
+mod obb;
 mod point;
 mod position;
+mod quat;
 mod rect;
+mod simd;
 mod size;
 mod timing;
+mod transform;
 mod utils;
 mod vector;
 mod vertex;
 
+pub use camera::{Camera3D, CameraUniform, FlyController, Frustum, OrbitController, Projection};
 pub use color::Color;
 pub use matrix::Matrix4;
 pub use mvp::ModelViewProjection;
+pub use obb::Obb2;
 pub use point::Point2;
 pub use position::Position;
+pub use quat::Quat;
 pub use rect::{Rect, RectF};
 pub use size::Size;
 pub use timing::Timing;
+pub use transform::{Transform, TransformHierarchy, TransformId};
 pub use utils::*;
 pub use vector::{Vector2, Vector2I, Vector3, Vector3I, Vector4};
 pub use vertex::Vertex;
@@ -0,0 +1,371 @@
+//! Gradient noise for procedural textures and terrain.
+//!
+//! [Noise] generates perlin and simplex noise from a seed, the same seed always gives the same
+//! noise. Values are roughly in the range [-1.0, 1.0] and the noise repeats every 256 units.
+//!
+//! # Example
+//! ```ignore
+//! let noise = Noise::new(42);
+//!
+//! let height = noise.fbm2(x * 0.01, z * 0.01, &Fbm::default());
+//! let clouds = noise.bake_texture(&mut gpu, Point2::new(512, 512), 8.0, &Fbm::default())?;
+//! ```
+
+use crate::gpu::{
+    GPU,
+    texture::{Texture, TextureError, TextureFormat, TextureUsage},
+};
+
+use super::Point2;
+
+/// Which noise [Fbm] sums.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoiseKind {
+    #[default]
+    Perlin,
+    /// Cheaper than perlin in 3D and without its grid-aligned artifacts.
+    Simplex,
+}
+
+/// Fractal brownian motion settings, summing octaves of noise at increasing frequencies and
+/// decreasing amplitudes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fbm {
+    pub kind: NoiseKind,
+    pub octaves: u32,
+    /// Frequency multiplier between octaves.
+    pub lacunarity: f32,
+    /// Amplitude multiplier between octaves.
+    pub gain: f32,
+}
+
+impl Default for Fbm {
+    fn default() -> Self {
+        Self {
+            kind: NoiseKind::Perlin,
+            octaves: 4,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+}
+
+/// Seeded perlin and simplex noise generator.
+#[derive(Clone)]
+pub struct Noise {
+    perm: [u8; 512],
+}
+
+impl std::fmt::Debug for Noise {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Noise").finish_non_exhaustive()
+    }
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+
+        // Fisher-Yates shuffle with splitmix64, kept local so the noise of a seed doesn't change
+        // with the version of a random crate.
+        let mut state = seed;
+        for i in (1..table.len()).rev() {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+
+            table.swap(i, (z % (i as u64 + 1)) as usize);
+        }
+
+        Self {
+            perm: std::array::from_fn(|i| table[i & 255]),
+        }
+    }
+
+    pub fn perlin2(&self, x: f32, y: f32) -> f32 {
+        let (xi, xf) = split(x);
+        let (yi, yf) = split(y);
+        let (u, v) = (fade(xf), fade(yf));
+
+        let a = self.hash(xi) + yi;
+        let b = self.hash(xi + 1) + yi;
+
+        let x0 = lerp(
+            grad2(self.hash(a), xf, yf),
+            grad2(self.hash(b), xf - 1.0, yf),
+            u,
+        );
+        let x1 = lerp(
+            grad2(self.hash(a + 1), xf, yf - 1.0),
+            grad2(self.hash(b + 1), xf - 1.0, yf - 1.0),
+            u,
+        );
+
+        lerp(x0, x1, v)
+    }
+
+    pub fn perlin3(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (xi, xf) = split(x);
+        let (yi, yf) = split(y);
+        let (zi, zf) = split(z);
+        let (u, v, w) = (fade(xf), fade(yf), fade(zf));
+
+        let a = self.hash(xi) + yi;
+        let aa = self.hash(a) + zi;
+        let ab = self.hash(a + 1) + zi;
+        let b = self.hash(xi + 1) + yi;
+        let ba = self.hash(b) + zi;
+        let bb = self.hash(b + 1) + zi;
+
+        let corner = |hash: usize, dx: f32, dy: f32, dz: f32| {
+            grad3(self.hash(hash), xf - dx, yf - dy, zf - dz)
+        };
+
+        lerp(
+            lerp(
+                lerp(corner(aa, 0.0, 0.0, 0.0), corner(ba, 1.0, 0.0, 0.0), u),
+                lerp(corner(ab, 0.0, 1.0, 0.0), corner(bb, 1.0, 1.0, 0.0), u),
+                v,
+            ),
+            lerp(
+                lerp(
+                    corner(aa + 1, 0.0, 0.0, 1.0),
+                    corner(ba + 1, 1.0, 0.0, 1.0),
+                    u,
+                ),
+                lerp(
+                    corner(ab + 1, 0.0, 1.0, 1.0),
+                    corner(bb + 1, 1.0, 1.0, 1.0),
+                    u,
+                ),
+                v,
+            ),
+            w,
+        )
+    }
+
+    pub fn simplex2(&self, x: f32, y: f32) -> f32 {
+        const F2: f32 = 0.366_025_42; // (sqrt(3) - 1) / 2
+        const G2: f32 = 0.211_324_87; // (3 - sqrt(3)) / 6
+
+        // Skew to the simplex grid to find the cell, then back to get the distance to its origin.
+        let s = (x + y) * F2;
+        let (i, j) = ((x + s).floor(), (y + s).floor());
+        let t = (i + j) * G2;
+        let (x0, y0) = (x - (i - t), y - (j - t));
+
+        // The cell is split in two triangles, find the middle corner of the one we're in.
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+
+        let corners = [
+            (x0, y0, 0, 0),
+            (x0 - i1 as f32 + G2, y0 - j1 as f32 + G2, i1, j1),
+            (x0 - 1.0 + 2.0 * G2, y0 - 1.0 + 2.0 * G2, 1, 1),
+        ];
+
+        let (i, j) = (wrap(i), wrap(j));
+        let total: f32 = corners
+            .iter()
+            .map(|&(dx, dy, oi, oj)| {
+                let t = 0.5 - dx * dx - dy * dy;
+                if t < 0.0 {
+                    return 0.0;
+                }
+
+                let hash = self.hash(i + oi + self.hash(j + oj));
+                t.powi(4) * grad2(hash, dx, dy)
+            })
+            .sum();
+
+        70.0 * total
+    }
+
+    pub fn simplex3(&self, x: f32, y: f32, z: f32) -> f32 {
+        const F3: f32 = 1.0 / 3.0;
+        const G3: f32 = 1.0 / 6.0;
+
+        let s = (x + y + z) * F3;
+        let (i, j, k) = ((x + s).floor(), (y + s).floor(), (z + s).floor());
+        let t = (i + j + k) * G3;
+        let (x0, y0, z0) = (x - (i - t), y - (j - t), z - (k - t));
+
+        // The cell is split in six tetrahedra, ordering the offsets finds the one we're in.
+        let ((i1, j1, k1), (i2, j2, k2)) = if x0 >= y0 {
+            if y0 >= z0 {
+                ((1, 0, 0), (1, 1, 0))
+            } else if x0 >= z0 {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else if y0 < z0 {
+            ((0, 0, 1), (0, 1, 1))
+        } else if x0 < z0 {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        };
+
+        let corner = |oi: usize, oj: usize, ok: usize, g: f32| {
+            (
+                x0 - oi as f32 + g,
+                y0 - oj as f32 + g,
+                z0 - ok as f32 + g,
+                oi,
+                oj,
+                ok,
+            )
+        };
+        let corners = [
+            corner(0, 0, 0, 0.0),
+            corner(i1, j1, k1, G3),
+            corner(i2, j2, k2, 2.0 * G3),
+            corner(1, 1, 1, 3.0 * G3),
+        ];
+
+        let (i, j, k) = (wrap(i), wrap(j), wrap(k));
+        let total: f32 = corners
+            .iter()
+            .map(|&(dx, dy, dz, oi, oj, ok)| {
+                let t = 0.6 - dx * dx - dy * dy - dz * dz;
+                if t < 0.0 {
+                    return 0.0;
+                }
+
+                let hash = self.hash(i + oi + self.hash(j + oj + self.hash(k + ok)));
+                t.powi(4) * grad3(hash, dx, dy, dz)
+            })
+            .sum();
+
+        32.0 * total
+    }
+
+    /// Sums octaves of 2D noise, normalized back to roughly [-1.0, 1.0].
+    pub fn fbm2(&self, x: f32, y: f32, fbm: &Fbm) -> f32 {
+        self.fbm(fbm, |frequency| match fbm.kind {
+            NoiseKind::Perlin => self.perlin2(x * frequency, y * frequency),
+            NoiseKind::Simplex => self.simplex2(x * frequency, y * frequency),
+        })
+    }
+
+    /// Sums octaves of 3D noise, normalized back to roughly [-1.0, 1.0].
+    pub fn fbm3(&self, x: f32, y: f32, z: f32, fbm: &Fbm) -> f32 {
+        self.fbm(fbm, |frequency| match fbm.kind {
+            NoiseKind::Perlin => self.perlin3(x * frequency, y * frequency, z * frequency),
+            NoiseKind::Simplex => self.simplex3(x * frequency, y * frequency, z * frequency),
+        })
+    }
+
+    /// Samples [Noise::fbm2] over an image of `size` pixels, mapped to [0.0, 1.0] in row order.
+    ///
+    /// `frequency` is the number of noise units across the width, the height uses the same
+    /// scale so features stay square.
+    pub fn bake(&self, size: Point2, frequency: f32, fbm: &Fbm) -> Vec<f32> {
+        let (width, height) = (size.x.max(0) as usize, size.y.max(0) as usize);
+        let scale = frequency / width.max(1) as f32;
+
+        (0..width * height)
+            .map(|index| {
+                let x = (index % width) as f32 + 0.5;
+                let y = (index / width) as f32 + 0.5;
+
+                (self.fbm2(x * scale, y * scale, fbm) * 0.5 + 0.5).clamp(0.0, 1.0)
+            })
+            .collect()
+    }
+
+    /// Bakes [Noise::bake] into a grayscale [TextureFormat::Rgba8Unorm] texture that can be
+    /// sampled or drawn.
+    pub fn bake_texture(
+        &self,
+        gpu: &mut GPU,
+        size: Point2,
+        frequency: f32,
+        fbm: &Fbm,
+    ) -> Result<Texture, TextureError> {
+        let pixels: Vec<u8> = self
+            .bake(size, frequency, fbm)
+            .into_iter()
+            .flat_map(|value| {
+                let value = (value * 255.0).round() as u8;
+                [value, value, value, 255]
+            })
+            .collect();
+
+        gpu.create_texture()
+            .set_raw_image(&pixels, size, TextureFormat::Rgba8Unorm)
+            .set_usage(TextureUsage::Sampler)
+            .set_label("Noise texture")
+            .build()
+    }
+
+    fn fbm(&self, fbm: &Fbm, sample: impl Fn(f32) -> f32) -> f32 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut max = 0.0;
+
+        for _ in 0..fbm.octaves.max(1) {
+            total += sample(frequency) * amplitude;
+            max += amplitude;
+
+            amplitude *= fbm.gain;
+            frequency *= fbm.lacunarity;
+        }
+
+        total / max
+    }
+
+    fn hash(&self, index: usize) -> usize {
+        self.perm[index & 511] as usize
+    }
+}
+
+/// Integer cell, wrapped to the permutation table, and position inside it.
+fn split(value: f32) -> (usize, f32) {
+    let floor = value.floor();
+    (wrap(floor), value - floor)
+}
+
+fn wrap(value: f32) -> usize {
+    (value as i64 & 255) as usize
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Dot product with one of 8 gradients, the axes and diagonals.
+fn grad2(hash: usize, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+/// Dot product with one of the 12 cube edge gradients of improved perlin noise.
+fn grad3(hash: usize, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
@@ -0,0 +1,101 @@
+use super::{RectF, Vector2};
+
+/// A rotated rectangle (oriented bounding box) in 2D.
+///
+/// The angle is in degrees and turns the same way as
+/// [DrawingContext::set_rotation](crate::gpu::command::drawing::DrawingContext::set_rotation),
+/// so a box drawn with
+/// [DrawingContext::rect_rotated](crate::gpu::command::drawing::DrawingContext::rect_rotated)
+/// can be hit-tested with the same values.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Obb2 {
+    pub center: Vector2,
+    pub half_extents: Vector2,
+    pub angle: f32,
+}
+
+impl Obb2 {
+    pub fn new(center: Vector2, size: Vector2, angle: f32) -> Self {
+        Self {
+            center,
+            half_extents: size * 0.5,
+            angle,
+        }
+    }
+
+    /// Creates a box from a rectangle rotated around its center.
+    pub fn from_rect(rect: RectF, angle: f32) -> Self {
+        Self::new(
+            Vector2::new(rect.x + rect.w * 0.5, rect.y + rect.h * 0.5),
+            Vector2::new(rect.w, rect.h),
+            angle,
+        )
+    }
+
+    pub fn size(&self) -> Vector2 {
+        self.half_extents * 2.0
+    }
+
+    /// Unit vectors along the local x and y axes of the box.
+    pub fn axes(&self) -> (Vector2, Vector2) {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+
+        (Vector2::new(cos, sin), Vector2::new(-sin, cos))
+    }
+
+    /// Corners in top-left, top-right, bottom-right, bottom-left order (before rotation).
+    pub fn corners(&self) -> [Vector2; 4] {
+        let (axis_x, axis_y) = self.axes();
+        let x = axis_x * self.half_extents.x;
+        let y = axis_y * self.half_extents.y;
+
+        [
+            self.center - x - y,
+            self.center + x - y,
+            self.center + x + y,
+            self.center - x + y,
+        ]
+    }
+
+    /// Smallest axis-aligned rectangle containing the box.
+    pub fn bounding_rect(&self) -> RectF {
+        let corners = self.corners();
+
+        let min = corners.iter().fold(corners[0], |acc, c| acc.min(c));
+        let max = corners.iter().fold(corners[0], |acc, c| acc.max(c));
+
+        RectF::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    pub fn contains(&self, point: Vector2) -> bool {
+        let (axis_x, axis_y) = self.axes();
+        let offset = point - self.center;
+
+        offset.dot(&axis_x).abs() <= self.half_extents.x
+            && offset.dot(&axis_y).abs() <= self.half_extents.y
+    }
+
+    /// Tests whether the boxes overlap, using the separating axis theorem.
+    pub fn intersects(&self, other: &Obb2) -> bool {
+        let (a_x, a_y) = self.axes();
+        let (b_x, b_y) = other.axes();
+        let offset = other.center - self.center;
+
+        [a_x, a_y, b_x, b_y].iter().all(|axis| {
+            let distance = offset.dot(axis).abs();
+
+            distance <= self.projected_radius(axis) + other.projected_radius(axis)
+        })
+    }
+
+    pub fn intersects_rect(&self, rect: &RectF) -> bool {
+        self.intersects(&Obb2::from_rect(*rect, 0.0))
+    }
+
+    /// Half length of the box projected on `axis`.
+    fn projected_radius(&self, axis: &Vector2) -> f32 {
+        let (axis_x, axis_y) = self.axes();
+
+        self.half_extents.x * axis_x.dot(axis).abs() + self.half_extents.y * axis_y.dot(axis).abs()
+    }
+}
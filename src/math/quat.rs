@@ -0,0 +1,197 @@
+use std::ops::Mul;
+
+use bytemuck::{Pod, Zeroable};
+
+use super::{Matrix4, Vector3};
+
+/// A rotation stored as a unit quaternion.
+///
+/// Angles are in degrees, like the rest of the crate's rotation helpers, and rotations follow the
+/// right-hand rule: a positive angle around an axis pointing at the viewer turns
+/// counter-clockwise.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Quat {
+    pub const IDENTITY: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+        w: 1.0,
+    };
+
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Self { x, y, z, w }
+    }
+
+    /// Rotation of `angle` degrees around `axis`, which doesn't need to be normalized.
+    pub fn from_axis_angle(axis: Vector3, angle: f32) -> Self {
+        let length = axis.length();
+        if length <= f32::EPSILON {
+            return Self::IDENTITY;
+        }
+
+        let axis = axis / length;
+        let (sin, cos) = (angle.to_radians() * 0.5).sin_cos();
+
+        Self::new(axis.x * sin, axis.y * sin, axis.z * sin, cos)
+    }
+
+    /// Rotation from euler angles in degrees, applied as roll around Z, then pitch around X, then
+    /// yaw around Y.
+    pub fn from_euler(pitch: f32, yaw: f32, roll: f32) -> Self {
+        Self::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), yaw)
+            * Self::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), pitch)
+            * Self::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), roll)
+    }
+
+    /// Euler angles in degrees as `(pitch, yaw, roll)`, the inverse of [Quat::from_euler].
+    pub fn to_euler(&self) -> (f32, f32, f32) {
+        let Self { x, y, z, w } = *self;
+
+        let sin_pitch = (2.0 * (w * x - y * z)).clamp(-1.0, 1.0);
+        let pitch = sin_pitch.asin();
+
+        let (yaw, roll) = if sin_pitch.abs() > 0.9999 {
+            // Gimbal lock, the roll is folded into the yaw.
+            (
+                (2.0 * (w * y - x * z)).atan2(1.0 - 2.0 * (y * y + z * z)),
+                0.0,
+            )
+        } else {
+            (
+                (2.0 * (x * z + w * y)).atan2(1.0 - 2.0 * (x * x + y * y)),
+                (2.0 * (x * y + w * z)).atan2(1.0 - 2.0 * (x * x + z * z)),
+            )
+        };
+
+        (pitch.to_degrees(), yaw.to_degrees(), roll.to_degrees())
+    }
+
+    pub fn dot(&self, other: &Self) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Self {
+        let length = self.length();
+        if length <= f32::EPSILON {
+            return Self::IDENTITY;
+        }
+
+        Self::new(
+            self.x / length,
+            self.y / length,
+            self.z / length,
+            self.w / length,
+        )
+    }
+
+    /// The opposite rotation, assuming the quaternion is normalized.
+    pub fn inverse(&self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    /// Interpolates along the shortest arc between two rotations, `t` goes from 0 to 1.
+    pub fn slerp(&self, other: &Self, t: f32) -> Self {
+        let mut other = *other;
+        let mut cos = self.dot(&other);
+
+        // q and -q are the same rotation, going through the closest one takes the shortest arc.
+        if cos < 0.0 {
+            other = Self::new(-other.x, -other.y, -other.z, -other.w);
+            cos = -cos;
+        }
+
+        let (a, b) = if cos > 0.9995 {
+            // Nearly identical rotations, a linear interpolation avoids dividing by ~0.
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+
+        Self::new(
+            self.x * a + other.x * b,
+            self.y * a + other.y * b,
+            self.z * a + other.z * b,
+            self.w * a + other.w * b,
+        )
+        .normalize()
+    }
+
+    pub fn rotate_vector(&self, vector: Vector3) -> Vector3 {
+        let q = Vector3::new(self.x, self.y, self.z);
+        let t = q.cross(&vector) * 2.0;
+
+        vector + t * self.w + q.cross(&t)
+    }
+
+    /// Rotation matrix, in the row-major convention of [Matrix4].
+    pub fn to_matrix(&self) -> Matrix4 {
+        let Self { x, y, z, w } = *self;
+
+        Matrix4 {
+            m: [
+                [
+                    1.0 - 2.0 * (y * y + z * z),
+                    2.0 * (x * y - w * z),
+                    2.0 * (x * z + w * y),
+                    0.0,
+                ],
+                [
+                    2.0 * (x * y + w * z),
+                    1.0 - 2.0 * (x * x + z * z),
+                    2.0 * (y * z - w * x),
+                    0.0,
+                ],
+                [
+                    2.0 * (x * z - w * y),
+                    2.0 * (y * z + w * x),
+                    1.0 - 2.0 * (x * x + y * y),
+                    0.0,
+                ],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+}
+
+/// Combines two rotations, `a * b` applies `b` first.
+impl Mul for Quat {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        )
+    }
+}
+
+impl Mul<Vector3> for Quat {
+    type Output = Vector3;
+
+    fn mul(self, rhs: Vector3) -> Vector3 {
+        self.rotate_vector(rhs)
+    }
+}
@@ -99,6 +99,21 @@ impl RectF {
     pub fn is_empty(&self) -> bool {
         self.w <= 0.0 || self.h <= 0.0
     }
+
+    /// Returns the overlapping area of both rectangles, or an empty rectangle if they don't overlap.
+    pub fn intersect(&self, other: &RectF) -> RectF {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = (self.x + self.w).min(other.x + other.w);
+        let y1 = (self.y + self.h).min(other.y + other.h);
+
+        RectF {
+            x: x0,
+            y: y0,
+            w: (x1 - x0).max(0.0),
+            h: (y1 - y0).max(0.0),
+        }
+    }
 }
 
 impl PartialEq for RectF {
@@ -0,0 +1,256 @@
+//! 4-lane `f32` vectors backing the `simd` feature, using SSE on x86_64, NEON on aarch64 and
+//! plain arrays elsewhere.
+//!
+//! Both instruction sets are part of the baseline of their targets, so no runtime detection is
+//! needed. The [Matrix4] rows map to one vector each, which lets the row-major products and row
+//! operations run four lanes at a time.
+
+use super::{Matrix4, Vector4};
+
+#[cfg(target_arch = "x86_64")]
+mod backend {
+    use std::arch::x86_64::*;
+
+    #[derive(Clone, Copy)]
+    pub struct F32x4(__m128);
+
+    impl F32x4 {
+        #[inline(always)]
+        pub fn load(values: &[f32; 4]) -> Self {
+            // SAFETY: `values` is valid for reading 4 floats, the load is unaligned.
+            Self(unsafe { _mm_loadu_ps(values.as_ptr()) })
+        }
+
+        #[inline(always)]
+        pub fn store(self) -> [f32; 4] {
+            let mut result = [0.0; 4];
+            // SAFETY: `result` is valid for writing 4 floats, the store is unaligned.
+            unsafe { _mm_storeu_ps(result.as_mut_ptr(), self.0) };
+            result
+        }
+
+        #[inline(always)]
+        pub fn splat(value: f32) -> Self {
+            // SAFETY: SSE is part of the x86_64 baseline, same for the operations below.
+            Self(unsafe { _mm_set1_ps(value) })
+        }
+
+        #[inline(always)]
+        pub fn add(self, other: Self) -> Self {
+            Self(unsafe { _mm_add_ps(self.0, other.0) })
+        }
+
+        #[inline(always)]
+        pub fn sub(self, other: Self) -> Self {
+            Self(unsafe { _mm_sub_ps(self.0, other.0) })
+        }
+
+        #[inline(always)]
+        pub fn mul(self, other: Self) -> Self {
+            Self(unsafe { _mm_mul_ps(self.0, other.0) })
+        }
+
+        #[inline(always)]
+        pub fn sum(self) -> f32 {
+            // (x + y, x + y, z + w, z + w), then the high pair is added to the low one.
+            unsafe {
+                let swapped = _mm_shuffle_ps::<0b10_11_00_01>(self.0, self.0);
+                let pairs = _mm_add_ps(self.0, swapped);
+                let high = _mm_movehl_ps(pairs, pairs);
+
+                _mm_cvtss_f32(_mm_add_ss(pairs, high))
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod backend {
+    use std::arch::aarch64::*;
+
+    #[derive(Clone, Copy)]
+    pub struct F32x4(float32x4_t);
+
+    impl F32x4 {
+        #[inline(always)]
+        pub fn load(values: &[f32; 4]) -> Self {
+            // SAFETY: `values` is valid for reading 4 floats.
+            Self(unsafe { vld1q_f32(values.as_ptr()) })
+        }
+
+        #[inline(always)]
+        pub fn store(self) -> [f32; 4] {
+            let mut result = [0.0; 4];
+            // SAFETY: `result` is valid for writing 4 floats.
+            unsafe { vst1q_f32(result.as_mut_ptr(), self.0) };
+            result
+        }
+
+        #[inline(always)]
+        pub fn splat(value: f32) -> Self {
+            // SAFETY: NEON is part of the aarch64 baseline, same for the operations below.
+            Self(unsafe { vdupq_n_f32(value) })
+        }
+
+        #[inline(always)]
+        pub fn add(self, other: Self) -> Self {
+            Self(unsafe { vaddq_f32(self.0, other.0) })
+        }
+
+        #[inline(always)]
+        pub fn sub(self, other: Self) -> Self {
+            Self(unsafe { vsubq_f32(self.0, other.0) })
+        }
+
+        #[inline(always)]
+        pub fn mul(self, other: Self) -> Self {
+            Self(unsafe { vmulq_f32(self.0, other.0) })
+        }
+
+        #[inline(always)]
+        pub fn sum(self) -> f32 {
+            unsafe { vaddvq_f32(self.0) }
+        }
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod backend {
+    #[derive(Clone, Copy)]
+    pub struct F32x4([f32; 4]);
+
+    impl F32x4 {
+        #[inline(always)]
+        pub fn load(values: &[f32; 4]) -> Self {
+            Self(*values)
+        }
+
+        #[inline(always)]
+        pub fn store(self) -> [f32; 4] {
+            self.0
+        }
+
+        #[inline(always)]
+        pub fn splat(value: f32) -> Self {
+            Self([value; 4])
+        }
+
+        #[inline(always)]
+        pub fn add(self, other: Self) -> Self {
+            Self(std::array::from_fn(|i| self.0[i] + other.0[i]))
+        }
+
+        #[inline(always)]
+        pub fn sub(self, other: Self) -> Self {
+            Self(std::array::from_fn(|i| self.0[i] - other.0[i]))
+        }
+
+        #[inline(always)]
+        pub fn mul(self, other: Self) -> Self {
+            Self(std::array::from_fn(|i| self.0[i] * other.0[i]))
+        }
+
+        #[inline(always)]
+        pub fn sum(self) -> f32 {
+            self.0[0] + self.0[1] + self.0[2] + self.0[3]
+        }
+    }
+}
+
+use backend::F32x4;
+
+#[inline(always)]
+fn load_rows(matrix: &Matrix4) -> [F32x4; 4] {
+    matrix.m.each_ref().map(F32x4::load)
+}
+
+#[inline(always)]
+fn store_rows(rows: [F32x4; 4]) -> Matrix4 {
+    Matrix4 {
+        m: rows.map(F32x4::store),
+    }
+}
+
+/// `a * b`, each row of the result is a combination of the rows of `b`.
+pub(crate) fn mul_matrix(a: &Matrix4, b: &Matrix4) -> Matrix4 {
+    let [b0, b1, b2, b3] = load_rows(b);
+    let mut result = Matrix4::new();
+
+    for (row, out) in a.m.iter().zip(result.m.iter_mut()) {
+        *out = b0
+            .mul(F32x4::splat(row[0]))
+            .add(b1.mul(F32x4::splat(row[1])))
+            .add(b2.mul(F32x4::splat(row[2])))
+            .add(b3.mul(F32x4::splat(row[3])))
+            .store();
+    }
+
+    result
+}
+
+pub(crate) fn mul_vector(matrix: &Matrix4, vector: &Vector4) -> Vector4 {
+    let vector = F32x4::load(&[vector.x, vector.y, vector.z, vector.w]);
+    let [x, y, z, w] = matrix
+        .m
+        .each_ref()
+        .map(|row| F32x4::load(row).mul(vector).sum());
+
+    Vector4 { x, y, z, w }
+}
+
+/// Inverse from the 2x2 sub-determinants of the upper and lower halves, returns the identity for
+/// singular matrices like the scalar implementation.
+pub(crate) fn inverse(matrix: &Matrix4) -> Matrix4 {
+    let m = &matrix.m;
+    let v = |a: f32, b: f32, c: f32, d: f32| F32x4::load(&[a, b, c, d]);
+
+    // s0..s5 come from rows 0 and 1, c0..c5 from rows 2 and 3.
+    let [s0, s1, s2, s3] = v(m[0][0], m[0][0], m[0][0], m[0][1])
+        .mul(v(m[1][1], m[1][2], m[1][3], m[1][2]))
+        .sub(v(m[1][0], m[1][0], m[1][0], m[1][1]).mul(v(m[0][1], m[0][2], m[0][3], m[0][2])))
+        .store();
+    let [s4, s5, c5, c4] = v(m[0][1], m[0][2], m[2][2], m[2][1])
+        .mul(v(m[1][3], m[1][3], m[3][3], m[3][3]))
+        .sub(v(m[1][1], m[1][2], m[3][2], m[3][1]).mul(v(m[0][3], m[0][3], m[2][3], m[2][3])))
+        .store();
+    let [c3, c2, c1, c0] = v(m[2][1], m[2][0], m[2][0], m[2][0])
+        .mul(v(m[3][2], m[3][3], m[3][2], m[3][1]))
+        .sub(v(m[3][1], m[3][0], m[3][0], m[3][0]).mul(v(m[2][2], m[2][3], m[2][2], m[2][1])))
+        .store();
+
+    let det = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+    if det == 0.0 {
+        return Matrix4::identity();
+    }
+
+    // Each row of the adjugate combines three signed columns with pairs of sub-determinants.
+    let column = |j: usize| v(m[1][j], -m[0][j], m[3][j], -m[2][j]);
+    let pair = |c: f32, s: f32| v(c, c, s, s);
+    let [k0, k1, k2, k3, k4, k5] =
+        [(c0, s0), (c1, s1), (c2, s2), (c3, s3), (c4, s4), (c5, s5)].map(|(c, s)| pair(c, s));
+    let [a0, a1, a2, a3] = [0, 1, 2, 3].map(column);
+
+    let scale = F32x4::splat(1.0 / det);
+    let rows = [
+        a1.mul(k5).sub(a2.mul(k4)).add(a3.mul(k3)),
+        a2.mul(k2).sub(a0.mul(k5)).sub(a3.mul(k1)),
+        a0.mul(k4).sub(a1.mul(k2)).add(a3.mul(k0)),
+        a1.mul(k1).sub(a0.mul(k3)).sub(a2.mul(k0)),
+    ];
+
+    store_rows(rows.map(|row| row.mul(scale)))
+}
+
+pub(crate) fn dot(a: &Vector4, b: &Vector4) -> f32 {
+    F32x4::load(&[a.x, a.y, a.z, a.w])
+        .mul(F32x4::load(&[b.x, b.y, b.z, b.w]))
+        .sum()
+}
+
+pub(crate) fn normalize(vector: &Vector4) -> Vector4 {
+    let values = F32x4::load(&[vector.x, vector.y, vector.z, vector.w]);
+    let length = values.mul(values).sum().sqrt();
+    let [x, y, z, w] = values.mul(F32x4::splat(1.0 / length)).store();
+
+    Vector4 { x, y, z, w }
+}
@@ -1,7 +1,12 @@
-use std::{
-    thread,
-    time::{Duration, Instant},
-};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{thread, time::Duration};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+// The clock of std is not available in the browser
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 
 // Based on: https://stackoverflow.com/a/33554241
 // Totally obscure and not very well explained, but it works.
@@ -27,6 +32,8 @@ impl Timing {
         }
     }
 
+    /// Sleeps until the next frame is due, on the web use [Timing::is_frame_due].
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn sleep(&mut self) {
         if self.fps > 60 {
             let freq = 1_000_000_000; // Nanoseconds per second
@@ -72,6 +79,21 @@ impl Timing {
         }
     }
 
+    /// Returns true when the next frame is due, for the browser where the thread can't sleep
+    /// and frames not due yet are skipped instead.
+    #[cfg(target_arch = "wasm32")]
+    pub fn is_frame_due(&mut self) -> bool {
+        let delta_in_seconds = self.last_time.elapsed().as_secs_f64();
+        if self.fps > 0 && delta_in_seconds < 1.0 / self.fps as f64 {
+            return false;
+        }
+
+        self.last_time = Instant::now();
+        self.frame_time = delta_in_seconds;
+
+        true
+    }
+
     pub fn get_fps(&self) -> u32 {
         (1.0 / self.frame_time) as u32
     }
@@ -0,0 +1,380 @@
+use std::ops::Mul;
+
+use super::{Matrix4, Quat, Vector3};
+
+/// Translation, rotation and scale of an object, applied in scale, rotation, translation order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3,
+    pub rotation: Quat,
+    pub scale: Vector3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vector3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        rotation: Quat::IDENTITY,
+        scale: Vector3 {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        },
+    };
+
+    pub fn new(translation: Vector3, rotation: Quat, scale: Vector3) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn from_translation(translation: Vector3) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vector3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Matrix of the transform, in the row-major convention of [Matrix4].
+    pub fn to_matrix(&self) -> Matrix4 {
+        let mut matrix = self.rotation.to_matrix();
+
+        for row in matrix.m.iter_mut().take(3) {
+            row[0] *= self.scale.x;
+            row[1] *= self.scale.y;
+            row[2] *= self.scale.z;
+        }
+
+        matrix.m[0][3] = self.translation.x;
+        matrix.m[1][3] = self.translation.y;
+        matrix.m[2][3] = self.translation.z;
+
+        matrix
+    }
+
+    pub fn transform_point(&self, point: Vector3) -> Vector3 {
+        self.transform_vector(point) + self.translation
+    }
+
+    /// Scales and rotates a direction, without translating it.
+    pub fn transform_vector(&self, vector: Vector3) -> Vector3 {
+        self.rotation * scale(vector, self.scale)
+    }
+
+    /// The transform undoing this one.
+    ///
+    /// Exact for uniform scales, a rotated non-uniform scale can't be represented by a
+    /// [Transform] and is approximated.
+    pub fn inverse(&self) -> Self {
+        let inverse_scale = Vector3::new(
+            reciprocal(self.scale.x),
+            reciprocal(self.scale.y),
+            reciprocal(self.scale.z),
+        );
+        let rotation = self.rotation.inverse();
+
+        Self {
+            translation: scale(rotation * (self.translation * -1.0), inverse_scale),
+            rotation,
+            scale: inverse_scale,
+        }
+    }
+}
+
+/// Combines two transforms, `parent * child` places `child` in the space of `parent`.
+///
+/// Like [Transform::inverse] this is exact for uniform scales, use the matrices of
+/// [Transform::to_matrix] when the parent has a non-uniform scale and a rotated child.
+impl Mul for Transform {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            translation: self.transform_point(rhs.translation),
+            rotation: (self.rotation * rhs.rotation).normalize(),
+            scale: scale(self.scale, rhs.scale),
+        }
+    }
+}
+
+fn scale(vector: Vector3, scale: Vector3) -> Vector3 {
+    Vector3::new(vector.x * scale.x, vector.y * scale.y, vector.z * scale.z)
+}
+
+fn reciprocal(value: f32) -> f32 {
+    if value.abs() > f32::EPSILON {
+        1.0 / value
+    } else {
+        0.0
+    }
+}
+
+/// Handle of a node in a [TransformHierarchy].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransformId {
+    index: u32,
+    generation: u32,
+}
+
+#[derive(Debug, Clone)]
+struct TransformNode {
+    generation: u32,
+    alive: bool,
+    local: Transform,
+    parent: Option<u32>,
+    children: Vec<u32>,
+    world: Matrix4,
+    dirty: bool,
+}
+
+/// A tree of [Transform], computing the world matrix of each node from its parents.
+///
+/// World matrices are cached and only recomputed for the nodes whose transform, or a parent's
+/// transform, changed since they were last read.
+///
+/// # Example
+/// ```ignore
+/// let mut hierarchy = TransformHierarchy::new();
+///
+/// let body = hierarchy.add(Transform::from_translation(Vector3::new(0.0, 1.0, 0.0)), None);
+/// let arm = hierarchy.add(Transform::from_translation(Vector3::new(0.5, 0.0, 0.0)), Some(body));
+///
+/// let arm_world = hierarchy.world_matrix(arm).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TransformHierarchy {
+    nodes: Vec<TransformNode>,
+    free: Vec<u32>,
+}
+
+impl TransformHierarchy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of nodes in the hierarchy.
+    pub fn len(&self) -> usize {
+        self.nodes.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, id: TransformId) -> bool {
+        self.node(id).is_some()
+    }
+
+    /// Adds a node, as a root when `parent` is [None] or no longer exists.
+    pub fn add(&mut self, local: Transform, parent: Option<TransformId>) -> TransformId {
+        let parent = parent.filter(|&id| self.contains(id)).map(|id| id.index);
+
+        let index = match self.free.pop() {
+            Some(index) => {
+                let node = &mut self.nodes[index as usize];
+                node.generation += 1;
+                node.alive = true;
+                node.local = local;
+                node.parent = parent;
+                node.dirty = true;
+
+                index
+            }
+            None => {
+                self.nodes.push(TransformNode {
+                    generation: 0,
+                    alive: true,
+                    local,
+                    parent,
+                    children: Vec::new(),
+                    world: Matrix4::identity(),
+                    dirty: true,
+                });
+
+                self.nodes.len() as u32 - 1
+            }
+        };
+
+        if let Some(parent) = parent {
+            self.nodes[parent as usize].children.push(index);
+        }
+
+        TransformId {
+            index,
+            generation: self.nodes[index as usize].generation,
+        }
+    }
+
+    /// Removes a node and all its descendants.
+    pub fn remove(&mut self, id: TransformId) {
+        if !self.contains(id) {
+            return;
+        }
+
+        if let Some(parent) = self.nodes[id.index as usize].parent {
+            self.nodes[parent as usize]
+                .children
+                .retain(|&child| child != id.index);
+        }
+
+        let mut stack = vec![id.index];
+        while let Some(index) = stack.pop() {
+            let node = &mut self.nodes[index as usize];
+            node.alive = false;
+            node.parent = None;
+            stack.append(&mut node.children);
+
+            self.free.push(index);
+        }
+    }
+
+    pub fn local(&self, id: TransformId) -> Option<Transform> {
+        self.node(id).map(|node| node.local)
+    }
+
+    pub fn set_local(&mut self, id: TransformId, local: Transform) {
+        if self.contains(id) {
+            self.nodes[id.index as usize].local = local;
+            self.mark_dirty(id.index);
+        }
+    }
+
+    pub fn parent(&self, id: TransformId) -> Option<TransformId> {
+        self.node(id)
+            .and_then(|node| node.parent)
+            .map(|index| self.id_of(index))
+    }
+
+    pub fn children(&self, id: TransformId) -> Vec<TransformId> {
+        self.node(id)
+            .map(|node| {
+                node.children
+                    .iter()
+                    .map(|&index| self.id_of(index))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Moves a node under another parent, or to the roots with [None].
+    ///
+    /// The local transform is kept, so the node moves along with its new parent. Fails if a node
+    /// doesn't exist or if `parent` is the node itself or one of its descendants.
+    pub fn set_parent(
+        &mut self,
+        id: TransformId,
+        parent: Option<TransformId>,
+    ) -> Result<(), String> {
+        if !self.contains(id) {
+            return Err("Node doesn't exist".to_string());
+        }
+
+        if let Some(parent) = parent {
+            if !self.contains(parent) {
+                return Err("Parent node doesn't exist".to_string());
+            }
+
+            let mut ancestor = Some(parent.index);
+            while let Some(index) = ancestor {
+                if index == id.index {
+                    return Err("A node can't be parented to itself or its descendants".to_string());
+                }
+
+                ancestor = self.nodes[index as usize].parent;
+            }
+        }
+
+        if let Some(old_parent) = self.nodes[id.index as usize].parent {
+            self.nodes[old_parent as usize]
+                .children
+                .retain(|&child| child != id.index);
+        }
+
+        let parent = parent.map(|parent| parent.index);
+        if let Some(parent) = parent {
+            self.nodes[parent as usize].children.push(id.index);
+        }
+
+        self.nodes[id.index as usize].parent = parent;
+        self.mark_dirty(id.index);
+
+        Ok(())
+    }
+
+    /// Matrix placing the node in world space, [None] if it doesn't exist.
+    pub fn world_matrix(&mut self, id: TransformId) -> Option<Matrix4> {
+        if !self.contains(id) {
+            return None;
+        }
+
+        Some(self.compute_world(id.index))
+    }
+
+    pub fn world_position(&mut self, id: TransformId) -> Option<Vector3> {
+        self.world_matrix(id)
+            .map(|matrix| Vector3::new(matrix.m[0][3], matrix.m[1][3], matrix.m[2][3]))
+    }
+
+    fn compute_world(&mut self, index: u32) -> Matrix4 {
+        let node = &self.nodes[index as usize];
+        if !node.dirty {
+            return node.world;
+        }
+
+        let local = node.local.to_matrix();
+        let world = match node.parent {
+            Some(parent) => self.compute_world(parent) * local,
+            None => local,
+        };
+
+        let node = &mut self.nodes[index as usize];
+        node.world = world;
+        node.dirty = false;
+
+        world
+    }
+
+    fn mark_dirty(&mut self, index: u32) {
+        let mut stack = vec![index];
+
+        while let Some(index) = stack.pop() {
+            let node = &mut self.nodes[index as usize];
+            node.dirty = true;
+            stack.extend_from_slice(&node.children);
+        }
+    }
+
+    fn node(&self, id: TransformId) -> Option<&TransformNode> {
+        self.nodes
+            .get(id.index as usize)
+            .filter(|node| node.alive && node.generation == id.generation)
+    }
+
+    fn id_of(&self, index: u32) -> TransformId {
+        TransformId {
+            index,
+            generation: self.nodes[index as usize].generation,
+        }
+    }
+}
@@ -1,10 +1,10 @@
 use super::Color;
 
+/// Encodes the color channels to sRGB, alpha is stored linearly and left as is.
 pub fn rgb_to_srgb(color: &mut Color) {
     color.r = linear_to_srgb(color.r);
     color.g = linear_to_srgb(color.g);
     color.b = linear_to_srgb(color.b);
-    color.a = linear_to_srgb(color.a);
 }
 
 fn linear_to_srgb(value: f32) -> f32 {
@@ -15,11 +15,11 @@ fn linear_to_srgb(value: f32) -> f32 {
     }
 }
 
+/// Decodes the color channels from sRGB, alpha is stored linearly and left as is.
 pub fn srgb_to_rgb(color: &mut Color) {
     color.r = srgb_to_linear(color.r);
     color.g = srgb_to_linear(color.g);
     color.b = srgb_to_linear(color.b);
-    color.a = srgb_to_linear(color.a);
 }
 
 fn srgb_to_linear(value: f32) -> f32 {
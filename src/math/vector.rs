@@ -503,6 +503,10 @@ impl Vector4 {
     }
 
     pub fn normalize(&self) -> Self {
+        if cfg!(feature = "simd") {
+            return super::simd::normalize(self);
+        }
+
         let length = self.length();
         Self {
             x: self.x / length,
@@ -513,6 +517,10 @@ impl Vector4 {
     }
 
     pub fn dot(&self, other: &Self) -> f32 {
+        if cfg!(feature = "simd") {
+            return super::simd::dot(self, other);
+        }
+
         self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
     }
 
@@ -2,27 +2,56 @@ pub use super::runner::{
     Runner,
     Event,
     PumpMode,
+    Application,
+    RunnerError,
 };
 
 pub use super::gpu::{
     GPU,
     GPUBuilder,
+    GpuBuildError,
+    GPUFeatures,
     GPUAdapter,
     GPUWaitType,
     AdapterBackend,
+    PresentMode,
+    SurfaceAlphaMode,
+    SurfaceCaps,
+    SurfaceFormatPreference,
 
     command::{
         CommandBuffer,
+        FinishedCommand,
+        copy::{
+            BufferTextureLayout,
+            TextureRegion,
+            COPY_ROW_ALIGNMENT,
+        },
         computepass::{
             ComputePass,
             ComputePassBuildError,
+            ComputePassError,
+            DispatchIndirectArgs,
         },
         renderpass::{
             RenderPass,
             RenderpassBuilder,
             RenderPassBuildError,
+            LoadOp,
+            StoreOp,
+            DrawIndirectArgs,
+            DrawIndexedIndirectArgs,
         },
         drawing::DrawingContext,
+        display_list::DisplayList,
+        frame::{
+            FrameDesc,
+            FrameItem,
+            FrameLayer,
+            FrameRenderer,
+            FrameStats,
+            FrameTransform,
+        },
     },
 
     pipeline::{
@@ -42,9 +71,34 @@ pub use super::gpu::{
         TextureError,
         TextureFormat,
         TextureSampler,
+        SamplerBorderColor,
+        SamplerError,
+        AddressMode,
+        FilterMode,
         TextureUsage,
         BlendState,
         SampleCount,
+        CompareFunction,
+        animated::{
+            AnimatedTexture,
+            AnimatedTextureBuilder,
+            AnimatedTextureError,
+            AnimationFrame,
+        },
+        atlas::{
+            AtlasRegion,
+            AtlasSource,
+        },
+        embedded::EmbeddedTarget,
+        msaa::MsaaTargets,
+        pingpong::{PingPongSize, PingPongTarget, PingPongTargetBuilder},
+        scaled::{PresentScaling, ScaledTarget},
+        streaming::{StreamingFormat, StreamingTexture, StreamingTextureError},
+        view::{TextureAspect, TextureView, TextureViewDesc},
+        yuv::{YuvColorSpace, YuvFormat},
+        StencilState,
+        StencilFaceState,
+        StencilOperation,
     },
 
     shader::{
@@ -57,26 +111,112 @@ pub use super::gpu::{
             ComputeShader,
             ComputeShaderBuilder,
         },
+        bind_group::{
+            BindGroup,
+            BindGroupBuilder,
+            BindGroupError,
+        },
+        uniform::{
+            GpuUniform,
+            UniformLayout,
+            UniformLayoutError,
+        },
+    },
+
+    error::{
+        GpuError,
+        GpuErrorKind,
+    },
+
+    geometry::{self, Mesh},
+
+    mesh::{
+        self,
+        Material,
+        MeshBuilder,
+        MeshError,
+        MeshVertex,
+        Submesh,
+    },
+
+    budget::{
+        BudgetExceeded,
+        FrameBudget,
+        FrameUsage,
+    },
+
+    cache::{
+        CachePolicy,
+        CacheStats,
     },
 
+    diagnostics::DiagnosticsReport,
+
+    stats::{
+        GpuStats,
+        DrawStats,
+    },
+
+    effects::{
+        Blur,
+        Bloom,
+        Tonemap,
+        TonemapOperator,
+        EffectError,
+    },
+
+    graph::{
+        GraphBuffer,
+        GraphPassBuilder,
+        GraphResources,
+        GraphTexture,
+        RenderGraph,
+        RenderGraphError,
+    },
+
+    profiler::{
+        GpuProfiler,
+        ProfilerError,
+        ProfilerTiming,
+    },
+
+    debug_overlay::DebugOverlay,
+    debug_draw::{DebugDraw3D, DebugDrawError},
+
     buffer::{
         Buffer,
         BufferBuilder,
         BufferError,
         BufferUsage,
         BufferMapMode,
+        gpu_vec::GpuVec,
+        pool::{BufferPool, DEFAULT_SLAB_SIZE},
+        uniform::Uniform,
+        uniform_arena::{UniformArena, UniformArenaError},
     }
 };
 
 pub use super::window::{
     Window,
     WindowError,
+    WindowCreateError,
+    Fullscreen,
+    Monitor,
+    VideoMode,
+    PhysicalSize,
+    LogicalSize,
+    ScaleFactor,
 };
 
 pub use super::input::{
     Input,
     KeyboardEvent,
     MouseEvent,
+    InputBinding,
+    Key,
+    KeyCode,
+    NamedKey,
+    Modifiers,
     MouseMoveEvent
 };
 
@@ -87,4 +227,29 @@ pub use super::software::{
     PixelBuffer,
     PixelBufferBuilder,
     PixelBufferBuilderError
-};
\ No newline at end of file
+};
+#[cfg(feature = "software")]
+pub use super::renderer::{
+    Canvas,
+    Presenter,
+    Renderer,
+    RendererError,
+    PresentError,
+};
+#[cfg(feature = "hot-reload")]
+pub use super::gpu::shader::watcher::{
+    ShaderWatcher,
+    ShaderWatcherError,
+    ShaderReload,
+};
+#[cfg(feature = "clipboard")]
+pub use super::runner::ClipboardError;
+#[cfg(feature = "gamepad")]
+pub use super::runner::gamepad::Gamepad;
+#[cfg(all(feature = "external-memory", any(target_os = "linux", target_os = "android")))]
+pub use super::gpu::texture::external::{
+    DmaBufPlane,
+    ExternalHandle,
+    ExternalHandleType,
+    ExternalTextureError,
+};
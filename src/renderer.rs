@@ -0,0 +1,317 @@
+//! Renderer that falls back to software rendering when no GPU is available.
+//!
+//! Applications draw into a [Canvas] on the CPU and hand it to a [Presenter], which shows it
+//! in the window either through the GPU or through a [PixelBuffer]. This allows the same code
+//! to run on virtual machines and headless CI machines without a usable adapter.
+//!
+//! # Example
+//! ```ignore
+//! let mut renderer = est_render::renderer::auto_renderer(&mut window)?;
+//! let mut canvas = Canvas::new(window.size());
+//!
+//! // Every frame
+//! canvas.clear(Color::BLACK);
+//! canvas.set_pixel(10, 10, Color::WHITE);
+//! renderer.present(&canvas)?;
+//! ```
+
+use crate::{
+    gpu::{
+        GPU, GpuBuildError,
+        texture::{Texture, TextureError, TextureFormat, TextureSampler, TextureUsage},
+    },
+    math::{Color, Point2, Vector2},
+    software::{PixelBuffer, PixelBufferBuilderError, PixelBufferError},
+    window::Window,
+};
+
+/// Creates a renderer for the window, using the GPU if it can be initialized and a
+/// [PixelBuffer] otherwise.
+pub fn auto_renderer(window: &mut Window) -> Result<Renderer, RendererError> {
+    let gpu_error = match crate::gpu::new(Some(&mut *window)).build() {
+        Ok(gpu) => return Ok(Renderer::Hardware(GpuPresenter::new(gpu))),
+        Err(err) => err,
+    };
+
+    crate::warn_log!(
+        "GPU initialization failed ({}), falling back to software rendering",
+        gpu_error
+    );
+
+    match crate::software::new(Some(window)).build() {
+        Ok(pixel_buffer) => Ok(Renderer::Software(pixel_buffer)),
+        Err(software) => Err(RendererError::NoRendererAvailable {
+            gpu: gpu_error,
+            software,
+        }),
+    }
+}
+
+/// CPU pixel storage, one `0x00RRGGBB` value per pixel in row-major order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Canvas {
+    size: Point2,
+    pixels: Vec<u32>,
+}
+
+impl Canvas {
+    /// Creates a black canvas of `size` pixels.
+    pub fn new(size: Point2) -> Self {
+        let size = Point2::new(size.x.max(0), size.y.max(0));
+
+        Self {
+            size,
+            pixels: vec![0; (size.x * size.y) as usize],
+        }
+    }
+
+    pub fn size(&self) -> Point2 {
+        self.size
+    }
+
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [u32] {
+        &mut self.pixels
+    }
+
+    /// Resizes the canvas, the content is cleared to black.
+    pub fn resize(&mut self, size: Point2) {
+        *self = Canvas::new(size);
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        self.pixels.fill(Self::pack(color));
+    }
+
+    /// Sets a pixel, coordinates outside of the canvas are ignored.
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if let Some(index) = self.index(x, y) {
+            self.pixels[index] = Self::pack(color);
+        }
+    }
+
+    /// Returns the pixel at the coordinates, [None] if it is outside of the canvas.
+    pub fn get_pixel(&self, x: i32, y: i32) -> Option<Color> {
+        self.index(x, y).map(|index| {
+            let pixel = self.pixels[index];
+
+            Color::from_rgb(
+                (pixel >> 16) & 0xFF,
+                (pixel >> 8) & 0xFF,
+                pixel & 0xFF,
+                255,
+            )
+        })
+    }
+
+    /// Fills a rectangle, clipped to the canvas.
+    pub fn fill_rect(&mut self, pos: Point2, size: Point2, color: Color) {
+        let x0 = pos.x.clamp(0, self.size.x);
+        let y0 = pos.y.clamp(0, self.size.y);
+        // A negative size draws nothing instead of an inverted range
+        let x1 = pos.x.saturating_add(size.x).clamp(0, self.size.x).max(x0);
+        let y1 = pos.y.saturating_add(size.y).clamp(0, self.size.y).max(y0);
+
+        let pixel = Self::pack(color);
+        for y in y0..y1 {
+            let row = (y * self.size.x) as usize;
+            self.pixels[row + x0 as usize..row + x1 as usize].fill(pixel);
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.size.x || y >= self.size.y {
+            return None;
+        }
+
+        Some((y * self.size.x + x) as usize)
+    }
+
+    fn pack(color: Color) -> u32 {
+        let [r, g, b, _] = color.into_rgb();
+
+        ((r as u32) << 16) | ((g as u32) << 8) | b as u32
+    }
+}
+
+/// Shows a [Canvas] in a window.
+pub trait Presenter {
+    /// Presents the canvas, stretched to the size of the window.
+    fn present(&mut self, canvas: &Canvas) -> Result<(), PresentError>;
+
+    /// Returns true if the canvas is presented through the GPU.
+    fn is_hardware(&self) -> bool;
+}
+
+impl Presenter for PixelBuffer {
+    fn present(&mut self, canvas: &Canvas) -> Result<(), PresentError> {
+        self.write_buffers(canvas.pixels(), canvas.size())
+            .map_err(PresentError::Software)
+    }
+
+    fn is_hardware(&self) -> bool {
+        false
+    }
+}
+
+/// Presents a [Canvas] by uploading it to a texture and drawing it over the whole window.
+#[derive(Clone, Debug)]
+pub struct GpuPresenter {
+    gpu: GPU,
+    texture: Option<Texture>,
+    upload: Vec<u32>,
+}
+
+impl GpuPresenter {
+    pub fn new(gpu: GPU) -> Self {
+        Self {
+            gpu,
+            texture: None,
+            upload: Vec::new(),
+        }
+    }
+
+    pub fn gpu(&mut self) -> &mut GPU {
+        &mut self.gpu
+    }
+}
+
+impl Presenter for GpuPresenter {
+    fn present(&mut self, canvas: &Canvas) -> Result<(), PresentError> {
+        let size = canvas.size();
+        if size.x == 0 || size.y == 0 {
+            return Err(PresentError::InvalidCanvasSize);
+        }
+
+        // The canvas has no alpha, the texture is Bgra8 with the same byte order. Its bytes are
+        // sRGB encoded, sampling decodes them before the surface encodes them again
+        self.upload.clear();
+        self.upload
+            .extend(canvas.pixels().iter().map(|pixel| pixel | 0xFF00_0000));
+
+        let texture = match &mut self.texture {
+            Some(texture) if texture.size() == size => {
+                texture.write(&self.upload).map_err(PresentError::Texture)?;
+                texture
+            }
+            _ => {
+                let texture = self
+                    .gpu
+                    .create_texture()
+                    .set_raw_image(
+                        bytemuck::cast_slice(&self.upload),
+                        size,
+                        TextureFormat::Bgra8UnormSrgb,
+                    )
+                    .set_usage(TextureUsage::Sampler)
+                    .set_sampler(TextureSampler::NEAREST)
+                    .build()
+                    .map_err(PresentError::Texture)?;
+
+                self.texture.insert(texture)
+            }
+        };
+
+        let surface_size = {
+            let inner = self.gpu.inner.borrow();
+            inner
+                .config
+                .as_ref()
+                .map(|config| Vector2::new(config.width as f32, config.height as f32))
+                .unwrap_or_default()
+        };
+
+        let mut cmd = self
+            .gpu
+            .begin_command()
+            .map_err(|_| PresentError::SurfaceUnavailable)?;
+
+        let mut pass = cmd
+            .begin_renderpass()
+            .map_err(|_| PresentError::SurfaceUnavailable)?;
+
+        if let Some(mut drawing) = pass.begin_drawing() {
+            drawing.set_texture(Some(texture));
+            drawing.draw_rect_image(Vector2::new(0.0, 0.0), surface_size, Color::WHITE);
+        }
+
+        Ok(())
+    }
+
+    fn is_hardware(&self) -> bool {
+        true
+    }
+}
+
+/// The renderer picked by [auto_renderer].
+#[derive(Clone, Debug)]
+pub enum Renderer {
+    Hardware(GpuPresenter),
+    Software(PixelBuffer),
+}
+
+impl Renderer {
+    /// Returns the GPU when rendering through it, eg. to draw on top of the canvas.
+    pub fn gpu(&mut self) -> Option<&mut GPU> {
+        match self {
+            Renderer::Hardware(presenter) => Some(presenter.gpu()),
+            Renderer::Software(_) => None,
+        }
+    }
+}
+
+impl Presenter for Renderer {
+    fn present(&mut self, canvas: &Canvas) -> Result<(), PresentError> {
+        match self {
+            Renderer::Hardware(presenter) => presenter.present(canvas),
+            Renderer::Software(presenter) => presenter.present(canvas),
+        }
+    }
+
+    fn is_hardware(&self) -> bool {
+        matches!(self, Renderer::Hardware(_))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum RendererError {
+    /// Both the GPU and the software renderer failed to initialize.
+    NoRendererAvailable {
+        gpu: GpuBuildError,
+        software: PixelBufferBuilderError,
+    },
+}
+
+impl std::fmt::Display for RendererError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RendererError::NoRendererAvailable { gpu, software } => write!(
+                f,
+                "No renderer available, GPU: {}, software: {}",
+                gpu, software
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum PresentError {
+    InvalidCanvasSize,
+    SurfaceUnavailable,
+    Texture(TextureError),
+    Software(PixelBufferError),
+}
+
+impl std::fmt::Display for PresentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PresentError::InvalidCanvasSize => write!(f, "Canvas size is zero"),
+            PresentError::SurfaceUnavailable => write!(f, "Window surface is unavailable"),
+            PresentError::Texture(e) => write!(f, "Texture error: {}", e),
+            PresentError::Software(e) => write!(f, "Pixel buffer error: {}", e),
+        }
+    }
+}
@@ -2,14 +2,25 @@ use std::{
     collections::HashMap, hash::{Hash, Hasher}, io::Read, sync::{atomic::{AtomicBool, AtomicUsize}, Arc, Mutex}, thread::ThreadId, time::Duration
 };
 
-use crate::{input::{self, InputInner}, math::{Point2, Timing}, utils::{ArcMut, ArcRef}, window::{Window, WindowBuilder, WindowInner}};
+use crate::{input::{self, InputInner, KeyCode, Modifiers}, math::{Point2, Timing, Vector2}, utils::{ArcMut, ArcRef}, window::{Monitor, ScaleFactor, Window, WindowBuilder, WindowCreateError, WindowInner, WindowOptions}};
 
 use smol_str::SmolStr;
 use wgpu::rwh::HasWindowHandle;
+
 use winit::{
-    application::ApplicationHandler, dpi::{PhysicalPosition, PhysicalSize}, event, event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy}, keyboard::{Key, NamedKey, NativeKey}, platform::pump_events::{EventLoopExtPumpEvents, PumpStatus}, window::{Cursor, CustomCursor, CustomCursorSource, Window as WinitWindow, WindowAttributes, WindowId}
+    application::ApplicationHandler, dpi::{PhysicalPosition, PhysicalSize}, error::OsError, event, event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy}, keyboard::{Key, NativeKey}, monitor::MonitorHandle, window::{Cursor, CursorGrabMode as WinitCursorGrabMode, CustomCursor, CustomCursorSource, Fullscreen, Window as WinitWindow, WindowAttributes, WindowId, WindowLevel}
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+use winit::platform::pump_events::{EventLoopExtPumpEvents, PumpStatus};
+
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
+pub(crate) mod touch;
+mod app;
+
+pub use app::Application;
+
 #[cfg(target_os = "windows")]
 use winit::platform::windows::EventLoopBuilderExtWindows;
 
@@ -52,18 +63,24 @@ unsafe impl Send for EventLoopWrapper {}
 ///
 /// This wrap winit's [EventLoop] and provides a way to create windows and handle events.
 /// But with some limitations:
-/// - No support for iOS and WASM platforms.
+/// - No support for iOS platform.
+/// - WASM platform can't pump the events, [Runner::run] hands the event loop to the browser instead.
 /// - macOS platform have to use [PollMode::WaitDraw] or drawing at event [Event::RedrawRequested] because
 /// how winit setup the window drawing on macOS.
 #[allow(dead_code)]
 pub struct Runner {
     pub(crate) app_runner: RunnerInner,
+    #[cfg(not(target_arch = "wasm32"))]
     pub(crate) event_loop: ArcRef<EventLoop<WindowEvent>>,
     pub(crate) event_loop_proxy: EventLoopProxy<WindowEvent>,
     pub(crate) window_events_attributes: Vec<ArcRef<WindowInner>>,
     pub(crate) input_events_attributes: Vec<ArcRef<InputInner>>,
     pub(crate) rate_timing: Timing,
     pub(crate) pending_events: Vec<Event>,
+    #[cfg(feature = "clipboard")]
+    pub(crate) clipboard: Option<arboard::Clipboard>,
+    #[cfg(feature = "gamepad")]
+    pub(crate) gamepad: Option<gamepad::GamepadInner>,
 }
 
 impl Runner {
@@ -120,12 +137,17 @@ impl Runner {
 
         Ok(Self {
             app_runner: RunnerInner::new(),
+            #[cfg(not(target_arch = "wasm32"))]
             event_loop,
             event_loop_proxy,
             window_events_attributes: Vec::new(),
             input_events_attributes: Vec::new(),
             rate_timing: Timing::new(0),
             pending_events: Vec::new(),
+            #[cfg(feature = "clipboard")]
+            clipboard: None,
+            #[cfg(feature = "gamepad")]
+            gamepad: gamepad::GamepadInner::new(),
         })
     }
 
@@ -158,9 +180,9 @@ impl Runner {
         title: String,
         size: Point2,
         pos: Option<Point2>,
+        options: WindowOptions,
     ) -> Result<(usize, EventLoopProxy<WindowEvent>), RunnerError> {
-        let mut event_loop = self.event_loop.wait_borrow_mut();
-        let event_loop_proxy = event_loop.create_proxy();
+        let event_loop_proxy = self.event_loop_proxy.clone();
 
         let window_id = CURRENT_WINDOW_ID.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
         if window_id >= 1000 {
@@ -168,25 +190,54 @@ impl Runner {
             return Err(RunnerError::MaximumWindowReached);
         }
 
-        let res = event_loop_proxy.send_event(WindowEvent::Create {
-            ref_id: window_id,
-            parent_ref_id: parent,
-            title,
-            size,
-            pos,
-        });
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let mut event_loop = self.event_loop.wait_borrow_mut();
 
-        if res.is_err() {
-            let err = self
-                .app_runner
-                .last_error
-                .clone()
-                .unwrap_or_else(|| "Failed to create window!".to_string());
+            let res = event_loop_proxy.send_event(WindowEvent::Create {
+                ref_id: window_id,
+                parent_ref_id: parent,
+                title,
+                size,
+                pos,
+                options,
+            });
 
-            return Err(RunnerError::FailedToCreateWindow(err));
+            if res.is_err() {
+                return Err(RunnerError::FailedToCreateWindow(
+                    WindowCreateError::EventLoopClosed,
+                ));
+            }
+
+            event_loop.pump_app_events(Some(Duration::ZERO), &mut self.app_runner);
         }
 
-        event_loop.pump_app_events(Some(Duration::ZERO), &mut self.app_runner);
+        // The event loop can't be pumped in the browser, the window is created directly
+        #[cfg(target_arch = "wasm32")]
+        {
+            let Some(event_loop) = CURRENT_LOOP
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|current_loop| current_loop.event_loop.clone())
+            else {
+                return Err(RunnerError::FailedToCreateWindow(
+                    WindowCreateError::EventLoopRunning,
+                ));
+            };
+
+            let event_loop = event_loop.wait_borrow();
+
+            if let Some(window_attributes) =
+                self.app_runner
+                    .window_attributes(parent, title, size, pos, &options)
+            {
+                #[allow(deprecated)]
+                let window = event_loop.create_window(window_attributes);
+
+                self.app_runner.add_window(window_id, window, &options);
+            }
+        }
 
         let mut found = false;
         for (_id, handle) in self.app_runner.handles.iter() {
@@ -199,9 +250,9 @@ impl Runner {
         if !found {
             let err = self
                 .app_runner
-                .last_error
-                .clone()
-                .unwrap_or_else(|| "Failed to create window!".to_string());
+                .window_error
+                .take()
+                .unwrap_or(WindowCreateError::EventLoopClosed);
 
             return Err(RunnerError::FailedToCreateWindow(err));
         }
@@ -222,13 +273,15 @@ impl Runner {
     ///
     /// # Incompatible platforms
     /// - iOS: This method is not supported on iOS due to platform limitations.
-    /// - WASM: This method is not supported on WASM due to how the browser handles events, unless
-    /// you using the emscripten event loop.
+    /// - WASM: This method is not available on WASM due to how the browser handles events, use
+    ///   [Runner::run] instead.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn pump_events<T>(&mut self, mode: T) -> bool
     where
         T: Into<Option<PumpMode>>,
     {
-        let mut event_loop = self.event_loop.wait_borrow_mut();
+        let event_loop = self.event_loop.clone();
+        let mut event_loop = event_loop.wait_borrow_mut();
         let mode = mode.into();
 
         let duration = match mode {
@@ -243,192 +296,20 @@ impl Runner {
             _ => false,
         };
 
-        self.pending_events.clear();
-
-        loop {
-            match event_loop.pump_app_events(duration, &mut self.app_runner) {
-                PumpStatus::Continue => {
-                    for window in self.window_events_attributes.iter() {
-                        if let Some(mut window) = window.try_borrow_mut() {
-                            window.process_event();
-
-                            {
-                                let window_events = window.window_events.wait_borrow_mut();
-                                for event in window_events.iter() {
-                                    match event {
-                                        event::WindowEvent::CloseRequested => {
-                                            self.pending_events.push(Event::WindowClosed {
-                                                window_id: window.window_id,
-                                            });
-                                        }
-                                        event::WindowEvent::Resized(size) => {
-                                            self.pending_events.push(Event::WindowResized {
-                                                window_id: window.window_id,
-                                                size: Point2::new(size.width, size.height),
-                                            });
-                                        }
-                                        event::WindowEvent::Moved(pos) => {
-                                            self.pending_events.push(Event::WindowMoved {
-                                                window_id: window.window_id,
-                                                pos: Point2::new(pos.x, pos.y),
-                                            });
-                                        }
-                                        event::WindowEvent::RedrawRequested => {
-                                            self.pending_events.push(Event::RedrawRequested {
-                                                window_id: window.window_id,
-                                            });
-                                        }
-                                        event::WindowEvent::KeyboardInput {
-                                            event,
-                                            is_synthetic,
-                                            ..
-                                        } => {
-                                            if *is_synthetic {
-                                                continue;
-                                            }
-
-                                            let is_pressed =
-                                                event.state == event::ElementState::Pressed;
-
-                                            match event.logical_key {
-                                                Key::Character(ref smol_str) => {
-                                                    let smol_key = smol_str.clone();
-
-                                                    self.pending_events.push(Event::KeyboardInput {
-                                                        window_id: window.window_id,
-                                                        key: smol_key,
-                                                        pressed: is_pressed,
-                                                    });
-                                                }
-                                                Key::Named(ref named_key) => {
-                                                    let smol_key = named_key_to_str(named_key);
-                                                    if smol_key.is_none() {
-                                                        continue;
-                                                    }
-
-                                                    let smol_key = smol_key.unwrap();
-
-                                                    self.pending_events.push(Event::KeyboardInput {
-                                                        window_id: window.window_id,
-                                                        key: smol_key,
-                                                        pressed: is_pressed,
-                                                    });
-                                                }
-                                                Key::Unidentified(NativeKey::Windows(virtual_key)) => {
-                                                    let fmt = format!("virtual-key:{:?}", virtual_key);
-                                                    let smol_key = SmolStr::new(fmt);
-
-                                                    self.pending_events.push(Event::KeyboardInput {
-                                                        window_id: window.window_id,
-                                                        key: smol_key,
-                                                        pressed: is_pressed,
-                                                    });
-                                                }
-                                                _ => {
-                                                    // ignore
-                                                }
-                                            }
-                                        }
-                                        event::WindowEvent::MouseWheel {
-                                            delta, phase: _, ..
-                                        } => {
-                                            let delta = match delta {
-                                                event::MouseScrollDelta::LineDelta(
-                                                    delta_x,
-                                                    delta_y,
-                                                ) => MouseScrollDelta::LineDelta {
-                                                    delta_x: *delta_x,
-                                                    delta_y: *delta_y,
-                                                },
-                                                event::MouseScrollDelta::PixelDelta(delta_pos) => {
-                                                    MouseScrollDelta::PixelDelta {
-                                                        delta_x: delta_pos.x as f32,
-                                                        delta_y: delta_pos.y as f32,
-                                                    }
-                                                }
-                                            };
-
-                                            self.pending_events.push(Event::MouseWheel {
-                                                window_id: window.window_id,
-                                                delta,
-                                            });
-                                        }
-                                        event::WindowEvent::MouseInput {
-                                            device_id: _,
-                                            state,
-                                            button,
-                                        } => {
-                                            let is_pressed = *state == event::ElementState::Pressed;
-                                            let smoll_str = match button {
-                                                event::MouseButton::Left => SmolStr::new("Left"),
-                                                event::MouseButton::Right => SmolStr::new("Right"),
-                                                event::MouseButton::Middle => SmolStr::new("Middle"),
-                                                event::MouseButton::Back => SmolStr::new("Back"),
-                                                event::MouseButton::Forward => SmolStr::new("Forward"),
-                                                event::MouseButton::Other(_) => continue, // Ignore other buttons
-                                            };
-
-                                            self.pending_events.push(Event::MouseInput {
-                                                window_id: window.window_id,
-                                                button: smoll_str,
-                                                pressed: is_pressed,
-                                            });
-                                        }
-                                        event::WindowEvent::CursorEntered { device_id: _ } => {
-                                            self.pending_events.push(Event::CursorEntered {
-                                                window_id: window.window_id,
-                                            });
-                                        }
-                                        event::WindowEvent::CursorLeft { device_id: _ } => {
-                                            self.pending_events.push(Event::CursorLeft {
-                                                window_id: window.window_id,
-                                            });
-                                        }
-                                        event::WindowEvent::CursorMoved {
-                                            device_id: _,
-                                            position,
-                                        } => {
-                                            self.pending_events.push(Event::CursorMoved {
-                                                window_id: window.window_id,
-                                                pos: Point2::new(position.x, position.y),
-                                            });
-                                        }
-                                        event::WindowEvent::Focused(focused) => {
-                                            self.pending_events.push(Event::WindowFocused {
-                                                window_id: window.window_id,
-                                                focused: *focused,
-                                            });
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            }
+        self.begin_frame();
 
-                            window.cycle();
-                        }
-                    }
-                }
-                PumpStatus::Exit(_code) => {
-                    // Exit the event loop
-                    crate::dbg_log!("Event loop exited with code: {}", _code);
+        // Events already given to the inputs in a previous iteration of the loop
+        let mut processed_events = 0;
 
-                    return false;
-                }
-            }
+        loop {
+            if let PumpStatus::Exit(_code) = event_loop.pump_app_events(duration, &mut self.app_runner) {
+                // Exit the event loop
+                crate::dbg_log!("Event loop exited with code: {}", _code);
 
-            for window in self.window_events_attributes.iter() {
-                window.wait_borrow().window_events.wait_borrow_mut().clear();
+                return false;
             }
 
-            if self.input_events_attributes.len() > 0 {
-                for event in self.pending_events.iter() {
-                    for input in self.input_events_attributes.iter() {
-                        if let Some(mut input) = input.try_borrow_mut() {
-                            input.process_event(event);
-                        }
-                    }
-                }
-            }
+            processed_events = self.collect_events(processed_events);
 
             if wait_for_redraw {
                 if self
@@ -450,6 +331,366 @@ impl Runner {
         true
     }
 
+    /// Starts a new frame, clearing the events of the previous one.
+    fn begin_frame(&mut self) {
+        self.pending_events.clear();
+
+        for input in self.input_events_attributes.iter() {
+            if let Some(mut input) = input.try_borrow_mut() {
+                input.begin_frame();
+            }
+        }
+    }
+
+    /// Turns what the event loop received into [Event]s, giving the ones after `processed_events`
+    /// to the inputs, and returns the number of events of the frame.
+    fn collect_events(&mut self, processed_events: usize) -> usize {
+        for window in self.window_events_attributes.iter() {
+            if let Some(mut window) = window.try_borrow_mut() {
+                window.process_event();
+
+                let mut touches = Vec::new();
+                let mut modifiers = window.modifiers;
+
+                {
+                    let window_events = window.window_events.wait_borrow_mut();
+                    for event in window_events.iter() {
+                        match event {
+                            event::WindowEvent::CloseRequested => {
+                                self.pending_events.push(Event::WindowClosed {
+                                    window_id: window.window_id,
+                                });
+                            }
+                            event::WindowEvent::Resized(size) => {
+                                self.pending_events.push(Event::WindowResized {
+                                    window_id: window.window_id,
+                                    size: Point2::new(size.width, size.height),
+                                });
+                            }
+                            event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                                self.pending_events.push(Event::WindowScaleFactorChanged {
+                                    window_id: window.window_id,
+                                    scale: ScaleFactor(*scale_factor),
+                                });
+                            }
+                            event::WindowEvent::Moved(pos) => {
+                                self.pending_events.push(Event::WindowMoved {
+                                    window_id: window.window_id,
+                                    pos: Point2::new(pos.x, pos.y),
+                                });
+                            }
+                            event::WindowEvent::RedrawRequested => {
+                                self.pending_events.push(Event::RedrawRequested {
+                                    window_id: window.window_id,
+                                });
+                            }
+                            event::WindowEvent::ModifiersChanged(state) => {
+                                modifiers = Modifiers::from_winit(&state.state());
+                            }
+                            event::WindowEvent::KeyboardInput {
+                                event,
+                                is_synthetic,
+                                ..
+                            } => {
+                                if *is_synthetic {
+                                    continue;
+                                }
+
+                                let is_pressed =
+                                    event.state == event::ElementState::Pressed;
+                                let code = KeyCode::from_physical(&event.physical_key);
+                                let logical_key = input::Key::from_winit(&event.logical_key);
+
+                                let smol_key = match (&logical_key, &event.logical_key) {
+                                    (input::Key::Character(smol_str), _) => smol_str.clone(),
+                                    (input::Key::Named(named_key), _) => SmolStr::new_static(named_key.as_str()),
+                                    (_, Key::Unidentified(NativeKey::Windows(virtual_key))) => {
+                                        SmolStr::new(format!("virtual-key:{:?}", virtual_key))
+                                    }
+                                    _ if code != KeyCode::Unidentified => SmolStr::new(format!("{:?}", code)),
+                                    _ => continue,
+                                };
+
+                                self.pending_events.push(Event::KeyboardInput {
+                                    window_id: window.window_id,
+                                    key: smol_key,
+                                    code,
+                                    logical_key,
+                                    modifiers,
+                                    pressed: is_pressed,
+                                });
+                            }
+                            event::WindowEvent::Ime(ime) => match ime {
+                                event::Ime::Preedit(text, cursor) => {
+                                    self.pending_events.push(Event::ImePreedit {
+                                        window_id: window.window_id,
+                                        text: text.clone(),
+                                        cursor: *cursor,
+                                    });
+                                }
+                                event::Ime::Commit(text) => {
+                                    self.pending_events.push(Event::ImeCommit {
+                                        window_id: window.window_id,
+                                        text: text.clone(),
+                                    });
+                                }
+                                _ => {}
+                            },
+                            event::WindowEvent::MouseWheel {
+                                delta, phase: _, ..
+                            } => {
+                                let delta = match delta {
+                                    event::MouseScrollDelta::LineDelta(
+                                        delta_x,
+                                        delta_y,
+                                    ) => MouseScrollDelta::LineDelta {
+                                        delta_x: *delta_x,
+                                        delta_y: *delta_y,
+                                    },
+                                    event::MouseScrollDelta::PixelDelta(delta_pos) => {
+                                        MouseScrollDelta::PixelDelta {
+                                            delta_x: delta_pos.x as f32,
+                                            delta_y: delta_pos.y as f32,
+                                        }
+                                    }
+                                };
+
+                                self.pending_events.push(Event::MouseWheel {
+                                    window_id: window.window_id,
+                                    delta,
+                                });
+                            }
+                            event::WindowEvent::MouseInput {
+                                device_id: _,
+                                state,
+                                button,
+                            } => {
+                                let is_pressed = *state == event::ElementState::Pressed;
+                                let smoll_str = match button {
+                                    event::MouseButton::Left => SmolStr::new("Left"),
+                                    event::MouseButton::Right => SmolStr::new("Right"),
+                                    event::MouseButton::Middle => SmolStr::new("Middle"),
+                                    event::MouseButton::Back => SmolStr::new("Back"),
+                                    event::MouseButton::Forward => SmolStr::new("Forward"),
+                                    event::MouseButton::Other(_) => continue, // Ignore other buttons
+                                };
+
+                                self.pending_events.push(Event::MouseInput {
+                                    window_id: window.window_id,
+                                    button: smoll_str,
+                                    pressed: is_pressed,
+                                });
+                            }
+                            event::WindowEvent::CursorEntered { device_id: _ } => {
+                                self.pending_events.push(Event::CursorEntered {
+                                    window_id: window.window_id,
+                                });
+                            }
+                            event::WindowEvent::CursorLeft { device_id: _ } => {
+                                self.pending_events.push(Event::CursorLeft {
+                                    window_id: window.window_id,
+                                });
+                            }
+                            event::WindowEvent::CursorMoved {
+                                device_id: _,
+                                position,
+                            } => {
+                                self.pending_events.push(Event::CursorMoved {
+                                    window_id: window.window_id,
+                                    pos: Point2::new(position.x, position.y),
+                                });
+                            }
+                            event::WindowEvent::Touch(touch) => {
+                                let phase = match touch.phase {
+                                    event::TouchPhase::Started => TouchPhase::Started,
+                                    event::TouchPhase::Moved => TouchPhase::Moved,
+                                    event::TouchPhase::Ended => TouchPhase::Ended,
+                                    event::TouchPhase::Cancelled => TouchPhase::Cancelled,
+                                };
+
+                                self.pending_events.push(Event::Touch {
+                                    window_id: window.window_id,
+                                    id: touch.id,
+                                    phase,
+                                    pos: Point2::new(touch.location.x, touch.location.y),
+                                });
+
+                                let pos = Vector2::new(touch.location.x, touch.location.y);
+                                touches.push((touch.id, phase, pos));
+                            }
+                            event::WindowEvent::PinchGesture { delta, .. } => {
+                                self.pending_events.push(Event::Gesture {
+                                    window_id: window.window_id,
+                                    gesture: Gesture::Pinch { delta: *delta as f32 },
+                                });
+                            }
+                            event::WindowEvent::RotationGesture { delta, .. } => {
+                                self.pending_events.push(Event::Gesture {
+                                    window_id: window.window_id,
+                                    gesture: Gesture::Rotate { delta: *delta },
+                                });
+                            }
+                            event::WindowEvent::PanGesture { delta, .. } => {
+                                self.pending_events.push(Event::Gesture {
+                                    window_id: window.window_id,
+                                    gesture: Gesture::Pan {
+                                        delta_x: delta.x,
+                                        delta_y: delta.y,
+                                    },
+                                });
+                            }
+                            event::WindowEvent::Focused(focused) => {
+                                self.pending_events.push(Event::WindowFocused {
+                                    window_id: window.window_id,
+                                    focused: *focused,
+                                });
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+
+                window.modifiers = modifiers;
+
+                for (id, phase, pos) in touches {
+                    for gesture in window.gestures.update(id, phase, pos) {
+                        self.pending_events.push(Event::Gesture {
+                            window_id: window.window_id,
+                            gesture,
+                        });
+                    }
+                }
+
+                if let Some(fullscreen) = window.fullscreen_changed.take() {
+                    window.reconfigure_surfaces();
+
+                    self.pending_events.push(Event::WindowFullscreenChanged {
+                        window_id: window.window_id,
+                        fullscreen,
+                    });
+                }
+
+                if window.cycle() {
+                    self.pending_events.push(Event::DeviceRestored {
+                        window_id: window.window_id,
+                    });
+                }
+            }
+        }
+
+        for window in self.window_events_attributes.iter() {
+            window.wait_borrow().window_events.wait_borrow_mut().clear();
+        }
+
+        if let Some((delta_x, delta_y)) = self.app_runner.mouse_motion.take() {
+            self.pending_events.push(Event::MouseMotionRaw {
+                delta: MouseMotionDelta {
+                    delta_x: delta_x as f32,
+                    delta_y: delta_y as f32,
+                },
+            });
+        }
+
+        #[cfg(feature = "gamepad")]
+        if let Some(gamepad) = &mut self.gamepad {
+            gamepad.poll(&mut self.pending_events);
+        }
+
+        if !self.input_events_attributes.is_empty() {
+            for event in self.pending_events[processed_events..].iter() {
+                for input in self.input_events_attributes.iter() {
+                    if let Some(mut input) = input.try_borrow_mut() {
+                        input.process_event(event);
+                    }
+                }
+            }
+        }
+
+        self.pending_events.len()
+    }
+
+    /// Runs `app` until every window is closed, calling its [Application] methods instead of
+    /// pumping the events manually with [Runner::pump_events].
+    ///
+    /// Each frame, [Application::on_event] is called for the events, then [Application::update]
+    /// and [Application::render] for every window which needs a redraw. The events of the frame
+    /// are also available from [Runner::get_events].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run<A: Application>(&mut self, mut app: A) {
+        let mode = app.pump_mode();
+
+        while self.pump_events(mode) {
+            self.run_frame(&mut app, mode);
+        }
+    }
+
+    /// Runs `app` in the browser, calling its [Application] methods on each animation frame.
+    ///
+    /// The event loop is handed to the browser, so this returns immediately and the windows
+    /// have to be created before calling it.
+    #[cfg(target_arch = "wasm32")]
+    pub fn run<A: Application + 'static>(mut self, app: A) {
+        use winit::platform::web::EventLoopExtWebSys;
+
+        let event_loop = CURRENT_LOOP
+            .lock()
+            .unwrap()
+            .take()
+            .map(|current_loop| current_loop.event_loop.try_unwrap());
+
+        let Some(Ok(event_loop)) = event_loop else {
+            crate::error_log!("The event loop is already running or used by another Runner");
+            return;
+        };
+
+        // The browser paces the frames with requestAnimationFrame
+        self.rate_timing.set_fps(0);
+
+        let mode = app.pump_mode();
+        event_loop.spawn_app(app::WebApplication {
+            runner: self,
+            app,
+            mode,
+        });
+    }
+
+    /// Gives the events of the frame to `app`, then updates and renders it.
+    fn run_frame<A: Application>(&mut self, app: &mut A, mode: PumpMode) {
+        let events = std::mem::take(&mut self.pending_events);
+        for event in events.iter() {
+            app.on_event(self, event);
+        }
+        self.pending_events = events;
+
+        let dt = self.get_frame_time();
+        app.update(self, dt);
+
+        let mut redraws = self
+            .pending_events
+            .iter()
+            .filter_map(|event| match event {
+                Event::RedrawRequested { window_id } => Some(*window_id),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // Redraws requested during skipped frames are drawn once
+        redraws.sort_unstable();
+        redraws.dedup();
+
+        for window_id in redraws {
+            app.render(self, window_id);
+
+            // Keep drawing frames, the system only asks for a redraw when the window changes
+            if mode != PumpMode::Wait
+                && let Some(handle) = self.get_window_pointer(window_id)
+                && let Some(window) = handle.lock().window.as_ref()
+            {
+                window.request_redraw();
+            }
+        }
+    }
+
     /// Set the rate (frame rate) for the event loop.
     ///
     /// This only useful if you want to control the frame rate of the event loop.
@@ -494,6 +735,109 @@ impl Runner {
         self.rate_timing.get_frame_time()
     }
 
+    /// Lists the monitors connected to the system, for [Window::set_fullscreen].
+    ///
+    /// The list is queried again on each call, so monitors plugged in later show up.
+    pub fn monitors(&mut self) -> Vec<Monitor> {
+        self.query_monitors();
+
+        self.app_runner
+            .monitors
+            .iter()
+            .map(|handle| Monitor {
+                handle: handle.clone(),
+            })
+            .collect()
+    }
+
+    /// The primary monitor of the system, always [None] on Wayland.
+    pub fn primary_monitor(&mut self) -> Option<Monitor> {
+        self.query_monitors();
+
+        self.app_runner
+            .primary_monitor
+            .clone()
+            .map(|handle| Monitor { handle })
+    }
+
+    /// Lists the connected gamepads, empty if gamepads are unsupported on the platform.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepads(&self) -> Vec<gamepad::Gamepad> {
+        self.gamepad
+            .as_ref()
+            .map(|gamepad| gamepad.gamepads())
+            .unwrap_or_default()
+    }
+
+    /// Returns the text in the system clipboard.
+    #[cfg(feature = "clipboard")]
+    pub fn clipboard_get_text(&mut self) -> Result<String, ClipboardError> {
+        self.clipboard()?.get_text().map_err(ClipboardError::from)
+    }
+
+    /// Replaces the content of the system clipboard with `text`.
+    #[cfg(feature = "clipboard")]
+    pub fn clipboard_set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+        self.clipboard()?.set_text(text).map_err(ClipboardError::from)
+    }
+
+    /// Returns the image in the system clipboard, as its size and RGBA8 pixels.
+    #[cfg(feature = "clipboard")]
+    pub fn clipboard_get_image(&mut self) -> Result<(Point2, Vec<u8>), ClipboardError> {
+        let image = self.clipboard()?.get_image()?;
+
+        Ok((
+            Point2::new(image.width, image.height),
+            image.bytes.into_owned(),
+        ))
+    }
+
+    /// Replaces the content of the system clipboard with an image of `size` made of RGBA8 `pixels`.
+    #[cfg(feature = "clipboard")]
+    pub fn clipboard_set_image(&mut self, size: Point2, pixels: &[u8]) -> Result<(), ClipboardError> {
+        if size.x <= 0 || size.y <= 0 || pixels.len() != size.x as usize * size.y as usize * 4 {
+            return Err(ClipboardError::InvalidData);
+        }
+
+        let image = arboard::ImageData {
+            width: size.x as usize,
+            height: size.y as usize,
+            bytes: pixels.into(),
+        };
+
+        self.clipboard()?.set_image(image).map_err(ClipboardError::from)
+    }
+
+    // The clipboard is kept alive with the runner, on X11 and Wayland the copied content is
+    // served by it and would be lost when dropped.
+    #[cfg(feature = "clipboard")]
+    fn clipboard(&mut self) -> Result<&mut arboard::Clipboard, ClipboardError> {
+        if self.clipboard.is_none() {
+            let clipboard = arboard::Clipboard::new()
+                .map_err(|err| ClipboardError::Unavailable(err.to_string()))?;
+
+            self.clipboard = Some(clipboard);
+        }
+
+        Ok(self.clipboard.as_mut().unwrap())
+    }
+
+    /// Monitors can only be listed from inside the event loop, like windows are created.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn query_monitors(&mut self) {
+        let mut event_loop = self.event_loop.wait_borrow_mut();
+
+        if self.event_loop_proxy.send_event(WindowEvent::QueryMonitors).is_ok() {
+            event_loop.pump_app_events(Some(Duration::ZERO), &mut self.app_runner);
+        }
+    }
+
+    // Without pumping, the monitors are updated once the running event loop handles the query
+    #[cfg(target_arch = "wasm32")]
+    fn query_monitors(&mut self) {
+        _ = self.event_loop_proxy.send_event(WindowEvent::QueryMonitors);
+    }
+
     pub(crate) fn get_events_pointer(
         &self,
         window_id: usize,
@@ -511,6 +855,34 @@ pub(crate) struct Handle {
     pub window: Option<Arc<WinitWindow>>,
     pub is_closed: bool,
     pub is_pinned: bool,
+
+    /// State restored when leaving borderless fullscreen, [None] while windowed.
+    pub windowed_state: Option<WindowedState>,
+
+    /// Fixed size windows are locked to their size, resizable ones use `min_size` and `max_size`.
+    pub resizable: bool,
+    pub min_size: Option<PhysicalSize<u32>>,
+    pub max_size: Option<PhysicalSize<u32>>,
+    pub transparent: bool,
+}
+
+fn physical_size(size: Point2) -> PhysicalSize<u32> {
+    PhysicalSize::new(size.x as u32, size.y as u32)
+}
+
+fn window_level(always_on_top: bool) -> WindowLevel {
+    if always_on_top {
+        WindowLevel::AlwaysOnTop
+    } else {
+        WindowLevel::Normal
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct WindowedState {
+    pub size: PhysicalSize<u32>,
+    pub pos: Option<PhysicalPosition<i32>>,
+    pub decorations: bool,
 }
 
 #[allow(dead_code)]
@@ -520,6 +892,24 @@ impl Handle {
             window: Some(window),
             is_closed: false,
             is_pinned: false,
+            windowed_state: None,
+            resizable: false,
+            min_size: None,
+            max_size: None,
+            transparent: false,
+        }
+    }
+
+    /// Applies the size limits of the window, locking a fixed size window to `size`.
+    pub fn apply_size_limits(&self, size: PhysicalSize<u32>) {
+        let window = self.get_window();
+
+        if self.resizable {
+            window.set_max_inner_size(self.max_size);
+            window.set_min_inner_size(self.min_size);
+        } else {
+            window.set_max_inner_size(Some(size));
+            window.set_min_inner_size(Some(size));
         }
     }
 
@@ -577,8 +967,12 @@ impl Drop for WindowHandle {
 pub(crate) struct RunnerInner {
     pub handles: HashMap<WindowId, WindowHandle>,
     pub last_error: Option<String>,
+    pub window_error: Option<WindowCreateError>,
     pub has_redraw_requested: AtomicBool,
     pub cursor_cache: HashMap<u64, CustomCursor>,
+    pub monitors: Vec<MonitorHandle>,
+    pub primary_monitor: Option<MonitorHandle>,
+    pub mouse_motion: Option<(f64, f64)>,
 }
 
 impl RunnerInner {
@@ -586,8 +980,12 @@ impl RunnerInner {
         Self {
             handles: HashMap::new(),
             last_error: None,
+            window_error: None,
             has_redraw_requested: AtomicBool::new(false),
             cursor_cache: HashMap::new(),
+            monitors: Vec::new(),
+            primary_monitor: None,
+            mouse_motion: None,
         }
     }
 
@@ -607,6 +1005,127 @@ impl RunnerInner {
             .find(|(_, handle)| handle.ref_id == ref_id)
             .map(|(_, handle)| handle.events.clone())
     }
+
+    /// Builds the attributes of a window to create, [None] if its parent can't be used.
+    pub fn window_attributes(
+        &mut self,
+        parent_ref_id: Option<usize>,
+        title: String,
+        size: Point2,
+        pos: Option<Point2>,
+        options: &WindowOptions,
+    ) -> Option<WindowAttributes> {
+        let size: PhysicalSize<u32> = PhysicalSize::new(size.x as u32, size.y as u32);
+        let min_size = options.min_size.map(physical_size);
+        let max_size = options.max_size.map(physical_size);
+
+        let mut window_attributes = WindowAttributes::default()
+            .with_title(title)
+            .with_visible(options.visible)
+            .with_inner_size(size)
+            .with_resizable(options.resizable)
+            .with_decorations(options.decorations)
+            .with_transparent(options.transparent)
+            .with_maximized(options.maximized)
+            .with_window_level(window_level(options.always_on_top));
+
+        if options.resizable {
+            if let Some(min_size) = min_size {
+                window_attributes = window_attributes.with_min_inner_size(min_size);
+            }
+
+            if let Some(max_size) = max_size {
+                window_attributes = window_attributes.with_max_inner_size(max_size);
+            }
+        } else {
+            window_attributes = window_attributes
+                .with_max_inner_size(size)
+                .with_min_inner_size(size);
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            use winit::platform::windows::{CornerPreference, WindowAttributesExtWindows};
+
+            window_attributes =
+                window_attributes.with_corner_preference(CornerPreference::DoNotRound);
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            use winit::platform::web::WindowAttributesExtWebSys;
+
+            let append = options.canvas.is_none();
+            window_attributes = window_attributes
+                .with_canvas(options.canvas.clone())
+                .with_append(append);
+        }
+
+        if let Some(pos) = pos {
+            let pos: PhysicalPosition<i32> = PhysicalPosition::new(pos.x, pos.y);
+            window_attributes = window_attributes.with_position(pos);
+        }
+
+        if let Some(parent_ref_id) = parent_ref_id
+            && let Some(parent_window) = self.get_window_handle_by_ref(parent_ref_id)
+        {
+            let parent_window = parent_window.lock();
+
+            // SAFETY: We are using the `window_handle` method to get the raw window handle,
+            // which is safe as long as the window is valid and not dropped.
+            unsafe {
+                if parent_window.is_closed() {
+                    self.window_error = Some(WindowCreateError::ParentClosed);
+                    return None;
+                }
+
+                let parent_window = parent_window.get_window().window_handle();
+
+                if let Err(e) = parent_window {
+                    self.window_error = Some(WindowCreateError::ParentHandle(e.to_string()));
+
+                    return None;
+                }
+
+                let parent_window_handle = parent_window.unwrap().as_raw();
+                window_attributes =
+                    window_attributes.with_parent_window(Some(parent_window_handle));
+            }
+        }
+
+        Some(window_attributes)
+    }
+
+    /// Keeps track of a created window, or of the error if it failed to be created.
+    pub fn add_window(
+        &mut self,
+        ref_id: usize,
+        window: Result<WinitWindow, OsError>,
+        options: &WindowOptions,
+    ) {
+        if let Ok(window) = window {
+            let window_id = window.id();
+            window.set_ime_allowed(options.ime);
+
+            let mut handle = Handle::new(Arc::new(window));
+            handle.resizable = options.resizable;
+            handle.min_size = options.min_size.map(physical_size);
+            handle.max_size = options.max_size.map(physical_size);
+            handle.transparent = options.transparent;
+
+            let window_handle = WindowHandle {
+                window: ArcMut::new(handle),
+                events: ArcRef::new(Vec::new()),
+                ref_id,
+            };
+
+            crate::dbg_log!("Window {} created", ref_id);
+            self.handles.insert(window_id, window_handle);
+        } else {
+            crate::dbg_log!("Failed to create window: {:?}", window);
+            self.window_error = window.err().map(|e| WindowCreateError::Os(e.to_string()));
+        }
+    }
 }
 
 impl ApplicationHandler<WindowEvent> for RunnerInner {
@@ -658,6 +1177,18 @@ impl ApplicationHandler<WindowEvent> for RunnerInner {
         }
     }
 
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: event::DeviceId,
+        event: event::DeviceEvent,
+    ) {
+        if let event::DeviceEvent::MouseMotion { delta } = event {
+            let (x, y) = self.mouse_motion.unwrap_or_default();
+            self.mouse_motion = Some((x + delta.0, y + delta.1));
+        }
+    }
+
     #[allow(unused_variables, unreachable_patterns)]
     fn user_event(&mut self, event_loop: &ActiveEventLoop, event: WindowEvent) {
         match event {
@@ -667,160 +1198,289 @@ impl ApplicationHandler<WindowEvent> for RunnerInner {
                 title,
                 size,
                 pos,
+                options,
             } => {
-                let size: PhysicalSize<u32> = PhysicalSize::new(size.x as u32, size.y as u32);
-                let mut window_attributes = WindowAttributes::default()
-                    .with_title(title)
-                    .with_visible(true)
-                    .with_inner_size(size)
-                    .with_resizable(false)
-                    .with_max_inner_size(size)
-                    .with_min_inner_size(size);
-
-                #[cfg(target_os = "windows")]
-                {
-                    use winit::platform::windows::{CornerPreference, WindowAttributesExtWindows};
+                let Some(window_attributes) =
+                    self.window_attributes(parent_ref_id, title, size, pos, &options)
+                else {
+                    return;
+                };
 
-                    window_attributes =
-                        window_attributes.with_corner_preference(CornerPreference::DoNotRound);
+                let window = event_loop.create_window(window_attributes);
+                self.add_window(ref_id, window, &options);
+            }
+            WindowEvent::Close { ref_id } => {
+                if self.handles.is_empty() {
+                    event_loop.exit();
+
+                    return;
                 }
 
-                if let Some(pos) = pos {
-                    let pos: PhysicalPosition<i32> =
-                        PhysicalPosition::new(pos.x as i32, pos.y as i32);
-                    window_attributes = window_attributes.with_position(pos);
+                let mut to_remove = None;
+
+                for (window_id, handle) in &self.handles {
+                    if handle.ref_id == ref_id {
+                        to_remove = Some(*window_id);
+                        break;
+                    }
                 }
 
-                if let Some(parent_ref_id) = parent_ref_id {
-                    if let Some(parent_window) = self.get_window_handle_by_ref(parent_ref_id) {
-                        let parent_window = parent_window.lock();
-
-                        // SAFETY: We are using the `window_handle` method to get the raw window handle,
-                        // which is safe as long as the window is valid and not dropped.
-                        unsafe {
-                            if parent_window.is_closed() {
-                                self.last_error = Some(format!(
-                                    "Parent window is None for ref_id: {}",
-                                    parent_ref_id
-                                ));
-                                return;
-                            }
+                if let Some(window_id) = to_remove {
+                    if let Some(handle) = self.handles.get_mut(&window_id) {
+                        handle.window.lock().close();
+                    }
 
-                            let parent_window = parent_window.get_window().window_handle();
+                    crate::dbg_log!("Window {} closed", ref_id);
+                    self.handles.remove(&window_id);
+                }
 
-                            if let Err(e) = parent_window {
-                                self.last_error =
-                                    Some(format!("Failed to set parent window: {:?}", e));
+                if self.handles.is_empty() {
+                    crate::dbg_log!("All windows closed, exiting event loop");
+                    event_loop.exit();
+                }
+            }
+            WindowEvent::Title { ref_id, title } => {
+                if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
+                    let window = handle.lock();
+                    let window = window.get_window();
+
+                    crate::dbg_log!("Window {} title: {}", ref_id, title);
 
-                                return;
+                    window.set_title(title.as_str());
+                }
+            }
+            WindowEvent::Size { ref_id, size } => {
+                if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
+                    let size: PhysicalSize<u32> = size.into();
+
+                    let mut handle_ref = handle.lock();
+
+                    // Applied when leaving fullscreen
+                    if let Some(state) = &mut handle_ref.windowed_state {
+                        state.size = size;
+                        return;
+                    }
+
+                    crate::dbg_log!("Window {} size: {:?}", ref_id, size);
+
+                    handle_ref.apply_size_limits(size);
+                    _ = handle_ref.get_window().request_inner_size(size);
+                }
+            }
+            WindowEvent::Position { ref_id, pos } => {
+                if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
+                    let pos = PhysicalPosition::new(pos.x as i32, pos.y as i32);
+
+                    let mut handle_ref = handle.lock();
+
+                    if let Some(state) = &mut handle_ref.windowed_state {
+                        state.pos = Some(pos);
+                        return;
+                    }
+
+                    let window = handle_ref.get_window();
+
+                    crate::dbg_log!("Window {} position: {:?}", ref_id, pos);
+                    window.set_outer_position(pos);
+                }
+            }
+            WindowEvent::Fullscreen { ref_id, fullscreen } => {
+                if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
+                    let mut handle_ref = handle.lock();
+                    let window = Arc::clone(handle_ref.get_window());
+
+                    crate::dbg_log!("Window {} fullscreen: {:?}", ref_id, fullscreen);
+
+                    if let Some(fullscreen) = fullscreen {
+                        if handle_ref.windowed_state.is_none() {
+                            handle_ref.windowed_state = Some(WindowedState {
+                                size: window.inner_size(),
+                                pos: window.outer_position().ok(),
+                                decorations: window.is_decorated(),
+                            });
+                        }
+
+                        let fullscreen = match fullscreen {
+                            crate::window::Fullscreen::Borderless(monitor) => Fullscreen::Borderless(
+                                monitor
+                                    .map(|monitor| monitor.handle)
+                                    .or_else(|| window.current_monitor()),
+                            ),
+                            crate::window::Fullscreen::Exclusive(mode) => {
+                                Fullscreen::Exclusive(mode.handle)
                             }
+                        };
+
+                        // The fixed size of the window would keep it from covering the monitor
+                        window.set_min_inner_size(None::<PhysicalSize<u32>>);
+                        window.set_max_inner_size(None::<PhysicalSize<u32>>);
+                        window.set_fullscreen(Some(fullscreen));
+                    } else if let Some(state) = handle_ref.windowed_state.take() {
+                        window.set_fullscreen(None);
+                        window.set_decorations(state.decorations);
+
+                        handle_ref.apply_size_limits(state.size);
+                        _ = window.request_inner_size(state.size);
+
+                        if let Some(pos) = state.pos {
+                            window.set_outer_position(pos);
+                        }
+                    }
+                }
+            }
+            WindowEvent::QueryMonitors => {
+                self.monitors = event_loop.available_monitors().collect();
+                self.primary_monitor = event_loop.primary_monitor();
+            }
+            WindowEvent::Visible { ref_id, visible } => {
+                if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
+                    let handle_ref = handle.lock();
+                    let window = handle_ref.get_window();
 
-                            let parent_window_handle = parent_window.unwrap().as_raw();
-                            window_attributes =
-                                window_attributes.with_parent_window(Some(parent_window_handle));
-                        }
-                    }
+                    crate::dbg_log!("Window {} visible: {}", ref_id, visible);
+                    window.set_visible(visible);
                 }
+            }
+            WindowEvent::ImeEnabled { ref_id, enabled } => {
+                if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
+                    let handle_ref = handle.lock();
 
-                let window = event_loop.create_window(window_attributes);
-
-                if let Ok(window) = window {
-                    let window_id = window.id();
-                    let handle = Handle::new(Arc::new(window));
-
-                    let window_handle = WindowHandle {
-                        window: ArcMut::new(handle),
-                        events: ArcRef::new(Vec::new()),
-                        ref_id,
-                    };
-
-                    crate::dbg_log!("Window {} created", ref_id);
-                    self.handles.insert(window_id, window_handle);
-                } else {
-                    crate::dbg_log!("Failed to create window: {:?}", window);
-                    self.last_error = Some(format!("Failed to create window: {:?}", window));
+                    crate::dbg_log!("Window {} IME enabled: {}", ref_id, enabled);
+                    handle_ref.get_window().set_ime_allowed(enabled);
                 }
             }
-            WindowEvent::Close { ref_id } => {
-                if self.handles.is_empty() {
-                    event_loop.exit();
+            WindowEvent::ImeCursorArea { ref_id, pos, size } => {
+                if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
+                    let handle_ref = handle.lock();
 
-                    return;
+                    handle_ref.get_window().set_ime_cursor_area(
+                        PhysicalPosition::new(pos.x, pos.y),
+                        physical_size(size),
+                    );
                 }
+            }
+            WindowEvent::Resizable { ref_id, resizable } => {
+                if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
+                    let mut handle_ref = handle.lock();
+                    handle_ref.resizable = resizable;
 
-                let mut to_remove = None;
+                    crate::dbg_log!("Window {} resizable: {}", ref_id, resizable);
 
-                for (window_id, handle) in &self.handles {
-                    if handle.ref_id == ref_id {
-                        to_remove = Some(*window_id);
-                        break;
+                    // Applied when leaving fullscreen
+                    if handle_ref.windowed_state.is_some() {
+                        return;
                     }
+
+                    let window = handle_ref.get_window();
+                    window.set_resizable(resizable);
+                    handle_ref.apply_size_limits(window.inner_size());
                 }
+            }
+            WindowEvent::Decorations {
+                ref_id,
+                decorations,
+            } => {
+                if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
+                    let mut handle_ref = handle.lock();
 
-                if let Some(window_id) = to_remove {
-                    if let Some(handle) = self.handles.get_mut(&window_id) {
-                        handle.window.lock().close();
+                    crate::dbg_log!("Window {} decorations: {}", ref_id, decorations);
+
+                    if let Some(state) = &mut handle_ref.windowed_state {
+                        state.decorations = decorations;
+                        return;
                     }
 
-                    crate::dbg_log!("Window {} closed", ref_id);
-                    self.handles.remove(&window_id);
+                    handle_ref.get_window().set_decorations(decorations);
                 }
+            }
+            WindowEvent::AlwaysOnTop {
+                ref_id,
+                always_on_top,
+            } => {
+                if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
+                    let handle_ref = handle.lock();
 
-                if self.handles.is_empty() {
-                    crate::dbg_log!("All windows closed, exiting event loop");
-                    event_loop.exit();
+                    crate::dbg_log!("Window {} always on top: {}", ref_id, always_on_top);
+                    handle_ref
+                        .get_window()
+                        .set_window_level(window_level(always_on_top));
                 }
             }
-            WindowEvent::Title { ref_id, title } => {
+            WindowEvent::MinSize { ref_id, size } => {
                 if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
-                    let window = handle.lock();
-                    let window = window.get_window();
-
-                    crate::dbg_log!("Window {} title: {}", ref_id, title);
+                    let mut handle_ref = handle.lock();
+                    handle_ref.min_size = size.map(physical_size);
 
-                    window.set_title(title.as_str());
+                    if handle_ref.resizable && handle_ref.windowed_state.is_none() {
+                        let size = handle_ref.get_window().inner_size();
+                        handle_ref.apply_size_limits(size);
+                    }
                 }
             }
-            WindowEvent::Size { ref_id, size } => {
+            WindowEvent::MaxSize { ref_id, size } => {
                 if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
-                    let size: PhysicalSize<u32> = size.into();
+                    let mut handle_ref = handle.lock();
+                    handle_ref.max_size = size.map(physical_size);
 
+                    if handle_ref.resizable && handle_ref.windowed_state.is_none() {
+                        let size = handle_ref.get_window().inner_size();
+                        handle_ref.apply_size_limits(size);
+                    }
+                }
+            }
+            WindowEvent::Maximized { ref_id, maximized } => {
+                if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
                     let handle_ref = handle.lock();
-                    let window = handle_ref.get_window();
-
-                    crate::dbg_log!("Window {} size: {:?}", ref_id, size);
 
-                    window.set_max_inner_size(Some(size));
-                    window.set_min_inner_size(Some(size));
-                    _ = window.request_inner_size(size);
+                    crate::dbg_log!("Window {} maximized: {}", ref_id, maximized);
+                    handle_ref.get_window().set_maximized(maximized);
                 }
             }
-            WindowEvent::Position { ref_id, pos } => {
+            WindowEvent::Redraw { ref_id } => {
                 if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
-                    let pos = PhysicalPosition::new(pos.x as i32, pos.y as i32);
-
                     let handle_ref = handle.lock();
                     let window = handle_ref.get_window();
 
-                    crate::dbg_log!("Window {} position: {:?}", ref_id, pos);
-                    window.set_outer_position(pos);
+                    window.request_redraw();
                 }
             }
-            WindowEvent::Visible { ref_id, visible } => {
+            WindowEvent::CursorGrab { ref_id, mode } => {
                 if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
                     let handle_ref = handle.lock();
                     let window = handle_ref.get_window();
 
-                    crate::dbg_log!("Window {} visible: {}", ref_id, visible);
-                    window.set_visible(visible);
+                    // Platforms only support one of the grab modes, fall back to the other one:
+                    // Windows and X11 can't lock the cursor, macOS can't confine it.
+                    let (mode, fallback) = match mode {
+                        CursorGrabMode::None => (WinitCursorGrabMode::None, None),
+                        CursorGrabMode::Confined => (
+                            WinitCursorGrabMode::Confined,
+                            Some(WinitCursorGrabMode::Locked),
+                        ),
+                        CursorGrabMode::Locked => (
+                            WinitCursorGrabMode::Locked,
+                            Some(WinitCursorGrabMode::Confined),
+                        ),
+                    };
+
+                    crate::dbg_log!("Window {} cursor grab: {:?}", ref_id, mode);
+
+                    let result = window.set_cursor_grab(mode).or_else(|err| match fallback {
+                        Some(fallback) => window.set_cursor_grab(fallback),
+                        None => Err(err),
+                    });
+
+                    if let Err(err) = result {
+                        crate::warn_log!("Failed to grab the cursor of window {}: {}", ref_id, err);
+                    }
                 }
             }
-            WindowEvent::Redraw { ref_id } => {
+            WindowEvent::CursorVisible { ref_id, visible } => {
                 if let Some(handle) = self.get_window_handle_by_ref(ref_id) {
                     let handle_ref = handle.lock();
-                    let window = handle_ref.get_window();
 
-                    window.request_redraw();
+                    crate::dbg_log!("Window {} cursor visible: {}", ref_id, visible);
+                    handle_ref.get_window().set_cursor_visible(visible);
                 }
             }
             WindowEvent::Cursor { ref_id, cursor } => {
@@ -909,55 +1569,6 @@ fn decode_cursor(cursor: CustomCursorItem) -> Result<CustomCursorSource, String>
     Ok(cursor)
 }
 
-pub(crate) fn named_key_to_str(key: &NamedKey) -> Option<SmolStr> {
-    match key {
-        NamedKey::Alt => Some(SmolStr::new("Alt")),
-        NamedKey::AltGraph => Some(SmolStr::new("AltGraph")),
-        NamedKey::CapsLock => Some(SmolStr::new("CapsLock")),
-        NamedKey::Control => Some(SmolStr::new("Control")),
-        NamedKey::Fn => Some(SmolStr::new("Fn")),
-        NamedKey::FnLock => Some(SmolStr::new("FnLock")),
-        NamedKey::NumLock => Some(SmolStr::new("NumLock")),
-        NamedKey::ScrollLock => Some(SmolStr::new("ScrollLock")),
-        NamedKey::Shift => Some(SmolStr::new("Shift")),
-        NamedKey::Symbol => Some(SmolStr::new("Symbol")),
-        NamedKey::SymbolLock => Some(SmolStr::new("SymbolLock")),
-        NamedKey::Meta => Some(SmolStr::new("Meta")),
-        NamedKey::Hyper => Some(SmolStr::new("Hyper")),
-        NamedKey::Super => Some(SmolStr::new("Super")),
-        NamedKey::Enter => Some(SmolStr::new("Enter")),
-        NamedKey::Tab => Some(SmolStr::new("Tab")),
-        NamedKey::Space => Some(SmolStr::new("Space")),
-        NamedKey::ArrowDown => Some(SmolStr::new("ArrowDown")),
-        NamedKey::ArrowLeft => Some(SmolStr::new("ArrowLeft")),
-        NamedKey::ArrowRight => Some(SmolStr::new("ArrowRight")),
-        NamedKey::ArrowUp => Some(SmolStr::new("ArrowUp")),
-        NamedKey::End => Some(SmolStr::new("End")),
-        NamedKey::Home => Some(SmolStr::new("Home")),
-        NamedKey::PageDown => Some(SmolStr::new("PageDown")),
-        NamedKey::PageUp => Some(SmolStr::new("PageUp")),
-        NamedKey::Backspace => Some(SmolStr::new("Backspace")),
-        NamedKey::Clear => Some(SmolStr::new("Clear")),
-        NamedKey::Delete => Some(SmolStr::new("Delete")),
-        NamedKey::Insert => Some(SmolStr::new("Insert")),
-        NamedKey::Escape => Some(SmolStr::new("Escape")),
-        NamedKey::Pause => Some(SmolStr::new("Pause")),
-        NamedKey::F1 => Some(SmolStr::new("F1")),
-        NamedKey::F2 => Some(SmolStr::new("F2")),
-        NamedKey::F3 => Some(SmolStr::new("F3")),
-        NamedKey::F4 => Some(SmolStr::new("F4")),
-        NamedKey::F5 => Some(SmolStr::new("F5")),
-        NamedKey::F6 => Some(SmolStr::new("F6")),
-        NamedKey::F7 => Some(SmolStr::new("F7")),
-        NamedKey::F8 => Some(SmolStr::new("F8")),
-        NamedKey::F9 => Some(SmolStr::new("F9")),
-        NamedKey::F10 => Some(SmolStr::new("F10")),
-        NamedKey::F11 => Some(SmolStr::new("F11")),
-        NamedKey::F12 => Some(SmolStr::new("F12")),
-        _ => None,
-    }
-}
-
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum PumpMode {
     /// The event loop will poll for events and return immediately.
@@ -969,6 +1580,142 @@ pub enum PumpMode {
     WaitDraw,
 }
 
+/// The phase of an [Event::Touch].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    /// The touch was interrupted by the system, eg. when the window lost focus.
+    Cancelled,
+}
+
+/// A gesture of [Event::Gesture], with the change since the last one.
+#[derive(Debug, Clone, Copy)]
+pub enum Gesture {
+    /// Fingers moved apart (positive) or together (negative), `delta` is the change of the
+    /// scale, eg. 0.1 when the distance between the fingers grew by 10%.
+    Pinch { delta: f32 },
+    /// Fingers rotated, `delta` is in degrees and positive counter clockwise.
+    Rotate { delta: f32 },
+    /// Fingers moved together, `delta_x` and `delta_y` are in pixels.
+    Pan { delta_x: f32, delta_y: f32 },
+}
+
+impl Gesture {
+    fn values(&self) -> (u8, f32, f32) {
+        match self {
+            Gesture::Pinch { delta } => (0, *delta, 0.0),
+            Gesture::Rotate { delta } => (1, *delta, 0.0),
+            Gesture::Pan { delta_x, delta_y } => (2, *delta_x, *delta_y),
+        }
+    }
+}
+
+impl PartialEq for Gesture {
+    fn eq(&self, other: &Self) -> bool {
+        // use near equality for floating point comparison
+        let (kind, x, y) = self.values();
+        let (other_kind, other_x, other_y) = other.values();
+
+        kind == other_kind && (x - other_x).abs() < f32::EPSILON && (y - other_y).abs() < f32::EPSILON
+    }
+}
+
+impl Eq for Gesture {}
+
+impl PartialOrd for Gesture {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Gesture {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let (kind, x, y) = self.values();
+        let (other_kind, other_x, other_y) = other.values();
+
+        kind.cmp(&other_kind)
+            .then(x.partial_cmp(&other_x).unwrap_or(std::cmp::Ordering::Equal))
+            .then(y.partial_cmp(&other_y).unwrap_or(std::cmp::Ordering::Equal))
+    }
+}
+
+/// The raw mouse motion of [Event::MouseMotionRaw], positive `delta_y` is downward.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MouseMotionDelta {
+    pub delta_x: f32,
+    pub delta_y: f32,
+}
+
+impl PartialEq for MouseMotionDelta {
+    fn eq(&self, other: &Self) -> bool {
+        // use near equality for floating point comparison
+        (self.delta_x - other.delta_x).abs() < f32::EPSILON
+            && (self.delta_y - other.delta_y).abs() < f32::EPSILON
+    }
+}
+
+impl Eq for MouseMotionDelta {}
+
+impl PartialOrd for MouseMotionDelta {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MouseMotionDelta {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.delta_x
+            .partial_cmp(&other.delta_x)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(
+                self.delta_y
+                    .partial_cmp(&other.delta_y)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            )
+    }
+}
+
+impl From<MouseMotionDelta> for Vector2 {
+    fn from(delta: MouseMotionDelta) -> Self {
+        Vector2::new(delta.delta_x, delta.delta_y)
+    }
+}
+
+/// The value of a gamepad axis in [Event::GamepadAxis].
+#[derive(Debug, Clone, Copy)]
+pub struct GamepadAxisValue(pub f32);
+
+impl PartialEq for GamepadAxisValue {
+    fn eq(&self, other: &Self) -> bool {
+        // use near equality for floating point comparison
+        (self.0 - other.0).abs() < f32::EPSILON
+    }
+}
+
+impl Eq for GamepadAxisValue {}
+
+impl PartialOrd for GamepadAxisValue {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GamepadAxisValue {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0
+            .partial_cmp(&other.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    }
+}
+
+impl From<GamepadAxisValue> for f32 {
+    fn from(value: GamepadAxisValue) -> Self {
+        value.0
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum MouseScrollDelta {
     LineDelta { delta_x: f32, delta_y: f32 },
@@ -1127,6 +1874,20 @@ pub enum Event {
         /// The new size of the window in pixels.
         size: Point2,
     },
+    /// Happen when the scale factor of the window changes, eg. when it is moved to a monitor
+    /// with a different DPI. The value can also be read with [Window::scale_factor].
+    ///
+    /// The swapchain is reconfigured automatically. Fonts loaded with
+    /// [FontManager::load_font_scaled](crate::font::FontManager::load_font_scaled) should be
+    /// refreshed with [FontManager::update_scaled_font](crate::font::FontManager::update_scaled_font).
+    WindowScaleFactorChanged {
+        /// The ID of the window that was closed, which can be used to identify the window in the application.
+        ///
+        /// The window ID can be obtained from the [Window] instance using the [Window::id] method.
+        window_id: usize,
+        /// The new scale factor, the ratio between physical pixels and logical units.
+        scale: ScaleFactor,
+    },
     /// Happen when the window is moved.
     WindowMoved {
         /// The ID of the window that was closed, which can be used to identify the window in the application.
@@ -1136,6 +1897,18 @@ pub enum Event {
         /// The new position of the window in pixels.
         pos: Point2,
     },
+    /// Happen when the window enters, leaves or switches fullscreen mode with
+    /// [Window::set_fullscreen] or [Window::set_borderless_fullscreen].
+    ///
+    /// The new size is reported by a following [Event::WindowResized].
+    WindowFullscreenChanged {
+        /// The ID of the window that was closed, which can be used to identify the window in the application.
+        ///
+        /// The window ID can be obtained from the [Window] instance using the [Window::id] method.
+        window_id: usize,
+        /// True when the window is now fullscreen.
+        fullscreen: bool,
+    },
     /// Happen when the cursor enters the window.
     CursorEntered {
         /// The ID of the window that was closed, which can be used to identify the window in the application.
@@ -1159,6 +1932,42 @@ pub enum Event {
         /// The new position of the cursor in pixels.
         pos: Point2, // Position in pixels
     },
+    /// Happen when the mouse moves, with the raw motion of the device since the last
+    /// [Runner::pump_events].
+    ///
+    /// Unlike [Event::CursorMoved] the motion isn't stopped by the edges of the window or screen, and
+    /// is still reported while the cursor is locked with [Window::set_cursor_grab]. It's only
+    /// reported while a window of the application is focused, and isn't scaled by the cursor
+    /// acceleration of the system on most platforms.
+    MouseMotionRaw {
+        /// The motion in device units, usually close to pixels.
+        delta: MouseMotionDelta,
+    },
+    /// Happen when a finger touches, moves on or leaves a touch screen.
+    Touch {
+        /// The ID of the window that was closed, which can be used to identify the window in the application.
+        ///
+        /// The window ID can be obtained from the [Window] instance using the [Window::id] method.
+        window_id: usize,
+        /// The ID of the finger, unique while it touches the screen.
+        id: u64,
+        /// Whether the touch started, moved, ended or was cancelled.
+        phase: TouchPhase,
+        /// The position of the finger in pixels.
+        pos: Point2,
+    },
+    /// Happen when a pinch, rotate or pan gesture is made.
+    ///
+    /// Gestures are recognized from two finger [Event::Touch] movements, and reported directly
+    /// by touchpads on macOS.
+    Gesture {
+        /// The ID of the window that was closed, which can be used to identify the window in the application.
+        ///
+        /// The window ID can be obtained from the [Window] instance using the [Window::id] method.
+        window_id: usize,
+        /// The gesture that was made.
+        gesture: Gesture,
+    },
     /// Happen when the mouse wheel is scrolled.
     MouseWheel {
         /// The ID of the window that was closed, which can be used to identify the window in the application.
@@ -1200,10 +2009,83 @@ pub enum Event {
         ///
         /// The key string can be modifier keys like "Alt", "Control", "Shift", etc.
         /// Which where the cases like `a` can be `A`.
+        ///
+        /// Prefer matching `code` or `logical_key`, the string form is kept for keys they don't
+        /// identify, eg. "virtual-key:..." on Windows.
         key: SmolStr,
+        /// The physical key, the same regardless of the keyboard layout.
+        code: KeyCode,
+        /// The logical key, with the keyboard layout and modifiers applied.
+        logical_key: input::Key,
+        /// The modifier keys held when the key was pressed or released.
+        modifiers: Modifiers,
         /// Whether the key was pressed or released.
         pressed: bool, // true if pressed, false if released
     },
+    /// Happen when the text being composed with an input method changes, see [Window::set_ime_enabled].
+    ///
+    /// The text should be drawn in place of the text field's cursor until it's committed with
+    /// [Event::ImeCommit]. An empty text means the composition was cleared.
+    ImePreedit {
+        /// The ID of the window that was closed, which can be used to identify the window in the application.
+        ///
+        /// The window ID can be obtained from the [Window] instance using the [Window::id] method.
+        window_id: usize,
+        /// The text being composed.
+        text: String,
+        /// The byte range of the cursor in `text`, [None] if the cursor should be hidden.
+        cursor: Option<(usize, usize)>,
+    },
+    /// Happen when the text composed with an input method is committed and should be inserted
+    /// in the text field.
+    ImeCommit {
+        /// The ID of the window that was closed, which can be used to identify the window in the application.
+        ///
+        /// The window ID can be obtained from the [Window] instance using the [Window::id] method.
+        window_id: usize,
+        /// The committed text.
+        text: String,
+    },
+    /// Happen when a gamepad is connected, requires the `gamepad` feature.
+    ///
+    /// Gamepads are polled by [Runner::pump_events], [PumpMode::Wait] only returns once a window
+    /// event arrives so games should use [PumpMode::Poll].
+    GamepadConnected {
+        /// The ID of the gamepad, reused if the same gamepad is connected again.
+        gamepad_id: usize,
+        /// The name of the gamepad reported by the system.
+        name: String,
+    },
+    /// Happen when a gamepad is disconnected, requires the `gamepad` feature.
+    GamepadDisconnected {
+        /// The ID of the gamepad.
+        gamepad_id: usize,
+    },
+    /// Happen when a gamepad button is pressed or released, requires the `gamepad` feature.
+    GamepadButton {
+        /// The ID of the gamepad.
+        gamepad_id: usize,
+        /// The button that was pressed or released.
+        ///
+        /// Either "South", "East", "North", "West", "C", "Z", "LeftTrigger", "LeftTrigger2",
+        /// "RightTrigger", "RightTrigger2", "Select", "Start", "Mode", "LeftThumb", "RightThumb",
+        /// "DPadUp", "DPadDown", "DPadLeft" or "DPadRight".
+        button: SmolStr,
+        /// Whether the button was pressed or released.
+        pressed: bool,
+    },
+    /// Happen when a gamepad axis moves, requires the `gamepad` feature.
+    GamepadAxis {
+        /// The ID of the gamepad.
+        gamepad_id: usize,
+        /// The axis that moved.
+        ///
+        /// Either "LeftStickX", "LeftStickY", "LeftZ", "RightStickX", "RightStickY", "RightZ",
+        /// "DPadX" or "DPadY", and "LeftTrigger2" or "RightTrigger2" for analog triggers.
+        axis: SmolStr,
+        /// The new value, in the range [-1.0, 1.0] for sticks and [0.0, 1.0] for triggers.
+        value: GamepadAxisValue,
+    },
     /// Happen when a drag and drop event occurs in the window.
     DragAndDrop {
         /// The ID of the window that was closed, which can be used to identify the window in the application.
@@ -1213,6 +2095,16 @@ pub enum Event {
         /// The drag and drop event that occurred.
         event: DragAndDropEvent,
     },
+    /// Happen when the GPU device of the window was lost (eg. driver reset or update) and has been re-created.
+    ///
+    /// Textures, buffers, shaders and pipelines are re-created automatically but the contents of
+    /// textures and buffers are lost, so the application must upload its data again.
+    DeviceRestored {
+        /// The ID of the window whose GPU was restored, which can be used to identify the window in the application.
+        ///
+        /// The window ID can be obtained from the [Window] instance using the [Window::id] method.
+        window_id: usize,
+    },
 }
 
 #[allow(dead_code)]
@@ -1224,6 +2116,7 @@ pub(crate) enum WindowEvent {
         title: String,
         size: Point2,
         pos: Option<Point2>,
+        options: WindowOptions,
     },
     Close {
         ref_id: usize,
@@ -1236,6 +2129,14 @@ pub(crate) enum WindowEvent {
         ref_id: usize,
         cursor: Option<CursorIcon>,
     },
+    CursorGrab {
+        ref_id: usize,
+        mode: CursorGrabMode,
+    },
+    CursorVisible {
+        ref_id: usize,
+        visible: bool,
+    },
     Size {
         ref_id: usize,
         size: Point2,
@@ -1248,6 +2149,44 @@ pub(crate) enum WindowEvent {
         ref_id: usize,
         visible: bool,
     },
+    Resizable {
+        ref_id: usize,
+        resizable: bool,
+    },
+    Decorations {
+        ref_id: usize,
+        decorations: bool,
+    },
+    AlwaysOnTop {
+        ref_id: usize,
+        always_on_top: bool,
+    },
+    MinSize {
+        ref_id: usize,
+        size: Option<Point2>,
+    },
+    MaxSize {
+        ref_id: usize,
+        size: Option<Point2>,
+    },
+    Maximized {
+        ref_id: usize,
+        maximized: bool,
+    },
+    ImeEnabled {
+        ref_id: usize,
+        enabled: bool,
+    },
+    ImeCursorArea {
+        ref_id: usize,
+        pos: Point2,
+        size: Point2,
+    },
+    Fullscreen {
+        ref_id: usize,
+        fullscreen: Option<crate::window::Fullscreen>,
+    },
+    QueryMonitors,
     Redraw {
         ref_id: usize,
     },
@@ -1259,6 +2198,23 @@ pub(crate) enum WindowEvent {
 //     Buffer(Vec<u8>),
 // }
 
+/// How the cursor is held by a window, set with [Window::set_cursor_grab].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum CursorGrabMode {
+    /// The cursor moves freely.
+    #[default]
+    None,
+    /// The cursor can't leave the window but still moves inside it.
+    ///
+    /// Falls back to [CursorGrabMode::Locked] on macOS.
+    Confined,
+    /// The cursor stays where it is, movement is only reported by [Event::MouseMotionRaw].
+    ///
+    /// Falls back to [CursorGrabMode::Confined] on Windows and X11, hide the cursor with
+    /// [Window::set_cursor_visible] and keep using [Event::MouseMotionRaw].
+    Locked,
+}
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum CursorIcon {
     Default,
@@ -1349,9 +2305,77 @@ impl Into<Cursor> for CursorIcon {
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum RunnerError {
+    /// The runner was created on another thread than the first one.
     ThreadMissmatch,
     WinitEventLoopPanic,
     WinitEventLoopFailed,
     MaximumWindowReached,
-    FailedToCreateWindow(String),
+    FailedToCreateWindow(WindowCreateError),
+}
+
+impl std::fmt::Display for RunnerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunnerError::ThreadMissmatch => {
+                write!(f, "Runner must be used on the thread it was created on")
+            }
+            RunnerError::WinitEventLoopPanic => write!(f, "Event loop panicked on creation"),
+            RunnerError::WinitEventLoopFailed => write!(f, "Failed to create the event loop"),
+            RunnerError::MaximumWindowReached => write!(f, "Maximum number of windows reached"),
+            RunnerError::FailedToCreateWindow(e) => write!(f, "Failed to create window: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RunnerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RunnerError::FailedToCreateWindow(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "clipboard")]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ClipboardError {
+    /// The system clipboard couldn't be opened.
+    Unavailable(String),
+    /// The clipboard is empty or doesn't hold content of the requested kind.
+    Empty,
+    /// Another application is holding the clipboard, trying again later may succeed.
+    Occupied,
+    /// The image size doesn't match its pixels, or the content couldn't be converted.
+    InvalidData,
+    Failed(String),
+}
+
+#[cfg(feature = "clipboard")]
+impl From<arboard::Error> for ClipboardError {
+    fn from(err: arboard::Error) -> Self {
+        match err {
+            arboard::Error::ContentNotAvailable => ClipboardError::Empty,
+            arboard::Error::ClipboardNotSupported => {
+                ClipboardError::Unavailable(err.to_string())
+            }
+            arboard::Error::ClipboardOccupied => ClipboardError::Occupied,
+            arboard::Error::ConversionFailure => ClipboardError::InvalidData,
+            err => ClipboardError::Failed(err.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "clipboard")]
+impl std::fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClipboardError::Unavailable(reason) => {
+                write!(f, "Clipboard is unavailable: {}", reason)
+            }
+            ClipboardError::Empty => write!(f, "Clipboard has no content of the requested kind"),
+            ClipboardError::Occupied => write!(f, "Clipboard is occupied by another application"),
+            ClipboardError::InvalidData => write!(f, "Invalid clipboard data"),
+            ClipboardError::Failed(reason) => write!(f, "Clipboard operation failed: {}", reason),
+        }
+    }
 }
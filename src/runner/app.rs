@@ -0,0 +1,144 @@
+use super::{Event, PumpMode, Runner};
+
+#[cfg(target_arch = "wasm32")]
+use super::WindowEvent;
+
+#[cfg(target_arch = "wasm32")]
+use winit::{
+    application::ApplicationHandler,
+    event,
+    event_loop::{ActiveEventLoop, ControlFlow},
+    window::WindowId,
+};
+
+/// An application driven by [Runner::run], as an alternative to pumping the events manually.
+///
+/// Every method has a default implementation, so only the needed ones have to be written.
+///
+/// ```rust,ignore
+/// struct Game {
+///     window: Window,
+///     gpu: GPU,
+/// }
+///
+/// impl Application for Game {
+///     fn update(&mut self, _runner: &mut Runner, dt: f64) {
+///         // Move things by `dt` seconds
+///     }
+///
+///     fn render(&mut self, _runner: &mut Runner, window_id: usize) {
+///         if window_id != self.window.id() {
+///             return;
+///         }
+///
+///         if let Ok(mut cmd) = self.gpu.begin_command() {
+///             // Draw the frame
+///         }
+///     }
+/// }
+///
+/// let mut runner = est_render::runner::new()?;
+/// let mut window = runner.create_window("Game", Point2::new(800, 600)).build()?;
+/// let gpu = est_render::gpu::new(Some(&mut window)).build()?;
+///
+/// runner.run(Game { window, gpu });
+/// ```
+pub trait Application {
+    /// Called for every event, before [Application::update].
+    fn on_event(&mut self, runner: &mut Runner, event: &Event) {
+        _ = (runner, event);
+    }
+
+    /// Called once per frame with the time since the last frame in seconds.
+    fn update(&mut self, runner: &mut Runner, dt: f64) {
+        _ = (runner, dt);
+    }
+
+    /// Called when a window should be redrawn, after [Application::update].
+    ///
+    /// Windows are redrawn continuously, unless [Application::pump_mode] returns
+    /// [PumpMode::Wait] where they are only redrawn when the system or
+    /// [Window::request_redraw](crate::window::Window::request_redraw) asks for it.
+    fn render(&mut self, runner: &mut Runner, window_id: usize) {
+        _ = (runner, window_id);
+    }
+
+    /// How [Runner::run] waits for events, [PumpMode::WaitDraw] on macOS where drawing outside
+    /// of a redraw is unreliable, [PumpMode::Poll] elsewhere.
+    fn pump_mode(&self) -> PumpMode {
+        if cfg!(target_os = "macos") {
+            PumpMode::WaitDraw
+        } else {
+            PumpMode::Poll
+        }
+    }
+}
+
+/// Drives an [Application] from the browser event loop spawned by [Runner::run].
+#[cfg(target_arch = "wasm32")]
+pub(crate) struct WebApplication<A: Application> {
+    pub runner: Runner,
+    pub app: A,
+    pub mode: PumpMode,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl<A: Application> ApplicationHandler<WindowEvent> for WebApplication<A> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        // Frames come from the redraws requested after each render
+        event_loop.set_control_flow(ControlFlow::Wait);
+
+        for handle in self.runner.app_runner.handles.values() {
+            if let Some(window) = handle.window.lock().window.as_ref() {
+                window.request_redraw();
+            }
+        }
+
+        self.runner.app_runner.resumed(event_loop);
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: event::WindowEvent,
+    ) {
+        self.runner
+            .app_runner
+            .window_event(event_loop, window_id, event);
+    }
+
+    fn device_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        device_id: event::DeviceId,
+        event: event::DeviceEvent,
+    ) {
+        self.runner
+            .app_runner
+            .device_event(event_loop, device_id, event);
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: WindowEvent) {
+        self.runner.app_runner.user_event(event_loop, event);
+    }
+
+    // Called once the browser has delivered a batch of events, eg. the animation frame
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        // The events are kept for the next frame, which the redraw keeps coming
+        if !self.runner.rate_timing.is_frame_due() {
+            for handle in self.runner.app_runner.handles.values() {
+                if let Some(window) = handle.window.lock().window.as_ref() {
+                    window.request_redraw();
+                }
+            }
+
+            return;
+        }
+
+        self.runner.begin_frame();
+        self.runner.collect_events(0);
+
+        self.runner.run_frame(&mut self.app, self.mode);
+    }
+}
@@ -0,0 +1,169 @@
+//! Gamepad input using gilrs, polled together with the window events in
+//! [Runner::pump_events](super::Runner::pump_events).
+//!
+//! Gamepads are reported with [Event::GamepadConnected], [Event::GamepadDisconnected],
+//! [Event::GamepadButton] and [Event::GamepadAxis]. Gamepads already connected when the
+//! [Runner](super::Runner) is created are reported as connected on the first pump.
+//!
+//! Buttons and axes use the names of a standard controller layout where `South`, `East`,
+//! `North` and `West` are the face buttons (`A`, `B`, `Y` and `X` on an Xbox controller).
+
+use smol_str::SmolStr;
+
+use super::{Event, GamepadAxisValue};
+
+/// A connected gamepad, listed with [Runner::gamepads](super::Runner::gamepads).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gamepad {
+    /// The ID used by the gamepad events, reused if the same gamepad is connected again.
+    pub id: usize,
+    pub name: String,
+}
+
+pub(crate) struct GamepadInner {
+    gilrs: gilrs::Gilrs,
+    pending_connected: Vec<usize>,
+}
+
+impl GamepadInner {
+    /// Returns [None] when the platform has no gamepad support, eg. udev is unavailable on Linux.
+    pub fn new() -> Option<Self> {
+        match gilrs::Gilrs::new() {
+            Ok(gilrs) => {
+                let pending_connected = gilrs.gamepads().map(|(id, _)| usize::from(id)).collect();
+
+                Some(Self {
+                    gilrs,
+                    pending_connected,
+                })
+            }
+            Err(err) => {
+                crate::warn_log!("Gamepad support is unavailable: {}", err);
+                None
+            }
+        }
+    }
+
+    pub fn gamepads(&self) -> Vec<Gamepad> {
+        self.gilrs
+            .gamepads()
+            .map(|(id, gamepad)| Gamepad {
+                id: usize::from(id),
+                name: gamepad.name().to_string(),
+            })
+            .collect()
+    }
+
+    pub fn poll(&mut self, events: &mut Vec<Event>) {
+        for gamepad_id in self.pending_connected.drain(..) {
+            let name = self
+                .gilrs
+                .gamepads()
+                .find(|(id, _)| usize::from(*id) == gamepad_id)
+                .map(|(_, gamepad)| gamepad.name().to_string())
+                .unwrap_or_default();
+
+            events.push(Event::GamepadConnected { gamepad_id, name });
+        }
+
+        while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+            let gamepad_id = usize::from(id);
+
+            match event {
+                gilrs::EventType::Connected => {
+                    let name = self.gilrs.gamepad(id).name().to_string();
+
+                    events.push(Event::GamepadConnected { gamepad_id, name });
+                }
+                gilrs::EventType::Disconnected => {
+                    events.push(Event::GamepadDisconnected { gamepad_id });
+                }
+                gilrs::EventType::ButtonPressed(button, _) => {
+                    if let Some(button) = button_to_str(button) {
+                        events.push(Event::GamepadButton {
+                            gamepad_id,
+                            button,
+                            pressed: true,
+                        });
+                    }
+                }
+                gilrs::EventType::ButtonReleased(button, _) => {
+                    if let Some(button) = button_to_str(button) {
+                        events.push(Event::GamepadButton {
+                            gamepad_id,
+                            button,
+                            pressed: false,
+                        });
+                    }
+                }
+                // Analog triggers also report how far they are pressed
+                gilrs::EventType::ButtonChanged(
+                    button @ (gilrs::Button::LeftTrigger2 | gilrs::Button::RightTrigger2),
+                    value,
+                    _,
+                ) => {
+                    if let Some(axis) = button_to_str(button) {
+                        events.push(Event::GamepadAxis {
+                            gamepad_id,
+                            axis,
+                            value: GamepadAxisValue(value),
+                        });
+                    }
+                }
+                gilrs::EventType::AxisChanged(axis, value, _) => {
+                    if let Some(axis) = axis_to_str(axis) {
+                        events.push(Event::GamepadAxis {
+                            gamepad_id,
+                            axis,
+                            value: GamepadAxisValue(value),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn button_to_str(button: gilrs::Button) -> Option<SmolStr> {
+    let name = match button {
+        gilrs::Button::South => "South",
+        gilrs::Button::East => "East",
+        gilrs::Button::North => "North",
+        gilrs::Button::West => "West",
+        gilrs::Button::C => "C",
+        gilrs::Button::Z => "Z",
+        gilrs::Button::LeftTrigger => "LeftTrigger",
+        gilrs::Button::LeftTrigger2 => "LeftTrigger2",
+        gilrs::Button::RightTrigger => "RightTrigger",
+        gilrs::Button::RightTrigger2 => "RightTrigger2",
+        gilrs::Button::Select => "Select",
+        gilrs::Button::Start => "Start",
+        gilrs::Button::Mode => "Mode",
+        gilrs::Button::LeftThumb => "LeftThumb",
+        gilrs::Button::RightThumb => "RightThumb",
+        gilrs::Button::DPadUp => "DPadUp",
+        gilrs::Button::DPadDown => "DPadDown",
+        gilrs::Button::DPadLeft => "DPadLeft",
+        gilrs::Button::DPadRight => "DPadRight",
+        gilrs::Button::Unknown => return None,
+    };
+
+    Some(SmolStr::new_static(name))
+}
+
+fn axis_to_str(axis: gilrs::Axis) -> Option<SmolStr> {
+    let name = match axis {
+        gilrs::Axis::LeftStickX => "LeftStickX",
+        gilrs::Axis::LeftStickY => "LeftStickY",
+        gilrs::Axis::LeftZ => "LeftZ",
+        gilrs::Axis::RightStickX => "RightStickX",
+        gilrs::Axis::RightStickY => "RightStickY",
+        gilrs::Axis::RightZ => "RightZ",
+        gilrs::Axis::DPadX => "DPadX",
+        gilrs::Axis::DPadY => "DPadY",
+        gilrs::Axis::Unknown => return None,
+    };
+
+    Some(SmolStr::new_static(name))
+}
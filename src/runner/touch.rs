@@ -0,0 +1,95 @@
+use crate::math::Vector2;
+
+use super::{Gesture, TouchPhase};
+
+/// Recognizes two finger pinch, rotate and pan gestures from the touches of a window.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GestureRecognizer {
+    touches: Vec<(u64, Vector2)>,
+}
+
+impl GestureRecognizer {
+    /// Tracks a touch and returns the gestures made by its movement.
+    pub fn update(&mut self, id: u64, phase: TouchPhase, pos: Vector2) -> Vec<Gesture> {
+        match phase {
+            TouchPhase::Started => {
+                self.touches.retain(|(touch_id, _)| *touch_id != id);
+                self.touches.push((id, pos));
+
+                Vec::new()
+            }
+            TouchPhase::Moved => {
+                let Some(index) = self
+                    .touches
+                    .iter()
+                    .position(|(touch_id, _)| *touch_id == id)
+                else {
+                    return Vec::new();
+                };
+
+                // Gestures are only made with exactly two fingers
+                if self.touches.len() != 2 {
+                    self.touches[index].1 = pos;
+                    return Vec::new();
+                }
+
+                let before = (self.touches[0].1, self.touches[1].1);
+                self.touches[index].1 = pos;
+                let after = (self.touches[0].1, self.touches[1].1);
+
+                Self::gestures(before, after)
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.retain(|(touch_id, _)| *touch_id != id);
+
+                Vec::new()
+            }
+        }
+    }
+
+    fn gestures(before: (Vector2, Vector2), after: (Vector2, Vector2)) -> Vec<Gesture> {
+        let mut gestures = Vec::new();
+
+        let center_before = (before.0 + before.1) * 0.5;
+        let center_after = (after.0 + after.1) * 0.5;
+        let pan = center_after - center_before;
+
+        if pan.x != 0.0 || pan.y != 0.0 {
+            gestures.push(Gesture::Pan {
+                delta_x: pan.x,
+                delta_y: pan.y,
+            });
+        }
+
+        let span_before = before.1 - before.0;
+        let span_after = after.1 - after.0;
+
+        let distance_before = span_before.length();
+        let distance_after = span_after.length();
+
+        if distance_before > 0.0 && distance_after != distance_before {
+            gestures.push(Gesture::Pinch {
+                delta: distance_after / distance_before - 1.0,
+            });
+        }
+
+        if distance_before > 0.0 && distance_after > 0.0 {
+            // Screen Y points down, so the angle is negated to be counter clockwise
+            let angle_before = span_before.y.atan2(span_before.x);
+            let angle_after = span_after.y.atan2(span_after.x);
+
+            let mut delta = -(angle_after - angle_before).to_degrees();
+            if delta > 180.0 {
+                delta -= 360.0;
+            } else if delta < -180.0 {
+                delta += 360.0;
+            }
+
+            if delta != 0.0 {
+                gestures.push(Gesture::Rotate { delta });
+            }
+        }
+
+        gestures
+    }
+}
@@ -1,20 +1,78 @@
-use std::{cell::RefCell, sync::Arc};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
 use std::time::{Duration, Instant};
 
-/// Custom ArcRef type that wraps a Arc with RefCell without
+/// Custom ArcRef type that wraps a Arc with RwLock without
 /// Making it difficult to type.
+///
+/// The value can be shared between threads when it is Send and Sync, a borrow
+/// from another thread blocks until the conflicting borrow is released.
+///
+/// Locks are taken in the order of their [LockRank]: a thread borrowing the graphics
+/// state must not borrow a texture, buffer, shader or pipeline until it released it,
+/// otherwise two threads taking them in the opposite order deadlock.
+///
+/// NOTE: In debug mode, the borrows held by each thread are tracked, and borrowing
+/// a value that conflicts with a borrow of the same thread, or out of the lock order,
+/// panics instead of deadlocking. Release builds only catch a value borrowed again
+/// while the same thread borrows it mutably.
 // #[derive(Clone)]
 pub struct ArcRef<T> {
-    inner: Arc<RefCell<T>>,
+    inner: Arc<Shared<T>>,
+}
+
+struct Shared<T> {
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    rank: Option<LockRank>,
+    /// [thread_id] of the thread borrowing the value mutably, 0 when there is none.
+    writer: AtomicU64,
+    lock: RwLock<T>,
+}
+
+/// Position of an [ArcRef] in the lock order, lower ranks are borrowed first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum LockRank {
+    /// Textures, buffers, shaders and pipelines.
+    Resource,
+    /// The graphics state, borrowed after the resources.
+    Graphics,
 }
 
 impl<T> ArcRef<T> {
     /// Create a new ArcRef with the given value.
     pub fn new(value: T) -> ArcRef<T> {
         ArcRef {
-            inner: Arc::new(RefCell::new(value)),
+            inner: Arc::new(Shared {
+                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+                rank: None,
+                writer: AtomicU64::new(0),
+                lock: RwLock::new(value),
+            }),
+        }
+    }
+
+    /// Create a new ArcRef taking part in the lock order with the given rank.
+    #[cfg_attr(
+        not(any(debug_assertions, feature = "enable-release-validation")),
+        expect(unused_variables, reason = "the rank is only checked with validation")
+    )]
+    pub(crate) fn new_ranked(value: T, rank: LockRank) -> ArcRef<T> {
+        ArcRef {
+            inner: Arc::new(Shared {
+                #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+                rank: Some(rank),
+                writer: AtomicU64::new(0),
+                lock: RwLock::new(value),
+            }),
         }
     }
 
@@ -25,75 +83,168 @@ impl<T> ArcRef<T> {
         }
     }
 
-    /// Borrow the value immutably. This will atomically increment the reference count.
+    /// Borrow the value immutably.
     /// If the value is already borrowed mutably, this will block until the mutable borrow is released.
-    /// NOTE: In debug mode, this will panic if the value is already borrowed immutably for more than 5 seconds.
-    pub fn wait_borrow(&self) -> std::cell::Ref<T> {
+    /// NOTE: In debug mode, this will panic if the value is already borrowed mutably for more than 5 seconds.
+    pub fn wait_borrow(&self) -> Ref<'_, T> {
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        self.check_borrow(false);
+
         #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
         let start = Instant::now();
 
         loop {
-            if let Ok(borrow) = self.inner.try_borrow() {
+            if let Some(borrow) = self.try_borrow() {
                 return borrow;
             }
 
+            self.check_writer();
+
             #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
             if start.elapsed() > Duration::from_secs(5) {
                 panic!("wait_borrow: waited more than 5 seconds to acquire immutable borrow");
             }
+
+            std::thread::yield_now();
         }
     }
 
-    /// Borrow the value mutably. This will atomically increment the reference count.
+    /// Borrow the value mutably.
     /// If the value is already borrowed, this will block until the borrow is released.
-    /// NOTE: In debug mode, this will panic if the value is already borrowed mutably for more than 5 seconds.
-    pub fn wait_borrow_mut(&self) -> std::cell::RefMut<T> {
+    /// NOTE: In debug mode, this will panic if the value is already borrowed for more than 5 seconds.
+    pub fn wait_borrow_mut(&self) -> RefMut<'_, T> {
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        self.check_borrow(true);
+
         #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
         let start = Instant::now();
 
         loop {
-            if let Ok(borrow) = self.inner.try_borrow_mut() {
+            if let Some(borrow) = self.try_borrow_mut() {
                 return borrow;
             }
 
+            self.check_writer();
+
             #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
             if start.elapsed() > Duration::from_secs(5) {
                 panic!("wait_borrow_mut: waited more than 5 seconds to acquire mutable borrow");
             }
+
+            std::thread::yield_now();
         }
     }
 
-    /// Try to borrow the value immutably. This will atomically increment the reference count.
-    /// Will panic if the value is already borrowed mutably.
-    pub fn borrow(&self) -> std::cell::Ref<T> {
-        self.inner.borrow()
+    /// Borrow the value immutably, blocking while another thread borrows it mutably.
+    /// Nested immutable borrows on the same thread never block.
+    /// NOTE: In debug mode, this will panic if the value is borrowed mutably on the same thread.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        self.check_borrow(false);
+
+        if let Some(borrow) = self.try_borrow() {
+            return borrow;
+        }
+
+        self.check_writer();
+
+        Ref {
+            guard: self.inner.lock.read_recursive(),
+            #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+            _held: Held::track(self, false),
+        }
     }
 
-    /// Try to borrow the value mutably. This will atomically increment the reference count.
-    /// Will panic if the value is already borrowed.
-    pub fn borrow_mut(&self) -> std::cell::RefMut<T> {
-        self.inner.borrow_mut()
+    /// Borrow the value mutably, blocking while another thread borrows it.
+    /// NOTE: In debug mode, this will panic if the value is borrowed on the same thread.
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+        self.check_borrow(true);
+
+        if let Some(borrow) = self.try_borrow_mut() {
+            return borrow;
+        }
+
+        self.check_writer();
+
+        let guard = self.inner.lock.write();
+        RefMut {
+            _writer: Writer::mark(&self.inner.writer),
+            guard,
+            #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+            _held: Held::track(self, true),
+        }
     }
 
-    /// Try to borrow the value immutably. This will atomically increment the reference count.
+    /// Try to borrow the value immutably.
     /// Will return None if the value is already borrowed mutably.
-    pub fn try_borrow(&self) -> Option<std::cell::Ref<T>> {
-        self.inner.try_borrow().ok()
+    pub fn try_borrow(&self) -> Option<Ref<'_, T>> {
+        Some(Ref {
+            guard: self.inner.lock.try_read_recursive()?,
+            #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+            _held: Held::track(self, false),
+        })
     }
 
-    /// Try to borrow the value mutably. This will atomically increment the reference count.
+    /// Try to borrow the value mutably.
     /// Will return None if the value is already borrowed.
-    pub fn try_borrow_mut(&self) -> Option<std::cell::RefMut<T>> {
-        self.inner.try_borrow_mut().ok()
+    pub fn try_borrow_mut(&self) -> Option<RefMut<'_, T>> {
+        let guard = self.inner.lock.try_write()?;
+        Some(RefMut {
+            _writer: Writer::mark(&self.inner.writer),
+            guard,
+            #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+            _held: Held::track(self, true),
+        })
+    }
+
+    /// Panics if the value is borrowed mutably by this thread, checked in every build once a
+    /// borrow failed so waiting for it would never end.
+    fn check_writer(&self) {
+        if self.inner.writer.load(Ordering::Relaxed) == thread_id() {
+            panic!(
+                "ArcRef<{}> is already borrowed mutably on this thread",
+                std::any::type_name::<T>()
+            );
+        }
+    }
+
+    /// Panics if borrowing the value would deadlock on a borrow held by this thread.
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    fn check_borrow(&self, mutable: bool) {
+        let lock = Arc::as_ptr(&self.inner) as usize;
+        let rank = self.inner.rank;
+
+        HELD.with_borrow(|held| {
+            for &(held_lock, held_mutable, held_rank) in held {
+                if held_lock == lock && (mutable || held_mutable) {
+                    panic!(
+                        "ArcRef<{}> is already borrowed{} on this thread",
+                        std::any::type_name::<T>(),
+                        if held_mutable { " mutably" } else { "" }
+                    );
+                }
+
+                if let (Some(rank), Some(held_rank)) = (rank, held_rank)
+                    && held_lock != lock
+                    && held_rank > rank
+                {
+                    panic!(
+                        "ArcRef<{}> borrowed while a {:?} lock is held, breaking the lock order",
+                        std::any::type_name::<T>(),
+                        held_rank
+                    );
+                }
+            }
+        });
     }
 
     /// Try to unwrap the ArcRef. This will return the inner value if there are no other references to it.
     /// If there are other references, this will return the ArcRef itself as an error.
     pub fn try_unwrap(self) -> Result<T, Self> {
-        let refcelled = Arc::try_unwrap(self.inner).map_err(|arc| ArcRef { inner: arc })?;
+        let shared = Arc::try_unwrap(self.inner).map_err(|arc| ArcRef { inner: arc })?;
 
-        let inner = refcelled.into_inner();
-        Ok(inner)
+        Ok(shared.lock.into_inner())
     }
 
     pub fn ptr_eq(&self, other: &Self) -> bool {
@@ -101,7 +252,154 @@ impl<T> ArcRef<T> {
     }
 
     pub fn as_ptr(_self: &Self) -> *const T {
-        Arc::as_ptr(&_self.inner) as *const T
+        _self.inner.lock.data_ptr() as *const T
+    }
+
+    /// Create a weak reference that doesn't keep the value alive.
+    pub fn downgrade(_self: &Self) -> WeakRef<T> {
+        WeakRef {
+            inner: Arc::downgrade(&_self.inner),
+        }
+    }
+}
+
+/// Returns a non-zero id unique to the calling thread.
+fn thread_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+    thread_local! {
+        static ID: u64 = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    }
+
+    ID.with(|id| *id)
+}
+
+/// Records the thread borrowing an [ArcRef] mutably, cleared before the borrow is released.
+struct Writer<'a>(&'a AtomicU64);
+
+impl<'a> Writer<'a> {
+    fn mark(writer: &'a AtomicU64) -> Writer<'a> {
+        writer.store(thread_id(), Ordering::Relaxed);
+        Writer(writer)
+    }
+}
+
+impl Drop for Writer<'_> {
+    fn drop(&mut self) {
+        self.0.store(0, Ordering::Relaxed);
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+thread_local! {
+    /// Lock address, mutability and rank of the borrows held by the thread.
+    static HELD: std::cell::RefCell<Vec<(usize, bool, Option<LockRank>)>> =
+        const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Entry of a borrow in [HELD], removed when the borrow is released.
+#[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+struct Held {
+    lock: usize,
+    mutable: bool,
+}
+
+#[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+impl Held {
+    fn track<T>(arc: &ArcRef<T>, mutable: bool) -> Held {
+        let lock = Arc::as_ptr(&arc.inner) as usize;
+        HELD.with_borrow_mut(|held| held.push((lock, mutable, arc.inner.rank)));
+
+        Held { lock, mutable }
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+impl Drop for Held {
+    fn drop(&mut self) {
+        // The thread local is gone when a borrow outlives it during thread exit
+        _ = HELD.try_with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(index) = held
+                .iter()
+                .rposition(|(lock, mutable, _)| *lock == self.lock && *mutable == self.mutable)
+            {
+                held.remove(index);
+            }
+        });
+    }
+}
+
+/// Immutable borrow of an [ArcRef], released when dropped.
+pub struct Ref<'a, T> {
+    guard: RwLockReadGuard<'a, T>,
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    _held: Held,
+}
+
+impl<T> Deref for Ref<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+/// Mutable borrow of an [ArcRef], released when dropped.
+pub struct RefMut<'a, T> {
+    // Dropped before the guard, another thread may take the lock right after
+    _writer: Writer<'a>,
+    guard: RwLockWriteGuard<'a, T>,
+    #[cfg(any(debug_assertions, feature = "enable-release-validation"))]
+    _held: Held,
+}
+
+impl<T> Deref for RefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for RefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+/// Weak counterpart of [ArcRef], created with [ArcRef::downgrade].
+pub struct WeakRef<T> {
+    inner: Weak<Shared<T>>,
+}
+
+impl<T> WeakRef<T> {
+    /// Returns the [ArcRef] if the value is still alive.
+    pub fn upgrade(&self) -> Option<ArcRef<T>> {
+        self.inner.upgrade().map(|inner| ArcRef { inner })
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.inner.strong_count() > 0
+    }
+
+    /// Returns true if both point to the same value, see [ArcRef::ptr_eq].
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Weak::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl<T> Clone for WeakRef<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for WeakRef<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WeakRef").finish()
     }
 }
 
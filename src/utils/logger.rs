@@ -1,9 +1,48 @@
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, OnceLock},
+};
+
+/// Number of log lines kept for the diagnostic bundles.
+const LOG_TAIL_CAPACITY: usize = 256;
+
+static LOG_TAIL: OnceLock<Mutex<VecDeque<String>>> = OnceLock::new();
+
+/// Keeps the line in the log tail included in the diagnostic bundles.
+#[doc(hidden)]
+pub fn record_log(level: &str, message: &str) {
+    let tail = LOG_TAIL.get_or_init(|| Mutex::new(VecDeque::with_capacity(LOG_TAIL_CAPACITY)));
+
+    // A poisoned tail only means another thread panicked while logging
+    let mut tail = tail.lock().unwrap_or_else(|e| e.into_inner());
+    if tail.len() == LOG_TAIL_CAPACITY {
+        tail.pop_front();
+    }
+
+    tail.push_back(format!("[{}]: {}", level, message));
+}
+
+/// Returns the last lines logged by the crate, oldest first.
+pub(crate) fn log_tail() -> Vec<String> {
+    match LOG_TAIL.get() {
+        Some(tail) => tail
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! log {
-    ($($arg:tt)*) => {
-        println!("[LOG]: {}", format!($($arg)*));
-    };
+    ($($arg:tt)*) => {{
+        let message = format!($($arg)*);
+        $crate::utils::record_log("LOG", &message);
+        println!("[LOG]: {}", message);
+    }};
 }
 
 #[macro_export]
@@ -18,15 +57,19 @@ macro_rules! dbg_log {
 #[macro_export]
 #[doc(hidden)]
 macro_rules! error_log {
-    ($($arg:tt)*) => {
-        eprintln!("[ERROR]: {}", format!($($arg)*));
-    };
+    ($($arg:tt)*) => {{
+        let message = format!($($arg)*);
+        $crate::utils::record_log("ERROR", &message);
+        eprintln!("[ERROR]: {}", message);
+    }};
 }
 
 #[macro_export]
 #[doc(hidden)]
 macro_rules! warn_log {
-    ($($arg:tt)*) => {
-        eprintln!("[WARNING]: {}", format!($($arg)*));
-    };
+    ($($arg:tt)*) => {{
+        let message = format!($($arg)*);
+        $crate::utils::record_log("WARNING", &message);
+        eprintln!("[WARNING]: {}", message);
+    }};
 }
@@ -2,7 +2,8 @@ mod arcmut;
 pub use arcmut::ArcMut;
 
 mod arcref;
-pub use arcref::ArcRef;
+pub use arcref::{ArcRef, Ref, RefMut, WeakRef};
+pub(crate) use arcref::LockRank;
 
 mod logger;
 #[allow(unused_imports)]
@@ -1,10 +1,14 @@
-use winit::{event, event_loop::EventLoopProxy};
+use winit::{
+    event,
+    event_loop::EventLoopProxy,
+    monitor::{MonitorHandle, VideoModeHandle},
+};
 
 #[cfg(feature = "software")]
 use crate::software::PixelBufferInner;
 
 use crate::{
-    gpu::GPUInner, math::Point2, runner::{CursorIcon, Handle, Runner, RunnerError, WindowEvent}, utils::{ArcMut, ArcRef}
+    gpu::GPUInner, input::Modifiers, math::Point2, runner::{touch::GestureRecognizer, CursorGrabMode, CursorIcon, Handle, Runner, RunnerError, WindowEvent}, utils::{ArcMut, ArcRef}
 };
 
 #[derive(Clone, Debug)]
@@ -19,6 +23,7 @@ impl Window {
         title: String,
         size: Point2,
         pos: Option<Point2>,
+        options: WindowOptions,
     ) -> Result<Self, WindowError> {
         let parent_id = if let Some(parent) = parent {
             Some(parent.inner.wait_borrow().window_id)
@@ -26,7 +31,7 @@ impl Window {
             None
         };
 
-        let result = runner.internal_new_window(parent_id, title, size, pos);
+        let result = runner.internal_new_window(parent_id, title, size, pos, options);
         if result.is_err() {
             return Err(WindowError::RunnerError(result.unwrap_err()));
         }
@@ -49,9 +54,16 @@ impl Window {
             proxy,
             graphics: None,
             size: size.into(),
+            fullscreen: None,
+            fullscreen_changed: None,
+            gestures: GestureRecognizer::default(),
+            modifiers: Modifiers::empty(),
 
             #[cfg(feature = "software")]
             pixelbuffer: None,
+
+            #[cfg(target_arch = "wasm32")]
+            recovery: None,
         });
 
         runner.window_events_attributes.push(inner.clone());
@@ -75,6 +87,33 @@ impl Window {
         self.inner.wait_borrow().size
     }
 
+    /// Get the size of the window in physical pixels, same as [Window::size].
+    pub fn physical_size(&self) -> PhysicalSize {
+        let size = self.size();
+
+        PhysicalSize::new(size.x as u32, size.y as u32)
+    }
+
+    /// Get the size of the window in logical units, the physical size divided by the
+    /// [Window::scale_factor].
+    pub fn logical_size(&self) -> LogicalSize {
+        self.physical_size().to_logical(self.scale_factor())
+    }
+
+    /// Get the scale factor of the monitor the window is on.
+    ///
+    /// This is the ratio between physical pixels and logical units, eg. `2.0` on a HiDPI
+    /// display. Returns `1.0` if the window has been closed.
+    pub fn scale_factor(&self) -> f64 {
+        let inner = self.inner.wait_borrow();
+
+        inner
+            .window_pointer
+            .as_ref()
+            .and_then(|pointer| pointer.lock().window.as_ref().map(|w| w.scale_factor()))
+            .unwrap_or(1.0)
+    }
+
     /// Send quit event to the runner to close the window.
     pub fn quit(&self) {
         let inner = self.inner.wait_borrow();
@@ -104,6 +143,29 @@ impl Window {
         });
     }
 
+    /// Confines or locks the cursor to the window, see [CursorGrabMode].
+    ///
+    /// Combined with [Window::set_cursor_visible] and [Event::MouseMotionRaw](crate::runner::Event::MouseMotionRaw)
+    /// for first-person camera controls.
+    pub fn set_cursor_grab(&mut self, mode: CursorGrabMode) {
+        let inner = self.inner.wait_borrow();
+
+        _ = inner.proxy.send_event(WindowEvent::CursorGrab {
+            ref_id: inner.window_id,
+            mode,
+        });
+    }
+
+    /// Shows or hides the cursor while it's over the window.
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        let inner = self.inner.wait_borrow();
+
+        _ = inner.proxy.send_event(WindowEvent::CursorVisible {
+            ref_id: inner.window_id,
+            visible,
+        });
+    }
+
     /// Set the window size.
     pub fn set_size(&mut self, size: Point2) {
         let inner = self.inner.wait_borrow();
@@ -124,6 +186,174 @@ impl Window {
         });
     }
 
+    /// Enters fullscreen with the given mode, or goes back to the windowed state with [None].
+    ///
+    /// The size, position and decorations of the window are restored when leaving, sizes and
+    /// positions set while fullscreen are applied then. The surface is reconfigured
+    /// automatically and [Event::WindowFullscreenChanged](crate::runner::Event::WindowFullscreenChanged)
+    /// is emitted, followed by [Event::WindowResized](crate::runner::Event::WindowResized) when
+    /// the size changed.
+    ///
+    /// [Fullscreen::Exclusive] is not supported on Wayland, use [Fullscreen::Borderless] there.
+    pub fn set_fullscreen(&mut self, fullscreen: Option<Fullscreen>) {
+        let mut inner = self.inner.wait_borrow_mut();
+        if inner.fullscreen == fullscreen {
+            return;
+        }
+
+        inner.fullscreen_changed = Some(fullscreen.is_some());
+        inner.fullscreen = fullscreen.clone();
+
+        _ = inner.proxy.send_event(WindowEvent::Fullscreen {
+            ref_id: inner.window_id,
+            fullscreen,
+        });
+    }
+
+    /// The current fullscreen mode, [None] while windowed.
+    pub fn fullscreen(&self) -> Option<Fullscreen> {
+        self.inner.wait_borrow().fullscreen.clone()
+    }
+
+    /// Enters or leaves borderless fullscreen on the current monitor.
+    ///
+    /// Shorthand for [Window::set_fullscreen] with [Fullscreen::Borderless].
+    pub fn set_borderless_fullscreen(&mut self, fullscreen: bool) {
+        self.set_fullscreen(if fullscreen {
+            Some(Fullscreen::Borderless(None))
+        } else {
+            None
+        });
+    }
+
+    /// Switches between borderless fullscreen and the windowed state, eg. on Alt+Enter.
+    pub fn toggle_borderless_fullscreen(&mut self) {
+        let fullscreen = self.is_borderless_fullscreen();
+        self.set_borderless_fullscreen(!fullscreen);
+    }
+
+    pub fn is_borderless_fullscreen(&self) -> bool {
+        matches!(
+            self.inner.wait_borrow().fullscreen,
+            Some(Fullscreen::Borderless(_))
+        )
+    }
+
+    /// The monitor the window is mostly on, [None] if it can't be detected or the window has
+    /// been closed.
+    pub fn current_monitor(&self) -> Option<Monitor> {
+        let inner = self.inner.wait_borrow();
+
+        inner
+            .window_pointer
+            .as_ref()
+            .and_then(|pointer| pointer.lock().window.as_ref()?.current_monitor())
+            .map(|handle| Monitor { handle })
+    }
+
+    /// Allows the user to resize the window.
+    ///
+    /// A window that isn't resizable is locked to the size given at creation or by
+    /// [Window::set_size], the limits of [Window::set_min_size] and [Window::set_max_size] only
+    /// apply to resizable windows.
+    pub fn set_resizable(&mut self, resizable: bool) {
+        let inner = self.inner.wait_borrow();
+
+        _ = inner.proxy.send_event(WindowEvent::Resizable {
+            ref_id: inner.window_id,
+            resizable,
+        });
+    }
+
+    /// Shows or hides the title bar and borders of the window.
+    pub fn set_decorations(&mut self, decorations: bool) {
+        let inner = self.inner.wait_borrow();
+
+        _ = inner.proxy.send_event(WindowEvent::Decorations {
+            ref_id: inner.window_id,
+            decorations,
+        });
+    }
+
+    /// Keeps the window above the other windows.
+    pub fn set_always_on_top(&mut self, always_on_top: bool) {
+        let inner = self.inner.wait_borrow();
+
+        _ = inner.proxy.send_event(WindowEvent::AlwaysOnTop {
+            ref_id: inner.window_id,
+            always_on_top,
+        });
+    }
+
+    /// Sets the smallest size a resizable window can be resized to, [None] for no limit.
+    pub fn set_min_size(&mut self, size: Option<Point2>) {
+        let inner = self.inner.wait_borrow();
+
+        _ = inner.proxy.send_event(WindowEvent::MinSize {
+            ref_id: inner.window_id,
+            size,
+        });
+    }
+
+    /// Sets the largest size a resizable window can be resized to, [None] for no limit.
+    pub fn set_max_size(&mut self, size: Option<Point2>) {
+        let inner = self.inner.wait_borrow();
+
+        _ = inner.proxy.send_event(WindowEvent::MaxSize {
+            ref_id: inner.window_id,
+            size,
+        });
+    }
+
+    /// Maximizes or restores the window, this only works on resizable windows.
+    pub fn set_maximized(&mut self, maximized: bool) {
+        let inner = self.inner.wait_borrow();
+
+        _ = inner.proxy.send_event(WindowEvent::Maximized {
+            ref_id: inner.window_id,
+            maximized,
+        });
+    }
+
+    /// Shows or hides the window.
+    pub fn set_visible(&mut self, visible: bool) {
+        let inner = self.inner.wait_borrow();
+
+        _ = inner.proxy.send_event(WindowEvent::Visible {
+            ref_id: inner.window_id,
+            visible,
+        });
+    }
+
+    /// Enables or disables IME (input method) text composition for the window.
+    ///
+    /// While enabled, text typed through an input method (eg. CJK languages) is reported with
+    /// [Event::ImePreedit](crate::runner::Event::ImePreedit) and
+    /// [Event::ImeCommit](crate::runner::Event::ImeCommit), and keys used by the composition may no
+    /// longer be reported as [Event::KeyboardInput](crate::runner::Event::KeyboardInput).
+    pub fn set_ime_enabled(&mut self, enabled: bool) {
+        let inner = self.inner.wait_borrow();
+
+        _ = inner.proxy.send_event(WindowEvent::ImeEnabled {
+            ref_id: inner.window_id,
+            enabled,
+        });
+    }
+
+    /// Sets the area of the text being composed, in pixels relative to the window.
+    ///
+    /// The input method places its candidate window next to this area, so it should be updated
+    /// to follow the text cursor of the focused text field.
+    pub fn set_ime_cursor_area(&mut self, pos: Point2, size: Point2) {
+        let inner = self.inner.wait_borrow();
+
+        _ = inner.proxy.send_event(WindowEvent::ImeCursorArea {
+            ref_id: inner.window_id,
+            pos,
+            size,
+        });
+    }
+
     /// Request a redraw of the window.
     pub fn request_redraw(&mut self) {
         let inner = self.inner.wait_borrow();
@@ -140,6 +370,7 @@ pub struct WindowBuilder<'a> {
     title: String,
     size: Point2,
     pos: Option<Point2>,
+    options: WindowOptions,
 }
 
 impl<'a> WindowBuilder<'a> {
@@ -150,6 +381,7 @@ impl<'a> WindowBuilder<'a> {
             title: title.to_string(),
             size,
             pos: None,
+            options: WindowOptions::default(),
         }
     }
 
@@ -179,6 +411,74 @@ impl<'a> WindowBuilder<'a> {
         self
     }
 
+    /// Allows the user to resize the window, the default is a fixed size window.
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.options.resizable = resizable;
+        self
+    }
+
+    /// Shows the title bar and borders of the window, enabled by default.
+    pub fn decorations(mut self, decorations: bool) -> Self {
+        self.options.decorations = decorations;
+        self
+    }
+
+    /// Makes the window background transparent where the rendered alpha is below 1.0.
+    ///
+    /// A [GPU](crate::gpu::GPU) created for the window picks a non opaque
+    /// [SurfaceAlphaMode](crate::gpu::SurfaceAlphaMode) when the platform supports one, clear
+    /// with a transparent color and draw with premultiplied colors. Transparency can only be
+    /// chosen at creation.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.options.transparent = transparent;
+        self
+    }
+
+    /// Keeps the window above the other windows.
+    pub fn always_on_top(mut self, always_on_top: bool) -> Self {
+        self.options.always_on_top = always_on_top;
+        self
+    }
+
+    /// Sets the smallest size of a resizable window.
+    pub fn min_size(mut self, size: Option<Point2>) -> Self {
+        self.options.min_size = size;
+        self
+    }
+
+    /// Sets the largest size of a resizable window.
+    pub fn max_size(mut self, size: Option<Point2>) -> Self {
+        self.options.max_size = size;
+        self
+    }
+
+    /// Opens the window maximized, this only works on resizable windows.
+    pub fn maximized(mut self, maximized: bool) -> Self {
+        self.options.maximized = maximized;
+        self
+    }
+
+    /// Whether the window is shown once created, enabled by default. A hidden window can be
+    /// shown later with [Window::set_visible], eg. after the first frame has been rendered.
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.options.visible = visible;
+        self
+    }
+
+    /// Enables IME text composition when the window is created, see [Window::set_ime_enabled].
+    pub fn ime(mut self, enabled: bool) -> Self {
+        self.options.ime = enabled;
+        self
+    }
+
+    /// Uses an existing canvas of the page for the window on the web. Without a canvas, a new
+    /// one is created and appended to the body of the page.
+    #[cfg(target_arch = "wasm32")]
+    pub fn canvas(mut self, canvas: web_sys::HtmlCanvasElement) -> Self {
+        self.options.canvas = Some(canvas);
+        self
+    }
+
     pub fn build(self) -> Result<Window, WindowError> {
         Window::new(
             self.runner,
@@ -186,25 +486,145 @@ impl<'a> WindowBuilder<'a> {
             self.title,
             self.size,
             self.pos,
+            self.options,
         )
     }
 }
 
+/// A size in physical pixels, the pixels of the display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PhysicalSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PhysicalSize {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    pub fn to_logical(&self, scale_factor: f64) -> LogicalSize {
+        LogicalSize::new(
+            self.width as f64 / scale_factor,
+            self.height as f64 / scale_factor,
+        )
+    }
+}
+
+/// A size in logical units, which keeps the same visual size across displays with different
+/// scale factors.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LogicalSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl LogicalSize {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self { width, height }
+    }
+
+    /// Converts to physical pixels, rounded to the nearest pixel.
+    pub fn to_physical(&self, scale_factor: f64) -> PhysicalSize {
+        PhysicalSize::new(
+            (self.width * scale_factor).round() as u32,
+            (self.height * scale_factor).round() as u32,
+        )
+    }
+}
+
+/// The scale factor of [Event::WindowScaleFactorChanged](crate::runner::Event::WindowScaleFactorChanged).
+#[derive(Clone, Copy, Debug)]
+pub struct ScaleFactor(pub f64);
+
+impl PartialEq for ScaleFactor {
+    fn eq(&self, other: &Self) -> bool {
+        // use near equality for floating point comparison
+        (self.0 - other.0).abs() < f64::EPSILON
+    }
+}
+
+impl Eq for ScaleFactor {}
+
+impl PartialOrd for ScaleFactor {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScaleFactor {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0
+            .partial_cmp(&other.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    }
+}
+
+impl From<ScaleFactor> for f64 {
+    fn from(scale: ScaleFactor) -> Self {
+        scale.0
+    }
+}
+
+/// Options of a window set through [WindowBuilder], applied when it's created.
+#[derive(Clone, Debug)]
+pub(crate) struct WindowOptions {
+    pub resizable: bool,
+    pub decorations: bool,
+    pub transparent: bool,
+    pub always_on_top: bool,
+    pub min_size: Option<Point2>,
+    pub max_size: Option<Point2>,
+    pub maximized: bool,
+    pub visible: bool,
+    pub ime: bool,
+    #[cfg(target_arch = "wasm32")]
+    pub canvas: Option<web_sys::HtmlCanvasElement>,
+}
+
+impl Default for WindowOptions {
+    fn default() -> Self {
+        Self {
+            resizable: false,
+            decorations: true,
+            transparent: false,
+            always_on_top: false,
+            min_size: None,
+            max_size: None,
+            maximized: false,
+            visible: true,
+            ime: false,
+            #[cfg(target_arch = "wasm32")]
+            canvas: None,
+        }
+    }
+}
+
 pub(crate) struct WindowInner {
     pub window_id: usize,
     pub window_events: ArcRef<Vec<event::WindowEvent>>,
     pub window_pointer: Option<ArcMut<Handle>>,
     pub proxy: EventLoopProxy<WindowEvent>,
     pub size: Point2,
+    pub fullscreen: Option<Fullscreen>,
+    pub fullscreen_changed: Option<bool>,
+    pub gestures: GestureRecognizer,
+    pub modifiers: Modifiers,
 
     pub(crate) graphics: Option<ArcRef<GPUInner>>,
 
     #[cfg(feature = "software")]
     pub(crate) pixelbuffer: Option<ArcRef<PixelBufferInner>>,
+
+    /// Result of the device recovery running in the browser, set once it is done.
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) recovery: Option<ArcMut<Option<Result<(), String>>>>,
 }
 
 impl WindowInner {
     pub fn process_event(&mut self) {
+        let mut scale_factor_changed = false;
+
         for event in self.window_events.wait_borrow_mut().iter() {
             match event {
                 event::WindowEvent::CloseRequested => {
@@ -223,18 +643,188 @@ impl WindowInner {
 
                     self.size = Point2::from(*size);
                 }
+                event::WindowEvent::ScaleFactorChanged { .. } => {
+                    scale_factor_changed = true;
+                }
                 _ => {}
             }
         }
+
+        // Some platforms change the scale factor without a following resize event
+        if scale_factor_changed {
+            self.reconfigure_surfaces();
+        }
+    }
+
+    /// Reconfigures the surfaces after a fullscreen change, which can invalidate them without
+    /// resizing the window.
+    pub fn reconfigure_surfaces(&mut self) {
+        let size = self
+            .window_pointer
+            .as_ref()
+            .and_then(|pointer| pointer.lock().window.as_ref().map(|w| w.inner_size()));
+
+        let Some(size) = size else {
+            return;
+        };
+
+        if let Some(gpu) = &self.graphics {
+            gpu.wait_borrow_mut().reconfigure(size);
+        }
+
+        #[cfg(feature = "software")]
+        if let Some(softbuffer) = &self.pixelbuffer {
+            _ = softbuffer.wait_borrow_mut().resize(size);
+        }
+
+        self.size = Point2::from(size);
     }
 
-    pub fn cycle(&mut self) {
+    /// Returns true when the GPU device was lost and has been restored during this cycle.
+    pub fn cycle(&mut self) -> bool {
+        let mut restored = false;
+
         if let Some(gpu) = &self.graphics {
+            #[cfg(not(target_arch = "wasm32"))]
+            if gpu.wait_borrow().is_device_lost() {
+                match GPUInner::recover_device(gpu) {
+                    Ok(()) => restored = true,
+                    Err(err) => {
+                        crate::error_log!("Failed to recover GPU device: {}", err);
+                    }
+                }
+            }
+
+            #[cfg(target_arch = "wasm32")]
+            {
+                restored = Self::poll_recovery(gpu, &mut self.recovery);
+            }
+
             gpu.wait_borrow_mut().cycle();
         }
+
+        restored
+    }
+
+    /// The browser can't be blocked on, the recovery runs on its event loop and its result
+    /// is picked up by a later cycle.
+    #[cfg(target_arch = "wasm32")]
+    fn poll_recovery(
+        gpu: &ArcRef<GPUInner>,
+        recovery: &mut Option<ArcMut<Option<Result<(), String>>>>,
+    ) -> bool {
+        if let Some(pending) = recovery {
+            let Some(result) = pending.lock().take() else {
+                return false;
+            };
+
+            *recovery = None;
+
+            return match result {
+                Ok(()) => true,
+                Err(err) => {
+                    crate::error_log!("Failed to recover GPU device: {}", err);
+                    false
+                }
+            };
+        }
+
+        if gpu.wait_borrow().is_device_lost() {
+            let pending = ArcMut::new(None);
+            *recovery = Some(pending.clone());
+
+            let gpu = ArcRef::clone(gpu);
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = GPUInner::recover_device_async(&gpu).await;
+                *pending.lock() = Some(result);
+            });
+        }
+
+        false
+    }
+}
+
+
+/// A monitor connected to the system, listed by [Runner::monitors].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Monitor {
+    pub(crate) handle: MonitorHandle,
+}
+
+impl Monitor {
+    /// Human readable name of the monitor, if the platform provides one.
+    pub fn name(&self) -> Option<String> {
+        self.handle.name()
+    }
+
+    /// Resolution of the monitor in pixels.
+    pub fn size(&self) -> Point2 {
+        Point2::from(self.handle.size())
+    }
+
+    /// Position of the top left corner of the monitor on the desktop, in pixels.
+    pub fn position(&self) -> Point2 {
+        let position = self.handle.position();
+        Point2::new(position.x, position.y)
+    }
+
+    pub fn scale_factor(&self) -> f64 {
+        self.handle.scale_factor()
+    }
+
+    /// Current refresh rate in Hz, if the platform reports it.
+    pub fn refresh_rate(&self) -> Option<f32> {
+        self.handle
+            .refresh_rate_millihertz()
+            .map(|rate| rate as f32 / 1000.0)
+    }
+
+    /// Display modes supported by the monitor, for [Fullscreen::Exclusive].
+    pub fn video_modes(&self) -> Vec<VideoMode> {
+        self.handle
+            .video_modes()
+            .map(|handle| VideoMode { handle })
+            .collect()
+    }
+}
+
+/// A resolution, bit depth and refresh rate combination of a [Monitor].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VideoMode {
+    pub(crate) handle: VideoModeHandle,
+}
+
+impl VideoMode {
+    pub fn size(&self) -> Point2 {
+        Point2::from(self.handle.size())
+    }
+
+    pub fn bit_depth(&self) -> u16 {
+        self.handle.bit_depth()
+    }
+
+    /// Refresh rate in Hz.
+    pub fn refresh_rate(&self) -> f32 {
+        self.handle.refresh_rate_millihertz() as f32 / 1000.0
+    }
+
+    /// The monitor this mode belongs to.
+    pub fn monitor(&self) -> Monitor {
+        Monitor {
+            handle: self.handle.monitor(),
+        }
     }
 }
 
+/// Fullscreen mode of a [Window], see [Window::set_fullscreen].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fullscreen {
+    /// Covers a monitor with the window without changing its display mode, the current monitor
+    /// of the window with [None].
+    Borderless(Option<Monitor>),
+    /// Switches the monitor of the mode to it and gives the window exclusive access to it.
+    Exclusive(VideoMode),
+}
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum RunMode {
@@ -247,3 +837,54 @@ pub enum WindowError {
     RunnerError(RunnerError),
     WindowNotFound,
 }
+
+impl std::fmt::Display for WindowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowError::RunnerError(e) => write!(f, "{}", e),
+            WindowError::WindowNotFound => write!(f, "Window not found"),
+        }
+    }
+}
+
+impl std::error::Error for WindowError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WindowError::RunnerError(e) => Some(e),
+            WindowError::WindowNotFound => None,
+        }
+    }
+}
+
+/// Why the system refused to create a window, see [RunnerError::FailedToCreateWindow].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum WindowCreateError {
+    /// The parent window was closed before the child window was created.
+    ParentClosed,
+    /// The native handle of the parent window couldn't be retrieved.
+    ParentHandle(String),
+    /// The event loop is gone, eg. the runner was dropped.
+    EventLoopClosed,
+    /// The event loop was handed to the browser by [Runner::run], windows must be created before.
+    EventLoopRunning,
+    /// The platform failed to create the window.
+    Os(String),
+}
+
+impl std::fmt::Display for WindowCreateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowCreateError::ParentClosed => write!(f, "Parent window is closed"),
+            WindowCreateError::ParentHandle(e) => {
+                write!(f, "Failed to get the parent window handle: {}", e)
+            }
+            WindowCreateError::EventLoopClosed => write!(f, "Event loop is closed"),
+            WindowCreateError::EventLoopRunning => {
+                write!(f, "Windows must be created before Runner::run on the web")
+            }
+            WindowCreateError::Os(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for WindowCreateError {}